@@ -25,7 +25,10 @@ footer-privacy = Privacy Policy
 
 # Admin app shell (top nav inside /admin).
 app-nav-overview = Overview
+app-nav-inbox = Inbox
+app-nav-contacts = Contacts
 app-nav-approvals = Approvals
+app-nav-messages = Messages
 app-nav-channels = Channels
 app-nav-email = Email Routing
 app-nav-billing = Billing
@@ -34,6 +37,7 @@ app-nav-aria-label = Admin sections
 app-nav-skip-link = Skip to main content
 app-nav-status-live = live
 app-nav-logout-aria = Sign out
+app-nav-notifications = Notifications
 
 # Maintenance fallback page.
 maintenance-title = Concierge is offline
@@ -192,6 +196,12 @@ admin-no = No
 admin-active = Active
 admin-disabled = Disabled
 
+# Admin: reply buffering limits, shown under every channel's wait control.
+admin-buffer-max-wait = Longest wait (seconds)
+admin-buffer-max-messages = Reply after this many messages
+admin-buffer-flush-question = Reply straight away when a message ends with a question mark
+admin-buffer-help = The wait restarts with each new message, but never runs past the longest wait. 0 = no limit.
+
 # Admin: login screen.
 admin-login-tagline = Sign in to manage your messaging channels.
 admin-login-google = Continue with Google
@@ -214,8 +224,44 @@ admin-settings-currency-h2 = Billing Currency
 admin-settings-currency-lead = All future charges will be in this currency.
 admin-settings-currency-inr = ₹ INR (Indian Rupee)
 admin-settings-currency-usd = $ USD (US Dollar)
+admin-settings-brands-h2 = Brands
+admin-settings-brands-lead = Two outlets, or a salon and a cafe? Give each its own details, opening hours and persona, and choose which channels answer for it.
+admin-settings-brands-cta = Manage brands
+admin-settings-tools-h2 = Lookup Tools
+admin-settings-tools-lead = Let AI replies check live data, like order status or open slots, from your own systems.
+admin-settings-tools-cta = Manage tools
+admin-settings-guardrails-h2 = Guardrails
+admin-settings-guardrails-lead = Hold AI drafts for review when they link to other sites, share someone else's contact details or repeat their instructions, and choose what personal data is masked before AI sees it.
+admin-settings-guardrails-cta = Manage guardrails
+admin-settings-team-h2 = Team
+admin-settings-team-lead = Let staff approve drafts, answer the inbox or help with setup under their own sign-in.
+admin-settings-team-cta = Manage team
+admin-settings-agency-h2 = Agency
+admin-settings-agency-lead = Run WhatsApp for other businesses? Invite them as clients, switch between them from this sign-in and choose who pays for their replies.
+admin-settings-agency-cta = Agency overview
+admin-settings-agency-managed-lead = { $agency } manages this account as an admin: settings, channels, rules and persona.
+admin-settings-agency-managed-cta = Remove agency access
+admin-settings-agency-managed-confirm = Remove { $agency }'s access to this account? Replies they paid for go back to your own credits.
+admin-settings-agency-pay-own-cta = Pay for replies myself
+admin-settings-agency-pay-own-confirm = Pay for this account's AI replies from your own credits from now on?
+admin-settings-agency-invite-h2 = Agency request
+admin-settings-agency-invite-lead = { $agency } asked to manage this account as { $business }. If you accept, they can manage its settings, channels, rules and persona until you remove them. Billing, exports, your team and your sign-in stay yours.
+admin-settings-agency-invite-billing-agency = They'd pay for its AI replies from their own credits.
+admin-settings-agency-invite-billing-client = You'd keep paying for its AI replies.
+admin-settings-agency-invite-accept = Accept
+admin-settings-agency-invite-decline = Decline
+admin-settings-agency-invite-confirm = Let { $agency } manage this account?
 admin-settings-session-h2 = Session
 admin-settings-signout = Sign Out
+admin-settings-export-h2 = Export your data
+admin-settings-export-lead = Download a ZIP of your profile, persona, channel and reply settings, lead forms, email addresses, billing ledger and payments, plus message metadata, lead submissions and approval history as CSV. Large accounts are prepared in the background and we email you when the file is ready. Downloads last { $days } days.
+admin-settings-export-cta = Request export
+admin-settings-export-download = Download
+admin-settings-export-pending = Preparing. We'll email you.
+admin-settings-export-failed = Failed
+admin-settings-export-expired = Expired
+admin-settings-export-th-requested = Requested
+admin-settings-export-th-archive = Archive
 admin-settings-delete-h2 = Delete Account
 admin-settings-delete-lead = Permanently delete your account and all associated data. This cannot be undone.
 admin-settings-delete-cta = Delete My Account
@@ -238,6 +284,8 @@ admin-dashboard-stat-instagram = Instagram
 admin-dashboard-stat-leads = Lead Forms
 admin-dashboard-stat-credits = Reply Credits
 admin-dashboard-email-eyebrow = Email Routing
+admin-dashboard-activity-eyebrow = Activity
+admin-dashboard-activity-loading = Loading activity…
 admin-dashboard-email-headline-active = Rules for the mail that comes in.
 admin-dashboard-email-headline-empty = Route your business email with AI.
 admin-dashboard-email-cta-active = Manage rules
@@ -365,7 +413,7 @@ privacy-effective = Effective April 29, 2026
 privacy-h2-collect = What we collect
 privacy-li-account = <strong>Account info:</strong> your Google account email address and display name, obtained when you sign in via Google OAuth using the <code>openid</code>, <code>email</code>, and <code>profile</code> scopes. We do not request access to Gmail, Drive, Calendar, Contacts, or any other Google Workspace data.
 privacy-li-connected = <strong>Connected accounts:</strong> WhatsApp phone number IDs, Instagram page IDs, and encrypted access tokens
-privacy-li-logs = <strong>Message logs:</strong> inbound/outbound WhatsApp and Instagram messages processed by auto-reply
+privacy-li-logs = <strong>Message logs:</strong> who wrote, on which channel, when, and how Concierge handled it. Message text is not stored unless you turn on message history, see Data retention below.
 privacy-li-leads = <strong>Lead form submissions:</strong> phone numbers submitted through your lead capture forms
 privacy-li-persona = <strong>Persona prompts and reply rules:</strong> the AI persona text you write and the rule descriptions you configure
 privacy-h2-use = How we use it
@@ -385,7 +433,19 @@ privacy-li-google-limited-4 = We do not use Google user data, or data derived fr
 privacy-li-google-limited-5 = We do not use Google user data for credit-worthiness, lending, insurance underwriting, or any similar evaluation.
 privacy-li-google-limited-6 = No human at Concierge reads your Google user data unless we have your affirmative consent for specific messages, it is necessary for security purposes (e.g. investigating abuse), to comply with applicable law, or the data is aggregated and used for internal operations in line with this policy.
 privacy-h2-retention = Data retention
-privacy-p-retention = Data is retained while your account is active. You can delete all your data at any time from <a href="/admin/settings">Settings</a>. When you delete your account, your Google account email, display name, and all associated tenant data are removed from our database immediately.
+privacy-p-retention = Account data is retained while your account is active. You can delete all your data at any time from <a href="/admin/settings">Settings</a>. When you delete your account, your Google account email, display name, and all associated tenant data are removed from our database immediately. Message history is off by default. If you turn it on from the Inbox, the text of new messages and replies is kept encrypted with AES-256-GCM for the window you pick (7, 30 or 90 days), deleted automatically once it is older than that, and deleted at once if you turn history off or delete your account.
+privacy-p-retention-periods = Logs are deleted automatically once they are older than the periods below. Daily totals (counts only, with no phone numbers, handles or addresses) are kept so reports over longer ranges still add up.
+privacy-retention-messages = Message logs
+privacy-retention-whatsapp-messages = WhatsApp delivery logs
+privacy-retention-instagram-messages = Instagram delivery logs
+privacy-retention-lead-form-submissions = Lead form submissions
+privacy-retention-pending-approvals = Decided reply drafts (approved, rejected or expired)
+privacy-retention-notifications = Notifications you've read
+privacy-retention-audit-log = Record of actions our operators take on accounts
+privacy-retention-period = { $days } days
+privacy-retention-forever = kept while your account is active
+privacy-p-history-off = Your account: message history is off. Concierge does not keep the text of your customers' messages.
+privacy-p-history-on = Your account: message history is on. The text of messages and replies is kept encrypted for { $days } days, then deleted.
 privacy-h2-deletion = Data deletion
 privacy-p-deletion-prefix = To delete your account and all associated data:
 privacy-li-deletion-1 = Go to <a href="/admin/settings">Settings</a> and click "Delete Account"
@@ -435,6 +495,9 @@ wizard-notifications-cadence-prefix = Send digest
 wizard-notifications-discord-missing = Discord isn't installed yet. You need the bot in a server before approvals can land there.
 wizard-notifications-discord-install = Install Discord
 wizard-notifications-continue = Continue →
+wizard-notifications-summary-eyebrow = Weekly summary
+wizard-notifications-summary-label = Email me a summary every Monday
+wizard-notifications-summary-sub = Messages handled, AI replies and credits, approvals, your busiest rules and any connection that needs attention.
 
 # Wizard step 4: Replies.
 wizard-replies-eyebrow = Replies
@@ -514,11 +577,23 @@ admin-email-verify-title = Email verification: Concierge
 admin-email-verify-h1 = Email verification
 admin-email-verify-back = Back to Concierge
 
+# Team invite result page (/auth/join).
+team-join-title = Team invite: Concierge
+team-join-h1 = Team invite
+team-join-back = Back to Concierge
+
 # Admin: Persona builder.
 admin-persona-title = Persona — Concierge
 admin-persona-back = ← Dashboard
 admin-persona-h1 = Persona
 admin-persona-lead = The persona is your AI assistant's voice. Every AI-generated reply uses this prompt as its system prompt.
+admin-persona-brand = Persona for { $brand }
+admin-persona-brand-all = All brands
+admin-persona-channel = Persona for this { $channel } account
+admin-persona-channel-inherited = Uses the { $brand } persona. Saving here gives this account its own.
+admin-persona-channel-reset = Use the { $brand } persona
+admin-persona-channel-reset-confirm = Drop this account's own persona and use the { $brand } persona again?
+admin-persona-channel-rules = Reply rules
 admin-persona-mode-eyebrow = Mode
 admin-persona-mode-preset = Preset
 admin-persona-mode-builder = Builder
@@ -598,6 +673,10 @@ admin-rules-list-routing-h2 = Routing rules
 admin-rules-list-empty = No rules yet. Add one below or rely on the default reply.
 admin-rules-list-add = + Add rule
 admin-rules-list-default-h2 = Default reply
+admin-rules-list-persona-h2 = Persona
+admin-rules-list-persona-own = AI replies here use this account's own persona.
+admin-rules-list-persona-inherited = AI replies here use the brand's persona. Give this account its own for a different voice, like a more formal one on email.
+admin-rules-list-persona-cta = Edit persona
 admin-rules-list-back-prefix = ←
 admin-rules-row-edit = Edit
 admin-rules-row-delete = Delete
@@ -632,12 +711,23 @@ admin-rules-form-threshold-prefix = Threshold:
 admin-rules-form-threshold-help = Higher = stricter match. Default 0.72.
 admin-rules-form-respond-with = Respond with
 admin-rules-form-response-canned = Canned text (no AI, no credit charge)
-admin-rules-form-response-prompt = AI prompt (1 credit per reply)
+admin-rules-form-response-prompt = AI prompt (credits per reply depend on the model)
 admin-rules-form-response-text-sr = Reply text
 admin-rules-form-response-placeholder = Hi! Here's what we recommend...
 admin-rules-form-response-help = This text is appended to your persona prompt and sent to the LLM.
 admin-rules-form-cancel = Cancel
 admin-rules-form-save = Save
+admin-rules-model-eyebrow = Model
+admin-rules-model-fast = Fast
+admin-rules-model-fast-detail = Quicker, cheaper replies for simple questions.
+admin-rules-model-quality = Quality
+admin-rules-model-quality-detail = Better for nuanced or multi-part conversations.
+admin-rules-model-cost = Credits per reply: { $credits }
+admin-rules-model-temperature = Creativity (temperature)
+admin-rules-model-temperature-help = 0 keeps replies predictable; higher values vary the wording. Leave blank for the model default.
+admin-rules-model-max-tokens = Max reply length (tokens)
+admin-rules-model-max-tokens-help = Caps how long a reply can be, from { $min } to { $max }. Leave blank for the model default.
+admin-rules-chip-tier = { $tier } · { $credits } cr
 admin-rules-approval-eyebrow = When should this AI reply send?
 admin-rules-approval-auto = Auto: send unless the safety check pauses it
 admin-rules-approval-auto-detail = Default. We send the draft straight away unless our heuristic spots a risk (money, commitments, off-persona) — then it goes to your approval queue.
@@ -657,6 +747,78 @@ admin-rules-modal-ack-2 = I accept the terms above.
 admin-rules-modal-cancel = Cancel
 admin-rules-modal-confirm = Turn off safety check and save
 
+# Admin: Lookup tools.
+admin-tools-title = Lookup Tools - Concierge
+admin-tools-back = ← Settings
+admin-tools-h1 = Lookup tools
+admin-tools-lead = HTTP endpoints the AI can call while drafting a reply, such as "where is order #1234?" or "is 4pm free tomorrow?". Every call is shown on the draft in your approval queue.
+admin-tools-empty = No tools yet. AI replies answer from your persona and rules alone.
+admin-tools-cap = You've reached the limit of { $max } tools. Remove one to add another.
+admin-tools-chip-on = on
+admin-tools-chip-off = off
+admin-tools-enable = Enable
+admin-tools-disable = Disable
+admin-tools-delete = Remove
+admin-tools-delete-confirm = Remove this tool? AI replies will stop calling it.
+admin-tools-schema = Parameters
+admin-tools-add-h2 = Add a tool
+admin-tools-name = Name
+admin-tools-name-help = Shown to the AI as the function name. Lowercase letters, digits and underscores.
+admin-tools-description = What it looks up
+admin-tools-description-placeholder = Current shipping status for an order number
+admin-tools-url = Endpoint (HTTPS)
+admin-tools-parameters = Parameters (JSON Schema)
+admin-tools-parameters-help = An object schema describing the JSON body we POST. Leave blank if the tool takes no input.
+admin-tools-secret = Signing secret
+admin-tools-secret-help = At least { $min } characters. Used to sign every request; we can't show it again after saving.
+admin-tools-save = Add tool
+admin-tools-verify-h2 = Verifying requests
+admin-tools-verify-lead = Each call is a JSON POST with these headers. Recompute the signature with your secret and reject requests that don't match or whose timestamp is more than a few minutes old.
+admin-tools-limits = Calls time out after { $timeout } s, only the first { $bytes } bytes of a response are used, and a reply can make at most { $calls } calls.
+
+# Admin: Guardrails.
+admin-guardrails-title = Guardrails - Concierge
+admin-guardrails-back = ← Settings
+admin-guardrails-h1 = Guardrails
+admin-guardrails-lead = Checks every AI draft gets before it's sent. A draft that trips one waits in your approval queue with the reason and what was found.
+admin-guardrails-urls = Check links
+admin-guardrails-urls-help = Hold drafts that link to a site not listed below. Subdomains of a listed site are allowed.
+admin-guardrails-domains = Allowed sites, one per line
+admin-guardrails-contacts = Check contact details
+admin-guardrails-contacts-help = Hold drafts that mention a phone number or email address that isn't yours or the customer's. Emails on an allowed site count as yours.
+admin-guardrails-phones = Your phone numbers, one per line
+admin-guardrails-emails = Your email addresses, one per line
+admin-guardrails-leak = Check for repeated instructions
+admin-guardrails-leak-help = Hold drafts that quote your persona or rule prompt word for word.
+admin-guardrails-policy = AI policy review
+admin-guardrails-policy-help = A second, fast model reads each draft against the rules below before it's sent. Adds a moment to every AI reply; if the review can't run, the draft is held.
+admin-guardrails-policy-text = Your rules
+admin-guardrails-policy-placeholder = Never promise delivery dates. Don't discuss competitors.
+admin-guardrails-saved = Guardrails saved.
+admin-guardrails-sim-h2 = Try a draft
+admin-guardrails-sim-lead = Paste a reply to see which checks it would trip with the saved settings. Nothing is sent.
+admin-guardrails-sim-placeholder = Sure! You can also reach my colleague on 99887 76655.
+admin-guardrails-sim-run = Check draft
+admin-guardrails-sim-off = off
+admin-guardrails-sim-pass = pass
+admin-guardrails-sim-flagged = flagged
+admin-guardrails-sim-send = Would be sent
+admin-guardrails-sim-queue = Would wait for approval
+admin-redaction-h2 = Personal data
+admin-redaction-lead = Mask personal details in incoming messages before any AI model sees them. The AI works with placeholders; your customer's own email and number are put back in the reply, while card and ID numbers only ever come back masked to their last four characters.
+admin-redaction-enabled = Mask personal data before AI
+admin-redaction-classes = What to mask (values caught in the last { $days } days)
+admin-redaction-saved = Redaction settings saved.
+admin-injection-h2 = Prompt injection
+admin-injection-lead = Incoming messages are scanned for attempts to take over the AI. Flagged messages never reach the AI; they get the holding reply below and wait in Approvals for you to answer.
+admin-injection-counts = Last { $days } days: { $detected } flagged, { $failed } held because the scan couldn't run.
+admin-injection-holding = Send a holding reply straight away
+admin-injection-reply-text = Holding reply
+admin-injection-relay = Queue flagged messages for a human reply
+admin-injection-allowlist = Senders to trust
+admin-injection-allowlist-help = One phone number, email or handle per line. Their messages skip the scan.
+admin-injection-saved = Prompt injection settings saved.
+
 # Admin: Lead form edit.
 admin-lf-edit-back = ← Back to Lead Forms
 admin-lf-edit-h1 = Edit Lead Form
@@ -683,3 +845,34 @@ admin-lf-edit-save = Save
 admin-lf-edit-embed-h3 = Embed Code
 admin-lf-edit-embed-lead = Copy and paste this into your website:
 admin-lf-edit-embed-copy = Copy
+
+# Weekly summary email.
+summary-subject = Your week: { $count } messages handled
+summary-heading = Your week, { $start } to { $end }
+summary-intro = Here's what your assistant handled over the last seven days (UTC).
+summary-section-messages = Messages handled
+summary-channel-line = { $channel }: { $count }
+summary-messages-none = No messages came in this week.
+summary-leads-line = Lead form submissions: { $count }
+summary-section-replies = Replies and credits
+summary-replies-canned = Canned replies: { $count }
+summary-replies-ai = AI replies sent: { $count }
+summary-replies-human = Replies from your team: { $count }
+summary-credits-used = Credits used: { $count }
+summary-credits-remaining = Credits remaining: { $count }
+summary-section-approvals = Approvals
+summary-approvals-pending = Waiting for you now: { $count }
+summary-approvals-week = This week: { $approved } approved, { $rejected } rejected, { $expired } expired
+summary-section-rules = Top matched rules
+summary-rule-line = { $rule }: { $count }
+summary-rules-none = No rules matched this week.
+summary-section-connections = Connections
+summary-connections-ok = All connections are working.
+summary-instagram-expiring = Instagram @{ $username }: the access token expires within a week and couldn't be renewed. Reconnect the account to keep replying.
+summary-instagram-expired = Instagram @{ $username }: the access token has expired. Reconnect the account to resume replies.
+summary-instagram-missing = Instagram @{ $username }: no access token is stored. Reconnect the account.
+summary-discord-unreachable = Discord { $server }: the bot can't reach the server. Reinstall it or restore its View Channels permission.
+summary-discord-hidden = Discord { $server }: the bot can't see the approvals channel. Give it access or pick another channel.
+summary-cta = Open dashboard
+summary-footer = You're getting this because weekly summaries are turned on for your tenant.
+summary-footer-link = Turn them off
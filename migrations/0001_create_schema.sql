@@ -19,6 +19,12 @@ CREATE TABLE IF NOT EXISTS tenants (
     currency TEXT NOT NULL DEFAULT 'INR',
     locale TEXT NOT NULL DEFAULT 'en-IN',
     email_address_extras_purchased INTEGER NOT NULL DEFAULT 0,
+    -- How AI replies are charged: 'per_reply' (tier's credit cost) or
+    -- 'per_token' (reply tokens / pricing_config.tokens_per_credit).
+    credit_mode TEXT NOT NULL DEFAULT 'per_reply',
+    -- Opt-in: days to keep encrypted message text for the web inbox
+    -- (conversation_messages). 0 = off, otherwise 7, 30 or 90.
+    retention_days INTEGER NOT NULL DEFAULT 0,
     -- Set the first time we observe a captured Razorpay payment for this
     -- tenant. The sign-up wizard charges a small refundable amount as an
     -- abuse-prevention check, and any other captured payment also flips
@@ -53,7 +59,9 @@ CREATE TABLE IF NOT EXISTS lead_form_submissions (
     lead_form_id TEXT NOT NULL,
     phone_number TEXT NOT NULL,
     whatsapp_account_id TEXT NOT NULL,
+    -- Empty when the number opted out and nothing was sent.
     message_sent TEXT NOT NULL,
+    -- static | ai | suppressed (number opted out; nothing sent)
     reply_mode TEXT NOT NULL,
     tenant_id TEXT NOT NULL,
     created_at TEXT NOT NULL DEFAULT (datetime('now'))
@@ -90,11 +98,158 @@ CREATE TABLE IF NOT EXISTS messages (
     tenant_id TEXT NOT NULL,
     channel_account_id TEXT NOT NULL DEFAULT '',
     action_taken TEXT,
+    -- JSON object of PII values masked before model calls, per class
+    -- (e.g. {"card":1,"phone":2}). NULL when nothing was redacted.
+    redactions TEXT,
+    -- contacts.id the sender resolved to; NULL for outbound rows.
+    contact_id TEXT,
+    -- Label of the reply rule that matched an inbound row, as it was then.
+    rule_label TEXT,
+    -- pending_approvals.id of the draft or held message this row belongs
+    -- to: the queued inbound message and every decision on it.
+    approval_id TEXT,
+    -- Reply credits the row charged; negative for a refunded draft.
+    credits INTEGER NOT NULL DEFAULT 0,
+    -- On rows that answered the customer: seconds since their first
+    -- message after the previous answer. NULL otherwise.
+    reply_secs INTEGER,
     created_at TEXT NOT NULL DEFAULT (datetime('now'))
 );
+CREATE INDEX IF NOT EXISTS idx_messages_contact ON messages(contact_id);
 CREATE INDEX IF NOT EXISTS idx_messages_tenant ON messages(tenant_id, created_at);
 CREATE INDEX IF NOT EXISTS idx_messages_channel ON messages(channel, tenant_id, created_at);
-CREATE INDEX IF NOT EXISTS idx_messages_channel_account ON messages(channel_account_id);
+-- Message log filters (`message_log`): each narrows by tenant, then one
+-- column, newest first.
+CREATE INDEX IF NOT EXISTS idx_messages_account ON messages(tenant_id, channel_account_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_messages_direction ON messages(tenant_id, direction, created_at);
+CREATE INDEX IF NOT EXISTS idx_messages_action ON messages(tenant_id, action_taken, created_at);
+CREATE INDEX IF NOT EXISTS idx_messages_sender ON messages(tenant_id, sender, created_at);
+CREATE INDEX IF NOT EXISTS idx_messages_recipient ON messages(tenant_id, recipient, created_at);
+CREATE INDEX IF NOT EXISTS idx_messages_rule ON messages(tenant_id, rule_label, created_at);
+
+-- Contact book: one row per person, whatever channels they write from.
+-- `tags` is a JSON array of lowercase labels; `notes` is private to the
+-- tenant.
+CREATE TABLE IF NOT EXISTS contacts (
+    id TEXT PRIMARY KEY,
+    tenant_id TEXT NOT NULL,
+    name TEXT,
+    tags TEXT NOT NULL DEFAULT '[]',
+    notes TEXT NOT NULL DEFAULT '',
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+CREATE INDEX IF NOT EXISTS idx_contacts_tenant ON contacts(tenant_id, updated_at);
+
+-- Each sender string a contact has written from (phone, Instagram id,
+-- email, Discord id). Every inbound message upserts one of these; merging
+-- contacts moves identities onto the surviving contact.
+CREATE TABLE IF NOT EXISTS contact_identities (
+    id TEXT PRIMARY KEY,
+    tenant_id TEXT NOT NULL,
+    contact_id TEXT NOT NULL,
+    channel TEXT NOT NULL,
+    identity TEXT NOT NULL,
+    -- Latest sender_name / WhatsApp profile name seen.
+    display_name TEXT,
+    message_count INTEGER NOT NULL DEFAULT 0,
+    first_seen_at TEXT NOT NULL DEFAULT (datetime('now')),
+    last_seen_at TEXT NOT NULL DEFAULT (datetime('now')),
+    UNIQUE (tenant_id, channel, identity)
+);
+CREATE INDEX IF NOT EXISTS idx_contact_identities_contact
+    ON contact_identities(contact_id);
+
+-- Senders who opted out. Checked before every outbound send
+-- (channel::send_reply, lead forms); a row also suppresses the contact's
+-- other identities when `contact_id` is set.
+CREATE TABLE IF NOT EXISTS suppressions (
+    tenant_id TEXT NOT NULL,
+    channel TEXT NOT NULL,
+    identity TEXT NOT NULL,
+    contact_id TEXT,
+    -- keyword | admin
+    source TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    PRIMARY KEY (tenant_id, channel, identity)
+);
+CREATE INDEX IF NOT EXISTS idx_suppressions_contact ON suppressions(contact_id);
+
+-- Append-only audit trail of consent changes.
+CREATE TABLE IF NOT EXISTS consent_events (
+    id TEXT PRIMARY KEY,
+    tenant_id TEXT NOT NULL,
+    channel TEXT NOT NULL,
+    identity TEXT NOT NULL,
+    contact_id TEXT,
+    -- opt_out | opt_in
+    change TEXT NOT NULL,
+    -- keyword | admin
+    source TEXT NOT NULL,
+    -- The keyword matched, or the admin who made the change.
+    detail TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+CREATE INDEX IF NOT EXISTS idx_consent_events_tenant
+    ON consent_events(tenant_id, created_at);
+
+-- Web inbox threads: one per tenant, channel and customer. Only written
+-- for tenants with retention_days > 0. `reply_metadata` is the latest
+-- inbound's channel metadata, needed to answer from the inbox, encrypted
+-- like the bodies.
+CREATE TABLE IF NOT EXISTS conversations (
+    id TEXT PRIMARY KEY,
+    tenant_id TEXT NOT NULL,
+    channel TEXT NOT NULL,
+    channel_account_id TEXT NOT NULL DEFAULT '',
+    contact TEXT NOT NULL,
+    contact_name TEXT,
+    contact_id TEXT,
+    recipient TEXT NOT NULL DEFAULT '',
+    reply_metadata TEXT NOT NULL DEFAULT '{}',
+    -- open | auto_replied | queued | human_handled
+    status TEXT NOT NULL DEFAULT 'open',
+    last_message_at TEXT NOT NULL DEFAULT (datetime('now')),
+    UNIQUE (tenant_id, channel, contact)
+);
+CREATE INDEX IF NOT EXISTS idx_conversations_tenant
+    ON conversations(tenant_id, last_message_at);
+
+-- Message text behind the inbox, AES-GCM encrypted with ENCRYPTION_KEY
+-- (hex IV || ciphertext). Capped per conversation
+-- (inbox::MAX_RETAINED_PER_CONVERSATION) and purged daily once older than
+-- the tenant's retention_days.
+CREATE TABLE IF NOT EXISTS conversation_messages (
+    id TEXT PRIMARY KEY,
+    conversation_id TEXT NOT NULL,
+    tenant_id TEXT NOT NULL,
+    direction TEXT NOT NULL,
+    body TEXT NOT NULL,
+    action_taken TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+CREATE INDEX IF NOT EXISTS idx_conversation_messages_thread
+    ON conversation_messages(conversation_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_conversation_messages_tenant
+    ON conversation_messages(tenant_id);
+
+-- Every model call, attributed to the tenant it ran for. Reply drafts
+-- with tool calls are one row summing every turn. `estimated` = 1 when
+-- the backend didn't report token counts.
+CREATE TABLE IF NOT EXISTS ai_usage (
+    id TEXT PRIMARY KEY,
+    tenant_id TEXT NOT NULL,
+    purpose TEXT NOT NULL,          -- reply | scan | safety | embed
+    tier TEXT NOT NULL,             -- fast | quality | embedding
+    provider TEXT NOT NULL,
+    model TEXT NOT NULL,
+    input_tokens INTEGER NOT NULL DEFAULT 0,
+    output_tokens INTEGER NOT NULL DEFAULT 0,
+    estimated INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+CREATE INDEX IF NOT EXISTS idx_ai_usage_tenant ON ai_usage(tenant_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_ai_usage_created ON ai_usage(created_at);
 
 -- Payment history
 CREATE TABLE IF NOT EXISTS payments (
@@ -118,6 +273,171 @@ CREATE TABLE IF NOT EXISTS tenant_billing (
     updated_at TEXT NOT NULL DEFAULT (datetime('now'))
 );
 
+-- Tenant data exports. Small tenants' archives are built on request;
+-- larger ones wait for the 15-minute cron. The ZIP itself lives in KV at
+-- `tenant_export:{id}:{part}` for EXPORT_TTL_DAYS.
+CREATE TABLE IF NOT EXISTS tenant_exports (
+    id TEXT PRIMARY KEY,
+    tenant_id TEXT NOT NULL,
+    -- pending | ready | failed
+    status TEXT NOT NULL DEFAULT 'pending',
+    parts INTEGER NOT NULL DEFAULT 0,
+    size_bytes INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    completed_at TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_tenant_exports_tenant ON tenant_exports(tenant_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_tenant_exports_status ON tenant_exports(status, created_at);
+
+-- Tenant deletion jobs. Outlive the tenant on purpose: the report is the
+-- record of what was removed, and `verification` of the last re-check
+-- that nothing remains. Neither holds anything but counts and ids.
+CREATE TABLE IF NOT EXISTS tenant_deletions (
+    id TEXT PRIMARY KEY,
+    tenant_id TEXT NOT NULL,
+    -- running | completed
+    status TEXT NOT NULL DEFAULT 'running',
+    -- Index of the next step in tenant_deletion::steps()
+    step INTEGER NOT NULL DEFAULT 0,
+    -- JSON DeletionReport
+    report TEXT NOT NULL DEFAULT '{}',
+    error TEXT,
+    -- operator email, 'tenant' or 'meta'
+    requested_by TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    completed_at TEXT,
+    -- JSON map of what a re-check still found, keyed like the report
+    verification TEXT,
+    verified_at TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_tenant_deletions_status ON tenant_deletions(status, created_at);
+CREATE INDEX IF NOT EXISTS idx_tenant_deletions_tenant ON tenant_deletions(tenant_id);
+
+-- Retention periods for the log tables in retention::RETAINED_TABLES.
+-- Rows here are the operator's changes; tables without one use the
+-- built-in default. 0 keeps rows forever.
+CREATE TABLE IF NOT EXISTS retention_defaults (
+    table_name TEXT PRIMARY KEY,
+    days INTEGER NOT NULL,
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- Per-tenant retention overrides, set by the operator.
+CREATE TABLE IF NOT EXISTS tenant_retention (
+    tenant_id TEXT NOT NULL,
+    table_name TEXT NOT NULL,
+    days INTEGER NOT NULL,
+    PRIMARY KEY (tenant_id, table_name)
+);
+
+-- Daily counts of the log tables, kept after the rows are pruned.
+-- `source` is the table; channel, direction and outcome are '' where the
+-- table has no such column. Operator tables roll up with tenant_id ''.
+-- `reply_within` buckets messages.reply_secs by upper bound in seconds
+-- (0 for rows that aren't answers, -1 past the last bucket); `credits`
+-- sums messages.credits.
+CREATE TABLE IF NOT EXISTS daily_rollups (
+    tenant_id TEXT NOT NULL,
+    day TEXT NOT NULL,
+    source TEXT NOT NULL,
+    channel TEXT NOT NULL DEFAULT '',
+    direction TEXT NOT NULL DEFAULT '',
+    outcome TEXT NOT NULL DEFAULT '',
+    reply_within INTEGER NOT NULL DEFAULT 0,
+    count INTEGER NOT NULL,
+    credits INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (tenant_id, day, source, channel, direction, outcome, reply_within)
+);
+CREATE INDEX IF NOT EXISTS idx_daily_rollups_source_day ON daily_rollups(source, day);
+
+-- First day of each source table not yet in daily_rollups. Pruning never
+-- deletes rows from that day on.
+CREATE TABLE IF NOT EXISTS rollup_state (
+    source TEXT PRIMARY KEY,
+    next_day TEXT NOT NULL,
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- The last Monday summary each tenant was handled for (`email::summary`):
+-- 'sent', 'skipped' (opted out or not set up) or 'failed'.
+CREATE TABLE IF NOT EXISTS weekly_summaries (
+    tenant_id TEXT PRIMARY KEY,
+    week TEXT NOT NULL,
+    status TEXT NOT NULL,
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- Team members (`team`): people the owner invited, with a role. The owner
+-- is `tenants.email` and has no row here. `accepted_at` is NULL while the
+-- invite is outstanding; `invite_token` is cleared once it's accepted.
+CREATE TABLE IF NOT EXISTS tenant_members (
+    id TEXT PRIMARY KEY,
+    tenant_id TEXT NOT NULL,
+    email TEXT NOT NULL,
+    role TEXT NOT NULL,
+    invite_token TEXT,
+    invited_by TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    accepted_at TEXT
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_tenant_members_tenant_email
+    ON tenant_members(tenant_id, email);
+CREATE INDEX IF NOT EXISTS idx_tenant_members_email ON tenant_members(email);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_tenant_members_invite
+    ON tenant_members(invite_token);
+
+-- Agency clients (`agency`): tenants an agency's owner manages from their
+-- own login. `tenant_id` is the client; a client has at most one agency.
+-- `billing` is 'agency' (replies draw on the agency's credits) or 'client'.
+CREATE TABLE IF NOT EXISTS agency_clients (
+    tenant_id TEXT PRIMARY KEY,
+    agency_id TEXT NOT NULL,
+    billing TEXT NOT NULL DEFAULT 'client',
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_agency_clients_agency ON agency_clients(agency_id);
+
+-- Agency client invites (`agency`): an agency asks the owner of `email` to
+-- let it manage their account. Nothing is linked until that owner, signed
+-- in as `email`, accepts from their Settings page. `name` is the business
+-- name the agency gave; `billing` is the one the link will start with.
+CREATE TABLE IF NOT EXISTS agency_invites (
+    id TEXT PRIMARY KEY,
+    agency_id TEXT NOT NULL,
+    email TEXT NOT NULL,
+    name TEXT NOT NULL,
+    billing TEXT NOT NULL DEFAULT 'client',
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_agency_invites_agency_email
+    ON agency_invites(agency_id, email);
+CREATE INDEX IF NOT EXISTS idx_agency_invites_email ON agency_invites(email);
+
+-- In-app notifications (`notifications`). `dedup_key` with `kind` keeps
+-- one unread row per event source; `read_at` is NULL until opened.
+CREATE TABLE IF NOT EXISTS notifications (
+    id TEXT PRIMARY KEY,
+    tenant_id TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    severity TEXT NOT NULL,
+    title TEXT NOT NULL,
+    body TEXT NOT NULL,
+    link TEXT NOT NULL DEFAULT '',
+    dedup_key TEXT NOT NULL DEFAULT '',
+    read_at TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_notifications_tenant_created
+    ON notifications(tenant_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_notifications_tenant_unread
+    ON notifications(tenant_id, read_at, kind);
+
 -- Audit log
 CREATE TABLE IF NOT EXISTS audit_log (
     id TEXT PRIMARY KEY,
@@ -142,15 +462,23 @@ CREATE TABLE IF NOT EXISTS pending_approvals (
     rule_label          TEXT NOT NULL,
     sender              TEXT NOT NULL,
     sender_name         TEXT,
+    contact_id          TEXT,
     inbound_preview     TEXT NOT NULL,
     draft               TEXT NOT NULL,
     queue_reason        TEXT NOT NULL,
+    -- Guardrail finding behind a guard_* queue_reason (URL, number, phrase).
+    queue_detail        TEXT,
     status              TEXT NOT NULL DEFAULT 'pending',
     created_at          TEXT NOT NULL DEFAULT (datetime('now')),
     decided_at          TEXT,
     decided_by          TEXT,
     edited              INTEGER NOT NULL DEFAULT 0,
-    last_digest_at      TEXT
+    last_digest_at      TEXT,
+    -- Reply credits deducted for the draft (depends on the rule's model
+    -- tier). Refunded in full on reject/expiry.
+    credits_charged     INTEGER NOT NULL DEFAULT 1,
+    -- JSON array of ToolCallRecord: tenant lookups the draft was based on.
+    tool_calls          TEXT NOT NULL DEFAULT '[]'
 );
 
 CREATE INDEX IF NOT EXISTS idx_pa_tenant_status
@@ -165,6 +493,21 @@ CREATE TABLE IF NOT EXISTS pricing_config (
     -- Reply-email subscription pack size — addresses granted per pack
     -- purchase. Currency-independent; the price lives in pricing_amount.
     email_pack_size INTEGER NOT NULL DEFAULT 5,
+    -- Reply credits charged per AI reply, by the rule's model tier.
+    fast_reply_credits INTEGER NOT NULL DEFAULT 1,
+    quality_reply_credits INTEGER NOT NULL DEFAULT 1,
+    -- Comma-separated model tiers tenants may pick on AI rules. Rules on a
+    -- disallowed tier run on the first allowed one instead.
+    allowed_model_tiers TEXT NOT NULL DEFAULT 'fast,quality',
+    -- Operator cost per million tokens, in micro-USD, by model tier. Only
+    -- used for the usage report. Defaults are Workers AI list prices.
+    fast_input_rate INTEGER NOT NULL DEFAULT 45000,
+    fast_output_rate INTEGER NOT NULL DEFAULT 384000,
+    quality_input_rate INTEGER NOT NULL DEFAULT 270000,
+    quality_output_rate INTEGER NOT NULL DEFAULT 850000,
+    embedding_input_rate INTEGER NOT NULL DEFAULT 67000,
+    -- Reply tokens one credit buys for tenants on per-token pricing.
+    tokens_per_credit INTEGER NOT NULL DEFAULT 1000,
     updated_at TEXT DEFAULT (datetime('now'))
 );
 INSERT OR IGNORE INTO pricing_config (id) VALUES (1);
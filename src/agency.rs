@@ -0,0 +1,693 @@
+//! Agency mode: one login managing many tenants.
+//!
+//! An agency is an ordinary tenant whose owner invites clients from
+//! `/admin/agency`. Each client is a tenant of its own, owned by the shop
+//! owner's login. An invite (`agency_invites`) names the shop owner's
+//! address; nothing is linked until that owner, signed in as it, accepts
+//! from their Settings page, which adds the `agency_clients` row (creating
+//! their account first if they had none, like any sign-in). Either side
+//! can end the link later. The agency's owner switches into
+//! a client from the admin header and acts there as an Admin (billing,
+//! exports, the team and the account itself stay the client's); the session remembers which agency it came through
+//! (`storage::save_session_agency`) and every admin request checks the link
+//! still exists.
+//!
+//! `reachable` is the whole user-to-tenants relationship for a login: the
+//! tenant it owns, that tenant's clients, and the teams (`team`) it has
+//! joined. The header switcher lists exactly these.
+//!
+//! A client is billed on its own balance or the agency's (`ClientBilling`);
+//! `payer` picks the balance `billing` draws on and refunds to.
+
+use worker::*;
+
+use crate::approvals;
+use crate::email::send::OutboundEmail;
+use crate::helpers::{generate_id, html_escape};
+use crate::notifications;
+use crate::storage::{
+    get_discord_config_by_tenant, get_email_addresses, get_tenant_billing, list_instagram_accounts,
+    list_whatsapp_accounts,
+};
+use crate::team::{self, Actor};
+use crate::types::{ClientBilling, MemberRole, NotificationSeverity};
+
+/// One of an agency's clients, with its name and address.
+#[derive(Debug, Clone)]
+pub struct Client {
+    pub tenant_id: String,
+    pub name: String,
+    pub email: String,
+    pub billing: ClientBilling,
+}
+
+impl Client {
+    fn from_row(row: &serde_json::Value) -> Self {
+        let s = |k: &str| {
+            row.get(k)
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string()
+        };
+        let email = s("email");
+        Client {
+            tenant_id: s("tenant_id"),
+            name: display_name(row, &email),
+            email,
+            billing: ClientBilling::from_wire(&s("billing")).unwrap_or_default(),
+        }
+    }
+}
+
+/// `tenants.name`, or the address when it has none.
+fn display_name(row: &serde_json::Value, email: &str) -> String {
+    row.get("name")
+        .and_then(|v| v.as_str())
+        .filter(|n| !n.trim().is_empty())
+        .unwrap_or(email)
+        .to_string()
+}
+
+/// How a login reaches a tenant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Access {
+    Owner,
+    Agency { agency_id: String },
+    Member { member_id: String, role: MemberRole },
+}
+
+/// A tenant a login can switch to.
+#[derive(Debug, Clone)]
+pub struct Reach {
+    pub tenant_id: String,
+    pub name: String,
+    pub access: Access,
+}
+
+impl Reach {
+    /// Who the login acts as once switched in. `email` is the login's.
+    pub fn actor(&self, email: &str) -> Actor {
+        match &self.access {
+            Access::Owner => Actor::owner(),
+            Access::Agency { agency_id } => Actor::agency(agency_id, email),
+            Access::Member { member_id, role } => Actor {
+                member_id: Some(member_id.clone()),
+                email: Some(team::normalize_email(email)),
+                agency_id: None,
+                role: *role,
+            },
+        }
+    }
+}
+
+/// Every tenant `email` can sign in to: the one it owns first, then that
+/// tenant's clients, then teams it joined, each tenant once.
+pub async fn reachable(db: &D1Database, email: &str) -> Result<Vec<Reach>> {
+    let rows = db
+        .prepare(
+            "SELECT t.id, t.name, t.email, 'owner' AS via, NULL AS ref, NULL AS role, 0 AS pos
+               FROM tenants t WHERE t.email = ?1
+             UNION ALL
+             SELECT t.id, t.name, t.email, 'agency', c.agency_id, NULL, 1
+               FROM agency_clients c
+               JOIN tenants a ON a.id = c.agency_id
+               JOIN tenants t ON t.id = c.tenant_id
+              WHERE a.email = ?1
+             UNION ALL
+             SELECT t.id, t.name, t.email, 'member', m.id, m.role, 2
+               FROM tenant_members m JOIN tenants t ON t.id = m.tenant_id
+              WHERE m.email = ?2 AND m.accepted_at IS NOT NULL
+             ORDER BY pos, name, email",
+        )
+        .bind(&[email.into(), team::normalize_email(email).into()])?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+
+    let mut reach: Vec<Reach> = Vec::new();
+    for row in &rows {
+        let s = |k: &str| row.get(k).and_then(|v| v.as_str()).unwrap_or("");
+        let tenant_id = s("id");
+        if reach.iter().any(|r| r.tenant_id == tenant_id) {
+            continue;
+        }
+        let access = match s("via") {
+            "owner" => Access::Owner,
+            "agency" => Access::Agency {
+                agency_id: s("ref").to_string(),
+            },
+            _ => Access::Member {
+                member_id: s("ref").to_string(),
+                role: MemberRole::from_wire(s("role")).unwrap_or(MemberRole::Viewer),
+            },
+        };
+        reach.push(Reach {
+            tenant_id: tenant_id.to_string(),
+            name: display_name(row, s("email")),
+            access,
+        });
+    }
+    Ok(reach)
+}
+
+/// The agency's clients by name.
+pub async fn clients(db: &D1Database, agency_id: &str) -> Result<Vec<Client>> {
+    let rows = db
+        .prepare(
+            "SELECT c.*, t.name, t.email FROM agency_clients c
+             JOIN tenants t ON t.id = c.tenant_id
+             WHERE c.agency_id = ?1 ORDER BY t.name, t.email",
+        )
+        .bind(&[agency_id.into()])?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    Ok(rows.iter().map(Client::from_row).collect())
+}
+
+/// The name of the agency managing `tenant_id`, if any.
+pub async fn managed_by(db: &D1Database, tenant_id: &str) -> Result<Option<String>> {
+    let row = db
+        .prepare(
+            "SELECT a.name, a.email FROM agency_clients c JOIN tenants a ON a.id = c.agency_id
+             WHERE c.tenant_id = ?1",
+        )
+        .bind(&[tenant_id.into()])?
+        .first::<serde_json::Value>(None)
+        .await?;
+    Ok(row.map(|r| {
+        let email = r.get("email").and_then(|v| v.as_str()).unwrap_or("");
+        display_name(&r, email)
+    }))
+}
+
+/// The agency owner's address while `agency_id` still manages
+/// `tenant_id`; what a session switched in through the agency acts as.
+pub async fn agency_login(
+    db: &D1Database,
+    agency_id: &str,
+    tenant_id: &str,
+) -> Result<Option<String>> {
+    let row = db
+        .prepare(
+            "SELECT a.email FROM agency_clients c JOIN tenants a ON a.id = c.agency_id
+             WHERE c.tenant_id = ?1 AND c.agency_id = ?2",
+        )
+        .bind(&[tenant_id.into(), agency_id.into()])?
+        .first::<serde_json::Value>(None)
+        .await?;
+    Ok(row.and_then(|r| r.get("email").and_then(|v| v.as_str()).map(str::to_string)))
+}
+
+/// An agency's request to manage the account of `email`, waiting for
+/// that address's owner to accept or decline.
+#[derive(Debug, Clone)]
+pub struct Invite {
+    pub id: String,
+    pub agency_id: String,
+    /// The agency's name, or its address when it has none.
+    pub agency: String,
+    pub email: String,
+    /// The business name the agency gave.
+    pub name: String,
+    pub billing: ClientBilling,
+    pub created_at: String,
+}
+
+impl Invite {
+    fn from_row(row: &serde_json::Value) -> Self {
+        let s = |k: &str| {
+            row.get(k)
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string()
+        };
+        let agency_email = s("agency_email");
+        let agency = row
+            .get("agency_name")
+            .and_then(|v| v.as_str())
+            .filter(|n| !n.trim().is_empty())
+            .map(str::to_string)
+            .unwrap_or(agency_email);
+        Invite {
+            id: s("id"),
+            agency_id: s("agency_id"),
+            agency,
+            email: s("email"),
+            name: s("name"),
+            billing: ClientBilling::from_wire(&s("billing")).unwrap_or_default(),
+            created_at: s("created_at"),
+        }
+    }
+}
+
+/// Invites an agency can have waiting at once.
+pub const MAX_PENDING_INVITES: usize = 25;
+
+const INSERT_INVITE: &str =
+    "INSERT OR IGNORE INTO agency_invites (id, agency_id, email, name, billing)
+    VALUES (?1, ?2, ?3, ?4, ?5)";
+
+const INVITE_COLUMNS: &str = "SELECT i.*, a.name AS agency_name, a.email AS agency_email
+    FROM agency_invites i JOIN tenants a ON a.id = i.agency_id";
+
+/// Record an invite for `email`. `Ok(None)` when the agency already has
+/// one out for that address.
+pub async fn invite(
+    db: &D1Database,
+    agency_id: &str,
+    email: &str,
+    name: &str,
+    billing: ClientBilling,
+) -> Result<Option<String>> {
+    let id = generate_id();
+    let result = db
+        .prepare(INSERT_INVITE)
+        .bind(&[
+            id.as_str().into(),
+            agency_id.into(),
+            team::normalize_email(email).into(),
+            name.into(),
+            billing.as_str().into(),
+        ])?
+        .run()
+        .await?;
+    let inserted = result
+        .meta()?
+        .and_then(|m| m.changes)
+        .is_some_and(|n| n > 0);
+    Ok(inserted.then_some(id))
+}
+
+pub async fn get_invite(db: &D1Database, id: &str) -> Result<Option<Invite>> {
+    let row = db
+        .prepare(format!("{INVITE_COLUMNS} WHERE i.id = ?1"))
+        .bind(&[id.into()])?
+        .first::<serde_json::Value>(None)
+        .await?;
+    Ok(row.as_ref().map(Invite::from_row))
+}
+
+/// The invites an agency has out, oldest first.
+pub async fn invites_sent(db: &D1Database, agency_id: &str) -> Result<Vec<Invite>> {
+    let rows = db
+        .prepare(format!(
+            "{INVITE_COLUMNS} WHERE i.agency_id = ?1 ORDER BY i.created_at, i.email"
+        ))
+        .bind(&[agency_id.into()])?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    Ok(rows.iter().map(Invite::from_row).collect())
+}
+
+/// The invites waiting for the owner of `email`, oldest first.
+pub async fn invites_for(db: &D1Database, email: &str) -> Result<Vec<Invite>> {
+    let rows = db
+        .prepare(format!(
+            "{INVITE_COLUMNS} WHERE i.email = ?1 ORDER BY i.created_at"
+        ))
+        .bind(&[team::normalize_email(email).into()])?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    Ok(rows.iter().map(Invite::from_row).collect())
+}
+
+/// Withdraw or decline an invite.
+pub async fn remove_invite(db: &D1Database, id: &str) -> Result<()> {
+    db.prepare("DELETE FROM agency_invites WHERE id = ?1")
+        .bind(&[id.into()])?
+        .run()
+        .await?;
+    Ok(())
+}
+
+const INSERT_CLIENT: &str =
+    "INSERT INTO agency_clients (tenant_id, agency_id, billing) VALUES (?1, ?2, ?3)";
+const CLEAR_INVITES: &str = "DELETE FROM agency_invites WHERE email = ?1";
+
+/// Link `tenant_id` to the invite's agency on the invite's billing. The
+/// other invites for the same address go with it: a client has one agency.
+/// The caller checks `tenant_id` is owned by the invited address.
+pub async fn accept_invite(db: &D1Database, invite: &Invite, tenant_id: &str) -> Result<()> {
+    let link = db.prepare(INSERT_CLIENT).bind(&[
+        tenant_id.into(),
+        invite.agency_id.as_str().into(),
+        invite.billing.as_str().into(),
+    ])?;
+    let clear = db
+        .prepare(CLEAR_INVITES)
+        .bind(&[invite.email.as_str().into()])?;
+    db.batch(vec![link, clear]).await?;
+    Ok(())
+}
+
+pub fn invite_url(base_url: &str) -> String {
+    format!("{base_url}/admin/settings")
+}
+
+pub fn build_invite_email(invite: &Invite, url: &str, from_addr: &str) -> OutboundEmail {
+    let agency = &invite.agency;
+    let business = &invite.name;
+    let billing = match invite.billing {
+        ClientBilling::Agency => "They'd pay for your AI replies from their own credits.",
+        ClientBilling::Client => "You'd keep paying for your own AI replies.",
+    };
+    let text = format!(
+        "{agency} asked to manage {business} for you on Concierge. If you accept, they can \
+         set up channels, edit the persona and answer customers from your account. {billing}\n\n\
+         Review it: {url}\n\n\
+         Sign in with Google or Facebook as {email}, then accept or decline in Settings. \
+         Nothing changes until you accept.\n",
+        email = invite.email,
+    );
+    let html = format!(
+        r#"<!doctype html>
+<html><body style="font-family:-apple-system,BlinkMacSystemFont,sans-serif;max-width:640px;margin:0 auto;padding:24px">
+<h1 style="font-size:20px;margin:0 0 12px">{agency} wants to manage {business} on Concierge</h1>
+<p>If you accept, they can set up channels, edit the persona and answer customers from your account. {billing}</p>
+<p style="margin-top:24px"><a href="{url}" style="display:inline-block;padding:8px 14px;background:#5865F2;color:#fff;border-radius:4px;text-decoration:none">Review the request</a></p>
+<p style="color:#999;font-size:12px;margin-top:32px">Sign in with Google or Facebook as {email}, then accept or decline in Settings. Nothing changes until you accept.</p>
+</body></html>"#,
+        agency = html_escape(agency),
+        business = html_escape(business),
+        url = html_escape(url),
+        email = html_escape(&invite.email),
+    );
+    OutboundEmail {
+        from: from_addr.to_string(),
+        to: invite.email.clone(),
+        subject: format!("{agency} wants to manage {business} on Concierge"),
+        text: Some(text),
+        html: Some(html),
+        reply_to: None,
+        cc: vec![],
+        bcc: vec![],
+        headers: vec![],
+    }
+}
+
+/// Whether the agency may move a client from `from` to `to` itself. It
+/// can take on a client's replies, but putting them back on the client's
+/// credits is the client owner's call (`pay_own_replies`).
+pub fn agency_can_rebill(from: ClientBilling, to: ClientBilling) -> bool {
+    !(from == ClientBilling::Agency && to == ClientBilling::Client)
+}
+
+pub async fn set_billing(
+    db: &D1Database,
+    agency_id: &str,
+    tenant_id: &str,
+    billing: ClientBilling,
+) -> Result<()> {
+    db.prepare("UPDATE agency_clients SET billing = ?3 WHERE agency_id = ?1 AND tenant_id = ?2")
+        .bind(&[agency_id.into(), tenant_id.into(), billing.as_str().into()])?
+        .run()
+        .await?;
+    Ok(())
+}
+
+/// The client's owner takes its replies back onto their own credits.
+pub async fn pay_own_replies(db: &D1Database, tenant_id: &str) -> Result<()> {
+    db.prepare("UPDATE agency_clients SET billing = 'client' WHERE tenant_id = ?1")
+        .bind(&[tenant_id.into()])?
+        .run()
+        .await?;
+    Ok(())
+}
+
+/// End the agency's access to `tenant_id`. Sessions switched into it stop
+/// working on their next request. The client's tenant stays as it is.
+pub async fn remove_client(db: &D1Database, tenant_id: &str) -> Result<()> {
+    db.prepare("DELETE FROM agency_clients WHERE tenant_id = ?1")
+        .bind(&[tenant_id.into()])?
+        .run()
+        .await?;
+    Ok(())
+}
+
+/// The tenant whose credits pay for `tenant_id`'s replies.
+pub async fn payer(db: &D1Database, tenant_id: &str) -> Result<String> {
+    let row = db
+        .prepare(
+            "SELECT c.agency_id FROM agency_clients c JOIN tenants a ON a.id = c.agency_id
+             WHERE c.tenant_id = ?1 AND c.billing = 'agency'",
+        )
+        .bind(&[tenant_id.into()])?
+        .first::<serde_json::Value>(None)
+        .await?;
+    Ok(row
+        .and_then(|r| {
+            r.get("agency_id")
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| tenant_id.to_string()))
+}
+
+/// A client's row on the agency overview.
+#[derive(Debug, Clone)]
+pub struct ClientStatus {
+    pub client: Client,
+    /// The client's own balance; agency-paid clients draw on the agency's.
+    pub credits: i64,
+    pub pending_approvals: usize,
+    /// WhatsApp numbers, Instagram accounts and email addresses.
+    pub channels: usize,
+    /// Instagram accounts switched off.
+    pub channels_off: usize,
+    pub discord: bool,
+    pub alerts: u32,
+    pub worst_alert: Option<NotificationSeverity>,
+}
+
+/// How a client's connections look at a glance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Health {
+    Healthy,
+    Check,
+    NeedsAttention,
+    NotConnected,
+}
+
+impl Health {
+    pub fn label(self) -> &'static str {
+        match self {
+            Health::Healthy => "Healthy",
+            Health::Check => "Check",
+            Health::NeedsAttention => "Needs attention",
+            Health::NotConnected => "No channels",
+        }
+    }
+}
+
+impl ClientStatus {
+    /// Unread critical notifications (an expired Instagram token, no
+    /// credits) need attention; warnings or a switched-off channel are worth
+    /// a look.
+    pub fn health(&self) -> Health {
+        if self.channels == 0 {
+            Health::NotConnected
+        } else if self.worst_alert == Some(NotificationSeverity::Critical) {
+            Health::NeedsAttention
+        } else if self.worst_alert == Some(NotificationSeverity::Warning) || self.channels_off > 0 {
+            Health::Check
+        } else {
+            Health::Healthy
+        }
+    }
+}
+
+pub async fn status(kv: &kv::KvStore, db: &D1Database, client: Client) -> Result<ClientStatus> {
+    let tenant_id = client.tenant_id.as_str();
+    let credits = get_tenant_billing(db, tenant_id).await?.total_remaining();
+    let pending_approvals = approvals::count_pending(db, tenant_id).await?;
+    let whatsapp = list_whatsapp_accounts(kv, tenant_id).await?;
+    let instagram = list_instagram_accounts(kv, tenant_id).await?;
+    let email = get_email_addresses(kv, tenant_id).await?;
+    let discord = get_discord_config_by_tenant(kv, tenant_id).await?.is_some();
+    let (alerts, worst_alert) = notifications::unread(db, tenant_id).await?;
+    Ok(ClientStatus {
+        channels: whatsapp.len() + instagram.len() + email.len(),
+        channels_off: instagram.iter().filter(|a| !a.enabled).count(),
+        client,
+        credits,
+        pending_approvals,
+        discord,
+        alerts,
+        worst_alert,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(channels: usize, off: usize, worst: Option<NotificationSeverity>) -> ClientStatus {
+        ClientStatus {
+            client: Client {
+                tenant_id: "t1".into(),
+                name: "Rose Salon".into(),
+                email: "rose@example.com".into(),
+                billing: ClientBilling::Client,
+            },
+            credits: 0,
+            pending_approvals: 0,
+            channels,
+            channels_off: off,
+            discord: false,
+            alerts: u32::from(worst.is_some()),
+            worst_alert: worst,
+        }
+    }
+
+    #[test]
+    fn health_follows_channels_and_alerts() {
+        assert_eq!(status(0, 0, None).health(), Health::NotConnected);
+        assert_eq!(status(2, 0, None).health(), Health::Healthy);
+        assert_eq!(status(2, 1, None).health(), Health::Check);
+        assert_eq!(
+            status(2, 0, Some(NotificationSeverity::Info)).health(),
+            Health::Healthy
+        );
+        assert_eq!(
+            status(2, 0, Some(NotificationSeverity::Warning)).health(),
+            Health::Check
+        );
+        assert_eq!(
+            status(1, 1, Some(NotificationSeverity::Critical)).health(),
+            Health::NeedsAttention
+        );
+    }
+
+    #[test]
+    fn only_the_client_moves_replies_onto_its_credits() {
+        use ClientBilling::{Agency, Client};
+        assert!(agency_can_rebill(Client, Agency));
+        assert!(agency_can_rebill(Agency, Agency));
+        assert!(agency_can_rebill(Client, Client));
+        assert!(!agency_can_rebill(Agency, Client));
+    }
+
+    #[test]
+    fn reach_acts_as_owner_agency_or_member() {
+        let reach = |access| Reach {
+            tenant_id: "t1".into(),
+            name: "Rose Salon".into(),
+            access,
+        };
+        let owner = reach(Access::Owner).actor("me@agency.example");
+        assert_eq!(owner.role, MemberRole::Owner);
+        assert_eq!(owner.email, None);
+
+        let agency = reach(Access::Agency {
+            agency_id: "a1".into(),
+        })
+        .actor("me@agency.example");
+        assert_eq!(agency.role, MemberRole::Admin);
+        assert_eq!(agency.agency_id.as_deref(), Some("a1"));
+        assert_eq!(agency.email.as_deref(), Some("me@agency.example"));
+
+        let member = reach(Access::Member {
+            member_id: "m1".into(),
+            role: MemberRole::Approver,
+        })
+        .actor("Me@Agency.example");
+        assert_eq!(member.role, MemberRole::Approver);
+        assert_eq!(member.member_id.as_deref(), Some("m1"));
+        assert_eq!(member.email.as_deref(), Some("me@agency.example"));
+    }
+
+    fn schema() -> rusqlite::Connection {
+        let db = rusqlite::Connection::open_in_memory().unwrap();
+        db.execute_batch(include_str!("../migrations/0001_create_schema.sql"))
+            .unwrap();
+        db
+    }
+
+    #[test]
+    fn invite_links_nothing_until_accepted() {
+        let db = schema();
+        db.execute(
+            "INSERT INTO tenants (id, email, name) VALUES ('a1', 'me@agency.example', 'Acme Agency'),
+             ('a2', 'you@other.example', NULL), ('t1', 'rose@example.com', NULL)",
+            [],
+        )
+        .unwrap();
+        let invite = |id: &str, agency: &str| {
+            db.execute(
+                INSERT_INVITE,
+                rusqlite::params![id, agency, "rose@example.com", "Rose Salon", "agency"],
+            )
+            .unwrap()
+        };
+        assert_eq!(invite("i1", "a1"), 1);
+        // One invite per agency and address.
+        assert_eq!(invite("i2", "a1"), 0);
+        assert_eq!(invite("i3", "a2"), 1);
+
+        let count = |table: &str| -> i64 {
+            db.query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |r| r.get(0))
+                .unwrap()
+        };
+        assert_eq!(count("agency_clients"), 0);
+
+        let row: serde_json::Value = db
+            .query_row(&format!("{INVITE_COLUMNS} WHERE i.id = 'i1'"), [], |r| {
+                Ok(serde_json::json!({
+                    "id": r.get::<_, String>("id")?,
+                    "agency_id": r.get::<_, String>("agency_id")?,
+                    "email": r.get::<_, String>("email")?,
+                    "name": r.get::<_, String>("name")?,
+                    "billing": r.get::<_, String>("billing")?,
+                    "agency_name": r.get::<_, Option<String>>("agency_name")?,
+                    "agency_email": r.get::<_, String>("agency_email")?,
+                }))
+            })
+            .unwrap();
+        let accepted = Invite::from_row(&row);
+        assert_eq!(accepted.agency, "Acme Agency");
+        assert_eq!(accepted.billing, ClientBilling::Agency);
+
+        db.execute(
+            INSERT_CLIENT,
+            rusqlite::params!["t1", accepted.agency_id, accepted.billing.as_str()],
+        )
+        .unwrap();
+        db.execute(CLEAR_INVITES, rusqlite::params![accepted.email])
+            .unwrap();
+        assert_eq!(count("agency_clients"), 1);
+        // The other agency's invite goes too: a client has one agency.
+        assert_eq!(count("agency_invites"), 0);
+    }
+
+    #[test]
+    fn invite_email_says_nothing_changes_until_accepted() {
+        let invite = Invite {
+            id: "i1".into(),
+            agency_id: "a1".into(),
+            agency: "Acme <Agency>".into(),
+            email: "rose@example.com".into(),
+            name: "Rose Salon".into(),
+            billing: ClientBilling::Client,
+            created_at: String::new(),
+        };
+        let email = build_invite_email(
+            &invite,
+            &invite_url("https://example.com"),
+            "noreply@example.com",
+        );
+        assert_eq!(email.to, "rose@example.com");
+        assert_eq!(
+            email.subject,
+            "Acme <Agency> wants to manage Rose Salon on Concierge"
+        );
+        let html = email.html.unwrap();
+        assert!(html.contains("Acme &lt;Agency&gt;"));
+        assert!(html.contains(r#"href="https://example.com/admin/settings""#));
+        let text = email.text.unwrap();
+        assert!(text.contains("You'd keep paying for your own AI replies."));
+        assert!(text.contains("Nothing changes until you accept."));
+    }
+}
@@ -0,0 +1,1171 @@
+//! AI provider abstraction.
+//!
+//! Every model call goes through here so the backend is an operator choice
+//! rather than a hard-coded `env.ai("AI")`. Three backends:
+//!
+//! - `WorkersAi` — the `AI` binding (default).
+//! - `OpenAi` — any OpenAI-compatible HTTP API (`/chat/completions`,
+//!   `/embeddings`), configured by `AI_OPENAI_BASE_URL` and the
+//!   `AI_OPENAI_API_KEY` secret.
+//! - `Stub` — deterministic, offline answers for tests and local dev.
+//!
+//! A call resolves to an ordered list of [`Target`]s: the tenant's pinned
+//! provider (or the operator default), then the operator fallback. Targets
+//! are tried in order and the first success wins.
+//!
+//! Every successful call also returns a [`Usage`]: the model that answered
+//! and its token counts, as reported by the backend or estimated from text
+//! length when it doesn't say.
+
+use worker::*;
+
+use crate::types::{AiOverride, AiProvider};
+
+const WORKERS_AI_MODEL: &str = "@cf/meta/llama-4-scout-17b-16e-instruct";
+const WORKERS_AI_FAST_MODEL: &str = "@cf/meta/llama-3.1-8b-instruct-fast";
+const WORKERS_AI_EMBEDDING_MODEL: &str = "@cf/baai/bge-base-en-v1.5";
+
+const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+const OPENAI_MODEL: &str = "gpt-4o-mini";
+const OPENAI_FAST_MODEL: &str = "gpt-4o-mini";
+const OPENAI_EMBEDDING_MODEL: &str = "text-embedding-3-small";
+
+const STUB_MODEL: &str = "stub";
+/// Dimensions of the stub's hashed bag-of-words embedding.
+const STUB_DIMS: usize = 64;
+
+#[derive(Clone, Debug, Default)]
+pub struct Message {
+    pub role: String,
+    pub content: String,
+    /// Set on an assistant turn that asked for tools.
+    pub tool_calls: Vec<ToolCall>,
+    /// Set on a `tool` turn: the call it answers.
+    pub tool_call: Option<ToolCall>,
+}
+
+impl Message {
+    pub fn system(content: impl Into<String>) -> Self {
+        Self {
+            role: "system".to_string(),
+            content: content.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self {
+            role: "user".to_string(),
+            content: content.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Echo of the model's own tool request, replayed so the follow-up
+    /// turn can see what it asked for.
+    pub fn tool_request(calls: Vec<ToolCall>) -> Self {
+        Self {
+            role: "assistant".to_string(),
+            tool_calls: calls,
+            ..Default::default()
+        }
+    }
+
+    pub fn tool_result(call: &ToolCall, content: impl Into<String>) -> Self {
+        Self {
+            role: "tool".to_string(),
+            content: content.into(),
+            tool_call: Some(call.clone()),
+            ..Default::default()
+        }
+    }
+
+    /// OpenAI chat-completions shape: tool requests carry ids and
+    /// stringified arguments; results reference the id.
+    fn to_openai(&self) -> serde_json::Value {
+        if !self.tool_calls.is_empty() {
+            let calls: Vec<serde_json::Value> = self
+                .tool_calls
+                .iter()
+                .map(|c| {
+                    serde_json::json!({
+                        "id": c.id,
+                        "type": "function",
+                        "function": { "name": c.name, "arguments": c.arguments.to_string() },
+                    })
+                })
+                .collect();
+            return serde_json::json!({ "role": "assistant", "content": null, "tool_calls": calls });
+        }
+        match &self.tool_call {
+            Some(call) => serde_json::json!({
+                "role": "tool",
+                "tool_call_id": call.id,
+                "content": self.content,
+            }),
+            None => serde_json::json!({ "role": self.role, "content": self.content }),
+        }
+    }
+
+    /// Workers AI shape: no call ids; the assistant's request is replayed
+    /// as JSON text and results are matched by tool name.
+    fn to_workers_ai(&self) -> serde_json::Value {
+        if !self.tool_calls.is_empty() {
+            let calls: Vec<serde_json::Value> = self
+                .tool_calls
+                .iter()
+                .map(|c| serde_json::json!({ "name": c.name, "arguments": c.arguments }))
+                .collect();
+            return serde_json::json!({
+                "role": "assistant",
+                "content": serde_json::Value::Array(calls).to_string(),
+            });
+        }
+        match &self.tool_call {
+            Some(call) => serde_json::json!({
+                "role": "tool",
+                "name": call.name,
+                "content": self.content,
+            }),
+            None => serde_json::json!({ "role": self.role, "content": self.content }),
+        }
+    }
+}
+
+/// A function the model may call, in the JSON-Schema form both backends
+/// accept.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ToolSpec {
+    pub name: String,
+    pub description: String,
+    pub parameters: serde_json::Value,
+}
+
+/// One function call the model asked for. `id` is the backend's call id
+/// where it has one (OpenAI), else synthesized.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    pub arguments: serde_json::Value,
+}
+
+/// What a completion turn produced: final text, or a request to run tools
+/// before answering.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Turn {
+    Text(String),
+    ToolCalls(Vec<ToolCall>),
+}
+
+/// Why a model is being called. Picks the model slot and lets the stub
+/// return something the caller can parse.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Purpose {
+    /// Drafting a customer-facing reply.
+    Reply,
+    /// Prompt-injection scan of inbound text.
+    Scan,
+    /// Persona safety classification.
+    Safety,
+    /// Text embedding for rule matching.
+    Embed,
+}
+
+/// Model slot within a provider.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tier {
+    Quality,
+    Fast,
+    Embedding,
+}
+
+impl Purpose {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Purpose::Reply => "reply",
+            Purpose::Scan => "scan",
+            Purpose::Safety => "safety",
+            Purpose::Embed => "embed",
+        }
+    }
+
+    pub fn tier(self) -> Tier {
+        match self {
+            Purpose::Reply => Tier::Quality,
+            Purpose::Scan | Purpose::Safety => Tier::Fast,
+            Purpose::Embed => Tier::Embedding,
+        }
+    }
+}
+
+impl Tier {
+    pub const ALL: [Tier; 3] = [Tier::Fast, Tier::Quality, Tier::Embedding];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Tier::Quality => "quality",
+            Tier::Fast => "fast",
+            Tier::Embedding => "embedding",
+        }
+    }
+
+    pub fn from_wire(s: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|t| t.as_str() == s)
+    }
+}
+
+impl From<crate::types::ModelTier> for Tier {
+    fn from(t: crate::types::ModelTier) -> Self {
+        match t {
+            crate::types::ModelTier::Fast => Tier::Fast,
+            crate::types::ModelTier::Quality => Tier::Quality,
+        }
+    }
+}
+
+/// Sampling knobs passed through to the backend. `None` keeps the
+/// provider's default. Workers AI and OpenAI-compatible APIs share the
+/// `temperature` / `max_tokens` names.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ChatOptions {
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+}
+
+impl ChatOptions {
+    fn apply(&self, payload: &mut serde_json::Value) {
+        if let Some(t) = self.temperature {
+            payload["temperature"] = serde_json::json!(t);
+        }
+        if let Some(n) = self.max_tokens {
+            payload["max_tokens"] = serde_json::json!(n);
+        }
+    }
+}
+
+/// One concrete provider + model to try.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Target {
+    pub provider: AiProvider,
+    pub model: String,
+}
+
+/// What one call (or a multi-turn exchange, summed) consumed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Usage {
+    pub provider: AiProvider,
+    pub model: String,
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    /// The backend didn't report counts; they're `estimate_tokens` guesses.
+    pub estimated: bool,
+}
+
+impl Usage {
+    fn new(target: &Target, reported: Option<(u32, u32)>, input: u32, output: u32) -> Self {
+        let (input_tokens, output_tokens, estimated) = match reported {
+            Some((i, o)) => (i, o, false),
+            None => (input, output, true),
+        };
+        Self {
+            provider: target.provider,
+            model: target.model.clone(),
+            input_tokens,
+            output_tokens,
+            estimated,
+        }
+    }
+
+    /// Fold a later turn into a running total. The model is the last one
+    /// that answered.
+    pub fn add(&mut self, other: &Usage) {
+        self.provider = other.provider;
+        self.model = other.model.clone();
+        self.input_tokens = self.input_tokens.saturating_add(other.input_tokens);
+        self.output_tokens = self.output_tokens.saturating_add(other.output_tokens);
+        self.estimated |= other.estimated;
+    }
+
+    pub fn total_tokens(&self) -> u32 {
+        self.input_tokens.saturating_add(self.output_tokens)
+    }
+}
+
+/// Rough token count for backends that don't report usage: ~4 characters
+/// per token, the usual rule of thumb for English BPE vocabularies.
+pub fn estimate_tokens(text: &str) -> u32 {
+    (text.chars().count() as u32).div_ceil(4)
+}
+
+fn estimate_input(messages: &[Message], tools: &[ToolSpec]) -> u32 {
+    let messages: u32 = messages
+        .iter()
+        .map(|m| {
+            let calls: u32 = m
+                .tool_calls
+                .iter()
+                .map(|c| estimate_tokens(&c.name) + estimate_tokens(&c.arguments.to_string()))
+                .sum();
+            estimate_tokens(&m.content) + calls
+        })
+        .sum();
+    let tools: u32 = tools
+        .iter()
+        .map(|t| {
+            estimate_tokens(&t.name)
+                + estimate_tokens(&t.description)
+                + estimate_tokens(&t.parameters.to_string())
+        })
+        .sum();
+    messages + tools
+}
+
+fn estimate_output(turn: &Turn) -> u32 {
+    match turn {
+        Turn::Text(text) => estimate_tokens(text),
+        Turn::ToolCalls(calls) => calls
+            .iter()
+            .map(|c| estimate_tokens(&c.name) + estimate_tokens(&c.arguments.to_string()))
+            .sum(),
+    }
+}
+
+/// `usage: { prompt_tokens, completion_tokens }`, which both OpenAI and
+/// the Workers AI text models that report usage return.
+fn reported_usage(response: &serde_json::Value) -> Option<(u32, u32)> {
+    let usage = response.get("usage")?;
+    let n = |k: &str| usage.get(k).and_then(|v| v.as_u64()).map(|v| v as u32);
+    let input = n("prompt_tokens")?;
+    Some((input, n("completion_tokens").unwrap_or(0)))
+}
+
+// ============================================================================
+// Operator settings
+// ============================================================================
+
+/// Operator-level configuration, read from worker vars. Built from a
+/// lookup closure so resolution can be unit-tested without an `Env`.
+#[derive(Clone, Debug, Default)]
+pub struct Settings {
+    pub primary: AiProvider,
+    pub fallback: Option<AiProvider>,
+    pub fallback_model: Option<String>,
+    models: Vec<(&'static str, String)>,
+}
+
+/// Var names that override a provider's default model for a tier.
+fn model_var(provider: AiProvider, tier: Tier) -> Option<&'static str> {
+    match (provider, tier) {
+        (AiProvider::WorkersAi, Tier::Quality) => Some("AI_MODEL"),
+        (AiProvider::WorkersAi, Tier::Fast) => Some("AI_FAST_MODEL"),
+        (AiProvider::WorkersAi, Tier::Embedding) => Some("AI_EMBEDDING_MODEL"),
+        (AiProvider::OpenAi, Tier::Quality) => Some("AI_OPENAI_MODEL"),
+        (AiProvider::OpenAi, Tier::Fast) => Some("AI_OPENAI_FAST_MODEL"),
+        (AiProvider::OpenAi, Tier::Embedding) => Some("AI_OPENAI_EMBEDDING_MODEL"),
+        (AiProvider::Stub, _) => None,
+    }
+}
+
+fn default_model(provider: AiProvider, tier: Tier) -> &'static str {
+    match (provider, tier) {
+        (AiProvider::WorkersAi, Tier::Quality) => WORKERS_AI_MODEL,
+        (AiProvider::WorkersAi, Tier::Fast) => WORKERS_AI_FAST_MODEL,
+        (AiProvider::WorkersAi, Tier::Embedding) => WORKERS_AI_EMBEDDING_MODEL,
+        (AiProvider::OpenAi, Tier::Quality) => OPENAI_MODEL,
+        (AiProvider::OpenAi, Tier::Fast) => OPENAI_FAST_MODEL,
+        (AiProvider::OpenAi, Tier::Embedding) => OPENAI_EMBEDDING_MODEL,
+        (AiProvider::Stub, _) => STUB_MODEL,
+    }
+}
+
+const MODEL_VARS: &[&str] = &[
+    "AI_MODEL",
+    "AI_FAST_MODEL",
+    "AI_EMBEDDING_MODEL",
+    "AI_OPENAI_MODEL",
+    "AI_OPENAI_FAST_MODEL",
+    "AI_OPENAI_EMBEDDING_MODEL",
+];
+
+impl Settings {
+    pub fn from_env(env: &Env) -> Self {
+        Self::from_lookup(|name| {
+            env.var(name)
+                .ok()
+                .map(|v| v.to_string())
+                .filter(|s| !s.trim().is_empty())
+        })
+    }
+
+    /// Unknown provider names fall back to Workers AI for the primary and
+    /// to "no fallback" for the secondary, so a typo can't take replies
+    /// down entirely.
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let primary = lookup("AI_PROVIDER")
+            .and_then(|s| AiProvider::from_wire(s.trim()))
+            .unwrap_or_default();
+        let fallback = lookup("AI_FALLBACK_PROVIDER").and_then(|s| AiProvider::from_wire(s.trim()));
+        let fallback_model = lookup("AI_FALLBACK_MODEL");
+        let models = MODEL_VARS
+            .iter()
+            .filter_map(|name| lookup(name).map(|v| (*name, v)))
+            .collect();
+        Self {
+            primary,
+            fallback,
+            fallback_model,
+            models,
+        }
+    }
+
+    /// Operator model for a provider/tier: the override var if set, else
+    /// the baked-in default.
+    pub fn model_for(&self, provider: AiProvider, tier: Tier) -> String {
+        model_var(provider, tier)
+            .and_then(|var| self.models.iter().find(|(k, _)| *k == var))
+            .map(|(_, v)| v.clone())
+            .unwrap_or_else(|| default_model(provider, tier).to_string())
+    }
+
+    /// Ordered targets for one call. The tenant pin (if any) replaces the
+    /// operator primary; the operator fallback is appended unless it
+    /// resolves to the same target. `AI_FALLBACK_MODEL` alone means "same
+    /// provider, different model". It only applies to chat tiers — an
+    /// embedding from an unrelated model would never match stored vectors.
+    pub fn chain(&self, tier: Tier, tenant: Option<&AiOverride>) -> Vec<Target> {
+        let provider = tenant.map(|o| o.provider).unwrap_or(self.primary);
+        let pinned_model = tenant.and_then(|o| match tier {
+            Tier::Quality => o.model.clone(),
+            Tier::Fast => o.fast_model.clone(),
+            Tier::Embedding => None,
+        });
+        let primary = Target {
+            provider,
+            model: pinned_model
+                .filter(|m| !m.trim().is_empty())
+                .unwrap_or_else(|| self.model_for(provider, tier)),
+        };
+
+        let fallback_provider = match (self.fallback, &self.fallback_model) {
+            (Some(p), _) => Some(p),
+            (None, Some(_)) => Some(self.primary),
+            (None, None) => None,
+        };
+        let fallback = fallback_provider.map(|p| Target {
+            provider: p,
+            model: match (tier, &self.fallback_model) {
+                (Tier::Quality | Tier::Fast, Some(m)) => m.clone(),
+                _ => self.model_for(p, tier),
+            },
+        });
+
+        let mut chain = vec![primary];
+        if let Some(f) = fallback {
+            if !chain.contains(&f) {
+                chain.push(f);
+            }
+        }
+        chain
+    }
+}
+
+// ============================================================================
+// Dispatch
+// ============================================================================
+
+/// Run a chat completion against each target in turn. Returns the text of
+/// the first success, or the last error if every target failed.
+pub async fn chat(
+    env: &Env,
+    targets: &[Target],
+    purpose: Purpose,
+    opts: &ChatOptions,
+    messages: &[Message],
+) -> Result<(String, Usage)> {
+    match complete(env, targets, purpose, opts, messages, &[]).await? {
+        (Turn::Text(text), usage) => Ok((text, usage)),
+        (Turn::ToolCalls(_), _) => Err(Error::from("Model asked for tools that weren't offered")),
+    }
+}
+
+/// One completion turn with `tools` on offer (none = plain chat). Tries
+/// each target in turn like `chat`; a tool request from one target is a
+/// success, so the follow-up turn starts again from the first target.
+pub async fn complete(
+    env: &Env,
+    targets: &[Target],
+    purpose: Purpose,
+    opts: &ChatOptions,
+    messages: &[Message],
+    tools: &[ToolSpec],
+) -> Result<(Turn, Usage)> {
+    let mut last_err = Error::from("No AI provider configured");
+    for target in targets {
+        let result = match target.provider {
+            AiProvider::WorkersAi => {
+                workers_ai_chat(env, &target.model, opts, messages, tools).await
+            }
+            AiProvider::OpenAi => openai_chat(env, &target.model, opts, messages, tools).await,
+            AiProvider::Stub => Ok((stub_complete(purpose, messages, tools), None)),
+        };
+        match result {
+            Ok((turn, reported)) => {
+                let usage = Usage::new(
+                    target,
+                    reported,
+                    estimate_input(messages, tools),
+                    estimate_output(&turn),
+                );
+                return Ok((turn, usage));
+            }
+            Err(e) => {
+                console_log!(
+                    "AI chat via {} ({}) failed: {:?}",
+                    target.provider.as_str(),
+                    target.model,
+                    e
+                );
+                last_err = e;
+            }
+        }
+    }
+    Err(last_err)
+}
+
+/// Embed `text` against each target in turn. Returns the vector and its
+/// usage; `usage.model` is the model that produced it, so callers can
+/// refuse to compare vectors from different models.
+pub async fn embed(env: &Env, targets: &[Target], text: &str) -> Result<(Vec<f32>, Usage)> {
+    let mut last_err = Error::from("No AI provider configured");
+    for target in targets {
+        let result = match target.provider {
+            AiProvider::WorkersAi => workers_ai_embed(env, &target.model, text).await,
+            AiProvider::OpenAi => openai_embed(env, &target.model, text).await,
+            AiProvider::Stub => Ok((stub_embed(text), None)),
+        };
+        match result {
+            Ok((v, reported)) => {
+                let usage = Usage::new(target, reported, estimate_tokens(text), 0);
+                return Ok((v, usage));
+            }
+            Err(e) => {
+                console_log!(
+                    "AI embedding via {} ({}) failed: {:?}",
+                    target.provider.as_str(),
+                    target.model,
+                    e
+                );
+                last_err = e;
+            }
+        }
+    }
+    Err(last_err)
+}
+
+// ============================================================================
+// Workers AI
+// ============================================================================
+
+async fn workers_ai_chat(
+    env: &Env,
+    model: &str,
+    opts: &ChatOptions,
+    messages: &[Message],
+    tools: &[ToolSpec],
+) -> Result<(Turn, Option<(u32, u32)>)> {
+    let ai = env.ai("AI")?;
+    let messages: Vec<serde_json::Value> = messages.iter().map(Message::to_workers_ai).collect();
+    let mut input = serde_json::json!({ "messages": messages });
+    if !tools.is_empty() {
+        let tools: Vec<serde_json::Value> = tools
+            .iter()
+            .map(|t| {
+                serde_json::json!({
+                    "name": t.name,
+                    "description": t.description,
+                    "parameters": t.parameters,
+                })
+            })
+            .collect();
+        input["tools"] = serde_json::Value::Array(tools);
+    }
+    opts.apply(&mut input);
+    let response: serde_json::Value = ai
+        .run(model, input)
+        .await
+        .map_err(|e| Error::from(format!("AI model error: {:?}", e)))?;
+    let turn = parse_workers_ai_turn(&response)
+        .ok_or_else(|| Error::from("Workers AI response missing text"))?;
+    Ok((turn, reported_usage(&response)))
+}
+
+async fn workers_ai_embed(
+    env: &Env,
+    model: &str,
+    text: &str,
+) -> Result<(Vec<f32>, Option<(u32, u32)>)> {
+    let ai = env.ai("AI")?;
+    let input = serde_json::json!({ "text": [text] });
+    let response: serde_json::Value = ai
+        .run(model, input)
+        .await
+        .map_err(|e| Error::from(format!("Embedding model error: {:?}", e)))?;
+    Ok((
+        parse_workers_ai_embedding(&response)?,
+        reported_usage(&response),
+    ))
+}
+
+/// Workers AI text models return either a bare string or `{ "response": .. }`.
+fn parse_workers_ai_chat(response: &serde_json::Value) -> Option<String> {
+    response
+        .as_str()
+        .or_else(|| response.get("response").and_then(|r| r.as_str()))
+        .map(|s| s.to_string())
+}
+
+/// Tool requests come back as `tool_calls: [{ name, arguments }]` with
+/// `arguments` as an object (or, on some models, a JSON string).
+fn parse_workers_ai_turn(response: &serde_json::Value) -> Option<Turn> {
+    let calls = response
+        .get("tool_calls")
+        .and_then(|v| v.as_array())
+        .filter(|a| !a.is_empty());
+    match calls {
+        Some(calls) => Some(Turn::ToolCalls(
+            calls
+                .iter()
+                .enumerate()
+                .filter_map(|(i, c)| {
+                    Some(ToolCall {
+                        id: format!("call_{i}"),
+                        name: c.get("name")?.as_str()?.to_string(),
+                        arguments: arguments_value(c.get("arguments")),
+                    })
+                })
+                .collect(),
+        )),
+        None => parse_workers_ai_chat(response).map(Turn::Text),
+    }
+}
+
+/// Normalise call arguments to an object: parse stringified JSON, and
+/// treat anything unparseable as "no arguments".
+fn arguments_value(raw: Option<&serde_json::Value>) -> serde_json::Value {
+    let parsed = match raw {
+        Some(serde_json::Value::String(s)) => serde_json::from_str(s).ok(),
+        Some(v) => Some(v.clone()),
+        None => None,
+    };
+    match parsed {
+        Some(v @ serde_json::Value::Object(_)) => v,
+        _ => serde_json::json!({}),
+    }
+}
+
+/// BGE returns { "data": [[..floats..]], "shape": [...] }. Defensively
+/// accept either `data` or `embeddings`.
+fn parse_workers_ai_embedding(response: &serde_json::Value) -> Result<Vec<f32>> {
+    let arr = response
+        .get("data")
+        .or_else(|| response.get("embeddings"))
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| Error::from("Embedding response missing data array"))?;
+    let first = arr
+        .first()
+        .ok_or_else(|| Error::from("Embedding response data array empty"))?;
+    let vec = first
+        .as_array()
+        .ok_or_else(|| Error::from("Embedding response inner not array"))?;
+    Ok(floats(vec))
+}
+
+// ============================================================================
+// OpenAI-compatible HTTP
+// ============================================================================
+
+fn openai_base_url(env: &Env) -> String {
+    env.var("AI_OPENAI_BASE_URL")
+        .map(|v| v.to_string())
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or_else(|| OPENAI_BASE_URL.to_string())
+        .trim_end_matches('/')
+        .to_string()
+}
+
+async fn openai_post(
+    env: &Env,
+    path: &str,
+    payload: &serde_json::Value,
+) -> Result<serde_json::Value> {
+    let url = format!("{}{}", openai_base_url(env), path);
+
+    let headers = Headers::new();
+    headers.set("Content-Type", "application/json")?;
+    // Self-hosted compatible servers often run without auth; only send the
+    // header when the secret is set.
+    if let Ok(key) = env.secret("AI_OPENAI_API_KEY") {
+        headers.set("Authorization", &format!("Bearer {key}"))?;
+    }
+
+    let request = Request::new_with_init(
+        &url,
+        RequestInit::new()
+            .with_method(Method::Post)
+            .with_headers(headers)
+            .with_body(Some(wasm_bindgen::JsValue::from_str(&payload.to_string()))),
+    )?;
+
+    let mut response = Fetch::Request(request).send().await?;
+    let status = response.status_code();
+    if !(200..300).contains(&status) {
+        let body = response.text().await.unwrap_or_default();
+        let snippet: String = body.chars().take(200).collect();
+        return Err(Error::from(format!(
+            "OpenAI-compatible API returned {status}: {snippet}"
+        )));
+    }
+    response.json().await
+}
+
+async fn openai_chat(
+    env: &Env,
+    model: &str,
+    opts: &ChatOptions,
+    messages: &[Message],
+    tools: &[ToolSpec],
+) -> Result<(Turn, Option<(u32, u32)>)> {
+    let messages: Vec<serde_json::Value> = messages.iter().map(Message::to_openai).collect();
+    let mut payload = serde_json::json!({ "model": model, "messages": messages });
+    if !tools.is_empty() {
+        let tools: Vec<serde_json::Value> = tools
+            .iter()
+            .map(|t| {
+                serde_json::json!({
+                    "type": "function",
+                    "function": {
+                        "name": t.name,
+                        "description": t.description,
+                        "parameters": t.parameters,
+                    },
+                })
+            })
+            .collect();
+        payload["tools"] = serde_json::Value::Array(tools);
+    }
+    opts.apply(&mut payload);
+    let response = openai_post(env, "/chat/completions", &payload).await?;
+    let turn =
+        parse_openai_turn(&response).ok_or_else(|| Error::from("Chat response missing content"))?;
+    Ok((turn, reported_usage(&response)))
+}
+
+async fn openai_embed(
+    env: &Env,
+    model: &str,
+    text: &str,
+) -> Result<(Vec<f32>, Option<(u32, u32)>)> {
+    let payload = serde_json::json!({ "model": model, "input": text });
+    let response = openai_post(env, "/embeddings", &payload).await?;
+    Ok((
+        parse_openai_embedding(&response)?,
+        reported_usage(&response),
+    ))
+}
+
+fn parse_openai_chat(response: &serde_json::Value) -> Option<String> {
+    response
+        .get("choices")?
+        .as_array()?
+        .first()?
+        .get("message")?
+        .get("content")?
+        .as_str()
+        .map(|s| s.to_string())
+}
+
+fn parse_openai_turn(response: &serde_json::Value) -> Option<Turn> {
+    let calls = response
+        .get("choices")?
+        .as_array()?
+        .first()?
+        .get("message")?
+        .get("tool_calls")
+        .and_then(|v| v.as_array())
+        .filter(|a| !a.is_empty());
+    match calls {
+        Some(calls) => Some(Turn::ToolCalls(
+            calls
+                .iter()
+                .enumerate()
+                .filter_map(|(i, c)| {
+                    let function = c.get("function")?;
+                    Some(ToolCall {
+                        id: c
+                            .get("id")
+                            .and_then(|v| v.as_str())
+                            .map(|s| s.to_string())
+                            .unwrap_or_else(|| format!("call_{i}")),
+                        name: function.get("name")?.as_str()?.to_string(),
+                        arguments: arguments_value(function.get("arguments")),
+                    })
+                })
+                .collect(),
+        )),
+        None => parse_openai_chat(response).map(Turn::Text),
+    }
+}
+
+fn parse_openai_embedding(response: &serde_json::Value) -> Result<Vec<f32>> {
+    let vec = response
+        .get("data")
+        .and_then(|d| d.as_array())
+        .and_then(|d| d.first())
+        .and_then(|d| d.get("embedding"))
+        .and_then(|e| e.as_array())
+        .ok_or_else(|| Error::from("Embedding response missing data[0].embedding"))?;
+    Ok(floats(vec))
+}
+
+fn floats(values: &[serde_json::Value]) -> Vec<f32> {
+    values
+        .iter()
+        .filter_map(|v| v.as_f64().map(|f| f as f32))
+        .collect()
+}
+
+// ============================================================================
+// Stub
+// ============================================================================
+
+/// Canned answers shaped like what each caller parses: scans say "NO",
+/// replies echo the last user message. Safety turns every persona down:
+/// the stub can be picked in a deployed worker (`AI_PROVIDER`, a tenant
+/// pin), and an unvetted prompt must never pass as checked.
+pub fn stub_chat(purpose: Purpose, messages: &[Message]) -> String {
+    match purpose {
+        Purpose::Scan => "NO".to_string(),
+        Purpose::Safety => r#"{"verdict":"reject","category":"stub"}"#.to_string(),
+        Purpose::Reply | Purpose::Embed => {
+            let last = messages
+                .iter()
+                .rev()
+                .find(|m| m.role == "user")
+                .map(|m| m.content.as_str())
+                .unwrap_or("");
+            let excerpt: String = last.chars().take(200).collect();
+            format!("[stub reply] {excerpt}")
+        }
+    }
+}
+
+/// `stub_chat`, plus just enough tool use to exercise the loop locally: on
+/// the first turn, call any offered tool whose name appears in the user's
+/// message; once results are in, fold them into the reply.
+pub fn stub_complete(purpose: Purpose, messages: &[Message], tools: &[ToolSpec]) -> Turn {
+    let results: Vec<&str> = messages
+        .iter()
+        .filter(|m| m.role == "tool")
+        .map(|m| m.content.as_str())
+        .collect();
+    if results.is_empty() {
+        let last = messages
+            .iter()
+            .rev()
+            .find(|m| m.role == "user")
+            .map(|m| m.content.to_lowercase())
+            .unwrap_or_default();
+        let wanted: Vec<ToolCall> = tools
+            .iter()
+            .filter(|t| last.contains(&t.name.to_lowercase()))
+            .enumerate()
+            .map(|(i, t)| ToolCall {
+                id: format!("call_{i}"),
+                name: t.name.clone(),
+                arguments: serde_json::json!({}),
+            })
+            .collect();
+        if !wanted.is_empty() {
+            return Turn::ToolCalls(wanted);
+        }
+        return Turn::Text(stub_chat(purpose, messages));
+    }
+    let excerpt: String = results.join(" | ").chars().take(200).collect();
+    Turn::Text(format!(
+        "{} [tools: {excerpt}]",
+        stub_chat(purpose, messages)
+    ))
+}
+
+/// Hashed bag-of-words vector. Texts that share words land close together
+/// under cosine similarity, which is enough to exercise Prompt matchers.
+pub fn stub_embed(text: &str) -> Vec<f32> {
+    let mut v = vec![0.0f32; STUB_DIMS];
+    for word in text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+    {
+        let digest = crate::helpers::sha256_hex(&word.to_lowercase());
+        let bucket = usize::from_str_radix(&digest[..8], 16).unwrap_or(0) % STUB_DIMS;
+        v[bucket] += 1.0;
+    }
+    v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(vars: &[(&str, &str)]) -> Settings {
+        let owned: Vec<(String, String)> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        Settings::from_lookup(move |name| {
+            owned
+                .iter()
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| v.clone())
+        })
+    }
+
+    #[test]
+    fn defaults_to_workers_ai_without_fallback() {
+        let s = settings(&[]);
+        assert_eq!(
+            s.chain(Tier::Quality, None),
+            vec![Target {
+                provider: AiProvider::WorkersAi,
+                model: WORKERS_AI_MODEL.to_string(),
+            }]
+        );
+        assert_eq!(s.chain(Tier::Fast, None)[0].model, WORKERS_AI_FAST_MODEL);
+    }
+
+    #[test]
+    fn model_vars_override_defaults() {
+        let s = settings(&[("AI_PROVIDER", "openai"), ("AI_OPENAI_MODEL", "gpt-x")]);
+        let chain = s.chain(Tier::Quality, None);
+        assert_eq!(chain[0].provider, AiProvider::OpenAi);
+        assert_eq!(chain[0].model, "gpt-x");
+        assert_eq!(
+            s.chain(Tier::Embedding, None)[0].model,
+            OPENAI_EMBEDDING_MODEL
+        );
+    }
+
+    #[test]
+    fn unknown_provider_falls_back_to_workers_ai() {
+        let s = settings(&[("AI_PROVIDER", "nope"), ("AI_FALLBACK_PROVIDER", "nope")]);
+        assert_eq!(s.primary, AiProvider::WorkersAi);
+        assert_eq!(s.chain(Tier::Quality, None).len(), 1);
+    }
+
+    #[test]
+    fn fallback_provider_is_appended() {
+        let s = settings(&[
+            ("AI_PROVIDER", "openai"),
+            ("AI_FALLBACK_PROVIDER", "workers_ai"),
+        ]);
+        let chain = s.chain(Tier::Fast, None);
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain[1].provider, AiProvider::WorkersAi);
+        assert_eq!(chain[1].model, WORKERS_AI_FAST_MODEL);
+    }
+
+    #[test]
+    fn fallback_model_alone_reuses_primary_provider() {
+        let s = settings(&[("AI_FALLBACK_MODEL", "@cf/meta/llama-3.1-8b-instruct")]);
+        let chain = s.chain(Tier::Quality, None);
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain[1].provider, AiProvider::WorkersAi);
+        assert_eq!(chain[1].model, "@cf/meta/llama-3.1-8b-instruct");
+        // Embeddings ignore the chat fallback model, which dedups away.
+        assert_eq!(s.chain(Tier::Embedding, None).len(), 1);
+    }
+
+    #[test]
+    fn tenant_override_replaces_primary_only() {
+        let s = settings(&[("AI_FALLBACK_PROVIDER", "workers_ai")]);
+        let pin = AiOverride {
+            provider: AiProvider::OpenAi,
+            model: Some("tenant-model".into()),
+            fast_model: Some("  ".into()),
+        };
+        let chain = s.chain(Tier::Quality, Some(&pin));
+        assert_eq!(chain[0].provider, AiProvider::OpenAi);
+        assert_eq!(chain[0].model, "tenant-model");
+        assert_eq!(chain[1].provider, AiProvider::WorkersAi);
+        // Blank pinned model falls through to the operator default.
+        assert_eq!(s.chain(Tier::Fast, Some(&pin))[0].model, OPENAI_FAST_MODEL);
+    }
+
+    #[test]
+    fn parses_workers_ai_shapes() {
+        assert_eq!(
+            parse_workers_ai_chat(&serde_json::json!("hi")).as_deref(),
+            Some("hi")
+        );
+        assert_eq!(
+            parse_workers_ai_chat(&serde_json::json!({"response": "yo"})).as_deref(),
+            Some("yo")
+        );
+        assert!(parse_workers_ai_chat(&serde_json::json!({"other": 1})).is_none());
+        let emb = parse_workers_ai_embedding(&serde_json::json!({"data": [[0.5, 1.0]]})).unwrap();
+        assert_eq!(emb, vec![0.5, 1.0]);
+    }
+
+    #[test]
+    fn parses_openai_shapes() {
+        let chat = serde_json::json!({
+            "choices": [{"message": {"role": "assistant", "content": "hello"}}]
+        });
+        assert_eq!(parse_openai_chat(&chat).as_deref(), Some("hello"));
+        assert!(parse_openai_chat(&serde_json::json!({"choices": []})).is_none());
+        let emb = serde_json::json!({"data": [{"embedding": [0.25, -1.0]}]});
+        assert_eq!(parse_openai_embedding(&emb).unwrap(), vec![0.25, -1.0]);
+    }
+
+    #[test]
+    fn usage_is_reported_or_estimated() {
+        let target = Target {
+            provider: AiProvider::OpenAi,
+            model: "m".into(),
+        };
+        let body = serde_json::json!({"usage": {"prompt_tokens": 120, "completion_tokens": 30}});
+        let reported = Usage::new(&target, reported_usage(&body), 9, 9);
+        assert_eq!(
+            (
+                reported.input_tokens,
+                reported.output_tokens,
+                reported.estimated
+            ),
+            (120, 30, false)
+        );
+        // Embedding responses only carry prompt_tokens.
+        let body = serde_json::json!({"usage": {"prompt_tokens": 7}});
+        assert_eq!(reported_usage(&body), Some((7, 0)));
+        assert_eq!(reported_usage(&serde_json::json!({"response": "hi"})), None);
+
+        let messages = [Message::system("12345678"), Message::user("abcd")];
+        let turn = Turn::Text("hello!".into());
+        let estimated = Usage::new(
+            &target,
+            None,
+            estimate_input(&messages, &[]),
+            estimate_output(&turn),
+        );
+        assert_eq!(
+            (
+                estimated.input_tokens,
+                estimated.output_tokens,
+                estimated.estimated
+            ),
+            (3, 2, true)
+        );
+
+        let mut total = reported.clone();
+        total.add(&estimated);
+        assert_eq!(total.total_tokens(), 155);
+        assert!(total.estimated);
+    }
+
+    #[test]
+    fn parses_tool_call_turns() {
+        let wa = serde_json::json!({
+            "response": null,
+            "tool_calls": [{"name": "order_status", "arguments": {"order": "1234"}}]
+        });
+        assert_eq!(
+            parse_workers_ai_turn(&wa),
+            Some(Turn::ToolCalls(vec![ToolCall {
+                id: "call_0".into(),
+                name: "order_status".into(),
+                arguments: serde_json::json!({"order": "1234"}),
+            }]))
+        );
+        let oa = serde_json::json!({
+            "choices": [{"message": {"role": "assistant", "content": null, "tool_calls": [{
+                "id": "call_abc",
+                "type": "function",
+                "function": {"name": "slots", "arguments": "{\"day\":\"tomorrow\"}"}
+            }]}}]
+        });
+        assert_eq!(
+            parse_openai_turn(&oa),
+            Some(Turn::ToolCalls(vec![ToolCall {
+                id: "call_abc".into(),
+                name: "slots".into(),
+                arguments: serde_json::json!({"day": "tomorrow"}),
+            }]))
+        );
+        // Garbage arguments degrade to an empty object, not a failed turn.
+        assert_eq!(
+            arguments_value(Some(&serde_json::json!("not json"))),
+            serde_json::json!({})
+        );
+        assert_eq!(
+            parse_workers_ai_turn(&serde_json::json!({"response": "hi", "tool_calls": []})),
+            Some(Turn::Text("hi".into()))
+        );
+    }
+
+    #[test]
+    fn tool_turns_serialise_per_backend() {
+        let call = ToolCall {
+            id: "call_1".into(),
+            name: "slots".into(),
+            arguments: serde_json::json!({"day": "mon"}),
+        };
+        let req = Message::tool_request(vec![call.clone()]);
+        let res = Message::tool_result(&call, "{\"free\":true}");
+
+        let oa = req.to_openai();
+        assert_eq!(oa["tool_calls"][0]["id"], "call_1");
+        assert_eq!(
+            oa["tool_calls"][0]["function"]["arguments"],
+            "{\"day\":\"mon\"}"
+        );
+        assert_eq!(res.to_openai()["tool_call_id"], "call_1");
+
+        assert_eq!(req.to_workers_ai()["role"], "assistant");
+        assert_eq!(res.to_workers_ai()["name"], "slots");
+        assert_eq!(
+            Message::user("hi").to_workers_ai(),
+            serde_json::json!({"role": "user", "content": "hi"})
+        );
+    }
+
+    #[test]
+    fn chat_options_only_set_present_fields() {
+        let mut payload = serde_json::json!({ "messages": [] });
+        ChatOptions::default().apply(&mut payload);
+        assert!(payload.get("temperature").is_none());
+        ChatOptions {
+            temperature: Some(0.25),
+            max_tokens: Some(64),
+        }
+        .apply(&mut payload);
+        assert_eq!(payload["temperature"], serde_json::json!(0.25));
+        assert_eq!(payload["max_tokens"], serde_json::json!(64));
+    }
+
+    #[test]
+    fn stub_answers_parse_for_each_purpose() {
+        let msgs = [Message::system("x"), Message::user("what are your hours?")];
+        assert_eq!(stub_chat(Purpose::Scan, &msgs), "NO");
+        assert!(stub_chat(Purpose::Safety, &msgs).contains(r#""verdict":"reject""#));
+        assert!(stub_chat(Purpose::Reply, &msgs).contains("what are your hours?"));
+    }
+
+    #[test]
+    fn stub_embedding_is_deterministic_and_similar_for_shared_words() {
+        let a = stub_embed("What are your opening hours");
+        assert_eq!(a, stub_embed("what are your opening hours"));
+        let b = stub_embed("opening hours please");
+        let c = stub_embed("refund my order");
+        assert!(crate::ai::cosine(&a, &b) > crate::ai::cosine(&a, &c));
+    }
+}
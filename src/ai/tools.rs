@@ -0,0 +1,758 @@
+//! Tenant lookup tools for reply drafting.
+//!
+//! A tenant registers simple HTTP endpoints (`AiTool`) — "order status",
+//! "free slots" — and the reply model may call them through function
+//! calling while drafting. Each call is a JSON POST of the model's
+//! arguments, signed so the tenant can check it came from us:
+//!
+//! ```text
+//! X-Concierge-Timestamp: <unix seconds>
+//! X-Concierge-Signature: sha256=<hex HMAC-SHA256(secret, "<timestamp>.<body>")>
+//! ```
+//!
+//! Calls are bounded three ways: a per-call timeout, a response-size cap
+//! and a per-reply call limit. Once the limit is spent the model is asked
+//! to answer without tools. Every call is recorded (`ToolCallRecord`) so
+//! the approval queue can show reviewers what data a draft was based on.
+//!
+//! The loop is written against [`Backend`] so it can be exercised off-worker
+//! against a stand-in endpoint; [`Live`] is the production implementation,
+//! whose headers, size cap and timeout are plain helpers tested directly.
+
+use std::cell::RefCell;
+
+use futures::StreamExt;
+use worker::*;
+
+use super::provider::{
+    self, ChatOptions, Message, Purpose, Target, ToolCall, ToolSpec, Turn, Usage,
+};
+use crate::pii::Redacted;
+use crate::types::ToolCallRecord;
+
+/// Tools a tenant may register.
+pub const MAX_TOOLS: usize = 8;
+/// Tool calls allowed while drafting one reply.
+pub const MAX_CALLS_PER_REPLY: usize = 3;
+/// Wall-clock budget for one call, connect to last byte.
+pub const CALL_TIMEOUT_MS: u64 = 3_000;
+/// Response bytes handed to the model; the rest is dropped.
+pub const MAX_RESPONSE_BYTES: usize = 4_096;
+/// Upper bound on a tool's serialized parameter schema.
+pub const MAX_SCHEMA_BYTES: usize = 4_096;
+pub const MAX_DESCRIPTION: usize = 300;
+pub const MIN_SECRET_LEN: usize = 16;
+
+pub const SIGNATURE_HEADER: &str = "X-Concierge-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-Concierge-Timestamp";
+
+/// A registered tool with its secret decrypted, ready to call.
+#[derive(Clone, Debug)]
+pub struct LiveTool {
+    pub spec: ToolSpec,
+    pub url: String,
+    pub secret: String,
+}
+
+/// What came back from an endpoint, already capped at
+/// `MAX_RESPONSE_BYTES`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reply {
+    pub status: u16,
+    pub body: Vec<u8>,
+    pub truncated: bool,
+}
+
+/// `sha256=<hex>` over `"<timestamp>.<body>"`. Binding the timestamp in
+/// lets the tenant reject replays.
+pub fn signature(secret: &str, timestamp: u64, body: &str) -> String {
+    let payload = format!("{timestamp}.{body}");
+    let hex =
+        crate::crypto::hmac_sha256_hex(secret.as_bytes(), payload.as_bytes()).unwrap_or_default();
+    format!("sha256={hex}")
+}
+
+// ============================================================================
+// Registration checks
+// ============================================================================
+
+/// Function names are what the model sees: `[a-z][a-z0-9_]*`, ≤ 40 chars.
+pub fn validate_name(name: &str) -> std::result::Result<(), String> {
+    let ok = !name.is_empty()
+        && name.len() <= 40
+        && name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if ok {
+        Ok(())
+    } else {
+        Err("Name must start with a letter and use only a-z, 0-9 and _ (max 40).".to_string())
+    }
+}
+
+/// Endpoints must be HTTPS: the body carries customer data.
+pub fn validate_url(url: &str) -> std::result::Result<(), String> {
+    match Url::parse(url) {
+        Ok(u) if u.scheme() == "https" && u.host_str().is_some() => Ok(()),
+        _ => Err("Endpoint must be an https:// URL.".to_string()),
+    }
+}
+
+/// Parameters must be a JSON-Schema object (`{"type": "object", ...}`) of
+/// modest size. Blank means "no arguments".
+pub fn parse_parameters(raw: &str) -> std::result::Result<serde_json::Value, String> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return Ok(serde_json::json!({ "type": "object", "properties": {} }));
+    }
+    if raw.len() > MAX_SCHEMA_BYTES {
+        return Err(format!(
+            "Parameter schema is too long (max {MAX_SCHEMA_BYTES} bytes)."
+        ));
+    }
+    let schema: serde_json::Value =
+        serde_json::from_str(raw).map_err(|_| "Parameter schema isn't valid JSON.".to_string())?;
+    if schema.get("type").and_then(|v| v.as_str()) != Some("object") {
+        return Err(r#"Parameter schema must have "type": "object"."#.to_string());
+    }
+    if let Some(props) = schema.get("properties") {
+        if !props.is_object() {
+            return Err(r#""properties" must be an object."#.to_string());
+        }
+    }
+    Ok(schema)
+}
+
+/// Minimal argument check against the schema: an object carrying every
+/// `required` key. Full JSON-Schema validation is the endpoint's job.
+fn check_arguments(
+    schema: &serde_json::Value,
+    args: &serde_json::Value,
+) -> std::result::Result<(), String> {
+    let Some(obj) = args.as_object() else {
+        return Err("arguments must be an object".to_string());
+    };
+    let required = schema
+        .get("required")
+        .and_then(|v| v.as_array())
+        .map(|a| a.iter().filter_map(|v| v.as_str()).collect::<Vec<_>>())
+        .unwrap_or_default();
+    match required.into_iter().find(|k| !obj.contains_key(*k)) {
+        Some(missing) => Err(format!("missing argument: {missing}")),
+        None => Ok(()),
+    }
+}
+
+// ============================================================================
+// Tool loop
+// ============================================================================
+
+/// The two side effects the loop needs: a completion turn and an HTTP
+/// POST to a tool endpoint.
+#[allow(async_fn_in_trait)]
+pub trait Backend {
+    async fn complete(&self, messages: &[Message], tools: &[ToolSpec]) -> Result<Turn>;
+    /// POST `body` to the tool with the given signature headers. `Err` is
+    /// a timeout or transport failure.
+    async fn post(
+        &self,
+        tool: &LiveTool,
+        body: &str,
+        timestamp: u64,
+        signature: &str,
+    ) -> std::result::Result<Reply, String>;
+    fn now_ms(&self) -> u64;
+}
+
+/// Draft a reply, letting the model call `tools` up to
+/// `MAX_CALLS_PER_REPLY` times. Returns the final text and a record of
+/// every call made (including refused and failed ones).
+///
+/// The model only sees `redacted`'s placeholders; they're restored in the
+/// arguments before a call is signed and sent.
+pub async fn run<B: Backend>(
+    backend: &B,
+    tools: &[LiveTool],
+    mut messages: Vec<Message>,
+    redacted: &Redacted,
+) -> Result<(String, Vec<ToolCallRecord>)> {
+    let specs: Vec<ToolSpec> = tools.iter().map(|t| t.spec.clone()).collect();
+    let mut records: Vec<ToolCallRecord> = Vec::new();
+
+    // One extra round so the model always gets a tool-free turn to answer
+    // after the last permitted call.
+    for _ in 0..=MAX_CALLS_PER_REPLY {
+        let offer: &[ToolSpec] = if records.len() < MAX_CALLS_PER_REPLY {
+            &specs
+        } else {
+            &[]
+        };
+        let calls = match backend.complete(&messages, offer).await? {
+            Turn::Text(text) => return Ok((text, records)),
+            Turn::ToolCalls(calls) => calls,
+        };
+
+        messages.push(Message::tool_request(calls.clone()));
+        for call in &calls {
+            let record = if records.len() >= MAX_CALLS_PER_REPLY {
+                refused(call, "tool call limit reached")
+            } else {
+                match tools.iter().find(|t| t.spec.name == call.name) {
+                    Some(tool) => invoke(backend, tool, call, redacted).await,
+                    None => refused(call, "unknown tool"),
+                }
+            };
+            messages.push(Message::tool_result(call, model_view(&record)));
+            records.push(record);
+        }
+    }
+
+    Err(Error::from(
+        "Model kept calling tools past the per-reply limit",
+    ))
+}
+
+async fn invoke<B: Backend>(
+    backend: &B,
+    tool: &LiveTool,
+    call: &ToolCall,
+    redacted: &Redacted,
+) -> ToolCallRecord {
+    if let Err(e) = check_arguments(&tool.spec.parameters, &call.arguments) {
+        return refused(call, &e);
+    }
+
+    // Recorded as sent, so reviewers see what the endpoint was given.
+    let arguments = redacted.restore_json(&call.arguments);
+    let body = arguments.to_string();
+    let started = backend.now_ms();
+    let timestamp = started / 1000;
+    let sig = signature(&tool.secret, timestamp, &body);
+    let result = backend.post(tool, &body, timestamp, &sig).await;
+    let duration_ms = backend.now_ms().saturating_sub(started);
+
+    match result {
+        Ok(reply) => ToolCallRecord {
+            tool: call.name.clone(),
+            arguments,
+            status: Some(reply.status),
+            output: String::from_utf8_lossy(&reply.body).into_owned(),
+            truncated: reply.truncated,
+            error: None,
+            duration_ms,
+        },
+        Err(e) => ToolCallRecord {
+            tool: call.name.clone(),
+            arguments,
+            error: Some(e),
+            duration_ms,
+            ..Default::default()
+        },
+    }
+}
+
+fn refused(call: &ToolCall, reason: &str) -> ToolCallRecord {
+    ToolCallRecord {
+        tool: call.name.clone(),
+        arguments: call.arguments.clone(),
+        error: Some(reason.to_string()),
+        ..Default::default()
+    }
+}
+
+/// What the model sees for a call. Failures are reduced to a fixed shape
+/// so the draft can say "I couldn't check that" without echoing transport
+/// errors to the customer.
+fn model_view(record: &ToolCallRecord) -> String {
+    match (&record.error, record.status) {
+        (Some(e), _) if e.starts_with("missing argument") => {
+            serde_json::json!({ "error": e }).to_string()
+        }
+        (Some(_), _) => serde_json::json!({ "error": "lookup failed" }).to_string(),
+        (None, Some(s)) if !(200..300).contains(&s) => {
+            serde_json::json!({ "error": format!("lookup returned HTTP {s}") }).to_string()
+        }
+        _ if record.truncated => format!("{}\n[truncated]", record.output),
+        _ => record.output.clone(),
+    }
+}
+
+/// The headers a tool call is sent with.
+fn request_headers(timestamp: u64, signature: &str) -> [(&'static str, String); 3] {
+    [
+        ("Content-Type", "application/json".to_string()),
+        (TIMESTAMP_HEADER, timestamp.to_string()),
+        (SIGNATURE_HEADER, signature.to_string()),
+    ]
+}
+
+/// Read at most `MAX_RESPONSE_BYTES` of a streamed body, flagging whether
+/// anything was dropped. Stops pulling once past the cap, so an oversized
+/// body is never fully buffered.
+async fn read_capped<S, E>(mut stream: S) -> std::result::Result<(Vec<u8>, bool), E>
+where
+    S: futures::Stream<Item = std::result::Result<Vec<u8>, E>> + Unpin,
+{
+    let mut body = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk?;
+        let room = MAX_RESPONSE_BYTES - body.len();
+        if chunk.len() > room {
+            body.extend_from_slice(&chunk[..room]);
+            return Ok((body, true));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok((body, false))
+}
+
+/// Race `request` against `timeout`. When the timeout wins, `abort`
+/// cancels the request and the call reports the timeout.
+async fn within<T>(
+    request: impl std::future::Future<Output = Result<T>>,
+    timeout: impl std::future::Future<Output = ()>,
+    abort: impl FnOnce(),
+) -> std::result::Result<T, String> {
+    futures::pin_mut!(request);
+    futures::pin_mut!(timeout);
+    match futures::future::select(request, timeout).await {
+        futures::future::Either::Left((result, _)) => result.map_err(|e| e.to_string()),
+        futures::future::Either::Right(_) => {
+            abort();
+            Err(format!("timed out after {CALL_TIMEOUT_MS} ms"))
+        }
+    }
+}
+
+// ============================================================================
+// Production backend
+// ============================================================================
+
+/// Completions through the provider chain; tool calls over `fetch` with an
+/// abort-on-timeout. Token usage is summed across turns into `usage`.
+pub struct Live<'a> {
+    pub env: &'a Env,
+    pub targets: &'a [Target],
+    pub opts: ChatOptions,
+    pub usage: RefCell<Usage>,
+}
+
+impl Backend for Live<'_> {
+    async fn complete(&self, messages: &[Message], tools: &[ToolSpec]) -> Result<Turn> {
+        let (turn, usage) = provider::complete(
+            self.env,
+            self.targets,
+            Purpose::Reply,
+            &self.opts,
+            messages,
+            tools,
+        )
+        .await?;
+        self.usage.borrow_mut().add(&usage);
+        Ok(turn)
+    }
+
+    async fn post(
+        &self,
+        tool: &LiveTool,
+        body: &str,
+        timestamp: u64,
+        signature: &str,
+    ) -> std::result::Result<Reply, String> {
+        let controller = AbortController::default();
+        let signal = controller.signal();
+
+        let request = async {
+            let headers = Headers::new();
+            for (name, value) in request_headers(timestamp, signature) {
+                headers.set(name, &value)?;
+            }
+            let req = Request::new_with_init(
+                &tool.url,
+                RequestInit::new()
+                    .with_method(Method::Post)
+                    .with_headers(headers)
+                    .with_body(Some(wasm_bindgen::JsValue::from_str(body))),
+            )?;
+            let mut resp = Fetch::Request(req).send_with_signal(&signal).await?;
+            let status = resp.status_code();
+            let (body, truncated) = read_capped(resp.stream()?).await?;
+            Ok::<_, Error>(Reply {
+                status,
+                body,
+                truncated,
+            })
+        };
+
+        let timeout = Delay::from(std::time::Duration::from_millis(CALL_TIMEOUT_MS));
+        within(request, timeout, || controller.abort()).await
+    }
+
+    fn now_ms(&self) -> u64 {
+        js_sys::Date::now() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+
+    const SECRET: &str = "s3cret-s3cret-s3cret";
+
+    /// Stand-in for a tenant's endpoint: checks the signature the way a
+    /// tenant would, then answers from a canned table. The "model" side
+    /// replays a script of turns and keeps what it was shown.
+    struct StandIn {
+        script: RefCell<Vec<Turn>>,
+        seen: RefCell<Vec<Vec<Message>>>,
+        offered: RefCell<Vec<usize>>,
+        answer: fn(&str) -> std::result::Result<Reply, String>,
+        posts: Cell<usize>,
+    }
+
+    impl StandIn {
+        fn new(script: Vec<Turn>, answer: fn(&str) -> std::result::Result<Reply, String>) -> Self {
+            Self {
+                script: RefCell::new(script.into_iter().rev().collect()),
+                seen: RefCell::default(),
+                offered: RefCell::default(),
+                answer,
+                posts: Cell::new(0),
+            }
+        }
+    }
+
+    impl Backend for StandIn {
+        async fn complete(&self, messages: &[Message], tools: &[ToolSpec]) -> Result<Turn> {
+            self.seen.borrow_mut().push(messages.to_vec());
+            self.offered.borrow_mut().push(tools.len());
+            Ok(self
+                .script
+                .borrow_mut()
+                .pop()
+                .unwrap_or(Turn::Text("done".into())))
+        }
+
+        async fn post(
+            &self,
+            tool: &LiveTool,
+            body: &str,
+            timestamp: u64,
+            sig: &str,
+        ) -> std::result::Result<Reply, String> {
+            self.posts.set(self.posts.get() + 1);
+            if sig != signature(SECRET, timestamp, body) {
+                return Ok(Reply {
+                    status: 401,
+                    body: b"bad signature".to_vec(),
+                    truncated: false,
+                });
+            }
+            let _ = tool;
+            (self.answer)(body)
+        }
+
+        fn now_ms(&self) -> u64 {
+            1_700_000_000_000
+        }
+    }
+
+    fn order_tool(secret: &str) -> LiveTool {
+        LiveTool {
+            spec: ToolSpec {
+                name: "order_status".into(),
+                description: "Look up an order".into(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": { "order": { "type": "string" } },
+                    "required": ["order"],
+                }),
+            },
+            url: "https://example.com/orders".into(),
+            secret: secret.into(),
+        }
+    }
+
+    fn call(args: serde_json::Value) -> Turn {
+        Turn::ToolCalls(vec![ToolCall {
+            id: "call_0".into(),
+            name: "order_status".into(),
+            arguments: args,
+        }])
+    }
+
+    fn shipped(_: &str) -> std::result::Result<Reply, String> {
+        Ok(Reply {
+            status: 200,
+            body: br#"{"status":"shipped"}"#.to_vec(),
+            truncated: false,
+        })
+    }
+
+    fn run_now<F: std::future::Future>(f: F) -> F::Output {
+        futures::executor::block_on(f)
+    }
+
+    #[test]
+    fn signature_covers_timestamp_and_body() {
+        let a = signature(SECRET, 1, "{}");
+        assert!(a.starts_with("sha256="));
+        assert_ne!(a, signature(SECRET, 2, "{}"));
+        assert_ne!(a, signature(SECRET, 1, "{ }"));
+        assert_ne!(a, signature("other-secret-value", 1, "{}"));
+    }
+
+    #[test]
+    fn result_is_fed_back_and_recorded() {
+        let backend = StandIn::new(
+            vec![
+                call(serde_json::json!({"order": "1234"})),
+                Turn::Text("Your order has shipped.".into()),
+            ],
+            shipped,
+        );
+        let (text, records) = run_now(run(
+            &backend,
+            &[order_tool(SECRET)],
+            vec![Message::user("where is order 1234?")],
+            &Redacted::default(),
+        ))
+        .unwrap();
+
+        assert_eq!(text, "Your order has shipped.");
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].status, Some(200));
+        assert_eq!(records[0].output, r#"{"status":"shipped"}"#);
+        assert_eq!(records[0].arguments, serde_json::json!({"order": "1234"}));
+
+        let second_turn = &backend.seen.borrow()[1];
+        let last = second_turn.last().unwrap();
+        assert_eq!(last.role, "tool");
+        assert_eq!(last.content, r#"{"status":"shipped"}"#);
+    }
+
+    #[test]
+    fn placeholders_are_restored_before_posting() {
+        fn echo(body: &str) -> std::result::Result<Reply, String> {
+            Ok(Reply {
+                status: 200,
+                body: body.as_bytes().to_vec(),
+                truncated: false,
+            })
+        }
+        let redacted = crate::pii::redact(
+            "my number is +91 98765 43210",
+            &[crate::types::PiiClass::Phone],
+        );
+        let backend = StandIn::new(
+            vec![call(
+                serde_json::json!({"order": "by [PHONE_1]", "tags": ["[PHONE_1]"]}),
+            )],
+            echo,
+        );
+        let (_, records) = run_now(run(
+            &backend,
+            &[order_tool(SECRET)],
+            vec![Message::user(redacted.text.clone())],
+            &redacted,
+        ))
+        .unwrap();
+
+        // The stand-in checked the signature over the restored body.
+        assert_eq!(records[0].status, Some(200));
+        let sent: serde_json::Value = serde_json::from_str(&records[0].output).unwrap();
+        assert_eq!(
+            sent,
+            serde_json::json!({"order": "by +91 98765 43210", "tags": ["+91 98765 43210"]})
+        );
+        assert_eq!(records[0].arguments, sent);
+    }
+
+    #[test]
+    fn wrong_secret_shows_up_as_failed_lookup() {
+        let backend = StandIn::new(vec![call(serde_json::json!({"order": "1"}))], shipped);
+        let (_, records) = run_now(run(
+            &backend,
+            &[order_tool("not-the-tenant-secret")],
+            vec![Message::user("hi")],
+            &Redacted::default(),
+        ))
+        .unwrap();
+        assert_eq!(records[0].status, Some(401));
+        let fed_back = backend.seen.borrow()[1].last().unwrap().content.clone();
+        assert!(fed_back.contains("HTTP 401"));
+    }
+
+    #[test]
+    fn call_limit_stops_offering_tools() {
+        let script = (0..5)
+            .map(|_| call(serde_json::json!({"order": "1"})))
+            .collect();
+        let backend = StandIn::new(script, shipped);
+        let result = run_now(run(
+            &backend,
+            &[order_tool(SECRET)],
+            vec![],
+            &Redacted::default(),
+        ));
+
+        // The stand-in model ignores the empty tool list and keeps asking,
+        // so the loop gives up rather than spinning.
+        assert!(result.is_err());
+        assert_eq!(backend.posts.get(), MAX_CALLS_PER_REPLY);
+        assert_eq!(
+            *backend.offered.borrow().last().unwrap(),
+            0,
+            "final turn must offer no tools"
+        );
+    }
+
+    #[test]
+    fn missing_arguments_and_unknown_tools_are_refused_without_posting() {
+        let backend = StandIn::new(
+            vec![
+                Turn::ToolCalls(vec![
+                    ToolCall {
+                        id: "a".into(),
+                        name: "order_status".into(),
+                        arguments: serde_json::json!({}),
+                    },
+                    ToolCall {
+                        id: "b".into(),
+                        name: "delete_everything".into(),
+                        arguments: serde_json::json!({}),
+                    },
+                ]),
+                Turn::Text("Which order number?".into()),
+            ],
+            shipped,
+        );
+        let (text, records) = run_now(run(
+            &backend,
+            &[order_tool(SECRET)],
+            vec![],
+            &Redacted::default(),
+        ))
+        .unwrap();
+        assert_eq!(text, "Which order number?");
+        assert_eq!(backend.posts.get(), 0);
+        assert_eq!(records[0].error.as_deref(), Some("missing argument: order"));
+        assert_eq!(records[1].error.as_deref(), Some("unknown tool"));
+    }
+
+    #[test]
+    fn timeouts_are_recorded_and_hidden_from_the_model() {
+        fn slow(_: &str) -> std::result::Result<Reply, String> {
+            Err(format!("timed out after {CALL_TIMEOUT_MS} ms"))
+        }
+        let backend = StandIn::new(vec![call(serde_json::json!({"order": "1"}))], slow);
+        let (_, records) = run_now(run(
+            &backend,
+            &[order_tool(SECRET)],
+            vec![],
+            &Redacted::default(),
+        ))
+        .unwrap();
+        assert!(records[0].error.as_deref().unwrap().contains("timed out"));
+        let fed_back = backend.seen.borrow()[1].last().unwrap().content.clone();
+        assert_eq!(fed_back, r#"{"error":"lookup failed"}"#);
+    }
+
+    #[test]
+    fn oversized_bodies_are_capped() {
+        // Counts the chunks pulled, to show reading stops at the cap.
+        let pulled = Cell::new(0);
+        let chunks = [vec![b'a'; 3000], vec![b'b'; 3000], vec![b'c'; 3000]];
+        let stream = futures::stream::iter(chunks.into_iter().map(|c| {
+            pulled.set(pulled.get() + 1);
+            Ok::<_, ()>(c)
+        }));
+        let (body, truncated) = run_now(read_capped(stream)).unwrap();
+        assert_eq!(body.len(), MAX_RESPONSE_BYTES);
+        assert!(body[3000..].iter().all(|b| *b == b'b'));
+        assert!(truncated);
+        assert_eq!(pulled.get(), 2);
+
+        let stream = futures::stream::iter([Ok::<_, ()>(b"ok".to_vec())]);
+        let (body, truncated) = run_now(read_capped(stream)).unwrap();
+        assert_eq!(body, b"ok");
+        assert!(!truncated);
+
+        let stream = futures::stream::iter([Ok(b"ok".to_vec()), Err("reset")]);
+        assert_eq!(run_now(read_capped(stream)), Err("reset"));
+
+        let record = ToolCallRecord {
+            output: "abc".into(),
+            status: Some(200),
+            truncated: true,
+            ..Default::default()
+        };
+        assert!(model_view(&record).ends_with("[truncated]"));
+    }
+
+    #[test]
+    fn slow_calls_are_aborted() {
+        let aborted = Cell::new(false);
+        let result: std::result::Result<(), String> = run_now(within(
+            futures::future::pending(),
+            futures::future::ready(()),
+            || aborted.set(true),
+        ));
+        assert_eq!(result, Err(format!("timed out after {CALL_TIMEOUT_MS} ms")));
+        assert!(aborted.get());
+
+        let aborted = Cell::new(false);
+        let result = run_now(within(
+            futures::future::ready(Ok(7)),
+            futures::future::pending(),
+            || aborted.set(true),
+        ));
+        assert_eq!(result, Ok(7));
+        assert!(!aborted.get());
+
+        let result: std::result::Result<(), String> = run_now(within(
+            futures::future::ready(Err(Error::from("connection refused"))),
+            futures::future::pending(),
+            || {},
+        ));
+        assert_eq!(result, Err("connection refused".to_string()));
+    }
+
+    #[test]
+    fn calls_carry_the_signature_headers() {
+        let body = r#"{"order":"1234"}"#;
+        let sig = signature(SECRET, 1_700_000_000, body);
+        let headers = request_headers(1_700_000_000, &sig);
+        let get = |name: &str| {
+            headers
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, v)| v.as_str())
+        };
+        assert_eq!(get("Content-Type"), Some("application/json"));
+        assert_eq!(get(TIMESTAMP_HEADER), Some("1700000000"));
+        assert_eq!(get(SIGNATURE_HEADER), Some(sig.as_str()));
+        // What a tenant recomputes from the headers and the raw body.
+        let expected = format!(
+            "sha256={}",
+            crate::crypto::hmac_sha256_hex(SECRET.as_bytes(), b"1700000000.{\"order\":\"1234\"}")
+                .unwrap()
+        );
+        assert_eq!(sig, expected);
+    }
+
+    #[test]
+    fn registration_checks() {
+        assert!(validate_name("order_status").is_ok());
+        assert!(validate_name("Order").is_err());
+        assert!(validate_name("1abc").is_err());
+        assert!(validate_url("https://shop.example/api").is_ok());
+        assert!(validate_url("http://shop.example/api").is_err());
+        assert!(parse_parameters("").is_ok());
+        assert!(parse_parameters(r#"{"type":"object","properties":{}}"#).is_ok());
+        assert!(parse_parameters(r#"{"type":"string"}"#).is_err());
+        assert!(parse_parameters("{nope").is_err());
+    }
+}
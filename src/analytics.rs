@@ -0,0 +1,383 @@
+//! Tenant activity for the dashboard: inbound per channel, what answered
+//! it, the approval queue, credits, lead forms and time-to-reply.
+//!
+//! Everything comes from `daily_rollups` (see `retention`), which holds
+//! per-day counts of the message log and lead form submissions long after
+//! the rows are pruned. Days the rollup hasn't reached yet (the last two,
+//! or everything before the first run) are counted live from the tables
+//! with the same SQL, so a range always reads complete.
+//!
+//! Reply times are bucketed when rolled up, so the median is the bucket
+//! it falls in, not an exact figure.
+
+use std::collections::BTreeMap;
+
+use worker::*;
+
+use crate::helpers::days_from_now;
+use crate::retention;
+use crate::types::Channel;
+
+/// Ranges the dashboard offers, in days, ending today.
+pub const RANGES: &[u32] = &[7, 30, 90, 365];
+pub const DEFAULT_RANGE: u32 = 30;
+
+/// Upper bounds of the reply-time buckets, in seconds.
+pub const REPLY_BUCKETS: &[i64] = &[
+    10, 30, 60, 120, 300, 900, 1_800, 3_600, 7_200, 14_400, 43_200, 86_400, 259_200, 604_800,
+];
+
+/// Bucket for replies slower than the last bound.
+pub const REPLY_SLOWER: i64 = -1;
+
+/// SQL bucketing `column` (seconds) into `daily_rollups.reply_within`:
+/// the first bound it fits under, `REPLY_SLOWER` past them all, and 0 for
+/// rows that weren't answers.
+pub fn reply_within_sql(column: &str) -> String {
+    let mut sql = format!("CASE WHEN {column} IS NULL THEN 0");
+    for bound in REPLY_BUCKETS {
+        sql.push_str(&format!(" WHEN {column} <= {bound} THEN {bound}"));
+    }
+    sql.push_str(&format!(" ELSE {REPLY_SLOWER} END"));
+    sql
+}
+
+/// The range picked in the query string, if it's one of `RANGES`.
+pub fn range(url: &Url) -> u32 {
+    url.query_pairs()
+        .find(|(k, _)| k == "days")
+        .and_then(|(_, v)| v.parse().ok())
+        .filter(|d| RANGES.contains(d))
+        .unwrap_or(DEFAULT_RANGE)
+}
+
+/// One day's activity.
+#[derive(Debug, Default, Clone)]
+pub struct DayActivity {
+    pub day: String,
+    /// Inbound messages by channel wire name.
+    pub inbound: BTreeMap<String, u64>,
+    /// Canned auto-replies: rule text and holding replies.
+    pub canned: u64,
+    /// AI replies sent without approval.
+    pub ai: u64,
+    /// Replies written by a person (inbox or Discord relay).
+    pub human: u64,
+    pub queued: u64,
+    pub approved: u64,
+    pub rejected: u64,
+    pub expired: u64,
+    /// Credits charged, net of refunded drafts.
+    pub credits: i64,
+    pub leads: u64,
+    /// Answers by reply-time bucket.
+    pub reply_times: BTreeMap<i64, u64>,
+}
+
+impl DayActivity {
+    pub fn inbound_total(&self) -> u64 {
+        self.inbound.values().sum()
+    }
+
+    pub fn median_reply(&self) -> Option<i64> {
+        median_bucket(&self.reply_times)
+    }
+}
+
+/// Activity over a range, one entry per day, oldest first.
+#[derive(Debug, Default)]
+pub struct Activity {
+    pub days: Vec<DayActivity>,
+}
+
+impl Activity {
+    pub fn total(&self, f: impl Fn(&DayActivity) -> u64) -> u64 {
+        self.days.iter().map(f).sum()
+    }
+
+    pub fn credits(&self) -> i64 {
+        self.days.iter().map(|d| d.credits).sum()
+    }
+
+    pub fn median_reply(&self) -> Option<i64> {
+        let mut merged = BTreeMap::new();
+        for day in &self.days {
+            for (bucket, n) in &day.reply_times {
+                *merged.entry(*bucket).or_insert(0) += n;
+            }
+        }
+        median_bucket(&merged)
+    }
+}
+
+/// The tenant's activity over the last `range` days, today included.
+pub async fn load(db: &D1Database, tenant_id: &str, range: u32) -> Result<Activity> {
+    let days: Vec<String> = (0..range as i64)
+        .rev()
+        .map(|back| days_from_now(-back).chars().take(10).collect())
+        .collect();
+    load_days(db, tenant_id, &days).await
+}
+
+/// The tenant's activity on `days` (consecutive, oldest first).
+pub async fn load_days(db: &D1Database, tenant_id: &str, days: &[String]) -> Result<Activity> {
+    let first = days.first().cloned().unwrap_or_default();
+
+    let live = |name: &str| {
+        let table = retention::table(name).expect("rolled-up table is registered");
+        retention::counts_sql(
+            table,
+            &format!(
+                "tenant_id = ?1 AND created_at >= max(?2, COALESCE(
+                     (SELECT next_day FROM rollup_state WHERE source = '{name}'), ''))"
+            ),
+        )
+    };
+    let rows = db
+        .prepare(format!(
+            "SELECT day, source, channel, direction, outcome, reply_within,
+                    SUM(count) AS count, SUM(credits) AS credits
+             FROM (
+                 SELECT tenant_id, day, source, channel, direction, outcome, reply_within, count, credits
+                 FROM daily_rollups
+                 WHERE tenant_id = ?1 AND day >= ?2
+                   AND source IN ('messages', 'lead_form_submissions')
+                 UNION ALL {messages}
+                 UNION ALL {leads}
+             )
+             GROUP BY day, source, channel, direction, outcome, reply_within",
+            messages = live("messages"),
+            leads = live("lead_form_submissions"),
+        ))
+        .bind(&[tenant_id.into(), first.into()])?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    Ok(tally(&rows, days))
+}
+
+/// Sort rollup rows into `days`, which every day appears in even if
+/// nothing happened. Rows outside `days` are dropped.
+fn tally(rows: &[serde_json::Value], days: &[String]) -> Activity {
+    let mut out: Vec<DayActivity> = days
+        .iter()
+        .map(|day| DayActivity {
+            day: day.clone(),
+            ..Default::default()
+        })
+        .collect();
+    let index: BTreeMap<&str, usize> = days
+        .iter()
+        .enumerate()
+        .map(|(i, d)| (d.as_str(), i))
+        .collect();
+
+    for row in rows {
+        let s = |k: &str| row.get(k).and_then(|v| v.as_str()).unwrap_or("");
+        let n = |k: &str| {
+            row.get(k)
+                .and_then(|v| v.as_i64().or_else(|| v.as_f64().map(|f| f as i64)))
+                .unwrap_or(0)
+        };
+        let Some(&i) = index.get(s("day")) else {
+            continue;
+        };
+        let day = &mut out[i];
+        let count = n("count").max(0) as u64;
+        match s("source") {
+            "messages" => {
+                day.credits += n("credits");
+                if s("direction") == "inbound" {
+                    *day.inbound.entry(s("channel").to_string()).or_insert(0) += count;
+                    continue;
+                }
+                let slot = match s("outcome") {
+                    "auto_reply" => Some(&mut day.canned),
+                    "ai_reply" => Some(&mut day.ai),
+                    "relay" => Some(&mut day.human),
+                    "ai_queued" => Some(&mut day.queued),
+                    "ai_approved" => Some(&mut day.approved),
+                    "ai_rejected" => Some(&mut day.rejected),
+                    "ai_expired" => Some(&mut day.expired),
+                    _ => None,
+                };
+                if let Some(slot) = slot {
+                    *slot += count;
+                }
+                let bucket = n("reply_within");
+                if bucket != 0 {
+                    *day.reply_times.entry(bucket).or_insert(0) += count;
+                }
+            }
+            "lead_form_submissions" => day.leads += count,
+            _ => {}
+        }
+    }
+    Activity { days: out }
+}
+
+/// The bucket holding the middle answer.
+fn median_bucket(buckets: &BTreeMap<i64, u64>) -> Option<i64> {
+    let total: u64 = buckets.values().sum();
+    if total == 0 {
+        return None;
+    }
+    let mut ordered: Vec<(i64, u64)> = buckets.iter().map(|(b, n)| (*b, *n)).collect();
+    ordered.sort_by_key(|(b, _)| if *b == REPLY_SLOWER { i64::MAX } else { *b });
+    let middle = total.div_ceil(2);
+    let mut seen = 0;
+    for (bucket, n) in ordered {
+        seen += n;
+        if seen >= middle {
+            return Some(bucket);
+        }
+    }
+    None
+}
+
+/// A reply-time bucket in words, e.g. "within 5 min".
+pub fn describe_bucket(bucket: i64) -> String {
+    let span = |secs: i64| match secs {
+        s if s < 60 => format!("{s}s"),
+        s if s < 3_600 => format!("{} min", s / 60),
+        s if s < 86_400 => format!("{} h", s / 3_600),
+        86_400 => "1 day".to_string(),
+        s => format!("{} days", s / 86_400),
+    };
+    if bucket == REPLY_SLOWER {
+        let last = REPLY_BUCKETS.last().copied().unwrap_or(0);
+        format!("over {}", span(last))
+    } else {
+        format!("within {}", span(bucket))
+    }
+}
+
+/// The range as CSV, one row per day.
+pub fn csv(activity: &Activity) -> String {
+    let inbound: Vec<String> = Channel::ALL
+        .iter()
+        .map(|c| format!("inbound_{}", c.as_str()))
+        .collect();
+    let mut columns = vec!["day"];
+    columns.extend(inbound.iter().map(String::as_str));
+    columns.extend([
+        "canned_replies",
+        "ai_replies",
+        "human_replies",
+        "drafts_queued",
+        "drafts_approved",
+        "drafts_rejected",
+        "drafts_expired",
+        "credits",
+        "lead_form_submissions",
+        "median_reply_within_secs",
+    ]);
+    let rows: Vec<serde_json::Value> = activity
+        .days
+        .iter()
+        .map(|d| {
+            let mut row = serde_json::json!({
+                "day": d.day,
+                "canned_replies": d.canned,
+                "ai_replies": d.ai,
+                "human_replies": d.human,
+                "drafts_queued": d.queued,
+                "drafts_approved": d.approved,
+                "drafts_rejected": d.rejected,
+                "drafts_expired": d.expired,
+                "credits": d.credits,
+                "lead_form_submissions": d.leads,
+                "median_reply_within_secs": d.median_reply(),
+            });
+            for (channel, column) in Channel::ALL.iter().zip(&inbound) {
+                row[column] = d.inbound.get(channel.as_str()).copied().unwrap_or(0).into();
+            }
+            row
+        })
+        .collect();
+    crate::archive::csv(&columns, &rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn days() -> Vec<String> {
+        vec![
+            "2026-03-01".into(),
+            "2026-03-02".into(),
+            "2026-03-03".into(),
+        ]
+    }
+
+    #[test]
+    fn bucket_sql_covers_every_bound() {
+        let sql = reply_within_sql("reply_secs");
+        assert!(sql.starts_with("CASE WHEN reply_secs IS NULL THEN 0"));
+        for bound in REPLY_BUCKETS {
+            assert!(sql.contains(&format!("THEN {bound}")));
+        }
+        assert!(sql.ends_with("ELSE -1 END"));
+        assert!(REPLY_BUCKETS.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn tally_fills_empty_days_and_sorts_outcomes() {
+        let rows = vec![
+            json!({"day": "2026-03-02", "source": "messages", "channel": "whatsapp",
+                   "direction": "inbound", "outcome": "suppressed", "reply_within": 0,
+                   "count": 4, "credits": 0}),
+            json!({"day": "2026-03-02", "source": "messages", "channel": "whatsapp",
+                   "direction": "outbound", "outcome": "ai_reply", "reply_within": 30,
+                   "count": 2, "credits": 3}),
+            json!({"day": "2026-03-02", "source": "messages", "channel": "email",
+                   "direction": "outbound", "outcome": "ai_rejected", "reply_within": 0,
+                   "count": 1, "credits": -1.0}),
+            json!({"day": "2026-03-03", "source": "lead_form_submissions", "channel": "whatsapp",
+                   "direction": "inbound", "outcome": "canned", "reply_within": 0,
+                   "count": 5, "credits": 0}),
+            json!({"day": "2026-02-01", "source": "messages", "channel": "whatsapp",
+                   "direction": "inbound", "outcome": "", "reply_within": 0,
+                   "count": 9, "credits": 0}),
+        ];
+        let a = tally(&rows, &days());
+        assert_eq!(a.days.len(), 3);
+        assert_eq!(a.days[0].inbound_total(), 0);
+        assert_eq!(a.total(DayActivity::inbound_total), 4);
+        assert_eq!(a.days[1].inbound.get("whatsapp"), Some(&4));
+        assert_eq!(a.total(|d| d.ai), 2);
+        assert_eq!(a.total(|d| d.rejected), 1);
+        assert_eq!(a.credits(), 2);
+        assert_eq!(a.total(|d| d.leads), 5);
+        assert_eq!(a.median_reply(), Some(30));
+    }
+
+    #[test]
+    fn median_is_the_bucket_holding_the_middle_answer() {
+        assert_eq!(median_bucket(&BTreeMap::new()), None);
+        let b = BTreeMap::from([(10, 1), (60, 1), (REPLY_SLOWER, 1)]);
+        assert_eq!(median_bucket(&b), Some(60));
+        let b = BTreeMap::from([(10, 1), (REPLY_SLOWER, 3)]);
+        assert_eq!(median_bucket(&b), Some(REPLY_SLOWER));
+    }
+
+    #[test]
+    fn buckets_read_naturally() {
+        assert_eq!(describe_bucket(30), "within 30s");
+        assert_eq!(describe_bucket(900), "within 15 min");
+        assert_eq!(describe_bucket(14_400), "within 4 h");
+        assert_eq!(describe_bucket(86_400), "within 1 day");
+        assert_eq!(describe_bucket(REPLY_SLOWER), "over 7 days");
+    }
+
+    #[test]
+    fn csv_has_a_row_per_day_and_a_column_per_channel() {
+        let a = tally(&[], &days());
+        let csv = csv(&a);
+        let lines: Vec<&str> = csv.trim_end().split("\r\n").collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("day,inbound_whatsapp,inbound_instagram"));
+        assert!(lines[1].starts_with("2026-03-01,0,0,0,0,"));
+    }
+}
@@ -119,6 +119,7 @@ mod tests {
             matcher: ReplyMatcher::Default,
             response: ReplyResponse::Prompt { text: "x".into() },
             approval: policy,
+            generation: Default::default(),
         }
     }
 
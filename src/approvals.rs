@@ -7,23 +7,69 @@ use worker::*;
 
 use crate::discord;
 use crate::helpers::{generate_id, now_iso};
+use crate::notifications::{self, Notice};
 use crate::storage::{get_discord_config_by_tenant, save_conversation_context};
-use crate::types::{ConversationContext, InboundMessage, PendingApproval, QueueReason, ReplyRule};
-
-/// Enqueue an AI draft for human approval. The caller has already paid the
-/// AI credit and produced the draft text; this function only persists state
-/// and (best-effort) posts to Discord.
+use crate::types::{
+    ConversationContext, InboundMessage, NotificationKind, PendingApproval, QueueReason, ReplyRule,
+    ToolCallRecord,
+};
+
+/// Enqueue an AI draft for human approval. The caller has already paid
+/// `credits` for the draft; this function only persists state (including
+/// the charge, so a reject/expiry refunds the right amount) and
+/// (best-effort) posts to Discord. Returns the approval id.
 ///
 /// The `id` lives across three places: the KV ConversationContext key, the
 /// D1 pending_approvals.id, and the Discord button custom_id. A single
 /// token threads decisions across surfaces.
+#[allow(clippy::too_many_arguments)]
 pub async fn enqueue(
     env: &Env,
     msg: &InboundMessage,
     rule: &ReplyRule,
     draft: &str,
+    tool_calls: &[ToolCallRecord],
     reason: QueueReason,
-) -> Result<()> {
+    detail: Option<&str>,
+    credits: i64,
+) -> Result<String> {
+    insert_and_post(
+        env,
+        msg,
+        Some(rule),
+        draft,
+        tool_calls,
+        reason,
+        detail,
+        credits,
+    )
+    .await
+}
+
+/// Queue an inbound message no model answered, for a human to reply to.
+/// `draft` is a suggested answer the reviewer can send as-is; empty means
+/// they have to write one. Nothing was charged, so nothing is refunded.
+/// Returns the approval id.
+pub async fn enqueue_for_human(
+    env: &Env,
+    msg: &InboundMessage,
+    draft: &str,
+    reason: QueueReason,
+) -> Result<String> {
+    insert_and_post(env, msg, None, draft, &[], reason, None, 0).await
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn insert_and_post(
+    env: &Env,
+    msg: &InboundMessage,
+    rule: Option<&ReplyRule>,
+    draft: &str,
+    tool_calls: &[ToolCallRecord],
+    reason: QueueReason,
+    detail: Option<&str>,
+    credits: i64,
+) -> Result<String> {
     let kv = env.kv("KV")?;
     let db = env.d1("DB")?;
 
@@ -47,6 +93,7 @@ pub async fn enqueue(
         channel_account_id: msg.channel_account_id.clone(),
         reply_metadata: msg.raw_metadata.clone(),
         ai_draft: Some(draft.to_string()),
+        credits_charged: credits,
         created_at: now_iso(),
     };
 
@@ -61,19 +108,23 @@ pub async fn enqueue(
             tenant_id: msg.tenant_id.clone(),
             channel: msg.channel.clone(),
             channel_account_id: msg.channel_account_id.clone(),
-            rule_id: rule.id.clone(),
-            rule_label: rule.label.clone(),
+            rule_id: rule.map(|r| r.id.clone()).unwrap_or_default(),
+            rule_label: rule.map(|r| r.label.clone()).unwrap_or_default(),
             sender: msg.sender.clone(),
             sender_name: msg.sender_name.clone(),
+            contact_id: msg.contact_id.clone(),
             inbound_preview: inbound_preview.clone(),
             draft: draft.to_string(),
             queue_reason: reason,
+            queue_detail: detail.map(str::to_string),
             status: crate::types::ApprovalStatus::Pending,
             created_at: ctx.created_at.clone(),
             decided_at: None,
             decided_by: None,
             edited: false,
             last_digest_at: None,
+            credits_charged: credits,
+            tool_calls: tool_calls.to_vec(),
         },
     )
     .await?;
@@ -85,13 +136,17 @@ pub async fn enqueue(
     // Discord post is best-effort: if the bot isn't configured or the post
     // fails, the row still lives in D1 and the web queue surfaces it.
     if !discord_channel_id.is_empty() {
+        let reason_text = match detail {
+            Some(d) => format!("{}: {d}", queue_reason_label(reason)),
+            None => queue_reason_label(reason).to_string(),
+        };
         if let Err(e) = discord::post_ai_draft(
             env,
             &ctx,
             msg.subject.as_deref(),
             &inbound_preview,
-            Some(queue_reason_label(reason)),
-            Some(&rule.label),
+            Some(&reason_text),
+            rule.map(|r| r.label.as_str()),
         )
         .await
         {
@@ -99,10 +154,22 @@ pub async fn enqueue(
                 "Discord draft post failed for tenant {}: {e:?}",
                 msg.tenant_id
             );
+            notifications::notify(
+                env,
+                &msg.tenant_id,
+                Notice {
+                    kind: NotificationKind::DiscordPostFailed,
+                    title: "A draft didn't reach Discord".into(),
+                    body: "Concierge couldn't post a draft to your approvals channel. It's waiting on the Approvals page; check the bot can still post in that channel.".into(),
+                    link: "/admin/approvals".into(),
+                    key: String::new(),
+                },
+            )
+            .await;
         }
     }
 
-    Ok(())
+    Ok(id)
 }
 
 /// Read the current status of a pending approval row, or None if the row
@@ -142,6 +209,21 @@ pub async fn list_pending(db: &D1Database, tenant_id: &str) -> Result<Vec<Pendin
     Ok(rows.iter().map(from_row).collect())
 }
 
+/// How many drafts are waiting on the tenant.
+pub async fn count_pending(db: &D1Database, tenant_id: &str) -> Result<usize> {
+    let row = db
+        .prepare(
+            "SELECT COUNT(*) AS n FROM pending_approvals
+             WHERE tenant_id = ? AND status = 'pending'",
+        )
+        .bind(&[tenant_id.into()])?
+        .first::<serde_json::Value>(None)
+        .await?;
+    Ok(row
+        .and_then(|r| r.get("n").and_then(|v| v.as_u64()))
+        .unwrap_or(0) as usize)
+}
+
 /// Update the draft text in place. Used by edit-and-approve to capture the
 /// human-edited version so the audit row preserves what was actually sent.
 pub async fn save_edited_draft(db: &D1Database, id: &str, draft: &str) -> Result<()> {
@@ -270,6 +352,12 @@ fn from_row(row: &serde_json::Value) -> PendingApproval {
         "risk_money_word" => QueueReason::RiskMoneyWord,
         "risk_commitment" => QueueReason::RiskCommitment,
         "risk_persona_drift" => QueueReason::RiskPersonaDrift,
+        "guard_unknown_url" => QueueReason::GuardUnknownUrl,
+        "guard_foreign_contact" => QueueReason::GuardForeignContact,
+        "guard_prompt_leak" => QueueReason::GuardPromptLeak,
+        "guard_policy" => QueueReason::GuardPolicy,
+        "injection_suspected" => QueueReason::InjectionSuspected,
+        "injection_scan_failed" => QueueReason::InjectionScanFailed,
         _ => QueueReason::RiskLength,
     };
     let status = parse_status(&s("status"));
@@ -278,6 +366,16 @@ fn from_row(row: &serde_json::Value) -> PendingApproval {
         .and_then(|v| v.as_i64())
         .map(|n| n != 0)
         .unwrap_or(false);
+    let credits_charged = row
+        .get("credits_charged")
+        .and_then(|v| v.as_i64())
+        .unwrap_or(1);
+    // Stored as a JSON array; an absent/garbled column reads as "no calls".
+    let tool_calls = row
+        .get("tool_calls")
+        .and_then(|v| v.as_str())
+        .and_then(|s| serde_json::from_str(s).ok())
+        .unwrap_or_default();
 
     PendingApproval {
         id: s("id"),
@@ -288,15 +386,19 @@ fn from_row(row: &serde_json::Value) -> PendingApproval {
         rule_label: s("rule_label"),
         sender: s("sender"),
         sender_name: opt("sender_name"),
+        contact_id: opt("contact_id"),
         inbound_preview: s("inbound_preview"),
         draft: s("draft"),
         queue_reason,
+        queue_detail: opt("queue_detail"),
         status,
         created_at: s("created_at"),
         decided_at: opt("decided_at"),
         decided_by: opt("decided_by"),
         edited,
         last_digest_at: opt("last_digest_at"),
+        credits_charged,
+        tool_calls,
     }
 }
 
@@ -339,9 +441,9 @@ async fn insert_pending_approval(db: &D1Database, row: &PendingApproval) -> Resu
     let stmt = db.prepare(
         "INSERT INTO pending_approvals (
              id, tenant_id, channel, channel_account_id, rule_id, rule_label,
-             sender, sender_name, inbound_preview, draft, queue_reason,
-             status, created_at, edited
-         ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+             sender, sender_name, contact_id, inbound_preview, draft, queue_reason,
+             queue_detail, status, created_at, edited, credits_charged, tool_calls
+         ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
     );
     stmt.bind(&[
         row.id.clone().into(),
@@ -355,12 +457,24 @@ async fn insert_pending_approval(db: &D1Database, row: &PendingApproval) -> Resu
             .clone()
             .map(wasm_bindgen::JsValue::from)
             .unwrap_or(wasm_bindgen::JsValue::null()),
+        row.contact_id
+            .clone()
+            .map(wasm_bindgen::JsValue::from)
+            .unwrap_or(wasm_bindgen::JsValue::null()),
         row.inbound_preview.clone().into(),
         row.draft.clone().into(),
         queue_reason_wire(row.queue_reason).into(),
+        row.queue_detail
+            .clone()
+            .map(wasm_bindgen::JsValue::from)
+            .unwrap_or(wasm_bindgen::JsValue::null()),
         approval_status_wire(row.status).into(),
         row.created_at.clone().into(),
         wasm_bindgen::JsValue::from(if row.edited { 1.0_f64 } else { 0.0_f64 }),
+        wasm_bindgen::JsValue::from(row.credits_charged as f64),
+        serde_json::to_string(&row.tool_calls)
+            .unwrap_or_else(|_| "[]".to_string())
+            .into(),
     ])?
     .run()
     .await?;
@@ -374,6 +488,12 @@ fn queue_reason_wire(reason: QueueReason) -> &'static str {
         QueueReason::RiskMoneyWord => "risk_money_word",
         QueueReason::RiskCommitment => "risk_commitment",
         QueueReason::RiskPersonaDrift => "risk_persona_drift",
+        QueueReason::GuardUnknownUrl => "guard_unknown_url",
+        QueueReason::GuardForeignContact => "guard_foreign_contact",
+        QueueReason::GuardPromptLeak => "guard_prompt_leak",
+        QueueReason::GuardPolicy => "guard_policy",
+        QueueReason::InjectionSuspected => "injection_suspected",
+        QueueReason::InjectionScanFailed => "injection_scan_failed",
     }
 }
 
@@ -385,6 +505,12 @@ pub fn queue_reason_label(reason: QueueReason) -> &'static str {
         QueueReason::RiskMoneyWord => "Mentions money",
         QueueReason::RiskCommitment => "Makes a commitment",
         QueueReason::RiskPersonaDrift => "Off-topic for persona",
+        QueueReason::GuardUnknownUrl => "Links to an unlisted site",
+        QueueReason::GuardForeignContact => "Shares someone else's contact details",
+        QueueReason::GuardPromptLeak => "Repeats its instructions",
+        QueueReason::GuardPolicy => "Policy review flagged it",
+        QueueReason::InjectionSuspected => "Looks like a prompt injection",
+        QueueReason::InjectionScanFailed => "Injection scan failed",
     }
 }
 
@@ -393,19 +519,24 @@ pub fn queue_reason_label(reason: QueueReason) -> &'static str {
 /// DO binding is missing or the call fails, the browsers' 5s polling
 /// fallback still picks up the change.
 pub async fn notify_change(env: &Env, tenant_id: &str) {
-    if let Err(e) = notify_change_inner(env, tenant_id).await {
+    if let Err(e) = broadcast(env, tenant_id, "approval-changed").await {
         console_log!("ApprovalsDO broadcast failed for tenant {tenant_id}: {e:?}");
     }
 }
 
-async fn notify_change_inner(env: &Env, tenant_id: &str) -> Result<()> {
+/// Send `event` to every SSE stream the tenant has open on the
+/// `ApprovalsDO` (approvals and inbox tabs alike).
+pub async fn broadcast(env: &Env, tenant_id: &str, event: &str) -> Result<()> {
     let ns = env.durable_object("APPROVALS_DO")?;
     let stub = ns.id_from_name(tenant_id)?.get_stub()?;
     // Any URL works; the DO routes on path. Origin doesn't matter for
     // intra-worker DO fetches but the URL must be parseable.
     let mut init = RequestInit::new();
     init.with_method(Method::Post);
-    let req = Request::new_with_init("https://do.invalid/broadcast", &init)?;
+    let req = Request::new_with_init(
+        &format!("https://do.invalid/broadcast?event={event}"),
+        &init,
+    )?;
     stub.fetch_with_request(req).await?;
     Ok(())
 }
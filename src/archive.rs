@@ -0,0 +1,223 @@
+//! Writers for downloadable archives: CSV tables and an uncompressed ZIP
+//! to bundle them. Data exports are mostly short text rows that KV already
+//! compresses at rest, so storing entries without deflate keeps the writer
+//! small and dependency-free.
+
+/// One file in an archive.
+pub struct ArchiveFile {
+    pub name: String,
+    pub bytes: Vec<u8>,
+}
+
+/// MS-DOS date and time, as ZIP headers store them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DosTime {
+    pub date: u16,
+    pub time: u16,
+}
+
+impl DosTime {
+    /// Clamps to the format's 1980-2107 range; seconds have 2s resolution.
+    pub fn new(year: u32, month: u32, day: u32, hour: u32, minute: u32, second: u32) -> Self {
+        let year = year.clamp(1980, 2107) - 1980;
+        DosTime {
+            date: ((year << 9) | (month.clamp(1, 12) << 5) | day.clamp(1, 31)) as u16,
+            time: ((hour.min(23) << 11) | (minute.min(59) << 5) | (second.min(59) / 2)) as u16,
+        }
+    }
+}
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xEDB8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+}
+
+/// CRC-32 (IEEE), as ZIP uses it.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, &b| {
+        CRC_TABLE[((crc ^ b as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// A ZIP archive of `files`, stored without compression. File names are
+/// flagged UTF-8. Callers keep archives under 4 GiB (no ZIP64).
+pub fn zip(files: &[ArchiveFile], modified: DosTime) -> Vec<u8> {
+    const VERSION: u16 = 20;
+    const UTF8_NAMES: u16 = 1 << 11;
+
+    let mut out = Vec::new();
+    let mut central = Vec::new();
+    for file in files {
+        let offset = out.len() as u32;
+        let crc = crc32(&file.bytes);
+        let size = file.bytes.len() as u32;
+        let name = file.name.as_bytes();
+
+        out.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        for field in [VERSION, UTF8_NAMES, 0, modified.time, modified.date] {
+            out.extend_from_slice(&field.to_le_bytes());
+        }
+        for field in [crc, size, size] {
+            out.extend_from_slice(&field.to_le_bytes());
+        }
+        out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out.extend_from_slice(name);
+        out.extend_from_slice(&file.bytes);
+
+        central.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        for field in [
+            VERSION,
+            VERSION,
+            UTF8_NAMES,
+            0,
+            modified.time,
+            modified.date,
+        ] {
+            central.extend_from_slice(&field.to_le_bytes());
+        }
+        for field in [crc, size, size] {
+            central.extend_from_slice(&field.to_le_bytes());
+        }
+        // Name length, extra, comment, disk, internal attributes.
+        for field in [name.len() as u16, 0, 0, 0, 0] {
+            central.extend_from_slice(&field.to_le_bytes());
+        }
+        for field in [0u32, offset] {
+            central.extend_from_slice(&field.to_le_bytes());
+        }
+        central.extend_from_slice(name);
+    }
+
+    let central_offset = out.len() as u32;
+    let central_size = central.len() as u32;
+    out.extend_from_slice(&central);
+    out.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+    let entries = files.len() as u16;
+    for field in [0u16, 0, entries, entries] {
+        out.extend_from_slice(&field.to_le_bytes());
+    }
+    for field in [central_size, central_offset] {
+        out.extend_from_slice(&field.to_le_bytes());
+    }
+    out.extend_from_slice(&0u16.to_le_bytes());
+    out
+}
+
+/// One CSV field, quoted when it has to be (RFC 4180).
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn cell(value: Option<&serde_json::Value>) -> String {
+    match value {
+        None | Some(serde_json::Value::Null) => String::new(),
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+    }
+}
+
+/// A CSV table of `rows` (D1 result objects) with a header row of
+/// `columns`. Missing columns are empty cells.
+pub fn csv(columns: &[&str], rows: &[serde_json::Value]) -> String {
+    let mut out = columns.join(",");
+    out.push_str("\r\n");
+    for row in rows {
+        let line: Vec<String> = columns
+            .iter()
+            .map(|c| csv_field(&cell(row.get(*c))))
+            .collect();
+        out.push_str(&line.join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc32_matches_the_standard_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn zip_has_headers_entries_and_end_record() {
+        let files = vec![
+            ArchiveFile {
+                name: "a.json".into(),
+                bytes: b"{}".to_vec(),
+            },
+            ArchiveFile {
+                name: "b.csv".into(),
+                bytes: b"x\r\n1\r\n".to_vec(),
+            },
+        ];
+        let when = DosTime::new(2026, 10, 18, 12, 30, 10);
+        let z = zip(&files, when);
+        assert_eq!(&z[..4], &[0x50, 0x4b, 0x03, 0x04]);
+        assert_eq!(&z[30..36], b"a.json");
+        assert_eq!(&z[36..38], b"{}");
+
+        let end = z.len() - 22;
+        assert_eq!(&z[end..end + 4], &[0x50, 0x4b, 0x05, 0x06]);
+        let entries = u16::from_le_bytes([z[end + 10], z[end + 11]]);
+        assert_eq!(entries, 2);
+        let cd_size = u32::from_le_bytes(z[end + 12..end + 16].try_into().unwrap()) as usize;
+        let cd_offset = u32::from_le_bytes(z[end + 16..end + 20].try_into().unwrap()) as usize;
+        assert_eq!(cd_offset + cd_size, end);
+        assert_eq!(&z[cd_offset..cd_offset + 4], &[0x50, 0x4b, 0x01, 0x02]);
+    }
+
+    #[test]
+    fn dos_time_packs_fields() {
+        let t = DosTime::new(1980, 1, 1, 0, 0, 0);
+        assert_eq!(
+            t,
+            DosTime {
+                date: 0x21,
+                time: 0
+            }
+        );
+        let t = DosTime::new(2026, 10, 18, 12, 30, 10);
+        assert_eq!(t.date >> 9, 46);
+        assert_eq!((t.date >> 5) & 0xf, 10);
+        assert_eq!(t.time >> 11, 12);
+        assert_eq!(t.time & 0x1f, 5);
+    }
+
+    #[test]
+    fn csv_quotes_only_when_needed() {
+        let rows = vec![
+            serde_json::json!({"id": "m1", "body": "plain", "n": 3}),
+            serde_json::json!({"id": "m2", "body": "a, \"b\"\nc", "n": null}),
+        ];
+        assert_eq!(
+            csv(&["id", "body", "n", "missing"], &rows),
+            "id,body,n,missing\r\nm1,plain,3,\r\nm2,\"a, \"\"b\"\"\nc\",,\r\n"
+        );
+    }
+}
@@ -8,6 +8,9 @@
 //! Deduction happens BEFORE send (optimistic). If send fails,
 //! credits are restored. This prevents double-spend races.
 //! Soonest-expiring credits are consumed first.
+//!
+//! A client an agency pays for (`agency::payer`) draws on, and is refunded
+//! to, the agency's ledger.
 
 pub mod cadence;
 pub mod razorpay;
@@ -35,40 +38,54 @@ pub fn calculate_total(credits: i64, milli_price: i64) -> i64 {
     (credits * milli_price + 500) / 1000
 }
 
-/// Try to deduct one reply credit. Returns true if credit was available
-/// and deducted. Returns false if out of credits.
+/// Try to deduct `credits` for one reply (the rule's tier cost, see
+/// `storage::Pricing::reply_credits`). All-or-nothing: returns false and
+/// leaves the balance alone if fewer than `credits` remain.
 /// Must be called BEFORE sending the reply.
-pub async fn try_deduct(db: &D1Database, tenant_id: &str) -> Result<bool> {
+pub async fn try_deduct(db: &D1Database, tenant_id: &str, credits: i64) -> Result<bool> {
+    let tenant_id = &crate::agency::payer(db, tenant_id).await?;
     let mut billing = storage::get_tenant_billing(db, tenant_id).await?;
     prune_expired(&mut billing);
     sort_credits(&mut billing);
 
-    let mut deducted = false;
-    for entry in billing.credits.iter_mut() {
-        if entry.amount > 0 {
-            entry.amount -= 1;
-            deducted = true;
-            break;
-        }
-    }
-
-    if !deducted {
+    if !deduct(&mut billing, credits) {
         storage::save_tenant_billing(db, tenant_id, &billing).await?;
         return Ok(false);
     }
 
-    billing.credits.retain(|e| e.amount > 0);
     billing.replies_used += 1;
     storage::save_tenant_billing(db, tenant_id, &billing).await?;
     Ok(true)
 }
 
-/// Restore one reply credit after a failed send.
+/// Take `credits` from a sorted ledger, soonest-expiring first, spanning
+/// entries if needed. Returns false without touching the ledger when the
+/// balance is short.
+fn deduct(billing: &mut TenantBilling, credits: i64) -> bool {
+    let credits = credits.max(1);
+    if billing.total_remaining() < credits {
+        return false;
+    }
+    let mut left = credits;
+    for entry in billing.credits.iter_mut() {
+        let take = entry.amount.max(0).min(left);
+        entry.amount -= take;
+        left -= take;
+        if left == 0 {
+            break;
+        }
+    }
+    billing.credits.retain(|e| e.amount > 0);
+    true
+}
+
+/// Restore `credits` after a failed send or a rejected/expired draft.
 /// Adds as a non-expiring purchase credit for simplicity.
-pub async fn restore_credit(db: &D1Database, tenant_id: &str) -> Result<()> {
+pub async fn restore_credit(db: &D1Database, tenant_id: &str, credits: i64) -> Result<()> {
+    let tenant_id = &crate::agency::payer(db, tenant_id).await?;
     let mut billing = storage::get_tenant_billing(db, tenant_id).await?;
     billing.credits.push(CreditEntry {
-        amount: 1,
+        amount: credits.max(1),
         source: CreditSource::Purchase,
         expires_at: None,
         granted_at: now_iso(),
@@ -225,6 +242,26 @@ mod tests {
         assert_eq!(b.credits.len(), 2);
     }
 
+    #[test]
+    fn deduct_spans_entries_soonest_first() {
+        let mut b = make_billing(vec![
+            entry(1, CreditSource::Grant, Some("2027-01-01T00:00:00Z")),
+            entry(5, CreditSource::Purchase, None),
+        ]);
+        assert!(deduct(&mut b, 3));
+        assert_eq!(b.credits.len(), 1);
+        assert_eq!(b.credits[0].amount, 3);
+    }
+
+    #[test]
+    fn deduct_is_all_or_nothing() {
+        let mut b = make_billing(vec![entry(2, CreditSource::Purchase, None)]);
+        assert!(!deduct(&mut b, 3));
+        assert_eq!(b.total_remaining(), 2);
+        assert!(deduct(&mut b, 2));
+        assert!(!b.has_credits());
+    }
+
     #[test]
     fn test_calculate_total() {
         // 10 paise (10,000 milli-paise) per reply
@@ -238,4 +275,54 @@ mod tests {
         assert_eq!(calculate_total(500, 100), 50); // $0.50
         assert_eq!(calculate_total(1000, 100), 100); // $1.00
     }
+
+    #[test]
+    fn reply_cost_follows_the_allowed_tier() {
+        use crate::storage::Pricing;
+        use crate::types::ModelTier;
+
+        let pricing = Pricing {
+            fast_reply_credits: 1,
+            quality_reply_credits: 3,
+            allowed_tiers: vec![ModelTier::Fast],
+            ..Default::default()
+        };
+        // A rule pinned to a disallowed tier runs (and is charged) on the
+        // first allowed one.
+        assert_eq!(pricing.effective_tier(ModelTier::Quality), ModelTier::Fast);
+        assert_eq!(pricing.reply_credits(ModelTier::Quality), 1);
+
+        let pricing = Pricing {
+            quality_reply_credits: 3,
+            ..Default::default()
+        };
+        assert_eq!(pricing.reply_credits(ModelTier::Quality), 3);
+        assert_eq!(pricing.reply_credits(ModelTier::Fast), 1);
+    }
+
+    #[test]
+    fn token_pricing_rounds_up_and_costs_by_tier() {
+        use crate::ai::provider::Tier;
+        use crate::storage::{Pricing, TokenRate};
+
+        let pricing = Pricing {
+            tokens_per_credit: 1_000,
+            quality_rate: TokenRate {
+                input: 270_000,
+                output: 850_000,
+            },
+            ..Default::default()
+        };
+        // Any reply costs at least one credit; partial blocks round up.
+        assert_eq!(pricing.token_credits(0), 1);
+        assert_eq!(pricing.token_credits(1_000), 1);
+        assert_eq!(pricing.token_credits(1_001), 2);
+
+        // 1M input + 1M output on quality = $0.27 + $0.85.
+        assert_eq!(
+            pricing.token_cost(Tier::Quality, 1_000_000, 1_000_000),
+            1_120_000
+        );
+        assert_eq!(pricing.token_cost(Tier::Quality, 2_000, 0), 540);
+    }
 }
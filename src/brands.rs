@@ -0,0 +1,315 @@
+//! Brands: several businesses or locations inside one tenant.
+//!
+//! Each brand has its own business details, persona and opening hours, and
+//! each channel account points at one through its `brand_id`. The main
+//! brand is the onboarding state's business, persona and hours, so tenants
+//! that never add a brand behave exactly as before; extra brands live in
+//! KV at `brands:{tenant}`. An account whose brand is unset, or was
+//! removed, answers as the main brand.
+
+use worker::*;
+
+use crate::helpers::{generate_id, now_iso};
+use crate::storage::{
+    get_brands, get_discord_config_by_tenant, get_email_addresses, get_instagram_account,
+    get_onboarding, get_whatsapp_account, list_instagram_accounts, list_whatsapp_accounts,
+    save_brands, save_discord_config, save_email_address, save_instagram_account, save_onboarding,
+    save_whatsapp_account,
+};
+use crate::types::{
+    Brand, BusinessInfo, Channel, DayHours, OnboardingState, OpeningHours, PersonaConfig,
+};
+
+fn main_brand(state: OnboardingState) -> Brand {
+    Brand {
+        id: Brand::MAIN.to_string(),
+        business: state.business,
+        persona: state.persona,
+        hours: state.hours,
+        created_at: String::new(),
+    }
+}
+
+/// Every brand of the tenant, main brand first.
+pub async fn list(kv: &kv::KvStore, tenant_id: &str) -> Result<Vec<Brand>> {
+    let mut brands = vec![main_brand(get_onboarding(kv, tenant_id).await?)];
+    brands.extend(get_brands(kv, tenant_id).await?);
+    Ok(brands)
+}
+
+pub async fn get(kv: &kv::KvStore, tenant_id: &str, id: &str) -> Result<Option<Brand>> {
+    if id == Brand::MAIN {
+        return Ok(Some(main_brand(get_onboarding(kv, tenant_id).await?)));
+    }
+    Ok(get_brands(kv, tenant_id)
+        .await?
+        .into_iter()
+        .find(|b| b.id == id))
+}
+
+/// The brand a channel account answers for: its own when set and still
+/// there, otherwise the main brand.
+pub async fn resolve(kv: &kv::KvStore, tenant_id: &str, brand_id: Option<&str>) -> Result<Brand> {
+    if let Some(id) = brand_id {
+        if let Some(brand) = get(kv, tenant_id, id).await? {
+            return Ok(brand);
+        }
+    }
+    Ok(main_brand(get_onboarding(kv, tenant_id).await?))
+}
+
+/// Write a brand back. The main brand's fields go to the onboarding state.
+pub async fn save(kv: &kv::KvStore, tenant_id: &str, brand: &Brand) -> Result<()> {
+    if brand.is_main() {
+        let mut state = get_onboarding(kv, tenant_id).await?;
+        state.business = brand.business.clone();
+        state.persona = brand.persona.clone();
+        state.hours = brand.hours.clone();
+        return save_onboarding(kv, tenant_id, &state).await;
+    }
+    let mut brands = get_brands(kv, tenant_id).await?;
+    match brands.iter_mut().find(|b| b.id == brand.id) {
+        Some(existing) => *existing = brand.clone(),
+        None => brands.push(brand.clone()),
+    }
+    save_brands(kv, tenant_id, &brands).await
+}
+
+/// Start a brand with a name and the default persona. Its persona still
+/// needs a safety check before it can draft replies.
+pub async fn create(kv: &kv::KvStore, tenant_id: &str, name: &str) -> Result<Brand> {
+    let brand = Brand {
+        id: generate_id(),
+        business: BusinessInfo {
+            name: name.to_string(),
+            ..Default::default()
+        },
+        persona: PersonaConfig::default(),
+        hours: OpeningHours::default(),
+        created_at: now_iso(),
+    };
+    save(kv, tenant_id, &brand).await?;
+    Ok(brand)
+}
+
+/// Remove an extra brand and move its channel accounts back to the main
+/// brand. The main brand can't be removed.
+pub async fn remove(kv: &kv::KvStore, tenant_id: &str, id: &str) -> Result<bool> {
+    let mut brands = get_brands(kv, tenant_id).await?;
+    let before = brands.len();
+    brands.retain(|b| b.id != id);
+    if brands.len() == before {
+        return Ok(false);
+    }
+    for account in assignments(kv, tenant_id).await? {
+        if account.brand_id.as_deref() == Some(id) {
+            assign(kv, tenant_id, account.channel, &account.account_id, None).await?;
+        }
+    }
+    save_brands(kv, tenant_id, &brands).await?;
+    Ok(true)
+}
+
+/// One channel account and the brand it answers for.
+pub struct Assignment {
+    pub channel: Channel,
+    pub account_id: String,
+    pub label: String,
+    pub brand_id: Option<String>,
+}
+
+/// Every channel account of the tenant with its brand.
+pub async fn assignments(kv: &kv::KvStore, tenant_id: &str) -> Result<Vec<Assignment>> {
+    let mut out = Vec::new();
+    for a in list_whatsapp_accounts(kv, tenant_id).await? {
+        out.push(Assignment {
+            channel: Channel::WhatsApp,
+            label: format!("{} ({})", a.name, a.phone_number),
+            account_id: a.id,
+            brand_id: a.brand_id,
+        });
+    }
+    for a in list_instagram_accounts(kv, tenant_id).await? {
+        out.push(Assignment {
+            channel: Channel::Instagram,
+            label: format!("@{}", a.instagram_username),
+            account_id: a.id,
+            brand_id: a.brand_id,
+        });
+    }
+    for a in get_email_addresses(kv, tenant_id).await? {
+        out.push(Assignment {
+            channel: Channel::Email,
+            label: a.local_part.clone(),
+            account_id: a.local_part,
+            brand_id: a.brand_id,
+        });
+    }
+    if let Some(c) = get_discord_config_by_tenant(kv, tenant_id).await? {
+        out.push(Assignment {
+            channel: Channel::Discord,
+            label: c.guild_name.unwrap_or_else(|| c.guild_id.clone()),
+            account_id: c.guild_id,
+            brand_id: c.brand_id,
+        });
+    }
+    Ok(out)
+}
+
+/// The brand a channel account answers for. Discord has one config per
+/// tenant, so its `account_id` isn't compared.
+pub async fn of_account(
+    kv: &kv::KvStore,
+    tenant_id: &str,
+    channel: &Channel,
+    account_id: &str,
+) -> Result<Brand> {
+    let brand_id = assignments(kv, tenant_id)
+        .await?
+        .into_iter()
+        .find(|a| {
+            a.channel == *channel && (a.account_id == account_id || *channel == Channel::Discord)
+        })
+        .and_then(|a| a.brand_id);
+    resolve(kv, tenant_id, brand_id.as_deref()).await
+}
+
+/// Point one of the tenant's channel accounts at a brand (`None` for the
+/// main brand). Returns false when the tenant has no such account.
+pub async fn assign(
+    kv: &kv::KvStore,
+    tenant_id: &str,
+    channel: Channel,
+    account_id: &str,
+    brand_id: Option<String>,
+) -> Result<bool> {
+    match channel {
+        Channel::WhatsApp => match get_whatsapp_account(kv, account_id).await? {
+            Some(mut a) if a.tenant_id == tenant_id => {
+                a.brand_id = brand_id;
+                save_whatsapp_account(kv, &a).await?;
+            }
+            _ => return Ok(false),
+        },
+        Channel::Instagram => match get_instagram_account(kv, account_id).await? {
+            Some(mut a) if a.tenant_id == tenant_id => {
+                a.brand_id = brand_id;
+                save_instagram_account(kv, &a).await?;
+            }
+            _ => return Ok(false),
+        },
+        Channel::Email => {
+            let addrs = get_email_addresses(kv, tenant_id).await?;
+            match addrs.into_iter().find(|a| a.local_part == account_id) {
+                Some(mut a) => {
+                    a.brand_id = brand_id;
+                    save_email_address(kv, tenant_id, &a).await?;
+                }
+                None => return Ok(false),
+            }
+        }
+        Channel::Discord => match get_discord_config_by_tenant(kv, tenant_id).await? {
+            Some(mut c) if c.guild_id == account_id => {
+                c.brand_id = brand_id;
+                save_discord_config(kv, &c).await?;
+            }
+            _ => return Ok(false),
+        },
+    }
+    Ok(true)
+}
+
+/// The system prompt for a brand's replies: the persona, then the opening
+/// hours when the brand has any.
+pub fn system_prompt(persona: &PersonaConfig, hours: &OpeningHours) -> String {
+    let prompt = persona.active_prompt();
+    let hours = hours.summary();
+    match (prompt.is_empty(), hours.is_empty()) {
+        (_, true) => prompt,
+        (true, false) => hours,
+        (false, false) => format!("{prompt}\n\n{hours}"),
+    }
+}
+
+/// Normalise a 24-hour time to `HH:MM`. Accepts `9:30` and `09:30`.
+fn parse_time(s: &str) -> Option<String> {
+    let (h, m) = s.trim().split_once(':')?;
+    let (h, m): (u8, u8) = (h.parse().ok()?, m.parse().ok()?);
+    (h < 24 && m < 60).then(|| format!("{h:02}:{m:02}"))
+}
+
+/// Read `open_0`..`close_6` (Monday first) from the brand form. A day with
+/// both times blank is closed; one blank or a malformed time is an error.
+pub fn hours_from_form(form: &serde_json::Value) -> std::result::Result<OpeningHours, String> {
+    let field = |k: String| {
+        form.get(&k)
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .trim()
+            .to_string()
+    };
+    let mut days = Vec::with_capacity(7);
+    for (i, name) in OpeningHours::DAYS.iter().enumerate() {
+        let (open, close) = (field(format!("open_{i}")), field(format!("close_{i}")));
+        if open.is_empty() && close.is_empty() {
+            days.push(DayHours::default());
+            continue;
+        }
+        match (parse_time(&open), parse_time(&close)) {
+            (Some(open), Some(close)) => days.push(DayHours { open, close }),
+            _ => {
+                return Err(format!(
+                    "Give {name} an opening and a closing time like 09:30, or leave both blank."
+                ))
+            }
+        }
+    }
+    Ok(OpeningHours { days })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn times_normalise_and_reject_nonsense() {
+        assert_eq!(parse_time("9:30").as_deref(), Some("09:30"));
+        assert_eq!(parse_time(" 18:00 ").as_deref(), Some("18:00"));
+        assert_eq!(parse_time("24:00"), None);
+        assert_eq!(parse_time("10:60"), None);
+        assert_eq!(parse_time("10am"), None);
+    }
+
+    #[test]
+    fn hours_form_reads_open_and_closed_days() {
+        let form = serde_json::json!({
+            "open_0": "9:00", "close_0": "18:00",
+            "open_5": "10:00", "close_5": "14:00",
+        });
+        let hours = hours_from_form(&form).unwrap();
+        assert_eq!(hours.days.len(), 7);
+        assert_eq!(hours.day(0).unwrap().open, "09:00");
+        assert!(hours.day(1).is_none());
+        assert_eq!(
+            hours.summary(),
+            "Opening hours: Monday 09:00-18:00; Tuesday closed; Wednesday closed; \
+             Thursday closed; Friday closed; Saturday 10:00-14:00; Sunday closed."
+        );
+
+        let half = serde_json::json!({ "open_2": "9:00" });
+        assert!(hours_from_form(&half).unwrap_err().contains("Wednesday"));
+    }
+
+    #[test]
+    fn system_prompt_adds_hours_only_when_set() {
+        let persona = PersonaConfig::default();
+        let none = OpeningHours::default();
+        assert_eq!(system_prompt(&persona, &none), persona.active_prompt());
+
+        let form = serde_json::json!({ "open_6": "11:00", "close_6": "15:00" });
+        let hours = hours_from_form(&form).unwrap();
+        let prompt = system_prompt(&persona, &hours);
+        assert!(prompt.starts_with(&persona.active_prompt()));
+        assert!(prompt.ends_with("Sunday 11:00-15:00."));
+    }
+}
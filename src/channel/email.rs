@@ -12,12 +12,14 @@ use crate::types::*;
 /// - `original_subject`, `message_id`, `references`: for thread headers.
 ///
 /// CC and BCC recipients come from the address's verified
-/// `notification_recipients` list — owner is always present.
+/// `notification_recipients` list — owner is always present. `html` is
+/// the formatted alternative part, sent alongside the plain-text `body`.
 pub async fn send_reply(
     env: &Env,
     metadata: &serde_json::Value,
     to: &str,
     body: &str,
+    html: Option<&str>,
     subject: Option<&str>,
 ) -> Result<()> {
     let kv = env.kv("KV")?;
@@ -94,7 +96,7 @@ pub async fn send_reply(
         to: to.to_string(),
         subject,
         text: Some(body.to_string()),
-        html: None,
+        html: html.map(str::to_string),
         reply_to: Some(from_addr),
         cc: cc_list,
         bcc: bcc_list,
@@ -0,0 +1,646 @@
+//! Channel-aware reply formatting.
+//!
+//! AI drafts are written in a small Markdown subset: `**bold**`,
+//! `*italic*` / `_italic_`, `~~strike~~`, `` `code` ``, `[label](url)`,
+//! `#` headings and `-` / `1.` lists. This module parses that neutral form
+//! once and renders it per channel: WhatsApp's `*bold*` syntax, Discord's
+//! Markdown, plain text for Instagram, and a plain-text + HTML pair for
+//! email. Text people wrote (canned and holding replies, inbox and relay
+//! messages) is sent as written; it may already use a channel's own
+//! syntax, like WhatsApp's `*bold*`. Replies longer than a channel's
+//! message limit are split into ordered chunks, preferring paragraph, then
+//! line, then sentence boundaries.
+
+use crate::helpers::html_escape;
+use crate::types::Channel;
+
+/// WhatsApp Cloud API text body limit.
+const WHATSAPP_LIMIT: usize = 4096;
+/// Instagram DM text limit.
+const INSTAGRAM_LIMIT: usize = 1000;
+/// Discord message content limit.
+const DISCORD_LIMIT: usize = 2000;
+
+/// A reply rendered for one channel. `chunks` are sent in order; email
+/// always has exactly one chunk plus the `html` part.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Formatted {
+    pub chunks: Vec<String>,
+    pub html: Option<String>,
+}
+
+/// A reply body and how it was written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Body<'a> {
+    /// An AI draft in the Markdown subset; rendered for the channel.
+    Markdown(&'a str),
+    /// Written by the tenant or an agent; only split to fit.
+    Verbatim(&'a str),
+}
+
+pub fn format_reply(channel: &Channel, body: Body<'_>) -> Formatted {
+    let text = match body {
+        Body::Markdown(text) => text,
+        Body::Verbatim(text) => {
+            let chunks = match channel {
+                Channel::WhatsApp => split(text, WHATSAPP_LIMIT),
+                Channel::Instagram => split(text, INSTAGRAM_LIMIT),
+                Channel::Discord => split(text, DISCORD_LIMIT),
+                Channel::Email => vec![text.to_string()],
+            };
+            return Formatted { chunks, html: None };
+        }
+    };
+    let doc = parse(text);
+    match channel {
+        Channel::WhatsApp => Formatted {
+            chunks: split(&render_text(&doc, Syntax::WhatsApp), WHATSAPP_LIMIT),
+            html: None,
+        },
+        Channel::Instagram => Formatted {
+            chunks: split(&render_text(&doc, Syntax::Plain), INSTAGRAM_LIMIT),
+            html: None,
+        },
+        Channel::Discord => Formatted {
+            chunks: split(&render_text(&doc, Syntax::Discord), DISCORD_LIMIT),
+            html: None,
+        },
+        Channel::Email => Formatted {
+            chunks: vec![render_text(&doc, Syntax::Plain)],
+            html: Some(render_html(&doc)),
+        },
+    }
+}
+
+/// An AI draft as plain text, each link as `label (url)`. Every link
+/// target any channel renders (Discord's Markdown, the email's anchors)
+/// appears here as a word of its own, which is what `guardrails` checks.
+pub fn plain_text(markdown: &str) -> String {
+    render_text(&parse(markdown), Syntax::Plain)
+}
+
+// ============================================================================
+// Parsing
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Inline {
+    Text(String),
+    Bold(Vec<Inline>),
+    Italic(Vec<Inline>),
+    Strike(Vec<Inline>),
+    Code(String),
+    Link { label: Vec<Inline>, url: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Line {
+    Blank,
+    Text(Vec<Inline>),
+    Heading(Vec<Inline>),
+    Bullet(Vec<Inline>),
+    Numbered(u32, Vec<Inline>),
+}
+
+fn parse(text: &str) -> Vec<Line> {
+    text.lines()
+        .map(|raw| {
+            let line = raw.trim_end();
+            let trimmed = line.trim_start();
+            if trimmed.is_empty() {
+                return Line::Blank;
+            }
+            let hashes = trimmed.chars().take_while(|c| *c == '#').count();
+            if (1..=6).contains(&hashes) && trimmed[hashes..].starts_with(' ') {
+                return Line::Heading(parse_inline(trimmed[hashes..].trim()));
+            }
+            for marker in ["- ", "* ", "+ ", "• "] {
+                if let Some(rest) = trimmed.strip_prefix(marker) {
+                    return Line::Bullet(parse_inline(rest.trim_start()));
+                }
+            }
+            let digits = trimmed.chars().take_while(|c| c.is_ascii_digit()).count();
+            if digits > 0 && digits < 4 {
+                let rest = &trimmed[digits..];
+                if let Some(rest) = rest.strip_prefix(". ").or_else(|| rest.strip_prefix(") ")) {
+                    let n = trimmed[..digits].parse().unwrap_or(1);
+                    return Line::Numbered(n, parse_inline(rest.trim_start()));
+                }
+            }
+            Line::Text(parse_inline(line))
+        })
+        .collect()
+}
+
+/// Parse inline markup. A delimiter only counts when its closing pair is
+/// on the same line; otherwise it's literal text, so stray `*` or `_`
+/// (prices, snake_case, emoticons) pass through untouched.
+fn parse_inline(s: &str) -> Vec<Inline> {
+    let mut out = Vec::new();
+    let mut text = String::new();
+    let mut i = 0;
+
+    while i < s.len() {
+        let rest = &s[i..];
+        let prev = s[..i].chars().next_back();
+
+        if let Some((node, used)) = parse_span(rest, prev) {
+            if !text.is_empty() {
+                out.push(Inline::Text(std::mem::take(&mut text)));
+            }
+            out.push(node);
+            i += used;
+            continue;
+        }
+
+        let ch = rest.chars().next().unwrap_or_default();
+        text.push(ch);
+        i += ch.len_utf8();
+    }
+    if !text.is_empty() {
+        out.push(Inline::Text(text));
+    }
+    out
+}
+
+/// Try to parse one delimited span at the start of `rest`. Returns the
+/// node and how many bytes it consumed.
+fn parse_span(rest: &str, prev: Option<char>) -> Option<(Inline, usize)> {
+    if let Some(body) = rest.strip_prefix('`') {
+        let end = body.find('`')?;
+        if end == 0 {
+            return None;
+        }
+        return Some((Inline::Code(body[..end].to_string()), end + 2));
+    }
+
+    if rest.starts_with('[') {
+        let close = rest.find("](")?;
+        let url_end = rest[close + 2..].find(')')?;
+        let url = rest[close + 2..close + 2 + url_end].trim();
+        if url.is_empty() || url.contains(char::is_whitespace) {
+            return None;
+        }
+        return Some((
+            Inline::Link {
+                label: parse_inline(&rest[1..close]),
+                url: url.to_string(),
+            },
+            close + 2 + url_end + 1,
+        ));
+    }
+
+    for (delim, wrap) in [
+        ("**", Inline::Bold as fn(Vec<Inline>) -> Inline),
+        ("__", Inline::Bold),
+        ("~~", Inline::Strike),
+        ("*", Inline::Italic),
+        ("_", Inline::Italic),
+    ] {
+        let Some(body) = rest.strip_prefix(delim) else {
+            continue;
+        };
+        // Opening delimiter must hug its text; `_` also can't sit inside
+        // a word (snake_case, email local parts).
+        if body.starts_with(char::is_whitespace) || body.is_empty() {
+            continue;
+        }
+        if delim == "_" && prev.is_some_and(char::is_alphanumeric) {
+            continue;
+        }
+        let Some(end) = find_closing(body, delim) else {
+            continue;
+        };
+        return Some((wrap(parse_inline(&body[..end])), delim.len() * 2 + end));
+    }
+    None
+}
+
+fn find_closing(body: &str, delim: &str) -> Option<usize> {
+    let mut from = 0;
+    while let Some(pos) = body[from..].find(delim) {
+        let at = from + pos;
+        let before = body[..at].chars().next_back();
+        let after = body[at + delim.len()..].chars().next();
+        let hugs = before.is_some_and(|c| !c.is_whitespace());
+        let word_inside = delim == "_" && after.is_some_and(char::is_alphanumeric);
+        // `**` inside a single-`*` span belongs to a nested bold.
+        let doubled = delim.len() == 1 && body[at + 1..].starts_with(delim);
+        if at > 0 && hugs && !word_inside && !doubled {
+            return Some(at);
+        }
+        from = at + delim.len() + usize::from(doubled);
+    }
+    None
+}
+
+// ============================================================================
+// Rendering
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Syntax {
+    WhatsApp,
+    Discord,
+    Plain,
+}
+
+fn render_text(doc: &[Line], syntax: Syntax) -> String {
+    let bullet = if syntax == Syntax::Plain {
+        "• "
+    } else {
+        "- "
+    };
+    let lines: Vec<String> = doc
+        .iter()
+        .map(|line| match line {
+            Line::Blank => String::new(),
+            Line::Text(spans) => render_spans(spans, syntax),
+            Line::Heading(spans) => match syntax {
+                Syntax::Plain => render_spans(spans, syntax),
+                _ => render_spans(&[Inline::Bold(spans.clone())], syntax),
+            },
+            Line::Bullet(spans) => format!("{bullet}{}", render_spans(spans, syntax)),
+            Line::Numbered(n, spans) => format!("{n}. {}", render_spans(spans, syntax)),
+        })
+        .collect();
+    collapse_blank_lines(&lines.join("\n"))
+}
+
+fn render_spans(spans: &[Inline], syntax: Syntax) -> String {
+    spans.iter().map(|s| render_span(s, syntax)).collect()
+}
+
+fn render_span(span: &Inline, syntax: Syntax) -> String {
+    let wrap = |marker: &str, inner: &[Inline]| {
+        let body = render_spans(inner, syntax);
+        if syntax == Syntax::Plain {
+            body
+        } else {
+            format!("{marker}{body}{marker}")
+        }
+    };
+    match (span, syntax) {
+        (Inline::Text(t), _) => t.clone(),
+        (Inline::Bold(inner), Syntax::WhatsApp) => wrap("*", inner),
+        (Inline::Bold(inner), _) => wrap("**", inner),
+        (Inline::Italic(inner), Syntax::WhatsApp) => wrap("_", inner),
+        (Inline::Italic(inner), _) => wrap("*", inner),
+        (Inline::Strike(inner), Syntax::WhatsApp) => wrap("~", inner),
+        (Inline::Strike(inner), _) => wrap("~~", inner),
+        (Inline::Code(code), Syntax::WhatsApp) => format!("```{code}```"),
+        (Inline::Code(code), Syntax::Discord) => format!("`{code}`"),
+        (Inline::Code(code), Syntax::Plain) => code.clone(),
+        (Inline::Link { label, url }, Syntax::Discord) => {
+            format!("[{}]({url})", render_spans(label, syntax))
+        }
+        (Inline::Link { label, url }, _) => {
+            let label = render_spans(label, syntax);
+            if label == *url {
+                label
+            } else {
+                format!("{label} ({url})")
+            }
+        }
+    }
+}
+
+fn render_html(doc: &[Line]) -> String {
+    let mut out = String::new();
+    let mut paragraph: Vec<String> = Vec::new();
+    let mut list: Option<(&str, Vec<String>)> = None;
+
+    let flush_paragraph = |out: &mut String, paragraph: &mut Vec<String>| {
+        if !paragraph.is_empty() {
+            out.push_str(&format!("<p>{}</p>", paragraph.join("<br>")));
+            paragraph.clear();
+        }
+    };
+    let flush_list = |out: &mut String, list: &mut Option<(&str, Vec<String>)>| {
+        if let Some((tag, items)) = list.take() {
+            let items: String = items.iter().map(|i| format!("<li>{i}</li>")).collect();
+            out.push_str(&format!("<{tag}>{items}</{tag}>"));
+        }
+    };
+
+    for line in doc {
+        match line {
+            Line::Blank => {
+                flush_paragraph(&mut out, &mut paragraph);
+                flush_list(&mut out, &mut list);
+            }
+            Line::Text(spans) => {
+                flush_list(&mut out, &mut list);
+                paragraph.push(html_spans(spans));
+            }
+            Line::Heading(spans) => {
+                flush_paragraph(&mut out, &mut paragraph);
+                flush_list(&mut out, &mut list);
+                out.push_str(&format!("<p><strong>{}</strong></p>", html_spans(spans)));
+            }
+            Line::Bullet(spans) | Line::Numbered(_, spans) => {
+                flush_paragraph(&mut out, &mut paragraph);
+                let tag = if matches!(line, Line::Bullet(_)) {
+                    "ul"
+                } else {
+                    "ol"
+                };
+                if list.as_ref().is_some_and(|(t, _)| *t != tag) {
+                    flush_list(&mut out, &mut list);
+                }
+                list.get_or_insert((tag, Vec::new()))
+                    .1
+                    .push(html_spans(spans));
+            }
+        }
+    }
+    flush_paragraph(&mut out, &mut paragraph);
+    flush_list(&mut out, &mut list);
+    out
+}
+
+fn html_spans(spans: &[Inline]) -> String {
+    spans
+        .iter()
+        .map(|span| match span {
+            Inline::Text(t) => html_escape(t),
+            Inline::Bold(inner) => format!("<strong>{}</strong>", html_spans(inner)),
+            Inline::Italic(inner) => format!("<em>{}</em>", html_spans(inner)),
+            Inline::Strike(inner) => format!("<s>{}</s>", html_spans(inner)),
+            Inline::Code(code) => format!("<code>{}</code>", html_escape(code)),
+            Inline::Link { label, url } if is_web_url(url) => format!(
+                r#"<a href="{}">{}</a>"#,
+                html_escape(url),
+                html_spans(label)
+            ),
+            Inline::Link { label, url } => {
+                format!("{} ({})", html_spans(label), html_escape(url))
+            }
+        })
+        .collect()
+}
+
+/// Only http(s) and mailto links become anchors in the HTML part.
+fn is_web_url(url: &str) -> bool {
+    let lower = url.to_ascii_lowercase();
+    lower.starts_with("https://") || lower.starts_with("http://") || lower.starts_with("mailto:")
+}
+
+fn collapse_blank_lines(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut blank_run = 0;
+    for line in text.trim().lines() {
+        if line.trim().is_empty() {
+            blank_run += 1;
+            if blank_run > 1 {
+                continue;
+            }
+        } else {
+            blank_run = 0;
+        }
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(line);
+    }
+    out
+}
+
+// ============================================================================
+// Splitting
+// ============================================================================
+
+/// Split `text` into chunks of at most `limit` characters. Breaks on
+/// paragraphs first, then lines, then sentences, then words; a single
+/// word longer than the limit is hard-cut.
+pub fn split(text: &str, limit: usize) -> Vec<String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Vec::new();
+    }
+    split_at_level(text, limit, 0)
+}
+
+fn split_at_level(text: &str, limit: usize, level: usize) -> Vec<String> {
+    if text.chars().count() <= limit {
+        return vec![text.to_string()];
+    }
+    let (parts, sep): (Vec<String>, &str) = match level {
+        0 => (text.split("\n\n").map(str::to_string).collect(), "\n\n"),
+        1 => (text.lines().map(str::to_string).collect(), "\n"),
+        2 => (sentences(text), " "),
+        3 => (text.split_whitespace().map(str::to_string).collect(), " "),
+        _ => return hard_cut(text, limit),
+    };
+
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for part in parts {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        if part.chars().count() > limit {
+            if !current.is_empty() {
+                chunks.push(std::mem::take(&mut current));
+            }
+            chunks.extend(split_at_level(part, limit, level + 1));
+            continue;
+        }
+        let joined = current.chars().count() + sep.len() + part.chars().count();
+        if current.is_empty() {
+            current = part.to_string();
+        } else if joined > limit {
+            chunks.push(std::mem::replace(&mut current, part.to_string()));
+        } else {
+            current.push_str(sep);
+            current.push_str(part);
+        }
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Split after `.`, `!`, `?` or the Devanagari danda when followed by
+/// whitespace. Terminators stay with their sentence.
+fn sentences(text: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut current = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        current.push(c);
+        let ends = matches!(c, '.' | '!' | '?' | '।');
+        if ends && chars.peek().is_some_and(|n| n.is_whitespace()) {
+            out.push(std::mem::take(&mut current).trim().to_string());
+        }
+    }
+    if !current.trim().is_empty() {
+        out.push(current.trim().to_string());
+    }
+    out
+}
+
+fn hard_cut(text: &str, limit: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    chars
+        .chunks(limit.max(1))
+        .map(|c| c.iter().collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "## Opening hours\n\nWe're open **Mon–Sat**, _10am to 7pm_.\n- Call `+91 98765 43210`\n- Or see [our site](https://example.com)\n\nPrices ~~₹500~~ now ₹400.";
+
+    #[test]
+    fn whatsapp_uses_native_markers() {
+        let out = format_reply(&Channel::WhatsApp, Body::Markdown(SAMPLE));
+        assert_eq!(out.chunks.len(), 1);
+        assert_eq!(
+            out.chunks[0],
+            "*Opening hours*\n\nWe're open *Mon–Sat*, _10am to 7pm_.\n- Call ```+91 98765 43210```\n- Or see our site (https://example.com)\n\nPrices ~₹500~ now ₹400."
+        );
+        assert!(out.html.is_none());
+    }
+
+    #[test]
+    fn instagram_strips_markup() {
+        let out = format_reply(&Channel::Instagram, Body::Markdown(SAMPLE));
+        assert_eq!(
+            out.chunks[0],
+            "Opening hours\n\nWe're open Mon–Sat, 10am to 7pm.\n• Call +91 98765 43210\n• Or see our site (https://example.com)\n\nPrices ₹500 now ₹400."
+        );
+    }
+
+    #[test]
+    fn discord_keeps_markdown() {
+        let out = format_reply(&Channel::Discord, Body::Markdown(SAMPLE));
+        assert_eq!(
+            out.chunks[0],
+            "**Opening hours**\n\nWe're open **Mon–Sat**, *10am to 7pm*.\n- Call `+91 98765 43210`\n- Or see [our site](https://example.com)\n\nPrices ~~₹500~~ now ₹400."
+        );
+    }
+
+    #[test]
+    fn email_gets_plain_and_html_parts() {
+        let out = format_reply(&Channel::Email, Body::Markdown(SAMPLE));
+        assert_eq!(out.chunks.len(), 1);
+        assert!(out.chunks[0].starts_with("Opening hours\n\nWe're open Mon–Sat"));
+        assert_eq!(
+            out.html.as_deref(),
+            Some(
+                "<p><strong>Opening hours</strong></p><p>We&#x27;re open <strong>Mon–Sat</strong>, <em>10am to 7pm</em>.</p><ul><li>Call <code>+91 98765 43210</code></li><li>Or see <a href=\"https://example.com\">our site</a></li></ul><p>Prices <s>₹500</s> now ₹400.</p>"
+            )
+        );
+    }
+
+    #[test]
+    fn email_html_escapes_and_refuses_script_links() {
+        let out = format_reply(
+            &Channel::Email,
+            Body::Markdown("<b>hi</b> [click](javascript:alert(1)) & bye"),
+        );
+        let html = out.html.unwrap();
+        assert!(html.contains("&lt;b&gt;hi&lt;/b&gt;"));
+        assert!(!html.contains("<a "));
+        assert!(html.contains("&amp; bye"));
+    }
+
+    #[test]
+    fn verbatim_text_keeps_whatsapp_syntax() {
+        let canned = "*Diwali sale* on now: _20% off_ all bouquets.\n- Order by 5pm";
+        let out = format_reply(&Channel::WhatsApp, Body::Verbatim(canned));
+        assert_eq!(out.chunks, vec![canned.to_string()]);
+        // Markdown would have read the single stars as italics.
+        assert_ne!(
+            format_reply(&Channel::WhatsApp, Body::Markdown(canned)).chunks[0],
+            canned
+        );
+
+        let out = format_reply(&Channel::Email, Body::Verbatim(canned));
+        assert_eq!(out.chunks, vec![canned.to_string()]);
+        assert!(out.html.is_none());
+        let long = "Hello there. ".repeat(200);
+        assert!(
+            format_reply(&Channel::Instagram, Body::Verbatim(&long))
+                .chunks
+                .len()
+                > 1
+        );
+    }
+
+    #[test]
+    fn stray_markers_stay_literal() {
+        let text = "Use order_id 5*3 = 15, or email a_b@example.com. 2 * 3 works too";
+        let out = format_reply(&Channel::WhatsApp, Body::Markdown(text));
+        assert_eq!(out.chunks[0], text);
+    }
+
+    #[test]
+    fn nested_emphasis_renders_per_channel() {
+        let text = "**bold _and italic_ text**";
+        assert_eq!(
+            format_reply(&Channel::WhatsApp, Body::Markdown(text)).chunks[0],
+            "*bold _and italic_ text*"
+        );
+        assert_eq!(
+            format_reply(&Channel::Discord, Body::Markdown(text)).chunks[0],
+            "**bold *and italic* text**"
+        );
+    }
+
+    #[test]
+    fn long_replies_split_on_sentences_within_limit() {
+        let sentence = "This sentence is exactly fifty characters long ok. ";
+        let text = sentence.repeat(30);
+        let out = format_reply(&Channel::Instagram, Body::Markdown(&text));
+        assert!(out.chunks.len() >= 2);
+        for chunk in &out.chunks {
+            assert!(chunk.chars().count() <= INSTAGRAM_LIMIT);
+            assert!(chunk.ends_with("ok."), "chunk split mid-sentence: {chunk}");
+        }
+        assert_eq!(out.chunks.join(" "), text.trim());
+
+        let out = format_reply(&Channel::Discord, Body::Markdown(&text));
+        assert!(out
+            .chunks
+            .iter()
+            .all(|c| c.chars().count() <= DISCORD_LIMIT));
+        assert!(out.chunks.len() < 3);
+    }
+
+    #[test]
+    fn paragraphs_are_preferred_split_points() {
+        let para = "word ".repeat(150);
+        let text = format!("{}\n\n{}", para.trim(), para.trim());
+        let chunks = split(&text, 1000);
+        assert_eq!(
+            chunks,
+            vec![para.trim().to_string(), para.trim().to_string()]
+        );
+    }
+
+    #[test]
+    fn unbreakable_text_is_hard_cut() {
+        let text = "x".repeat(2500);
+        let chunks = split(&text, 1000);
+        assert_eq!(
+            chunks.iter().map(|c| c.len()).collect::<Vec<_>>(),
+            vec![1000, 1000, 500]
+        );
+    }
+
+    #[test]
+    fn short_replies_are_unchanged() {
+        let out = format_reply(
+            &Channel::WhatsApp,
+            Body::Markdown("Thanks! We'll call you soon."),
+        );
+        assert_eq!(out.chunks, vec!["Thanks! We'll call you soon.".to_string()]);
+        assert!(split("   ", 100).is_empty());
+    }
+}
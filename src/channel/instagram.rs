@@ -37,6 +37,7 @@ pub fn parse_inbound(
             "instagram_account_id": account.id,
             "message_mid": dm.mid,
         }),
+        contact_id: None,
     })
 }
 
@@ -98,6 +99,7 @@ mod tests {
             page_id: "page-123".into(),
             auto_reply: ReplyConfig::default(),
             enabled: true,
+            brand_id: None,
             created_at: String::new(),
             updated_at: String::new(),
         }
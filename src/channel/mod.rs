@@ -1,25 +1,60 @@
 pub mod discord;
 pub mod email;
+pub mod format;
 pub mod instagram;
 pub mod whatsapp;
 
 use worker::*;
 
+use crate::consent;
 use crate::types::Channel;
 
-/// Dispatch a reply to the correct channel adapter.
+pub use format::Body;
+
+/// Send a reply unless `to` opted out (`consent::is_suppressed`). Every
+/// reply to a customer goes through here; a suppressed recipient is an
+/// error, so callers log it like any other failed send.
 pub async fn send_reply(
+    channel: &Channel,
+    env: &Env,
+    tenant_id: &str,
+    metadata: &serde_json::Value,
+    to: &str,
+    body: Body<'_>,
+    subject: Option<&str>,
+) -> Result<()> {
+    let db = env.d1("DB")?;
+    if consent::is_suppressed(&db, tenant_id, channel, to).await? {
+        return Err(Error::from("Recipient has opted out of messages"));
+    }
+    deliver(channel, env, metadata, to, body, subject).await
+}
+
+/// Dispatch a reply to the correct channel adapter. `body` is rendered for
+/// the channel first (see `format::Body`), and chat channels get one
+/// message per chunk, in order.
+///
+/// Skips the suppression check: only for opt-out confirmations. Everything
+/// else uses `send_reply`.
+pub async fn deliver(
     channel: &Channel,
     env: &Env,
     metadata: &serde_json::Value,
     to: &str,
-    body: &str,
+    body: Body<'_>,
     subject: Option<&str>,
 ) -> Result<()> {
-    match channel {
-        Channel::WhatsApp => whatsapp::send_reply(env, metadata, to, body).await,
-        Channel::Instagram => instagram::send_reply(env, metadata, to, body).await,
-        Channel::Email => email::send_reply(env, metadata, to, body, subject).await,
-        Channel::Discord => discord::send_reply(env, metadata, to, body).await,
+    let formatted = format::format_reply(channel, body);
+    for chunk in &formatted.chunks {
+        match channel {
+            Channel::WhatsApp => whatsapp::send_reply(env, metadata, to, chunk).await?,
+            Channel::Instagram => instagram::send_reply(env, metadata, to, chunk).await?,
+            Channel::Email => {
+                let html = formatted.html.as_deref();
+                email::send_reply(env, metadata, to, chunk, html, subject).await?
+            }
+            Channel::Discord => discord::send_reply(env, metadata, to, chunk).await?,
+        }
     }
+    Ok(())
 }
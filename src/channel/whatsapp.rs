@@ -35,6 +35,7 @@ pub fn parse_inbound(change: &WebhookChange, account: &WhatsAppAccount) -> Vec<I
                     "whatsapp_account_id": account.id,
                     "message_id": msg.id,
                 }),
+                contact_id: None,
             })
         })
         .collect()
@@ -72,6 +73,7 @@ mod tests {
             phone_number: "+1234567890".into(),
             phone_number_id: "phone-123".into(),
             auto_reply: ReplyConfig::default(),
+            brand_id: None,
             created_at: String::new(),
             updated_at: String::new(),
         }
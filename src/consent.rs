@@ -0,0 +1,309 @@
+//! Opt-out handling: keyword detection, the per-tenant suppression list and
+//! the consent audit trail.
+//!
+//! Inbound messages that are an opt-out / opt-in keyword
+//! (`ConsentConfig`, per language) change the sender's consent and get a
+//! confirmation in the matching language, before any buffering or AI.
+//! Every outbound send goes through `is_suppressed`: `channel::send_reply`
+//! refuses suppressed recipients and lead forms skip them. A suppression
+//! covers all of the contact's identities, so opting out on WhatsApp also
+//! stops email replies to the same person.
+
+use worker::*;
+
+use crate::channel;
+use crate::contacts::normalize_identity;
+use crate::helpers::generate_id;
+use crate::inbox;
+use crate::storage::{get_consent_config, set_message_action};
+use crate::types::{Channel, ConsentChange, ConsentSource, InboundMessage, MessageAction};
+
+/// Audit events shown on the consent page.
+pub const CONSENT_EVENTS_SHOWN: u32 = 100;
+
+/// One row of the `suppressions` table.
+#[derive(Debug, Clone)]
+pub struct Suppression {
+    pub channel: Channel,
+    pub identity: String,
+    pub contact_id: Option<String>,
+    pub source: ConsentSource,
+    pub created_at: String,
+}
+
+/// One row of the `consent_events` table.
+#[derive(Debug, Clone)]
+pub struct ConsentEvent {
+    pub channel: Channel,
+    pub identity: String,
+    pub change: ConsentChange,
+    pub source: ConsentSource,
+    pub detail: Option<String>,
+    pub created_at: String,
+}
+
+/// Whether the tenant may not message `identity` on `channel`: it, or any
+/// identity of the same contact, opted out.
+pub async fn is_suppressed(
+    db: &D1Database,
+    tenant_id: &str,
+    channel: &Channel,
+    identity: &str,
+) -> Result<bool> {
+    let identity = normalize_identity(channel, identity);
+    let row = db
+        .prepare(
+            "SELECT 1 AS hit FROM suppressions s
+             WHERE s.tenant_id = ?1 AND (
+                 (s.channel = ?2 AND s.identity = ?3)
+                 OR s.contact_id IN (
+                     SELECT contact_id FROM contact_identities
+                     WHERE tenant_id = ?1 AND channel = ?2 AND identity = ?3
+                 )
+             ) LIMIT 1",
+        )
+        .bind(&[tenant_id.into(), channel.as_str().into(), identity.into()])?
+        .first::<serde_json::Value>(None)
+        .await?;
+    Ok(row.is_some())
+}
+
+/// Whether any of the contact's identities opted out.
+pub async fn contact_suppressed(
+    db: &D1Database,
+    tenant_id: &str,
+    contact_id: &str,
+) -> Result<bool> {
+    let row = db
+        .prepare(
+            "SELECT 1 AS hit FROM suppressions s
+             WHERE s.tenant_id = ?1 AND (s.contact_id = ?2 OR EXISTS (
+                 SELECT 1 FROM contact_identities i
+                 WHERE i.contact_id = ?2 AND i.channel = s.channel AND i.identity = s.identity
+             )) LIMIT 1",
+        )
+        .bind(&[tenant_id.into(), contact_id.into()])?
+        .first::<serde_json::Value>(None)
+        .await?;
+    Ok(row.is_some())
+}
+
+/// Apply a consent change and append it to the audit trail. Opting out
+/// suppresses the identity; opting in clears it and every other
+/// suppression for the same contact.
+pub async fn record_change(
+    db: &D1Database,
+    tenant_id: &str,
+    channel: &Channel,
+    identity: &str,
+    change: ConsentChange,
+    source: ConsentSource,
+    detail: Option<&str>,
+) -> Result<()> {
+    let identity = normalize_identity(channel, identity);
+    let contact_id = contact_for(db, tenant_id, channel, &identity).await?;
+    let contact_js = contact_id
+        .clone()
+        .map(wasm_bindgen::JsValue::from)
+        .unwrap_or(wasm_bindgen::JsValue::null());
+
+    match change {
+        ConsentChange::OptOut => {
+            db.prepare(
+                "INSERT INTO suppressions (tenant_id, channel, identity, contact_id, source)
+                 VALUES (?, ?, ?, ?, ?)
+                 ON CONFLICT(tenant_id, channel, identity) DO UPDATE SET
+                   contact_id = excluded.contact_id",
+            )
+            .bind(&[
+                tenant_id.into(),
+                channel.as_str().into(),
+                identity.clone().into(),
+                contact_js.clone(),
+                source.as_str().into(),
+            ])?
+            .run()
+            .await?;
+        }
+        ConsentChange::OptIn => {
+            db.prepare(
+                "DELETE FROM suppressions
+                 WHERE tenant_id = ?1
+                   AND ((channel = ?2 AND identity = ?3) OR (?4 IS NOT NULL AND contact_id = ?4))",
+            )
+            .bind(&[
+                tenant_id.into(),
+                channel.as_str().into(),
+                identity.clone().into(),
+                contact_js.clone(),
+            ])?
+            .run()
+            .await?;
+        }
+    }
+
+    db.prepare(
+        "INSERT INTO consent_events (id, tenant_id, channel, identity, contact_id, change, source, detail)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&[
+        generate_id().into(),
+        tenant_id.into(),
+        channel.as_str().into(),
+        identity.into(),
+        contact_js,
+        change.as_str().into(),
+        source.as_str().into(),
+        detail
+            .map(wasm_bindgen::JsValue::from)
+            .unwrap_or(wasm_bindgen::JsValue::null()),
+    ])?
+    .run()
+    .await?;
+    Ok(())
+}
+
+async fn contact_for(
+    db: &D1Database,
+    tenant_id: &str,
+    channel: &Channel,
+    identity: &str,
+) -> Result<Option<String>> {
+    let row = db
+        .prepare(
+            "SELECT contact_id FROM contact_identities
+             WHERE tenant_id = ? AND channel = ? AND identity = ?",
+        )
+        .bind(&[tenant_id.into(), channel.as_str().into(), identity.into()])?
+        .first::<serde_json::Value>(None)
+        .await?;
+    Ok(row.and_then(|r| {
+        r.get("contact_id")
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+    }))
+}
+
+/// Handle an inbound opt-out / opt-in keyword. Returns `true` when the
+/// message was one, and so needs no other reply.
+pub async fn handle_keywords(
+    env: &Env,
+    kv: &kv::KvStore,
+    db: &D1Database,
+    msg: &InboundMessage,
+) -> Result<bool> {
+    let config = get_consent_config(kv, &msg.tenant_id).await?;
+    let Some((change, set)) = config.classify(&msg.body) else {
+        return Ok(false);
+    };
+    let keyword: String = msg.body.trim().chars().take(100).collect();
+    record_change(
+        db,
+        &msg.tenant_id,
+        &msg.channel,
+        &msg.sender,
+        change,
+        ConsentSource::Keyword,
+        Some(&keyword),
+    )
+    .await?;
+
+    let (action, reply) = match change {
+        ConsentChange::OptOut => (MessageAction::OptOut, set.opt_out_reply.trim()),
+        ConsentChange::OptIn => (MessageAction::OptIn, set.opt_in_reply.trim()),
+    };
+    if let Err(e) = set_message_action(db, &msg.id, action).await {
+        console_log!("Failed to stamp consent action: {:?}", e);
+    }
+    if !reply.is_empty() {
+        // The one send allowed past the suppression list: the confirmation
+        // of the opt-out itself.
+        if let Err(e) = channel::deliver(
+            &msg.channel,
+            env,
+            &msg.raw_metadata,
+            &msg.sender,
+            channel::Body::Verbatim(reply),
+            msg.subject.as_deref(),
+        )
+        .await
+        {
+            console_log!("Consent confirmation failed: {:?}", e);
+        }
+    }
+    inbox::record_reply(
+        env,
+        &msg.tenant_id,
+        &msg.channel,
+        &msg.sender,
+        (!reply.is_empty()).then_some(reply),
+        action,
+    )
+    .await;
+    Ok(true)
+}
+
+/// The tenant's suppression list, newest first.
+pub async fn list_suppressions(db: &D1Database, tenant_id: &str) -> Result<Vec<Suppression>> {
+    let rows = db
+        .prepare("SELECT * FROM suppressions WHERE tenant_id = ? ORDER BY created_at DESC")
+        .bind(&[tenant_id.into()])?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let s = |k: &str| row.get(k).and_then(|v| v.as_str()).map(str::to_string);
+            Suppression {
+                channel: s("channel")
+                    .as_deref()
+                    .and_then(Channel::from_wire)
+                    .unwrap_or(Channel::Email),
+                identity: s("identity").unwrap_or_default(),
+                contact_id: s("contact_id"),
+                source: s("source")
+                    .as_deref()
+                    .and_then(ConsentSource::from_wire)
+                    .unwrap_or(ConsentSource::Keyword),
+                created_at: s("created_at").unwrap_or_default(),
+            }
+        })
+        .collect())
+}
+
+/// Most recent consent changes, newest first.
+pub async fn list_events(db: &D1Database, tenant_id: &str) -> Result<Vec<ConsentEvent>> {
+    let rows = db
+        .prepare(
+            "SELECT * FROM consent_events WHERE tenant_id = ?
+             ORDER BY created_at DESC, rowid DESC LIMIT ?",
+        )
+        .bind(&[
+            tenant_id.into(),
+            wasm_bindgen::JsValue::from(CONSENT_EVENTS_SHOWN as f64),
+        ])?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            let s = |k: &str| row.get(k).and_then(|v| v.as_str()).map(str::to_string);
+            Some(ConsentEvent {
+                channel: s("channel")
+                    .as_deref()
+                    .and_then(Channel::from_wire)
+                    .unwrap_or(Channel::Email),
+                identity: s("identity").unwrap_or_default(),
+                change: ConsentChange::from_wire(&s("change")?)?,
+                source: s("source")
+                    .as_deref()
+                    .and_then(ConsentSource::from_wire)
+                    .unwrap_or(ConsentSource::Keyword),
+                detail: s("detail"),
+                created_at: s("created_at").unwrap_or_default(),
+            })
+        })
+        .collect())
+}
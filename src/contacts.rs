@@ -0,0 +1,394 @@
+//! Cross-channel contact book. Every inbound message upserts the sender's
+//! identity (`contact_identities`: channel + sender string) and resolves to
+//! a `contacts` row that messages, approvals and inbox conversations carry
+//! as `contact_id`. A new identity gets a contact of its own; tenants merge
+//! contacts by hand when one person writes from several channels.
+
+use worker::*;
+
+use crate::helpers::generate_id;
+use crate::types::{Channel, InboundMessage};
+
+/// Contacts listed per page.
+pub const CONTACTS_PAGE_SIZE: u32 = 100;
+
+/// Most tags kept on a contact.
+pub const MAX_TAGS: usize = 20;
+
+/// Longest tag kept, in characters.
+pub const MAX_TAG_LEN: usize = 32;
+
+/// Longest private note kept, in characters.
+pub const MAX_NOTES: usize = 4000;
+
+/// `contacts` columns plus the identity roll-ups `row_to_contact` reads.
+const CONTACT_COLUMNS: &str = "c.*,
+    (SELECT MAX(last_seen_at) FROM contact_identities i WHERE i.contact_id = c.id)
+        AS last_seen_at,
+    (SELECT COALESCE(SUM(message_count), 0) FROM contact_identities i
+     WHERE i.contact_id = c.id) AS message_count,
+    (SELECT identity FROM contact_identities i WHERE i.contact_id = c.id
+     ORDER BY last_seen_at DESC LIMIT 1) AS latest_identity";
+
+/// One row of the `contacts` table, with identity roll-ups for the list.
+#[derive(Debug, Clone)]
+pub struct Contact {
+    pub id: String,
+    pub name: Option<String>,
+    pub tags: Vec<String>,
+    pub notes: String,
+    pub created_at: String,
+    /// Latest `last_seen_at` across the contact's identities.
+    pub last_seen_at: Option<String>,
+    /// Sum of `message_count` across identities.
+    pub message_count: i64,
+    /// Most recently seen identity, to label contacts without a name.
+    pub latest_identity: Option<String>,
+}
+
+/// One row of the `contact_identities` table.
+#[derive(Debug, Clone)]
+pub struct ContactIdentity {
+    pub channel: Channel,
+    pub identity: String,
+    pub display_name: Option<String>,
+    pub message_count: i64,
+    pub first_seen_at: String,
+    pub last_seen_at: String,
+}
+
+/// The stored form of a sender string. Email addresses compare
+/// case-insensitively and WhatsApp numbers by digits alone (webhooks send
+/// `919876543210`, lead forms may say `+91 98765 43210`); other ids are
+/// kept as the channel sent them.
+pub fn normalize_identity(channel: &Channel, sender: &str) -> String {
+    let sender = sender.trim();
+    match channel {
+        Channel::Email => sender.to_lowercase(),
+        Channel::WhatsApp => sender.chars().filter(char::is_ascii_digit).collect(),
+        _ => sender.to_string(),
+    }
+}
+
+/// Parse a comma-separated tag field: trimmed, lowercased, de-duplicated,
+/// at most `MAX_TAGS` of at most `MAX_TAG_LEN` characters.
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in input.split(',') {
+        let tag: String = tag
+            .trim()
+            .to_lowercase()
+            .chars()
+            .take(MAX_TAG_LEN)
+            .collect();
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+        if tags.len() == MAX_TAGS {
+            break;
+        }
+    }
+    tags
+}
+
+/// Record the message against its sender's identity and return the
+/// contact it belongs to, creating both on first contact.
+pub async fn resolve(db: &D1Database, msg: &InboundMessage) -> Result<String> {
+    let identity = normalize_identity(&msg.channel, &msg.sender);
+    let name = msg
+        .sender_name
+        .clone()
+        .filter(|n| !n.trim().is_empty())
+        .map(wasm_bindgen::JsValue::from)
+        .unwrap_or(wasm_bindgen::JsValue::null());
+    let new_contact_id = generate_id();
+    let row = db
+        .prepare(
+            "INSERT INTO contact_identities (
+                 id, tenant_id, contact_id, channel, identity, display_name, message_count
+             ) VALUES (?, ?, ?, ?, ?, ?, 1)
+             ON CONFLICT(tenant_id, channel, identity) DO UPDATE SET
+               display_name = COALESCE(excluded.display_name, display_name),
+               message_count = message_count + 1,
+               last_seen_at = datetime('now')
+             RETURNING contact_id",
+        )
+        .bind(&[
+            generate_id().into(),
+            msg.tenant_id.clone().into(),
+            new_contact_id.clone().into(),
+            msg.channel.as_str().into(),
+            identity.into(),
+            name.clone(),
+        ])?
+        .first::<serde_json::Value>(None)
+        .await?;
+    let contact_id = row
+        .and_then(|r| {
+            r.get("contact_id")
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        })
+        .ok_or_else(|| Error::from("contact identity upsert returned no id"))?;
+
+    if contact_id == new_contact_id {
+        db.prepare("INSERT INTO contacts (id, tenant_id, name) VALUES (?, ?, ?)")
+            .bind(&[
+                contact_id.clone().into(),
+                msg.tenant_id.clone().into(),
+                name,
+            ])?
+            .run()
+            .await?;
+    } else if msg.sender_name.is_some() {
+        // Fill in a name for contacts first seen without one.
+        db.prepare("UPDATE contacts SET name = ? WHERE id = ? AND (name IS NULL OR name = '')")
+            .bind(&[name, contact_id.clone().into()])?
+            .run()
+            .await?;
+    }
+    Ok(contact_id)
+}
+
+/// Contacts ordered by most recent activity, optionally filtered by a
+/// substring of the name or any identity, or by an exact tag.
+pub async fn list_contacts(
+    db: &D1Database,
+    tenant_id: &str,
+    query: &str,
+    tag: &str,
+) -> Result<Vec<Contact>> {
+    let like = format!("%{}%", query.trim());
+    let tag_like = format!("%\"{}\"%", tag.trim().to_lowercase());
+    let rows = db
+        .prepare(format!(
+            "SELECT {CONTACT_COLUMNS} FROM contacts c
+             WHERE c.tenant_id = ?1
+               AND (?2 = '%%' OR c.name LIKE ?2 OR EXISTS (
+                   SELECT 1 FROM contact_identities i
+                   WHERE i.contact_id = c.id AND (i.identity LIKE ?2 OR i.display_name LIKE ?2)
+               ))
+               AND (?3 = '%\"\"%' OR c.tags LIKE ?3)
+             ORDER BY last_seen_at DESC LIMIT ?4"
+        ))
+        .bind(&[
+            tenant_id.into(),
+            like.into(),
+            tag_like.into(),
+            wasm_bindgen::JsValue::from(CONTACTS_PAGE_SIZE as f64),
+        ])?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    Ok(rows.iter().map(row_to_contact).collect())
+}
+
+pub async fn get_contact(db: &D1Database, tenant_id: &str, id: &str) -> Result<Option<Contact>> {
+    let row = db
+        .prepare(format!(
+            "SELECT {CONTACT_COLUMNS} FROM contacts c WHERE c.id = ? AND c.tenant_id = ?"
+        ))
+        .bind(&[id.into(), tenant_id.into()])?
+        .first::<serde_json::Value>(None)
+        .await?;
+    Ok(row.as_ref().map(row_to_contact))
+}
+
+/// A contact's identities, most recently seen first.
+pub async fn list_identities(db: &D1Database, contact_id: &str) -> Result<Vec<ContactIdentity>> {
+    let rows = db
+        .prepare(
+            "SELECT * FROM contact_identities WHERE contact_id = ?
+             ORDER BY last_seen_at DESC",
+        )
+        .bind(&[contact_id.into()])?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let s = |k: &str| row.get(k).and_then(|v| v.as_str()).map(str::to_string);
+            ContactIdentity {
+                channel: s("channel")
+                    .as_deref()
+                    .and_then(Channel::from_wire)
+                    .unwrap_or(Channel::Email),
+                identity: s("identity").unwrap_or_default(),
+                display_name: s("display_name"),
+                message_count: row
+                    .get("message_count")
+                    .and_then(|v| v.as_i64())
+                    .unwrap_or(0),
+                first_seen_at: s("first_seen_at").unwrap_or_default(),
+                last_seen_at: s("last_seen_at").unwrap_or_default(),
+            }
+        })
+        .collect())
+}
+
+/// Save the tenant-editable fields.
+pub async fn update_contact(
+    db: &D1Database,
+    tenant_id: &str,
+    id: &str,
+    name: &str,
+    tags: &[String],
+    notes: &str,
+) -> Result<()> {
+    let name = name.trim();
+    let name = if name.is_empty() {
+        wasm_bindgen::JsValue::null()
+    } else {
+        wasm_bindgen::JsValue::from(name)
+    };
+    let notes: String = notes.chars().take(MAX_NOTES).collect();
+    db.prepare(
+        "UPDATE contacts SET name = ?, tags = ?, notes = ?, updated_at = datetime('now')
+         WHERE id = ? AND tenant_id = ?",
+    )
+    .bind(&[
+        name,
+        serde_json::to_string(tags)
+            .unwrap_or_else(|_| "[]".to_string())
+            .into(),
+        notes.into(),
+        id.into(),
+        tenant_id.into(),
+    ])?
+    .run()
+    .await?;
+    Ok(())
+}
+
+/// Fold `from` into `into`: identities and every `contact_id` reference
+/// move over, tags are combined, notes appended, and `from` is deleted.
+pub async fn merge(db: &D1Database, tenant_id: &str, into: &Contact, from: &Contact) -> Result<()> {
+    if into.id == from.id {
+        return Err(Error::from("cannot merge a contact into itself"));
+    }
+    let (name, tags, notes) = merged_fields(into, from);
+    update_contact(
+        db,
+        tenant_id,
+        &into.id,
+        name.as_deref().unwrap_or(""),
+        &tags,
+        &notes,
+    )
+    .await?;
+    for table in [
+        "contact_identities",
+        "messages",
+        "conversations",
+        "pending_approvals",
+    ] {
+        db.prepare(format!(
+            "UPDATE {table} SET contact_id = ? WHERE contact_id = ? AND tenant_id = ?"
+        ))
+        .bind(&[
+            into.id.clone().into(),
+            from.id.clone().into(),
+            tenant_id.into(),
+        ])?
+        .run()
+        .await?;
+    }
+    db.prepare("DELETE FROM contacts WHERE id = ? AND tenant_id = ?")
+        .bind(&[from.id.clone().into(), tenant_id.into()])?
+        .run()
+        .await?;
+    Ok(())
+}
+
+/// Name, tags and notes of the merged contact. The surviving contact's
+/// name wins; tags are unioned (still capped); notes are concatenated.
+fn merged_fields(into: &Contact, from: &Contact) -> (Option<String>, Vec<String>, String) {
+    let name = into
+        .name
+        .clone()
+        .filter(|n| !n.is_empty())
+        .or_else(|| from.name.clone());
+    let tags = parse_tags(&[into.tags.join(","), from.tags.join(",")].join(","));
+    let notes = match (into.notes.trim(), from.notes.trim()) {
+        (a, "") => a.to_string(),
+        ("", b) => b.to_string(),
+        (a, b) => format!("{a}\n\n{b}"),
+    };
+    (name, tags, notes)
+}
+
+fn row_to_contact(row: &serde_json::Value) -> Contact {
+    let s = |k: &str| row.get(k).and_then(|v| v.as_str()).map(str::to_string);
+    Contact {
+        id: s("id").unwrap_or_default(),
+        name: s("name").filter(|n| !n.is_empty()),
+        tags: s("tags")
+            .and_then(|t| serde_json::from_str(&t).ok())
+            .unwrap_or_default(),
+        notes: s("notes").unwrap_or_default(),
+        created_at: s("created_at").unwrap_or_default(),
+        last_seen_at: s("last_seen_at"),
+        message_count: row
+            .get("message_count")
+            .and_then(|v| v.as_i64())
+            .unwrap_or(0),
+        latest_identity: s("latest_identity"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contact(name: Option<&str>, tags: &[&str], notes: &str) -> Contact {
+        Contact {
+            id: generate_id(),
+            name: name.map(str::to_string),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            notes: notes.to_string(),
+            created_at: String::new(),
+            last_seen_at: None,
+            message_count: 0,
+            latest_identity: None,
+        }
+    }
+
+    #[test]
+    fn identities_normalize_per_channel() {
+        assert_eq!(
+            normalize_identity(&Channel::Email, " Priya@Example.com "),
+            "priya@example.com"
+        );
+        assert_eq!(normalize_identity(&Channel::Instagram, "AbC123"), "AbC123");
+        assert_eq!(
+            normalize_identity(&Channel::WhatsApp, "+91 98765-43210"),
+            "919876543210"
+        );
+    }
+
+    #[test]
+    fn tags_are_trimmed_lowercased_and_deduplicated() {
+        assert_eq!(
+            parse_tags(" VIP, wholesale,,vip "),
+            vec!["vip", "wholesale"]
+        );
+        let many: Vec<String> = (0..30).map(|i| format!("t{i}")).collect();
+        assert_eq!(parse_tags(&many.join(",")).len(), MAX_TAGS);
+    }
+
+    #[test]
+    fn merge_keeps_surviving_name_and_combines_the_rest() {
+        let into = contact(Some("Priya"), &["vip"], "Prefers mornings");
+        let from = contact(Some("priya.s"), &["vip", "wholesale"], "Asked about bulk");
+        let (name, tags, notes) = merged_fields(&into, &from);
+        assert_eq!(name.as_deref(), Some("Priya"));
+        assert_eq!(tags, vec!["vip", "wholesale"]);
+        assert_eq!(notes, "Prefers mornings\n\nAsked about bulk");
+
+        let unnamed = contact(None, &[], "");
+        let (name, _, notes) = merged_fields(&unnamed, &from);
+        assert_eq!(name.as_deref(), Some("priya.s"));
+        assert_eq!(notes, "Asked about bulk");
+    }
+}
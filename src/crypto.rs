@@ -12,7 +12,21 @@ const TAG_LENGTH: u8 = 128;
 pub async fn encrypt_token(token: &InstagramToken, key: &str) -> Result<String> {
     let plaintext = serde_json::to_string(token)
         .map_err(|e| Error::from(format!("Failed to serialize token: {}", e)))?;
+    encrypt_secret(&plaintext, key).await
+}
+
+/// Decrypt an Instagram token from storage
+pub async fn decrypt_token(encrypted: &str, key: &str) -> Result<InstagramToken> {
+    let plaintext = decrypt_secret(encrypted, key).await?;
+    let token: InstagramToken = serde_json::from_str(&plaintext)
+        .map_err(|e| Error::from(format!("Failed to deserialize token: {}", e)))?;
+
+    Ok(token)
+}
 
+/// Encrypt an arbitrary secret string (e.g. a tenant's tool HMAC secret).
+/// Output is hex(IV || ciphertext), same shape as `encrypt_token`.
+pub async fn encrypt_secret(plaintext: &str, key: &str) -> Result<String> {
     let crypto = get_crypto()?;
     let key_bytes = hex_decode(key)?;
     let crypto_key = import_key(&crypto, &key_bytes).await?;
@@ -30,8 +44,8 @@ pub async fn encrypt_token(token: &InstagramToken, key: &str) -> Result<String>
     Ok(hex_encode(&combined))
 }
 
-/// Decrypt an Instagram token from storage
-pub async fn decrypt_token(encrypted: &str, key: &str) -> Result<InstagramToken> {
+/// Decrypt a string produced by `encrypt_secret`.
+pub async fn decrypt_secret(encrypted: &str, key: &str) -> Result<String> {
     let combined = hex_decode(encrypted)?;
 
     if combined.len() < IV_LENGTH {
@@ -46,11 +60,7 @@ pub async fn decrypt_token(encrypted: &str, key: &str) -> Result<InstagramToken>
     let crypto_key = import_key(&crypto, &key_bytes).await?;
 
     let plaintext = decrypt(&crypto, &crypto_key, iv, ciphertext).await?;
-
-    let token: InstagramToken = serde_json::from_slice(&plaintext)
-        .map_err(|e| Error::from(format!("Failed to deserialize token: {}", e)))?;
-
-    Ok(token)
+    String::from_utf8(plaintext).map_err(|_| Error::from("Decrypted data is not UTF-8"))
 }
 
 // ============================================================================
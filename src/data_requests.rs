@@ -0,0 +1,554 @@
+//! Data subject requests: everything a tenant holds about one of their
+//! customers, exported as JSON or erased.
+//!
+//! A request names a sender identity (a phone number, email address or
+//! handle). When that identity belongs to a contact, the request covers the
+//! contact and all of its identities. `collect` gathers every D1 row and KV
+//! record that references them; `erase` deletes the same set. Both produce
+//! a `Receipt` that goes into the audit log with the subject hashed, so the
+//! log can later prove a request was handled without keeping the identity.
+//!
+//! Suppressions and consent events outlive an erasure: they are the
+//! record that the customer asked not to be contacted, and dropping them
+//! would let the next inbound message opt them back in. They are unlinked
+//! from the erased contact instead.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+use worker::*;
+
+use crate::contacts::{list_identities, normalize_identity};
+use crate::crypto::decrypt_secret;
+use crate::helpers::{generate_id, now_iso, sha256_hex};
+use crate::management::audit::log_action;
+use crate::storage::tenant_listed_ids;
+use crate::types::{Channel, ConversationContext, DataRequestKind, EmailReverseAlias};
+
+/// Receipts shown on the data requests page.
+pub const RECEIPTS_SHOWN: u32 = 50;
+
+/// Tables with `channel` and `contact_id` columns, and the columns holding
+/// a sender identity.
+const CONTACT_TABLES: &[(&str, &[&str])] = &[
+    ("messages", &["sender", "recipient"]),
+    ("pending_approvals", &["sender"]),
+    ("conversations", &["contact"]),
+    ("contact_identities", &["identity"]),
+    ("suppressions", &["identity"]),
+    ("consent_events", &["identity"]),
+];
+
+/// Per-channel tables without a `channel` column.
+const CHANNEL_TABLES: &[(Channel, &str, &[&str])] = &[
+    (
+        Channel::WhatsApp,
+        "lead_form_submissions",
+        &["phone_number"],
+    ),
+    (
+        Channel::WhatsApp,
+        "whatsapp_messages",
+        &["from_number", "to_number"],
+    ),
+    (
+        Channel::Instagram,
+        "instagram_messages",
+        &["sender_id", "recipient_id"],
+    ),
+];
+
+/// Kept through an erasure; see the module docs.
+const RETAINED_TABLES: &[&str] = &["suppressions", "consent_events"];
+
+/// Who a request is about.
+#[derive(Debug, Clone)]
+pub struct Subject {
+    pub channel: Channel,
+    pub identity: String,
+    pub contact_id: Option<String>,
+    /// The identity itself plus every other identity of its contact.
+    pub identities: Vec<(Channel, String)>,
+}
+
+impl Subject {
+    /// Stable hash of the identity the tenant entered, for the audit log.
+    pub fn fingerprint(&self) -> String {
+        subject_fingerprint(&self.channel, &self.identity)
+    }
+}
+
+/// Proof a request was carried out, written to the audit log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Receipt {
+    pub id: String,
+    pub kind: DataRequestKind,
+    pub tenant_id: String,
+    /// `subject_fingerprint` of the identity the tenant entered.
+    pub subject: String,
+    pub identities: usize,
+    /// Records found per table or KV family.
+    pub records: BTreeMap<String, usize>,
+    /// SHA-256 of the records as compact JSON (the export's `records`
+    /// object); for an erasure, of what was deleted.
+    pub digest: String,
+    pub created_at: String,
+}
+
+impl Receipt {
+    pub fn total(&self) -> usize {
+        self.records.values().sum()
+    }
+}
+
+/// Everything stored about a subject, by table or KV family.
+pub type Records = BTreeMap<String, Vec<serde_json::Value>>;
+
+pub fn subject_fingerprint(channel: &Channel, identity: &str) -> String {
+    sha256_hex(&format!(
+        "{}:{}",
+        channel.as_str(),
+        normalize_identity(channel, identity)
+    ))
+}
+
+/// SQL for a column's value in `normalize_identity` form, so stored
+/// senders match however the channel formatted them.
+fn normalized_column(channel: &Channel, column: &str) -> String {
+    match channel {
+        Channel::Email => format!("lower(trim({column}))"),
+        Channel::WhatsApp => ["+", " ", "-", "(", ")", "."]
+            .iter()
+            .fold(column.to_string(), |expr, c| {
+                format!("replace({expr}, '{c}', '')")
+            }),
+        _ => format!("trim({column})"),
+    }
+}
+
+/// `WHERE` clause matching a subject identity in `table`. Binds: ?1 tenant,
+/// ?2 identity, ?3 channel, ?4 contact id (or null).
+fn subject_clause(channel: &Channel, columns: &[&str], by_contact: bool) -> String {
+    let columns: Vec<String> = columns
+        .iter()
+        .map(|c| format!("{} = ?2", normalized_column(channel, c)))
+        .collect();
+    let identity = columns.join(" OR ");
+    if by_contact {
+        format!("tenant_id = ?1 AND ((channel = ?3 AND ({identity})) OR contact_id = ?4)")
+    } else {
+        format!("tenant_id = ?1 AND ({identity})")
+    }
+}
+
+/// Resolve what the tenant typed to a subject, expanding to the contact's
+/// other identities.
+pub async fn resolve_subject(
+    db: &D1Database,
+    tenant_id: &str,
+    channel: Channel,
+    input: &str,
+) -> Result<Subject> {
+    let identity = normalize_identity(&channel, input);
+    let row = db
+        .prepare(
+            "SELECT contact_id FROM contact_identities
+             WHERE tenant_id = ? AND channel = ? AND identity = ?",
+        )
+        .bind(&[
+            tenant_id.into(),
+            channel.as_str().into(),
+            identity.as_str().into(),
+        ])?
+        .first::<serde_json::Value>(None)
+        .await?;
+    let contact_id = row.and_then(|r| {
+        r.get("contact_id")
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+    });
+
+    let mut identities = vec![(channel.clone(), identity.clone())];
+    if let Some(contact_id) = &contact_id {
+        for i in list_identities(db, contact_id).await? {
+            if !identities.contains(&(i.channel.clone(), i.identity.clone())) {
+                identities.push((i.channel, i.identity));
+            }
+        }
+    }
+    Ok(Subject {
+        channel,
+        identity,
+        contact_id,
+        identities,
+    })
+}
+
+fn binds(tenant_id: &str, channel: &Channel, identity: &str, contact_id: &JsValue) -> [JsValue; 4] {
+    [
+        tenant_id.into(),
+        identity.into(),
+        channel.as_str().into(),
+        contact_id.clone(),
+    ]
+}
+
+fn contact_js(subject: &Subject) -> JsValue {
+    subject
+        .contact_id
+        .clone()
+        .map(JsValue::from)
+        .unwrap_or(JsValue::null())
+}
+
+/// Add rows to a table's list, skipping ones another identity already found.
+fn push_rows(records: &mut Records, table: &str, rows: Vec<serde_json::Value>) {
+    let list = records.entry(table.to_string()).or_default();
+    for row in rows {
+        if !list.contains(&row) {
+            list.push(row);
+        }
+    }
+}
+
+/// Every record referencing the subject. Retained message bodies are
+/// decrypted when `key` is set.
+pub async fn collect(
+    kv: &kv::KvStore,
+    db: &D1Database,
+    key: Option<&str>,
+    tenant_id: &str,
+    subject: &Subject,
+) -> Result<Records> {
+    let contact = contact_js(subject);
+    let mut records = Records::new();
+
+    for (channel, identity) in &subject.identities {
+        for (table, columns) in CONTACT_TABLES {
+            let rows = db
+                .prepare(format!(
+                    "SELECT * FROM {table} WHERE {}",
+                    subject_clause(channel, columns, true)
+                ))
+                .bind(&binds(tenant_id, channel, identity, &contact))?
+                .all()
+                .await?
+                .results::<serde_json::Value>()?;
+            push_rows(&mut records, table, rows);
+        }
+        for (table_channel, table, columns) in CHANNEL_TABLES {
+            if table_channel != channel {
+                continue;
+            }
+            let rows = db
+                .prepare(format!(
+                    "SELECT * FROM {table} WHERE {}",
+                    subject_clause(channel, columns, false)
+                ))
+                .bind(&binds(tenant_id, channel, identity, &contact))?
+                .all()
+                .await?
+                .results::<serde_json::Value>()?;
+            push_rows(&mut records, table, rows);
+        }
+    }
+
+    if let Some(contact_id) = &subject.contact_id {
+        let rows = db
+            .prepare("SELECT * FROM contacts WHERE tenant_id = ? AND id = ?")
+            .bind(&[tenant_id.into(), contact_id.as_str().into()])?
+            .all()
+            .await?
+            .results::<serde_json::Value>()?;
+        push_rows(&mut records, "contacts", rows);
+    }
+
+    let conversation_ids: Vec<String> = records
+        .get("conversations")
+        .into_iter()
+        .flatten()
+        .filter_map(|c| c.get("id").and_then(|v| v.as_str()).map(str::to_string))
+        .collect();
+    for conversation_id in &conversation_ids {
+        let rows = db
+            .prepare("SELECT * FROM conversation_messages WHERE conversation_id = ?")
+            .bind(&[conversation_id.as_str().into()])?
+            .all()
+            .await?
+            .results::<serde_json::Value>()?;
+        push_rows(&mut records, "conversation_messages", rows);
+    }
+    if let Some(key) = key {
+        decrypt_field(&mut records, "conversations", "reply_metadata", key).await;
+        decrypt_field(&mut records, "conversation_messages", "body", key).await;
+    }
+
+    let matches = |channel: &Channel, sender: &str| {
+        subject
+            .identities
+            .contains(&(channel.clone(), normalize_identity(channel, sender)))
+    };
+    let contexts = listed_kv::<ConversationContext>(kv, tenant_id, "conv:").await?;
+    let contexts = contexts
+        .into_iter()
+        .filter(|(_, ctx)| {
+            ctx.tenant_id == tenant_id && matches(&ctx.origin_channel, &ctx.origin_sender)
+        })
+        .map(|(key, ctx)| serde_json::json!({ "key": key, "value": ctx }))
+        .collect();
+    push_rows(&mut records, "conversation_contexts", contexts);
+
+    let aliases = listed_kv::<EmailReverseAlias>(kv, tenant_id, "email_reverse:").await?;
+    let aliases = aliases
+        .into_iter()
+        .filter(|(_, alias)| {
+            alias.tenant_id == tenant_id && matches(&Channel::Email, &alias.original_sender)
+        })
+        .map(|(key, alias)| serde_json::json!({ "key": key, "value": alias }))
+        .collect();
+    push_rows(&mut records, "email_reverse_aliases", aliases);
+
+    records.retain(|_, rows| !rows.is_empty());
+    Ok(records)
+}
+
+async fn decrypt_field(records: &mut Records, table: &str, field: &str, key: &str) {
+    for row in records.get_mut(table).into_iter().flatten() {
+        let Some(cipher) = row.get(field).and_then(|v| v.as_str()) else {
+            continue;
+        };
+        match decrypt_secret(cipher, key).await {
+            Ok(plain) => row[field] = serde_json::Value::String(plain),
+            Err(e) => console_log!("Data request decrypt failed: {e:?}"),
+        }
+    }
+}
+
+/// The tenant's `{prefix}{id}` values that parse as `T`, with their keys.
+/// These records are keyed by id alone; `tenant:{tenant_id}:{prefix}{id}`
+/// lists which are the tenant's.
+async fn listed_kv<T: serde::de::DeserializeOwned>(
+    kv: &kv::KvStore,
+    tenant_id: &str,
+    prefix: &str,
+) -> Result<Vec<(String, T)>> {
+    let mut found = Vec::new();
+    for id in tenant_listed_ids(kv, tenant_id, prefix).await? {
+        let key = format!("{prefix}{id}");
+        if let Some(value) = kv
+            .get(&key)
+            .json::<T>()
+            .await
+            .map_err(|e| Error::from(e.to_string()))?
+        {
+            found.push((key, value));
+        }
+    }
+    Ok(found)
+}
+
+/// Delete everything `collect` found, apart from `RETAINED_TABLES`.
+pub async fn erase(
+    kv: &kv::KvStore,
+    db: &D1Database,
+    tenant_id: &str,
+    subject: &Subject,
+    records: &Records,
+) -> Result<()> {
+    let contact = contact_js(subject);
+
+    for conversation in records.get("conversations").into_iter().flatten() {
+        if let Some(id) = conversation.get("id").and_then(|v| v.as_str()) {
+            db.prepare(
+                "DELETE FROM conversation_messages WHERE tenant_id = ? AND conversation_id = ?",
+            )
+            .bind(&[tenant_id.into(), id.into()])?
+            .run()
+            .await?;
+        }
+    }
+
+    for (channel, identity) in &subject.identities {
+        for (table, columns) in CONTACT_TABLES {
+            if RETAINED_TABLES.contains(table) {
+                continue;
+            }
+            db.prepare(format!(
+                "DELETE FROM {table} WHERE {}",
+                subject_clause(channel, columns, true)
+            ))
+            .bind(&binds(tenant_id, channel, identity, &contact))?
+            .run()
+            .await?;
+        }
+        for (table_channel, table, columns) in CHANNEL_TABLES {
+            if table_channel != channel {
+                continue;
+            }
+            db.prepare(format!(
+                "DELETE FROM {table} WHERE {}",
+                subject_clause(channel, columns, false)
+            ))
+            .bind(&binds(tenant_id, channel, identity, &contact))?
+            .run()
+            .await?;
+        }
+    }
+
+    if let Some(contact_id) = &subject.contact_id {
+        db.prepare("DELETE FROM contacts WHERE tenant_id = ? AND id = ?")
+            .bind(&[tenant_id.into(), contact_id.as_str().into()])?
+            .run()
+            .await?;
+        for table in RETAINED_TABLES {
+            db.prepare(format!(
+                "UPDATE {table} SET contact_id = NULL WHERE tenant_id = ? AND contact_id = ?"
+            ))
+            .bind(&[tenant_id.into(), contact_id.as_str().into()])?
+            .run()
+            .await?;
+        }
+    }
+
+    for family in ["conversation_contexts", "email_reverse_aliases"] {
+        for record in records.get(family).into_iter().flatten() {
+            if let Some(key) = record.get("key").and_then(|v| v.as_str()) {
+                kv.delete(key).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The receipt for a request over `records`.
+pub fn receipt(
+    kind: DataRequestKind,
+    tenant_id: &str,
+    subject: &Subject,
+    records: &Records,
+) -> Receipt {
+    Receipt {
+        id: generate_id(),
+        kind,
+        tenant_id: tenant_id.to_string(),
+        subject: subject.fingerprint(),
+        identities: subject.identities.len(),
+        records: records
+            .iter()
+            .map(|(table, rows)| (table.clone(), rows.len()))
+            .collect(),
+        digest: records_digest(records),
+        created_at: now_iso(),
+    }
+}
+
+/// SHA-256 of the records as serialized in the export.
+pub fn records_digest(records: &Records) -> String {
+    sha256_hex(&serde_json::to_string(records).unwrap_or_default())
+}
+
+/// Write the receipt to the audit log.
+pub async fn log_receipt(db: &D1Database, actor_email: &str, receipt: &Receipt) -> Result<()> {
+    let details = serde_json::to_value(receipt)?;
+    log_action(
+        db,
+        actor_email,
+        &format!("data_request_{}", receipt.kind.as_str()),
+        "data_request",
+        Some(&receipt.id),
+        Some(&details),
+    )
+    .await
+}
+
+/// The tenant's most recent receipts, newest first.
+pub async fn list_receipts(db: &D1Database, tenant_id: &str) -> Result<Vec<Receipt>> {
+    let rows = db
+        .prepare(
+            "SELECT details FROM audit_log
+             WHERE resource_type = 'data_request' AND json_extract(details, '$.tenant_id') = ?
+             ORDER BY created_at DESC LIMIT ?",
+        )
+        .bind(&[tenant_id.into(), JsValue::from(RECEIPTS_SHOWN as f64)])?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            let details = row.get("details")?.as_str()?;
+            serde_json::from_str(details).ok()
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clauses_normalize_stored_senders() {
+        assert_eq!(
+            normalized_column(&Channel::Email, "sender"),
+            "lower(trim(sender))"
+        );
+        let phone = normalized_column(&Channel::WhatsApp, "phone_number");
+        assert!(phone.starts_with("replace(replace("));
+        assert!(phone.contains("phone_number, '+', ''"));
+        assert_eq!(
+            subject_clause(&Channel::Instagram, &["sender_id", "recipient_id"], false),
+            "tenant_id = ?1 AND (trim(sender_id) = ?2 OR trim(recipient_id) = ?2)"
+        );
+        assert_eq!(
+            subject_clause(&Channel::Discord, &["sender"], true),
+            "tenant_id = ?1 AND ((channel = ?3 AND (trim(sender) = ?2)) OR contact_id = ?4)"
+        );
+    }
+
+    #[test]
+    fn fingerprint_ignores_formatting() {
+        assert_eq!(
+            subject_fingerprint(&Channel::WhatsApp, "+91 98765-43210"),
+            subject_fingerprint(&Channel::WhatsApp, "919876543210")
+        );
+        assert_eq!(
+            subject_fingerprint(&Channel::Email, "Alice@Example.com "),
+            subject_fingerprint(&Channel::Email, "alice@example.com")
+        );
+        assert_ne!(
+            subject_fingerprint(&Channel::Email, "alice@example.com"),
+            subject_fingerprint(&Channel::Instagram, "alice@example.com")
+        );
+    }
+
+    #[test]
+    fn digest_follows_the_records() {
+        let mut records = Records::new();
+        push_rows(
+            &mut records,
+            "messages",
+            vec![
+                serde_json::json!({"id": "m1"}),
+                serde_json::json!({"id": "m2"}),
+            ],
+        );
+        push_rows(
+            &mut records,
+            "messages",
+            vec![serde_json::json!({"id": "m1"})],
+        );
+        assert_eq!(records["messages"].len(), 2);
+
+        let digest = records_digest(&records);
+        assert_eq!(digest.len(), 64);
+        assert_eq!(digest, records_digest(&records.clone()));
+
+        push_rows(
+            &mut records,
+            "contacts",
+            vec![serde_json::json!({"id": "c1"})],
+        );
+        assert_ne!(digest, records_digest(&records));
+    }
+}
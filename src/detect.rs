@@ -0,0 +1,164 @@
+//! Email and phone detection shared by `pii` (masking inbound text before a
+//! model sees it) and `guardrails` (flagging contacts in drafts), so both
+//! agree on what counts as a contact detail.
+//!
+//! Tuned to miss rather than mangle: prices, order numbers and short digit
+//! runs aren't phone numbers.
+
+/// Phone numbers: 10-15 digits, or 8+ with a leading `+`. Shorter runs
+/// without a `+` are more likely order numbers or prices.
+const MIN_PHONE_DIGITS: usize = 10;
+const MIN_INTL_PHONE_DIGITS: usize = 8;
+const MAX_PHONE_DIGITS: usize = 15;
+/// Punctuation allowed between the digits of one number, and how much of
+/// it in a row: "98765-43210", "+1 (415) 555-0100".
+const SEPARATORS: &[char] = &[' ', '-', '.', '(', ')'];
+const MAX_SEPARATORS: usize = 2;
+
+/// Punctuation stripped from either end of a word before classifying it.
+pub const TRIM: &[char] = &[
+    '(', ')', '[', ']', '<', '>', '"', '\'', ',', '.', ';', ':', '!', '?', '*', '`',
+];
+
+/// Whitespace-separated words of `text` with surrounding punctuation and a
+/// `mailto:` prefix stripped, paired with their byte offset. URLs are left
+/// out.
+pub fn words(text: &str) -> Vec<(usize, &str)> {
+    let mut out = Vec::new();
+    let mut offset = 0;
+    for word in text.split_inclusive(char::is_whitespace) {
+        let start = offset;
+        offset += word.len();
+        let bare = word.trim_end_matches(char::is_whitespace);
+        let trimmed_start = bare.trim_start_matches(TRIM);
+        let lead = bare.len() - trimmed_start.len();
+        let token = trimmed_start.trim_end_matches(TRIM);
+        let (token, skip) = match token.strip_prefix("mailto:") {
+            Some(rest) => (rest, "mailto:".len()),
+            None => (token, 0),
+        };
+        if token.is_empty() || token.contains("://") {
+            continue;
+        }
+        out.push((start + lead + skip, token));
+    }
+    out
+}
+
+/// `local@domain.tld`, one `@`, with a dotted domain.
+pub fn is_email(token: &str) -> bool {
+    let Some((local, domain)) = token.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !domain.contains('@')
+}
+
+/// A run of digits with phone-style separators between them.
+pub struct DigitRun {
+    /// Byte range in the scanned text.
+    pub start: usize,
+    pub end: usize,
+    pub digits: Vec<u8>,
+    /// Starts with `+`.
+    pub plus: bool,
+}
+
+impl DigitRun {
+    pub fn is_phone(&self) -> bool {
+        let n = self.digits.len();
+        n <= MAX_PHONE_DIGITS
+            && (n >= MIN_PHONE_DIGITS || (self.plus && n >= MIN_INTL_PHONE_DIGITS))
+    }
+}
+
+/// Digit runs in `text` that stand alone (not glued to letters on either
+/// side). A run may open with `+` or `(` and always ends on a digit.
+pub fn digit_runs(text: &str) -> Vec<DigitRun> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let digit_at = |k: usize| chars.get(k).is_some_and(|(_, c)| c.is_ascii_digit());
+    let mut runs = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let (start, c) = chars[i];
+        let opens = matches!(c, '+' | '(') && digit_at(i + 1);
+        let boundary = i == 0 || !chars[i - 1].1.is_alphanumeric();
+        if !(c.is_ascii_digit() || opens) || !boundary {
+            i += 1;
+            continue;
+        }
+        let mut j = i + 1;
+        let mut end = i + 1;
+        while j < chars.len() {
+            if chars[j].1.is_ascii_digit() {
+                j += 1;
+                end = j;
+                continue;
+            }
+            let seps = chars[j..]
+                .iter()
+                .take_while(|(_, c)| SEPARATORS.contains(c))
+                .count();
+            if seps == 0 || seps > MAX_SEPARATORS || !digit_at(j + seps) {
+                break;
+            }
+            j += seps;
+        }
+        let followed_by_word = chars.get(end).is_some_and(|(_, n)| n.is_alphanumeric());
+        let end_byte = chars.get(end).map(|(b, _)| *b).unwrap_or(text.len());
+        if !followed_by_word {
+            runs.push(DigitRun {
+                start,
+                end: end_byte,
+                digits: text[start..end_byte]
+                    .chars()
+                    .filter_map(|c| c.to_digit(10).map(|d| d as u8))
+                    .collect(),
+                plus: c == '+',
+            });
+        }
+        i = end.max(i + 1);
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn phones(text: &str) -> Vec<&str> {
+        digit_runs(text)
+            .into_iter()
+            .filter(DigitRun::is_phone)
+            .map(|r| &text[r.start..r.end])
+            .collect()
+    }
+
+    #[test]
+    fn phone_formats_and_non_phones() {
+        assert_eq!(
+            phones("Call +91 98765 43210, +1 (415) 555-0100 or 098765-43210."),
+            vec!["+91 98765 43210", "+1 (415) 555-0100", "098765-43210"]
+        );
+        assert_eq!(phones("(415) 555-0100 x"), vec!["(415) 555-0100"]);
+        assert!(phones("Order 12345678 ships today, total 1,499.00").is_empty());
+        assert!(phones("ref AB9876543210 or 9876543210abc").is_empty());
+    }
+
+    #[test]
+    fn emails_and_words() {
+        let found: Vec<&str> =
+            words("Mail (priya@example.com) or mailto:a@b.co, see https://x.io/@me")
+                .into_iter()
+                .map(|(_, w)| w)
+                .filter(|w| is_email(w))
+                .collect();
+        assert_eq!(found, vec!["priya@example.com", "a@b.co"]);
+        assert!(!is_email("priya@okicici"));
+        assert!(!is_email("@example.com"));
+        assert!(!is_email("a@example."));
+    }
+}
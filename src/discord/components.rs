@@ -8,6 +8,7 @@ use crate::approvals;
 use crate::billing;
 use crate::channel;
 use crate::helpers::generate_id;
+use crate::inbox;
 use crate::storage::*;
 use crate::types::*;
 
@@ -62,7 +63,7 @@ pub async fn handle_modal_submit(interaction: &Interaction, env: &Env) -> Result
         if reply_text.is_empty() {
             return ephemeral("Reply cannot be empty.");
         }
-        return send_relay_reply(ctx_id, reply_text, env).await;
+        return send_relay_reply(ctx_id, reply_text, interaction, env).await;
     }
 
     ephemeral("Unknown modal")
@@ -81,11 +82,23 @@ fn show_reply_modal(ctx_id: &str) -> Result<Response> {
     Response::from_json(&resp)
 }
 
-/// Send a relay reply back through the originating channel.
-async fn send_relay_reply(ctx_id: &str, reply_text: &str, env: &Env) -> Result<Response> {
+/// Send a relay reply back through the originating channel. When the
+/// context backs a queued message (one a human had to answer), the reply
+/// also decides its approval row.
+async fn send_relay_reply(
+    ctx_id: &str,
+    reply_text: &str,
+    interaction: &Interaction,
+    env: &Env,
+) -> Result<Response> {
     let kv = env.kv("KV")?;
     let db = env.d1("DB")?;
 
+    let queued = approvals::get_status(&db, ctx_id).await?;
+    if queued.is_some_and(|status| status != ApprovalStatus::Pending) {
+        return ephemeral("Already handled by another reviewer.");
+    }
+
     let ctx = match get_conversation_context(&kv, ctx_id).await? {
         Some(c) => c,
         None => return ephemeral("Conversation expired or not found."),
@@ -100,9 +113,10 @@ async fn send_relay_reply(ctx_id: &str, reply_text: &str, env: &Env) -> Result<R
     match channel::send_reply(
         &ctx.origin_channel,
         env,
+        &ctx.tenant_id,
         &ctx.reply_metadata,
         &ctx.origin_sender,
-        reply_text,
+        channel::Body::Verbatim(reply_text),
         subject,
     )
     .await
@@ -117,16 +131,45 @@ async fn send_relay_reply(ctx_id: &str, reply_text: &str, env: &Env) -> Result<R
     let _ = save_message(
         &db,
         &generate_id(),
+        &MessageRow {
+            channel: &ctx.origin_channel,
+            direction: MessageDirection::Relay,
+            sender: &ctx.origin_recipient,
+            recipient: &ctx.origin_sender,
+            tenant_id: &ctx.tenant_id,
+            channel_account_id: &ctx.channel_account_id,
+            action_taken: Some(MessageAction::Relay),
+            credits: 0,
+            approval_id: None,
+        },
+    )
+    .await;
+    inbox::record_reply(
+        env,
+        &ctx.tenant_id,
         &ctx.origin_channel,
-        MessageDirection::Relay,
-        &ctx.origin_recipient,
         &ctx.origin_sender,
-        &ctx.tenant_id,
-        &ctx.channel_account_id,
-        Some(MessageAction::Relay),
+        Some(reply_text),
+        MessageAction::Relay,
     )
     .await;
 
+    if queued.is_some() {
+        let decided_by = ApprovalDecider::Discord {
+            user_id: member_user_id(interaction),
+        };
+        if let Err(e) = approvals::save_edited_draft(&db, ctx_id, reply_text).await {
+            console_log!("Failed to save relay reply on approval row: {e:?}");
+        }
+        if let Err(e) =
+            approvals::mark_decided(&db, ctx_id, ApprovalStatus::Approved, &decided_by, true).await
+        {
+            console_log!("Failed to mark approval row decided: {e:?}");
+        }
+        let _ = delete_conversation_context(&kv, ctx_id).await;
+        approvals::notify_change(env, &ctx.tenant_id).await;
+    }
+
     ephemeral(&format!(
         "Reply sent to {} via {}.",
         ctx.origin_sender,
@@ -152,6 +195,7 @@ async fn handle_approve(ctx_id: &str, interaction: &Interaction, env: &Env) -> R
     };
 
     let draft = match &ctx.ai_draft {
+        Some(d) if d.is_empty() => return show_reply_modal(ctx_id),
         Some(d) => d.clone(),
         None => return ephemeral("No draft found."),
     };
@@ -165,9 +209,10 @@ async fn handle_approve(ctx_id: &str, interaction: &Interaction, env: &Env) -> R
     match channel::send_reply(
         &ctx.origin_channel,
         env,
+        &ctx.tenant_id,
         &ctx.reply_metadata,
         &ctx.origin_sender,
-        &draft,
+        channel::Body::Markdown(&draft),
         subject,
     )
     .await
@@ -188,13 +233,26 @@ async fn handle_approve(ctx_id: &str, interaction: &Interaction, env: &Env) -> R
     let _ = save_message(
         &db,
         &generate_id(),
+        &MessageRow {
+            channel: &ctx.origin_channel,
+            direction: MessageDirection::Outbound,
+            sender: &ctx.origin_recipient,
+            recipient: &ctx.origin_sender,
+            tenant_id: &ctx.tenant_id,
+            channel_account_id: &ctx.channel_account_id,
+            action_taken: Some(MessageAction::AiApproved),
+            credits: 0,
+            approval_id: Some(ctx_id),
+        },
+    )
+    .await;
+    inbox::record_reply(
+        env,
+        &ctx.tenant_id,
         &ctx.origin_channel,
-        MessageDirection::Outbound,
-        &ctx.origin_recipient,
         &ctx.origin_sender,
-        &ctx.tenant_id,
-        &ctx.channel_account_id,
-        Some(MessageAction::AiApproved),
+        Some(&draft),
+        MessageAction::AiApproved,
     )
     .await;
 
@@ -233,19 +291,32 @@ async fn handle_reject(ctx_id: &str, interaction: &Interaction, env: &Env) -> Re
     }
 
     if let Some(ctx) = &ctx {
-        if let Err(e) = billing::restore_credit(&db, &ctx.tenant_id).await {
+        if let Err(e) = billing::restore_credit(&db, &ctx.tenant_id, ctx.credits_charged).await {
             console_log!("Failed to restore credit on rejection: {e:?}");
         }
         let _ = save_message(
             &db,
             &generate_id(),
+            &MessageRow {
+                channel: &ctx.origin_channel,
+                direction: MessageDirection::Outbound,
+                sender: &ctx.origin_recipient,
+                recipient: &ctx.origin_sender,
+                tenant_id: &ctx.tenant_id,
+                channel_account_id: &ctx.channel_account_id,
+                action_taken: Some(MessageAction::AiRejected),
+                credits: -ctx.credits_charged,
+                approval_id: Some(ctx_id),
+            },
+        )
+        .await;
+        inbox::record_reply(
+            env,
+            &ctx.tenant_id,
             &ctx.origin_channel,
-            MessageDirection::Outbound,
-            &ctx.origin_recipient,
             &ctx.origin_sender,
-            &ctx.tenant_id,
-            &ctx.channel_account_id,
-            Some(MessageAction::AiRejected),
+            None,
+            MessageAction::AiRejected,
         )
         .await;
     }
@@ -141,6 +141,7 @@ pub async fn handle_event(mut req: Request, env: Env) -> Result<Response> {
             "channel_id": msg.channel_id,
             "message_id": msg.id,
         }),
+        contact_id: None,
     };
 
     if let Err(e) = pipeline::process_inbound(&inbound, &env).await {
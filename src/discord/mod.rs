@@ -54,6 +54,7 @@ pub async fn post_forwarded_message(
         channel_account_id: msg.channel_account_id.clone(),
         reply_metadata: msg.raw_metadata.clone(),
         ai_draft: None,
+        credits_charged: 0,
         created_at: crate::helpers::now_iso(),
     };
 
@@ -152,12 +153,28 @@ pub async fn post_ai_draft(
     };
     let footer = footer_text.map(|text| EmbedFooter { text });
 
+    // No draft means nothing a reviewer could approve as-is: offer the
+    // relay reply modal instead of the Approve button.
+    let (content, description, send_button) = if draft.is_empty() {
+        (
+            "**Needs a human reply**: Reply or reject.",
+            "_No draft. Write the reply yourself._".to_string(),
+            Component::primary_button(format!("reply:{}", ctx.id), "Reply"),
+        )
+    } else {
+        (
+            "**AI Draft Reply**: Approve or reject.",
+            format!("**Draft:**\n{draft}"),
+            Component::success_button(format!("approve:{}", ctx.id), "Approve"),
+        )
+    };
+
     let params = CreateMessage {
-        content: "**AI Draft Reply**: Approve or reject.".into(),
+        content: content.into(),
         embeds: vec![
             Embed {
                 title: Some(format!("Re: {}", inbound_subject.unwrap_or("(message)"))),
-                description: Some(format!("**Draft:**\n{draft}")),
+                description: Some(description),
                 color: Some(0x5865F2),
                 fields: vec![
                     EmbedField {
@@ -181,7 +198,7 @@ pub async fn post_ai_draft(
             },
         ],
         components: vec![ActionRow::new(vec![
-            Component::success_button(format!("approve:{}", ctx.id), "Approve"),
+            send_button,
             Component::danger_button(format!("reject:{}", ctx.id), "Reject"),
         ])],
         ..Default::default()
@@ -191,6 +208,61 @@ pub async fn post_ai_draft(
     Ok(message.id)
 }
 
+/// Post an in-app notification (`notifications`) to `channel_id` as an
+/// embed whose colour follows its severity.
+pub async fn post_notification(
+    env: &Env,
+    channel_id: &str,
+    severity: NotificationSeverity,
+    title: &str,
+    body: &str,
+    url: &str,
+) -> Result<()> {
+    let bot = bot_from_env(env).ok_or_else(|| Error::from("Discord not configured"))?;
+    let color = match severity {
+        NotificationSeverity::Info => 0x99AAB5,
+        NotificationSeverity::Warning => 0xFAA61A,
+        NotificationSeverity::Critical => 0xED4245,
+    };
+    let params = CreateMessage {
+        embeds: vec![Embed {
+            title: Some(title.to_string()),
+            description: Some(format!("{body}\n\n{url}")),
+            color: Some(color),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    bot.create_message(channel_id, params).await?;
+    Ok(())
+}
+
+/// Why the bot can't do its job in a tenant's server.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessProblem {
+    /// The server's channels can't be listed: the bot was removed, or
+    /// lost View Channels.
+    ServerUnreachable,
+    /// The approval channel isn't among the ones the bot can see.
+    ApprovalChannelHidden,
+}
+
+/// Check the bot can still see the tenant's server and approval channel.
+/// `None` when it can, or when the bot isn't configured at all (that's
+/// the operator's problem, not the tenant's).
+pub async fn check_access(env: &Env, cfg: &DiscordConfig) -> Option<AccessProblem> {
+    let bot = bot_from_env(env)?;
+    match bot.list_guild_text_channels(&cfg.guild_id).await {
+        Err(_) => Some(AccessProblem::ServerUnreachable),
+        Ok(channels) => match &cfg.approval_channel_id {
+            Some(id) if !channels.iter().any(|c| &c.id == id) => {
+                Some(AccessProblem::ApprovalChannelHidden)
+            }
+            _ => None,
+        },
+    }
+}
+
 /// Build a clamped preview of an inbound message body for embedding into a
 /// Discord draft post.
 pub fn truncate_inbound_preview(body: &str) -> String {
@@ -1,10 +1,15 @@
-//! Per-tenant Server-Sent Events fan-out for the approvals page.
+//! Per-tenant Server-Sent Events fan-out for the admin pages.
 //!
-//! Each connected `/admin/approvals` browser tab opens an SSE stream that
-//! routes through this Durable Object. When `approvals::enqueue` queues a
-//! new draft, or any surface (Discord button, web button) resolves one,
-//! the caller posts to this DO's `/broadcast` endpoint. The DO writes a
-//! single SSE event to every open writer; clients re-fetch the list.
+//! Each admin browser tab opens an SSE stream for the notification bell,
+//! and `/admin/approvals` and `/admin/inbox` tabs one more for their
+//! lists; all route through this Durable Object. When
+//! `approvals::enqueue` queues a new draft, or any surface (Discord button,
+//! web button) resolves one, the caller posts to this DO's `/broadcast`
+//! endpoint; `inbox::record` does the same with `?event=inbox-changed`
+//! when a retained message lands, and `notifications::notify` with
+//! `?event=notification-changed` for the bell every admin page shows. The
+//! DO writes a single SSE event to every open writer; clients re-fetch
+//! whichever list listens for it.
 //!
 //! Design notes:
 //! - Singleton per tenant: id derived from `tenant_id` via `id_from_name`.
@@ -13,8 +18,9 @@
 //!   `EventSource` reconnects automatically and the new DO instance starts
 //!   with an empty writer set.
 //! - Event payloads are tiny pings ("data: {}\n\n"). The list HTML lives
-//!   in the worker's `/admin/approvals/list` endpoint — the browser fires
-//!   `hx-trigger="sse:approval-changed"` to refetch it. Keeping template
+//!   in the worker's `/admin/approvals/list` and `/admin/inbox/list`
+//!   endpoints — the browser fires `hx-trigger="sse:approval-changed"` (or
+//!   `sse:inbox-changed`) to refetch it. Keeping template
 //!   logic out of the DO means the DO knows nothing about what changed,
 //!   only that *something* changed for this tenant.
 
@@ -24,7 +30,9 @@ use futures::channel::mpsc::{unbounded, UnboundedSender};
 use futures::stream::StreamExt;
 use worker::*;
 
-const SSE_PING: &[u8] = b"event: approval-changed\ndata: {}\n\n";
+/// Event names a broadcast may carry. Anything else falls back to the
+/// first, so an old caller without `?event=` keeps working.
+const EVENTS: [&str; 3] = ["approval-changed", "inbox-changed", "notification-changed"];
 
 /// Cap on simultaneous SSE writers per tenant DO. A normal session has one
 /// or two open tabs; this exists so a misbehaving client can't grow the
@@ -61,7 +69,15 @@ impl DurableObject for ApprovalsDO {
 
         match (req.method(), path) {
             (Method::Get, "/subscribe") => self.handle_subscribe(),
-            (Method::Post, "/broadcast") => self.handle_broadcast(),
+            (Method::Post, "/broadcast") => {
+                let event = url
+                    .query_pairs()
+                    .find(|(k, _)| k == "event")
+                    .and_then(|(_, v)| EVENTS.into_iter().find(|e| *e == v))
+                    .unwrap_or(EVENTS[0]);
+                self.handle_broadcast(event)
+            }
+            (Method::Post, "/close") => self.handle_close(),
             _ => Response::error("Not Found", 404),
         }
     }
@@ -91,13 +107,21 @@ impl ApprovalsDO {
         Ok(resp)
     }
 
-    fn handle_broadcast(&self) -> Result<Response> {
+    fn handle_broadcast(&self, event: &str) -> Result<Response> {
+        let ping = format!("event: {event}\ndata: {{}}\n\n").into_bytes();
         // Iterate, send to each subscriber, drop any that failed (client
         // disconnected — the receiver was dropped, so unbounded_send
         // returns Err). Doing this in one pass with retain_mut keeps the
         // live set tight without a second iteration.
         let mut subs = self.subscribers.borrow_mut();
-        subs.retain_mut(|tx| tx.unbounded_send(SSE_PING.to_vec()).is_ok());
+        subs.retain_mut(|tx| tx.unbounded_send(ping.clone()).is_ok());
         Response::ok("")
     }
+
+    /// End every open stream without telling the browsers to refetch; used
+    /// when the tenant is deleted. Answers with how many were open.
+    fn handle_close(&self) -> Result<Response> {
+        let closed = self.subscribers.borrow_mut().drain(..).count();
+        Response::ok(closed.to_string())
+    }
 }
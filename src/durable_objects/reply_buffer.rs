@@ -3,7 +3,11 @@
 //! after the AI call returns.
 //!
 //! Keyed by `(tenant_id, channel, sender)` — one DO instance per
-//! conversation. Sliding window: each new message resets the alarm.
+//! conversation. Sliding window: each new message resets the alarm, but
+//! never past `max_wait_seconds` after the first buffered message. A full
+//! buffer (`max_buffered`) or a message ending in a question mark flushes
+//! straight away. `/purge` drops the buffer unanswered (tenant deletion);
+//! any other path is a push.
 
 use std::time::Duration;
 
@@ -13,11 +17,37 @@ use worker::*;
 use crate::pipeline;
 use crate::types::*;
 
+/// The buffering knobs from the channel's `ReplyConfig`, sent with each
+/// push so the DO never has to read KV.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BufferSettings {
+    pub wait_seconds: u32,
+    pub max_wait_seconds: u32,
+    pub max_buffered: u32,
+    pub flush_on_question: bool,
+}
+
+impl From<&ReplyConfig> for BufferSettings {
+    fn from(cfg: &ReplyConfig) -> Self {
+        Self {
+            wait_seconds: cfg.wait_seconds,
+            max_wait_seconds: cfg.max_wait_seconds,
+            max_buffered: cfg.max_buffered,
+            flush_on_question: cfg.flush_on_question,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 struct BufferedMsg {
     id: String,
     body: String,
     sender_name: Option<String>,
+    #[serde(default)]
+    has_attachment: bool,
+    /// Epoch milliseconds the DO received the message.
+    #[serde(default)]
+    received_at: u64,
     raw_metadata: serde_json::Value,
 }
 
@@ -29,12 +59,14 @@ struct ConversationCtx {
     recipient: String,
     channel_account_id: String,
     subject: Option<String>,
+    #[serde(default)]
+    contact_id: Option<String>,
 }
 
 #[derive(Deserialize)]
 struct PushPayload {
     msg: InboundMessage,
-    wait_seconds: u32,
+    settings: BufferSettings,
 }
 
 #[durable_object]
@@ -49,6 +81,9 @@ impl DurableObject for ReplyBufferDO {
     }
 
     async fn fetch(&self, mut req: Request) -> Result<Response> {
+        if req.path() == "/purge" {
+            return self.purge().await;
+        }
         let payload: PushPayload = req.json().await?;
         let msg = payload.msg;
 
@@ -59,6 +94,7 @@ impl DurableObject for ReplyBufferDO {
             recipient: msg.recipient.clone(),
             channel_account_id: msg.channel_account_id.clone(),
             subject: msg.subject.clone(),
+            contact_id: msg.contact_id.clone(),
         };
         self.state.storage().put("ctx", &ctx).await?;
 
@@ -70,21 +106,27 @@ impl DurableObject for ReplyBufferDO {
             .ok()
             .flatten()
             .unwrap_or_default();
+        let now = Date::now().as_millis();
         pending.push(BufferedMsg {
             id: msg.id,
             body: msg.body,
             sender_name: msg.sender_name,
+            has_attachment: msg.has_attachment,
+            received_at: now,
             raw_metadata: msg.raw_metadata,
         });
         self.state.storage().put("pending", &pending).await?;
 
-        // (Re)schedule alarm — sliding window.
+        // (Re)schedule alarm — sliding window up to the ceiling, or now.
+        let first_at = pending.first().map(|m| m.received_at).unwrap_or(now);
+        let last_body = pending.last().map(|m| m.body.as_str()).unwrap_or("");
+        let delay = flush_delay_ms(&payload.settings, first_at, now, pending.len(), last_body);
         self.state
             .storage()
-            .set_alarm(Duration::from_secs(payload.wait_seconds.max(1) as u64))
+            .set_alarm(Duration::from_millis(delay))
             .await?;
 
-        Response::ok("queued")
+        Response::ok(if delay == 0 { "flushing" } else { "queued" })
     }
 
     async fn alarm(&self) -> Result<Response> {
@@ -110,27 +152,7 @@ impl DurableObject for ReplyBufferDO {
             return Response::ok("empty");
         }
 
-        let last = pending.last().unwrap().clone();
-        let first = pending.first().unwrap().clone();
-        let combined_body = pending
-            .iter()
-            .map(|m| m.body.clone())
-            .collect::<Vec<_>>()
-            .join("\n");
-
-        let synth = InboundMessage {
-            id: last.id,
-            channel: ctx.channel,
-            sender: ctx.sender,
-            sender_name: first.sender_name,
-            recipient: ctx.recipient,
-            body: combined_body,
-            subject: ctx.subject,
-            has_attachment: false,
-            tenant_id: ctx.tenant_id,
-            channel_account_id: ctx.channel_account_id,
-            raw_metadata: last.raw_metadata,
-        };
+        let synth = synthesize(ctx, &pending);
 
         if let Err(e) = pipeline::process_inbound_immediate(&synth, &self.env).await {
             console_log!("ReplyBufferDO alarm error: {:?}", e);
@@ -139,3 +161,179 @@ impl DurableObject for ReplyBufferDO {
         Response::ok("done")
     }
 }
+
+impl ReplyBufferDO {
+    /// Drop anything buffered without replying, for tenant deletion.
+    /// Answers `purged` when there was something to drop.
+    async fn purge(&self) -> Result<Response> {
+        let storage = self.state.storage();
+        let held = storage
+            .get::<ConversationCtx>("ctx")
+            .await
+            .ok()
+            .flatten()
+            .is_some();
+        storage.delete_alarm().await?;
+        storage.delete_all().await?;
+        Response::ok(if held { "purged" } else { "empty" })
+    }
+}
+
+/// Milliseconds until the buffer should flush after a push. Zero when the
+/// buffer is full or the latest message asks a question; otherwise the
+/// sliding `wait_seconds`, clipped to `max_wait_seconds` after the first
+/// buffered message.
+fn flush_delay_ms(
+    settings: &BufferSettings,
+    first_at: u64,
+    now: u64,
+    buffered: usize,
+    last_body: &str,
+) -> u64 {
+    if settings.max_buffered > 0 && buffered >= settings.max_buffered as usize {
+        return 0;
+    }
+    if settings.flush_on_question && ends_with_question(last_body) {
+        return 0;
+    }
+    let sliding = u64::from(settings.wait_seconds.max(1)) * 1000;
+    if settings.max_wait_seconds == 0 {
+        return sliding;
+    }
+    let deadline = first_at + u64::from(settings.max_wait_seconds) * 1000;
+    sliding.min(deadline.saturating_sub(now))
+}
+
+fn ends_with_question(body: &str) -> bool {
+    body.trim_end().ends_with(['?', '？', '¿'])
+}
+
+/// Fold the buffered messages into one `InboundMessage`. Bodies are kept
+/// as separate paragraphs; the per-message ids, receive times and
+/// attachment flags ride along in `raw_metadata.buffered`.
+fn synthesize(ctx: ConversationCtx, pending: &[BufferedMsg]) -> InboundMessage {
+    let last = pending.last().cloned();
+    let body = pending
+        .iter()
+        .map(|m| m.body.trim())
+        .filter(|b| !b.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let buffered: Vec<serde_json::Value> = pending
+        .iter()
+        .map(|m| {
+            serde_json::json!({
+                "id": m.id,
+                "received_at": m.received_at,
+                "has_attachment": m.has_attachment,
+            })
+        })
+        .collect();
+
+    let mut raw_metadata = last
+        .as_ref()
+        .map(|m| m.raw_metadata.clone())
+        .unwrap_or_else(|| serde_json::json!({}));
+    if let Some(obj) = raw_metadata.as_object_mut() {
+        obj.insert("buffered".into(), serde_json::Value::Array(buffered));
+    }
+
+    InboundMessage {
+        id: last.map(|m| m.id).unwrap_or_default(),
+        channel: ctx.channel,
+        sender: ctx.sender,
+        sender_name: pending.iter().find_map(|m| m.sender_name.clone()),
+        recipient: ctx.recipient,
+        body,
+        subject: ctx.subject,
+        has_attachment: pending.iter().any(|m| m.has_attachment),
+        tenant_id: ctx.tenant_id,
+        channel_account_id: ctx.channel_account_id,
+        raw_metadata,
+        contact_id: ctx.contact_id,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> BufferSettings {
+        BufferSettings {
+            wait_seconds: 5,
+            max_wait_seconds: 20,
+            max_buffered: 3,
+            flush_on_question: true,
+        }
+    }
+
+    fn buffered(id: &str, body: &str, at: u64, attachment: bool) -> BufferedMsg {
+        BufferedMsg {
+            id: id.into(),
+            body: body.into(),
+            sender_name: None,
+            has_attachment: attachment,
+            received_at: at,
+            raw_metadata: serde_json::json!({ "message_id": id }),
+        }
+    }
+
+    #[test]
+    fn sliding_wait_is_capped_by_max_wait() {
+        let s = settings();
+        // Fresh burst: plain sliding window.
+        assert_eq!(flush_delay_ms(&s, 0, 0, 1, "hi"), 5_000);
+        // 17s into a burst only 3s of the ceiling is left.
+        assert_eq!(flush_delay_ms(&s, 0, 17_000, 2, "and"), 3_000);
+        // Past the ceiling: flush now.
+        assert_eq!(flush_delay_ms(&s, 0, 25_000, 2, "more"), 0);
+        // No ceiling: pure sliding window.
+        let open = BufferSettings {
+            max_wait_seconds: 0,
+            ..s
+        };
+        assert_eq!(flush_delay_ms(&open, 0, 600_000, 2, "more"), 5_000);
+    }
+
+    #[test]
+    fn full_buffer_or_question_flushes_early() {
+        let s = settings();
+        assert_eq!(flush_delay_ms(&s, 0, 1_000, 3, "third"), 0);
+        assert_eq!(flush_delay_ms(&s, 0, 1_000, 1, "Are you open today? "), 0);
+        assert_eq!(flush_delay_ms(&s, 0, 1_000, 1, "क्या आप खुले हैं？"), 0);
+        let patient = BufferSettings {
+            flush_on_question: false,
+            max_buffered: 0,
+            ..s
+        };
+        assert_eq!(flush_delay_ms(&patient, 0, 1_000, 40, "Open today?"), 5_000);
+    }
+
+    #[test]
+    fn synthesized_message_keeps_attachments_and_timestamps() {
+        let ctx = ConversationCtx {
+            tenant_id: "t1".into(),
+            channel: Channel::WhatsApp,
+            sender: "user".into(),
+            recipient: "biz".into(),
+            channel_account_id: "acc".into(),
+            subject: None,
+            contact_id: None,
+        };
+        let pending = vec![
+            buffered("m1", "Hi", 1_000, false),
+            buffered("m2", "  ", 2_000, true),
+            buffered("m3", "Is this in stock?", 3_000, false),
+        ];
+        let msg = synthesize(ctx, &pending);
+
+        assert_eq!(msg.id, "m3");
+        assert_eq!(msg.body, "Hi\n\nIs this in stock?");
+        assert!(msg.has_attachment);
+        assert_eq!(msg.raw_metadata["message_id"], "m3");
+        let parts = msg.raw_metadata["buffered"].as_array().unwrap();
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[1]["received_at"], 2_000);
+        assert_eq!(parts[1]["has_attachment"], true);
+    }
+}
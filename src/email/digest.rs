@@ -17,7 +17,8 @@ use crate::approvals::queue_reason_label;
 use crate::billing;
 use crate::email::send::{send_outbound, OutboundEmail};
 use crate::helpers::{generate_id, html_escape};
-use crate::storage::{get_onboarding, get_tenant, save_message};
+use crate::inbox;
+use crate::storage::{get_onboarding, get_tenant, save_message, MessageRow};
 use crate::types::{MessageAction, MessageDirection, PendingApproval};
 
 /// Hard expiry for pending approvals: anything older than 24h is dropped.
@@ -34,7 +35,7 @@ pub async fn sweep(env: &Env) -> Result<()> {
     let db = env.d1("DB")?;
     let kv = env.kv("KV")?;
 
-    expire_pass(&db).await;
+    expire_pass(env, &db).await;
 
     let now = js_sys::Date::new_0();
     let hour = now.get_utc_hours();
@@ -115,24 +116,39 @@ async fn sweep_one(
     Ok(())
 }
 
-async fn expire_pass(db: &D1Database) {
+async fn expire_pass(env: &Env, db: &D1Database) {
     let cutoff = expiry_cutoff_iso();
     match approvals::expire_stale(db, &cutoff).await {
         Ok(rows) => {
             for row in rows {
-                if let Err(e) = billing::restore_credit(db, &row.tenant_id).await {
+                if let Err(e) =
+                    billing::restore_credit(db, &row.tenant_id, row.credits_charged).await
+                {
                     console_log!("Failed to restore credit on expiry: {e:?}");
                 }
                 let _ = save_message(
                     db,
                     &generate_id(),
+                    &MessageRow {
+                        channel: &row.channel,
+                        direction: MessageDirection::Outbound,
+                        sender: &row.sender,
+                        recipient: &row.sender,
+                        tenant_id: &row.tenant_id,
+                        channel_account_id: &row.channel_account_id,
+                        action_taken: Some(MessageAction::AiExpired),
+                        credits: -row.credits_charged,
+                        approval_id: Some(&row.id),
+                    },
+                )
+                .await;
+                inbox::record_reply(
+                    env,
+                    &row.tenant_id,
                     &row.channel,
-                    MessageDirection::Outbound,
                     &row.sender,
-                    &row.sender,
-                    &row.tenant_id,
-                    &row.channel_account_id,
-                    Some(MessageAction::AiExpired),
+                    None,
+                    MessageAction::AiExpired,
                 )
                 .await;
                 console_log!(
@@ -123,6 +123,7 @@ pub async fn handle_email(
             "message_id": message_id,
             "references": references,
         }),
+        contact_id: None,
     };
 
     if let Err(e) = pipeline::process_inbound(&msg, env).await {
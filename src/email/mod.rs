@@ -3,6 +3,7 @@ pub mod forward;
 pub mod handler;
 pub mod mime;
 pub mod send;
+pub mod summary;
 
 const RESERVED_LOCAL_PARTS: &[&str] = &[
     "admin",
@@ -0,0 +1,597 @@
+//! Monday summary emails for tenant owners.
+//!
+//! The 15-minute cron calls `sweep`. From `SEND_HOUR_UTC` on Monday it
+//! picks tenants not yet handled for the week (`weekly_summaries`), a
+//! batch per tick, and emails each owner who hasn't opted out
+//! (`NotificationConfig::weekly_summary`) what happened in the seven days
+//! before: messages per channel, replies and credits, approvals, the rules
+//! that matched most, and connections that need attention. Copy is in the
+//! tenant's locale.
+//!
+//! Counts come from `analytics`, so the summary and the dashboard agree.
+
+use worker::*;
+
+use crate::agency;
+use crate::analytics::{self, Activity, DayActivity};
+use crate::approvals;
+use crate::billing;
+use crate::crypto;
+use crate::discord::{self, AccessProblem};
+use crate::email::send::{send_outbound, OutboundEmail};
+use crate::helpers::html_escape;
+use crate::i18n::{t, t_args};
+use crate::instagram;
+use crate::locale::Locale;
+use crate::storage::{
+    get_discord_config_by_tenant, get_onboarding, get_tenant, get_tenant_billing,
+    list_instagram_accounts,
+};
+use crate::types::{Channel, DeletionStatus};
+
+/// Hour (UTC) on Monday from which summaries go out: 08:30 in India.
+const SEND_HOUR_UTC: i64 = 3;
+
+/// Tenants handled per cron tick.
+const SUMMARIES_PER_RUN: u32 = 20;
+
+/// Rules listed in the summary.
+const TOP_RULES: u32 = 5;
+
+const DAY_MS: f64 = 86_400_000.0;
+
+/// A connection that needs the owner's attention.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    InstagramTokenExpiring {
+        username: String,
+    },
+    InstagramTokenExpired {
+        username: String,
+    },
+    InstagramTokenMissing {
+        username: String,
+    },
+    Discord {
+        server: String,
+        problem: AccessProblem,
+    },
+}
+
+/// Everything one email reports.
+#[derive(Debug)]
+pub struct Summary {
+    pub activity: Activity,
+    pub credits_remaining: i64,
+    pub pending: usize,
+    /// Rule labels and the inbound messages they matched, busiest first.
+    pub top_rules: Vec<(String, u64)>,
+    pub problems: Vec<Problem>,
+}
+
+/// Run one tick of the summary sweep.
+pub async fn sweep(env: &Env) -> Result<()> {
+    let monday = summary_monday(js_sys::Date::now());
+    let db = env.d1("DB")?;
+    let due = due_tenants(&db, &civil_date(monday)).await?;
+    if due.is_empty() {
+        return Ok(());
+    }
+
+    let base_url = env
+        .var("PUBLIC_BASE_URL")
+        .ok()
+        .map(|v| v.to_string())
+        .filter(|s| !s.is_empty());
+    let email_domain = env
+        .var("EMAIL_DOMAIN")
+        .ok()
+        .map(|v| v.to_string())
+        .filter(|s| !s.is_empty());
+    let (Some(base_url), Some(email_domain)) = (base_url, email_domain) else {
+        console_log!("Weekly summary skipped: PUBLIC_BASE_URL and EMAIL_DOMAIN must both be set");
+        return Ok(());
+    };
+    let from_addr = format!("noreply@{email_domain}");
+    let days = week_days(monday);
+    let kv = env.kv("KV")?;
+
+    for tenant_id in due {
+        let status = match send_one(env, &db, &kv, &tenant_id, &days, &base_url, &from_addr).await {
+            Ok(true) => "sent",
+            Ok(false) => "skipped",
+            Err(e) => {
+                console_log!("Weekly summary for tenant {tenant_id} failed: {e:?}");
+                "failed"
+            }
+        };
+        db.prepare(
+            "INSERT INTO weekly_summaries (tenant_id, week, status) VALUES (?1, ?2, ?3)
+             ON CONFLICT (tenant_id) DO UPDATE SET week = ?2, status = ?3, updated_at = datetime('now')",
+        )
+        .bind(&[
+            tenant_id.as_str().into(),
+            civil_date(monday).into(),
+            status.into(),
+        ])?
+        .run()
+        .await?;
+    }
+    Ok(())
+}
+
+/// Tenants not yet handled for the week starting `monday`. Tenants that
+/// signed up since, or are being deleted, are left out.
+async fn due_tenants(db: &D1Database, monday: &str) -> Result<Vec<String>> {
+    let rows = db
+        .prepare(
+            "SELECT t.id FROM tenants t
+             LEFT JOIN weekly_summaries w ON w.tenant_id = t.id
+             WHERE (w.week IS NULL OR w.week < ?1)
+               AND t.created_at < ?1
+               AND NOT EXISTS (SELECT 1 FROM tenant_deletions d
+                               WHERE d.tenant_id = t.id AND d.status = ?2)
+             LIMIT ?3",
+        )
+        .bind(&[
+            monday.into(),
+            DeletionStatus::Running.as_str().into(),
+            (SUMMARIES_PER_RUN as f64).into(),
+        ])?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    Ok(rows
+        .iter()
+        .filter_map(|r| r.get("id")?.as_str().map(str::to_string))
+        .collect())
+}
+
+/// Build and send one tenant's summary. `Ok(false)` when there's nothing
+/// to send: the owner opted out or never finished setting up.
+async fn send_one(
+    env: &Env,
+    db: &D1Database,
+    kv: &kv::KvStore,
+    tenant_id: &str,
+    days: &[String],
+    base_url: &str,
+    from_addr: &str,
+) -> Result<bool> {
+    let Some(tenant) = get_tenant(db, tenant_id).await? else {
+        return Ok(false);
+    };
+    let onboarding = get_onboarding(kv, tenant_id).await?;
+    if !onboarding.completed || !onboarding.notifications.weekly_summary {
+        return Ok(false);
+    }
+
+    let activity = analytics::load_days(db, tenant_id, days).await?;
+    // An agency-paid client's replies draw on the agency's balance.
+    let payer = agency::payer(db, tenant_id).await?;
+    let mut billing = get_tenant_billing(db, &payer).await?;
+    billing::refresh_billing(&mut billing);
+    let pending = approvals::list_pending(db, tenant_id).await?.len();
+    let top_rules = top_rules(db, tenant_id, days).await?;
+    let problems = connection_problems(env, kv, tenant_id).await;
+
+    let summary = Summary {
+        activity,
+        credits_remaining: billing.total_remaining(),
+        pending,
+        top_rules,
+        problems,
+    };
+    let locale = Locale::from_tenant(&tenant.locale, Some(tenant.currency));
+    let email = build_summary_email(&summary, &locale, &tenant.email, base_url, from_addr);
+    send_outbound(env, &email).await?;
+    Ok(true)
+}
+
+/// Rules by the inbound messages they matched over `days`.
+async fn top_rules(
+    db: &D1Database,
+    tenant_id: &str,
+    days: &[String],
+) -> Result<Vec<(String, u64)>> {
+    let (Some(first), Some(last)) = (days.first(), days.last()) else {
+        return Ok(Vec::new());
+    };
+    let rows = db
+        .prepare(
+            "SELECT rule_label, COUNT(*) AS n FROM messages
+             WHERE tenant_id = ?1 AND direction = 'inbound' AND rule_label IS NOT NULL
+               AND created_at >= ?2 AND created_at < date(?3, '+1 day')
+             GROUP BY rule_label ORDER BY n DESC, rule_label LIMIT ?4",
+        )
+        .bind(&[
+            tenant_id.into(),
+            first.as_str().into(),
+            last.as_str().into(),
+            (TOP_RULES as f64).into(),
+        ])?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    Ok(rows
+        .iter()
+        .filter_map(|r| {
+            Some((
+                r.get("rule_label")?.as_str()?.to_string(),
+                r.get("n")?
+                    .as_u64()
+                    .or_else(|| r.get("n")?.as_f64().map(|f| f as u64))?,
+            ))
+        })
+        .collect())
+}
+
+/// Instagram tokens the daily refresh couldn't keep alive, and a Discord
+/// bot that lost access. Checks that can't run are skipped, not reported.
+async fn connection_problems(env: &Env, kv: &kv::KvStore, tenant_id: &str) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    let key = env
+        .secret("ENCRYPTION_KEY")
+        .map(|s| s.to_string())
+        .unwrap_or_default();
+    let accounts = list_instagram_accounts(kv, tenant_id)
+        .await
+        .unwrap_or_default();
+    for account in accounts.into_iter().filter(|a| a.enabled) {
+        let username = account.instagram_username.clone();
+        let stored = kv
+            .get(&format!("instagram_token:{}", account.id))
+            .text()
+            .await
+            .ok()
+            .flatten();
+        let Some(stored) = stored else {
+            problems.push(Problem::InstagramTokenMissing { username });
+            continue;
+        };
+        if key.is_empty() {
+            continue;
+        }
+        match crypto::decrypt_token(&stored, &key).await {
+            Ok(token) if instagram::token_is_expired(&token) => {
+                problems.push(Problem::InstagramTokenExpired { username })
+            }
+            Ok(token) if instagram::token_needs_refresh(&token) => {
+                problems.push(Problem::InstagramTokenExpiring { username })
+            }
+            Ok(_) => {}
+            Err(e) => console_log!("Summary: can't read token for {}: {e:?}", account.id),
+        }
+    }
+
+    if let Ok(Some(cfg)) = get_discord_config_by_tenant(kv, tenant_id).await {
+        if let Some(problem) = discord::check_access(env, &cfg).await {
+            problems.push(Problem::Discord {
+                server: cfg.guild_name.clone().unwrap_or(cfg.guild_id.clone()),
+                problem,
+            });
+        }
+    }
+    problems
+}
+
+/// Days since the epoch of the Monday whose summary is due at `now_ms`:
+/// today if it's Monday past `SEND_HOUR_UTC`, otherwise the one before.
+fn summary_monday(now_ms: f64) -> i64 {
+    let day = (now_ms / DAY_MS).floor() as i64;
+    let hour = ((now_ms - day as f64 * DAY_MS) / 3_600_000.0) as i64;
+    // 1970-01-01 was a Thursday.
+    let since_monday = (day + 3).rem_euclid(7);
+    if since_monday == 0 && hour < SEND_HOUR_UTC {
+        day - 7
+    } else {
+        day - since_monday
+    }
+}
+
+/// The seven days before `monday`, oldest first.
+fn week_days(monday: i64) -> Vec<String> {
+    (monday - 7..monday).map(civil_date).collect()
+}
+
+/// `YYYY-MM-DD` for days since the epoch (proleptic Gregorian).
+fn civil_date(days: i64) -> String {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    format!("{y:04}-{m:02}-{d:02}")
+}
+
+fn problem_text(problem: &Problem, locale: &Locale) -> String {
+    match problem {
+        Problem::InstagramTokenExpiring { username } => t_args(
+            locale,
+            "summary-instagram-expiring",
+            &[("username", username)],
+        ),
+        Problem::InstagramTokenExpired { username } => t_args(
+            locale,
+            "summary-instagram-expired",
+            &[("username", username)],
+        ),
+        Problem::InstagramTokenMissing { username } => t_args(
+            locale,
+            "summary-instagram-missing",
+            &[("username", username)],
+        ),
+        Problem::Discord {
+            server,
+            problem: AccessProblem::ServerUnreachable,
+        } => t_args(locale, "summary-discord-unreachable", &[("server", server)]),
+        Problem::Discord {
+            server,
+            problem: AccessProblem::ApprovalChannelHidden,
+        } => t_args(locale, "summary-discord-hidden", &[("server", server)]),
+    }
+}
+
+/// The summary as a (title, lines) list per section, shared by the HTML
+/// and text bodies.
+fn sections(s: &Summary, locale: &Locale) -> Vec<(String, Vec<String>)> {
+    let a = &s.activity;
+    let count = |f: &dyn Fn(&DayActivity) -> u64| a.total(f).to_string();
+
+    let mut messages: Vec<String> = Channel::ALL
+        .iter()
+        .filter_map(|c| {
+            let n = a.total(|d| d.inbound.get(c.as_str()).copied().unwrap_or(0));
+            (n > 0).then(|| {
+                t_args(
+                    locale,
+                    "summary-channel-line",
+                    &[("channel", c.label()), ("count", &n.to_string())],
+                )
+            })
+        })
+        .collect();
+    if messages.is_empty() {
+        messages.push(t(locale, "summary-messages-none"));
+    }
+    let leads = a.total(|d| d.leads);
+    if leads > 0 {
+        messages.push(t_args(
+            locale,
+            "summary-leads-line",
+            &[("count", &leads.to_string())],
+        ));
+    }
+
+    let replies = vec![
+        t_args(
+            locale,
+            "summary-replies-canned",
+            &[("count", &count(&|d| d.canned))],
+        ),
+        t_args(
+            locale,
+            "summary-replies-ai",
+            &[("count", &count(&|d| d.ai + d.approved))],
+        ),
+        t_args(
+            locale,
+            "summary-replies-human",
+            &[("count", &count(&|d| d.human))],
+        ),
+        t_args(
+            locale,
+            "summary-credits-used",
+            &[("count", &a.credits().to_string())],
+        ),
+        t_args(
+            locale,
+            "summary-credits-remaining",
+            &[("count", &s.credits_remaining.to_string())],
+        ),
+    ];
+
+    let approvals = vec![
+        t_args(
+            locale,
+            "summary-approvals-pending",
+            &[("count", &s.pending.to_string())],
+        ),
+        t_args(
+            locale,
+            "summary-approvals-week",
+            &[
+                ("approved", &count(&|d| d.approved)),
+                ("rejected", &count(&|d| d.rejected)),
+                ("expired", &count(&|d| d.expired)),
+            ],
+        ),
+    ];
+
+    let rules = if s.top_rules.is_empty() {
+        vec![t(locale, "summary-rules-none")]
+    } else {
+        s.top_rules
+            .iter()
+            .map(|(rule, n)| {
+                t_args(
+                    locale,
+                    "summary-rule-line",
+                    &[("rule", rule), ("count", &n.to_string())],
+                )
+            })
+            .collect()
+    };
+
+    let connections = if s.problems.is_empty() {
+        vec![t(locale, "summary-connections-ok")]
+    } else {
+        s.problems.iter().map(|p| problem_text(p, locale)).collect()
+    };
+
+    vec![
+        (t(locale, "summary-section-messages"), messages),
+        (t(locale, "summary-section-replies"), replies),
+        (t(locale, "summary-section-approvals"), approvals),
+        (t(locale, "summary-section-rules"), rules),
+        (t(locale, "summary-section-connections"), connections),
+    ]
+}
+
+fn build_summary_email(
+    s: &Summary,
+    locale: &Locale,
+    recipient: &str,
+    base_url: &str,
+    from_addr: &str,
+) -> OutboundEmail {
+    let first = s
+        .activity
+        .days
+        .first()
+        .map(|d| d.day.as_str())
+        .unwrap_or("");
+    let last = s.activity.days.last().map(|d| d.day.as_str()).unwrap_or("");
+    let handled = s.activity.total(DayActivity::inbound_total).to_string();
+    let subject = t_args(locale, "summary-subject", &[("count", &handled)]);
+    let heading = t_args(
+        locale,
+        "summary-heading",
+        &[("start", first), ("end", last)],
+    );
+    let intro = t(locale, "summary-intro");
+    let cta = t(locale, "summary-cta");
+    let footer = t(locale, "summary-footer");
+    let footer_link = t(locale, "summary-footer-link");
+    let sections = sections(s, locale);
+
+    let html_sections: String = sections
+        .iter()
+        .map(|(title, lines)| {
+            let items: String = lines
+                .iter()
+                .map(|l| format!("<li>{}</li>", html_escape(l)))
+                .collect();
+            format!(
+                r#"<h2 style="font-size:16px;margin:24px 0 8px">{title}</h2>
+<ul style="padding-left:20px;margin:0">{items}</ul>"#,
+                title = html_escape(title),
+            )
+        })
+        .collect();
+    let html = format!(
+        r#"<!doctype html>
+<html><body style="font-family:-apple-system,BlinkMacSystemFont,sans-serif;max-width:640px;margin:0 auto;padding:24px">
+<h1 style="font-size:22px;margin:0 0 8px">{heading}</h1>
+<p style="color:#555">{intro}</p>
+{html_sections}
+<p style="margin-top:24px"><a href="{base_url}/admin" style="display:inline-block;padding:8px 14px;background:#5865F2;color:#fff;border-radius:4px;text-decoration:none">{cta}</a></p>
+<p style="color:#999;font-size:12px;margin-top:32px">{footer} <a href="{base_url}/admin/wizard/notifications">{footer_link}</a>.</p>
+</body></html>"#,
+        heading = html_escape(&heading),
+        intro = html_escape(&intro),
+        cta = html_escape(&cta),
+        footer = html_escape(&footer),
+        footer_link = html_escape(&footer_link),
+    );
+
+    let text_sections: String = sections
+        .iter()
+        .map(|(title, lines)| {
+            let items: String = lines.iter().map(|l| format!("- {l}\n")).collect();
+            format!("{title}\n{items}\n")
+        })
+        .collect();
+    let text = format!(
+        "{heading}\n\n{intro}\n\n{text_sections}{cta}: {base_url}/admin\n\n{footer} {footer_link}: {base_url}/admin/wizard/notifications\n"
+    );
+
+    OutboundEmail {
+        from: from_addr.to_string(),
+        to: recipient.to_string(),
+        subject,
+        text: Some(text),
+        html: Some(html),
+        reply_to: None,
+        cc: vec![],
+        bcc: vec![],
+        headers: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR_MS: f64 = 3_600_000.0;
+
+    /// Days since the epoch of 2026-10-19, a Monday.
+    const MONDAY: i64 = 20_745;
+
+    #[test]
+    fn dates_convert_from_epoch_days() {
+        assert_eq!(civil_date(0), "1970-01-01");
+        assert_eq!(civil_date(MONDAY), "2026-10-19");
+        assert_eq!(civil_date(11_016), "2000-02-29");
+    }
+
+    #[test]
+    fn summaries_fall_due_on_monday_morning() {
+        let monday_ms = MONDAY as f64 * DAY_MS;
+        // Before the send hour, last week's Monday is still current.
+        assert_eq!(summary_monday(monday_ms + HOUR_MS), MONDAY - 7);
+        assert_eq!(
+            summary_monday(monday_ms + SEND_HOUR_UTC as f64 * HOUR_MS),
+            MONDAY
+        );
+        // The rest of the week keeps the same Monday.
+        assert_eq!(summary_monday(monday_ms + 6.5 * DAY_MS), MONDAY);
+    }
+
+    #[test]
+    fn a_week_is_the_seven_days_before_monday() {
+        let days = week_days(MONDAY);
+        assert_eq!(days.len(), 7);
+        assert_eq!(days[0], "2026-10-12");
+        assert_eq!(days[6], "2026-10-18");
+    }
+
+    #[test]
+    fn email_lists_problems_and_the_opt_out() {
+        let locale = Locale::default_inr();
+        let summary = Summary {
+            activity: Activity::default(),
+            credits_remaining: 42,
+            pending: 3,
+            top_rules: vec![("Opening hours".into(), 12)],
+            problems: vec![
+                Problem::InstagramTokenExpired {
+                    username: "shop".into(),
+                },
+                Problem::Discord {
+                    server: "Team".into(),
+                    problem: AccessProblem::ApprovalChannelHidden,
+                },
+            ],
+        };
+        let email = build_summary_email(
+            &summary,
+            &locale,
+            "owner@example.com",
+            "https://example.com",
+            "noreply@example.com",
+        );
+        let text = email.text.unwrap();
+        assert!(text.contains("Opening hours"));
+        assert!(text.contains("@shop"));
+        assert!(text.contains("Team"));
+        assert!(text.contains("42"));
+        assert!(text.contains("https://example.com/admin/wizard/notifications"));
+        assert!(!text.contains("summary-"), "missing FTL key in:\n{text}");
+    }
+}
@@ -0,0 +1,477 @@
+//! Tenant data exports: a ZIP of everything the tenant configured and
+//! everything we logged for them, so they can take it elsewhere.
+//!
+//! `request` records a `tenant_exports` row. Tenants under
+//! `INLINE_ROW_LIMIT` logged rows get their archive built there and then;
+//! larger ones are left pending for the 15-minute cron (`sweep`), which
+//! builds them and emails the tenant when the download is ready. The
+//! archive is split across KV values (`tenant_export:{id}:{part}`) to stay
+//! under the per-value limit, and expires after `EXPORT_TTL_DAYS`.
+//!
+//! Contents: the tenant profile, onboarding state and persona, every
+//! channel account with its `ReplyConfig`, lead forms, email addresses
+//! with their notification recipients and the billing ledger as JSON;
+//! message metadata, lead submissions, approval history, notifications
+//! and payments as CSV. Message text is not included: it is never logged,
+//! and the opt-in inbox history is per-customer data (see `data_requests`).
+
+use wasm_bindgen::JsValue;
+use worker::*;
+
+use crate::archive::{self, ArchiveFile, DosTime};
+use crate::email::send::{send_outbound, OutboundEmail};
+use crate::helpers::generate_id;
+use crate::storage::{
+    get_brands, get_discord_config_by_tenant, get_email_addresses, get_onboarding, get_tenant,
+    get_tenant_billing, list_instagram_accounts, list_lead_forms, list_whatsapp_accounts,
+};
+use crate::types::ExportStatus;
+
+/// Days a finished archive can be downloaded.
+pub const EXPORT_TTL_DAYS: u32 = 7;
+
+/// Exports listed on the settings page.
+pub const EXPORTS_SHOWN: u32 = 5;
+
+/// Logged rows above which the archive is built by the cron instead of
+/// during the request.
+const INLINE_ROW_LIMIT: i64 = 5_000;
+
+/// Bytes per KV value; KV caps values at 25 MiB.
+const PART_BYTES: usize = 20 * 1024 * 1024;
+
+/// Pending exports built per cron tick.
+const EXPORTS_PER_SWEEP: u32 = 3;
+
+/// Rows fetched per D1 query while building.
+const PAGE_ROWS: u32 = 5_000;
+
+/// D1 tables exported as CSV, with their columns. Every one has `tenant_id`.
+const CSV_TABLES: &[(&str, &str, &[&str])] = &[
+    (
+        "messages.csv",
+        "messages",
+        &[
+            "id",
+            "created_at",
+            "channel",
+            "direction",
+            "sender",
+            "recipient",
+            "channel_account_id",
+            "action_taken",
+            "contact_id",
+            "redactions",
+        ],
+    ),
+    (
+        "lead_form_submissions.csv",
+        "lead_form_submissions",
+        &[
+            "id",
+            "created_at",
+            "lead_form_id",
+            "phone_number",
+            "whatsapp_account_id",
+            "reply_mode",
+        ],
+    ),
+    (
+        "approvals.csv",
+        "pending_approvals",
+        &[
+            "id",
+            "created_at",
+            "channel",
+            "channel_account_id",
+            "rule_id",
+            "rule_label",
+            "sender",
+            "sender_name",
+            "contact_id",
+            "inbound_preview",
+            "draft",
+            "queue_reason",
+            "queue_detail",
+            "status",
+            "decided_at",
+            "decided_by",
+            "edited",
+            "credits_charged",
+            "tool_calls",
+        ],
+    ),
+    (
+        "notifications.csv",
+        "notifications",
+        &[
+            "id",
+            "created_at",
+            "kind",
+            "severity",
+            "title",
+            "body",
+            "link",
+            "read_at",
+        ],
+    ),
+    (
+        "payments.csv",
+        "payments",
+        &[
+            "id",
+            "created_at",
+            "razorpay_payment_id",
+            "razorpay_subscription_id",
+            "amount",
+            "currency",
+            "status",
+        ],
+    ),
+];
+
+/// One row of `tenant_exports`.
+#[derive(Debug, Clone)]
+pub struct TenantExport {
+    pub id: String,
+    pub status: ExportStatus,
+    pub parts: u32,
+    pub size_bytes: i64,
+    pub error: Option<String>,
+    pub created_at: String,
+    /// Ready, but the archive has aged out of KV.
+    pub expired: bool,
+}
+
+fn row_to_export(row: &serde_json::Value) -> Option<TenantExport> {
+    let s = |k: &str| row.get(k).and_then(|v| v.as_str()).map(str::to_string);
+    let n = |k: &str| row.get(k).and_then(|v| v.as_i64()).unwrap_or(0);
+    Some(TenantExport {
+        id: s("id")?,
+        status: ExportStatus::from_wire(&s("status")?)?,
+        parts: n("parts") as u32,
+        size_bytes: n("size_bytes"),
+        error: s("error"),
+        created_at: s("created_at").unwrap_or_default(),
+        expired: n("expired") == 1,
+    })
+}
+
+const EXPORT_COLUMNS: &str = "id, status, parts, size_bytes, error, created_at,
+    (status = 'ready' AND completed_at < datetime('now', '-' || ?2 || ' days')) AS expired";
+
+/// The tenant's latest exports, newest first.
+pub async fn list_exports(db: &D1Database, tenant_id: &str) -> Result<Vec<TenantExport>> {
+    let rows = db
+        .prepare(format!(
+            "SELECT {EXPORT_COLUMNS} FROM tenant_exports WHERE tenant_id = ?1
+             ORDER BY created_at DESC LIMIT ?3"
+        ))
+        .bind(&[
+            tenant_id.into(),
+            JsValue::from(EXPORT_TTL_DAYS as f64),
+            JsValue::from(EXPORTS_SHOWN as f64),
+        ])?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    Ok(rows.iter().filter_map(row_to_export).collect())
+}
+
+pub async fn get_export(
+    db: &D1Database,
+    tenant_id: &str,
+    id: &str,
+) -> Result<Option<TenantExport>> {
+    let row = db
+        .prepare(format!(
+            "SELECT {EXPORT_COLUMNS} FROM tenant_exports WHERE tenant_id = ?1 AND id = ?3"
+        ))
+        .bind(&[
+            tenant_id.into(),
+            JsValue::from(EXPORT_TTL_DAYS as f64),
+            id.into(),
+        ])?
+        .first::<serde_json::Value>(None)
+        .await?;
+    Ok(row.as_ref().and_then(row_to_export))
+}
+
+/// Start an export, or return the one already waiting. Small tenants come
+/// back `Ready`; the rest stay `Pending` until the cron builds them.
+pub async fn request(env: &Env, tenant_id: &str) -> Result<TenantExport> {
+    let db = env.d1("DB")?;
+    let waiting = db
+        .prepare("SELECT id FROM tenant_exports WHERE tenant_id = ? AND status = 'pending' LIMIT 1")
+        .bind(&[tenant_id.into()])?
+        .first::<serde_json::Value>(None)
+        .await?
+        .and_then(|r| r.get("id").and_then(|v| v.as_str()).map(str::to_string));
+
+    let id = match waiting {
+        Some(id) => id,
+        None => {
+            let id = generate_id();
+            db.prepare("INSERT INTO tenant_exports (id, tenant_id) VALUES (?, ?)")
+                .bind(&[id.as_str().into(), tenant_id.into()])?
+                .run()
+                .await?;
+            if logged_rows(&db, tenant_id).await? <= INLINE_ROW_LIMIT {
+                let kv = env.kv("KV")?;
+                let result = build(&kv, &db, tenant_id, &id).await;
+                finish_failed(&db, &id, result).await?;
+            }
+            id
+        }
+    };
+    get_export(&db, tenant_id, &id)
+        .await?
+        .ok_or_else(|| Error::from("Export vanished"))
+}
+
+async fn logged_rows(db: &D1Database, tenant_id: &str) -> Result<i64> {
+    let sum: Vec<String> = CSV_TABLES
+        .iter()
+        .map(|(_, table, _)| format!("(SELECT COUNT(*) FROM {table} WHERE tenant_id = ?1)"))
+        .collect();
+    let row = db
+        .prepare(format!("SELECT {} AS n", sum.join(" + ")))
+        .bind(&[tenant_id.into()])?
+        .first::<serde_json::Value>(None)
+        .await?;
+    Ok(row
+        .and_then(|r| r.get("n").and_then(|v| v.as_i64()))
+        .unwrap_or(0))
+}
+
+/// Mark the export failed when building it errored.
+async fn finish_failed(db: &D1Database, id: &str, result: Result<()>) -> Result<()> {
+    if let Err(e) = result {
+        console_log!("Export {id} failed: {e:?}");
+        db.prepare(
+            "UPDATE tenant_exports SET status = 'failed', error = ?, completed_at = datetime('now')
+             WHERE id = ?",
+        )
+        .bind(&[e.to_string().into(), id.into()])?
+        .run()
+        .await?;
+    }
+    Ok(())
+}
+
+/// Build the archive, store it in KV and mark the export ready.
+async fn build(kv: &kv::KvStore, db: &D1Database, tenant_id: &str, id: &str) -> Result<()> {
+    let files = collect_files(kv, db, tenant_id).await?;
+    let now = js_sys::Date::new_0();
+    let modified = DosTime::new(
+        now.get_utc_full_year(),
+        now.get_utc_month() + 1,
+        now.get_utc_date(),
+        now.get_utc_hours(),
+        now.get_utc_minutes(),
+        now.get_utc_seconds(),
+    );
+    let bytes = archive::zip(&files, modified);
+
+    let chunks: Vec<&[u8]> = bytes.chunks(PART_BYTES).collect();
+    for (part, chunk) in chunks.iter().enumerate() {
+        kv.put_bytes(&format!("tenant_export:{id}:{part}"), chunk)?
+            .expiration_ttl(EXPORT_TTL_DAYS as u64 * 24 * 60 * 60)
+            .execute()
+            .await?;
+    }
+    db.prepare(
+        "UPDATE tenant_exports
+         SET status = 'ready', parts = ?, size_bytes = ?, error = NULL, completed_at = datetime('now')
+         WHERE id = ?",
+    )
+    .bind(&[
+        JsValue::from(chunks.len() as f64),
+        JsValue::from(bytes.len() as f64),
+        id.into(),
+    ])?
+    .run()
+    .await?;
+    Ok(())
+}
+
+fn json_file(name: &str, value: &impl serde::Serialize) -> Result<ArchiveFile> {
+    Ok(ArchiveFile {
+        name: name.to_string(),
+        bytes: serde_json::to_vec_pretty(value)?,
+    })
+}
+
+async fn collect_files(
+    kv: &kv::KvStore,
+    db: &D1Database,
+    tenant_id: &str,
+) -> Result<Vec<ArchiveFile>> {
+    let tenant = get_tenant(db, tenant_id)
+        .await?
+        .ok_or_else(|| Error::from("Tenant not found"))?;
+    let email_addresses = get_email_addresses(kv, tenant_id).await?;
+    let mut files = vec![
+        json_file("tenant.json", &tenant)?,
+        json_file("onboarding.json", &get_onboarding(kv, tenant_id).await?)?,
+        json_file("brands.json", &get_brands(kv, tenant_id).await?)?,
+        json_file(
+            "channels/whatsapp.json",
+            &list_whatsapp_accounts(kv, tenant_id).await?,
+        )?,
+        json_file(
+            "channels/instagram.json",
+            &list_instagram_accounts(kv, tenant_id).await?,
+        )?,
+        json_file(
+            "channels/discord.json",
+            &get_discord_config_by_tenant(kv, tenant_id).await?,
+        )?,
+        json_file("channels/email.json", &email_addresses)?,
+        json_file("lead_forms.json", &list_lead_forms(kv, tenant_id).await?)?,
+        json_file("billing.json", &get_tenant_billing(db, tenant_id).await?)?,
+    ];
+
+    let mut counts = serde_json::Map::new();
+    for (file, table, columns) in CSV_TABLES {
+        let rows = all_rows(db, table, columns, tenant_id).await?;
+        counts.insert(file.to_string(), rows.len().into());
+        files.push(ArchiveFile {
+            name: file.to_string(),
+            bytes: archive::csv(columns, &rows).into_bytes(),
+        });
+    }
+
+    let manifest = serde_json::json!({
+        "tenant_id": tenant_id,
+        "generated_at": crate::helpers::now_iso(),
+        "files": files.iter().map(|f| f.name.as_str()).collect::<Vec<_>>(),
+        "rows": counts,
+    });
+    files.insert(0, json_file("manifest.json", &manifest)?);
+    Ok(files)
+}
+
+/// Every row of a tenant's table, oldest first, a page at a time.
+async fn all_rows(
+    db: &D1Database,
+    table: &str,
+    columns: &[&str],
+    tenant_id: &str,
+) -> Result<Vec<serde_json::Value>> {
+    let mut rows = Vec::new();
+    loop {
+        let page = db
+            .prepare(format!(
+                "SELECT {} FROM {table} WHERE tenant_id = ? ORDER BY created_at, id LIMIT ? OFFSET ?",
+                columns.join(", ")
+            ))
+            .bind(&[
+                tenant_id.into(),
+                JsValue::from(PAGE_ROWS as f64),
+                JsValue::from(rows.len() as f64),
+            ])?
+            .all()
+            .await?
+            .results::<serde_json::Value>()?;
+        let done = page.len() < PAGE_ROWS as usize;
+        rows.extend(page);
+        if done {
+            return Ok(rows);
+        }
+    }
+}
+
+/// The finished archive, reassembled from KV. `None` once it has expired.
+pub async fn archive_bytes(kv: &kv::KvStore, export: &TenantExport) -> Result<Option<Vec<u8>>> {
+    let mut bytes = Vec::with_capacity(export.size_bytes.max(0) as usize);
+    for part in 0..export.parts {
+        let chunk = kv
+            .get(&format!("tenant_export:{}:{part}", export.id))
+            .bytes()
+            .await
+            .map_err(|e| Error::from(e.to_string()))?;
+        match chunk {
+            Some(chunk) => bytes.extend_from_slice(&chunk),
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(bytes))
+}
+
+/// Build pending exports, oldest first, and email each tenant when theirs
+/// is ready. Runs on the 15-minute cron.
+pub async fn sweep(env: &Env) -> Result<()> {
+    let db = env.d1("DB")?;
+    let kv = env.kv("KV")?;
+    let pending = db
+        .prepare(
+            "SELECT id, tenant_id FROM tenant_exports WHERE status = 'pending'
+             ORDER BY created_at LIMIT ?",
+        )
+        .bind(&[JsValue::from(EXPORTS_PER_SWEEP as f64)])?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+
+    for row in pending {
+        let s = |k: &str| {
+            row.get(k)
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string()
+        };
+        let (id, tenant_id) = (s("id"), s("tenant_id"));
+        let result = build(&kv, &db, &tenant_id, &id).await;
+        let ready = result.is_ok();
+        finish_failed(&db, &id, result).await?;
+        if let Err(e) = notify(env, &db, &tenant_id, ready).await {
+            console_log!("Export notification for {tenant_id} failed: {e:?}");
+        }
+    }
+    Ok(())
+}
+
+async fn notify(env: &Env, db: &D1Database, tenant_id: &str, ready: bool) -> Result<()> {
+    let var = |name: &str| {
+        env.var(name)
+            .ok()
+            .map(|v| v.to_string())
+            .filter(|s| !s.is_empty())
+    };
+    let (Some(base_url), Some(email_domain)) = (var("PUBLIC_BASE_URL"), var("EMAIL_DOMAIN")) else {
+        return Ok(());
+    };
+    let Some(tenant) = get_tenant(db, tenant_id).await? else {
+        return Ok(());
+    };
+    let link = format!("{base_url}/admin/settings");
+    let (subject, text) = if ready {
+        (
+            "Your Concierge data export is ready".to_string(),
+            format!(
+                "Your data export is ready to download from {link}.\n\nThe link works for {EXPORT_TTL_DAYS} days.\n"
+            ),
+        )
+    } else {
+        (
+            "Your Concierge data export failed".to_string(),
+            format!("We couldn't build your data export. Try again from {link}.\n"),
+        )
+    };
+    send_outbound(
+        env,
+        &OutboundEmail {
+            from: format!("noreply@{email_domain}"),
+            to: tenant.email,
+            subject,
+            text: Some(text),
+            html: None,
+            reply_to: None,
+            cc: vec![],
+            bcc: vec![],
+            headers: vec![],
+        },
+    )
+    .await
+}
@@ -0,0 +1,383 @@
+//! Post-generation guardrails for AI drafts.
+//!
+//! The keyword risk gate in `approval` catches tone (money, commitments,
+//! drift). Guardrails catch content the model shouldn't hand a customer at
+//! all, whatever the wording:
+//!
+//! - links to domains the tenant hasn't allowlisted,
+//! - phone numbers / emails that aren't the business's (or the customer's),
+//! - verbatim chunks of the persona + rule prompt,
+//! - optionally, whatever a model-based review of the tenant's own policy
+//!   flags.
+//!
+//! The static checks are pure and cheap; the model review is one fast-tier
+//! call and fails closed. Each check is toggled per tenant in
+//! `GuardrailConfig`. A hit queues the draft with a `Guard*` `QueueReason`
+//! and a short detail (the offending URL, number, phrase) for the reviewer.
+
+use std::collections::HashSet;
+
+use worker::*;
+
+use crate::ai::{self, Message, Purpose};
+use crate::detect::{self, DigitRun, TRIM};
+use crate::types::{GuardrailConfig, QueueReason};
+
+/// Consecutive prompt words a draft must repeat to count as a leak. Long
+/// enough that stock phrases ("let me know if you have any") don't trip it.
+const LEAK_WINDOW: usize = 8;
+/// Trailing digits compared when matching numbers, so "+91 98765 43210"
+/// and "098765-43210" are the same line.
+const PHONE_MATCH_DIGITS: usize = 10;
+
+/// Cap on the tenant's free-text policy fed to the review model.
+pub const MAX_POLICY: usize = 1000;
+/// Cap on each allowlist (domains, phones, emails).
+pub const MAX_LIST_ENTRIES: usize = 50;
+
+const POLICY_PROMPT: &str = "\
+You review a reply a business assistant is about to send to a customer. \
+Flag it if it gives medical, legal or financial advice, insults or pressures the customer, \
+discloses private information about other people, makes claims it cannot know, \
+or breaks any of the business's own rules listed below.\n\n\
+Return ONLY \"YES\" if the reply should be held for human review.\n\
+Return ONLY \"NO\" if it is fine to send.\n\n\
+Respond with exactly one word: YES or NO.";
+
+/// One guardrail finding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hit {
+    pub reason: QueueReason,
+    /// What tripped it, shown to the reviewer.
+    pub detail: String,
+}
+
+/// Everything a check needs besides the draft.
+pub struct Context<'a> {
+    pub config: &'a GuardrailConfig,
+    /// Persona + rule prompt the draft was generated from.
+    pub system_prompt: &'a str,
+    /// The customer's own handle (phone, email). Quoting it back is fine.
+    pub sender: &'a str,
+}
+
+/// Run the enabled static checks. Returns every hit, in check order, so
+/// the simulator can show all of them; the pipeline uses the first.
+pub fn check(draft: &str, ctx: &Context<'_>) -> Vec<Hit> {
+    // Drafts are Markdown; `[site](https://…)` only splits into words once
+    // rendered, and it's the rendered link that reaches the customer.
+    let draft = &crate::channel::format::plain_text(draft);
+    let cfg = ctx.config;
+    let mut hits = Vec::new();
+
+    if cfg.check_urls {
+        for host in urls(draft) {
+            if !domain_allowed(&host, &cfg.allowed_domains) {
+                hits.push(Hit {
+                    reason: QueueReason::GuardUnknownUrl,
+                    detail: host,
+                });
+            }
+        }
+    }
+
+    if cfg.check_contacts {
+        for email in emails(draft) {
+            let own = cfg
+                .business_emails
+                .iter()
+                .any(|e| e.trim().eq_ignore_ascii_case(&email))
+                || email.eq_ignore_ascii_case(ctx.sender.trim())
+                || email
+                    .rsplit_once('@')
+                    .map(|(_, d)| domain_allowed(d, &cfg.allowed_domains))
+                    .unwrap_or(false);
+            if !own {
+                hits.push(Hit {
+                    reason: QueueReason::GuardForeignContact,
+                    detail: email,
+                });
+            }
+        }
+        for phone in phones(draft) {
+            let own = cfg
+                .business_phones
+                .iter()
+                .map(String::as_str)
+                .chain(std::iter::once(ctx.sender))
+                .any(|p| same_phone(p, &phone));
+            if !own {
+                hits.push(Hit {
+                    reason: QueueReason::GuardForeignContact,
+                    detail: phone,
+                });
+            }
+        }
+    }
+
+    if cfg.check_prompt_leak {
+        if let Some(phrase) = leaked_phrase(draft, ctx.system_prompt) {
+            hits.push(Hit {
+                reason: QueueReason::GuardPromptLeak,
+                detail: phrase,
+            });
+        }
+    }
+
+    hits
+}
+
+/// Pipeline entry point: the first static hit, else the model review when
+/// the tenant has it on.
+pub async fn review(env: &Env, tenant_id: &str, draft: &str, ctx: &Context<'_>) -> Option<Hit> {
+    if let Some(hit) = check(draft, ctx).into_iter().next() {
+        return Some(hit);
+    }
+    if ctx.config.llm_policy {
+        return policy_review(env, tenant_id, draft, ctx.config).await;
+    }
+    None
+}
+
+/// Model-based policy review. Fails closed: if the reviewer can't be
+/// reached the draft waits for a human.
+pub async fn policy_review(
+    env: &Env,
+    tenant_id: &str,
+    draft: &str,
+    cfg: &GuardrailConfig,
+) -> Option<Hit> {
+    let policy = cfg.policy.trim();
+    let system = if policy.is_empty() {
+        POLICY_PROMPT.to_string()
+    } else {
+        let policy: String = policy.chars().take(MAX_POLICY).collect();
+        format!("{POLICY_PROMPT}\n\nThe business's rules:\n{policy}")
+    };
+    let messages = [Message::system(system), Message::user(draft)];
+    match ai::chat(env, tenant_id, Purpose::Scan, &messages).await {
+        Ok(answer) if answer.trim().to_uppercase().starts_with("NO") => None,
+        Ok(_) => Some(Hit {
+            reason: QueueReason::GuardPolicy,
+            detail: "Flagged by policy review".to_string(),
+        }),
+        Err(e) => {
+            console_log!("Guardrail policy review error: {:?}", e);
+            Some(Hit {
+                reason: QueueReason::GuardPolicy,
+                detail: "Policy review unavailable".to_string(),
+            })
+        }
+    }
+}
+
+// ============================================================================
+// Detectors
+// ============================================================================
+
+/// Hostnames of `http(s)://` and `www.` links, lowercased, in order.
+fn urls(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|w| w.trim_matches(TRIM))
+        .filter_map(|w| {
+            let lower = w.to_lowercase();
+            let rest = lower
+                .strip_prefix("https://")
+                .or_else(|| lower.strip_prefix("http://"))
+                .or_else(|| lower.starts_with("www.").then_some(lower.as_str()))?;
+            let host = rest
+                .split(['/', '?', '#', ':'])
+                .next()
+                .unwrap_or("")
+                .trim_end_matches('.');
+            (!host.is_empty()).then(|| host.to_string())
+        })
+        .collect()
+}
+
+/// Normalise an allowlist entry: accept pasted URLs and `www.` forms.
+fn normalise_domain(entry: &str) -> String {
+    let lower = entry.trim().to_lowercase();
+    let rest = lower
+        .strip_prefix("https://")
+        .or_else(|| lower.strip_prefix("http://"))
+        .unwrap_or(&lower);
+    let host = rest.split(['/', '?', '#', ':']).next().unwrap_or("");
+    host.strip_prefix("www.").unwrap_or(host).to_string()
+}
+
+/// `host` is an allowlisted domain or a subdomain of one.
+fn domain_allowed(host: &str, allowed: &[String]) -> bool {
+    let host = host.to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host);
+    allowed
+        .iter()
+        .map(|d| normalise_domain(d))
+        .filter(|d| !d.is_empty())
+        .any(|d| host == d || host.ends_with(&format!(".{d}")))
+}
+
+fn emails(text: &str) -> Vec<String> {
+    detect::words(text)
+        .into_iter()
+        .filter(|(_, w)| detect::is_email(w))
+        .map(|(_, w)| w.to_lowercase())
+        .collect()
+}
+
+fn phones(text: &str) -> Vec<String> {
+    detect::digit_runs(text)
+        .into_iter()
+        .filter(DigitRun::is_phone)
+        .map(|r| text[r.start..r.end].to_string())
+        .collect()
+}
+
+fn same_phone(a: &str, b: &str) -> bool {
+    let tail = |s: &str| {
+        let digits: Vec<char> = s.chars().filter(char::is_ascii_digit).collect();
+        let start = digits.len().saturating_sub(PHONE_MATCH_DIGITS);
+        digits[start..].iter().collect::<String>()
+    };
+    let (a, b) = (tail(a), tail(b));
+    !a.is_empty() && a == b
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+/// First `LEAK_WINDOW`-word run of the prompt that the draft repeats.
+fn leaked_phrase(draft: &str, prompt: &str) -> Option<String> {
+    let prompt_words = words(prompt);
+    if prompt_words.len() < LEAK_WINDOW {
+        return None;
+    }
+    let grams: HashSet<&[String]> = prompt_words.windows(LEAK_WINDOW).collect();
+    words(draft)
+        .windows(LEAK_WINDOW)
+        .find(|w| grams.contains(w))
+        .map(|w| w.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cfg() -> GuardrailConfig {
+        GuardrailConfig {
+            allowed_domains: vec!["https://www.bloom.example/".into()],
+            business_phones: vec!["+91 98765 43210".into()],
+            business_emails: vec!["hello@bloom.example".into()],
+            ..Default::default()
+        }
+    }
+
+    fn hits(draft: &str, config: &GuardrailConfig, prompt: &str) -> Vec<QueueReason> {
+        let ctx = Context {
+            config,
+            system_prompt: prompt,
+            sender: "+1 415 555 0100",
+        };
+        check(draft, &ctx).into_iter().map(|h| h.reason).collect()
+    }
+
+    #[test]
+    fn clean_draft_passes() {
+        assert!(hits("Sure, we open at 9am. See you soon!", &cfg(), "").is_empty());
+    }
+
+    #[test]
+    fn markdown_link_targets_are_checked() {
+        let c = cfg();
+        assert_eq!(
+            hits("Details on [our site](https://evil.example/offer).", &c, ""),
+            vec![QueueReason::GuardUnknownUrl]
+        );
+        assert_eq!(
+            hits("[Write to us](mailto:sales@other.example)", &c, ""),
+            vec![QueueReason::GuardForeignContact]
+        );
+        assert!(hits("See [the menu](https://bloom.example/menu).", &c, "").is_empty());
+    }
+
+    #[test]
+    fn allowlisted_and_sub_domains_pass() {
+        let c = cfg();
+        assert!(hits("Menu: https://bloom.example/menu.", &c, "").is_empty());
+        assert!(hits("Book at shop.bloom.example/book", &c, "").is_empty());
+        assert!(hits("Book at (https://shop.bloom.example/book)", &c, "").is_empty());
+    }
+
+    #[test]
+    fn unknown_urls_are_flagged_with_host() {
+        let ctx_cfg = cfg();
+        let ctx = Context {
+            config: &ctx_cfg,
+            system_prompt: "",
+            sender: "",
+        };
+        let found = check("Pay here: https://bloom.example.evil.io/pay", &ctx);
+        assert_eq!(found[0].reason, QueueReason::GuardUnknownUrl);
+        assert_eq!(found[0].detail, "bloom.example.evil.io");
+        assert_eq!(
+            hits("see www.other.example", &ctx_cfg, ""),
+            vec![QueueReason::GuardUnknownUrl]
+        );
+    }
+
+    #[test]
+    fn contacts_other_than_business_or_sender_are_flagged() {
+        let c = cfg();
+        assert!(hits("Call us on 098765-43210 or hello@bloom.example", &c, "").is_empty());
+        assert!(hits("We'll ring you back on +1 (415) 555-0100.", &c, "").is_empty());
+        assert_eq!(
+            hits("Try my cousin at 99887 76655", &c, ""),
+            vec![QueueReason::GuardForeignContact]
+        );
+        assert_eq!(
+            hits("Write to someone@gmail.com", &c, ""),
+            vec![QueueReason::GuardForeignContact]
+        );
+        // Order numbers and prices aren't phone numbers.
+        assert!(hits("Order 12345678 ships today, total 1,499.00", &c, "").is_empty());
+    }
+
+    #[test]
+    fn prompt_leak_needs_a_long_verbatim_run() {
+        let prompt = "You are Bloom's assistant. Never reveal supplier names or wholesale prices to customers.";
+        let c = cfg();
+        assert!(hits("We never share supplier details, sorry!", &c, prompt).is_empty());
+        assert_eq!(
+            hits(
+                "My instructions say: never reveal supplier names or wholesale prices to customers.",
+                &c,
+                prompt
+            ),
+            vec![QueueReason::GuardPromptLeak]
+        );
+    }
+
+    #[test]
+    fn disabled_checks_stay_quiet() {
+        let c = GuardrailConfig {
+            check_urls: false,
+            check_contacts: false,
+            check_prompt_leak: false,
+            ..Default::default()
+        };
+        assert!(hits("https://x.example 99887 76655 a@b.example", &c, "").is_empty());
+    }
+
+    #[test]
+    fn defaults_check_everything_but_policy_review() {
+        let d = GuardrailConfig::default();
+        assert!(d.check_urls && d.check_contacts && d.check_prompt_leak);
+        assert!(!d.llm_policy);
+        let parsed: GuardrailConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(parsed, d);
+    }
+}
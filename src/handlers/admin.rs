@@ -10,9 +10,9 @@ use crate::templates::*;
 pub async fn handle_admin(req: Request, env: Env, path: &str, method: Method) -> Result<Response> {
     let kv = env.kv("KV")?;
 
-    // Resolve tenant from session cookie only: no header fallback
-    let tenant_id = match super::auth::resolve_tenant_id(&req, &kv).await {
-        Some(id) => id,
+    // Resolve tenant and actor from session cookie only: no header fallback
+    let (tenant_id, actor) = match super::auth::resolve_session(&req, &env).await? {
+        Some(session) => (session.tenant_id, session.actor),
         None => {
             let headers = Headers::new();
             headers.set("Location", "/auth/login")?;
@@ -23,9 +23,24 @@ pub async fn handle_admin(req: Request, env: Env, path: &str, method: Method) ->
     let base_url = get_base_url(&req);
     let locale = crate::locale::Locale::from_request(&req);
 
+    // Team members only reach what their role allows; the owner reaches all.
+    let mutating = matches!(method, Method::Post | Method::Put | Method::Delete);
+    let needed = crate::team::required_role(path, mutating);
+    if !actor.role.allows(needed) {
+        return Response::error(
+            format!(
+                "Your role ({}) can't do that. Ask the account owner.",
+                actor.role.label()
+            ),
+            403,
+        );
+    }
+
     // CSRF validation on state-changing requests
-    if matches!(method, Method::Post | Method::Put | Method::Delete) {
-        if let Err(e) = super::auth::validate_csrf(&req, &kv, &tenant_id).await {
+    if mutating {
+        if let Err(e) =
+            super::auth::validate_csrf(&req, &kv, &tenant_id, actor.csrf_scope().as_deref()).await
+        {
             return Response::error(format!("CSRF validation failed: {e}"), 403);
         }
     }
@@ -50,15 +65,35 @@ pub async fn handle_admin(req: Request, env: Env, path: &str, method: Method) ->
         let wa = list_whatsapp_accounts(&kv, &tenant_id).await?;
         let ig = list_instagram_accounts(&kv, &tenant_id).await?;
         let dc = get_discord_config_by_tenant(&kv, &tenant_id).await?;
+        // An agency acting here sees the overview link, not the card that
+        // lets the owner remove it.
+        let managed_by = match actor.agency_id {
+            Some(_) => None,
+            None => crate::agency::managed_by(&db, &tenant_id).await?,
+        };
+        let agency_pays =
+            managed_by.is_some() && crate::agency::payer(&db, &tenant_id).await? != tenant_id;
+        // Agency invites are for the invited address's own login.
+        let agency_invites = if actor.is_owner() {
+            crate::agency::invites_for(&db, &tenant.email).await?
+        } else {
+            Vec::new()
+        };
         return Response::from_html(admin_settings_html(
-            &tenant,
-            &base_url,
-            &google_client_id,
-            &meta_app_id,
-            &wa,
-            &ig,
-            dc.as_ref(),
-            &tenant_id,
+            &SettingsProps {
+                tenant: &tenant,
+                tenant_id: &tenant_id,
+                base_url: &base_url,
+                google_client_id: &google_client_id,
+                meta_app_id: &meta_app_id,
+                wa: &wa,
+                ig: &ig,
+                discord: dc.as_ref(),
+                role: actor.role,
+                managed_by: managed_by.as_deref(),
+                agency_pays,
+                agency_invites: &agency_invites,
+            },
             &locale,
         ));
     }
@@ -103,8 +138,9 @@ pub async fn handle_admin(req: Request, env: Env, path: &str, method: Method) ->
     }
 
     if path == "/admin/delete-account" && method == Method::Delete {
-        let db = env.d1("DB")?;
-        delete_tenant_data(&kv, &db, &tenant_id).await?;
+        // Sessions are the first thing the job removes, so the tenant is
+        // signed out even if the rest is left for the cron.
+        crate::tenant_deletion::start(&env, &tenant_id, "tenant").await?;
 
         // Clear session cookie
         let headers = Headers::new();
@@ -116,6 +152,10 @@ pub async fn handle_admin(req: Request, env: Env, path: &str, method: Method) ->
         return Ok(Response::empty()?.with_status(302).with_headers(headers));
     }
 
+    if path == "/admin/export" || path.starts_with("/admin/export/") {
+        return super::admin_exports::handle_exports(req, env, path, &base_url, &tenant_id).await;
+    }
+
     if path.starts_with("/admin/billing") {
         return super::admin_billing::handle_billing_admin(req, env, path, &base_url, &tenant_id)
             .await;
@@ -153,17 +193,81 @@ pub async fn handle_admin(req: Request, env: Env, path: &str, method: Method) ->
         return super::onboarding::handle_wizard(req, env, path, &base_url, &tenant_id).await;
     }
 
+    if path == "/admin/brands" || path.starts_with("/admin/brands/") {
+        return super::admin_brands::handle_brands(req, env, path, &base_url, &tenant_id).await;
+    }
+
     if path.starts_with("/admin/persona") {
         return super::admin_persona::handle_persona_admin(req, env, path, &base_url, &tenant_id)
             .await;
     }
 
+    if path == "/admin/guardrails" || path.starts_with("/admin/guardrails/") {
+        return super::admin_guardrails::handle_guardrails_admin(
+            req, env, path, &base_url, &tenant_id,
+        )
+        .await;
+    }
+
+    if path == "/admin/tools" || path.starts_with("/admin/tools/") {
+        return super::admin_tools::handle_tools_admin(req, env, path, &base_url, &tenant_id).await;
+    }
+
     if path.starts_with("/admin/rules/") {
         return super::admin_rules::handle_rules(req, env, path, &base_url, &tenant_id).await;
     }
 
     if path == "/admin/approvals" || path.starts_with("/admin/approvals/") {
-        return super::admin_approvals::handle_approvals(req, env, path, &base_url, &tenant_id)
+        return super::admin_approvals::handle_approvals(
+            req, env, path, &base_url, &tenant_id, &actor,
+        )
+        .await;
+    }
+
+    if path == "/admin/inbox" || path.starts_with("/admin/inbox/") {
+        return super::admin_inbox::handle_inbox(req, env, path, &base_url, &tenant_id).await;
+    }
+
+    if path == "/admin/consent" || path.starts_with("/admin/consent/") {
+        return super::admin_consent::handle_consent(req, env, path, &base_url, &tenant_id).await;
+    }
+
+    if path == "/admin/contacts" || path.starts_with("/admin/contacts/") {
+        return super::admin_contacts::handle_contacts(req, env, path, &base_url, &tenant_id).await;
+    }
+
+    if path == "/admin/data-requests" || path.starts_with("/admin/data-requests/") {
+        return super::admin_data_requests::handle_data_requests(
+            req, env, path, &base_url, &tenant_id,
+        )
+        .await;
+    }
+
+    if path == "/admin/messages"
+        || path == "/admin/messages.csv"
+        || path.starts_with("/admin/messages/")
+    {
+        return super::admin_messages::handle_messages(req, env, path, &base_url, &tenant_id).await;
+    }
+
+    if path == "/admin/notifications" || path.starts_with("/admin/notifications/") {
+        return super::admin_notifications::handle_notifications(
+            req, env, path, &base_url, &tenant_id,
+        )
+        .await;
+    }
+
+    if path == "/admin/agency" || path.starts_with("/admin/agency/") {
+        return super::admin_agency::handle_agency(req, env, path, &base_url, &tenant_id, &actor)
+            .await;
+    }
+
+    if path == "/admin/team" || path.starts_with("/admin/team/") {
+        return super::admin_team::handle_team(req, env, path, &base_url, &tenant_id).await;
+    }
+
+    if path == "/admin/analytics" || path == "/admin/analytics.csv" {
+        return super::admin_analytics::handle_analytics(req, env, path, &base_url, &tenant_id)
             .await;
     }
 
@@ -193,7 +297,8 @@ pub async fn handle_admin(req: Request, env: Env, path: &str, method: Method) ->
         let lead_forms = list_lead_forms(&kv, &tenant_id).await?;
         let email_addrs = crate::storage::get_email_addresses(&kv, &tenant_id).await?;
         let db = env.d1("DB")?;
-        let mut billing = crate::storage::get_tenant_billing(&db, &tenant_id).await?;
+        let payer = crate::agency::payer(&db, &tenant_id).await?;
+        let mut billing = crate::storage::get_tenant_billing(&db, &payer).await?;
         crate::billing::refresh_billing(&mut billing);
 
         let mut resp = Response::from_html(admin_dashboard_html(
@@ -0,0 +1,337 @@
+//! `/admin/agency` routes: the agency overview and the tenant switcher.
+//!
+//! Routes:
+//!   GET    /admin/agency                        clients with credits, approvals and health
+//!   GET    /admin/agency/switcher               the header's tenant switcher
+//!   POST   /admin/agency/switch                 move the session to another tenant
+//!   POST   /admin/agency/clients                invite a business owner to become a client
+//!   POST   /admin/agency/clients/{id}/billing   the agency takes on a client's replies
+//!   DELETE /admin/agency/clients/{id}           stop managing a client
+//!   POST   /admin/agency/invites/{id}/accept    the invited owner links their account
+//!   DELETE /admin/agency/invites/{id}           the invited owner declines, or the agency withdraws
+//!   POST   /admin/agency/manager/billing        the client's owner pays for its own replies again
+//!   DELETE /admin/agency/manager                the client's owner removes its agency
+//!
+//! These act for the signed-in login rather than the current tenant, so
+//! `team::required_role` lets every role through and each route checks
+//! the login here. Authenticated and CSRF-protected by the `handle_admin`
+//! dispatcher.
+
+use worker::*;
+
+use crate::agency::{self, Access, Invite};
+use crate::email::send::send_outbound;
+use crate::helpers::html_escape;
+use crate::storage::{
+    get_onboarding, get_tenant, get_tenant_billing, get_tenant_by_email, save_onboarding,
+};
+use crate::team::{self, Actor};
+use crate::templates::agency::{agency_page_html, switcher_html};
+use crate::types::{ClientBilling, Tenant};
+
+pub async fn handle_agency(
+    mut req: Request,
+    env: Env,
+    path: &str,
+    base_url: &str,
+    tenant_id: &str,
+    actor: &Actor,
+) -> Result<Response> {
+    let db = env.d1("DB")?;
+    let kv = env.kv("KV")?;
+    let locale = crate::locale::Locale::from_request(&req);
+    let rest = path.strip_prefix("/admin/agency").unwrap_or("");
+
+    // Members and agencies carry their address; the owner's is the tenant's.
+    let login = match &actor.email {
+        Some(email) => email.clone(),
+        None => get_tenant(&db, tenant_id)
+            .await?
+            .map(|t| t.email)
+            .unwrap_or_default(),
+    };
+
+    match (req.method(), rest) {
+        (Method::Get, "/switcher") => {
+            let reach = agency::reachable(&db, &login).await?;
+            let mut resp = Response::from_html(switcher_html(&reach, tenant_id, base_url))?;
+            resp.headers_mut().set("Cache-Control", "no-store")?;
+            Ok(resp)
+        }
+
+        (Method::Post, "/switch") => {
+            let form: serde_json::Value = req.json().await?;
+            let target = form.get("tenant_id").and_then(|v| v.as_str()).unwrap_or("");
+            let reach = agency::reachable(&db, &login).await?;
+            match reach.iter().find(|r| r.tenant_id == target) {
+                Some(reach) => super::auth::switch_session(&req, &kv, reach, &login).await,
+                None => Response::error("You don't have access to that account", 403),
+            }
+        }
+
+        (method, invite_path) if invite_path.starts_with("/invites/") => {
+            let (id, action) = match invite_path.trim_start_matches("/invites/").split_once('/') {
+                Some((id, action)) => (id, action),
+                None => (invite_path.trim_start_matches("/invites/"), ""),
+            };
+            let Some(invite) = agency::get_invite(&db, id).await? else {
+                return Response::error("Invite not found", 404);
+            };
+            // The invited address's own login, not a member or an agency
+            // standing in its account.
+            let invitee = actor.is_owner() && team::normalize_email(&login) == invite.email;
+
+            match (method, action) {
+                (Method::Post, "accept") if invitee => {
+                    accept_invite(&env, tenant_id, &invite).await
+                }
+
+                (Method::Delete, "") => {
+                    let sender = get_tenant_by_email(&db, &login)
+                        .await?
+                        .is_some_and(|home| home.id == invite.agency_id);
+                    if !invitee && !sender {
+                        return Response::error("Invite not found", 404);
+                    }
+                    agency::remove_invite(&db, &invite.id).await?;
+                    let headers = Headers::new();
+                    headers.set("HX-Refresh", "true")?;
+                    Ok(Response::ok("")?.with_headers(headers))
+                }
+
+                (Method::Post, "accept") => {
+                    Response::error("Sign in as the invited address to accept this invite", 403)
+                }
+
+                _ => Response::error("Not Found", 404),
+            }
+        }
+
+        (Method::Post, "/manager/billing") => {
+            if !actor.is_owner() {
+                return Response::error("Only the account owner can change who pays", 403);
+            }
+            agency::pay_own_replies(&db, tenant_id).await?;
+            let headers = Headers::new();
+            headers.set("HX-Refresh", "true")?;
+            Ok(Response::ok("")?.with_headers(headers))
+        }
+
+        (Method::Delete, "/manager") => {
+            if !actor.is_owner() {
+                return Response::error("Only the account owner can remove its agency", 403);
+            }
+            agency::remove_client(&db, tenant_id).await?;
+            let headers = Headers::new();
+            headers.set("HX-Refresh", "true")?;
+            Ok(Response::ok("")?.with_headers(headers))
+        }
+
+        (method, rest) => {
+            // Everything else manages the clients of the tenant the login owns.
+            let Some(home) = get_tenant_by_email(&db, &login).await? else {
+                return Response::error("Only account owners can manage clients", 403);
+            };
+            match (method, rest) {
+                (Method::Get, "" | "/") => {
+                    let mut rows = Vec::new();
+                    for client in agency::clients(&db, &home.id).await? {
+                        rows.push(agency::status(&kv, &db, client).await?);
+                    }
+                    let invites = agency::invites_sent(&db, &home.id).await?;
+                    let mut bill = get_tenant_billing(&db, &home.id).await?;
+                    crate::billing::refresh_billing(&mut bill);
+                    let name = home.name.clone().unwrap_or(home.email.clone());
+                    let mut resp = Response::from_html(agency_page_html(
+                        &name,
+                        bill.total_remaining(),
+                        &rows,
+                        &invites,
+                        base_url,
+                        &locale,
+                    ))?;
+                    resp.headers_mut().set("Cache-Control", "no-store")?;
+                    Ok(resp)
+                }
+
+                (Method::Post, "/clients") => {
+                    let form: serde_json::Value = req.json().await?;
+                    invite_client(&env, &home, &form, base_url).await
+                }
+
+                (method, client_path) if client_path.starts_with("/clients/") => {
+                    let parts: Vec<&str> = client_path
+                        .trim_start_matches("/clients/")
+                        .split('/')
+                        .collect();
+                    let (id, action) = match parts.as_slice() {
+                        [id] => (*id, ""),
+                        [id, action] => (*id, *action),
+                        _ => return Response::error("Not Found", 404),
+                    };
+                    let Some(client) = agency::clients(&db, &home.id)
+                        .await?
+                        .into_iter()
+                        .find(|c| c.tenant_id == id)
+                    else {
+                        return Response::error("Client not found", 404);
+                    };
+
+                    match (method, action) {
+                        (Method::Post, "billing") => {
+                            let form: serde_json::Value = req.json().await?;
+                            let Some(billing) = form
+                                .get("billing")
+                                .and_then(|v| v.as_str())
+                                .and_then(ClientBilling::from_wire)
+                            else {
+                                return Response::error("Unknown billing", 400);
+                            };
+                            if !agency::agency_can_rebill(client.billing, billing) {
+                                return Response::error(
+                                    "Only the client's owner can move replies back onto their own credits",
+                                    403,
+                                );
+                            }
+                            agency::set_billing(&db, &home.id, id, billing).await?;
+                            let headers = Headers::new();
+                            headers.set("HX-Refresh", "true")?;
+                            Ok(Response::ok("")?.with_headers(headers))
+                        }
+
+                        (Method::Delete, "") => {
+                            agency::remove_client(&db, id).await?;
+                            // Standing in the client just removed: go home
+                            // rather than lose the session.
+                            if id == tenant_id {
+                                let home_reach = agency::Reach {
+                                    tenant_id: home.id.clone(),
+                                    name: String::new(),
+                                    access: Access::Owner,
+                                };
+                                return super::auth::switch_session(&req, &kv, &home_reach, &login)
+                                    .await;
+                            }
+                            let headers = Headers::new();
+                            headers.set("HX-Refresh", "true")?;
+                            Ok(Response::ok("")?.with_headers(headers))
+                        }
+
+                        _ => Response::error("Not Found", 404),
+                    }
+                }
+
+                _ => Response::error("Not Found", 404),
+            }
+        }
+    }
+}
+
+/// Invite the shop owner's address to become a client. Nothing is linked
+/// until its owner signs in as that address and accepts (`accept_invite`).
+async fn invite_client(
+    env: &Env,
+    home: &Tenant,
+    form: &serde_json::Value,
+    base_url: &str,
+) -> Result<Response> {
+    let field = |k: &str| form.get(k).and_then(|v| v.as_str()).unwrap_or("").trim();
+    let name: String = field("name").chars().take(100).collect();
+    let email = team::normalize_email(field("email"));
+    let billing = ClientBilling::from_wire(field("billing")).unwrap_or_default();
+    if name.is_empty() {
+        return error("Enter the business name.");
+    }
+    if !email.contains('@') || email.len() > 254 {
+        return error("Enter the business owner's email address.");
+    }
+    let db = env.d1("DB")?;
+    if email == team::normalize_email(&home.email) {
+        return error("That's your own address. Use the business owner's.");
+    }
+    if let Some(existing) = get_tenant_by_email(&db, &email).await? {
+        if agency::managed_by(&db, &existing.id).await?.is_some() {
+            return error("That account already has an agency. Its owner has to remove it first.");
+        }
+    }
+    if agency::invites_sent(&db, &home.id).await?.len() >= agency::MAX_PENDING_INVITES {
+        return error("Too many invites are waiting. Withdraw some before sending more.");
+    }
+    let Some(id) = agency::invite(&db, &home.id, &email, &name, billing).await? else {
+        return error("You've already invited that address.");
+    };
+
+    let sent = match agency::get_invite(&db, &id).await? {
+        Some(invite) => send_invite(env, &invite, base_url).await,
+        None => false,
+    };
+    let headers = Headers::new();
+    headers.set("HX-Refresh", "true")?;
+    let note = if sent {
+        "Invite sent. The business shows up here once its owner accepts.".to_string()
+    } else {
+        format!("Invite saved. Ask the owner to sign in as {email} and accept it in Settings.")
+    };
+    Ok(Response::from_html(format!(
+        r#"<div class="success">{}</div>"#,
+        html_escape(&note)
+    ))?
+    .with_headers(headers))
+}
+
+/// Email the invite to the shop owner. False when email isn't set up or
+/// the send failed; the invite still waits in their Settings.
+async fn send_invite(env: &Env, invite: &Invite, base_url: &str) -> bool {
+    let domain = env
+        .var("EMAIL_DOMAIN")
+        .map(|v| v.to_string())
+        .unwrap_or_default();
+    if domain.is_empty() {
+        console_log!("Agency invite email skipped: EMAIL_DOMAIN is not set");
+        return false;
+    }
+    let email = agency::build_invite_email(
+        invite,
+        &agency::invite_url(base_url),
+        &format!("noreply@{domain}"),
+    );
+    match send_outbound(env, &email).await {
+        Ok(()) => true,
+        Err(e) => {
+            console_log!("Agency invite email to {} failed: {e:?}", invite.email);
+            false
+        }
+    }
+}
+
+/// The invited owner links the account they're signed in to. The caller
+/// checked the login is the invited address and owns `tenant_id`.
+async fn accept_invite(env: &Env, tenant_id: &str, invite: &Invite) -> Result<Response> {
+    let db = env.d1("DB")?;
+    if invite.agency_id == tenant_id {
+        return error("An account can't manage itself.");
+    }
+    if agency::managed_by(&db, tenant_id).await?.is_some() {
+        return error("This account already has an agency. Remove it first.");
+    }
+    agency::accept_invite(&db, invite, tenant_id).await?;
+
+    // Fill in the setup wizard's business name if the owner hasn't.
+    let kv = env.kv("KV")?;
+    let mut state = get_onboarding(&kv, tenant_id).await?;
+    if state.business.name.trim().is_empty() {
+        state.business.name = invite.name.clone();
+        save_onboarding(&kv, tenant_id, &state).await?;
+    }
+
+    let headers = Headers::new();
+    headers.set("HX-Refresh", "true")?;
+    Ok(Response::ok("")?.with_headers(headers))
+}
+
+fn error(message: &str) -> Result<Response> {
+    Response::from_html(format!(
+        r#"<div class="error">{}</div>"#,
+        html_escape(message)
+    ))
+}
@@ -0,0 +1,51 @@
+//! `/admin/analytics` routes: the dashboard's activity panel.
+//!
+//! Routes:
+//!   GET  /admin/analytics?days=N      activity panel fragment
+//!   GET  /admin/analytics.csv?days=N  the same range, one CSV row per day
+//!
+//! All routes are authenticated by the `handle_admin` dispatcher.
+
+use worker::*;
+
+use crate::analytics;
+use crate::templates::analytics::activity_panel_html;
+
+pub async fn handle_analytics(
+    req: Request,
+    env: Env,
+    path: &str,
+    base_url: &str,
+    tenant_id: &str,
+) -> Result<Response> {
+    let db = env.d1("DB")?;
+    let range = analytics::range(&req.url()?);
+
+    match (req.method(), path) {
+        (Method::Get, "/admin/analytics") => {
+            let activity = analytics::load(&db, tenant_id, range).await?;
+            let mut resp = Response::from_html(activity_panel_html(&activity, range, base_url))?;
+            resp.headers_mut().set("Cache-Control", "no-store")?;
+            Ok(resp)
+        }
+
+        (Method::Get, "/admin/analytics.csv") => {
+            let activity = analytics::load(&db, tenant_id, range).await?;
+            let last = activity
+                .days
+                .last()
+                .map(|d| d.day.clone())
+                .unwrap_or_default();
+            let headers = Headers::new();
+            headers.set("Content-Type", "text/csv; charset=utf-8")?;
+            headers.set(
+                "Content-Disposition",
+                &format!("attachment; filename=\"concierge-activity-{range}d-{last}.csv\""),
+            )?;
+            headers.set("Cache-Control", "no-store")?;
+            Ok(Response::ok(analytics::csv(&activity))?.with_headers(headers))
+        }
+
+        _ => Response::error("Not Found", 404),
+    }
+}
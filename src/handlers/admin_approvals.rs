@@ -1,4 +1,5 @@
-//! `/admin/approvals/*` routes: list pending AI drafts and act on them.
+//! `/admin/approvals/*` routes: list pending AI drafts, act on them, and
+//! show one approval's record (`/admin/approvals/{id}`).
 //!
 //! All routes are authenticated and CSRF-protected by the `handle_admin`
 //! dispatcher. Approve / edit / reject mutate the D1 row, the KV
@@ -11,10 +12,12 @@ use crate::approvals;
 use crate::billing;
 use crate::channel;
 use crate::helpers::{generate_id, now_iso};
+use crate::inbox;
 use crate::storage::{
-    delete_conversation_context, get_conversation_context, get_tenant, save_message,
+    delete_conversation_context, get_conversation_context, get_tenant, save_message, MessageRow,
 };
-use crate::templates::approvals::{approvals_list_html, approvals_page_html};
+use crate::team::Actor;
+use crate::templates::approvals::{approval_page_html, approvals_list_html, approvals_page_html};
 use crate::types::{
     ApprovalDecider, ApprovalStatus, Channel, ConversationContext, MessageAction, MessageDirection,
     PendingApproval,
@@ -26,6 +29,7 @@ pub async fn handle_approvals(
     path: &str,
     base_url: &str,
     tenant_id: &str,
+    actor: &Actor,
 ) -> Result<Response> {
     let kv = env.kv("KV")?;
     let db = env.d1("DB")?;
@@ -57,6 +61,15 @@ pub async fn handle_approvals(
             stub.fetch_with_str("https://do.invalid/subscribe").await
         }
 
+        (Method::Get, id_path) if id_path.starts_with('/') && !id_path[1..].contains('/') => {
+            match approvals::get_row(&db, &id_path[1..]).await? {
+                Some(row) if row.tenant_id == tenant_id => {
+                    Response::from_html(approval_page_html(&row, base_url, &locale))
+                }
+                _ => Response::error("Approval not found", 404),
+            }
+        }
+
         (Method::Post, action_path) if action_path.starts_with('/') => {
             let parts: Vec<&str> = action_path.trim_start_matches('/').split('/').collect();
             let (id, action) = match parts.as_slice() {
@@ -74,8 +87,11 @@ pub async fn handle_approvals(
             }
 
             match action {
-                "approve" => approve(&env, &kv, &db, tenant_id, row, None).await,
-                "reject" => reject(&env, &kv, &db, tenant_id, row).await,
+                "approve" if row.draft.trim().is_empty() => Response::from_html(
+                    r#"<div class="error">No draft to send. Edit and write the reply.</div>"#,
+                ),
+                "approve" => approve(&env, &kv, &db, tenant_id, actor, row, None).await,
+                "reject" => reject(&env, &kv, &db, tenant_id, actor, row).await,
                 "edit" => {
                     let form: serde_json::Value =
                         req.json().await.unwrap_or(serde_json::Value::Null);
@@ -91,7 +107,7 @@ pub async fn handle_approvals(
                             r#"<div class="error">Edited reply can't be empty.</div>"#,
                         );
                     }
-                    approve(&env, &kv, &db, tenant_id, row, Some(edited)).await
+                    approve(&env, &kv, &db, tenant_id, actor, row, Some(edited)).await
                 }
                 _ => Response::error("Not Found", 404),
             }
@@ -107,6 +123,7 @@ async fn approve(
     kv: &kv::KvStore,
     db: &D1Database,
     tenant_id: &str,
+    actor: &Actor,
     row: PendingApproval,
     edit: Option<String>,
 ) -> Result<Response> {
@@ -131,9 +148,10 @@ async fn approve(
     if let Err(e) = channel::send_reply(
         &ctx.origin_channel,
         env,
+        &ctx.tenant_id,
         &ctx.reply_metadata,
         &ctx.origin_sender,
-        &draft_text,
+        channel::Body::Markdown(&draft_text),
         subject,
     )
     .await
@@ -142,9 +160,7 @@ async fn approve(
         return Response::from_html(format!(r#"<div class="error">Failed to send: {}</div>"#, e));
     }
 
-    let decided_by = ApprovalDecider::Web {
-        email: web_decider(db, tenant_id).await,
-    };
+    let decided_by = web_decider(db, tenant_id, actor).await;
     if let Err(e) =
         approvals::mark_decided(db, &row.id, ApprovalStatus::Approved, &decided_by, edited).await
     {
@@ -154,13 +170,26 @@ async fn approve(
     let _ = save_message(
         db,
         &generate_id(),
+        &MessageRow {
+            channel: &ctx.origin_channel,
+            direction: MessageDirection::Outbound,
+            sender: &ctx.origin_recipient,
+            recipient: &ctx.origin_sender,
+            tenant_id: &ctx.tenant_id,
+            channel_account_id: &ctx.channel_account_id,
+            action_taken: Some(MessageAction::AiApproved),
+            credits: 0,
+            approval_id: Some(&row.id),
+        },
+    )
+    .await;
+    inbox::record_reply(
+        env,
+        tenant_id,
         &ctx.origin_channel,
-        MessageDirection::Outbound,
-        &ctx.origin_recipient,
         &ctx.origin_sender,
-        &ctx.tenant_id,
-        &ctx.channel_account_id,
-        Some(MessageAction::AiApproved),
+        Some(&draft_text),
+        MessageAction::AiApproved,
     )
     .await;
 
@@ -183,31 +212,43 @@ async fn reject(
     kv: &kv::KvStore,
     db: &D1Database,
     tenant_id: &str,
+    actor: &Actor,
     row: PendingApproval,
 ) -> Result<Response> {
-    let decided_by = ApprovalDecider::Web {
-        email: web_decider(db, tenant_id).await,
-    };
+    let decided_by = web_decider(db, tenant_id, actor).await;
     if let Err(e) =
         approvals::mark_decided(db, &row.id, ApprovalStatus::Rejected, &decided_by, false).await
     {
         console_log!("Failed to mark rejection row decided: {e:?}");
     }
 
-    if let Err(e) = billing::restore_credit(db, &row.tenant_id).await {
+    if let Err(e) = billing::restore_credit(db, &row.tenant_id, row.credits_charged).await {
         console_log!("Failed to restore credit on rejection: {e:?}");
     }
 
     let _ = save_message(
         db,
         &generate_id(),
+        &MessageRow {
+            channel: &row.channel,
+            direction: MessageDirection::Outbound,
+            sender: &row.sender,
+            recipient: &row.sender,
+            tenant_id: &row.tenant_id,
+            channel_account_id: &row.channel_account_id,
+            action_taken: Some(MessageAction::AiRejected),
+            credits: -row.credits_charged,
+            approval_id: Some(&row.id),
+        },
+    )
+    .await;
+    inbox::record_reply(
+        env,
+        tenant_id,
         &row.channel,
-        MessageDirection::Outbound,
         &row.sender,
-        &row.sender,
-        &row.tenant_id,
-        &row.channel_account_id,
-        Some(MessageAction::AiRejected),
+        None,
+        MessageAction::AiRejected,
     )
     .await;
 
@@ -247,11 +288,19 @@ fn email_subject_for(ctx: &ConversationContext) -> Option<&'static str> {
     }
 }
 
-/// Best-effort lookup of the tenant's login email for the audit row. Falls
-/// back to tenant_id if the lookup fails.
-async fn web_decider(db: &D1Database, tenant_id: &str) -> String {
-    match get_tenant(db, tenant_id).await {
-        Ok(Some(t)) => t.email,
-        _ => tenant_id.to_string(),
+/// Who decided, for the audit row: the team member's address, or a
+/// best-effort lookup of the owner's login email that falls back to
+/// tenant_id if the lookup fails.
+async fn web_decider(db: &D1Database, tenant_id: &str, actor: &Actor) -> ApprovalDecider {
+    let email = match &actor.email {
+        Some(email) => email.clone(),
+        None => match get_tenant(db, tenant_id).await {
+            Ok(Some(t)) => t.email,
+            _ => tenant_id.to_string(),
+        },
+    };
+    ApprovalDecider::Web {
+        email,
+        member_id: actor.member_id.clone(),
     }
 }
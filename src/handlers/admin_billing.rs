@@ -2,6 +2,7 @@
 
 use worker::*;
 
+use crate::agency;
 use crate::billing;
 use crate::billing::razorpay;
 use crate::helpers::*;
@@ -28,6 +29,21 @@ pub async fn handle_billing_admin(
     match (method, sub) {
         // Billing overview
         (Method::Get, "" | "/") => {
+            let payer = agency::payer(&db, tenant_id).await?;
+            if payer != tenant_id {
+                let mut bill = storage::get_tenant_billing(&db, &payer).await?;
+                crate::billing::refresh_billing(&mut bill);
+                let name = agency::managed_by(&db, tenant_id)
+                    .await?
+                    .unwrap_or_else(|| "Your agency".to_string());
+                let locale = crate::locale::Locale::from_request(&req);
+                return Response::from_html(tmpl::billing_paid_by_agency_html(
+                    &name,
+                    bill.total_remaining(),
+                    base_url,
+                    &locale,
+                ));
+            }
             let mut bill = storage::get_tenant_billing(&db, tenant_id).await?;
             crate::billing::refresh_billing(&mut bill);
             storage::save_tenant_billing(&db, tenant_id, &bill).await?;
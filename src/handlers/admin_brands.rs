@@ -0,0 +1,157 @@
+//! `/admin/brands` routes: brands and locations inside the tenant, and
+//! which brand each channel account answers for.
+//!
+//! Routes:
+//!   GET    /admin/brands           brands, channel assignments and the add form
+//!   POST   /admin/brands           add a brand
+//!   POST   /admin/brands/assign    point a channel account at a brand
+//!   GET    /admin/brands/{id}      a brand's business details and opening hours
+//!   POST   /admin/brands/{id}      save them
+//!   DELETE /admin/brands/{id}      remove a brand; its channels go back to the main brand
+//!
+//! A brand's persona is edited at `/admin/persona?brand={id}`.
+//! Authenticated and CSRF-protected by the `handle_admin` dispatcher.
+
+use worker::*;
+
+use crate::brands;
+use crate::helpers::html_escape;
+use crate::templates::brands::{brand_page_html, brands_page_html};
+use crate::types::{Brand, Channel};
+
+/// Extra brands a tenant can add next to its main brand.
+const MAX_BRANDS: usize = 20;
+
+pub async fn handle_brands(
+    mut req: Request,
+    env: Env,
+    path: &str,
+    base_url: &str,
+    tenant_id: &str,
+) -> Result<Response> {
+    let kv = env.kv("KV")?;
+    let locale = crate::locale::Locale::from_request(&req);
+    let rest = path.strip_prefix("/admin/brands").unwrap_or("");
+
+    match (req.method(), rest) {
+        (Method::Get, "" | "/") => {
+            let all = brands::list(&kv, tenant_id).await?;
+            let accounts = brands::assignments(&kv, tenant_id).await?;
+            let mut resp =
+                Response::from_html(brands_page_html(&all, &accounts, base_url, &locale))?;
+            resp.headers_mut().set("Cache-Control", "no-store")?;
+            Ok(resp)
+        }
+
+        (Method::Post, "" | "/") => {
+            let form: serde_json::Value = req.json().await?;
+            let name: String = form
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .trim()
+                .chars()
+                .take(100)
+                .collect();
+            if name.is_empty() {
+                return error("Enter the brand's name.");
+            }
+            if brands::list(&kv, tenant_id).await?.len() > MAX_BRANDS {
+                return error(&format!(
+                    "An account can have up to {MAX_BRANDS} extra brands."
+                ));
+            }
+            let brand = brands::create(&kv, tenant_id, &name).await?;
+            let headers = Headers::new();
+            headers.set(
+                "HX-Redirect",
+                &format!("{base_url}/admin/brands/{}", brand.id),
+            )?;
+            Ok(Response::empty()?.with_status(200).with_headers(headers))
+        }
+
+        (Method::Post, "/assign") => {
+            let form: serde_json::Value = req.json().await?;
+            let field = |k: &str| form.get(k).and_then(|v| v.as_str()).unwrap_or("");
+            let Some(channel) = Channel::from_wire(field("channel")) else {
+                return Response::error("Unknown channel", 400);
+            };
+            let brand_id = match field("brand_id") {
+                "" | Brand::MAIN => None,
+                id => match brands::get(&kv, tenant_id, id).await? {
+                    Some(b) => Some(b.id),
+                    None => return Response::error("Brand not found", 404),
+                },
+            };
+            if !brands::assign(&kv, tenant_id, channel, field("account_id"), brand_id).await? {
+                return Response::error("Channel not found", 404);
+            }
+            Response::ok("")
+        }
+
+        (method, brand_path) => {
+            let id = brand_path.trim_start_matches('/');
+            let Some(mut brand) = brands::get(&kv, tenant_id, id).await? else {
+                return Response::error("Brand not found", 404);
+            };
+            match method {
+                Method::Get => {
+                    let mut resp = Response::from_html(brand_page_html(&brand, base_url, &locale))?;
+                    resp.headers_mut().set("Cache-Control", "no-store")?;
+                    Ok(resp)
+                }
+
+                Method::Post => {
+                    let form: serde_json::Value = req.json().await?;
+                    let field = |k: &str| {
+                        form.get(k)
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("")
+                            .trim()
+                            .chars()
+                            .take(200)
+                            .collect::<String>()
+                    };
+                    let name = field("name");
+                    if name.is_empty() {
+                        return error("Enter the brand's name.");
+                    }
+                    let hours = match brands::hours_from_form(&form) {
+                        Ok(h) => h,
+                        Err(message) => return error(&message),
+                    };
+                    // KYC fields (PAN, GSTIN, business type) stay with the
+                    // account and aren't edited here.
+                    brand.business.name = name;
+                    brand.business.contact_name = field("contact_name");
+                    brand.business.phone = field("phone");
+                    brand.business.address = field("address");
+                    brand.business.state = field("state");
+                    brand.business.pincode = field("pincode");
+                    brand.hours = hours;
+                    brands::save(&kv, tenant_id, &brand).await?;
+                    Response::from_html(r#"<div class="success">Saved.</div>"#)
+                }
+
+                Method::Delete => {
+                    if brand.is_main() {
+                        return Response::error("The main brand can't be removed", 400);
+                    }
+                    brands::remove(&kv, tenant_id, &brand.id).await?;
+                    let headers = Headers::new();
+                    headers.set("HX-Redirect", &format!("{base_url}/admin/brands"))?;
+                    Ok(Response::empty()?.with_status(200).with_headers(headers))
+                }
+
+                _ => Response::error("Not Found", 404),
+            }
+        }
+    }
+}
+
+fn error(message: &str) -> Result<Response> {
+    Response::from_html(format!(
+        r#"<div class="error">{}</div>"#,
+        html_escape(message)
+    ))
+}
@@ -0,0 +1,131 @@
+//! `/admin/consent/*` routes: opt-out keywords and the suppression list.
+//!
+//! Routes:
+//!   GET  /admin/consent           keywords, suppression list, audit trail
+//!   POST /admin/consent/keywords  save the keyword sets
+//!   POST /admin/consent/opt-out   suppress a sender by hand
+//!   POST /admin/consent/opt-in    lift a suppression
+//!
+//! All routes are authenticated and CSRF-protected by the `handle_admin`
+//! dispatcher. Admin changes land in the audit trail with the signed-in
+//! tenant's email.
+
+use worker::*;
+
+use crate::consent;
+use crate::storage::{get_consent_config, get_tenant, save_consent_config};
+use crate::templates::consent::{consent_page_html, MAX_CONSENT_REPLY, MAX_KEYWORD_SETS};
+use crate::types::{Channel, ConsentChange, ConsentConfig, ConsentKeywords, ConsentSource};
+
+pub async fn handle_consent(
+    mut req: Request,
+    env: Env,
+    path: &str,
+    base_url: &str,
+    tenant_id: &str,
+) -> Result<Response> {
+    let kv = env.kv("KV")?;
+    let db = env.d1("DB")?;
+    let method = req.method();
+    let locale = crate::locale::Locale::from_request(&req);
+
+    match (method, path) {
+        (Method::Get, "/admin/consent") => {
+            let config = get_consent_config(&kv, tenant_id).await?;
+            let suppressions = consent::list_suppressions(&db, tenant_id).await?;
+            let events = consent::list_events(&db, tenant_id).await?;
+            Response::from_html(consent_page_html(
+                &config,
+                &suppressions,
+                &events,
+                base_url,
+                &locale,
+            ))
+        }
+
+        (Method::Post, "/admin/consent/keywords") => {
+            let form: serde_json::Value = req.json().await?;
+            save_consent_config(&kv, tenant_id, &config_from_form(&form)).await?;
+            let headers = Headers::new();
+            headers.set("HX-Refresh", "true")?;
+            Ok(Response::ok("")?.with_headers(headers))
+        }
+
+        (Method::Post, "/admin/consent/opt-out" | "/admin/consent/opt-in") => {
+            let form: serde_json::Value = req.json().await?;
+            let channel = form
+                .get("channel")
+                .and_then(|v| v.as_str())
+                .and_then(Channel::from_wire);
+            let identity = form
+                .get("identity")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .trim();
+            let Some(channel) = channel.filter(|_| !identity.is_empty()) else {
+                return Response::error("Channel and identity are required", 400);
+            };
+            let change = if path.ends_with("opt-out") {
+                ConsentChange::OptOut
+            } else {
+                ConsentChange::OptIn
+            };
+            let by = match get_tenant(&db, tenant_id).await? {
+                Some(t) => t.email,
+                None => tenant_id.to_string(),
+            };
+            consent::record_change(
+                &db,
+                tenant_id,
+                &channel,
+                identity,
+                change,
+                ConsentSource::Admin,
+                Some(&by),
+            )
+            .await?;
+            let headers = Headers::new();
+            headers.set("HX-Refresh", "true")?;
+            Ok(Response::ok("")?.with_headers(headers))
+        }
+
+        _ => Response::error("Not Found", 404),
+    }
+}
+
+/// Keywords kept per list.
+const MAX_KEYWORDS: usize = 30;
+
+fn keyword_list(input: &str) -> Vec<String> {
+    input
+        .split(',')
+        .map(|k| k.trim().to_lowercase())
+        .filter(|k| !k.is_empty())
+        .take(MAX_KEYWORDS)
+        .collect()
+}
+
+/// Read the numbered fieldsets back. Sets without any keywords are dropped,
+/// which is how a language is removed.
+fn config_from_form(form: &serde_json::Value) -> ConsentConfig {
+    let field = |name: String| {
+        form.get(&name)
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .trim()
+            .to_string()
+    };
+    let reply = |name: String| field(name).chars().take(MAX_CONSENT_REPLY).collect();
+    let keyword_sets = (0..=MAX_KEYWORD_SETS)
+        .map(|i| ConsentKeywords {
+            locale: field(format!("locale_{i}")).chars().take(16).collect(),
+            opt_out: keyword_list(&field(format!("opt_out_{i}"))),
+            opt_in: keyword_list(&field(format!("opt_in_{i}"))),
+            opt_out_reply: reply(format!("opt_out_reply_{i}")),
+            opt_in_reply: reply(format!("opt_in_reply_{i}")),
+        })
+        .filter(|set| !set.opt_out.is_empty() || !set.opt_in.is_empty())
+        .take(MAX_KEYWORD_SETS)
+        .collect();
+    ConsentConfig { keyword_sets }
+}
@@ -0,0 +1,108 @@
+//! `/admin/contacts/*` routes: the cross-channel contact book.
+//!
+//! Routes:
+//!   GET  /admin/contacts              contact list (optional ?q= and ?tag=)
+//!   GET  /admin/contacts/{id}         identities, tags, notes, merge
+//!   PUT  /admin/contacts/{id}         save name, tags and notes
+//!   POST /admin/contacts/{id}/merge   fold another contact into this one
+//!
+//! All routes are authenticated and CSRF-protected by the `handle_admin`
+//! dispatcher.
+
+use worker::*;
+
+use crate::consent;
+use crate::contacts;
+use crate::templates::contacts::{contact_page_html, contacts_page_html};
+
+pub async fn handle_contacts(
+    mut req: Request,
+    env: Env,
+    path: &str,
+    base_url: &str,
+    tenant_id: &str,
+) -> Result<Response> {
+    let db = env.d1("DB")?;
+    let method = req.method();
+    let locale = crate::locale::Locale::from_request(&req);
+
+    let rest = path.strip_prefix("/admin/contacts").unwrap_or("");
+
+    match (method, rest) {
+        (Method::Get, "" | "/") => {
+            let url = req.url()?;
+            let param = |name: &str| {
+                url.query_pairs()
+                    .find(|(k, _)| k == name)
+                    .map(|(_, v)| v.into_owned())
+                    .unwrap_or_default()
+            };
+            let (query, tag) = (param("q"), param("tag"));
+            let list = contacts::list_contacts(&db, tenant_id, &query, &tag).await?;
+            Response::from_html(contacts_page_html(&list, &query, &tag, base_url, &locale))
+        }
+
+        (method, contact_path) if contact_path.starts_with('/') => {
+            let parts: Vec<&str> = contact_path.trim_start_matches('/').split('/').collect();
+            let (id, action) = match parts.as_slice() {
+                [id] => (*id, ""),
+                [id, action] => (*id, *action),
+                _ => return Response::error("Not Found", 404),
+            };
+            let Some(contact) = contacts::get_contact(&db, tenant_id, id).await? else {
+                return Response::error("Contact not found", 404);
+            };
+
+            match (method, action) {
+                (Method::Get, "") => {
+                    let identities = contacts::list_identities(&db, &contact.id).await?;
+                    let others = contacts::list_contacts(&db, tenant_id, "", "").await?;
+                    let suppressed =
+                        consent::contact_suppressed(&db, tenant_id, &contact.id).await?;
+                    Response::from_html(contact_page_html(
+                        &contact,
+                        &identities,
+                        &others,
+                        suppressed,
+                        base_url,
+                        &locale,
+                    ))
+                }
+                (Method::Put, "") => {
+                    let form: serde_json::Value = req.json().await?;
+                    let field = |k: &str| form.get(k).and_then(|v| v.as_str()).unwrap_or("");
+                    let name: String = field("name").chars().take(120).collect();
+                    contacts::update_contact(
+                        &db,
+                        tenant_id,
+                        &contact.id,
+                        &name,
+                        &contacts::parse_tags(field("tags")),
+                        field("notes"),
+                    )
+                    .await?;
+                    Response::from_html(r#"<div class="success">Saved.</div>"#)
+                }
+                (Method::Post, "merge") => {
+                    let form: serde_json::Value = req.json().await?;
+                    let from_id = form.get("from").and_then(|v| v.as_str()).unwrap_or("");
+                    let from = match contacts::get_contact(&db, tenant_id, from_id).await? {
+                        Some(from) if from.id != contact.id => from,
+                        _ => {
+                            return Response::from_html(
+                                r#"<div class="error">Pick another contact to merge.</div>"#,
+                            )
+                        }
+                    };
+                    contacts::merge(&db, tenant_id, &contact, &from).await?;
+                    let headers = Headers::new();
+                    headers.set("HX-Refresh", "true")?;
+                    Ok(Response::ok("")?.with_headers(headers))
+                }
+                _ => Response::error("Not Found", 404),
+            }
+        }
+
+        _ => Response::error("Not Found", 404),
+    }
+}
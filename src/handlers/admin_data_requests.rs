@@ -0,0 +1,149 @@
+//! `/admin/data-requests/*` routes: a customer's export and erasure
+//! requests.
+//!
+//! Routes:
+//!   GET  /admin/data-requests          request form and past receipts
+//!                                      (optional ?channel= and ?identity=)
+//!   POST /admin/data-requests/export   JSON download of every record
+//!   POST /admin/data-requests/erase    delete those records
+//!
+//! All routes are authenticated and CSRF-protected by the `handle_admin`
+//! dispatcher. The export is a plain form post so the browser downloads
+//! it; the erase goes through HTMX. Both write a receipt to the audit log.
+
+use worker::*;
+
+use crate::data_requests::{self, Receipt, Records, Subject};
+use crate::inbox;
+use crate::storage::get_tenant;
+use crate::templates::data_requests::{data_requests_page_html, receipt_html};
+use crate::types::{Channel, DataRequestKind};
+
+pub async fn handle_data_requests(
+    mut req: Request,
+    env: Env,
+    path: &str,
+    base_url: &str,
+    tenant_id: &str,
+) -> Result<Response> {
+    let kv = env.kv("KV")?;
+    let db = env.d1("DB")?;
+    let method = req.method();
+    let locale = crate::locale::Locale::from_request(&req);
+
+    match (method, path) {
+        (Method::Get, "/admin/data-requests") => {
+            let url = req.url()?;
+            let param = |name: &str| {
+                url.query_pairs()
+                    .find(|(k, _)| k == name)
+                    .map(|(_, v)| v.into_owned())
+                    .unwrap_or_default()
+            };
+            let channel = Channel::from_wire(&param("channel"));
+            let receipts = data_requests::list_receipts(&db, tenant_id).await?;
+            Response::from_html(data_requests_page_html(
+                channel.as_ref(),
+                &param("identity"),
+                &receipts,
+                base_url,
+                &locale,
+            ))
+        }
+
+        (Method::Post, "/admin/data-requests/export") => {
+            let form = req.form_data().await?;
+            let field = |name: &str| match form.get(name) {
+                Some(FormEntry::Field(v)) => v,
+                _ => String::new(),
+            };
+            let Some(subject) =
+                subject_from(&db, tenant_id, &field("channel"), &field("identity")).await?
+            else {
+                return Response::error("Channel and identifier are required", 400);
+            };
+            let key = inbox::encryption_key(&env).ok();
+            let records =
+                data_requests::collect(&kv, &db, key.as_deref(), tenant_id, &subject).await?;
+            let receipt =
+                record(&db, tenant_id, DataRequestKind::Export, &subject, &records).await?;
+
+            let export = serde_json::json!({
+                "receipt": receipt,
+                "subject": {
+                    "channel": subject.channel.as_str(),
+                    "identity": subject.identity,
+                    "contact_id": subject.contact_id,
+                    "identities": subject
+                        .identities
+                        .iter()
+                        .map(|(c, i)| serde_json::json!({ "channel": c.as_str(), "identity": i }))
+                        .collect::<Vec<_>>(),
+                },
+                "records": records,
+            });
+            let headers = Headers::new();
+            headers.set("Content-Type", "application/json")?;
+            headers.set(
+                "Content-Disposition",
+                &format!("attachment; filename=\"data-request-{}.json\"", receipt.id),
+            )?;
+            Ok(Response::ok(serde_json::to_string_pretty(&export)?)?.with_headers(headers))
+        }
+
+        (Method::Post, "/admin/data-requests/erase") => {
+            let form: serde_json::Value = req.json().await?;
+            let field = |k: &str| form.get(k).and_then(|v| v.as_str()).unwrap_or("");
+            let Some(subject) =
+                subject_from(&db, tenant_id, field("channel"), field("identity")).await?
+            else {
+                return Response::from_html(
+                    r#"<div class="error">Pick a channel and enter an identifier.</div>"#,
+                );
+            };
+            // Erase exactly what an export would have returned, so the
+            // receipt's digest covers what was deleted.
+            let records = data_requests::collect(&kv, &db, None, tenant_id, &subject).await?;
+            data_requests::erase(&kv, &db, tenant_id, &subject, &records).await?;
+            let receipt =
+                record(&db, tenant_id, DataRequestKind::Erasure, &subject, &records).await?;
+            Response::from_html(receipt_html(&receipt))
+        }
+
+        _ => Response::error("Not Found", 404),
+    }
+}
+
+async fn subject_from(
+    db: &D1Database,
+    tenant_id: &str,
+    channel: &str,
+    identity: &str,
+) -> Result<Option<Subject>> {
+    let (Some(channel), identity) = (Channel::from_wire(channel), identity.trim()) else {
+        return Ok(None);
+    };
+    if identity.is_empty() {
+        return Ok(None);
+    }
+    data_requests::resolve_subject(db, tenant_id, channel, identity)
+        .await
+        .map(Some)
+}
+
+/// Build the receipt and log it under the signed-in tenant's email.
+async fn record(
+    db: &D1Database,
+    tenant_id: &str,
+    kind: DataRequestKind,
+    subject: &Subject,
+    records: &Records,
+) -> Result<Receipt> {
+    let receipt = data_requests::receipt(kind, tenant_id, subject, records);
+    let by = match get_tenant(db, tenant_id).await? {
+        Some(t) => t.email,
+        None => tenant_id.to_string(),
+    };
+    data_requests::log_receipt(db, &by, &receipt).await?;
+    Ok(receipt)
+}
@@ -104,6 +104,7 @@ pub async fn handle_email_admin(
                 tenant_id: tenant_id.to_string(),
                 auto_reply: ReplyConfig::default(),
                 notification_recipients: vec![owner],
+                brand_id: None,
                 created_at: now.clone(),
                 updated_at: now,
             };
@@ -159,6 +160,8 @@ pub async fn handle_email_admin(
                 .get("wait_seconds")
                 .and_then(|v| v.as_u64())
                 .unwrap_or(5) as u32;
+            addr.auto_reply
+                .apply_buffer_form(|k| json_form_field(&form, k));
             addr.updated_at = now_iso();
 
             save_email_address(&kv, tenant_id, &addr).await?;
@@ -0,0 +1,83 @@
+//! `/admin/export/*` routes: the tenant's own data export.
+//!
+//! Routes:
+//!   GET  /admin/export        export list fragment for the settings page
+//!   POST /admin/export        request an export
+//!   GET  /admin/export/{id}   download a finished archive
+//!
+//! All routes are authenticated and CSRF-protected by the `handle_admin`
+//! dispatcher.
+
+use worker::*;
+
+use crate::exports;
+use crate::management::audit::log_action;
+use crate::storage::get_tenant;
+use crate::templates::exports_list_html;
+use crate::types::ExportStatus;
+
+pub async fn handle_exports(
+    req: Request,
+    env: Env,
+    path: &str,
+    base_url: &str,
+    tenant_id: &str,
+) -> Result<Response> {
+    let db = env.d1("DB")?;
+    let method = req.method();
+    let locale = crate::locale::Locale::from_request(&req);
+
+    match (method, path) {
+        (Method::Get, "/admin/export") => {
+            let list = exports::list_exports(&db, tenant_id).await?;
+            Response::from_html(exports_list_html(&list, base_url, &locale))
+        }
+
+        (Method::Post, "/admin/export") => {
+            let export = exports::request(&env, tenant_id).await?;
+            let by = match get_tenant(&db, tenant_id).await? {
+                Some(t) => t.email,
+                None => tenant_id.to_string(),
+            };
+            let details = serde_json::json!({
+                "tenant_id": tenant_id,
+                "status": export.status.as_str(),
+            });
+            log_action(
+                &db,
+                &by,
+                "tenant_export",
+                "tenant",
+                Some(tenant_id),
+                Some(&details),
+            )
+            .await?;
+            let list = exports::list_exports(&db, tenant_id).await?;
+            Response::from_html(exports_list_html(&list, base_url, &locale))
+        }
+
+        (Method::Get, export_path) => {
+            let id = export_path.strip_prefix("/admin/export/").unwrap_or("");
+            let export = match exports::get_export(&db, tenant_id, id).await? {
+                Some(e) if e.status == ExportStatus::Ready && !e.expired => e,
+                _ => return Response::error("Export not found", 404),
+            };
+            let kv = env.kv("KV")?;
+            let Some(bytes) = exports::archive_bytes(&kv, &export).await? else {
+                return Response::error("Export expired", 410);
+            };
+            let headers = Headers::new();
+            headers.set("Content-Type", "application/zip")?;
+            headers.set(
+                "Content-Disposition",
+                &format!(
+                    "attachment; filename=\"concierge-export-{}.zip\"",
+                    export.created_at.get(..10).unwrap_or("")
+                ),
+            )?;
+            Ok(Response::from_bytes(bytes)?.with_headers(headers))
+        }
+
+        _ => Response::error("Not Found", 404),
+    }
+}
@@ -0,0 +1,164 @@
+//! `/admin/guardrails` — per-tenant output checks on AI drafts.
+//!
+//! Routes:
+//!   GET  /admin/guardrails           settings + simulator
+//!   POST /admin/guardrails           save settings
+//!   POST /admin/guardrails/simulate  run the checks on a pasted draft
+//!   POST /admin/guardrails/redaction PII classes masked before model calls
+//!   POST /admin/guardrails/injection what happens to flagged messages
+//!
+//! The simulator never sends or queues anything. Prompt-leak checks run
+//! against the persona prompt, since a pasted draft has no rule.
+
+use worker::*;
+
+use crate::guardrails::{self, MAX_LIST_ENTRIES, MAX_POLICY};
+use crate::storage::{
+    get_guardrails, get_injection_config, get_onboarding, get_redaction_config, injection_totals,
+    redaction_totals, save_guardrails, save_injection_config, save_redaction_config,
+};
+use crate::templates::guardrails::{
+    guardrails_admin_html, simulation_html, MAX_HOLDING_REPLY, REDACTION_AUDIT_DAYS,
+};
+use crate::types::{GuardrailConfig, InjectionConfig, PiiClass, RedactionConfig};
+
+pub async fn handle_guardrails_admin(
+    mut req: Request,
+    env: Env,
+    path: &str,
+    base_url: &str,
+    tenant_id: &str,
+) -> Result<Response> {
+    let kv = env.kv("KV")?;
+    let method = req.method();
+    let locale = crate::locale::Locale::from_request(&req);
+    let config = get_guardrails(&kv, tenant_id).await?;
+
+    match (method, path) {
+        (Method::Get, "/admin/guardrails") => {
+            let db = env.d1("DB")?;
+            let redaction = get_redaction_config(&kv, tenant_id).await?;
+            let totals = redaction_totals(&db, tenant_id, REDACTION_AUDIT_DAYS).await?;
+            let injection = get_injection_config(&kv, tenant_id).await?;
+            let held = injection_totals(&db, tenant_id, REDACTION_AUDIT_DAYS).await?;
+            Response::from_html(guardrails_admin_html(
+                &config, &redaction, &totals, &injection, held, base_url, &locale,
+            ))
+        }
+
+        (Method::Post, "/admin/guardrails") => {
+            let form: serde_json::Value = req.json().await?;
+            save_guardrails(&kv, tenant_id, &config_from_form(&form)).await?;
+            Response::from_html(format!(
+                r#"<div class="success">{}</div>"#,
+                crate::i18n::t(&locale, "admin-guardrails-saved")
+            ))
+        }
+
+        (Method::Post, "/admin/guardrails/simulate") => {
+            let form: serde_json::Value = req.json().await?;
+            let draft: String = form
+                .get("draft")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .chars()
+                .take(2000)
+                .collect();
+            let prompt = get_onboarding(&kv, tenant_id)
+                .await?
+                .persona
+                .active_prompt();
+            let ctx = guardrails::Context {
+                config: &config,
+                system_prompt: &prompt,
+                sender: "",
+            };
+            let hits = guardrails::check(&draft, &ctx);
+            let policy = if config.llm_policy {
+                Some(guardrails::policy_review(&env, tenant_id, &draft, &config).await)
+            } else {
+                None
+            };
+            Response::from_html(simulation_html(&config, &hits, policy.as_ref(), &locale))
+        }
+
+        (Method::Post, "/admin/guardrails/redaction") => {
+            let form: serde_json::Value = req.json().await?;
+            let redaction = RedactionConfig {
+                enabled: form.get("enabled").is_some(),
+                classes: PiiClass::ALL
+                    .iter()
+                    .copied()
+                    .filter(|c| form.get(format!("pii_{}", c.as_str())).is_some())
+                    .collect(),
+            };
+            save_redaction_config(&kv, tenant_id, &redaction).await?;
+            Response::from_html(format!(
+                r#"<div class="success">{}</div>"#,
+                crate::i18n::t(&locale, "admin-redaction-saved")
+            ))
+        }
+
+        (Method::Post, "/admin/guardrails/injection") => {
+            let form: serde_json::Value = req.json().await?;
+            save_injection_config(&kv, tenant_id, &injection_from_form(&form)).await?;
+            Response::from_html(format!(
+                r#"<div class="success">{}</div>"#,
+                crate::i18n::t(&locale, "admin-injection-saved")
+            ))
+        }
+
+        _ => Response::error("Not Found", 404),
+    }
+}
+
+/// One entry per line or comma, trimmed and capped.
+fn form_list(form: &serde_json::Value, key: &str) -> Vec<String> {
+    form.get(key)
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .split(['\n', ','])
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .take(MAX_LIST_ENTRIES)
+        .map(str::to_string)
+        .collect()
+}
+
+fn injection_from_form(form: &serde_json::Value) -> InjectionConfig {
+    InjectionConfig {
+        send_holding_reply: form.get("send_holding_reply").is_some(),
+        reply_text: form
+            .get("reply_text")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .trim()
+            .chars()
+            .take(MAX_HOLDING_REPLY)
+            .collect(),
+        relay_to_human: form.get("relay_to_human").is_some(),
+        allowed_senders: form_list(form, "allowed_senders"),
+    }
+}
+
+fn config_from_form(form: &serde_json::Value) -> GuardrailConfig {
+    let list = |k: &str| form_list(form, k);
+
+    GuardrailConfig {
+        check_urls: form.get("check_urls").is_some(),
+        allowed_domains: list("allowed_domains"),
+        check_contacts: form.get("check_contacts").is_some(),
+        business_phones: list("business_phones"),
+        business_emails: list("business_emails"),
+        check_prompt_leak: form.get("check_prompt_leak").is_some(),
+        llm_policy: form.get("llm_policy").is_some(),
+        policy: form
+            .get("policy")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .trim()
+            .chars()
+            .take(MAX_POLICY)
+            .collect(),
+    }
+}
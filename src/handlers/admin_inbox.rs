@@ -0,0 +1,221 @@
+//! `/admin/inbox/*` routes: retained conversations across channels and
+//! replies sent from the web.
+//!
+//! Routes:
+//!   GET  /admin/inbox               conversation list (optional ?status=)
+//!   GET  /admin/inbox/list          list fragment, refetched on SSE pings
+//!   GET  /admin/inbox/stream        SSE subscription (tenant's ApprovalsDO)
+//!   PUT  /admin/inbox/settings      set how long message text is kept (0 = off)
+//!   GET  /admin/inbox/{id}          thread + reply box
+//!   GET  /admin/inbox/{id}/thread   thread fragment
+//!   POST /admin/inbox/{id}/reply    send through the origin channel
+//!
+//! All routes are authenticated and CSRF-protected by the `handle_admin`
+//! dispatcher.
+
+use worker::*;
+
+use crate::channel;
+use crate::helpers::{generate_id, now_iso};
+use crate::inbox;
+use crate::storage::{get_tenant, save_message, save_tenant, MessageRow};
+use crate::templates::inbox::{
+    conversation_page_html, inbox_list_html, inbox_page_html, thread_html,
+};
+use crate::types::{Channel, ConversationStatus, MessageAction, MessageDirection};
+
+pub async fn handle_inbox(
+    mut req: Request,
+    env: Env,
+    path: &str,
+    base_url: &str,
+    tenant_id: &str,
+) -> Result<Response> {
+    let db = env.d1("DB")?;
+    let method = req.method();
+    let locale = crate::locale::Locale::from_request(&req);
+    let filter = req
+        .url()?
+        .query_pairs()
+        .find(|(k, _)| k == "status")
+        .and_then(|(_, v)| ConversationStatus::from_wire(&v));
+
+    let rest = path.strip_prefix("/admin/inbox").unwrap_or("");
+
+    match (method, rest) {
+        (Method::Get, "" | "/") => {
+            let retention_days = get_tenant(&db, tenant_id)
+                .await?
+                .map(|t| t.retention_days)
+                .unwrap_or(0);
+            let conversations = if retention_days > 0 {
+                let key = inbox::encryption_key(&env)?;
+                inbox::list_conversations(&db, &key, tenant_id, filter).await?
+            } else {
+                Vec::new()
+            };
+            Response::from_html(inbox_page_html(
+                &conversations,
+                retention_days,
+                filter,
+                base_url,
+                &locale,
+            ))
+        }
+
+        (Method::Get, "/list") => {
+            let key = inbox::encryption_key(&env)?;
+            let conversations = inbox::list_conversations(&db, &key, tenant_id, filter).await?;
+            Response::from_html(inbox_list_html(&conversations, filter))
+        }
+
+        (Method::Get, "/stream") => {
+            let ns = env.durable_object("APPROVALS_DO")?;
+            let stub = ns.id_from_name(tenant_id)?.get_stub()?;
+            stub.fetch_with_str("https://do.invalid/subscribe").await
+        }
+
+        (Method::Put, "/settings") => {
+            let form: serde_json::Value = req.json().await?;
+            // The select posts a string; accept a number too.
+            let requested = form
+                .get("retention_days")
+                .and_then(|v| {
+                    v.as_u64()
+                        .or_else(|| v.as_str().and_then(|s| s.parse().ok()))
+                })
+                .unwrap_or(0);
+            let days = inbox::retention_window(u32::try_from(requested).unwrap_or(0));
+            if let Some(mut tenant) = get_tenant(&db, tenant_id).await? {
+                if tenant.retention_days != days {
+                    tenant.retention_days = days;
+                    tenant.updated_at = now_iso();
+                    save_tenant(&db, &tenant).await?;
+                    if days == 0 {
+                        inbox::forget(&db, tenant_id).await?;
+                    }
+                }
+            }
+            let headers = Headers::new();
+            headers.set("HX-Refresh", "true")?;
+            Ok(Response::ok("")?.with_headers(headers))
+        }
+
+        (method, conversation_path) if conversation_path.starts_with('/') => {
+            let parts: Vec<&str> = conversation_path
+                .trim_start_matches('/')
+                .split('/')
+                .collect();
+            let (id, action) = match parts.as_slice() {
+                [id] => (*id, ""),
+                [id, action] => (*id, *action),
+                _ => return Response::error("Not Found", 404),
+            };
+            let key = inbox::encryption_key(&env)?;
+            let Some(conversation) = inbox::get_conversation(&db, &key, tenant_id, id).await?
+            else {
+                return Response::error("Conversation not found", 404);
+            };
+
+            match (method, action) {
+                (Method::Get, "") => {
+                    let messages = inbox::list_messages(&db, &key, &conversation.id).await?;
+                    Response::from_html(conversation_page_html(
+                        &conversation,
+                        &messages,
+                        base_url,
+                        &locale,
+                    ))
+                }
+                (Method::Get, "thread") => {
+                    let messages = inbox::list_messages(&db, &key, &conversation.id).await?;
+                    Response::from_html(thread_html(&conversation, &messages))
+                }
+                (Method::Post, "reply") => {
+                    let form: serde_json::Value = req.json().await?;
+                    let text: String = form
+                        .get("text")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("")
+                        .trim()
+                        .chars()
+                        .take(2000)
+                        .collect();
+                    if text.is_empty() {
+                        return Response::from_html(
+                            r#"<div class="error">Reply can't be empty.</div>"#,
+                        );
+                    }
+                    reply(&env, &db, tenant_id, &conversation, &text).await
+                }
+                _ => Response::error("Not Found", 404),
+            }
+        }
+
+        _ => Response::error("Not Found", 404),
+    }
+}
+
+/// Send a human reply through the conversation's channel and log it the
+/// way the Discord relay does.
+async fn reply(
+    env: &Env,
+    db: &D1Database,
+    tenant_id: &str,
+    conversation: &inbox::Conversation,
+    text: &str,
+) -> Result<Response> {
+    let subject = if conversation.channel == Channel::Email {
+        Some("Re: your message")
+    } else {
+        None
+    };
+
+    if let Err(e) = channel::send_reply(
+        &conversation.channel,
+        env,
+        tenant_id,
+        &conversation.reply_metadata,
+        &conversation.contact,
+        channel::Body::Verbatim(text),
+        subject,
+    )
+    .await
+    {
+        console_log!("Inbox reply error: {e:?}");
+        return Response::from_html(format!(
+            r#"<div class="error">Failed to send: {}</div>"#,
+            crate::helpers::html_escape(&e.to_string())
+        ));
+    }
+
+    let _ = save_message(
+        db,
+        &generate_id(),
+        &MessageRow {
+            channel: &conversation.channel,
+            direction: MessageDirection::Relay,
+            sender: &conversation.recipient,
+            recipient: &conversation.contact,
+            tenant_id,
+            channel_account_id: &conversation.channel_account_id,
+            action_taken: Some(MessageAction::Relay),
+            credits: 0,
+            approval_id: None,
+        },
+    )
+    .await;
+    inbox::record_reply(
+        env,
+        tenant_id,
+        &conversation.channel,
+        &conversation.contact,
+        Some(text),
+        MessageAction::Relay,
+    )
+    .await;
+
+    let headers = Headers::new();
+    headers.set("HX-Trigger", "inbox-replied")?;
+    Ok(Response::from_html(r#"<div class="success">Sent.</div>"#)?.with_headers(headers))
+}
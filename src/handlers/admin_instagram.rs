@@ -84,6 +84,10 @@ pub async fn handle_instagram_admin(
                     account.auto_reply.wait_seconds = n.min(30);
                 }
             }
+            account.auto_reply.apply_buffer_form(|k| match form.get(k) {
+                Some(FormEntry::Field(v)) => Some(v),
+                _ => None,
+            });
 
             account.updated_at = crate::helpers::now_iso();
             save_instagram_account(&kv, &account).await?;
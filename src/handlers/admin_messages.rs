@@ -0,0 +1,129 @@
+//! `/admin/messages` routes: the searchable message log.
+//!
+//! Routes:
+//!   GET  /admin/messages           filter form and the first page
+//!   GET  /admin/messages/rows      the next page (`?after=`), as table rows
+//!   GET  /admin/messages.csv       every row matching the filter, as CSV
+//!
+//! All take the filter as query parameters (see `message_log::Filter`) and
+//! are authenticated by the `handle_admin` dispatcher.
+
+use worker::*;
+
+use crate::message_log::{self, Cursor, Filter, PAGE_SIZE};
+use crate::storage::{
+    get_discord_config_by_tenant, list_instagram_accounts, list_whatsapp_accounts,
+};
+use crate::templates::messages::{message_rows_html, messages_page_html, AccountOption};
+use crate::types::Channel;
+
+pub async fn handle_messages(
+    req: Request,
+    env: Env,
+    path: &str,
+    base_url: &str,
+    tenant_id: &str,
+) -> Result<Response> {
+    let db = env.d1("DB")?;
+    let locale = crate::locale::Locale::from_request(&req);
+    let url = req.url()?;
+    let filter = Filter::from_url(&url);
+
+    match (req.method(), path) {
+        (Method::Get, "/admin/messages" | "/admin/messages/") => {
+            let page = message_log::search(&db, tenant_id, &filter, None, PAGE_SIZE).await?;
+            let (accounts, rules) = message_log::facets(&db, tenant_id).await?;
+            let accounts = account_labels(&env, tenant_id, &accounts).await?;
+            let mut resp = Response::from_html(messages_page_html(
+                &page, &filter, &accounts, &rules, base_url, &locale,
+            ))?;
+            resp.headers_mut().set("Cache-Control", "no-store")?;
+            Ok(resp)
+        }
+
+        (Method::Get, "/admin/messages/rows") => {
+            let after = url
+                .query_pairs()
+                .find(|(k, _)| k == "after")
+                .and_then(|(_, v)| Cursor::decode(&v));
+            let Some(after) = after else {
+                return Response::error("Missing cursor", 400);
+            };
+            let page =
+                message_log::search(&db, tenant_id, &filter, Some(&after), PAGE_SIZE).await?;
+            let seen: Vec<(String, String)> = page
+                .rows
+                .iter()
+                .map(|r| (r.channel.clone(), r.channel_account_id.clone()))
+                .collect();
+            let accounts = account_labels(&env, tenant_id, &seen).await?;
+            Response::from_html(message_rows_html(
+                &page.rows,
+                page.next.as_ref(),
+                &filter,
+                &accounts,
+                base_url,
+            ))
+        }
+
+        (Method::Get, "/admin/messages.csv") => {
+            let (rows, truncated) = message_log::export(&db, tenant_id, &filter).await?;
+            let headers = Headers::new();
+            headers.set("Content-Type", "text/csv; charset=utf-8")?;
+            headers.set(
+                "Content-Disposition",
+                "attachment; filename=\"concierge-messages.csv\"",
+            )?;
+            headers.set("Cache-Control", "no-store")?;
+            if truncated {
+                headers.set("X-Truncated", "true")?;
+            }
+            Ok(Response::ok(message_log::csv(&rows))?.with_headers(headers))
+        }
+
+        _ => Response::error("Not Found", 404),
+    }
+}
+
+/// Name each `(channel, channel_account_id)` the log mentions: the
+/// WhatsApp account name, the Instagram handle, the email address or the
+/// Discord server. Accounts since removed keep their raw id.
+async fn account_labels(
+    env: &Env,
+    tenant_id: &str,
+    accounts: &[(String, String)],
+) -> Result<Vec<AccountOption>> {
+    let kv = env.kv("KV")?;
+    let whatsapp = list_whatsapp_accounts(&kv, tenant_id).await?;
+    let instagram = list_instagram_accounts(&kv, tenant_id).await?;
+    let discord = get_discord_config_by_tenant(&kv, tenant_id).await?;
+    let email_domain = env
+        .var("EMAIL_DOMAIN")
+        .map(|v| v.to_string())
+        .unwrap_or_default();
+
+    Ok(accounts
+        .iter()
+        .map(|(channel, id)| {
+            let name = match Channel::from_wire(channel) {
+                Some(Channel::WhatsApp) => whatsapp
+                    .iter()
+                    .find(|a| a.id == *id)
+                    .map(|a| format!("{} ({})", a.name, a.phone_number)),
+                Some(Channel::Instagram) => instagram
+                    .iter()
+                    .find(|a| a.id == *id)
+                    .map(|a| format!("@{}", a.instagram_username)),
+                Some(Channel::Email) if !email_domain.is_empty() => {
+                    Some(format!("{id}@{email_domain}"))
+                }
+                Some(Channel::Discord) => discord
+                    .as_ref()
+                    .filter(|d| d.guild_id == *id)
+                    .and_then(|d| d.guild_name.clone()),
+                _ => None,
+            };
+            (id.clone(), name.unwrap_or_else(|| id.clone()))
+        })
+        .collect())
+}
@@ -0,0 +1,129 @@
+//! `/admin/notifications` routes: the notification center and the bell.
+//!
+//! Routes:
+//!   GET  /admin/notifications            list and delivery choices
+//!   GET  /admin/notifications/bell       the bell's icon and unread count
+//!   GET  /admin/notifications/stream     SSE; `notification-changed` refreshes the bell
+//!   GET  /admin/notifications/{id}/open  mark read and go to its page
+//!   POST /admin/notifications/read-all   mark everything read
+//!   POST /admin/notifications/delivery   save the email / Discord kinds
+//!
+//! Authenticated and CSRF-protected by the `handle_admin` dispatcher.
+
+use worker::*;
+
+use crate::notifications::{self, PAGE_LIMIT};
+use crate::storage::{get_discord_config_by_tenant, get_onboarding, save_onboarding};
+use crate::templates::notifications::{bell_html, notifications_page_html};
+use crate::types::NotificationKind;
+
+pub async fn handle_notifications(
+    mut req: Request,
+    env: Env,
+    path: &str,
+    base_url: &str,
+    tenant_id: &str,
+) -> Result<Response> {
+    let db = env.d1("DB")?;
+    let locale = crate::locale::Locale::from_request(&req);
+    let rest = path.strip_prefix("/admin/notifications").unwrap_or("");
+
+    match (req.method(), rest) {
+        (Method::Get, "" | "/") => {
+            let kv = env.kv("KV")?;
+            let rows = notifications::list(&db, tenant_id, PAGE_LIMIT).await?;
+            let config = get_onboarding(&kv, tenant_id).await?.notifications;
+            let discord_channel = get_discord_config_by_tenant(&kv, tenant_id)
+                .await?
+                .and_then(|c| c.approval_channel_id)
+                .is_some();
+            let mut resp = Response::from_html(notifications_page_html(
+                &rows,
+                &config,
+                discord_channel,
+                base_url,
+                &locale,
+            ))?;
+            resp.headers_mut().set("Cache-Control", "no-store")?;
+            Ok(resp)
+        }
+
+        (Method::Get, "/bell") => {
+            let (count, worst) = notifications::unread(&db, tenant_id).await?;
+            let mut resp = Response::from_html(bell_html(count, worst, base_url))?;
+            resp.headers_mut().set("Cache-Control", "no-store")?;
+            Ok(resp)
+        }
+
+        (Method::Get, "/stream") => {
+            let ns = env.durable_object("APPROVALS_DO")?;
+            let stub = ns.id_from_name(tenant_id)?.get_stub()?;
+            stub.fetch_with_str("https://do.invalid/subscribe").await
+        }
+
+        (Method::Post, "/read-all") => {
+            notifications::mark_read(&db, tenant_id, None).await?;
+            changed(&env, tenant_id).await;
+            let headers = Headers::new();
+            headers.set("HX-Refresh", "true")?;
+            Ok(Response::ok("")?.with_headers(headers))
+        }
+
+        (Method::Post, "/delivery") => {
+            let form: serde_json::Value = req.json().await?;
+            let picked = |prefix: &str| -> Vec<NotificationKind> {
+                NotificationKind::ALL
+                    .into_iter()
+                    .filter(|k| {
+                        form.get(format!("{prefix}_{}", k.as_str()))
+                            .and_then(|v| v.as_str())
+                            == Some("true")
+                    })
+                    .collect()
+            };
+            let kv = env.kv("KV")?;
+            let mut state = get_onboarding(&kv, tenant_id).await?;
+            state.notifications.email_kinds = picked("email");
+            state.notifications.discord_kinds = picked("discord")
+                .into_iter()
+                .filter(|k| k.discord_deliverable())
+                .collect();
+            save_onboarding(&kv, tenant_id, &state).await?;
+            let headers = Headers::new();
+            headers.set("HX-Refresh", "true")?;
+            Ok(Response::ok("")?.with_headers(headers))
+        }
+
+        (Method::Get, open) if open.ends_with("/open") => {
+            let id = open
+                .trim_start_matches('/')
+                .trim_end_matches("/open")
+                .trim_end_matches('/');
+            let Some(notification) = notifications::get(&db, tenant_id, id).await? else {
+                return Response::error("Notification not found", 404);
+            };
+            if notification.read_at.is_none() {
+                notifications::mark_read(&db, tenant_id, Some(id)).await?;
+                changed(&env, tenant_id).await;
+            }
+            // Links are stored as admin paths; anything else goes to the list.
+            let target = if notification.link.starts_with("/admin") {
+                notification.link
+            } else {
+                "/admin/notifications".to_string()
+            };
+            let headers = Headers::new();
+            headers.set("Location", &format!("{base_url}{target}"))?;
+            Ok(Response::empty()?.with_status(302).with_headers(headers))
+        }
+
+        _ => Response::error("Not Found", 404),
+    }
+}
+
+/// Tell the tenant's other tabs to refresh their bell.
+async fn changed(env: &Env, tenant_id: &str) {
+    if let Err(e) = crate::approvals::broadcast(env, tenant_id, "notification-changed").await {
+        console_log!("Notification broadcast failed for tenant {tenant_id}: {e:?}");
+    }
+}
@@ -1,16 +1,23 @@
 //! `/admin/persona` — read + edit the tenant's AI persona.
 //!
+//! `?brand={id}` edits another brand's persona (see `brands`) and
+//! `?channel={ch}&account={id}` a channel account's own persona, which
+//! replaces its brand's (`DELETE` goes back to the brand's). Without
+//! either the page edits the main brand's.
+//!
 //! The persona has three modes (PersonaSource): Preset / Builder / Custom.
 //! Each save recomputes `active_prompt_hash`; if it differs from the
 //! last-vetted hash, the safety check is re-enqueued.
 
 use worker::*;
 
+use crate::brands;
 use crate::personas;
-use crate::storage::{get_onboarding, save_onboarding};
-use crate::templates::persona::persona_admin_html;
+use crate::storage::{get_reply_config, update_reply_config};
+use crate::templates::persona::{persona_admin_html, PersonaScope};
 use crate::types::{
-    PersonaBuilder, PersonaConfig, PersonaPreset, PersonaSafety, PersonaSafetyStatus, PersonaSource,
+    Brand, Channel, PersonaBuilder, PersonaConfig, PersonaPreset, PersonaSafety,
+    PersonaSafetyStatus, PersonaSource,
 };
 
 /// Maximum length of the user-provided custom prompt. Mirrors the value
@@ -28,11 +35,59 @@ pub async fn handle_persona_admin(
     let kv = env.kv("KV")?;
     let method = req.method();
     let locale = crate::locale::Locale::from_request(&req);
-    let mut state = get_onboarding(&kv, tenant_id).await?;
+    let url = req.url()?;
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.into_owned())
+    };
+    let channel = param("channel").and_then(|c| Channel::from_wire(&c));
+    let account_id = param("account").unwrap_or_default();
+
+    // The brand whose persona is edited, or whose persona the channel
+    // account replaces; and the account's own persona when it has one.
+    let (mut brand, own) = match &channel {
+        Some(channel) => {
+            let Some(cfg) = get_reply_config(&kv, tenant_id, channel, &account_id).await? else {
+                return Response::error("Channel not found", 404);
+            };
+            let brand = brands::of_account(&kv, tenant_id, channel, &account_id).await?;
+            (brand, cfg.persona)
+        }
+        None => {
+            let brand_id = param("brand").unwrap_or_else(|| Brand::MAIN.to_string());
+            let Some(brand) = brands::get(&kv, tenant_id, &brand_id).await? else {
+                return Response::error("Brand not found", 404);
+            };
+            (brand, None)
+        }
+    };
+    let scope = match &channel {
+        Some(channel) => PersonaScope::Channel {
+            channel,
+            account_id: &account_id,
+            brand: &brand,
+            own: own.is_some(),
+        },
+        None => PersonaScope::Brand(&brand),
+    };
+    let query = scope.query();
+    // What the page shows and a save starts from.
+    let current = own.clone().unwrap_or_else(|| brand.persona.clone());
 
     match (method, path) {
         (Method::Get, "/admin/persona") => {
-            Response::from_html(persona_admin_html(&state.persona, base_url, &locale))
+            Response::from_html(persona_admin_html(&current, &scope, base_url, &locale))
+        }
+
+        (Method::Delete, "/admin/persona") => {
+            let Some(channel) = &channel else {
+                return Response::error("Only a channel's own persona can be removed", 400);
+            };
+            update_reply_config(&kv, tenant_id, channel, &account_id, |c| c.persona = None).await?;
+            let headers = Headers::new();
+            headers.set("HX-Redirect", &format!("{base_url}/admin/persona{query}"))?;
+            Ok(Response::empty()?.with_status(200).with_headers(headers))
         }
 
         (Method::Post, "/admin/persona") => {
@@ -105,11 +160,11 @@ pub async fn handle_persona_admin(
             // keep the existing safety verdict so the badge doesn't flicker.
             let mut new_persona = PersonaConfig {
                 source: new_source,
-                safety: state.persona.safety.clone(),
+                safety: current.safety.clone(),
             };
             let new_hash = new_persona.active_prompt_hash();
             let prompt_changed =
-                state.persona.safety.checked_prompt_hash.as_deref() != Some(new_hash.as_str());
+                current.safety.checked_prompt_hash.as_deref() != Some(new_hash.as_str());
 
             if prompt_changed {
                 new_persona.safety = PersonaSafety {
@@ -120,19 +175,32 @@ pub async fn handle_persona_admin(
                 };
             }
 
-            state.persona = new_persona;
-            save_onboarding(&kv, tenant_id, &state).await?;
+            match &channel {
+                Some(channel) => {
+                    update_reply_config(&kv, tenant_id, channel, &account_id, |c| {
+                        c.persona = Some(new_persona)
+                    })
+                    .await?;
+                }
+                None => {
+                    brand.persona = new_persona;
+                    brands::save(&kv, tenant_id, &brand).await?;
+                }
+            }
 
             if prompt_changed {
                 let job = crate::safety_queue::SafetyJob {
                     tenant_id: tenant_id.to_string(),
                     prompt_hash: new_hash,
+                    brand_id: (channel.is_none() && !brand.is_main()).then(|| brand.id.clone()),
+                    account_id: channel.as_ref().map(|_| account_id.clone()),
+                    channel,
                 };
                 let _ = crate::safety_queue::enqueue(&env, job).await;
             }
 
             let headers = Headers::new();
-            headers.set("HX-Redirect", &format!("{base_url}/admin/persona"))?;
+            headers.set("HX-Redirect", &format!("{base_url}/admin/persona{query}"))?;
             Ok(Response::empty()?.with_status(200).with_headers(headers))
         }
 
@@ -27,8 +27,9 @@ use crate::helpers::{generate_id, now_iso};
 use crate::storage::*;
 use crate::templates::rules::{rule_form_html, rule_form_title, rules_list_html};
 use crate::types::{
-    default_match_threshold, ApprovalPolicy, NoGateAcceptance, ReplyConfig, ReplyMatcher,
-    ReplyResponse, ReplyRule,
+    default_match_threshold, ApprovalPolicy, Channel, GenerationParams, ModelTier,
+    NoGateAcceptance, ReplyConfig, ReplyMatcher, ReplyResponse, ReplyRule, MAX_MAX_TOKENS,
+    MAX_TEMPERATURE, MIN_MAX_TOKENS,
 };
 
 const MAX_LABEL: usize = 80;
@@ -86,70 +87,30 @@ impl<'a> ChannelRef<'a> {
         format!("{base}/admin/rules/{}/{}", self.slug(), self.id_part())
     }
 
-    async fn load(&self, kv: &kv::KvStore, tenant_id: &str) -> Result<Option<ReplyConfig>> {
+    pub fn channel(&self) -> Channel {
         match self {
-            ChannelRef::WhatsApp { id } => Ok(get_whatsapp_account(kv, id)
-                .await?
-                .filter(|a| a.tenant_id == tenant_id)
-                .map(|a| a.auto_reply)),
-            ChannelRef::Instagram { id } => Ok(get_instagram_account(kv, id)
-                .await?
-                .filter(|a| a.tenant_id == tenant_id)
-                .map(|a| a.auto_reply)),
-            ChannelRef::Email { label } => Ok(get_email_address(kv, tenant_id, label)
-                .await?
-                .map(|a| a.auto_reply)),
-            ChannelRef::Discord => Ok(get_discord_config_by_tenant(kv, tenant_id)
-                .await?
-                .map(|c| c.auto_reply)),
+            ChannelRef::WhatsApp { .. } => Channel::WhatsApp,
+            ChannelRef::Instagram { .. } => Channel::Instagram,
+            ChannelRef::Email { .. } => Channel::Email,
+            ChannelRef::Discord => Channel::Discord,
         }
     }
 
+    /// The channel account's persona editor (`admin_persona`).
+    pub fn persona_url(&self, base: &str) -> String {
+        format!(
+            "{base}/admin/persona?channel={}&account={}",
+            self.slug(),
+            self.id_part()
+        )
+    }
+
+    async fn load(&self, kv: &kv::KvStore, tenant_id: &str) -> Result<Option<ReplyConfig>> {
+        get_reply_config(kv, tenant_id, &self.channel(), self.id_part()).await
+    }
+
     async fn save(&self, kv: &kv::KvStore, tenant_id: &str, cfg: ReplyConfig) -> Result<bool> {
-        let now = now_iso();
-        match self {
-            ChannelRef::WhatsApp { id } => {
-                let Some(mut account) = get_whatsapp_account(kv, id).await? else {
-                    return Ok(false);
-                };
-                if account.tenant_id != tenant_id {
-                    return Ok(false);
-                }
-                account.auto_reply = cfg;
-                account.updated_at = now;
-                save_whatsapp_account(kv, &account).await?;
-                Ok(true)
-            }
-            ChannelRef::Instagram { id } => {
-                let Some(mut account) = get_instagram_account(kv, id).await? else {
-                    return Ok(false);
-                };
-                if account.tenant_id != tenant_id {
-                    return Ok(false);
-                }
-                account.auto_reply = cfg;
-                account.updated_at = now;
-                save_instagram_account(kv, &account).await?;
-                Ok(true)
-            }
-            ChannelRef::Email { label } => {
-                let Some(mut addr) = get_email_address(kv, tenant_id, label).await? else {
-                    return Ok(false);
-                };
-                addr.auto_reply = cfg;
-                addr.updated_at = now;
-                save_email_address(kv, tenant_id, &addr).await?;
-                Ok(true)
-            }
-            ChannelRef::Discord => {
-                let Some(mut dc) = get_discord_config_by_tenant(kv, tenant_id).await? else {
-                    return Ok(false);
-                };
-                dc.auto_reply = cfg;
-                save_discord_config(kv, &dc).await?;
-                Ok(true)
-            }
-        }
+        update_reply_config(kv, tenant_id, &self.channel(), self.id_part(), |c| *c = cfg).await
     }
 }
 
@@ -194,12 +155,13 @@ pub async fn handle_rules(
     };
 
     let allow_no_gate = approval::allow_no_gate(&env);
+    let pricing = get_pricing(&env.d1("DB")?).await;
 
     let rest_slice: Vec<&str> = rest.iter().copied().collect();
     match (method, rest_slice.as_slice()) {
         // List page
         (Method::Get, []) => {
-            Response::from_html(rules_list_html(&cfg, &channel, base_url, &locale))
+            Response::from_html(rules_list_html(&cfg, &channel, &pricing, base_url, &locale))
         }
 
         // New-rule form
@@ -209,6 +171,7 @@ pub async fn handle_rules(
             base_url,
             crate::i18n::t(&locale, "admin-rules-form-title-add"),
             allow_no_gate,
+            &pricing,
             &locale,
         )),
 
@@ -220,7 +183,9 @@ pub async fn handle_rules(
                 ));
             }
             let form: serde_json::Value = req.json().await?;
-            let rule = match build_rule_from_form(&env, &generate_id(), &form, tenant_id).await {
+            let rule = match build_rule_from_form(&env, &generate_id(), &form, tenant_id, &pricing)
+                .await
+            {
                 Ok(r) => r,
                 Err(msg) => {
                     return Response::from_html(format!(r#"<div class="error">{msg}</div>"#));
@@ -238,6 +203,7 @@ pub async fn handle_rules(
             base_url,
             crate::i18n::t(&locale, "admin-rules-form-title-default"),
             allow_no_gate,
+            &pricing,
             &locale,
         )),
 
@@ -261,6 +227,13 @@ pub async fn handle_rules(
                 "canned" => ReplyResponse::Canned { text },
                 _ => ReplyResponse::Prompt { text },
             };
+            cfg.default_rule.generation =
+                match parse_generation(&form, &cfg.default_rule.response, &pricing) {
+                    Ok(g) => g,
+                    Err(msg) => {
+                        return Response::from_html(format!(r#"<div class="error">{msg}</div>"#));
+                    }
+                };
             // Allow renaming the default rule's label so admins can describe
             // their fallback ("General fallback", "After-hours", etc.).
             if let Some(label) = form.get("label").and_then(|v| v.as_str()) {
@@ -284,6 +257,7 @@ pub async fn handle_rules(
                 base_url,
                 rule_form_title(&existing, &locale),
                 allow_no_gate,
+                &pricing,
                 &locale,
             ))
         }
@@ -296,12 +270,13 @@ pub async fn handle_rules(
             };
             let prior = cfg.rules[idx].clone();
             let form: serde_json::Value = req.json().await?;
-            let mut updated = match build_rule_from_form(&env, &id, &form, tenant_id).await {
-                Ok(r) => r,
-                Err(msg) => {
-                    return Response::from_html(format!(r#"<div class="error">{msg}</div>"#));
-                }
-            };
+            let mut updated =
+                match build_rule_from_form(&env, &id, &form, tenant_id, &pricing).await {
+                    Ok(r) => r,
+                    Err(msg) => {
+                        return Response::from_html(format!(r#"<div class="error">{msg}</div>"#));
+                    }
+                };
             // If the rule was already NoGate and the user kept it on NoGate
             // without re-clicking the modal, carry the prior acceptance
             // forward instead of forcing a reaccept on every save.
@@ -372,6 +347,7 @@ async fn build_rule_from_form(
     id: &str,
     form: &serde_json::Value,
     tenant_id: &str,
+    pricing: &Pricing,
 ) -> std::result::Result<ReplyRule, String> {
     let label = form
         .get("label")
@@ -429,16 +405,16 @@ async fn build_rule_from_form(
             // Embed synchronously on save. If the AI binding is down, refuse
             // the save: a Prompt rule with no embedding can never match,
             // which would silently degrade routing.
-            let embedding = ai::embed(env, &description)
+            let embedding = ai::embed(env, tenant_id, &description)
                 .await
                 .map_err(|e| format!("Embedding failed: {e}. Try again in a moment."))?;
-            if embedding.is_empty() {
+            if embedding.vector.is_empty() {
                 return Err("Embedding came back empty. Try again.".to_string());
             }
             ReplyMatcher::Prompt {
                 description,
-                embedding,
-                embedding_model: ai::EMBEDDING_MODEL.to_string(),
+                embedding: embedding.vector,
+                embedding_model: embedding.model,
                 threshold,
             }
         }
@@ -469,6 +445,7 @@ async fn build_rule_from_form(
     };
 
     let approval = parse_approval_policy(env, form, tenant_id, &response).await?;
+    let generation = parse_generation(form, &response, pricing)?;
 
     Ok(ReplyRule {
         id: id.to_string(),
@@ -476,6 +453,60 @@ async fn build_rule_from_form(
         matcher,
         response,
         approval,
+        generation,
+    })
+}
+
+/// Model tier + sampling for AI rules. Canned rules get the defaults. Blank
+/// temperature / max length mean "provider default"; out-of-range numbers
+/// are clamped rather than rejected, matching the threshold slider.
+fn parse_generation(
+    form: &serde_json::Value,
+    response: &ReplyResponse,
+    pricing: &Pricing,
+) -> std::result::Result<GenerationParams, String> {
+    if !matches!(response, ReplyResponse::Prompt { .. }) {
+        return Ok(GenerationParams::default());
+    }
+
+    let tier = form
+        .get("model_tier")
+        .and_then(|v| v.as_str())
+        .map(|s| ModelTier::from_wire(s).ok_or_else(|| "Pick a model tier.".to_string()))
+        .transpose()?
+        .unwrap_or_default();
+    if !pricing.allowed_tiers.contains(&tier) {
+        return Err("That model tier isn't available. Pick another.".to_string());
+    }
+
+    // HTMX json-enc posts numbers as strings; accept both, treat "" as unset.
+    let number = |key: &str| -> std::result::Result<Option<f64>, String> {
+        match form.get(key) {
+            None | Some(serde_json::Value::Null) => Ok(None),
+            Some(v) => match v.as_f64() {
+                Some(n) => Ok(Some(n)),
+                None => {
+                    let s = v.as_str().unwrap_or("").trim();
+                    if s.is_empty() {
+                        Ok(None)
+                    } else {
+                        s.parse::<f64>()
+                            .map(Some)
+                            .map_err(|_| format!("Enter a number for {key}."))
+                    }
+                }
+            },
+        }
+    };
+
+    let temperature = number("temperature")?.map(|t| (t as f32).clamp(0.0, MAX_TEMPERATURE));
+    let max_tokens = number("max_tokens")?
+        .map(|n| (n.round().max(0.0) as u32).clamp(MIN_MAX_TOKENS, MAX_MAX_TOKENS));
+
+    Ok(GenerationParams {
+        tier,
+        temperature,
+        max_tokens,
     })
 }
 
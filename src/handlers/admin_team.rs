@@ -0,0 +1,181 @@
+//! `/admin/team` routes: the owner invites, re-roles and removes members.
+//!
+//! Routes:
+//!   GET    /admin/team               members, invites and the invite form
+//!   POST   /admin/team/invite        invite an address with a role
+//!   POST   /admin/team/{id}/role     change a member's role
+//!   POST   /admin/team/{id}/resend   new link and email for a pending invite
+//!   DELETE /admin/team/{id}          remove a member or withdraw an invite
+//!
+//! Owner-only (`team::required_role`); authenticated and CSRF-protected by
+//! the `handle_admin` dispatcher.
+
+use worker::*;
+
+use crate::email::send::send_outbound;
+use crate::storage::{get_onboarding, get_tenant};
+use crate::team::{self, Member, MAX_MEMBERS};
+use crate::templates::team::team_page_html;
+use crate::types::MemberRole;
+
+pub async fn handle_team(
+    mut req: Request,
+    env: Env,
+    path: &str,
+    base_url: &str,
+    tenant_id: &str,
+) -> Result<Response> {
+    let db = env.d1("DB")?;
+    let locale = crate::locale::Locale::from_request(&req);
+    let owner_email = get_tenant(&db, tenant_id)
+        .await?
+        .map(|t| t.email)
+        .unwrap_or_default();
+    let rest = path.strip_prefix("/admin/team").unwrap_or("");
+
+    match (req.method(), rest) {
+        (Method::Get, "" | "/") => {
+            let members = team::list(&db, tenant_id).await?;
+            let mut resp =
+                Response::from_html(team_page_html(&owner_email, &members, base_url, &locale))?;
+            resp.headers_mut().set("Cache-Control", "no-store")?;
+            Ok(resp)
+        }
+
+        (Method::Post, "/invite") => {
+            let form: serde_json::Value = req.json().await?;
+            let field = |k: &str| form.get(k).and_then(|v| v.as_str()).unwrap_or("").trim();
+            let email = team::normalize_email(field("email"));
+            let role =
+                MemberRole::from_wire(field("role")).filter(|r| MemberRole::INVITABLE.contains(r));
+            let Some(role) = role else {
+                return error("Pick a role.");
+            };
+            if !email.contains('@') || email.len() > 254 {
+                return error("Enter an email address.");
+            }
+            if email == team::normalize_email(&owner_email) {
+                return error("That's the owner's address.");
+            }
+            if team::list(&db, tenant_id).await?.len() >= MAX_MEMBERS {
+                return error(&format!("A team can have up to {MAX_MEMBERS} members."));
+            }
+            let Some(member) = team::invite(&db, tenant_id, &email, role, &owner_email).await?
+            else {
+                return error("That address is already on the team or invited.");
+            };
+            let sent = send_invite(&env, &member, base_url, &owner_email).await;
+            let headers = Headers::new();
+            headers.set("HX-Refresh", "true")?;
+            let note = if sent {
+                "Invite sent."
+            } else {
+                "Invite created. Copy its link from the list to send it yourself."
+            };
+            Ok(
+                Response::from_html(format!(r#"<div class="success">{note}</div>"#))?
+                    .with_headers(headers),
+            )
+        }
+
+        (method, member_path) if member_path.starts_with('/') => {
+            let parts: Vec<&str> = member_path.trim_start_matches('/').split('/').collect();
+            let (id, action) = match parts.as_slice() {
+                [id] => (*id, ""),
+                [id, action] => (*id, *action),
+                _ => return Response::error("Not Found", 404),
+            };
+            let Some(member) = team::get(&db, tenant_id, id).await? else {
+                return Response::error("Member not found", 404);
+            };
+
+            match (method, action) {
+                (Method::Post, "role") => {
+                    let form: serde_json::Value = req.json().await?;
+                    let role = form
+                        .get("role")
+                        .and_then(|v| v.as_str())
+                        .and_then(MemberRole::from_wire)
+                        .filter(|r| MemberRole::INVITABLE.contains(r));
+                    let Some(role) = role else {
+                        return Response::error("Unknown role", 400);
+                    };
+                    team::set_role(&db, tenant_id, &member.id, role).await?;
+                    Response::ok("")
+                }
+
+                (Method::Post, "resend") => {
+                    if let Some(token) = team::renew_invite(&db, tenant_id, &member.id).await? {
+                        let member = Member {
+                            invite_token: Some(token),
+                            ..member
+                        };
+                        send_invite(&env, &member, base_url, &owner_email).await;
+                    }
+                    let headers = Headers::new();
+                    headers.set("HX-Refresh", "true")?;
+                    Ok(Response::ok("")?.with_headers(headers))
+                }
+
+                (Method::Delete, "") => {
+                    team::revoke(&db, tenant_id, &member.id).await?;
+                    let headers = Headers::new();
+                    headers.set("HX-Refresh", "true")?;
+                    Ok(Response::ok("")?.with_headers(headers))
+                }
+
+                _ => Response::error("Not Found", 404),
+            }
+        }
+
+        _ => Response::error("Not Found", 404),
+    }
+}
+
+fn error(message: &str) -> Result<Response> {
+    Response::from_html(format!(
+        r#"<div class="error">{}</div>"#,
+        crate::helpers::html_escape(message)
+    ))
+}
+
+/// Email the invite link. Returns whether it went out; the owner can
+/// always copy the link from the members page instead.
+async fn send_invite(env: &Env, member: &Member, base_url: &str, owner_email: &str) -> bool {
+    let Some(token) = member.invite_token.as_deref() else {
+        return false;
+    };
+    let domain = env
+        .var("EMAIL_DOMAIN")
+        .map(|v| v.to_string())
+        .unwrap_or_default();
+    if domain.is_empty() {
+        console_log!("Team invite email skipped: EMAIL_DOMAIN is not set");
+        return false;
+    }
+    let business = match env.kv("KV") {
+        Ok(kv) => get_onboarding(&kv, &member.tenant_id)
+            .await
+            .map(|s| s.business.name)
+            .unwrap_or_default(),
+        Err(_) => String::new(),
+    };
+    let business = if business.trim().is_empty() {
+        owner_email.to_string()
+    } else {
+        business
+    };
+    let email = team::build_invite_email(
+        member,
+        &business,
+        &team::invite_url(base_url, token),
+        &format!("noreply@{domain}"),
+    );
+    match send_outbound(env, &email).await {
+        Ok(()) => true,
+        Err(e) => {
+            console_log!("Team invite email to {} failed: {e:?}", member.email);
+            false
+        }
+    }
+}
@@ -0,0 +1,146 @@
+//! `/admin/tools` — register the HTTP lookups the reply model may call.
+//!
+//! Routes:
+//!   GET    /admin/tools              list + add form
+//!   POST   /admin/tools              register a tool
+//!   POST   /admin/tools/{id}/toggle  enable / disable
+//!   DELETE /admin/tools/{id}         remove
+//!
+//! The HMAC secret is encrypted on save and never rendered back; to rotate
+//! it, remove the tool and add it again.
+
+use worker::*;
+
+use crate::ai::tools::{self as ai_tools, MAX_DESCRIPTION, MAX_TOOLS, MIN_SECRET_LEN};
+use crate::helpers::{generate_id, now_iso};
+use crate::storage::{get_ai_tools, save_ai_tools};
+use crate::templates::tools::tools_admin_html;
+use crate::types::AiTool;
+
+pub async fn handle_tools_admin(
+    mut req: Request,
+    env: Env,
+    path: &str,
+    base_url: &str,
+    tenant_id: &str,
+) -> Result<Response> {
+    let kv = env.kv("KV")?;
+    let method = req.method();
+    let locale = crate::locale::Locale::from_request(&req);
+    let mut tools = get_ai_tools(&kv, tenant_id).await?;
+
+    let rest: Vec<&str> = path
+        .trim_start_matches("/admin/tools")
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    match (method, rest.as_slice()) {
+        (Method::Get, []) => Response::from_html(tools_admin_html(&tools, base_url, &locale)),
+
+        (Method::Post, []) => {
+            if tools.len() >= MAX_TOOLS {
+                return Response::from_html(format!(
+                    r#"<div class="error">You've reached the tool cap ({MAX_TOOLS}). Remove one before adding another.</div>"#
+                ));
+            }
+            let form: serde_json::Value = req.json().await?;
+            let tool = match build_tool(&env, &form, &tools).await {
+                Ok(t) => t,
+                Err(msg) => {
+                    return Response::from_html(format!(
+                        r#"<div class="error">{}</div>"#,
+                        crate::helpers::html_escape(&msg)
+                    ));
+                }
+            };
+            tools.push(tool);
+            save_ai_tools(&kv, tenant_id, &tools).await?;
+            redirect(base_url)
+        }
+
+        (Method::Post, [id, "toggle"]) => {
+            let Some(tool) = tools.iter_mut().find(|t| t.id == *id) else {
+                return Response::error("Tool not found", 404);
+            };
+            tool.enabled = !tool.enabled;
+            save_ai_tools(&kv, tenant_id, &tools).await?;
+            redirect(base_url)
+        }
+
+        (Method::Delete, [id]) => {
+            let before = tools.len();
+            tools.retain(|t| t.id != *id);
+            if tools.len() == before {
+                return Response::error("Tool not found", 404);
+            }
+            save_ai_tools(&kv, tenant_id, &tools).await?;
+            // HTMX swaps the row out.
+            Response::ok("")
+        }
+
+        _ => Response::error("Not Found", 404),
+    }
+}
+
+/// Validate the add form and encrypt the secret.
+async fn build_tool(
+    env: &Env,
+    form: &serde_json::Value,
+    existing: &[AiTool],
+) -> std::result::Result<AiTool, String> {
+    let s = |k: &str| {
+        form.get(k)
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .trim()
+            .to_string()
+    };
+
+    let name = s("name");
+    ai_tools::validate_name(&name)?;
+    if existing.iter().any(|t| t.name == name) {
+        return Err(format!("A tool named {name} already exists."));
+    }
+
+    let description: String = s("description").chars().take(MAX_DESCRIPTION).collect();
+    if description.is_empty() {
+        return Err("Describe what the tool looks up so the model knows when to use it.".into());
+    }
+
+    let url = s("url");
+    ai_tools::validate_url(&url)?;
+
+    let parameters = ai_tools::parse_parameters(&s("parameters"))?;
+
+    let secret = s("secret");
+    if secret.len() < MIN_SECRET_LEN {
+        return Err(format!(
+            "Signing secret must be at least {MIN_SECRET_LEN} characters."
+        ));
+    }
+    let key = env
+        .secret("ENCRYPTION_KEY")
+        .map(|k| k.to_string())
+        .map_err(|_| "Server misconfiguration: encryption key not set.".to_string())?;
+    let secret_encrypted = crate::crypto::encrypt_secret(&secret, &key)
+        .await
+        .map_err(|e| format!("Couldn't store the secret: {e}"))?;
+
+    Ok(AiTool {
+        id: generate_id(),
+        name,
+        description,
+        parameters,
+        url,
+        secret_encrypted,
+        enabled: true,
+        created_at: now_iso(),
+    })
+}
+
+fn redirect(base_url: &str) -> Result<Response> {
+    let headers = Headers::new();
+    headers.set("HX-Redirect", &format!("{base_url}/admin/tools"))?;
+    Ok(Response::empty()?.with_status(200).with_headers(headers))
+}
@@ -68,6 +68,7 @@ pub async fn handle_whatsapp_admin(
                 phone_number: String::new(),
                 phone_number_id: String::new(),
                 auto_reply: ReplyConfig::default(),
+                brand_id: None,
                 created_at: now.clone(),
                 updated_at: now,
             };
@@ -138,6 +139,10 @@ pub async fn handle_whatsapp_admin(
                     account.auto_reply.wait_seconds = n.min(30);
                 }
             }
+            account.auto_reply.apply_buffer_form(|k| match form.get(k) {
+                Some(FormEntry::Field(v)) => Some(v),
+                _ => None,
+            });
 
             account.updated_at = now_iso();
             save_whatsapp_account(&kv, &account).await?;
@@ -4,9 +4,12 @@ use serde::Deserialize;
 use worker::*;
 
 use super::get_base_url;
+use crate::agency::{self, Access, Reach};
 use crate::helpers::*;
 use crate::storage::*;
+use crate::team::{self, Actor};
 use crate::templates::auth_login_html;
+use crate::templates::team::team_join_result_html;
 use crate::types::*;
 
 const GOOGLE_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
@@ -15,6 +18,10 @@ const GRAPH_API_BASE: &str = "https://graph.facebook.com";
 
 const SESSION_TTL_SECONDS: u64 = 7 * 24 * 60 * 60; // 7 days
 
+/// Holds a team invite from `/auth/join` until the sign-in callback.
+const INVITE_TTL_SECONDS: u64 = 24 * 60 * 60;
+const CLEAR_INVITE_COOKIE: &str = "invite=; Path=/auth; HttpOnly; Secure; SameSite=Lax; Max-Age=0";
+
 #[derive(Deserialize)]
 struct TokenResponse {
     access_token: String,
@@ -35,7 +42,7 @@ pub async fn handle_auth(req: Request, env: Env, path: &str, method: Method) ->
         (Method::Get, "/auth/login") => {
             // Already signed in: skip the login page.
             let kv = env.kv("KV")?;
-            if resolve_tenant_id(&req, &kv).await.is_some() {
+            if resolve_session(&req, &env).await?.is_some() {
                 let headers = Headers::new();
                 headers.set("Location", "/admin")?;
                 return Ok(Response::empty()?.with_status(302).with_headers(headers));
@@ -147,11 +154,27 @@ pub async fn handle_auth(req: Request, env: Env, path: &str, method: Method) ->
             let kv = env.kv("KV")?;
             let db = env.d1("DB")?;
 
-            // Find or create tenant. New tenants pick locale from
-            // Accept-Language > cf-ipcountry > en-IN; currency follows.
+            if let Some(resp) = join_team(&req, &kv, &db, &user.email, "google", &locale).await? {
+                return Ok(resp);
+            }
+
+            // Find or create tenant. An address that owns no tenant but
+            // belongs to a team signs in to that team. New tenants pick
+            // locale from Accept-Language > cf-ipcountry > en-IN; currency
+            // follows.
             let tenant = match get_tenant_by_email(&db, &user.email).await? {
                 Some(t) => t,
                 None => {
+                    if let Some(member) = team::active_by_email(&db, &user.email).await? {
+                        return create_session_and_redirect(
+                            &req,
+                            &kv,
+                            &member.tenant_id,
+                            Some(&member.id),
+                            "google",
+                        )
+                        .await;
+                    }
                     let signup_locale = crate::locale::Locale::from_request(&req);
                     let now = now_iso();
                     let tenant = Tenant {
@@ -164,6 +187,8 @@ pub async fn handle_auth(req: Request, env: Env, path: &str, method: Method) ->
                         currency: signup_locale.currency,
                         email_address_extras_purchased: 0,
                         verified_at: None,
+                        credit_mode: crate::types::CreditMode::PerReply,
+                        retention_days: 0,
                         created_at: now.clone(),
                         updated_at: now,
                     };
@@ -172,7 +197,7 @@ pub async fn handle_auth(req: Request, env: Env, path: &str, method: Method) ->
                 }
             };
 
-            create_session_and_redirect(&req, &kv, &tenant.id, "google").await
+            create_session_and_redirect(&req, &kv, &tenant.id, None, "google").await
         }
 
         // Facebook OAuth callback
@@ -259,7 +284,16 @@ pub async fn handle_auth(req: Request, env: Env, path: &str, method: Method) ->
             let kv = env.kv("KV")?;
             let db = env.d1("DB")?;
 
-            // Find tenant by facebook_id, then by email, then create
+            if !fb_email.is_empty() {
+                if let Some(resp) =
+                    join_team(&req, &kv, &db, &fb_email, "facebook", &locale).await?
+                {
+                    return Ok(resp);
+                }
+            }
+
+            // Find tenant by facebook_id, then by email, then a team the
+            // email belongs to, then create
             let tenant = if let Some(t) = get_tenant_by_facebook_id(&db, &fb_id).await? {
                 t
             } else if !fb_email.is_empty() {
@@ -269,6 +303,15 @@ pub async fn handle_auth(req: Request, env: Env, path: &str, method: Method) ->
                     t.updated_at = now_iso();
                     save_tenant(&db, &t).await?;
                     t
+                } else if let Some(member) = team::active_by_email(&db, &fb_email).await? {
+                    return create_session_and_redirect(
+                        &req,
+                        &kv,
+                        &member.tenant_id,
+                        Some(&member.id),
+                        "facebook",
+                    )
+                    .await;
                 } else {
                     let signup_locale = crate::locale::Locale::from_request(&req);
                     let now = now_iso();
@@ -282,6 +325,8 @@ pub async fn handle_auth(req: Request, env: Env, path: &str, method: Method) ->
                         currency: signup_locale.currency,
                         email_address_extras_purchased: 0,
                         verified_at: None,
+                        credit_mode: crate::types::CreditMode::PerReply,
+                        retention_days: 0,
                         created_at: now.clone(),
                         updated_at: now,
                     };
@@ -296,16 +341,15 @@ pub async fn handle_auth(req: Request, env: Env, path: &str, method: Method) ->
                 );
             };
 
-            create_session_and_redirect(&req, &kv, &tenant.id, "facebook").await
+            create_session_and_redirect(&req, &kv, &tenant.id, None, "facebook").await
         }
 
         // Unlink a provider
         (Method::Delete, "/auth/unlink/google") => {
-            let kv = env.kv("KV")?;
             let db = env.d1("DB")?;
-            let tenant_id = match resolve_tenant_id(&req, &kv).await {
-                Some(id) => id,
-                None => return Response::error("Unauthorized", 401),
+            let tenant_id = match owner_session(&req, &env).await? {
+                Ok(id) => id,
+                Err(resp) => return Ok(resp),
             };
             let mut tenant = match get_tenant(&db, &tenant_id).await? {
                 Some(t) => t,
@@ -327,11 +371,10 @@ pub async fn handle_auth(req: Request, env: Env, path: &str, method: Method) ->
         }
 
         (Method::Delete, "/auth/unlink/facebook") => {
-            let kv = env.kv("KV")?;
             let db = env.d1("DB")?;
-            let tenant_id = match resolve_tenant_id(&req, &kv).await {
-                Some(id) => id,
-                None => return Response::error("Unauthorized", 401),
+            let tenant_id = match owner_session(&req, &env).await? {
+                Ok(id) => id,
+                Err(resp) => return Ok(resp),
             };
             let mut tenant = match get_tenant(&db, &tenant_id).await? {
                 Some(t) => t,
@@ -351,6 +394,49 @@ pub async fn handle_auth(req: Request, env: Env, path: &str, method: Method) ->
             Response::from_html("<div class=\"success\">Facebook account unlinked.</div>")
         }
 
+        // Team invite link: remember the invite and send the invitee to
+        // sign in. The sign-in callbacks accept it (`join_team`).
+        (Method::Get, "/auth/join") => {
+            let url = req.url()?;
+            let token = url
+                .query_pairs()
+                .find(|(k, _)| k == "token")
+                .map(|(_, v)| v.to_string())
+                .unwrap_or_default();
+            let db = env.d1("DB")?;
+            let invite = if token.is_empty() {
+                None
+            } else {
+                team::find_invite(&db, &token).await?
+            };
+            if invite.is_none() {
+                return Response::from_html(team_join_result_html(
+                    "This invite has expired or was already used. Ask the owner to send a new one.",
+                    &locale,
+                ));
+            }
+
+            // Sign out whoever is signed in on this browser, so the invitee
+            // signs in as themselves.
+            if let Some(session_token) = get_session_cookie(&req) {
+                let kv = env.kv("KV")?;
+                delete_session(&kv, &session_token).await?;
+            }
+            let headers = Headers::new();
+            headers.set("Location", "/auth/login")?;
+            headers.set(
+                "Set-Cookie",
+                "session=; Path=/; HttpOnly; Secure; SameSite=Lax; Max-Age=0",
+            )?;
+            headers.append(
+                "Set-Cookie",
+                &format!(
+                    "invite={token}; Path=/auth; HttpOnly; Secure; SameSite=Lax; Max-Age={INVITE_TTL_SECONDS}"
+                ),
+            )?;
+            Ok(Response::empty()?.with_status(302).with_headers(headers))
+        }
+
         // Discord bot install callback
         (Method::Get, "/auth/discord/callback") => {
             super::discord_oauth::handle_discord_callback(req, env).await
@@ -377,16 +463,29 @@ pub async fn handle_auth(req: Request, env: Env, path: &str, method: Method) ->
     }
 }
 
+/// Start a session for the tenant's owner (`member_id` `None`) or one of
+/// its team members.
 pub(super) async fn create_session_and_redirect(
     _req: &Request,
     kv: &kv::KvStore,
     tenant_id: &str,
+    member_id: Option<&str>,
     provider: &str,
 ) -> Result<Response> {
     let session_token = generate_token()?;
     let csrf_token = generate_token()?;
     save_session(kv, &session_token, tenant_id, SESSION_TTL_SECONDS).await?;
-    save_csrf_token(kv, tenant_id, &csrf_token, SESSION_TTL_SECONDS).await?;
+    if let Some(member_id) = member_id {
+        save_session_member(
+            kv,
+            &session_token,
+            tenant_id,
+            member_id,
+            SESSION_TTL_SECONDS,
+        )
+        .await?;
+    }
+    save_csrf_token(kv, tenant_id, member_id, &csrf_token, SESSION_TTL_SECONDS).await?;
 
     let headers = Headers::new();
     headers.set("Location", "/admin")?;
@@ -435,17 +534,193 @@ pub fn get_session_cookie(req: &Request) -> Option<String> {
     get_cookie(req, "session")
 }
 
-/// Resolve tenant_id from session cookie, returns None if not authenticated
-pub async fn resolve_tenant_id(req: &Request, kv: &kv::KvStore) -> Option<String> {
-    let token = get_session_cookie(req)?;
-    get_session(kv, &token).await.ok()?
+/// A signed-in admin: the tenant and who in it.
+pub struct Session {
+    pub tenant_id: String,
+    pub actor: Actor,
 }
 
-/// Validate CSRF token from X-CSRF-Token header or csrf form field against stored token.
+/// Resolve the session cookie to its tenant and, for a team member's
+/// session, the member. A session whose member has since been removed is
+/// deleted and treated as signed out.
+pub async fn resolve_session(req: &Request, env: &Env) -> Result<Option<Session>> {
+    let Some(token) = get_session_cookie(req) else {
+        return Ok(None);
+    };
+    let kv = env.kv("KV")?;
+    let Some(tenant_id) = get_session(&kv, &token).await? else {
+        return Ok(None);
+    };
+    let member_id = get_session_member(&kv, &token).await?;
+    let agency_id = match member_id {
+        Some(_) => None,
+        None => get_session_agency(&kv, &token).await?,
+    };
+    let actor = match (member_id, agency_id) {
+        (None, None) => Actor::owner(),
+        (Some(member_id), _) => {
+            let db = env.d1("DB")?;
+            match team::get(&db, &tenant_id, &member_id).await? {
+                Some(member) if !member.is_pending() => Actor::member(&member),
+                _ => {
+                    delete_session(&kv, &token).await?;
+                    return Ok(None);
+                }
+            }
+        }
+        (None, Some(agency_id)) => {
+            let db = env.d1("DB")?;
+            match agency::agency_login(&db, &agency_id, &tenant_id).await? {
+                Some(email) => Actor::agency(&agency_id, &email),
+                None => {
+                    delete_session(&kv, &token).await?;
+                    return Ok(None);
+                }
+            }
+        }
+    };
+    Ok(Some(Session { tenant_id, actor }))
+}
+
+/// Point the current session at another tenant `login` can reach and
+/// issue a CSRF token for it. The session cookie stays; the page reloads
+/// at `/admin` as whoever the login is there.
+pub(super) async fn switch_session(
+    req: &Request,
+    kv: &kv::KvStore,
+    reach: &Reach,
+    login: &str,
+) -> Result<Response> {
+    let Some(session_token) = get_session_cookie(req) else {
+        return Response::error("Unauthorized", 401);
+    };
+    let csrf_token = generate_token()?;
+    delete_session(kv, &session_token).await?;
+    save_session(kv, &session_token, &reach.tenant_id, SESSION_TTL_SECONDS).await?;
+    match &reach.access {
+        Access::Owner => {}
+        Access::Agency { agency_id } => {
+            save_session_agency(
+                kv,
+                &session_token,
+                &reach.tenant_id,
+                agency_id,
+                SESSION_TTL_SECONDS,
+            )
+            .await?
+        }
+        Access::Member { member_id, .. } => {
+            save_session_member(
+                kv,
+                &session_token,
+                &reach.tenant_id,
+                member_id,
+                SESSION_TTL_SECONDS,
+            )
+            .await?
+        }
+    }
+    save_csrf_token(
+        kv,
+        &reach.tenant_id,
+        reach.actor(login).csrf_scope().as_deref(),
+        &csrf_token,
+        SESSION_TTL_SECONDS,
+    )
+    .await?;
+
+    let headers = Headers::new();
+    headers.set("HX-Redirect", "/admin")?;
+    headers.set(
+        "Set-Cookie",
+        &format!(
+            "csrf={}; Path=/; Secure; SameSite=Lax; Max-Age={}",
+            csrf_token, SESSION_TTL_SECONDS
+        ),
+    )?;
+    Ok(Response::empty()?.with_headers(headers))
+}
+
+/// The tenant id for an owner's session; a 401 or 403 response otherwise.
+async fn owner_session(req: &Request, env: &Env) -> Result<std::result::Result<String, Response>> {
+    match owner_tenant(resolve_session(req, env).await?) {
+        Ok(tenant_id) => Ok(Ok(tenant_id)),
+        Err((message, status)) => Ok(Err(Response::error(message, status)?)),
+    }
+}
+
+/// The tenant id when `session` is its owner's own; members and agencies
+/// acting for the tenant can't change how the owner signs in.
+fn owner_tenant(session: Option<Session>) -> std::result::Result<String, (&'static str, u16)> {
+    match session {
+        Some(session) if session.actor.is_owner() => Ok(session.tenant_id),
+        Some(_) => Err(("Only the account owner can change sign-in methods", 403)),
+        None => Err(("Unauthorized", 401)),
+    }
+}
+
+/// The tenant id when `session` may connect a channel: Admin and up, as
+/// `team::required_role` asks of the admin channel pages.
+pub fn admin_tenant(session: Option<Session>) -> std::result::Result<String, (&'static str, u16)> {
+    match session {
+        Some(session) if session.actor.role.allows(MemberRole::Admin) => Ok(session.tenant_id),
+        Some(_) => Err((
+            "Your role can't connect channels. Ask the account owner.",
+            403,
+        )),
+        None => Err(("Unauthorized", 401)),
+    }
+}
+
+/// Accept the team invite `/auth/join` left in the `invite` cookie, for the
+/// address that just signed in. `None` when there's no invite, so the
+/// caller carries on with a normal sign-in.
+async fn join_team(
+    req: &Request,
+    kv: &kv::KvStore,
+    db: &D1Database,
+    email: &str,
+    provider: &str,
+    locale: &crate::locale::Locale,
+) -> Result<Option<Response>> {
+    let Some(token) = get_cookie(req, "invite") else {
+        return Ok(None);
+    };
+    let Some(invite) = team::find_invite(db, &token).await? else {
+        let mut resp = Response::from_html(team_join_result_html(
+            "This invite has expired or was already used. Ask the owner to send a new one.",
+            locale,
+        ))?;
+        resp.headers_mut()
+            .append("Set-Cookie", CLEAR_INVITE_COOKIE)?;
+        return Ok(Some(resp));
+    };
+    if invite.email != team::normalize_email(email) {
+        // Keep the invite so they can sign in again with the right account.
+        return Ok(Some(Response::from_html(team_join_result_html(
+            &format!(
+                "This invite is for {}, but you signed in as {}. Sign in with that address to join.",
+                invite.email, email
+            ),
+            locale,
+        ))?));
+    }
+    team::accept(db, &invite.id).await?;
+    let mut resp =
+        create_session_and_redirect(req, kv, &invite.tenant_id, Some(&invite.id), provider).await?;
+    resp.headers_mut()
+        .append("Set-Cookie", CLEAR_INVITE_COOKIE)?;
+    Ok(Some(resp))
+}
+
+/// Validate CSRF token from X-CSRF-Token header or csrf form field against
+/// the token stored for the session's owner, member or agency
+/// (`Actor::csrf_scope`).
 pub async fn validate_csrf(
     req: &Request,
     kv: &kv::KvStore,
     tenant_id: &str,
+    scope: Option<&str>,
 ) -> std::result::Result<(), String> {
     use subtle::ConstantTimeEq;
 
@@ -458,7 +733,7 @@ pub async fn validate_csrf(
         .or_else(|| get_cookie(req, "csrf"))
         .ok_or_else(|| "Missing CSRF token".to_string())?;
 
-    let stored = get_csrf_token(kv, tenant_id)
+    let stored = get_csrf_token(kv, tenant_id, scope)
         .await
         .map_err(|e| format!("CSRF lookup failed: {e}"))?
         .ok_or_else(|| "No CSRF token stored for session".to_string())?;
@@ -469,3 +744,47 @@ pub async fn validate_csrf(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_owner_changes_sign_in_methods() {
+        let session = |actor| {
+            Some(Session {
+                tenant_id: "t1".into(),
+                actor,
+            })
+        };
+        assert_eq!(owner_tenant(session(Actor::owner())), Ok("t1".to_string()));
+        assert_eq!(
+            owner_tenant(session(Actor::agency("a1", "me@agency.example"))).map_err(|e| e.1),
+            Err(403)
+        );
+        assert_eq!(owner_tenant(None).map_err(|e| e.1), Err(401));
+    }
+
+    #[test]
+    fn viewers_and_approvers_cannot_connect_channels() {
+        let member = |role| {
+            Some(Session {
+                tenant_id: "t1".into(),
+                actor: Actor {
+                    member_id: Some("m1".into()),
+                    email: Some("desk@example.com".into()),
+                    agency_id: None,
+                    role,
+                },
+            })
+        };
+        assert_eq!(
+            admin_tenant(member(MemberRole::Admin)),
+            Ok("t1".to_string())
+        );
+        for role in [MemberRole::Approver, MemberRole::Viewer] {
+            assert_eq!(admin_tenant(member(role)).map_err(|e| e.1), Err(403));
+        }
+        assert_eq!(admin_tenant(None).map_err(|e| e.1), Err(401));
+    }
+}
@@ -1,12 +1,21 @@
 //! Facebook data deletion callback handler
+//!
+//! POST starts a tenant deletion job and answers with its id as the
+//! confirmation code; GET `?code=` is the status page Meta links the user
+//! to.
 
 use worker::*;
 
 use crate::helpers::generate_id;
 use crate::storage::*;
+use crate::tenant_deletion;
+use crate::types::DeletionStatus;
 
-/// Handle POST /data-deletion
+/// Handle /data-deletion
 pub async fn handle_data_deletion(mut req: Request, env: Env, method: Method) -> Result<Response> {
+    if method == Method::Get {
+        return deletion_status(&req, &env).await;
+    }
     if method != Method::Post {
         return Response::error("Method not allowed", 405);
     }
@@ -56,22 +65,22 @@ pub async fn handle_data_deletion(mut req: Request, env: Env, method: Method) ->
         return Response::error("Missing user_id", 400);
     }
 
-    let kv = env.kv("KV")?;
     let db = env.d1("DB")?;
 
-    // Find and delete tenant by facebook_id
-    if let Some(tenant) = get_tenant_by_facebook_id(&db, fb_user_id).await? {
-        delete_tenant_data(&kv, &db, &tenant.id).await?;
-    }
-
-    let confirmation_code = generate_id();
+    // Find and delete tenant by facebook_id. The job id doubles as the
+    // confirmation code; with no tenant there's nothing to track, so any
+    // fresh id will do.
+    let confirmation_code = match get_tenant_by_facebook_id(&db, fb_user_id).await? {
+        Some(tenant) => tenant_deletion::start(&env, &tenant.id, "meta").await?.id,
+        None => generate_id(),
+    };
     let base_url = req
         .url()
         .map(|u| format!("{}://{}", u.scheme(), u.host_str().unwrap_or("localhost")))
         .unwrap_or_default();
 
     let response = serde_json::json!({
-        "url": format!("{}/admin/settings", base_url),
+        "url": format!("{}/data-deletion?code={}", base_url, confirmation_code),
         "confirmation_code": confirmation_code
     });
 
@@ -80,6 +89,33 @@ pub async fn handle_data_deletion(mut req: Request, env: Env, method: Method) ->
     Ok(Response::ok(response.to_string())?.with_headers(headers))
 }
 
+/// Where the deletion with `?code=` is. Unknown codes read the same as
+/// finished ones: either way nothing is stored for that account.
+async fn deletion_status(req: &Request, env: &Env) -> Result<Response> {
+    let url = req.url()?;
+    let code = url
+        .query_pairs()
+        .find(|(k, _)| k == "code")
+        .map(|(_, v)| v.into_owned())
+        .unwrap_or_default();
+    if code.is_empty() {
+        return Response::error("Missing code", 400);
+    }
+    let db = env.d1("DB")?;
+    let status = tenant_deletion::get_deletion(&db, &code)
+        .await?
+        .map(|job| job.status);
+    let message = match status {
+        Some(DeletionStatus::Running) => {
+            format!("Deletion request {code} is in progress. Your data is being removed.")
+        }
+        Some(DeletionStatus::Completed) | None => {
+            format!("Deletion request {code} is complete. No data is stored for this account.")
+        }
+    };
+    Response::ok(message)
+}
+
 fn base64url_to_hex(input: &str) -> Result<String> {
     let b64 = input.replace('-', "+").replace('_', "/");
     let padded = match b64.len() % 4 {
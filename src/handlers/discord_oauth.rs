@@ -62,13 +62,15 @@ pub async fn handle_discord_admin(
 /// token for CSRF protection.
 pub async fn handle_discord_callback(req: Request, env: Env) -> Result<Response> {
     let kv = env.kv("KV")?;
-    let tenant_id = match super::auth::resolve_tenant_id(&req, &kv).await {
-        Some(id) => id,
-        None => {
+    let session = super::auth::resolve_session(&req, &env).await?;
+    let tenant_id = match super::auth::admin_tenant(session) {
+        Ok(id) => id,
+        Err((_, 401)) => {
             let headers = Headers::new();
             headers.set("Location", "/auth/login")?;
             return Ok(Response::empty()?.with_status(302).with_headers(headers));
         }
+        Err((message, status)) => return Response::error(message, status),
     };
     let base_url = super::get_base_url(&req);
 
@@ -129,6 +131,7 @@ pub async fn handle_discord_callback(req: Request, env: Env) -> Result<Response>
         inbound_mentions: false,
         inbound_channel_ids: Vec::new(),
         auto_reply: ReplyConfig::default(),
+        brand_id: None,
     };
     save_discord_config(&kv, &config).await?;
 
@@ -285,6 +288,8 @@ async fn save_channels(req: &mut Request, kv: &kv::KvStore, tenant_id: &str) ->
     }) {
         cfg.auto_reply.wait_seconds = (n as u32).min(30);
     }
+    cfg.auto_reply
+        .apply_buffer_form(|k| crate::helpers::json_form_field(&form, k));
 
     save_discord_config(kv, &cfg).await?;
     Response::from_html(r#"<div class="success">Channels saved.</div>"#.to_string())
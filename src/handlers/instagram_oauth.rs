@@ -163,6 +163,7 @@ pub async fn handle_instagram(
                 page_id,
                 auto_reply: ReplyConfig::default(),
                 enabled: true,
+                brand_id: None,
                 created_at: now.clone(),
                 updated_at: now,
             };
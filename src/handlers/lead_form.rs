@@ -4,6 +4,7 @@ use worker::*;
 
 use super::get_origin;
 use crate::ai;
+use crate::channel::Body;
 use crate::helpers::*;
 use crate::storage::*;
 use crate::templates::*;
@@ -122,6 +123,26 @@ pub async fn handle_lead_form(
                 }
             };
 
+            // Numbers that opted out get nothing, not even the AI call. The
+            // visitor still sees the usual success page.
+            if crate::consent::is_suppressed(&db, &form.tenant_id, &Channel::WhatsApp, &phone)
+                .await?
+            {
+                let _ = save_lead_form_submission(
+                    &db,
+                    &generate_id(),
+                    form_id,
+                    &phone,
+                    &form.whatsapp_account_id,
+                    "",
+                    "suppressed",
+                    &form.tenant_id,
+                )
+                .await;
+                let resp = Response::from_html(lead_form_success_html(&form))?;
+                return Ok(with_cors(resp, origin.as_deref(), &form.allowed_origins));
+            }
+
             // Platform token
             let platform_token = env
                 .secret("WHATSAPP_ACCESS_TOKEN")
@@ -134,13 +155,31 @@ pub async fn handle_lead_form(
                     if prompt.is_empty() {
                         "Thanks for reaching out! We'll be in touch soon.".to_string()
                     } else {
+                        // The lead's number goes to the model as a
+                        // placeholder, like inbound bodies in the pipeline.
+                        let redaction = get_redaction_config(&kv, &form.tenant_id).await?;
+                        let classes: &[PiiClass] = if redaction.enabled {
+                            &redaction.classes
+                        } else {
+                            &[]
+                        };
+                        let redacted = crate::pii::redact(&phone, classes);
                         let mut context = serde_json::Map::new();
                         context.insert(
                             "phone_number".to_string(),
-                            serde_json::Value::String(phone.clone()),
+                            serde_json::Value::String(redacted.text.clone()),
                         );
-                        match ai::generate_response(&env, prompt, &context).await {
-                            Ok(r) => r,
+                        match ai::generate_response(
+                            &env,
+                            &form.tenant_id,
+                            &GenerationParams::default(),
+                            prompt,
+                            &context,
+                            &redacted,
+                        )
+                        .await
+                        {
+                            Ok(draft) => redacted.restore(&draft.text),
                             Err(e) => {
                                 console_log!("AI error for lead form: {:?}", e);
                                 interpolate_or_default(prompt, &phone)
@@ -157,16 +196,25 @@ pub async fn handle_lead_form(
                 }
             };
 
-            // Send WhatsApp message
-            if let Err(e) = send_whatsapp_message(
-                &platform_token,
-                &wa_account.phone_number_id,
-                &phone,
-                &message,
-            )
-            .await
-            {
-                console_log!("Failed to send lead form WhatsApp: {:?}", e);
+            // Send WhatsApp message, one part per chunk
+            // Only the AI's draft is Markdown; canned text goes as written.
+            let body = match form.reply {
+                ReplyResponse::Prompt { .. } => Body::Markdown(&message),
+                ReplyResponse::Canned { .. } => Body::Verbatim(&message),
+            };
+            let formatted = crate::channel::format::format_reply(&Channel::WhatsApp, body);
+            for chunk in &formatted.chunks {
+                if let Err(e) = send_whatsapp_message(
+                    &platform_token,
+                    &wa_account.phone_number_id,
+                    &phone,
+                    chunk,
+                )
+                .await
+                {
+                    console_log!("Failed to send lead form WhatsApp: {:?}", e);
+                    break;
+                }
             }
 
             // Log to D1: keep the historical column populated with the
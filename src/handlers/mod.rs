@@ -1,13 +1,26 @@
 //! Handler modules for the concierge worker
 
 mod admin;
+mod admin_agency;
+mod admin_analytics;
 mod admin_approvals;
 mod admin_billing;
+mod admin_brands;
+mod admin_consent;
+mod admin_contacts;
+mod admin_data_requests;
 mod admin_email;
+mod admin_exports;
+mod admin_guardrails;
+mod admin_inbox;
 mod admin_instagram;
 mod admin_lead_forms;
+mod admin_messages;
+mod admin_notifications;
 mod admin_persona;
 pub mod admin_rules;
+mod admin_team;
+mod admin_tools;
 mod admin_whatsapp;
 pub mod auth;
 mod data_deletion;
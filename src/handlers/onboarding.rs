@@ -133,6 +133,7 @@ pub async fn handle_wizard(
                         tenant_id: tenant_id.to_string(),
                         auto_reply: ReplyConfig::default(),
                         notification_recipients: vec![owner],
+                        brand_id: None,
                         created_at: now.clone(),
                         updated_at: now,
                     };
@@ -202,6 +203,9 @@ pub async fn handle_wizard(
                 approval_discord,
                 approval_email,
                 approval_email_cadence: crate::types::DigestCadence::from_str(cadence_raw),
+                weekly_summary: is_true("weekly_summary"),
+                email_kinds: state.notifications.email_kinds.clone(),
+                discord_kinds: state.notifications.discord_kinds.clone(),
             };
             state.step = OnboardingStep::Replies;
             save_onboarding(&kv, tenant_id, &state).await?;
@@ -241,6 +245,9 @@ pub async fn handle_wizard(
             let job = crate::safety_queue::SafetyJob {
                 tenant_id: tenant_id.to_string(),
                 prompt_hash: state.persona.active_prompt_hash(),
+                brand_id: None,
+                channel: None,
+                account_id: None,
             };
             state.persona.safety.status = PersonaSafetyStatus::Pending;
             state.step = OnboardingStep::Launch;
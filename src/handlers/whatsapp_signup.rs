@@ -25,10 +25,12 @@ pub async fn handle_whatsapp_signup(
         (Method::Post, ["callback"]) => {
             let kv = env.kv("KV")?;
 
-            // Authenticate via session cookie
-            let tenant_id = match super::auth::resolve_tenant_id(&req, &kv).await {
-                Some(id) => id,
-                None => return Response::error("Unauthorized", 401),
+            // Authenticate via session cookie; connecting a number is an
+            // admin's job.
+            let session = super::auth::resolve_session(&req, &env).await?;
+            let tenant_id = match super::auth::admin_tenant(session) {
+                Ok(id) => id,
+                Err((message, status)) => return Response::error(message, status),
             };
 
             let form = req.form_data().await?;
@@ -116,6 +118,7 @@ pub async fn handle_whatsapp_signup(
                 phone_number,
                 phone_number_id,
                 auto_reply: ReplyConfig::default(),
+                brand_id: None,
                 created_at: now.clone(),
                 updated_at: now,
             };
@@ -217,6 +220,8 @@ pub async fn handle_whatsapp_signup(
                     currency: signup_locale.currency,
                     email_address_extras_purchased: 0,
                     verified_at: None,
+                    credit_mode: crate::types::CreditMode::PerReply,
+                    retention_days: 0,
                     created_at: now.clone(),
                     updated_at: now,
                 };
@@ -255,12 +260,13 @@ pub async fn handle_whatsapp_signup(
                 phone_number,
                 phone_number_id,
                 auto_reply: ReplyConfig::default(),
+                brand_id: None,
                 created_at: now.clone(),
                 updated_at: now,
             };
             save_whatsapp_account(&kv, &account).await?;
 
-            super::auth::create_session_and_redirect(&req, &kv, &tenant.id, "whatsapp").await
+            super::auth::create_session_and_redirect(&req, &kv, &tenant.id, None, "whatsapp").await
         }
 
         _ => Response::error("Not Found", 404),
@@ -157,6 +157,16 @@ pub fn truncate(s: &str, max: usize) -> String {
     s.chars().take(max).collect()
 }
 
+/// Read a json-enc form field as a string. HTMX posts inputs as strings,
+/// but numbers and booleans are accepted too.
+pub fn json_form_field(form: &serde_json::Value, key: &str) -> Option<String> {
+    match form.get(key)? {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Null => None,
+        v => Some(v.to_string()),
+    }
+}
+
 /// Format a count for display in the user's locale. Indian locales (en-IN,
 /// hi-IN, ...) get last-3-then-2s grouping (1,00,000); Western locales get
 /// thousands grouping (100,000). Backed by icu's `FixedDecimalFormatter`.
@@ -0,0 +1,437 @@
+//! Web inbox persistence: conversations grouped by customer and channel,
+//! built from the message text tenants opt in to keeping
+//! (`Tenant::retention_days`). Bodies and reply metadata are AES-GCM
+//! encrypted with `ENCRYPTION_KEY` before they reach D1, and a daily cron
+//! purges anything older than the tenant's window. Every write is
+//! best-effort and a no-op for tenants who haven't opted in; each one pings
+//! the tenant's `ApprovalsDO` so open `/admin/inbox` tabs refresh.
+
+use worker::*;
+
+use crate::approvals;
+use crate::crypto::{decrypt_secret, encrypt_secret};
+use crate::helpers::generate_id;
+use crate::types::{Channel, ConversationStatus, InboundMessage, MessageAction, MessageDirection};
+
+/// Retention windows a tenant can pick, in days. 0 means off.
+pub const RETENTION_WINDOWS: [u32; 3] = [7, 30, 90];
+
+/// Messages kept per conversation; older ones are dropped on insert.
+pub const MAX_RETAINED_PER_CONVERSATION: u32 = 100;
+
+/// Longest body kept, in characters.
+pub const MAX_RETAINED_BODY: usize = 4000;
+
+/// Conversations listed on the inbox page.
+pub const INBOX_PAGE_SIZE: u32 = 50;
+
+/// One row of the `conversations` table.
+#[derive(Debug, Clone)]
+pub struct Conversation {
+    pub id: String,
+    pub channel: Channel,
+    pub channel_account_id: String,
+    pub contact: String,
+    pub contact_name: Option<String>,
+    /// Contact-book entry, when the sender was resolved to one.
+    pub contact_id: Option<String>,
+    /// Our side of the thread (business number, inbox address).
+    pub recipient: String,
+    pub reply_metadata: serde_json::Value,
+    pub status: ConversationStatus,
+    pub last_message_at: String,
+    /// Latest retained body, for the list view.
+    pub preview: Option<String>,
+}
+
+/// One row of the `conversation_messages` table.
+#[derive(Debug, Clone)]
+pub struct RetainedMessage {
+    pub direction: MessageDirection,
+    pub body: String,
+    pub action: Option<String>,
+    pub created_at: String,
+}
+
+/// Keep an inbound message's text and reopen its conversation.
+pub async fn record_inbound(env: &Env, msg: &InboundMessage) {
+    let result = async {
+        let db = env.d1("DB")?;
+        if !retains(&db, &msg.tenant_id).await? {
+            return Ok(false);
+        }
+        let key = encryption_key(env)?;
+        let conversation_id = upsert_conversation(&db, &key, msg).await?;
+        insert_message(
+            &db,
+            &key,
+            &conversation_id,
+            &msg.tenant_id,
+            MessageDirection::Inbound,
+            &msg.body,
+            None,
+        )
+        .await?;
+        Ok::<bool, Error>(true)
+    }
+    .await;
+    finish(env, &msg.tenant_id, result).await;
+}
+
+/// Record what answered (or held) a conversation. `body` is the text sent
+/// to the customer, `None` when nothing went out (queued, rejected,
+/// expired). Conversations that started before the tenant opted in are
+/// left alone.
+pub async fn record_reply(
+    env: &Env,
+    tenant_id: &str,
+    channel: &Channel,
+    contact: &str,
+    body: Option<&str>,
+    action: MessageAction,
+) {
+    let result = async {
+        let db = env.d1("DB")?;
+        if !retains(&db, tenant_id).await? {
+            return Ok(false);
+        }
+        let row = db
+            .prepare(
+                "UPDATE conversations SET status = ?, last_message_at = datetime('now')
+                 WHERE tenant_id = ? AND channel = ? AND contact = ?
+                 RETURNING id",
+            )
+            .bind(&[
+                ConversationStatus::after(action).as_str().into(),
+                tenant_id.into(),
+                channel.as_str().into(),
+                contact.into(),
+            ])?
+            .first::<serde_json::Value>(None)
+            .await?;
+        let Some(id) = row
+            .as_ref()
+            .and_then(|r| r.get("id"))
+            .and_then(|v| v.as_str())
+        else {
+            return Ok(false);
+        };
+        if let Some(body) = body {
+            let key = encryption_key(env)?;
+            let direction = if action == MessageAction::Relay {
+                MessageDirection::Relay
+            } else {
+                MessageDirection::Outbound
+            };
+            insert_message(&db, &key, id, tenant_id, direction, body, Some(action)).await?;
+        }
+        Ok::<bool, Error>(true)
+    }
+    .await;
+    finish(env, tenant_id, result).await;
+}
+
+async fn finish(env: &Env, tenant_id: &str, result: Result<bool>) {
+    match result {
+        Ok(true) => {
+            if let Err(e) = approvals::broadcast(env, tenant_id, "inbox-changed").await {
+                console_log!("Inbox broadcast failed for tenant {tenant_id}: {e:?}");
+            }
+        }
+        Ok(false) => {}
+        Err(e) => console_log!("Inbox record failed for tenant {tenant_id}: {e:?}"),
+    }
+}
+
+/// The window to store for a requested one: one of `RETENTION_WINDOWS`,
+/// anything else turns retention off.
+pub fn retention_window(days: u32) -> u32 {
+    if RETENTION_WINDOWS.contains(&days) {
+        days
+    } else {
+        0
+    }
+}
+
+/// Without the key nothing is kept: history is never stored in the clear.
+pub fn encryption_key(env: &Env) -> Result<String> {
+    env.secret("ENCRYPTION_KEY")
+        .map(|s| s.to_string())
+        .map_err(|_| Error::from("ENCRYPTION_KEY not set; message history is not kept"))
+}
+
+async fn retains(db: &D1Database, tenant_id: &str) -> Result<bool> {
+    let row = db
+        .prepare("SELECT retention_days FROM tenants WHERE id = ?")
+        .bind(&[tenant_id.into()])?
+        .first::<serde_json::Value>(None)
+        .await?;
+    Ok(row
+        .and_then(|r| r.get("retention_days").and_then(|v| v.as_i64()))
+        .is_some_and(|n| n > 0))
+}
+
+async fn upsert_conversation(db: &D1Database, key: &str, msg: &InboundMessage) -> Result<String> {
+    let name = msg
+        .sender_name
+        .clone()
+        .map(wasm_bindgen::JsValue::from)
+        .unwrap_or(wasm_bindgen::JsValue::null());
+    let row = db
+        .prepare(
+            "INSERT INTO conversations (
+                 id, tenant_id, channel, channel_account_id, contact, contact_name,
+                 contact_id, recipient, reply_metadata, status
+             ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, 'open')
+             ON CONFLICT(tenant_id, channel, contact) DO UPDATE SET
+               channel_account_id = excluded.channel_account_id,
+               contact_name = COALESCE(excluded.contact_name, contact_name),
+               contact_id = COALESCE(excluded.contact_id, contact_id),
+               recipient = excluded.recipient,
+               reply_metadata = excluded.reply_metadata,
+               status = 'open',
+               last_message_at = datetime('now')
+             RETURNING id",
+        )
+        .bind(&[
+            generate_id().into(),
+            msg.tenant_id.clone().into(),
+            msg.channel.as_str().into(),
+            msg.channel_account_id.clone().into(),
+            msg.sender.clone().into(),
+            name,
+            msg.contact_id
+                .clone()
+                .map(wasm_bindgen::JsValue::from)
+                .unwrap_or(wasm_bindgen::JsValue::null()),
+            msg.recipient.clone().into(),
+            encrypt_secret(&msg.raw_metadata.to_string(), key)
+                .await?
+                .into(),
+        ])?
+        .first::<serde_json::Value>(None)
+        .await?;
+    row.and_then(|r| r.get("id").and_then(|v| v.as_str()).map(str::to_string))
+        .ok_or_else(|| Error::from("conversation upsert returned no id"))
+}
+
+/// Insert one message, then drop the conversation's oldest beyond the cap.
+async fn insert_message(
+    db: &D1Database,
+    key: &str,
+    conversation_id: &str,
+    tenant_id: &str,
+    direction: MessageDirection,
+    body: &str,
+    action: Option<MessageAction>,
+) -> Result<()> {
+    let body: String = body.chars().take(MAX_RETAINED_BODY).collect();
+    let action = action
+        .map(|a| wasm_bindgen::JsValue::from(a.as_str()))
+        .unwrap_or(wasm_bindgen::JsValue::null());
+    db.prepare(
+        "INSERT INTO conversation_messages (id, conversation_id, tenant_id, direction, body, action_taken)
+         VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&[
+        generate_id().into(),
+        conversation_id.into(),
+        tenant_id.into(),
+        direction.as_str().into(),
+        encrypt_secret(&body, key).await?.into(),
+        action,
+    ])?
+    .run()
+    .await?;
+    db.prepare(
+        "DELETE FROM conversation_messages
+         WHERE conversation_id = ?1 AND id NOT IN (
+             SELECT id FROM conversation_messages WHERE conversation_id = ?1
+             ORDER BY created_at DESC, rowid DESC LIMIT ?2
+         )",
+    )
+    .bind(&[
+        conversation_id.into(),
+        wasm_bindgen::JsValue::from(MAX_RETAINED_PER_CONVERSATION as f64),
+    ])?
+    .run()
+    .await?;
+    Ok(())
+}
+
+/// Most recently active conversations, optionally only those in `status`.
+pub async fn list_conversations(
+    db: &D1Database,
+    key: &str,
+    tenant_id: &str,
+    status: Option<ConversationStatus>,
+) -> Result<Vec<Conversation>> {
+    let status_filter = status.map(|s| s.as_str()).unwrap_or("");
+    let rows = db
+        .prepare(
+            "SELECT c.*, (
+                 SELECT body FROM conversation_messages m
+                 WHERE m.conversation_id = c.id
+                 ORDER BY m.created_at DESC, m.rowid DESC LIMIT 1
+             ) AS preview
+             FROM conversations c
+             WHERE c.tenant_id = ?1 AND (?2 = '' OR c.status = ?2)
+             ORDER BY c.last_message_at DESC LIMIT ?3",
+        )
+        .bind(&[
+            tenant_id.into(),
+            status_filter.into(),
+            wasm_bindgen::JsValue::from(INBOX_PAGE_SIZE as f64),
+        ])?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    let mut conversations = Vec::with_capacity(rows.len());
+    for row in &rows {
+        conversations.push(row_to_conversation(row, key).await);
+    }
+    Ok(conversations)
+}
+
+pub async fn get_conversation(
+    db: &D1Database,
+    key: &str,
+    tenant_id: &str,
+    id: &str,
+) -> Result<Option<Conversation>> {
+    let row = db
+        .prepare("SELECT *, NULL AS preview FROM conversations WHERE id = ? AND tenant_id = ?")
+        .bind(&[id.into(), tenant_id.into()])?
+        .first::<serde_json::Value>(None)
+        .await?;
+    Ok(match row {
+        Some(row) => Some(row_to_conversation(&row, key).await),
+        None => None,
+    })
+}
+
+/// A conversation's retained messages, oldest first.
+pub async fn list_messages(
+    db: &D1Database,
+    key: &str,
+    conversation_id: &str,
+) -> Result<Vec<RetainedMessage>> {
+    let rows = db
+        .prepare(
+            "SELECT direction, body, action_taken, created_at FROM conversation_messages
+             WHERE conversation_id = ? ORDER BY created_at, rowid",
+        )
+        .bind(&[conversation_id.into()])?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    let mut messages = Vec::with_capacity(rows.len());
+    for row in &rows {
+        let s = |k: &str| row.get(k).and_then(|v| v.as_str()).map(str::to_string);
+        messages.push(RetainedMessage {
+            direction: s("direction")
+                .as_deref()
+                .and_then(MessageDirection::from_wire)
+                .unwrap_or(MessageDirection::Inbound),
+            body: decrypt(s("body"), key).await.unwrap_or_default(),
+            action: s("action_taken"),
+            created_at: s("created_at").unwrap_or_default(),
+        });
+    }
+    Ok(messages)
+}
+
+/// Delete retained messages older than each tenant's window (all of them
+/// for tenants with retention off), then conversations left empty or idle
+/// past it. Run daily by the retention cron.
+pub async fn purge_expired(db: &D1Database) -> Result<()> {
+    db.prepare(
+        "DELETE FROM conversation_messages WHERE id IN (
+             SELECT m.id FROM conversation_messages m
+             LEFT JOIN tenants t ON t.id = m.tenant_id
+             WHERE COALESCE(t.retention_days, 0) = 0
+                OR m.created_at < datetime('now', '-' || t.retention_days || ' days')
+         )",
+    )
+    .run()
+    .await?;
+    db.prepare(
+        "DELETE FROM conversations WHERE id IN (
+             SELECT c.id FROM conversations c
+             LEFT JOIN tenants t ON t.id = c.tenant_id
+             WHERE COALESCE(t.retention_days, 0) = 0
+                OR c.last_message_at < datetime('now', '-' || t.retention_days || ' days')
+                OR NOT EXISTS (
+                    SELECT 1 FROM conversation_messages m WHERE m.conversation_id = c.id
+                )
+         )",
+    )
+    .run()
+    .await?;
+    Ok(())
+}
+
+/// Drop every retained conversation for the tenant. Run when they opt out
+/// and when the tenant is deleted.
+pub async fn forget(db: &D1Database, tenant_id: &str) -> Result<()> {
+    db.prepare("DELETE FROM conversation_messages WHERE tenant_id = ?")
+        .bind(&[tenant_id.into()])?
+        .run()
+        .await?;
+    db.prepare("DELETE FROM conversations WHERE tenant_id = ?")
+        .bind(&[tenant_id.into()])?
+        .run()
+        .await?;
+    Ok(())
+}
+
+/// Decrypt a stored value; rows that don't decrypt (key rotated, corrupt)
+/// read as missing rather than failing the page.
+async fn decrypt(value: Option<String>, key: &str) -> Option<String> {
+    let value = value?;
+    match decrypt_secret(&value, key).await {
+        Ok(plain) => Some(plain),
+        Err(e) => {
+            console_log!("Inbox decrypt failed: {e:?}");
+            None
+        }
+    }
+}
+
+async fn row_to_conversation(row: &serde_json::Value, key: &str) -> Conversation {
+    let s = |k: &str| row.get(k).and_then(|v| v.as_str()).map(str::to_string);
+    Conversation {
+        id: s("id").unwrap_or_default(),
+        channel: s("channel")
+            .as_deref()
+            .and_then(Channel::from_wire)
+            .unwrap_or(Channel::Email),
+        channel_account_id: s("channel_account_id").unwrap_or_default(),
+        contact: s("contact").unwrap_or_default(),
+        contact_name: s("contact_name"),
+        contact_id: s("contact_id"),
+        recipient: s("recipient").unwrap_or_default(),
+        reply_metadata: decrypt(s("reply_metadata"), key)
+            .await
+            .and_then(|m| serde_json::from_str(&m).ok())
+            .unwrap_or_default(),
+        status: s("status")
+            .as_deref()
+            .and_then(ConversationStatus::from_wire)
+            .unwrap_or_default(),
+        last_message_at: s("last_message_at").unwrap_or_default(),
+        preview: decrypt(s("preview"), key).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retention_window_only_accepts_offered_windows() {
+        assert_eq!(retention_window(30), 30);
+        assert_eq!(retention_window(0), 0);
+        assert_eq!(retention_window(365), 0);
+    }
+}
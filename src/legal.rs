@@ -1,7 +1,8 @@
 //! Legal pages: Terms of Service and Privacy Policy
 
-use crate::i18n::t;
+use crate::i18n::{t, t_args};
 use crate::locale::Locale;
+use crate::retention::Retention;
 use crate::templates::base::{base_html_with_meta, brand_mark, PageMeta};
 
 pub fn terms_of_service_html(locale: &Locale) -> String {
@@ -80,7 +81,52 @@ pub fn terms_of_service_html(locale: &Locale) -> String {
     )
 }
 
-pub fn privacy_policy_html(locale: &Locale) -> String {
+/// `history_days` is the signed-in tenant's message-history window
+/// (`Tenant::retention_days`, 0 = off); `None` for anonymous visitors.
+/// `retention` is the log retention in effect: the tenant's own for a
+/// signed-in visitor, the operator defaults otherwise.
+pub fn privacy_policy_html(
+    locale: &Locale,
+    history_days: Option<u32>,
+    retention: &[Retention],
+) -> String {
+    let periods: String = retention
+        .iter()
+        .map(|r| {
+            let period = match r.days() {
+                0 => t(locale, "privacy-retention-forever"),
+                days => t_args(
+                    locale,
+                    "privacy-retention-period",
+                    &[("days", &days.to_string())],
+                ),
+            };
+            format!("<li>{}: {period}</li>", t(locale, &r.table.privacy_key()))
+        })
+        .collect();
+    let p_periods = if periods.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "<p>{}</p>\n  <ul>{periods}</ul>",
+            t(locale, "privacy-p-retention-periods")
+        )
+    };
+    let p_history = match history_days {
+        Some(0) => format!(
+            "<p><strong>{}</strong></p>",
+            t(locale, "privacy-p-history-off")
+        ),
+        Some(days) => format!(
+            "<p><strong>{}</strong></p>",
+            t_args(
+                locale,
+                "privacy-p-history-on",
+                &[("days", &days.to_string())]
+            )
+        ),
+        None => String::new(),
+    };
     let content = format!(
         r##"<header class="site-header">
   {brand}
@@ -124,6 +170,8 @@ pub fn privacy_policy_html(locale: &Locale) -> String {
 
   <h2>{h2_retention}</h2>
   <p>{p_retention}</p>
+  {p_periods}
+  {p_history}
 
   <h2>{h2_deletion}</h2>
   <p>{p_deletion_prefix}</p>
@@ -16,6 +16,8 @@
 //! - `types`: Core data structures (Tenant, WhatsAppAccount, InstagramAccount, LeadCaptureForm)
 //! - `storage`: Cloudflare KV and D1 operations
 //! - `ai`: Cloudflare Workers AI integration for auto-reply generation
+//! - `guardrails`: post-generation checks on AI drafts (links, contacts, prompt leaks)
+//! - `pii`: masks personal data (cards, Aadhaar, PAN, contacts) before model calls
 //! - `whatsapp`: Meta Graph API client for sending WhatsApp messages
 //! - `instagram`: Facebook Login OAuth and Instagram DM sending
 //! - `crypto`: AES-256-GCM encryption and HMAC-SHA256 verification
@@ -24,29 +26,46 @@
 use wasm_bindgen::prelude::*;
 use worker::*;
 
+mod agency;
 mod ai;
+mod analytics;
 mod approval;
 mod approvals;
+mod archive;
 mod billing;
+mod brands;
 mod channel;
+mod consent;
+mod contacts;
 mod crypto;
+mod data_requests;
+mod detect;
 mod discord;
 mod durable_objects;
 mod email;
+mod exports;
+mod guardrails;
 mod handlers;
 mod helpers;
 mod i18n;
+mod inbox;
 mod instagram;
 mod legal;
 mod locale;
 mod management;
+mod message_log;
+mod notifications;
 mod personas;
+mod pii;
 mod pipeline;
+mod retention;
 mod safety;
 mod safety_queue;
 mod scheduled;
 mod storage;
+mod team;
 mod templates;
+mod tenant_deletion;
 mod types;
 mod whatsapp;
 
@@ -310,7 +329,24 @@ async fn handle_request(req: Request, env: Env) -> Result<Response> {
 
     // Privacy Policy
     if path == "/privacy" {
-        return Response::from_html(legal::privacy_policy_html(&request_locale));
+        // Signed-in tenants see their own message-history setting and
+        // log retention; everyone else the operator defaults.
+        let db = env.d1("DB")?;
+        let session = handlers::auth::resolve_session(&req, &env).await?;
+        let (history_days, retention) = match session.map(|s| s.tenant_id) {
+            Some(tenant_id) => (
+                storage::get_tenant(&db, &tenant_id)
+                    .await?
+                    .map(|t| t.retention_days),
+                retention::for_tenant(&db, &tenant_id).await?,
+            ),
+            None => (None, retention::defaults(&db).await?),
+        };
+        return Response::from_html(legal::privacy_policy_html(
+            &request_locale,
+            history_days,
+            &retention,
+        ));
     }
 
     // Marketing features overview
@@ -409,8 +445,7 @@ async fn handle_request(req: Request, env: Env) -> Result<Response> {
 
     // Landing → dashboard if already signed in, otherwise welcome page
     if path == "/" || path == "/index.html" {
-        let kv = env.kv("KV")?;
-        if handlers::auth::resolve_tenant_id(&req, &kv).await.is_some() {
+        if handlers::auth::resolve_session(&req, &env).await?.is_some() {
             let headers = Headers::new();
             headers.set("Location", "/admin")?;
             return Ok(Response::empty()?.with_status(302).with_headers(headers));
@@ -456,26 +491,41 @@ async fn handle_email_verify(env: Env, token: &str, locale: &locale::Locale) ->
         };
 
     let now = helpers::now_iso();
-    let mut found = false;
+    let mut verified = None;
     for r in addr.notification_recipients.iter_mut() {
         if r.id == payload.recipient_id {
             r.status = types::RecipientStatus::Verified;
             r.verified_at = Some(now.clone());
-            found = true;
+            verified = Some(r.address.clone());
             break;
         }
     }
     let _ = storage::delete_email_verification_token(&kv, token).await;
 
-    if !found {
+    let Some(recipient) = verified else {
         return Response::from_html(email_verify_result_html(
             "This recipient has been removed. No further action needed.",
             locale,
         ));
-    }
+    };
 
     addr.updated_at = now;
     storage::save_email_address(&kv, &payload.tenant_id, &addr).await?;
+    notifications::notify(
+        &env,
+        &payload.tenant_id,
+        notifications::Notice {
+            kind: types::NotificationKind::RecipientVerified,
+            title: format!("{recipient} confirmed"),
+            body: format!(
+                "{recipient} is now copied on replies sent from {}.",
+                payload.local_part
+            ),
+            link: format!("/admin/email/addresses/{}", payload.local_part),
+            key: payload.recipient_id.clone(),
+        },
+    )
+    .await;
     Response::from_html(email_verify_result_html(
         "You're verified: replies sent from this Concierge address will now copy you.",
         locale,
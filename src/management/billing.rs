@@ -5,6 +5,7 @@ use worker::*;
 use crate::management::audit;
 use crate::storage;
 use crate::templates::management as tmpl;
+use crate::types::ModelTier;
 
 pub async fn handle_billing(
     mut req: Request,
@@ -48,15 +49,71 @@ pub async fn handle_billing(
                     .or_else(|| v.as_str().and_then(|s| s.parse::<i64>().ok()))
             };
 
-            // Currency-agnostic settings.
+            // Currency-agnostic settings. The tier block is only applied
+            // when the form carries it, so older clients that post just
+            // the pack size don't wipe the allow-list.
+            let mut cfg = storage::get_pricing(db).await;
             if let Some(n) = pick("email_pack_size") {
                 if n <= 0 {
                     return Response::from_html(
                         r#"<div class="error">Invalid value for email_pack_size: must be a positive integer.</div>"#.to_string(),
                     );
                 }
-                storage::update_pricing_config(db, n).await?;
+                cfg.email_pack_size = n;
             }
+            if form.get("fast_reply_credits").is_some() {
+                for tier in ModelTier::ALL {
+                    let key = format!("{}_reply_credits", tier.as_str());
+                    let n = pick(&key).unwrap_or(0);
+                    if n <= 0 {
+                        return Response::from_html(format!(
+                            r#"<div class="error">Invalid value for {key}: must be a positive integer.</div>"#,
+                        ));
+                    }
+                    match tier {
+                        ModelTier::Fast => cfg.fast_reply_credits = n,
+                        ModelTier::Quality => cfg.quality_reply_credits = n,
+                    }
+                }
+                let allowed: Vec<ModelTier> = ModelTier::ALL
+                    .iter()
+                    .copied()
+                    .filter(|t| form.get(format!("tier_allowed__{}", t.as_str())).is_some())
+                    .collect();
+                if allowed.is_empty() {
+                    return Response::from_html(
+                        r#"<div class="error">Allow at least one model tier.</div>"#,
+                    );
+                }
+                cfg.allowed_tiers = allowed;
+            }
+            if form.get("tokens_per_credit").is_some() {
+                let n = pick("tokens_per_credit").unwrap_or(0);
+                if n <= 0 {
+                    return Response::from_html(
+                        r#"<div class="error">Invalid value for tokens_per_credit: must be a positive integer.</div>"#,
+                    );
+                }
+                cfg.tokens_per_credit = n;
+                let rates = [
+                    ("fast_input_rate", &mut cfg.fast_rate.input),
+                    ("fast_output_rate", &mut cfg.fast_rate.output),
+                    ("quality_input_rate", &mut cfg.quality_rate.input),
+                    ("quality_output_rate", &mut cfg.quality_rate.output),
+                    ("embedding_input_rate", &mut cfg.embedding_rate.input),
+                ];
+                for (key, slot) in rates {
+                    match pick(key) {
+                        Some(n) if n >= 0 => *slot = n,
+                        _ => {
+                            return Response::from_html(format!(
+                                r#"<div class="error">Invalid value for {key}: must be zero or more.</div>"#,
+                            ));
+                        }
+                    }
+                }
+            }
+            storage::update_pricing_config(db, &cfg).await?;
 
             // Per-(concept, currency) cells. We accept any currency code
             // the form sends, so adding a currency client-side just works.
@@ -0,0 +1,70 @@
+//! Tenant deletion jobs: progress, reports and re-verification.
+
+use worker::*;
+
+use crate::management::audit;
+use crate::templates::management as tmpl;
+use crate::tenant_deletion;
+
+/// Jobs listed on the page.
+const DELETIONS_SHOWN: u32 = 100;
+
+pub async fn handle_deletions(
+    req: Request,
+    env: &Env,
+    db: &D1Database,
+    sub: &str,
+    method: Method,
+    actor_email: &str,
+    base_url: &str,
+) -> Result<Response> {
+    let parts: Vec<&str> = sub
+        .strip_prefix("deletions")
+        .unwrap_or("")
+        .trim_start_matches('/')
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    let locale = crate::locale::Locale::from_request(&req);
+
+    match (method, parts.as_slice()) {
+        (Method::Get, []) => {
+            let jobs = tenant_deletion::list_deletions(db, DELETIONS_SHOWN).await?;
+            Response::from_html(tmpl::deletions_html(&jobs, base_url, &locale))
+        }
+
+        // Carry on with a running job now rather than waiting for the cron.
+        (Method::Post, [id, "resume"]) => {
+            let Some(mut job) = tenant_deletion::get_deletion(db, id).await? else {
+                return Response::error("Deletion not found", 404);
+            };
+            let deadline = js_sys::Date::now() + tenant_deletion::INLINE_BUDGET_MS;
+            tenant_deletion::run(env, &mut job, deadline).await?;
+            Response::from_html(tmpl::deletion_row_html(&job, base_url))
+        }
+
+        // Look again for anything left under the tenant id.
+        (Method::Post, [id, "verify"]) => {
+            let Some(mut job) = tenant_deletion::get_deletion(db, id).await? else {
+                return Response::error("Deletion not found", 404);
+            };
+            tenant_deletion::verify(env, &mut job).await?;
+            audit::log_action(
+                db,
+                actor_email,
+                "verify_tenant_deletion",
+                "tenant",
+                Some(&job.tenant_id),
+                Some(&serde_json::json!({
+                    "deletion_id": job.id,
+                    "remaining": job.verification,
+                })),
+            )
+            .await?;
+            Response::from_html(tmpl::deletion_row_html(&job, base_url))
+        }
+
+        _ => Response::error("Not Found", 404),
+    }
+}
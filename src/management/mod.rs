@@ -3,7 +3,10 @@
 
 pub mod audit;
 pub mod billing;
+pub mod deletions;
+pub mod retention;
 pub mod tenants;
+pub mod usage;
 
 use wasm_bindgen::JsCast;
 use worker::*;
@@ -37,6 +40,10 @@ pub async fn handle_management(
         return tenants::handle_tenants(req, &env, &kv, &db, sub, method, &email, &base_url).await;
     }
 
+    if sub.starts_with("deletions") {
+        return deletions::handle_deletions(req, &env, &db, sub, method, &email, &base_url).await;
+    }
+
     if sub.starts_with("billing") {
         return billing::handle_billing(req, &kv, &db, sub, method, &email, &base_url).await;
     }
@@ -54,6 +61,12 @@ pub async fn handle_management(
             ))
         }
 
+        (Method::Get, "usage") => usage::handle_usage(&db, &base_url, &locale).await,
+
+        (method, "retention") => {
+            retention::handle_retention(req, &db, method, &email, &base_url).await
+        }
+
         (Method::Get, "audit") => {
             let log = audit::get_audit_log(&db, 100).await?;
             Response::from_html(tmpl::audit_html(&log, &base_url, &locale))
@@ -0,0 +1,64 @@
+//! Management retention — default periods for the log tables.
+
+use worker::*;
+
+use crate::management::audit;
+use crate::retention;
+use crate::templates::management as tmpl;
+
+pub async fn handle_retention(
+    mut req: Request,
+    db: &D1Database,
+    method: Method,
+    actor_email: &str,
+    base_url: &str,
+) -> Result<Response> {
+    let locale = crate::locale::Locale::from_request(&req);
+
+    match method {
+        Method::Get => {
+            let defaults = retention::defaults(db).await?;
+            let rolled = retention::rolled_up_until(db).await?;
+            Response::from_html(tmpl::retention_html(&defaults, &rolled, base_url, &locale))
+        }
+
+        // The form posts one field per table, named after it, holding days.
+        Method::Post => {
+            let form: serde_json::Value = req.json().await?;
+            let mut changed = serde_json::Map::new();
+            for current in retention::defaults(db).await? {
+                let Some(days) = form_days(&form, current.table.name) else {
+                    continue;
+                };
+                if days != current.default_days {
+                    retention::save_default(db, current.table, days).await?;
+                    changed.insert(current.table.name.to_string(), days.into());
+                }
+            }
+            if !changed.is_empty() {
+                audit::log_action(
+                    db,
+                    actor_email,
+                    "update_retention",
+                    "retention",
+                    None,
+                    Some(&serde_json::Value::Object(changed)),
+                )
+                .await?;
+            }
+            Response::from_html(r#"<div class="success">Retention saved</div>"#)
+        }
+
+        _ => Response::error("Not Found", 404),
+    }
+}
+
+/// A period from the form, if it's one of the offered choices.
+pub fn form_days(form: &serde_json::Value, field: &str) -> Option<u32> {
+    let v = form.get(field)?;
+    let days = v
+        .as_u64()
+        .or_else(|| v.as_str().and_then(|s| s.trim().parse().ok()))?;
+    let days = u32::try_from(days).ok()?;
+    retention::CHOICES.contains(&days).then_some(days)
+}
@@ -8,7 +8,7 @@ use crate::templates::management as tmpl;
 
 pub async fn handle_tenants(
     mut req: Request,
-    _env: &Env,
+    env: &Env,
     kv: &kv::KvStore,
     db: &D1Database,
     sub: &str,
@@ -44,8 +44,20 @@ pub async fn handle_tenants(
             let addrs = get_email_addresses(kv, id).await?;
             let mut billing = get_tenant_billing(db, id).await?;
             crate::billing::refresh_billing(&mut billing);
+            let ai_pin = get_ai_override(kv, id).await.ok().flatten();
+            let pricing = get_pricing(db).await;
+            let retention = crate::retention::for_tenant(db, id).await?;
             Response::from_html(tmpl::tenant_detail_html(
-                &tenant, &wa, &ig, &addrs, &billing, base_url, &locale,
+                &tenant,
+                &wa,
+                &ig,
+                &addrs,
+                &billing,
+                ai_pin.as_ref(),
+                &retention,
+                pricing.tokens_per_credit,
+                base_url,
+                &locale,
             ))
         }
 
@@ -138,6 +150,89 @@ pub async fn handle_tenants(
             ))
         }
 
+        // Pin a tenant to an AI provider/model, or clear the pin (empty
+        // provider) so the operator default applies again.
+        (Method::Post, [id, "ai-provider"]) => {
+            if get_tenant(db, id).await?.is_none() {
+                return Response::error("Tenant not found", 404);
+            }
+            let form: serde_json::Value = req.json().await?;
+            let field = |key: &str| -> Option<String> {
+                form.get(key)
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+            };
+
+            let msg = match field("provider") {
+                None => {
+                    delete_ai_override(kv, id).await?;
+                    "AI provider reset to the operator default.".to_string()
+                }
+                Some(wire) => {
+                    let provider = match crate::types::AiProvider::from_wire(&wire)
+                        .filter(|p| crate::types::AiProvider::SELECTABLE.contains(p))
+                    {
+                        Some(p) => p,
+                        None => {
+                            return Response::from_html(format!(
+                                r#"<div class="error">Unknown provider "{}".</div>"#,
+                                crate::helpers::html_escape(&wire)
+                            ))
+                        }
+                    };
+                    let pin = crate::types::AiOverride {
+                        provider,
+                        model: field("model"),
+                        fast_model: field("fast_model"),
+                    };
+                    save_ai_override(kv, id, &pin).await?;
+                    format!("AI provider set to {}.", provider.label())
+                }
+            };
+
+            audit::log_action(
+                db,
+                actor_email,
+                "update_ai_provider",
+                "tenant",
+                Some(id),
+                Some(&form),
+            )
+            .await?;
+
+            Response::from_html(format!(r#"<div class="success">{msg}</div>"#))
+        }
+
+        // Retention overrides: one field per table, "" for the default.
+        (Method::Post, [id, "retention"]) => {
+            let form: serde_json::Value = req.json().await?;
+            let mut changes = serde_json::Map::new();
+            for current in crate::retention::for_tenant(db, id).await? {
+                let table = current.table;
+                if !crate::retention::overridable(table) || form.get(table.name).is_none() {
+                    continue;
+                }
+                let days = crate::management::retention::form_days(&form, table.name);
+                if days != current.override_days {
+                    crate::retention::save_override(db, id, table, days).await?;
+                    changes.insert(table.name.to_string(), days.into());
+                }
+            }
+            if !changes.is_empty() {
+                audit::log_action(
+                    db,
+                    actor_email,
+                    "update_tenant_retention",
+                    "tenant",
+                    Some(id),
+                    Some(&serde_json::Value::Object(changes)),
+                )
+                .await?;
+            }
+            Response::from_html(r#"<div class="success">Retention saved</div>"#)
+        }
+
         // Update tenant (plan)
         (Method::Put, [id]) => {
             let form: serde_json::Value = req.json().await?;
@@ -153,6 +248,13 @@ pub async fn handle_tenants(
             {
                 tenant.plan = plan;
             }
+            if let Some(mode) = form
+                .get("credit_mode")
+                .and_then(|v| v.as_str())
+                .and_then(crate::types::CreditMode::from_wire)
+            {
+                tenant.credit_mode = mode;
+            }
             tenant.updated_at = crate::helpers::now_iso();
             save_tenant(db, &tenant).await?;
 
@@ -169,12 +271,22 @@ pub async fn handle_tenants(
             Response::from_html(r#"<div class="success">Tenant updated</div>"#.to_string())
         }
 
-        // Delete tenant
+        // Delete tenant. Whatever doesn't finish in this request is
+        // resumed by the cron; the job shows under /manage/deletions.
         (Method::Delete, [id]) => {
-            audit::log_action(db, actor_email, "delete_tenant", "tenant", Some(id), None).await?;
-
-            delete_tenant_data(kv, db, id).await?;
-            Ok(Response::empty()?.with_status(200))
+            let job = crate::tenant_deletion::start(env, id, actor_email).await?;
+            audit::log_action(
+                db,
+                actor_email,
+                "delete_tenant",
+                "tenant",
+                Some(id),
+                Some(&serde_json::json!({ "deletion_id": job.id })),
+            )
+            .await?;
+            let headers = Headers::new();
+            headers.set("HX-Redirect", &format!("{base_url}/manage/deletions"))?;
+            Ok(Response::empty()?.with_status(200).with_headers(headers))
         }
 
         _ => Response::error("Not Found", 404),
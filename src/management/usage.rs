@@ -0,0 +1,95 @@
+//! Management usage report — what each tenant's model calls cost the
+//! operator next to what the tenant paid.
+
+use std::collections::BTreeMap;
+
+use worker::*;
+
+use crate::storage::{self, Pricing, UsageTotal};
+use crate::templates::management as tmpl;
+use crate::types::{CreditMode, Tenant};
+
+/// Days covered by the usage report.
+pub const USAGE_REPORT_DAYS: u32 = 30;
+
+/// One tenant's row in the usage report. Costs are micro-USD; revenue is
+/// minor units per currency code.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TenantUsage {
+    pub tenant_id: String,
+    pub email: String,
+    pub credit_mode: CreditMode,
+    pub calls: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    /// Reply drafts, including tool-call turns.
+    pub reply_cost: i64,
+    /// Injection scans, safety checks, guardrail reviews and embeddings.
+    pub overhead_cost: i64,
+    pub revenue: BTreeMap<String, i64>,
+}
+
+impl TenantUsage {
+    pub fn total_cost(&self) -> i64 {
+        self.reply_cost + self.overhead_cost
+    }
+}
+
+pub async fn handle_usage(
+    db: &D1Database,
+    base_url: &str,
+    locale: &crate::locale::Locale,
+) -> Result<Response> {
+    let tenants = storage::list_tenants(db).await?;
+    let totals = storage::ai_usage_totals(db, USAGE_REPORT_DAYS).await?;
+    let revenue = storage::revenue_totals(db, USAGE_REPORT_DAYS).await?;
+    let pricing = storage::get_pricing(db).await;
+    let rows = summarize(&tenants, &totals, &revenue, &pricing);
+    Response::from_html(tmpl::usage_report_html(&rows, base_url, locale))
+}
+
+/// Fold usage and payments into one row per tenant that either called a
+/// model or paid in the window, most expensive first. Tenants deleted
+/// since keep their id in place of an email.
+pub fn summarize(
+    tenants: &[Tenant],
+    totals: &[UsageTotal],
+    revenue: &[(String, String, i64)],
+    pricing: &Pricing,
+) -> Vec<TenantUsage> {
+    let blank = |id: &str| {
+        let tenant = tenants.iter().find(|t| t.id == id);
+        TenantUsage {
+            tenant_id: id.to_string(),
+            email: tenant
+                .map(|t| t.email.clone())
+                .unwrap_or_else(|| id.to_string()),
+            credit_mode: tenant.map(|t| t.credit_mode).unwrap_or_default(),
+            ..Default::default()
+        }
+    };
+    let mut rows: BTreeMap<&str, TenantUsage> = BTreeMap::new();
+
+    for total in totals {
+        let r = rows
+            .entry(&total.tenant_id)
+            .or_insert_with(|| blank(&total.tenant_id));
+        let cost = pricing.token_cost(total.tier, total.input_tokens, total.output_tokens);
+        if total.purpose == crate::ai::Purpose::Reply.as_str() {
+            r.reply_cost += cost;
+        } else {
+            r.overhead_cost += cost;
+        }
+        r.calls += total.calls;
+        r.input_tokens += total.input_tokens;
+        r.output_tokens += total.output_tokens;
+    }
+    for (tenant_id, currency, amount) in revenue {
+        let r = rows.entry(tenant_id).or_insert_with(|| blank(tenant_id));
+        *r.revenue.entry(currency.clone()).or_insert(0) += amount;
+    }
+
+    let mut out: Vec<TenantUsage> = rows.into_values().collect();
+    out.sort_by_key(|r| std::cmp::Reverse(r.total_cost()));
+    out
+}
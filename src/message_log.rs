@@ -0,0 +1,459 @@
+//! The admin message log: the tenant's `messages` rows filtered by
+//! channel, account, direction, action, customer, contact, rule and date,
+//! newest first. Pages are keyed on `(created_at, id)` so new rows don't
+//! shift the page being read, and the CSV export walks the same filter.
+
+use serde::Serialize;
+use wasm_bindgen::JsValue;
+use worker::*;
+
+use crate::types::{Channel, MessageAction, MessageDirection};
+
+/// Rows per page in the admin log.
+pub const PAGE_SIZE: u32 = 50;
+
+/// Rows in one CSV export. Narrow the filter for more.
+pub const CSV_MAX_ROWS: usize = 10_000;
+
+/// Rows fetched per query while exporting.
+const EXPORT_BATCH: u32 = 500;
+
+/// Longest free-text filter value kept.
+const MAX_TEXT: usize = 200;
+
+/// What the log is narrowed to. Empty strings and `None` don't filter.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Filter {
+    pub channel: Option<Channel>,
+    /// `channel_account_id`: the WhatsApp number, Instagram account, email
+    /// address or Discord server.
+    pub account: String,
+    pub direction: Option<MessageDirection>,
+    pub action: Option<MessageAction>,
+    /// The customer's address, as sender or recipient.
+    pub who: String,
+    /// A contact id: every identity of theirs, as sender or recipient.
+    pub contact: String,
+    pub rule: String,
+    /// First and last day, `YYYY-MM-DD` (UTC), both included.
+    pub from: String,
+    pub to: String,
+}
+
+impl Filter {
+    pub fn from_url(url: &Url) -> Self {
+        let param = |name: &str| {
+            url.query_pairs()
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| v.trim().chars().take(MAX_TEXT).collect::<String>())
+                .unwrap_or_default()
+        };
+        let day = |name: &str| Some(param(name)).filter(|d| is_day(d)).unwrap_or_default();
+        Filter {
+            channel: Channel::from_wire(&param("channel")),
+            account: param("account"),
+            direction: MessageDirection::from_wire(&param("direction")),
+            action: MessageAction::from_wire(&param("action")),
+            who: param("who"),
+            contact: param("contact"),
+            rule: param("rule"),
+            from: day("from"),
+            to: day("to"),
+        }
+    }
+
+    /// The filter as query parameters, for page and export links.
+    pub fn query_string(&self) -> String {
+        let pairs = [
+            (
+                "channel",
+                self.channel.as_ref().map(|c| c.as_str()).unwrap_or(""),
+            ),
+            ("account", self.account.as_str()),
+            (
+                "direction",
+                self.direction.map(|d| d.as_str()).unwrap_or(""),
+            ),
+            ("action", self.action.map(|a| a.as_str()).unwrap_or("")),
+            ("who", self.who.as_str()),
+            ("contact", self.contact.as_str()),
+            ("rule", self.rule.as_str()),
+            ("from", self.from.as_str()),
+            ("to", self.to.as_str()),
+        ];
+        pairs
+            .iter()
+            .filter(|(_, v)| !v.is_empty())
+            .map(|(k, v)| format!("{k}={}", urlencoding::encode(v)))
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Filter::default()
+    }
+
+    /// `WHERE` conditions after the tenant (`?1`), with their parameters
+    /// numbered from `?2`.
+    fn conditions(&self, after: Option<&Cursor>) -> (Vec<String>, Vec<String>) {
+        let mut sql = vec!["tenant_id = ?1".to_string()];
+        let mut params: Vec<String> = Vec::new();
+        let bind = |params: &mut Vec<String>, value: &str| {
+            params.push(value.to_string());
+            format!("?{}", params.len() + 1)
+        };
+
+        let exact = [
+            (
+                "channel",
+                self.channel.as_ref().map(|c| c.as_str()).unwrap_or(""),
+            ),
+            ("channel_account_id", self.account.as_str()),
+            (
+                "direction",
+                self.direction.map(|d| d.as_str()).unwrap_or(""),
+            ),
+            (
+                "action_taken",
+                self.action.map(|a| a.as_str()).unwrap_or(""),
+            ),
+            ("rule_label", self.rule.as_str()),
+        ];
+        for (column, value) in exact {
+            if !value.is_empty() {
+                sql.push(format!("{column} = {}", bind(&mut params, value)));
+            }
+        }
+        if !self.who.is_empty() {
+            let p = bind(&mut params, &self.who);
+            sql.push(format!("(sender = {p} OR recipient = {p})"));
+        }
+        if !self.contact.is_empty() {
+            let p = bind(&mut params, &self.contact);
+            let identities = format!(
+                "(SELECT identity FROM contact_identities WHERE tenant_id = ?1 AND contact_id = {p})"
+            );
+            sql.push(format!(
+                "(sender IN {identities} OR recipient IN {identities})"
+            ));
+        }
+        if !self.from.is_empty() {
+            sql.push(format!("created_at >= {}", bind(&mut params, &self.from)));
+        }
+        if !self.to.is_empty() {
+            sql.push(format!(
+                "created_at < date({}, '+1 day')",
+                bind(&mut params, &self.to)
+            ));
+        }
+        if let Some(cursor) = after {
+            let at = bind(&mut params, &cursor.created_at);
+            let id = bind(&mut params, &cursor.id);
+            sql.push(format!(
+                "(created_at < {at} OR (created_at = {at} AND id < {id}))"
+            ));
+        }
+        (sql, params)
+    }
+}
+
+/// `YYYY-MM-DD`.
+fn is_day(s: &str) -> bool {
+    s.len() == 10
+        && s.char_indices().all(|(i, c)| match i {
+            4 | 7 => c == '-',
+            _ => c.is_ascii_digit(),
+        })
+}
+
+/// Position after the last row of a page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cursor {
+    pub created_at: String,
+    pub id: String,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        format!("{}|{}", self.created_at, self.id)
+    }
+
+    pub fn decode(s: &str) -> Option<Self> {
+        let (created_at, id) = s.split_once('|')?;
+        (!created_at.is_empty() && !id.is_empty()).then(|| Cursor {
+            created_at: created_at.to_string(),
+            id: id.to_string(),
+        })
+    }
+}
+
+/// One `messages` row as the log shows it.
+#[derive(Debug, Clone, Serialize)]
+pub struct LogRow {
+    pub id: String,
+    pub created_at: String,
+    pub channel: String,
+    pub channel_account_id: String,
+    pub direction: String,
+    pub sender: String,
+    pub recipient: String,
+    pub action_taken: Option<String>,
+    pub rule_label: Option<String>,
+    pub contact_id: Option<String>,
+    pub approval_id: Option<String>,
+    pub credits: i64,
+    pub reply_secs: Option<i64>,
+}
+
+impl LogRow {
+    fn from_row(row: &serde_json::Value) -> Self {
+        let s = |k: &str| {
+            row.get(k)
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string()
+        };
+        let opt = |k: &str| {
+            row.get(k)
+                .and_then(|v| v.as_str())
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+        let int = |k: &str| {
+            row.get(k)
+                .and_then(|v| v.as_i64().or_else(|| v.as_f64().map(|f| f as i64)))
+        };
+        LogRow {
+            id: s("id"),
+            created_at: s("created_at"),
+            channel: s("channel"),
+            channel_account_id: s("channel_account_id"),
+            direction: s("direction"),
+            sender: s("sender"),
+            recipient: s("recipient"),
+            action_taken: opt("action_taken"),
+            rule_label: opt("rule_label"),
+            contact_id: opt("contact_id"),
+            approval_id: opt("approval_id"),
+            credits: int("credits").unwrap_or(0),
+            reply_secs: int("reply_secs"),
+        }
+    }
+
+    pub fn cursor(&self) -> Cursor {
+        Cursor {
+            created_at: self.created_at.clone(),
+            id: self.id.clone(),
+        }
+    }
+}
+
+/// A page of the log and where the next one starts.
+#[derive(Debug)]
+pub struct Page {
+    pub rows: Vec<LogRow>,
+    pub next: Option<Cursor>,
+}
+
+/// Up to `limit` rows matching `filter`, after `after`.
+pub async fn search(
+    db: &D1Database,
+    tenant_id: &str,
+    filter: &Filter,
+    after: Option<&Cursor>,
+    limit: u32,
+) -> Result<Page> {
+    let (conditions, params) = filter.conditions(after);
+    let mut bound: Vec<JsValue> = vec![tenant_id.into()];
+    bound.extend(params.iter().map(|p| JsValue::from(p.as_str())));
+    bound.push(JsValue::from((limit + 1) as f64));
+    let rows = db
+        .prepare(format!(
+            "SELECT id, created_at, channel, channel_account_id, direction, sender, recipient,
+                    action_taken, rule_label, contact_id, approval_id, credits, reply_secs
+             FROM messages WHERE {}
+             ORDER BY created_at DESC, id DESC LIMIT ?{}",
+            conditions.join(" AND "),
+            bound.len(),
+        ))
+        .bind(&bound)?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    let mut rows: Vec<LogRow> = rows.iter().map(LogRow::from_row).collect();
+    let next = if rows.len() > limit as usize {
+        rows.truncate(limit as usize);
+        rows.last().map(LogRow::cursor)
+    } else {
+        None
+    };
+    Ok(Page { rows, next })
+}
+
+/// Every row matching `filter`, newest first, up to `CSV_MAX_ROWS`. The
+/// flag is set when more rows matched than were returned.
+pub async fn export(
+    db: &D1Database,
+    tenant_id: &str,
+    filter: &Filter,
+) -> Result<(Vec<LogRow>, bool)> {
+    let mut rows = Vec::new();
+    let mut after: Option<Cursor> = None;
+    loop {
+        let page = search(db, tenant_id, filter, after.as_ref(), EXPORT_BATCH).await?;
+        rows.extend(page.rows);
+        match page.next {
+            Some(next) if rows.len() < CSV_MAX_ROWS => after = Some(next),
+            Some(_) => return Ok((rows, true)),
+            None => return Ok((rows, false)),
+        }
+    }
+}
+
+pub fn csv(rows: &[LogRow]) -> String {
+    const COLUMNS: [&str; 13] = [
+        "created_at",
+        "id",
+        "channel",
+        "channel_account_id",
+        "direction",
+        "sender",
+        "recipient",
+        "action_taken",
+        "rule_label",
+        "contact_id",
+        "approval_id",
+        "credits",
+        "reply_secs",
+    ];
+    let values: Vec<serde_json::Value> = rows
+        .iter()
+        .filter_map(|r| serde_json::to_value(r).ok())
+        .collect();
+    crate::archive::csv(&COLUMNS, &values)
+}
+
+/// Values the account and rule filters offer: every account and rule
+/// label the tenant's log mentions.
+pub async fn facets(
+    db: &D1Database,
+    tenant_id: &str,
+) -> Result<(Vec<(String, String)>, Vec<String>)> {
+    let accounts = db
+        .prepare(
+            "SELECT DISTINCT channel, channel_account_id FROM messages
+             WHERE tenant_id = ?1 AND channel_account_id != ''
+             ORDER BY channel, channel_account_id",
+        )
+        .bind(&[tenant_id.into()])?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    let rules = db
+        .prepare(
+            "SELECT DISTINCT rule_label FROM messages
+             WHERE tenant_id = ?1 AND rule_label IS NOT NULL
+             ORDER BY rule_label",
+        )
+        .bind(&[tenant_id.into()])?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    let s = |row: &serde_json::Value, k: &str| {
+        row.get(k)
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string()
+    };
+    Ok((
+        accounts
+            .iter()
+            .map(|r| (s(r, "channel"), s(r, "channel_account_id")))
+            .collect(),
+        rules.iter().map(|r| s(r, "rule_label")).collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(query: &str) -> Url {
+        Url::parse(&format!("https://example.com/admin/messages?{query}")).unwrap()
+    }
+
+    #[test]
+    fn filter_reads_known_values_and_drops_the_rest() {
+        let f = Filter::from_url(&url(
+            "channel=whatsapp&action=ai_queued&direction=sideways&from=2026-03-01&to=last+week&who=+91%2098",
+        ));
+        assert_eq!(f.channel, Some(Channel::WhatsApp));
+        assert_eq!(f.action, Some(MessageAction::AiQueued));
+        assert_eq!(f.direction, None);
+        assert_eq!(f.from, "2026-03-01");
+        assert_eq!(f.to, "");
+        assert_eq!(f.who, "91 98");
+        assert!(Filter::from_url(&url("")).is_empty());
+    }
+
+    #[test]
+    fn query_string_round_trips() {
+        let f = Filter {
+            channel: Some(Channel::Email),
+            rule: "Hours & prices".into(),
+            from: "2026-03-01".into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            f.query_string(),
+            "channel=email&rule=Hours%20%26%20prices&from=2026-03-01"
+        );
+        assert_eq!(Filter::from_url(&url(&f.query_string())), f);
+    }
+
+    #[test]
+    fn conditions_number_params_after_the_tenant() {
+        let f = Filter {
+            action: Some(MessageAction::Relay),
+            who: "a@example.com".into(),
+            to: "2026-03-31".into(),
+            ..Default::default()
+        };
+        let cursor = Cursor {
+            created_at: "2026-03-02 10:00:00".into(),
+            id: "m1".into(),
+        };
+        let (sql, params) = f.conditions(Some(&cursor));
+        assert_eq!(
+            sql,
+            [
+                "tenant_id = ?1",
+                "action_taken = ?2",
+                "(sender = ?3 OR recipient = ?3)",
+                "created_at < date(?4, '+1 day')",
+                "(created_at < ?5 OR (created_at = ?5 AND id < ?6))",
+            ]
+        );
+        assert_eq!(
+            params,
+            [
+                "relay",
+                "a@example.com",
+                "2026-03-31",
+                "2026-03-02 10:00:00",
+                "m1"
+            ]
+        );
+    }
+
+    #[test]
+    fn cursor_round_trips_and_rejects_junk() {
+        let c = Cursor {
+            created_at: "2026-03-02 10:00:00".into(),
+            id: "abc".into(),
+        };
+        assert_eq!(Cursor::decode(&c.encode()), Some(c));
+        assert_eq!(Cursor::decode("no-separator"), None);
+        assert_eq!(Cursor::decode("|abc"), None);
+    }
+}
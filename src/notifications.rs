@@ -0,0 +1,328 @@
+//! In-app notifications: events a tenant should know about (persona
+//! rejected, Instagram disconnected, credits gone, a Discord post failed,
+//! a recipient verified) stored per tenant with severity, read state and a
+//! link to the page that deals with them.
+//!
+//! `notify` is best-effort and never fails its caller. It stores the
+//! notification, pings the tenant's open tabs over the `ApprovalsDO` SSE
+//! stream so the bell updates, and emails it or posts it to Discord when
+//! the tenant picked that for its kind (`NotificationConfig`). While one is
+//! unread, repeats with the same kind and key are dropped, so a tenant out
+//! of credits hears about it once rather than per message.
+
+use worker::*;
+
+use crate::approvals;
+use crate::discord;
+use crate::email::send::{send_outbound, OutboundEmail};
+use crate::helpers::{generate_id, html_escape};
+use crate::storage::{get_discord_config_by_tenant, get_onboarding, get_tenant};
+use crate::types::{NotificationKind, NotificationSeverity};
+
+/// Notifications on the notification page.
+pub const PAGE_LIMIT: u32 = 100;
+
+/// Something to tell a tenant.
+#[derive(Debug, Clone)]
+pub struct Notice {
+    pub kind: NotificationKind,
+    pub title: String,
+    pub body: String,
+    /// Admin path it opens, e.g. `/admin/persona`.
+    pub link: String,
+    /// Tells repeats apart: an unread notification with the same kind and
+    /// key swallows new ones. Empty for one-at-a-time kinds.
+    pub key: String,
+}
+
+/// A stored notification.
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub id: String,
+    pub kind: Option<NotificationKind>,
+    pub severity: NotificationSeverity,
+    pub title: String,
+    pub body: String,
+    pub link: String,
+    pub read_at: Option<String>,
+    pub created_at: String,
+}
+
+impl Notification {
+    fn from_row(row: &serde_json::Value) -> Self {
+        let s = |k: &str| {
+            row.get(k)
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string()
+        };
+        Notification {
+            id: s("id"),
+            kind: NotificationKind::from_wire(&s("kind")),
+            severity: NotificationSeverity::from_wire(&s("severity"))
+                .unwrap_or(NotificationSeverity::Info),
+            title: s("title"),
+            body: s("body"),
+            link: s("link"),
+            read_at: row
+                .get("read_at")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+            created_at: s("created_at"),
+        }
+    }
+}
+
+/// Record `notice` for the tenant and deliver it. Errors are logged.
+pub async fn notify(env: &Env, tenant_id: &str, notice: Notice) {
+    let inserted = match env.d1("DB") {
+        Ok(db) => insert(&db, tenant_id, &notice).await,
+        Err(e) => Err(e),
+    };
+    match inserted {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(e) => {
+            console_log!(
+                "Notification {} for tenant {tenant_id} not stored: {e:?}",
+                notice.kind.as_str()
+            );
+            return;
+        }
+    }
+    if let Err(e) = approvals::broadcast(env, tenant_id, "notification-changed").await {
+        console_log!("Notification broadcast failed for tenant {tenant_id}: {e:?}");
+    }
+    if let Err(e) = deliver(env, tenant_id, &notice).await {
+        console_log!(
+            "Notification {} delivery failed for tenant {tenant_id}: {e:?}",
+            notice.kind.as_str()
+        );
+    }
+}
+
+/// Store `notice` unless an unread one with the same kind and key exists.
+/// Returns whether it was stored.
+async fn insert(db: &D1Database, tenant_id: &str, notice: &Notice) -> Result<bool> {
+    let result = db
+        .prepare(
+            "INSERT INTO notifications (id, tenant_id, kind, severity, title, body, link, dedup_key)
+             SELECT ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8
+             WHERE NOT EXISTS (SELECT 1 FROM notifications
+                               WHERE tenant_id = ?2 AND kind = ?3 AND dedup_key = ?8
+                                 AND read_at IS NULL)",
+        )
+        .bind(&[
+            generate_id().into(),
+            tenant_id.into(),
+            notice.kind.as_str().into(),
+            notice.kind.severity().as_str().into(),
+            notice.title.as_str().into(),
+            notice.body.as_str().into(),
+            notice.link.as_str().into(),
+            notice.key.as_str().into(),
+        ])?
+        .run()
+        .await?;
+    Ok(result
+        .meta()?
+        .and_then(|m| m.changes)
+        .is_some_and(|n| n > 0))
+}
+
+/// Email and Discord copies, for the kinds the tenant asked for.
+async fn deliver(env: &Env, tenant_id: &str, notice: &Notice) -> Result<()> {
+    let kv = env.kv("KV")?;
+    let config = get_onboarding(&kv, tenant_id).await?.notifications;
+    let by_email = config.email_kinds.contains(&notice.kind);
+    let by_discord =
+        config.discord_kinds.contains(&notice.kind) && notice.kind.discord_deliverable();
+    if !by_email && !by_discord {
+        return Ok(());
+    }
+    let base_url = env
+        .var("PUBLIC_BASE_URL")
+        .map(|v| v.to_string())
+        .unwrap_or_default();
+
+    if by_discord {
+        let channel = get_discord_config_by_tenant(&kv, tenant_id)
+            .await?
+            .and_then(|c| c.approval_channel_id);
+        if let Some(channel) = channel {
+            discord::post_notification(
+                env,
+                &channel,
+                notice.kind.severity(),
+                &notice.title,
+                &notice.body,
+                &format!("{base_url}{}", notice.link),
+            )
+            .await?;
+        }
+    }
+
+    if by_email {
+        let domain = env
+            .var("EMAIL_DOMAIN")
+            .map(|v| v.to_string())
+            .unwrap_or_default();
+        if base_url.is_empty() || domain.is_empty() {
+            console_log!(
+                "Notification email skipped: PUBLIC_BASE_URL and EMAIL_DOMAIN must both be set"
+            );
+            return Ok(());
+        }
+        let db = env.d1("DB")?;
+        if let Some(tenant) = get_tenant(&db, tenant_id).await? {
+            let email = build_email(
+                notice,
+                &tenant.email,
+                &base_url,
+                &format!("noreply@{domain}"),
+            );
+            send_outbound(env, &email).await?;
+        }
+    }
+    Ok(())
+}
+
+fn build_email(notice: &Notice, recipient: &str, base_url: &str, from_addr: &str) -> OutboundEmail {
+    let url = format!("{base_url}{}", notice.link);
+    let settings = format!("{base_url}/admin/notifications");
+    let text = format!(
+        "{title}\n\n{body}\n\nOpen: {url}\n\nYou're getting this because {kind} notifications are emailed to you. Change that at {settings}\n",
+        title = notice.title,
+        body = notice.body,
+        kind = notice.kind.label(),
+    );
+    let html = format!(
+        r#"<!doctype html>
+<html><body style="font-family:-apple-system,BlinkMacSystemFont,sans-serif;max-width:640px;margin:0 auto;padding:24px">
+<h1 style="font-size:20px;margin:0 0 12px">{title}</h1>
+<p style="white-space:pre-wrap">{body}</p>
+<p style="margin-top:24px"><a href="{url}" style="display:inline-block;padding:8px 14px;background:#5865F2;color:#fff;border-radius:4px;text-decoration:none">Open Concierge</a></p>
+<p style="color:#999;font-size:12px;margin-top:32px">You're getting this because {kind} notifications are emailed to you. <a href="{settings}">Change delivery</a>.</p>
+</body></html>"#,
+        title = html_escape(&notice.title),
+        body = html_escape(&notice.body),
+        url = html_escape(&url),
+        kind = html_escape(notice.kind.label()),
+        settings = html_escape(&settings),
+    );
+    OutboundEmail {
+        from: from_addr.to_string(),
+        to: recipient.to_string(),
+        subject: format!("[Concierge] {}", notice.title),
+        text: Some(text),
+        html: Some(html),
+        reply_to: None,
+        cc: vec![],
+        bcc: vec![],
+        headers: vec![],
+    }
+}
+
+/// The tenant's notifications, newest first.
+pub async fn list(db: &D1Database, tenant_id: &str, limit: u32) -> Result<Vec<Notification>> {
+    let rows = db
+        .prepare(
+            "SELECT * FROM notifications WHERE tenant_id = ?1
+             ORDER BY created_at DESC, id DESC LIMIT ?2",
+        )
+        .bind(&[tenant_id.into(), (limit as f64).into()])?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    Ok(rows.iter().map(Notification::from_row).collect())
+}
+
+pub async fn get(db: &D1Database, tenant_id: &str, id: &str) -> Result<Option<Notification>> {
+    let row = db
+        .prepare("SELECT * FROM notifications WHERE tenant_id = ?1 AND id = ?2")
+        .bind(&[tenant_id.into(), id.into()])?
+        .first::<serde_json::Value>(None)
+        .await?;
+    Ok(row.as_ref().map(Notification::from_row))
+}
+
+/// Unread notifications and the most severe among them.
+pub async fn unread(
+    db: &D1Database,
+    tenant_id: &str,
+) -> Result<(u32, Option<NotificationSeverity>)> {
+    let rows = db
+        .prepare(
+            "SELECT severity, COUNT(*) AS n FROM notifications
+             WHERE tenant_id = ?1 AND read_at IS NULL GROUP BY severity",
+        )
+        .bind(&[tenant_id.into()])?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    let mut count = 0;
+    let mut worst = None;
+    for row in &rows {
+        let n = row.get("n").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+        let severity = row
+            .get("severity")
+            .and_then(|v| v.as_str())
+            .and_then(NotificationSeverity::from_wire);
+        count += n;
+        worst = worst.max(severity);
+    }
+    Ok((count, worst))
+}
+
+/// Mark one notification read, or all of them when `id` is `None`.
+pub async fn mark_read(db: &D1Database, tenant_id: &str, id: Option<&str>) -> Result<()> {
+    match id {
+        Some(id) => {
+            db.prepare(
+                "UPDATE notifications SET read_at = datetime('now')
+                 WHERE tenant_id = ?1 AND id = ?2 AND read_at IS NULL",
+            )
+            .bind(&[tenant_id.into(), id.into()])?
+            .run()
+            .await?
+        }
+        None => {
+            db.prepare(
+                "UPDATE notifications SET read_at = datetime('now')
+                 WHERE tenant_id = ?1 AND read_at IS NULL",
+            )
+            .bind(&[tenant_id.into()])?
+            .run()
+            .await?
+        }
+    };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn email_links_the_page_and_delivery_settings() {
+        let notice = Notice {
+            kind: NotificationKind::CreditsExhausted,
+            title: "You're out of credits".into(),
+            body: "AI replies are paused <until you top up>.".into(),
+            link: "/admin/billing".into(),
+            key: String::new(),
+        };
+        let email = build_email(
+            &notice,
+            "owner@example.com",
+            "https://example.com",
+            "noreply@example.com",
+        );
+        assert_eq!(email.subject, "[Concierge] You're out of credits");
+        let html = email.html.unwrap();
+        assert!(html.contains(r#"href="https://example.com/admin/billing""#));
+        assert!(html.contains("https://example.com/admin/notifications"));
+        assert!(html.contains("&lt;until you top up&gt;"));
+        assert!(email.text.unwrap().contains("Out of credits notifications"));
+    }
+}
@@ -10,7 +10,8 @@
 //! match arm in every method here. The compiler enforces completeness.
 
 use crate::types::{
-    ApprovalPolicy, PersonaBuilder, PersonaPreset, ReplyMatcher, ReplyResponse, ReplyRule,
+    ApprovalPolicy, GenerationParams, PersonaBuilder, PersonaPreset, ReplyMatcher, ReplyResponse,
+    ReplyRule,
 };
 
 impl PersonaPreset {
@@ -91,6 +92,7 @@ impl PersonaPreset {
                             .to_string(),
                     },
                     approval: ApprovalPolicy::Auto,
+                    generation: GenerationParams::default(),
                 },
                 ReplyRule {
                     id: "pricing".to_string(),
@@ -107,6 +109,7 @@ impl PersonaPreset {
                             .to_string(),
                     },
                     approval: ApprovalPolicy::Auto,
+                    generation: GenerationParams::default(),
                 },
             ],
             PersonaPreset::ProfessionalSalon => vec![
@@ -127,6 +130,7 @@ impl PersonaPreset {
                             .to_string(),
                     },
                     approval: ApprovalPolicy::Auto,
+                    generation: GenerationParams::default(),
                 },
                 ReplyRule {
                     id: "cancellation".to_string(),
@@ -141,6 +145,7 @@ impl PersonaPreset {
                             .to_string(),
                     },
                     approval: ApprovalPolicy::Auto,
+                    generation: GenerationParams::default(),
                 },
             ],
             PersonaPreset::PlayfulCafe => vec![
@@ -159,6 +164,7 @@ impl PersonaPreset {
                         text: "We're open 7am-7pm every day. Come say hi! ☕".to_string(),
                     },
                     approval: ApprovalPolicy::Auto,
+                    generation: GenerationParams::default(),
                 },
                 ReplyRule {
                     id: "menu".to_string(),
@@ -176,6 +182,7 @@ impl PersonaPreset {
                             .to_string(),
                     },
                     approval: ApprovalPolicy::Auto,
+                    generation: GenerationParams::default(),
                 },
             ],
             PersonaPreset::OldSchoolClinic => vec![
@@ -197,6 +204,7 @@ impl PersonaPreset {
                             .to_string(),
                     },
                     approval: ApprovalPolicy::Auto,
+                    generation: GenerationParams::default(),
                 },
                 ReplyRule {
                     id: "appointment".to_string(),
@@ -214,6 +222,7 @@ impl PersonaPreset {
                             .to_string(),
                     },
                     approval: ApprovalPolicy::Auto,
+                    generation: GenerationParams::default(),
                 },
             ],
         }
@@ -0,0 +1,355 @@
+//! PII redaction for text headed to a model.
+//!
+//! Inbound bodies are masked before the injection scan, the embedding and
+//! the reply draft: each value becomes a numbered placeholder
+//! (`[CARD_1]`, `[PHONE_2]`) so the model can still refer to it. After
+//! drafting, `Redacted::restore` puts the customer's own contact details
+//! back and replaces identity / payment numbers with a masked form, so a
+//! card or Aadhaar number never round-trips through a model.
+//!
+//! Detection is pattern + checksum based (Luhn for cards, Verhoeff for
+//! Aadhaar), tuned to miss rather than mangle ordinary text like prices
+//! and order numbers.
+
+use std::collections::BTreeMap;
+
+use crate::detect::{self, DigitRun};
+use crate::types::PiiClass;
+
+/// PAN's fourth letter encodes the holder type (person, company, HUF...).
+const PAN_HOLDER_TYPES: &str = "PCHFATBLJG";
+/// Characters left visible when a non-restorable value is masked.
+const MASK_KEEP: usize = 4;
+
+#[derive(Debug, Clone)]
+struct Entry {
+    placeholder: String,
+    original: String,
+    class: PiiClass,
+}
+
+/// Redacted text plus what it takes to undo it.
+#[derive(Debug, Clone, Default)]
+pub struct Redacted {
+    pub text: String,
+    entries: Vec<Entry>,
+    counts: BTreeMap<PiiClass, u32>,
+}
+
+impl Redacted {
+    /// Occurrences masked per class (a value repeated twice counts twice).
+    pub fn counts(&self) -> &BTreeMap<PiiClass, u32> {
+        &self.counts
+    }
+
+    /// Put placeholders in a model reply back: restorable classes get the
+    /// original value, the rest a masked form. Placeholders the model
+    /// invented are left alone.
+    pub fn restore(&self, reply: &str) -> String {
+        let mut out = reply.to_string();
+        for entry in &self.entries {
+            if !out.contains(&entry.placeholder) {
+                continue;
+            }
+            let value = if entry.class.restorable() {
+                entry.original.clone()
+            } else {
+                mask(&entry.original)
+            };
+            out = out.replace(&entry.placeholder, &value);
+        }
+        out
+    }
+
+    /// `restore` over every string in a JSON value, for tool-call
+    /// arguments: the tenant's endpoint needs the customer's real number or
+    /// email to look anything up.
+    pub fn restore_json(&self, value: &serde_json::Value) -> serde_json::Value {
+        use serde_json::Value;
+        match value {
+            Value::String(s) => Value::String(self.restore(s)),
+            Value::Array(items) => {
+                Value::Array(items.iter().map(|v| self.restore_json(v)).collect())
+            }
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(k, v)| (k.clone(), self.restore_json(v)))
+                    .collect(),
+            ),
+            other => other.clone(),
+        }
+    }
+}
+
+/// Mask the enabled `classes` in `text`.
+pub fn redact(text: &str, classes: &[PiiClass]) -> Redacted {
+    let mut spans = token_spans(text, classes);
+    for span in digit_spans(text, classes) {
+        if !spans.iter().any(|s| s.0 < span.1 && span.0 < s.1) {
+            spans.push(span);
+        }
+    }
+    spans.sort_by_key(|s| s.0);
+
+    let mut out = Redacted::default();
+    let mut per_class: BTreeMap<PiiClass, u32> = BTreeMap::new();
+    let mut last = 0;
+    for (start, end, class) in spans {
+        let original = &text[start..end];
+        let placeholder = match out
+            .entries
+            .iter()
+            .find(|e| e.class == class && e.original == original)
+        {
+            Some(e) => e.placeholder.clone(),
+            None => {
+                let n = per_class.entry(class).or_insert(0);
+                *n += 1;
+                let placeholder = format!("[{}_{}]", class.as_str().to_uppercase(), n);
+                out.entries.push(Entry {
+                    placeholder: placeholder.clone(),
+                    original: original.to_string(),
+                    class,
+                });
+                placeholder
+            }
+        };
+        *out.counts.entry(class).or_insert(0) += 1;
+        out.text.push_str(&text[last..start]);
+        out.text.push_str(&placeholder);
+        last = end;
+    }
+    out.text.push_str(&text[last..]);
+    out
+}
+
+/// Keep the last few alphanumerics, X out the rest, leave separators.
+fn mask(value: &str) -> String {
+    let total = value.chars().filter(|c| c.is_ascii_alphanumeric()).count();
+    let mut seen = 0;
+    value
+        .chars()
+        .map(|c| {
+            if !c.is_ascii_alphanumeric() {
+                return c;
+            }
+            seen += 1;
+            if seen + MASK_KEEP > total {
+                c
+            } else {
+                'X'
+            }
+        })
+        .collect()
+}
+
+// ============================================================================
+// Detectors
+// ============================================================================
+
+/// Word-shaped identifiers: emails, UPI IDs, PAN, passport, voter ID.
+fn token_spans(text: &str, classes: &[PiiClass]) -> Vec<(usize, usize, PiiClass)> {
+    detect::words(text)
+        .into_iter()
+        .filter_map(|(start, token)| {
+            let class = classify_token(token).filter(|c| classes.contains(c))?;
+            Some((start, start + token.len(), class))
+        })
+        .collect()
+}
+
+fn classify_token(token: &str) -> Option<PiiClass> {
+    if let Some((local, domain)) = token.split_once('@') {
+        if detect::is_email(token) {
+            return Some(PiiClass::Email);
+        }
+        // UPI handles are a bare bank code: name@okicici, 98xxxx@ybl.
+        let upi = !local.is_empty()
+            && !domain.is_empty()
+            && domain.chars().all(|c| c.is_ascii_alphabetic());
+        return upi.then_some(PiiClass::Upi);
+    }
+
+    let chars: Vec<char> = token.chars().collect();
+    let alpha = |r: std::ops::Range<usize>| chars[r].iter().all(char::is_ascii_alphabetic);
+    let digit = |r: std::ops::Range<usize>| chars[r].iter().all(char::is_ascii_digit);
+    match chars.len() {
+        10 if alpha(0..5)
+            && digit(5..9)
+            && alpha(9..10)
+            && PAN_HOLDER_TYPES.contains(chars[3].to_ascii_uppercase()) =>
+        {
+            Some(PiiClass::Pan)
+        }
+        10 if alpha(0..3) && digit(3..10) => Some(PiiClass::VoterId),
+        8 if alpha(0..1) && digit(1..8) && chars[1] != '0' => Some(PiiClass::Passport),
+        _ => None,
+    }
+}
+
+/// Digit runs (see `detect::digit_runs`): cards, Aadhaar, phones, PIN
+/// codes, told apart by length and checksum.
+fn digit_spans(text: &str, classes: &[PiiClass]) -> Vec<(usize, usize, PiiClass)> {
+    detect::digit_runs(text)
+        .into_iter()
+        .filter_map(|run| {
+            let class =
+                classify_digits(&run, &text[run.start..run.end]).filter(|c| classes.contains(c))?;
+            Some((run.start, run.end, class))
+        })
+        .collect()
+}
+
+fn classify_digits(run: &DigitRun, raw: &str) -> Option<PiiClass> {
+    let digits = &run.digits;
+    let n = digits.len();
+    if (13..=19).contains(&n) && luhn(digits) {
+        return Some(PiiClass::Card);
+    }
+    if n == 12 && !run.plus && digits[0] >= 2 && verhoeff(digits) {
+        return Some(PiiClass::Aadhaar);
+    }
+    if run.is_phone() {
+        return Some(PiiClass::Phone);
+    }
+    if n == 6 && raw.len() == 6 && digits[0] != 0 {
+        return Some(PiiClass::Pincode);
+    }
+    None
+}
+
+fn luhn(digits: &[u8]) -> bool {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| {
+            let d = d as u32;
+            if i % 2 == 1 {
+                let dd = d * 2;
+                if dd > 9 {
+                    dd - 9
+                } else {
+                    dd
+                }
+            } else {
+                d
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// Verhoeff check digit validation (the scheme Aadhaar uses).
+fn verhoeff(digits: &[u8]) -> bool {
+    const D: [[u8; 10]; 10] = [
+        [0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+        [1, 2, 3, 4, 0, 6, 7, 8, 9, 5],
+        [2, 3, 4, 0, 1, 7, 8, 9, 5, 6],
+        [3, 4, 0, 1, 2, 8, 9, 5, 6, 7],
+        [4, 0, 1, 2, 3, 9, 5, 6, 7, 8],
+        [5, 9, 8, 7, 6, 0, 4, 3, 2, 1],
+        [6, 5, 9, 8, 7, 1, 0, 4, 3, 2],
+        [7, 6, 5, 9, 8, 2, 1, 0, 4, 3],
+        [8, 7, 6, 5, 9, 3, 2, 1, 0, 4],
+        [9, 8, 7, 6, 5, 4, 3, 2, 1, 0],
+    ];
+    const P: [[u8; 10]; 8] = [
+        [0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+        [1, 5, 7, 6, 2, 8, 3, 0, 9, 4],
+        [5, 8, 0, 3, 7, 9, 6, 1, 4, 2],
+        [8, 9, 1, 6, 0, 4, 3, 5, 2, 7],
+        [9, 4, 5, 3, 1, 2, 6, 8, 7, 0],
+        [4, 2, 8, 6, 5, 7, 3, 9, 0, 1],
+        [2, 7, 9, 3, 8, 0, 6, 4, 1, 5],
+        [7, 0, 4, 6, 9, 1, 3, 2, 5, 8],
+    ];
+    let c = digits.iter().rev().enumerate().fold(0u8, |c, (i, &d)| {
+        D[c as usize][P[i % 8][d as usize] as usize]
+    });
+    c == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all() -> Vec<PiiClass> {
+        PiiClass::ALL.to_vec()
+    }
+
+    #[test]
+    fn masks_generic_contacts() {
+        let r = redact(
+            "Mail me at priya@example.com or call +91 98765 43210.",
+            &all(),
+        );
+        assert_eq!(r.text, "Mail me at [EMAIL_1] or call [PHONE_1].");
+        assert_eq!(r.counts()[&PiiClass::Email], 1);
+        assert_eq!(r.counts()[&PiiClass::Phone], 1);
+    }
+
+    #[test]
+    fn masks_indian_identifiers() {
+        let r = redact(
+            "Aadhaar 2345 6789 0124, PAN abcpe1234f, pay to priya@okicici, voter ID ABC1234567, passport K1234567",
+            &all(),
+        );
+        assert_eq!(
+            r.text,
+            "Aadhaar [AADHAAR_1], PAN [PAN_1], pay to [UPI_1], voter ID [VOTER_ID_1], passport [PASSPORT_1]"
+        );
+    }
+
+    #[test]
+    fn cards_need_a_valid_checksum() {
+        let r = redact("Card 4111 1111 1111 1111 expired", &all());
+        assert_eq!(r.text, "Card [CARD_1] expired");
+        // Fails Luhn and is too long for a phone: left as is.
+        let r = redact("Ref 4111 1111 1111 1112", &all());
+        assert_eq!(r.text, "Ref 4111 1111 1111 1112");
+        // A 12-digit run that isn't Verhoeff-valid isn't Aadhaar.
+        let r = redact("Ticket 234567890123", &all());
+        assert_eq!(r.text, "Ticket [PHONE_1]");
+    }
+
+    #[test]
+    fn leaves_ordinary_numbers_alone() {
+        let text = "Order 12345678 costs Rs 1,499.00 for 2 items, ships 12-05.";
+        assert_eq!(redact(text, &all()).text, text);
+        // Six digits only count as a PIN code when that class is on.
+        let r = redact("Deliver to Bengaluru 560001", &[PiiClass::Phone]);
+        assert_eq!(r.text, "Deliver to Bengaluru 560001");
+        let r = redact("Deliver to Bengaluru 560001", &all());
+        assert_eq!(r.text, "Deliver to Bengaluru [PINCODE_1]");
+    }
+
+    #[test]
+    fn repeated_values_share_a_placeholder() {
+        let r = redact("a@b.example, again a@b.example; c@d.example", &all());
+        assert_eq!(r.text, "[EMAIL_1], again [EMAIL_1]; [EMAIL_2]");
+        assert_eq!(r.counts()[&PiiClass::Email], 3);
+    }
+
+    #[test]
+    fn restore_echoes_contacts_and_masks_identity_numbers() {
+        let r = redact(
+            "I'm on 9876543210, card 4111-1111-1111-1111, Aadhaar 234567890124",
+            &all(),
+        );
+        let reply = r.restore("We'll call [PHONE_1] about card [CARD_1] and [AADHAAR_1]. [CARD_7]");
+        assert_eq!(
+            reply,
+            "We'll call 9876543210 about card XXXX-XXXX-XXXX-1111 and XXXXXXXX0124. [CARD_7]"
+        );
+    }
+
+    #[test]
+    fn disabled_classes_and_unicode_pass_through() {
+        let r = redact("नमस्ते, मेरा नंबर 9876543210 है", &[]);
+        assert_eq!(r.text, "नमस्ते, मेरा नंबर 9876543210 है");
+        assert!(r.counts().is_empty());
+        let r = redact("नमस्ते, मेरा नंबर 9876543210 है", &all());
+        assert_eq!(r.text, "नमस्ते, मेरा नंबर [PHONE_1] है");
+    }
+}
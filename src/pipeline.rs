@@ -7,11 +7,54 @@ use crate::ai;
 use crate::approval;
 use crate::approvals;
 use crate::billing;
-use crate::channel;
+use crate::brands;
+use crate::channel::{self, Body};
+use crate::consent;
+use crate::contacts;
+use crate::durable_objects::reply_buffer::BufferSettings;
+use crate::guardrails;
 use crate::helpers::generate_id;
+use crate::inbox;
+use crate::notifications::{self, Notice};
+use crate::pii;
 use crate::storage::*;
 use crate::types::*;
 
+/// Settle a per-token tenant's reply against the `reserved` credits taken
+/// before generation: refund the surplus or deduct the shortfall. Returns
+/// what the reply ended up costing. A shortfall the balance can't cover is
+/// forgiven — the model already ran.
+async fn settle_token_credits(
+    db: &D1Database,
+    tenant_id: &str,
+    reserved: i64,
+    pricing: &Pricing,
+    tokens: u32,
+) -> i64 {
+    let actual = pricing.token_credits(tokens);
+    if actual < reserved {
+        if let Err(e) = billing::restore_credit(db, tenant_id, reserved - actual).await {
+            console_log!("Failed to refund token credits: {:?}", e);
+            return reserved;
+        }
+        actual
+    } else if actual > reserved {
+        match billing::try_deduct(db, tenant_id, actual - reserved).await {
+            Ok(true) => actual,
+            Ok(false) => {
+                console_log!("Tenant {tenant_id} short of credits for {tokens} reply tokens");
+                reserved
+            }
+            Err(e) => {
+                console_log!("Failed to deduct token credits: {:?}", e);
+                reserved
+            }
+        }
+    } else {
+        reserved
+    }
+}
+
 /// Process an inbound message from WhatsApp, Instagram, or Discord.
 ///
 /// Routes through the ReplyBufferDO so quick-fire messages from the same
@@ -21,17 +64,47 @@ pub async fn process_inbound(msg: &InboundMessage, env: &Env) -> Result<()> {
     let kv = env.kv("KV")?;
     let db = env.d1("DB")?;
 
-    // 1. Log inbound to unified messages table
+    // 1. Resolve the sender to a contact, then log inbound to the unified
+    //    messages table. A contact-book failure never blocks the reply.
+    let mut msg = msg.clone();
+    match contacts::resolve(&db, &msg).await {
+        Ok(contact_id) => msg.contact_id = Some(contact_id),
+        Err(e) => console_log!("Failed to resolve contact: {:?}", e),
+    }
+    let msg = &msg;
     if let Err(e) = save_inbound_message(&db, msg, None).await {
         console_log!("Failed to log inbound message: {:?}", e);
     }
+    inbox::record_inbound(env, msg).await;
 
-    let wait = lookup_wait_seconds(&kv, msg).await.unwrap_or(0);
-    if wait == 0 {
-        process_inbound_immediate(msg, env).await?;
-    } else if let Err(e) = forward_to_buffer(env, msg, wait).await {
-        console_log!("buffer route failed, falling back to immediate: {:?}", e);
-        process_inbound_immediate(msg, env).await?;
+    // 2. Opt-out / opt-in keywords are answered straight away, never
+    //    buffered with other messages or sent to the AI.
+    match consent::handle_keywords(env, &kv, &db, msg).await {
+        Ok(true) => return Ok(()),
+        Ok(false) => {}
+        Err(e) => console_log!("Consent keyword handling failed: {:?}", e),
+    }
+
+    // 3. Suppressed senders get no auto-reply (send_reply would refuse it
+    //    anyway); skipping here saves the AI call.
+    match consent::is_suppressed(&db, &msg.tenant_id, &msg.channel, &msg.sender).await {
+        Ok(true) => {
+            let _ = set_message_action(&db, &msg.id, MessageAction::Suppressed).await;
+            return Ok(());
+        }
+        Ok(false) => {}
+        Err(e) => console_log!("Suppression check failed: {:?}", e),
+    }
+
+    let settings = lookup_buffer_settings(&kv, msg).await.ok().flatten();
+    match settings {
+        Some(settings) if settings.wait_seconds > 0 => {
+            if let Err(e) = forward_to_buffer(env, msg, &settings).await {
+                console_log!("buffer route failed, falling back to immediate: {:?}", e);
+                process_inbound_immediate(msg, env).await?;
+            }
+        }
+        _ => process_inbound_immediate(msg, env).await?,
     }
 
     Ok(())
@@ -46,7 +119,10 @@ pub async fn process_inbound_immediate(msg: &InboundMessage, env: &Env) -> Resul
     handle_auto_reply(msg, &kv, &db, env).await
 }
 
-async fn lookup_wait_seconds(kv: &kv::KvStore, msg: &InboundMessage) -> Result<u32> {
+async fn lookup_buffer_settings(
+    kv: &kv::KvStore,
+    msg: &InboundMessage,
+) -> Result<Option<BufferSettings>> {
     let cfg = match msg.channel {
         Channel::WhatsApp => get_whatsapp_account(kv, &msg.channel_account_id)
             .await?
@@ -61,10 +137,14 @@ async fn lookup_wait_seconds(kv: &kv::KvStore, msg: &InboundMessage) -> Result<u
             .await?
             .map(|a| a.auto_reply),
     };
-    Ok(cfg.map(|c| c.wait_seconds).unwrap_or(0))
+    Ok(cfg.as_ref().map(BufferSettings::from))
 }
 
-async fn forward_to_buffer(env: &Env, msg: &InboundMessage, wait_seconds: u32) -> Result<()> {
+async fn forward_to_buffer(
+    env: &Env,
+    msg: &InboundMessage,
+    settings: &BufferSettings,
+) -> Result<()> {
     let ns = env.durable_object("REPLY_BUFFER")?;
     // One DO per conversation: tenant + channel + sender.
     let id_name = format!("{}:{}:{}", msg.tenant_id, msg.channel.as_str(), msg.sender);
@@ -72,7 +152,7 @@ async fn forward_to_buffer(env: &Env, msg: &InboundMessage, wait_seconds: u32) -
 
     let payload = serde_json::json!({
         "msg": msg,
-        "wait_seconds": wait_seconds,
+        "settings": settings,
     });
     let body = serde_json::to_string(&payload)?;
 
@@ -92,39 +172,45 @@ async fn forward_to_buffer(env: &Env, msg: &InboundMessage, wait_seconds: u32) -
 /// Pipeline:
 ///   1. Load the channel's `ReplyConfig`.
 ///   2. Skip if disabled.
-///   3. Run prompt-injection scan on the body.
+///   3. Mask PII per the tenant's redaction settings, then run the
+///      prompt-injection scan on the masked body. Hits get the tenant's
+///      holding reply and go to the approval queue for a human answer.
 ///   4. If any rule is `Prompt`-based, embed the body **once** for cosine
 ///      matching across all such rules.
 ///   5. Walk `rules` in order; first match wins. Otherwise the
 ///      mandatory `default_rule` fires.
 ///   6. Build the response: `Canned` → send verbatim (no AI, no credit);
-///      `Prompt` → run the LLM with `persona prompt + rule prompt` (one credit).
-///   7. AI replies are blocked unless the tenant's persona safety status
-///      is `Approved` and unchanged.
+///      `Prompt` → run the LLM with `persona prompt + rule prompt` (the tier's credit cost),
+///      using the account's own persona or else its brand's, plus the
+///      brand's opening hours.
+///   7. AI replies are blocked unless that effective persona's safety
+///      status is `Approved` and unchanged.
+///   8. AI drafts go through the approval gate, then the tenant's
+///      guardrails; either can queue the draft for a human.
 async fn handle_auto_reply(
     msg: &InboundMessage,
     kv: &kv::KvStore,
     db: &D1Database,
     env: &Env,
 ) -> Result<()> {
-    let config = match msg.channel {
+    let account = match msg.channel {
         Channel::WhatsApp => get_whatsapp_account(kv, &msg.channel_account_id)
             .await?
-            .map(|a| a.auto_reply),
+            .map(|a| (a.auto_reply, a.brand_id)),
         Channel::Instagram => get_instagram_account(kv, &msg.channel_account_id)
             .await?
             .filter(|a| a.enabled)
-            .map(|a| a.auto_reply),
+            .map(|a| (a.auto_reply, a.brand_id)),
         Channel::Email => get_email_address(kv, &msg.tenant_id, &msg.channel_account_id)
             .await?
-            .map(|a| a.auto_reply),
+            .map(|a| (a.auto_reply, a.brand_id)),
         Channel::Discord => get_discord_config_by_tenant(kv, &msg.tenant_id)
             .await?
-            .map(|c| c.auto_reply),
+            .map(|c| (c.auto_reply, c.brand_id)),
     };
 
-    let config = match config {
-        Some(c) if c.enabled => c,
+    let (config, brand_id) = match account {
+        Some((c, brand_id)) if c.enabled => (c, brand_id),
         _ => return Ok(()),
     };
 
@@ -132,13 +218,31 @@ async fn handle_auto_reply(
     // injection scanner, the matcher, and the AI context.
     let safe_body: String = msg.body.chars().take(1000).collect();
 
-    if ai::is_prompt_injection(env, &safe_body).await {
-        console_log!(
-            "Prompt injection detected from {} in tenant {}, skipping reply",
-            msg.sender,
-            msg.tenant_id
-        );
-        return Ok(());
+    // Mask PII before anything reaches a model. Keyword rules still match
+    // the raw text; the scanner, the embedding and the draft see the
+    // redacted copy, and the draft's placeholders are restored after.
+    let redaction = get_redaction_config(kv, &msg.tenant_id).await?;
+    let classes: &[PiiClass] = if redaction.enabled {
+        &redaction.classes
+    } else {
+        &[]
+    };
+    let redacted = pii::redact(&safe_body, classes);
+    if !redacted.counts().is_empty() {
+        if let Err(e) = set_message_redactions(db, &msg.id, redacted.counts()).await {
+            console_log!("Failed to record redactions: {:?}", e);
+        }
+    }
+
+    // Allowlisted senders skip the scan; a hit (or a scan that couldn't
+    // run) goes to a human instead of the model.
+    let injection = get_injection_config(kv, &msg.tenant_id).await?;
+    if !injection.allows(&msg.sender) {
+        let scan = ai::scan_injection(env, &msg.tenant_id, &redacted.text).await;
+        if scan.blocks() {
+            hold_for_human(msg, scan, &injection, db, env).await;
+            return Ok(());
+        }
     }
 
     // Embed once if any Prompt rule needs to be evaluated. Embedding errors
@@ -149,7 +253,7 @@ async fn handle_auto_reply(
         .iter()
         .any(|r| matches!(r.matcher, ReplyMatcher::Prompt { .. }));
     let body_embedding = if needs_embedding {
-        match ai::embed(env, &safe_body).await {
+        match ai::embed(env, &msg.tenant_id, &redacted.text).await {
             Ok(v) => Some(v),
             Err(e) => {
                 console_log!("Inbound embedding failed, prompt rules disabled: {:?}", e);
@@ -164,54 +268,106 @@ async fn handle_auto_reply(
     let matched: &ReplyRule = config
         .rules
         .iter()
-        .find(|rule| matches_rule(&rule.matcher, &safe_body, body_embedding.as_deref()))
+        .find(|rule| matches_rule(&rule.matcher, &safe_body, body_embedding.as_ref()))
         .unwrap_or(&config.default_rule);
+    if let Err(e) = set_message_rule(db, &msg.id, &matched.label).await {
+        console_log!("Failed to record matched rule: {:?}", e);
+    }
 
-    // Load persona for AI-mode rules. Skip the load entirely when the
-    // matched rule is canned — saves a KV hit on the hot keyword path.
+    // Load the account's brand (persona + hours) for AI-mode rules. Skip
+    // the load entirely when the matched rule is canned — saves a KV hit on
+    // the hot keyword path. The account's own persona, when it has one,
+    // replaces the brand's.
     let needs_persona = matches!(matched.response, ReplyResponse::Prompt { .. });
-    let persona = if needs_persona {
-        Some(get_onboarding(kv, &msg.tenant_id).await?.persona)
+    let brand = if needs_persona {
+        Some(brands::resolve(kv, &msg.tenant_id, brand_id.as_deref()).await?)
     } else {
         None
     };
+    let persona = brand
+        .as_ref()
+        .map(|b| config.persona.as_ref().unwrap_or(&b.persona));
 
     let is_ai = matches!(matched.response, ReplyResponse::Prompt { .. });
 
     // Block AI replies unless the persona has been approved AND the prompt
     // hasn't drifted since approval.
     if is_ai {
-        let safe = persona
-            .as_ref()
-            .map(|p| p.is_safe_to_use())
-            .unwrap_or(false);
+        let safe = persona.map(|p| p.is_safe_to_use()).unwrap_or(false);
         if !safe {
             console_log!(
-                "Persona not safety-approved for tenant {}, skipping AI reply",
-                msg.tenant_id
+                "Persona not safety-approved for tenant {} brand {}{}, skipping AI reply",
+                msg.tenant_id,
+                brand_id.as_deref().unwrap_or(Brand::MAIN),
+                if config.persona.is_some() {
+                    " (channel override)"
+                } else {
+                    ""
+                }
             );
             return Ok(());
         }
     }
 
-    if is_ai && !billing::try_deduct(db, &msg.tenant_id).await? {
+    // Resolve the rule's model tier against the operator allow-list; the
+    // credit cost follows the tier that actually runs. Per-token tenants
+    // reserve the tier's cost here and settle once the draft's token count
+    // is known.
+    let mut generation = matched.generation.clone();
+    let pricing = if is_ai {
+        get_pricing(db).await
+    } else {
+        Pricing::default()
+    };
+    let per_token = is_ai
+        && get_tenant(db, &msg.tenant_id)
+            .await?
+            .is_some_and(|t| t.credit_mode == CreditMode::PerToken);
+    let mut cost = if is_ai {
+        generation.tier = pricing.effective_tier(generation.tier);
+        pricing.reply_credits(generation.tier)
+    } else {
+        0
+    };
+
+    if is_ai && !billing::try_deduct(db, &msg.tenant_id, cost).await? {
         console_log!("Tenant {} out of AI-reply credits, skipping", msg.tenant_id);
+        notifications::notify(
+            env,
+            &msg.tenant_id,
+            Notice {
+                kind: NotificationKind::CreditsExhausted,
+                title: "You're out of reply credits".into(),
+                body: "AI replies are paused until you add credits. Canned replies still go out."
+                    .into(),
+                link: "/admin/billing".into(),
+                key: String::new(),
+            },
+        )
+        .await;
         return Ok(());
     }
 
-    let reply = match &matched.response {
-        ReplyResponse::Canned { text } => text.clone(),
+    // Persona + rule prompt. Kept outside the generate arm because the
+    // prompt-leak guardrail compares the draft against it.
+    let combined = match &matched.response {
+        ReplyResponse::Canned { .. } => String::new(),
         ReplyResponse::Prompt { text: rule_prompt } => {
             let persona_prompt = persona
-                .as_ref()
-                .map(|p| p.active_prompt())
+                .zip(brand.as_ref())
+                .map(|(p, b)| brands::system_prompt(p, &b.hours))
                 .unwrap_or_default();
-            let combined = if persona_prompt.is_empty() {
+            if persona_prompt.is_empty() {
                 rule_prompt.clone()
             } else {
                 format!("{persona_prompt}\n\n{rule_prompt}")
-            };
+            }
+        }
+    };
 
+    let (reply, tool_calls) = match &matched.response {
+        ReplyResponse::Canned { text } => (text.clone(), Vec::new()),
+        ReplyResponse::Prompt { .. } => {
             let mut context = serde_json::Map::new();
             if let Some(ref name) = msg.sender_name {
                 let safe_name: String = name.chars().take(100).collect();
@@ -219,14 +375,30 @@ async fn handle_auto_reply(
             }
             context.insert(
                 "message".into(),
-                serde_json::Value::String(safe_body.clone()),
+                serde_json::Value::String(redacted.text.clone()),
             );
 
-            match ai::generate_response(env, &combined, &context).await {
-                Ok(r) => r,
+            match ai::generate_response(
+                env,
+                &msg.tenant_id,
+                &generation,
+                &combined,
+                &context,
+                &redacted,
+            )
+            .await
+            {
+                Ok(draft) => {
+                    if per_token {
+                        let tokens = draft.usage.total_tokens();
+                        cost =
+                            settle_token_credits(db, &msg.tenant_id, cost, &pricing, tokens).await;
+                    }
+                    (redacted.restore(&draft.text), draft.tool_calls)
+                }
                 Err(e) => {
                     console_log!("AI auto-reply error: {:?}", e);
-                    if let Err(re) = billing::restore_credit(db, &msg.tenant_id).await {
+                    if let Err(re) = billing::restore_credit(db, &msg.tenant_id, cost).await {
                         console_log!("Failed to restore credit: {:?}", re);
                     }
                     return Ok(());
@@ -237,7 +409,7 @@ async fn handle_auto_reply(
 
     if reply.is_empty() {
         if is_ai {
-            if let Err(e) = billing::restore_credit(db, &msg.tenant_id).await {
+            if let Err(e) = billing::restore_credit(db, &msg.tenant_id, cost).await {
                 console_log!("Failed to restore credit: {:?}", e);
             }
         }
@@ -246,34 +418,78 @@ async fn handle_auto_reply(
 
     // For AI drafts, run the approval gate. The risk gate is the always-on
     // safety net for `Auto`; `Always` always queues; `NoGate` skips the
-    // gate, but only when the operator's env var is on.
+    // gate, but only when the operator's env var is on. Drafts the gate
+    // would send still go through the tenant's guardrails.
     if is_ai {
         let allow_no_gate = approval::allow_no_gate(env);
-        let persona_ref = persona.as_ref().expect("AI rule must have loaded persona");
-        let decision = approval::decide(matched, &reply, persona_ref, allow_no_gate);
-        if let approval::ApprovalDecision::Queue { reason } = decision {
-            if let Err(e) = approvals::enqueue(env, msg, matched, &reply, reason).await {
-                // Enqueue failed: don't send (we'd bypass the human review
-                // the rule asked for) and don't restore credit (the AI ran).
-                // Log for visibility and bail.
-                console_log!("Approval enqueue failed: {:?}", e);
-                return Ok(());
+        let persona_ref = persona.expect("AI rule must have loaded persona");
+        let queue = match approval::decide(matched, &reply, persona_ref, allow_no_gate) {
+            approval::ApprovalDecision::Queue { reason } => Some((reason, None)),
+            approval::ApprovalDecision::SendNow => {
+                let config = get_guardrails(kv, &msg.tenant_id).await?;
+                let ctx = guardrails::Context {
+                    config: &config,
+                    system_prompt: &combined,
+                    sender: &msg.sender,
+                };
+                guardrails::review(env, &msg.tenant_id, &reply, &ctx)
+                    .await
+                    .map(|hit| (hit.reason, Some(hit.detail)))
+            }
+        };
+        if let Some((reason, detail)) = queue {
+            let approval_id = match approvals::enqueue(
+                env,
+                msg,
+                matched,
+                &reply,
+                &tool_calls,
+                reason,
+                detail.as_deref(),
+                cost,
+            )
+            .await
+            {
+                Ok(id) => id,
+                Err(e) => {
+                    // Enqueue failed: don't send (we'd bypass the human
+                    // review the rule asked for) and don't restore credit
+                    // (the AI ran). Log for visibility and bail.
+                    console_log!("Approval enqueue failed: {:?}", e);
+                    return Ok(());
+                }
+            };
+            if let Err(e) = set_message_approval(db, &msg.id, &approval_id).await {
+                console_log!("Failed to link message to approval: {:?}", e);
             }
             if let Err(e) = save_message(
                 db,
                 &generate_id(),
-                &msg.channel,
-                MessageDirection::Outbound,
-                &msg.recipient,
-                &msg.sender,
-                &msg.tenant_id,
-                &msg.channel_account_id,
-                Some(MessageAction::AiQueued),
+                &MessageRow {
+                    channel: &msg.channel,
+                    direction: MessageDirection::Outbound,
+                    sender: &msg.recipient,
+                    recipient: &msg.sender,
+                    tenant_id: &msg.tenant_id,
+                    channel_account_id: &msg.channel_account_id,
+                    action_taken: Some(MessageAction::AiQueued),
+                    credits: cost,
+                    approval_id: Some(&approval_id),
+                },
             )
             .await
             {
                 console_log!("Failed to log queued message: {:?}", e);
             }
+            inbox::record_reply(
+                env,
+                &msg.tenant_id,
+                &msg.channel,
+                &msg.sender,
+                None,
+                MessageAction::AiQueued,
+            )
+            .await;
             return Ok(());
         }
     }
@@ -281,45 +497,170 @@ async fn handle_auto_reply(
     if let Err(e) = channel::send_reply(
         &msg.channel,
         env,
+        &msg.tenant_id,
         &msg.raw_metadata,
         &msg.sender,
-        &reply,
+        if is_ai {
+            Body::Markdown(&reply)
+        } else {
+            Body::Verbatim(&reply)
+        },
         None,
     )
     .await
     {
         console_log!("Auto-reply send error: {:?}", e);
         if is_ai {
-            if let Err(re) = billing::restore_credit(db, &msg.tenant_id).await {
+            if let Err(re) = billing::restore_credit(db, &msg.tenant_id, cost).await {
                 console_log!("Failed to restore credit: {:?}", re);
             }
         }
         return Ok(());
     }
 
+    let action = if is_ai {
+        MessageAction::AiReply
+    } else {
+        MessageAction::AutoReply
+    };
     if let Err(e) = save_message(
         db,
         &generate_id(),
-        &msg.channel,
-        MessageDirection::Outbound,
-        &msg.recipient,
-        &msg.sender,
-        &msg.tenant_id,
-        &msg.channel_account_id,
-        Some(MessageAction::AutoReply),
+        &MessageRow {
+            channel: &msg.channel,
+            direction: MessageDirection::Outbound,
+            sender: &msg.recipient,
+            recipient: &msg.sender,
+            tenant_id: &msg.tenant_id,
+            channel_account_id: &msg.channel_account_id,
+            action_taken: Some(action),
+            credits: cost,
+            approval_id: None,
+        },
     )
     .await
     {
         console_log!("Failed to log outbound message: {:?}", e);
     }
+    inbox::record_reply(
+        env,
+        &msg.tenant_id,
+        &msg.channel,
+        &msg.sender,
+        Some(&reply),
+        action,
+    )
+    .await;
 
     Ok(())
 }
 
+/// An inbound message the injection scan held back: record why, answer
+/// with the tenant's holding reply if they want one, and queue the message
+/// for a human. Each step is best-effort; no model sees the message.
+async fn hold_for_human(
+    msg: &InboundMessage,
+    scan: ai::InjectionScan,
+    config: &InjectionConfig,
+    db: &D1Database,
+    env: &Env,
+) {
+    let (action, reason) = match scan {
+        ai::InjectionScan::Failed => (
+            MessageAction::InjectionScanFailed,
+            QueueReason::InjectionScanFailed,
+        ),
+        _ => (
+            MessageAction::InjectionBlocked,
+            QueueReason::InjectionSuspected,
+        ),
+    };
+    console_log!(
+        "Holding message from {} in tenant {}: {}",
+        msg.sender,
+        msg.tenant_id,
+        action.as_str()
+    );
+    if let Err(e) = set_message_action(db, &msg.id, action).await {
+        console_log!("Failed to record injection hold: {:?}", e);
+    }
+
+    let holding = config.reply_text.trim();
+    let mut answered = false;
+    if config.send_holding_reply && !holding.is_empty() {
+        match channel::send_reply(
+            &msg.channel,
+            env,
+            &msg.tenant_id,
+            &msg.raw_metadata,
+            &msg.sender,
+            Body::Verbatim(holding),
+            None,
+        )
+        .await
+        {
+            Ok(()) => {
+                answered = true;
+                if let Err(e) = save_message(
+                    db,
+                    &generate_id(),
+                    &MessageRow {
+                        channel: &msg.channel,
+                        direction: MessageDirection::Outbound,
+                        sender: &msg.recipient,
+                        recipient: &msg.sender,
+                        tenant_id: &msg.tenant_id,
+                        channel_account_id: &msg.channel_account_id,
+                        action_taken: Some(MessageAction::AutoReply),
+                        credits: 0,
+                        approval_id: None,
+                    },
+                )
+                .await
+                {
+                    console_log!("Failed to log holding reply: {:?}", e);
+                }
+                inbox::record_reply(
+                    env,
+                    &msg.tenant_id,
+                    &msg.channel,
+                    &msg.sender,
+                    Some(holding),
+                    MessageAction::AutoReply,
+                )
+                .await;
+            }
+            Err(e) => console_log!("Holding reply send error: {:?}", e),
+        }
+    }
+
+    if config.relay_to_human {
+        // Once the holding reply is out, sending it again would be noise:
+        // the reviewer writes the real answer.
+        let draft = if answered { "" } else { holding };
+        match approvals::enqueue_for_human(env, msg, draft, reason).await {
+            Ok(approval_id) => {
+                if let Err(e) = set_message_approval(db, &msg.id, &approval_id).await {
+                    console_log!("Failed to link held message to approval: {:?}", e);
+                }
+                inbox::record_reply(env, &msg.tenant_id, &msg.channel, &msg.sender, None, action)
+                    .await
+            }
+            Err(e) => console_log!("Failed to queue held message: {:?}", e),
+        }
+    }
+}
+
 /// Decide whether a single rule's matcher fires on the inbound text.
 /// `body_embedding` is `None` if no Prompt rules exist or embedding failed —
-/// in that case Prompt matchers can never fire.
-fn matches_rule(matcher: &ReplyMatcher, body: &str, body_embedding: Option<&[f32]>) -> bool {
+/// in that case Prompt matchers can never fire. A rule embedded by a
+/// different model than the inbound message is skipped: the vectors live
+/// in different spaces.
+fn matches_rule(
+    matcher: &ReplyMatcher,
+    body: &str,
+    body_embedding: Option<&ai::Embedding>,
+) -> bool {
     match matcher {
         ReplyMatcher::Default => false, // default fires only via fallback path
         ReplyMatcher::Keyword { keywords } => {
@@ -330,6 +671,7 @@ fn matches_rule(matcher: &ReplyMatcher, body: &str, body_embedding: Option<&[f32
         }
         ReplyMatcher::Prompt {
             embedding,
+            embedding_model,
             threshold,
             ..
         } => {
@@ -339,7 +681,10 @@ fn matches_rule(matcher: &ReplyMatcher, body: &str, body_embedding: Option<&[f32
             if embedding.is_empty() {
                 return false;
             }
-            ai::cosine(body_vec, embedding) >= *threshold
+            if !embedding_model.is_empty() && *embedding_model != body_vec.model {
+                return false;
+            }
+            ai::cosine(&body_vec.vector, embedding) >= *threshold
         }
     }
 }
@@ -0,0 +1,512 @@
+//! Retention for the logs that would otherwise grow forever, and the
+//! daily rollups that outlive them.
+//!
+//! Each table in `RETAINED_TABLES` has a default period, which the
+//! operator can change (`retention_defaults`) and override per tenant
+//! (`tenant_retention`); 0 keeps rows forever. The daily cron first rolls
+//! complete days up into `daily_rollups` (counts by tenant, day, channel,
+//! direction and outcome, with reply times and credits for the message
+//! log; see `analytics`), then deletes rows past their tenant's period a
+//! batch at a time. Only days already rolled up are pruned, so counts over
+//! any range survive the rows behind them.
+//!
+//! The encrypted inbox history has its own, tenant-chosen window; see
+//! `inbox::purge_expired`.
+
+use std::collections::BTreeMap;
+
+use wasm_bindgen::JsValue;
+use worker::*;
+
+/// A log table the pruning job trims.
+#[derive(Debug)]
+pub struct RetainedTable {
+    pub name: &'static str,
+    /// What the operator pages call it.
+    pub label: &'static str,
+    pub default_days: u32,
+    /// `None` for operator tables with no tenant column.
+    tenant_column: Option<&'static str>,
+    /// Extra condition a row must meet to be pruned.
+    prunable: &'static str,
+    /// SQL for the rollup's channel, direction and outcome columns.
+    channel: &'static str,
+    direction: &'static str,
+    outcome: &'static str,
+    /// SQL for the credits each row charged.
+    credits: &'static str,
+    /// Whether rows carry `reply_secs` to bucket into `reply_within`.
+    reply_times: bool,
+}
+
+impl RetainedTable {
+    /// FTL key naming the table on the privacy page.
+    pub fn privacy_key(&self) -> String {
+        format!("privacy-retention-{}", self.name.replace('_', "-"))
+    }
+}
+
+pub const RETAINED_TABLES: &[RetainedTable] = &[
+    RetainedTable {
+        name: "messages",
+        label: "Message log",
+        default_days: 365,
+        tenant_column: Some("tenant_id"),
+        prunable: "",
+        channel: "channel",
+        direction: "direction",
+        outcome: "COALESCE(action_taken, '')",
+        credits: "credits",
+        reply_times: true,
+    },
+    RetainedTable {
+        name: "whatsapp_messages",
+        label: "WhatsApp delivery log",
+        default_days: 90,
+        tenant_column: Some("tenant_id"),
+        prunable: "",
+        channel: "'whatsapp'",
+        direction: "direction",
+        outcome: "''",
+        credits: "0",
+        reply_times: false,
+    },
+    RetainedTable {
+        name: "instagram_messages",
+        label: "Instagram delivery log",
+        default_days: 90,
+        tenant_column: Some("tenant_id"),
+        prunable: "",
+        channel: "'instagram'",
+        direction: "direction",
+        outcome: "''",
+        credits: "0",
+        reply_times: false,
+    },
+    RetainedTable {
+        name: "lead_form_submissions",
+        label: "Lead form submissions",
+        default_days: 365,
+        tenant_column: Some("tenant_id"),
+        prunable: "",
+        channel: "'whatsapp'",
+        direction: "'inbound'",
+        outcome: "reply_mode",
+        credits: "0",
+        reply_times: false,
+    },
+    RetainedTable {
+        name: "pending_approvals",
+        label: "Approval history",
+        default_days: 90,
+        tenant_column: Some("tenant_id"),
+        // Drafts still waiting for a decision are never pruned.
+        prunable: "AND status != 'pending'",
+        channel: "channel",
+        direction: "'outbound'",
+        outcome: "status",
+        credits: "0",
+        reply_times: false,
+    },
+    RetainedTable {
+        name: "notifications",
+        label: "Notifications",
+        default_days: 90,
+        tenant_column: Some("tenant_id"),
+        // Unread notifications stay until someone has seen them.
+        prunable: "AND read_at IS NOT NULL",
+        channel: "''",
+        direction: "''",
+        outcome: "kind",
+        credits: "0",
+        reply_times: false,
+    },
+    RetainedTable {
+        name: "audit_log",
+        label: "Operator audit log",
+        default_days: 730,
+        tenant_column: None,
+        prunable: "",
+        channel: "''",
+        direction: "''",
+        outcome: "action",
+        credits: "0",
+        reply_times: false,
+    },
+];
+
+pub fn table(name: &str) -> Option<&'static RetainedTable> {
+    RETAINED_TABLES.iter().find(|t| t.name == name)
+}
+
+/// Periods the operator can pick, in days. 0 keeps rows forever.
+pub const CHOICES: &[u32] = &[0, 30, 90, 180, 365, 730, 1825];
+
+/// Days the rollup stays behind today, so approvals have been decided and
+/// messages stamped with what the pipeline did before their day is counted.
+const ROLLUP_LAG_DAYS: u32 = 2;
+
+/// Days rolled up per statement, and statements per table per run.
+const ROLLUP_CHUNK_DAYS: u32 = 31;
+const ROLLUP_CHUNKS_PER_RUN: u32 = 12;
+
+/// Rows deleted per statement, and statements per table per run.
+const PRUNE_BATCH_ROWS: u32 = 1_000;
+const PRUNE_BATCHES_PER_RUN: u32 = 50;
+
+/// A table's period for one tenant.
+#[derive(Debug, Clone, Copy)]
+pub struct Retention {
+    pub table: &'static RetainedTable,
+    /// The operator's period, or the built-in one if never changed.
+    pub default_days: u32,
+    pub override_days: Option<u32>,
+}
+
+impl Retention {
+    pub fn days(&self) -> u32 {
+        self.override_days.unwrap_or(self.default_days)
+    }
+}
+
+fn days_by_table(rows: &[serde_json::Value]) -> BTreeMap<String, u32> {
+    rows.iter()
+        .filter_map(|r| {
+            let name = r.get("table_name")?.as_str()?;
+            let days = r.get("days")?.as_u64()?;
+            Some((name.to_string(), days as u32))
+        })
+        .collect()
+}
+
+/// Operator defaults, in registry order.
+pub async fn defaults(db: &D1Database) -> Result<Vec<Retention>> {
+    let rows = db
+        .prepare("SELECT table_name, days FROM retention_defaults")
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    Ok(merge(&days_by_table(&rows), &BTreeMap::new()))
+}
+
+/// Operator defaults with the tenant's overrides, in registry order.
+pub async fn for_tenant(db: &D1Database, tenant_id: &str) -> Result<Vec<Retention>> {
+    let defaults = db
+        .prepare("SELECT table_name, days FROM retention_defaults")
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    let overrides = db
+        .prepare("SELECT table_name, days FROM tenant_retention WHERE tenant_id = ?")
+        .bind(&[tenant_id.into()])?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    Ok(merge(&days_by_table(&defaults), &days_by_table(&overrides)))
+}
+
+fn merge(defaults: &BTreeMap<String, u32>, overrides: &BTreeMap<String, u32>) -> Vec<Retention> {
+    RETAINED_TABLES
+        .iter()
+        .map(|t| Retention {
+            table: t,
+            default_days: defaults.get(t.name).copied().unwrap_or(t.default_days),
+            // Operator tables aren't the tenant's to override.
+            override_days: t.tenant_column.and_then(|_| overrides.get(t.name).copied()),
+        })
+        .collect()
+}
+
+/// Whether the tenant can have their own period for `table`.
+pub fn overridable(table: &RetainedTable) -> bool {
+    table.tenant_column.is_some()
+}
+
+pub async fn save_default(db: &D1Database, table: &RetainedTable, days: u32) -> Result<()> {
+    db.prepare(
+        "INSERT INTO retention_defaults (table_name, days) VALUES (?1, ?2)
+         ON CONFLICT (table_name) DO UPDATE SET days = ?2, updated_at = datetime('now')",
+    )
+    .bind(&[table.name.into(), JsValue::from(days as f64)])?
+    .run()
+    .await?;
+    Ok(())
+}
+
+/// Set the tenant's period for `table`, or go back to the default.
+pub async fn save_override(
+    db: &D1Database,
+    tenant_id: &str,
+    table: &RetainedTable,
+    days: Option<u32>,
+) -> Result<()> {
+    match days {
+        Some(days) => {
+            db.prepare(
+                "INSERT INTO tenant_retention (tenant_id, table_name, days) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (tenant_id, table_name) DO UPDATE SET days = ?3",
+            )
+            .bind(&[
+                tenant_id.into(),
+                table.name.into(),
+                JsValue::from(days as f64),
+            ])?
+            .run()
+            .await?
+        }
+        None => {
+            db.prepare("DELETE FROM tenant_retention WHERE tenant_id = ? AND table_name = ?")
+                .bind(&[tenant_id.into(), table.name.into()])?
+                .run()
+                .await?
+        }
+    };
+    Ok(())
+}
+
+/// The first day not yet rolled up, per table.
+pub async fn rolled_up_until(db: &D1Database) -> Result<BTreeMap<String, String>> {
+    let rows = db
+        .prepare("SELECT source, next_day FROM rollup_state")
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    Ok(rows
+        .iter()
+        .filter_map(|r| {
+            Some((
+                r.get("source")?.as_str()?.to_string(),
+                r.get("next_day")?.as_str()?.to_string(),
+            ))
+        })
+        .collect())
+}
+
+/// Roll up, then prune. Runs on the daily retention cron.
+pub async fn run(db: &D1Database) -> Result<()> {
+    let defaults = defaults(db).await?;
+    for retention in &defaults {
+        let table = retention.table;
+        let rolled = rollup(db, table).await?;
+        let pruned = prune(db, table, retention.default_days).await?;
+        if rolled > 0 || pruned > 0 {
+            console_log!(
+                "Retention {}: rolled up {rolled} day(s), pruned {pruned} row(s)",
+                table.name
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Count the table's complete days into `daily_rollups`, resuming from
+/// `rollup_state`. Returns the days covered.
+async fn rollup(db: &D1Database, table: &RetainedTable) -> Result<u32> {
+    let mut days = 0;
+    for _ in 0..ROLLUP_CHUNKS_PER_RUN {
+        let state = db
+            .prepare(format!(
+                "SELECT COALESCE(
+                     (SELECT next_day FROM rollup_state WHERE source = ?1),
+                     (SELECT substr(MIN(created_at), 1, 10) FROM {name})
+                 ) AS start,
+                 date('now', ?2) AS cutoff",
+                name = table.name
+            ))
+            .bind(&[
+                table.name.into(),
+                format!("-{} days", ROLLUP_LAG_DAYS - 1).into(),
+            ])?
+            .first::<serde_json::Value>(None)
+            .await?;
+        let s = |k: &str| {
+            state
+                .as_ref()
+                .and_then(|r| r.get(k))
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        };
+        let (Some(start), Some(cutoff)) = (s("start"), s("cutoff")) else {
+            return Ok(days);
+        };
+        if start >= cutoff {
+            return Ok(days);
+        }
+
+        let end = db
+            .prepare("SELECT min(date(?1, ?2), ?3) AS end, julianday(min(date(?1, ?2), ?3)) - julianday(?1) AS days")
+            .bind(&[
+                start.as_str().into(),
+                format!("+{ROLLUP_CHUNK_DAYS} days").into(),
+                cutoff.as_str().into(),
+            ])?
+            .first::<serde_json::Value>(None)
+            .await?;
+        let Some(end_day) = end
+            .as_ref()
+            .and_then(|r| r.get("end"))
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+        else {
+            return Ok(days);
+        };
+        let roll = db
+            .prepare(format!(
+                "INSERT INTO daily_rollups
+                 (tenant_id, day, source, channel, direction, outcome, reply_within, count, credits)
+                 {counts}
+                 ON CONFLICT (tenant_id, day, source, channel, direction, outcome, reply_within)
+                 DO UPDATE SET count = excluded.count, credits = excluded.credits",
+                counts = counts_sql(table, "created_at >= ?1 AND created_at < ?2"),
+            ))
+            .bind(&[start.as_str().into(), end_day.as_str().into()])?;
+        let advance = db
+            .prepare(
+                "INSERT INTO rollup_state (source, next_day) VALUES (?1, ?2)
+                 ON CONFLICT (source) DO UPDATE SET next_day = ?2, updated_at = datetime('now')",
+            )
+            .bind(&[table.name.into(), end_day.as_str().into()])?;
+        // One transaction, so a day is never counted without the state
+        // moving past it, or skipped without being counted.
+        db.batch(vec![roll, advance]).await?;
+        days += end
+            .and_then(|r| r.get("days").and_then(|v| v.as_f64()))
+            .unwrap_or(0.0) as u32;
+    }
+    Ok(days)
+}
+
+/// Daily counts of `table` in `daily_rollups` column order, over the rows
+/// `filter` picks. The rollup and the live part of the analytics read it,
+/// so both count the same way.
+pub fn counts_sql(table: &RetainedTable, filter: &str) -> String {
+    let reply_within = if table.reply_times {
+        crate::analytics::reply_within_sql("reply_secs")
+    } else {
+        "0".to_string()
+    };
+    format!(
+        "SELECT {tenant}, substr(created_at, 1, 10), '{name}', {channel}, {direction}, {outcome},
+                {reply_within}, COUNT(*), SUM({credits})
+         FROM {name} WHERE {filter}
+         GROUP BY 1, 2, 4, 5, 6, 7",
+        tenant = table.tenant_column.unwrap_or("''"),
+        name = table.name,
+        channel = table.channel,
+        direction = table.direction,
+        outcome = table.outcome,
+        credits = table.credits,
+    )
+}
+
+/// Delete rows past their tenant's period and already rolled up, a batch
+/// at a time. Returns the rows deleted.
+async fn prune(db: &D1Database, table: &RetainedTable, default_days: u32) -> Result<u64> {
+    let sql = prune_sql(table);
+    let mut total = 0;
+    for _ in 0..PRUNE_BATCHES_PER_RUN {
+        let result = db
+            .prepare(sql.clone())
+            .bind(&[
+                table.name.into(),
+                JsValue::from(default_days as f64),
+                JsValue::from(PRUNE_BATCH_ROWS as f64),
+            ])?
+            .run()
+            .await?;
+        let deleted = result.meta()?.and_then(|m| m.changes).unwrap_or(0) as u64;
+        total += deleted;
+        if deleted < PRUNE_BATCH_ROWS as u64 {
+            break;
+        }
+    }
+    Ok(total)
+}
+
+/// One pruning batch. Binds: ?1 table name, ?2 default days, ?3 batch size.
+fn prune_sql(table: &RetainedTable) -> String {
+    let (join, days) = match table.tenant_column {
+        Some(column) => (
+            format!(
+                "LEFT JOIN tenant_retention r ON r.tenant_id = x.{column} AND r.table_name = ?1"
+            ),
+            "COALESCE(r.days, ?2)",
+        ),
+        None => (String::new(), "?2"),
+    };
+    format!(
+        "DELETE FROM {name} WHERE rowid IN (
+             SELECT x.rowid FROM {name} x {join}
+             WHERE {days} > 0
+               AND x.created_at < datetime('now', '-' || {days} || ' days')
+               AND x.created_at < (SELECT next_day FROM rollup_state WHERE source = ?1)
+               {prunable}
+             LIMIT ?3
+         )",
+        name = table.name,
+        prunable = table.prunable,
+    )
+}
+
+/// Human-readable period for operator pages.
+pub fn describe(days: u32) -> String {
+    match days {
+        0 => "Forever".to_string(),
+        d if d % 365 == 0 => format!("{} year{}", d / 365, if d == 365 { "" } else { "s" }),
+        d => format!("{d} days"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_are_offered_choices() {
+        for t in RETAINED_TABLES {
+            assert!(CHOICES.contains(&t.default_days), "{}", t.name);
+        }
+    }
+
+    #[test]
+    fn overrides_apply_only_to_tenant_tables() {
+        let defaults = BTreeMap::from([("messages".to_string(), 180)]);
+        let overrides =
+            BTreeMap::from([("messages".to_string(), 30), ("audit_log".to_string(), 30)]);
+        let merged = merge(&defaults, &overrides);
+        let get = |name: &str| merged.iter().find(|r| r.table.name == name).unwrap();
+        assert_eq!(get("messages").default_days, 180);
+        assert_eq!(get("messages").days(), 30);
+        assert_eq!(get("audit_log").override_days, None);
+        assert_eq!(get("audit_log").days(), 730);
+        assert_eq!(get("lead_form_submissions").days(), 365);
+    }
+
+    #[test]
+    fn prune_sql_keeps_pending_approvals_and_unrolled_days() {
+        let sql = prune_sql(table("pending_approvals").unwrap());
+        assert!(sql.contains("status != 'pending'"));
+        assert!(sql.contains("COALESCE(r.days, ?2)"));
+        assert!(sql.contains("rollup_state"));
+        let sql = prune_sql(table("audit_log").unwrap());
+        assert!(!sql.contains("tenant_retention"));
+        assert!(sql.contains("WHERE ?2 > 0"));
+    }
+
+    #[test]
+    fn every_table_is_named_on_the_privacy_page() {
+        let l = crate::locale::Locale::default_inr();
+        for table in RETAINED_TABLES {
+            let key = table.privacy_key();
+            assert_ne!(crate::i18n::t(&l, &key), key);
+        }
+    }
+
+    #[test]
+    fn periods_read_naturally() {
+        assert_eq!(describe(0), "Forever");
+        assert_eq!(describe(90), "90 days");
+        assert_eq!(describe(365), "1 year");
+        assert_eq!(describe(1825), "5 years");
+    }
+}
@@ -1,7 +1,7 @@
 //! Persona prompt safety classifier.
 //!
 //! Every time a tenant changes their persona prompt, we run it through the
-//! cheap fast model with Calculon Tech's trust/safety/fun policy. Approved
+//! fast model tier with Calculon Tech's trust/safety/fun policy. Approved
 //! prompts can drive AI replies; rejected prompts pause AI replies until the
 //! user edits and resubmits.
 //!
@@ -9,9 +9,10 @@
 //! so the user's save request stays fast and a slow/failing classifier
 //! doesn't block them.
 
-use serde::Serialize;
 use worker::*;
 
+use crate::ai::{self, Message, Purpose};
+
 const SYSTEM_PROMPT: &str = "\
 You are reviewing a business assistant persona prompt against Calculon Tech's trust, safety, \
 and fun policy. Reject prompts that incite violence, harass, discriminate by protected class, \
@@ -25,93 +26,20 @@ pub enum SafetyVerdict {
     Rejected { vague_reason: String },
 }
 
-#[derive(Serialize)]
-struct AiRequest {
-    messages: Vec<Message>,
-}
-
-#[derive(Serialize)]
-struct Message {
-    role: String,
-    content: String,
-}
-
-fn fast_model(env: &Env) -> String {
-    env.var("AI_FAST_MODEL")
-        .map(|v| v.to_string())
-        .ok()
-        .filter(|s: &String| !s.is_empty())
-        .unwrap_or_else(|| "@cf/meta/llama-3.1-8b-instruct-fast".to_string())
-}
-
 /// Classify a persona prompt. Network errors and parse failures fail
 /// **closed** (Rejected) so AI replies don't accidentally fire under a
 /// prompt that hasn't actually been vetted.
-pub async fn classify_persona(env: &Env, prompt: &str) -> SafetyVerdict {
-    let request = AiRequest {
-        messages: vec![
-            Message {
-                role: "system".to_string(),
-                content: SYSTEM_PROMPT.to_string(),
-            },
-            Message {
-                role: "user".to_string(),
-                content: prompt.to_string(),
-            },
-        ],
-    };
-
-    let request_json = match serde_json::to_string(&request) {
-        Ok(j) => j,
-        Err(_) => {
-            return SafetyVerdict::Rejected {
-                vague_reason: vague_reason_for("internal"),
-            }
-        }
-    };
-
-    let ai = match env.ai("AI") {
-        Ok(a) => a,
-        Err(_) => {
-            return SafetyVerdict::Rejected {
-                vague_reason: vague_reason_for("internal"),
-            }
-        }
-    };
-
-    let input: serde_json::Value = match serde_json::from_str(&request_json) {
-        Ok(v) => v,
-        Err(_) => {
-            return SafetyVerdict::Rejected {
-                vague_reason: vague_reason_for("internal"),
-            }
-        }
-    };
-
-    let model = fast_model(env);
-    let response: std::result::Result<serde_json::Value, _> = ai.run(&model, input).await;
-    let response = match response {
-        Ok(r) => r,
+pub async fn classify_persona(env: &Env, tenant_id: &str, prompt: &str) -> SafetyVerdict {
+    let messages = [Message::system(SYSTEM_PROMPT), Message::user(prompt)];
+    match ai::chat(env, tenant_id, Purpose::Safety, &messages).await {
+        Ok(raw) => parse_verdict(&raw),
         Err(e) => {
             console_log!("Persona safety classifier error: {:?}", e);
-            return SafetyVerdict::Rejected {
+            SafetyVerdict::Rejected {
                 vague_reason: vague_reason_for("internal"),
-            };
+            }
         }
-    };
-
-    let raw = response
-        .as_str()
-        .map(|s| s.to_string())
-        .or_else(|| {
-            response
-                .get("response")
-                .and_then(|r| r.as_str())
-                .map(|s| s.to_string())
-        })
-        .unwrap_or_default();
-
-    parse_verdict(&raw)
+    }
 }
 
 fn parse_verdict(raw: &str) -> SafetyVerdict {
@@ -2,8 +2,10 @@
 //!
 //! When a tenant saves a persona, the admin handler enqueues a `SafetyJob`.
 //! The queue consumer (wired in `lib.rs` via `#[event(queue)]`) re-reads the
-//! persona, confirms the prompt hash hasn't drifted (a newer save would
-//! supersede this job), runs the classifier, and writes the verdict back.
+//! persona (a brand's, see `brands`, or a channel account's override),
+//! confirms the prompt hash hasn't drifted (a newer save would supersede
+//! this job), runs the classifier, and writes the verdict back if the
+//! stored prompt still has that hash.
 //!
 //! Failures are surfaced via `message.retry()` so the queue's DLQ retry
 //! policy applies.
@@ -13,9 +15,13 @@ use worker::*;
 // `ack()` and `retry()` come from the `MessageExt` trait.
 use worker::MessageExt;
 
+use crate::brands;
+use crate::notifications::{self, Notice};
 use crate::safety::{classify_persona, SafetyVerdict};
-use crate::storage::{get_onboarding, save_onboarding};
-use crate::types::{PersonaSafety, PersonaSafetyStatus};
+use crate::storage::{get_reply_config, update_reply_config};
+use crate::types::{
+    Brand, Channel, NotificationKind, PersonaConfig, PersonaSafety, PersonaSafetyStatus,
+};
 
 /// Queue binding name in `wrangler.toml`.
 pub const QUEUE_BINDING: &str = "SAFETY_QUEUE";
@@ -28,6 +34,123 @@ pub struct SafetyJob {
     /// different hash, on the assumption that a newer save has already
     /// re-enqueued.
     pub prompt_hash: String,
+    /// Brand whose persona to check; `None` (and jobs queued before
+    /// brands existed) is the main brand.
+    #[serde(default)]
+    pub brand_id: Option<String>,
+    /// Set for a channel account's persona override, with `account_id`
+    /// as `storage::get_reply_config` takes it. Takes precedence over
+    /// `brand_id`.
+    #[serde(default)]
+    pub channel: Option<Channel>,
+    #[serde(default)]
+    pub account_id: Option<String>,
+}
+
+/// Where a job's persona lives.
+enum Slot {
+    Brand(Box<Brand>),
+    Channel {
+        channel: Channel,
+        account_id: String,
+        persona: Box<PersonaConfig>,
+    },
+}
+
+impl Slot {
+    async fn load(kv: &kv::KvStore, job: &SafetyJob) -> Result<Option<Slot>> {
+        if let (Some(channel), Some(account_id)) = (&job.channel, job.account_id.as_deref()) {
+            let persona = get_reply_config(kv, &job.tenant_id, channel, account_id)
+                .await?
+                .and_then(|c| c.persona);
+            return Ok(persona.map(|persona| Slot::Channel {
+                channel: channel.clone(),
+                account_id: account_id.to_string(),
+                persona: Box::new(persona),
+            }));
+        }
+        let brand_id = job.brand_id.as_deref().unwrap_or(Brand::MAIN);
+        Ok(brands::get(kv, &job.tenant_id, brand_id)
+            .await?
+            .map(|b| Slot::Brand(Box::new(b))))
+    }
+
+    fn persona(&self) -> &PersonaConfig {
+        match self {
+            Slot::Brand(brand) => &brand.persona,
+            Slot::Channel { persona, .. } => persona,
+        }
+    }
+
+    /// Write `safety` onto the stored persona, re-read so nothing else
+    /// saved since `load` is lost. Returns false (nothing written) when
+    /// the persona was removed or its prompt no longer hashes to
+    /// `prompt_hash`: a newer save owns the verdict.
+    async fn save_verdict(
+        &self,
+        kv: &kv::KvStore,
+        tenant_id: &str,
+        prompt_hash: &str,
+        safety: PersonaSafety,
+    ) -> Result<bool> {
+        match self {
+            Slot::Brand(brand) => {
+                let Some(mut current) = brands::get(kv, tenant_id, &brand.id).await? else {
+                    return Ok(false);
+                };
+                if current.persona.active_prompt_hash() != prompt_hash {
+                    return Ok(false);
+                }
+                current.persona.safety = safety;
+                brands::save(kv, tenant_id, &current).await?;
+                Ok(true)
+            }
+            Slot::Channel {
+                channel,
+                account_id,
+                ..
+            } => {
+                let mut wrote = false;
+                update_reply_config(kv, tenant_id, channel, account_id, |c| {
+                    if let Some(persona) = c
+                        .persona
+                        .as_mut()
+                        .filter(|p| p.active_prompt_hash() == prompt_hash)
+                    {
+                        persona.safety = safety;
+                        wrote = true;
+                    }
+                })
+                .await?;
+                Ok(wrote)
+            }
+        }
+    }
+
+    fn title(&self) -> String {
+        match self {
+            Slot::Brand(brand) if brand.is_main() => "Your persona was turned down".into(),
+            Slot::Brand(brand) => format!("The {} persona was turned down", brand.name()),
+            Slot::Channel { channel, .. } => {
+                format!("Your {} persona was turned down", channel.label())
+            }
+        }
+    }
+
+    fn link(&self) -> String {
+        match self {
+            Slot::Brand(brand) if brand.is_main() => "/admin/persona".to_string(),
+            Slot::Brand(brand) => format!("/admin/persona?brand={}", brand.id),
+            Slot::Channel {
+                channel,
+                account_id,
+                ..
+            } => format!(
+                "/admin/persona?channel={}&account={account_id}",
+                channel.as_str()
+            ),
+        }
+    }
 }
 
 /// Send a safety job onto the queue. Logs and returns `Ok(())` if the queue
@@ -65,11 +188,19 @@ pub async fn handle_batch(batch: MessageBatch<SafetyJob>, env: Env) -> Result<()
 
         let job = msg.body();
 
-        let mut state = match get_onboarding(&kv, &job.tenant_id).await {
-            Ok(s) => s,
+        let slot = match Slot::load(&kv, job).await {
+            Ok(Some(slot)) => slot,
+            Ok(None) => {
+                console_log!(
+                    "Safety queue: persona for {} is gone, skipping",
+                    job.tenant_id
+                );
+                msg.ack();
+                continue;
+            }
             Err(e) => {
                 console_log!(
-                    "Safety queue: load onboarding for {} failed: {e:?}",
+                    "Safety queue: load persona for {} failed: {e:?}",
                     job.tenant_id
                 );
                 msg.retry();
@@ -79,7 +210,7 @@ pub async fn handle_batch(batch: MessageBatch<SafetyJob>, env: Env) -> Result<()
 
         // Stale-check: if the active prompt has changed since this job was
         // enqueued, a newer save has already re-enqueued. Drop this one.
-        let current_hash = state.persona.active_prompt_hash();
+        let current_hash = slot.persona().active_prompt_hash();
         if current_hash != job.prompt_hash {
             console_log!(
                 "Safety queue: stale job for {} (hash drifted), skipping",
@@ -89,9 +220,21 @@ pub async fn handle_batch(batch: MessageBatch<SafetyJob>, env: Env) -> Result<()
             continue;
         }
 
-        let verdict = classify_persona(&env, &state.persona.active_prompt()).await;
+        let verdict = classify_persona(&env, &job.tenant_id, &slot.persona().active_prompt()).await;
         let now = crate::helpers::now_iso();
-        state.persona.safety = match verdict {
+        let rejection = match &verdict {
+            SafetyVerdict::Rejected { vague_reason } => Some(Notice {
+                kind: NotificationKind::PersonaRejected,
+                title: slot.title(),
+                body: format!(
+                    "The safety check didn't accept the persona prompt: {vague_reason}. AI replies stay off until you revise it."
+                ),
+                link: slot.link(),
+                key: current_hash.clone(),
+            }),
+            SafetyVerdict::Approved => None,
+        };
+        let safety = match verdict {
             SafetyVerdict::Approved => PersonaSafety {
                 status: PersonaSafetyStatus::Approved,
                 checked_prompt_hash: Some(current_hash),
@@ -106,8 +249,25 @@ pub async fn handle_batch(batch: MessageBatch<SafetyJob>, env: Env) -> Result<()
             },
         };
 
-        match save_onboarding(&kv, &job.tenant_id, &state).await {
-            Ok(()) => msg.ack(),
+        // The classifier call takes a while; the tenant may have saved a
+        // new prompt meanwhile, whose own job will deliver its verdict.
+        match slot
+            .save_verdict(&kv, &job.tenant_id, &job.prompt_hash, safety)
+            .await
+        {
+            Ok(true) => {
+                msg.ack();
+                if let Some(notice) = rejection {
+                    notifications::notify(&env, &job.tenant_id, notice).await;
+                }
+            }
+            Ok(false) => {
+                console_log!(
+                    "Safety queue: persona for {} changed during the check, dropping verdict",
+                    job.tenant_id
+                );
+                msg.ack();
+            }
             Err(e) => {
                 console_log!(
                     "Safety queue: write-back for {} failed: {e:?}",
@@ -1,12 +1,20 @@
 use worker::*;
 
 use crate::crypto;
-use crate::email::digest;
+use crate::email::{digest, summary};
+use crate::exports;
+use crate::inbox;
 use crate::instagram;
+use crate::notifications::{self, Notice};
+use crate::retention;
 use crate::storage::*;
+use crate::tenant_deletion;
+use crate::types::{InstagramAccount, NotificationKind};
 
-/// Approval-digest sweep + 24h expiry. Mirror this string in `wrangler.toml`
-/// and `.github/workflows/deploy.yml` so the deploy registers the trigger.
+/// Approval-digest sweep + 24h expiry, building pending data exports,
+/// resuming unfinished tenant deletions and sending Monday summaries.
+/// Mirror this string in `wrangler.toml` and `.github/workflows/deploy.yml`
+/// so the deploy registers the trigger.
 pub const CRON_DIGEST_SWEEP: &str = "*/15 * * * *";
 
 /// Daily Instagram long-lived-token refresh. Same wrangler/workflow contract.
@@ -16,6 +24,10 @@ pub const CRON_INSTAGRAM_REFRESH: &str = "0 6 * * *";
 /// whose next_run_at has passed and credits the targeted tenants.
 pub const CRON_SCHEDULED_GRANTS: &str = "0 * * * *";
 
+/// Daily purge of retained message history past each tenant's window,
+/// then rollup and pruning of the log tables (`retention`).
+pub const CRON_RETENTION_PURGE: &str = "30 3 * * *";
+
 pub async fn handle_scheduled(event: ScheduledEvent, env: Env, _ctx: ScheduleContext) {
     let cron = event.cron();
     console_log!("Scheduled job started: {cron}");
@@ -25,6 +37,15 @@ pub async fn handle_scheduled(event: ScheduledEvent, env: Env, _ctx: ScheduleCon
             if let Err(e) = digest::sweep(&env).await {
                 console_log!("Digest sweep error: {:?}", e);
             }
+            if let Err(e) = exports::sweep(&env).await {
+                console_log!("Export sweep error: {:?}", e);
+            }
+            if let Err(e) = tenant_deletion::sweep(&env).await {
+                console_log!("Tenant deletion sweep error: {:?}", e);
+            }
+            if let Err(e) = summary::sweep(&env).await {
+                console_log!("Weekly summary sweep error: {:?}", e);
+            }
         }
         CRON_INSTAGRAM_REFRESH => {
             if let Err(e) = refresh_instagram_tokens(&env).await {
@@ -36,6 +57,17 @@ pub async fn handle_scheduled(event: ScheduledEvent, env: Env, _ctx: ScheduleCon
                 console_log!("Scheduled-grants processor error: {:?}", e);
             }
         }
+        CRON_RETENTION_PURGE => match env.d1("DB") {
+            Ok(db) => {
+                if let Err(e) = inbox::purge_expired(&db).await {
+                    console_log!("Retention purge error: {:?}", e);
+                }
+                if let Err(e) = retention::run(&db).await {
+                    console_log!("Log retention error: {:?}", e);
+                }
+            }
+            Err(e) => console_log!("Retention purge error: {:?}", e),
+        },
         other => console_log!("Unknown cron schedule: {other}"),
     }
 
@@ -176,6 +208,16 @@ async fn refresh_instagram_tokens(env: &Env) -> Result<()> {
             if instagram::token_is_expired(&token) {
                 console_log!("Token expired for Instagram account {}", account.id);
                 failures += 1;
+                notifications::notify(
+                    env,
+                    tenant_id,
+                    instagram_notice(
+                        &account,
+                        format!("@{} is disconnected", account.instagram_username),
+                        "Its Instagram access expired, so DMs to it aren't being answered. Reconnect the account to resume.",
+                    ),
+                )
+                .await;
                 continue;
             }
 
@@ -193,6 +235,16 @@ async fn refresh_instagram_tokens(env: &Env) -> Result<()> {
                             e
                         );
                         failures += 1;
+                        notifications::notify(
+                            env,
+                            tenant_id,
+                            instagram_notice(
+                                &account,
+                                format!("@{} needs reconnecting", account.instagram_username),
+                                "Instagram wouldn't renew its access and it will stop working when the current access runs out. Reconnect the account to keep replies going.",
+                            ),
+                        )
+                        .await;
                     }
                 }
             }
@@ -208,3 +260,15 @@ async fn refresh_instagram_tokens(env: &Env) -> Result<()> {
 
     Ok(())
 }
+
+/// One unread notice per account: a failed refresh followed by expiry
+/// doesn't pile up.
+fn instagram_notice(account: &InstagramAccount, title: String, body: &str) -> Notice {
+    Notice {
+        kind: NotificationKind::InstagramToken,
+        title,
+        body: body.to_string(),
+        link: "/admin/instagram".into(),
+        key: account.id.clone(),
+    }
+}
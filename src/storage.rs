@@ -2,7 +2,9 @@ use wasm_bindgen::JsValue;
 use worker::*;
 
 use crate::types::{
-    CreditEntry, InstagramAccount, LeadCaptureForm, Tenant, TenantBilling, WhatsAppAccount,
+    AiOverride, AiTool, Brand, ConsentConfig, CreditEntry, GuardrailConfig, InjectionConfig,
+    InstagramAccount, LeadCaptureForm, PiiClass, RedactionConfig, Tenant, TenantBilling,
+    WhatsAppAccount,
 };
 
 // ============================================================================
@@ -52,6 +54,15 @@ fn row_to_tenant(row: &serde_json::Value) -> Tenant {
             .get("verified_at")
             .and_then(|v| v.as_str())
             .map(|s| s.to_string()),
+        credit_mode: row
+            .get("credit_mode")
+            .and_then(|v| v.as_str())
+            .and_then(crate::types::CreditMode::from_wire)
+            .unwrap_or_default(),
+        retention_days: row
+            .get("retention_days")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0) as u32,
         created_at: row
             .get("created_at")
             .and_then(|v| v.as_str())
@@ -109,8 +120,8 @@ pub async fn save_tenant(db: &D1Database, tenant: &Tenant) -> Result<()> {
         None => JsValue::NULL,
     };
     db.prepare(
-        "INSERT INTO tenants (id, email, name, facebook_id, plan, locale, currency, email_address_extras_purchased, verified_at, created_at, updated_at)
-         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "INSERT INTO tenants (id, email, name, facebook_id, plan, locale, currency, email_address_extras_purchased, verified_at, credit_mode, retention_days, created_at, updated_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
          ON CONFLICT(id) DO UPDATE SET
            email = excluded.email,
            name = excluded.name,
@@ -120,6 +131,8 @@ pub async fn save_tenant(db: &D1Database, tenant: &Tenant) -> Result<()> {
            currency = excluded.currency,
            email_address_extras_purchased = excluded.email_address_extras_purchased,
            verified_at = excluded.verified_at,
+           credit_mode = excluded.credit_mode,
+           retention_days = excluded.retention_days,
            updated_at = excluded.updated_at",
     )
     .bind(&[
@@ -132,6 +145,8 @@ pub async fn save_tenant(db: &D1Database, tenant: &Tenant) -> Result<()> {
         tenant.currency.as_str().into(),
         JsValue::from(tenant.email_address_extras_purchased as f64),
         verified_val,
+        tenant.credit_mode.as_str().into(),
+        JsValue::from(tenant.retention_days as f64),
         tenant.created_at.as_str().into(),
         tenant.updated_at.as_str().into(),
     ])?
@@ -185,28 +200,108 @@ pub async fn get_session(kv: &kv::KvStore, token: &str) -> Result<Option<String>
 
 pub async fn delete_session(kv: &kv::KvStore, token: &str) -> Result<()> {
     kv.delete(&format!("session:{}", token)).await?;
+    kv.delete(&format!("session_member:{}", token)).await?;
+    kv.delete(&format!("session_agency:{}", token)).await?;
+    Ok(())
+}
+
+/// Mark a session as a team member's (`team`). The session itself still
+/// maps to the tenant; owners' sessions have no member key.
+pub async fn save_session_member(
+    kv: &kv::KvStore,
+    token: &str,
+    tenant_id: &str,
+    member_id: &str,
+    ttl_seconds: u64,
+) -> Result<()> {
+    let value = serde_json::json!({ "tenant_id": tenant_id, "member_id": member_id });
+    kv.put(&format!("session_member:{}", token), value.to_string())?
+        .expiration_ttl(ttl_seconds)
+        .execute()
+        .await?;
     Ok(())
 }
 
+/// The member id a session belongs to, `None` for the owner's sessions.
+pub async fn get_session_member(kv: &kv::KvStore, token: &str) -> Result<Option<String>> {
+    let value = kv
+        .get(&format!("session_member:{}", token))
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| Error::from(e.to_string()))?;
+    Ok(value.and_then(|v| {
+        v.get("member_id")
+            .and_then(|m| m.as_str())
+            .map(str::to_string)
+    }))
+}
+
+/// Mark a session as switched in to a client through its agency
+/// (`agency`). The session maps to the client; this names the agency.
+pub async fn save_session_agency(
+    kv: &kv::KvStore,
+    token: &str,
+    tenant_id: &str,
+    agency_id: &str,
+    ttl_seconds: u64,
+) -> Result<()> {
+    let value = serde_json::json!({ "tenant_id": tenant_id, "agency_id": agency_id });
+    kv.put(&format!("session_agency:{}", token), value.to_string())?
+        .expiration_ttl(ttl_seconds)
+        .execute()
+        .await?;
+    Ok(())
+}
+
+/// The agency a session acts through, `None` unless it switched in to a
+/// client.
+pub async fn get_session_agency(kv: &kv::KvStore, token: &str) -> Result<Option<String>> {
+    let value = kv
+        .get(&format!("session_agency:{}", token))
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| Error::from(e.to_string()))?;
+    Ok(value.and_then(|v| {
+        v.get("agency_id")
+            .and_then(|m| m.as_str())
+            .map(str::to_string)
+    }))
+}
+
 // ============================================================================
 // CSRF Token KV Operations
 // ============================================================================
 
+/// The owner's token is `csrf:{tenant}`; each team member, and an agency
+/// acting for a client, has their own under `member_csrf:{tenant}:{scope}`
+/// (`Actor::csrf_scope`) so their sign-ins don't replace the owner's.
+fn csrf_key(tenant_id: &str, scope: Option<&str>) -> String {
+    match scope {
+        Some(scope) => format!("member_csrf:{tenant_id}:{scope}"),
+        None => format!("csrf:{tenant_id}"),
+    }
+}
+
 pub async fn save_csrf_token(
     kv: &kv::KvStore,
     tenant_id: &str,
+    scope: Option<&str>,
     token: &str,
     ttl_seconds: u64,
 ) -> Result<()> {
-    kv.put(&format!("csrf:{}", tenant_id), token)?
+    kv.put(&csrf_key(tenant_id, scope), token)?
         .expiration_ttl(ttl_seconds)
         .execute()
         .await?;
     Ok(())
 }
 
-pub async fn get_csrf_token(kv: &kv::KvStore, tenant_id: &str) -> Result<Option<String>> {
-    kv.get(&format!("csrf:{}", tenant_id))
+pub async fn get_csrf_token(
+    kv: &kv::KvStore,
+    tenant_id: &str,
+    scope: Option<&str>,
+) -> Result<Option<String>> {
+    kv.get(&csrf_key(tenant_id, scope))
         .text()
         .await
         .map_err(|e| Error::from(e.to_string()))
@@ -440,87 +535,6 @@ pub async fn list_lead_forms(kv: &kv::KvStore, tenant_id: &str) -> Result<Vec<Le
     Ok(forms)
 }
 
-// ============================================================================
-// Delete All Tenant Data
-// ============================================================================
-
-pub async fn delete_tenant_data(kv: &kv::KvStore, db: &D1Database, tenant_id: &str) -> Result<()> {
-    // Delete WhatsApp accounts + phone indexes
-    let wa_accounts = list_whatsapp_accounts(kv, tenant_id).await?;
-    for account in &wa_accounts {
-        delete_whatsapp_account(kv, tenant_id, &account.id).await?;
-    }
-
-    // Delete Instagram accounts + page indexes + tokens
-    let ig_accounts = list_instagram_accounts(kv, tenant_id).await?;
-    for account in &ig_accounts {
-        delete_instagram_account(kv, tenant_id, &account.id).await?;
-    }
-
-    // Delete lead forms
-    let forms = list_lead_forms(kv, tenant_id).await?;
-    for form in &forms {
-        delete_lead_form(kv, tenant_id, &form.id).await?;
-    }
-
-    // Delete D1 data: messages + billing (but preserve payments and audit_log for compliance)
-    for table in &[
-        "whatsapp_messages",
-        "lead_form_submissions",
-        "instagram_messages",
-        "email_messages",
-        "email_metrics",
-        "messages",
-        "tenant_billing",
-    ] {
-        let query = format!("DELETE FROM {} WHERE tenant_id = ?", table);
-        let stmt = db.prepare(&query);
-        if let Err(e) = stmt.bind(&[tenant_id.into()])?.run().await {
-            console_log!("Failed to delete from {}: {:?}", table, e);
-        }
-    }
-
-    // Nullify tenant_id in payments (preserve records for compliance)
-    if let Err(e) = db
-        .prepare("UPDATE payments SET tenant_id = NULL WHERE tenant_id = ?")
-        .bind(&[tenant_id.into()])?
-        .run()
-        .await
-    {
-        console_log!("Failed to nullify tenant in payments: {:?}", e);
-    }
-
-    // Delete email addresses + indices (KV)
-    if let Ok(addrs) = get_email_addresses(kv, tenant_id).await {
-        for a in &addrs {
-            let _ = delete_email_address_index(kv, &a.local_part).await;
-        }
-        let _ = save_email_addresses(kv, tenant_id, &[]).await;
-    }
-
-    // Delete discord config (KV)
-    if let Ok(Some(config)) = get_discord_config_by_tenant(kv, tenant_id).await {
-        kv.delete(&format!("discord_guild:{}", config.guild_id))
-            .await?;
-        kv.delete(&format!("discord_config:{}", tenant_id)).await?;
-    }
-
-    // Delete onboarding state and credentials (KV)
-    kv.delete(&format!("onboarding:{}", tenant_id)).await?;
-    kv.delete(&format!("tenant:{}:credentials", tenant_id))
-        .await?;
-    // Delete CSRF token (KV)
-    kv.delete(&format!("csrf:{}", tenant_id)).await?;
-
-    // Delete tenant record (D1)
-    db.prepare("DELETE FROM tenants WHERE id = ?")
-        .bind(&[tenant_id.into()])?
-        .run()
-        .await?;
-
-    Ok(())
-}
-
 // ============================================================================
 // D1 Operations (WhatsApp Messages)
 // ============================================================================
@@ -555,6 +569,10 @@ pub async fn save_whatsapp_message(
 // D1 Operations (Lead Form Submissions)
 // ============================================================================
 
+const INSERT_LEAD_FORM_SUBMISSION: &str =
+    "INSERT INTO lead_form_submissions (id, lead_form_id, phone_number, whatsapp_account_id, message_sent, reply_mode, tenant_id, created_at)
+     VALUES (?, ?, ?, ?, ?, ?, ?, datetime('now'))";
+
 #[allow(clippy::too_many_arguments)]
 pub async fn save_lead_form_submission(
     db: &D1Database,
@@ -566,10 +584,7 @@ pub async fn save_lead_form_submission(
     reply_mode: &str,
     tenant_id: &str,
 ) -> Result<()> {
-    let stmt = db.prepare(
-        "INSERT INTO lead_form_submissions (id, lead_form_id, phone_number, whatsapp_account_id, message_sent, reply_mode, tenant_id, created_at)
-         VALUES (?, ?, ?, ?, ?, ?, ?, datetime('now'))",
-    );
+    let stmt = db.prepare(INSERT_LEAD_FORM_SUBMISSION);
     stmt.bind(&[
         id.into(),
         lead_form_id.into(),
@@ -774,45 +789,116 @@ pub async fn get_email_reverse_alias(
         .map_err(|e| Error::from(e.to_string()))
 }
 
-/// Save a reverse alias mapping (30-day TTL).
+/// Save a reverse alias mapping (30-day TTL), listed under its tenant
+/// like a WhatsApp account.
 pub async fn save_email_reverse_alias(
     kv: &kv::KvStore,
     reverse_address: &str,
     alias: &EmailReverseAlias,
 ) -> Result<()> {
+    const TTL: u64 = 30 * 24 * 60 * 60;
     let key = format!("email_reverse:{reverse_address}");
     kv.put(&key, serde_json::to_string(alias)?)?
-        .expiration_ttl(30 * 24 * 60 * 60)
+        .expiration_ttl(TTL)
         .execute()
         .await?;
+    kv.put(
+        &format!("tenant:{}:email_reverse:{reverse_address}", alias.tenant_id),
+        "",
+    )?
+    .expiration_ttl(TTL)
+    .execute()
+    .await?;
     Ok(())
 }
 
+/// The ids a tenant has listed under `tenant:{tenant_id}:{section}`, for
+/// records keyed by id alone (`conv:{id}`, `email_reverse:{address}`).
+pub async fn tenant_listed_ids(
+    kv: &kv::KvStore,
+    tenant_id: &str,
+    section: &str,
+) -> Result<Vec<String>> {
+    let prefix = format!("tenant:{tenant_id}:{section}");
+    let mut ids = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let mut list = kv.list().prefix(prefix.clone());
+        if let Some(c) = cursor.take() {
+            list = list.cursor(c);
+        }
+        let page = list
+            .execute()
+            .await
+            .map_err(|e| Error::from(e.to_string()))?;
+        ids.extend(
+            page.keys
+                .iter()
+                .filter_map(|k| k.name.strip_prefix(&prefix))
+                .filter(|id| !id.is_empty())
+                .map(str::to_string),
+        );
+        match page.cursor {
+            Some(c) if !page.list_complete => cursor = Some(c),
+            _ => return Ok(ids),
+        }
+    }
+}
+
 // ============================================================================
 // Unified Message Storage
 // ============================================================================
 
 use crate::types::{
     Channel, ConversationContext, DiscordConfig, InboundMessage, MessageAction, MessageDirection,
-    OnboardingState,
+    OnboardingState, ReplyConfig,
 };
 
-/// Save a unified message to D1. No message content stored: metadata only.
-pub async fn save_message(
-    db: &D1Database,
-    id: &str,
-    channel: &Channel,
-    direction: MessageDirection,
-    sender: &str,
-    recipient: &str,
-    tenant_id: &str,
-    channel_account_id: &str,
-    action_taken: Option<MessageAction>,
-) -> Result<()> {
-    let stmt = db.prepare(
-        "INSERT INTO messages (id, channel, direction, sender, recipient, tenant_id, channel_account_id, action_taken)
-         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
-    );
+/// One outbound, relay or inbound row for `save_message`. No message
+/// content: metadata only.
+pub struct MessageRow<'a> {
+    pub channel: &'a Channel,
+    pub direction: MessageDirection,
+    pub sender: &'a str,
+    pub recipient: &'a str,
+    pub tenant_id: &'a str,
+    pub channel_account_id: &'a str,
+    pub action_taken: Option<MessageAction>,
+    /// What the row charged (negative for a refund).
+    pub credits: i64,
+    pub approval_id: Option<&'a str>,
+}
+
+/// Save a unified message to D1. A row that answers the customer also
+/// records how long they waited, from their first message since the last
+/// answer.
+pub async fn save_message(db: &D1Database, id: &str, row: &MessageRow<'_>) -> Result<()> {
+    let MessageRow {
+        channel,
+        direction,
+        sender,
+        recipient,
+        tenant_id,
+        channel_account_id,
+        action_taken,
+        credits,
+        approval_id,
+    } = *row;
+    let reply_secs = if action_taken.is_some_and(MessageAction::answers_customer) {
+        "(SELECT CAST(strftime('%s', 'now') - strftime('%s', MIN(i.created_at)) AS INTEGER)
+          FROM messages i
+          WHERE i.tenant_id = ?6 AND i.channel = ?2 AND i.sender = ?5 AND i.direction = 'inbound'
+            AND i.created_at > COALESCE(
+                (SELECT MAX(o.created_at) FROM messages o
+                 WHERE o.tenant_id = ?6 AND o.channel = ?2 AND o.recipient = ?5
+                   AND o.reply_secs IS NOT NULL), ''))"
+    } else {
+        "NULL"
+    };
+    let stmt = db.prepare(format!(
+        "INSERT INTO messages (id, channel, direction, sender, recipient, tenant_id, channel_account_id, action_taken, credits, approval_id, reply_secs)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, {reply_secs})"
+    ));
     stmt.bind(&[
         id.into(),
         channel.as_str().into(),
@@ -824,6 +910,8 @@ pub async fn save_message(
         action_taken
             .map(|a| JsValue::from(a.as_str()))
             .unwrap_or(JsValue::null()),
+        JsValue::from(credits as f64),
+        approval_id.map(JsValue::from).unwrap_or(JsValue::null()),
     ])?
     .run()
     .await?;
@@ -836,49 +924,29 @@ pub async fn save_inbound_message(
     msg: &InboundMessage,
     action_taken: Option<MessageAction>,
 ) -> Result<()> {
-    save_message(
-        db,
-        &msg.id,
-        &msg.channel,
-        MessageDirection::Inbound,
-        &msg.sender,
-        &msg.recipient,
-        &msg.tenant_id,
-        &msg.channel_account_id,
-        action_taken,
+    db.prepare(
+        "INSERT INTO messages (id, channel, direction, sender, recipient, tenant_id, channel_account_id, action_taken, contact_id)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
     )
-    .await
-}
-
-/// Get recent unified messages for a tenant.
-pub async fn get_messages(
-    db: &D1Database,
-    tenant_id: &str,
-    channel: Option<&Channel>,
-    limit: u32,
-) -> Result<Vec<serde_json::Value>> {
-    if let Some(ch) = channel {
-        let stmt = db.prepare(
-            "SELECT * FROM messages WHERE tenant_id = ? AND channel = ? ORDER BY created_at DESC LIMIT ?",
-        );
-        let result = stmt
-            .bind(&[
-                tenant_id.into(),
-                ch.as_str().into(),
-                JsValue::from(limit as f64),
-            ])?
-            .all()
-            .await?;
-        result.results::<serde_json::Value>()
-    } else {
-        let stmt = db
-            .prepare("SELECT * FROM messages WHERE tenant_id = ? ORDER BY created_at DESC LIMIT ?");
-        let result = stmt
-            .bind(&[tenant_id.into(), JsValue::from(limit as f64)])?
-            .all()
-            .await?;
-        result.results::<serde_json::Value>()
-    }
+    .bind(&[
+        msg.id.clone().into(),
+        msg.channel.as_str().into(),
+        MessageDirection::Inbound.as_str().into(),
+        msg.sender.clone().into(),
+        msg.recipient.clone().into(),
+        msg.tenant_id.clone().into(),
+        msg.channel_account_id.clone().into(),
+        action_taken
+            .map(|a| JsValue::from(a.as_str()))
+            .unwrap_or(JsValue::null()),
+        msg.contact_id
+            .clone()
+            .map(JsValue::from)
+            .unwrap_or(JsValue::null()),
+    ])?
+    .run()
+    .await?;
+    Ok(())
 }
 
 // ============================================================================
@@ -887,6 +955,8 @@ pub async fn get_messages(
 
 const CONVERSATION_TTL: u64 = 7 * 24 * 60 * 60; // 7 days
 
+/// Saved under `conv:{id}` (Discord buttons carry only the id) and listed
+/// under `tenant:{tenant}:conv:{id}`, which expires with it.
 pub async fn save_conversation_context(kv: &kv::KvStore, ctx: &ConversationContext) -> Result<()> {
     let key = format!("conv:{}", ctx.id);
     let json = serde_json::to_string(ctx).map_err(|e| Error::from(format!("JSON error: {e}")))?;
@@ -894,6 +964,10 @@ pub async fn save_conversation_context(kv: &kv::KvStore, ctx: &ConversationConte
         .expiration_ttl(CONVERSATION_TTL)
         .execute()
         .await?;
+    kv.put(&format!("tenant:{}:conv:{}", ctx.tenant_id, ctx.id), "")?
+        .expiration_ttl(CONVERSATION_TTL)
+        .execute()
+        .await?;
     Ok(())
 }
 
@@ -909,6 +983,10 @@ pub async fn get_conversation_context(
 }
 
 pub async fn delete_conversation_context(kv: &kv::KvStore, id: &str) -> Result<()> {
+    if let Some(ctx) = get_conversation_context(kv, id).await? {
+        kv.delete(&format!("tenant:{}:conv:{id}", ctx.tenant_id))
+            .await?;
+    }
     let key = format!("conv:{id}");
     kv.delete(&key).await?;
     Ok(())
@@ -978,6 +1056,479 @@ pub async fn get_discord_config_by_tenant(
         .map_err(|e| Error::from(e.to_string()))
 }
 
+// ============================================================================
+// Reply config (any channel)
+// ============================================================================
+
+/// A channel account's `ReplyConfig`. `account_id` is the WhatsApp or
+/// Instagram account id or the email local-part; Discord has one config
+/// per tenant and ignores it. None when the tenant has no such account.
+pub async fn get_reply_config(
+    kv: &kv::KvStore,
+    tenant_id: &str,
+    channel: &Channel,
+    account_id: &str,
+) -> Result<Option<ReplyConfig>> {
+    Ok(match channel {
+        Channel::WhatsApp => get_whatsapp_account(kv, account_id)
+            .await?
+            .filter(|a| a.tenant_id == tenant_id)
+            .map(|a| a.auto_reply),
+        Channel::Instagram => get_instagram_account(kv, account_id)
+            .await?
+            .filter(|a| a.tenant_id == tenant_id)
+            .map(|a| a.auto_reply),
+        Channel::Email => get_email_address(kv, tenant_id, account_id)
+            .await?
+            .map(|a| a.auto_reply),
+        Channel::Discord => get_discord_config_by_tenant(kv, tenant_id)
+            .await?
+            .map(|c| c.auto_reply),
+    })
+}
+
+/// Change a channel account's `ReplyConfig` in place and save the account.
+/// Returns false when the tenant has no such account.
+pub async fn update_reply_config(
+    kv: &kv::KvStore,
+    tenant_id: &str,
+    channel: &Channel,
+    account_id: &str,
+    f: impl FnOnce(&mut ReplyConfig),
+) -> Result<bool> {
+    let now = crate::helpers::now_iso();
+    match channel {
+        Channel::WhatsApp => {
+            let Some(mut account) = get_whatsapp_account(kv, account_id).await? else {
+                return Ok(false);
+            };
+            if account.tenant_id != tenant_id {
+                return Ok(false);
+            }
+            f(&mut account.auto_reply);
+            account.updated_at = now;
+            save_whatsapp_account(kv, &account).await?;
+        }
+        Channel::Instagram => {
+            let Some(mut account) = get_instagram_account(kv, account_id).await? else {
+                return Ok(false);
+            };
+            if account.tenant_id != tenant_id {
+                return Ok(false);
+            }
+            f(&mut account.auto_reply);
+            account.updated_at = now;
+            save_instagram_account(kv, &account).await?;
+        }
+        Channel::Email => {
+            let Some(mut addr) = get_email_address(kv, tenant_id, account_id).await? else {
+                return Ok(false);
+            };
+            f(&mut addr.auto_reply);
+            addr.updated_at = now;
+            save_email_address(kv, tenant_id, &addr).await?;
+        }
+        Channel::Discord => {
+            let Some(mut dc) = get_discord_config_by_tenant(kv, tenant_id).await? else {
+                return Ok(false);
+            };
+            f(&mut dc.auto_reply);
+            save_discord_config(kv, &dc).await?;
+        }
+    }
+    Ok(true)
+}
+
+// ============================================================================
+// Brands (KV)
+// ============================================================================
+
+/// A tenant's extra brands, oldest first. The main brand lives in the
+/// onboarding state; see `brands`.
+pub async fn get_brands(kv: &kv::KvStore, tenant_id: &str) -> Result<Vec<Brand>> {
+    let key = format!("brands:{tenant_id}");
+    kv.get(&key)
+        .json::<Vec<Brand>>()
+        .await
+        .map_err(|e| Error::from(e.to_string()))
+        .map(|opt| opt.unwrap_or_default())
+}
+
+pub async fn save_brands(kv: &kv::KvStore, tenant_id: &str, brands: &[Brand]) -> Result<()> {
+    let key = format!("brands:{tenant_id}");
+    kv.put(&key, serde_json::to_string(brands)?)?
+        .execute()
+        .await?;
+    Ok(())
+}
+
+// ============================================================================
+// AI Provider Override (KV)
+// ============================================================================
+
+pub async fn get_ai_override(kv: &kv::KvStore, tenant_id: &str) -> Result<Option<AiOverride>> {
+    let key = format!("ai_override:{tenant_id}");
+    kv.get(&key)
+        .json::<AiOverride>()
+        .await
+        .map_err(|e| Error::from(e.to_string()))
+}
+
+pub async fn save_ai_override(kv: &kv::KvStore, tenant_id: &str, pin: &AiOverride) -> Result<()> {
+    let key = format!("ai_override:{tenant_id}");
+    let json = serde_json::to_string(pin).map_err(|e| Error::from(format!("JSON error: {e}")))?;
+    kv.put(&key, json)?.execute().await?;
+    Ok(())
+}
+
+pub async fn delete_ai_override(kv: &kv::KvStore, tenant_id: &str) -> Result<()> {
+    kv.delete(&format!("ai_override:{tenant_id}")).await?;
+    Ok(())
+}
+
+// ============================================================================
+// AI Tools (KV)
+// ============================================================================
+
+pub async fn get_ai_tools(kv: &kv::KvStore, tenant_id: &str) -> Result<Vec<AiTool>> {
+    let key = format!("ai_tools:{tenant_id}");
+    Ok(kv
+        .get(&key)
+        .json::<Vec<AiTool>>()
+        .await
+        .map_err(|e| Error::from(e.to_string()))?
+        .unwrap_or_default())
+}
+
+pub async fn save_ai_tools(kv: &kv::KvStore, tenant_id: &str, tools: &[AiTool]) -> Result<()> {
+    let key = format!("ai_tools:{tenant_id}");
+    let json = serde_json::to_string(tools).map_err(|e| Error::from(format!("JSON error: {e}")))?;
+    kv.put(&key, json)?.execute().await?;
+    Ok(())
+}
+
+// ============================================================================
+// Guardrails (KV)
+// ============================================================================
+
+pub async fn get_guardrails(kv: &kv::KvStore, tenant_id: &str) -> Result<GuardrailConfig> {
+    let key = format!("guardrails:{tenant_id}");
+    Ok(kv
+        .get(&key)
+        .json::<GuardrailConfig>()
+        .await
+        .map_err(|e| Error::from(e.to_string()))?
+        .unwrap_or_default())
+}
+
+pub async fn save_guardrails(
+    kv: &kv::KvStore,
+    tenant_id: &str,
+    config: &GuardrailConfig,
+) -> Result<()> {
+    let key = format!("guardrails:{tenant_id}");
+    let json =
+        serde_json::to_string(config).map_err(|e| Error::from(format!("JSON error: {e}")))?;
+    kv.put(&key, json)?.execute().await?;
+    Ok(())
+}
+
+// ============================================================================
+// PII Redaction (KV config, D1 audit counts)
+// ============================================================================
+
+pub async fn get_redaction_config(kv: &kv::KvStore, tenant_id: &str) -> Result<RedactionConfig> {
+    let key = format!("redaction:{tenant_id}");
+    Ok(kv
+        .get(&key)
+        .json::<RedactionConfig>()
+        .await
+        .map_err(|e| Error::from(e.to_string()))?
+        .unwrap_or_default())
+}
+
+pub async fn save_redaction_config(
+    kv: &kv::KvStore,
+    tenant_id: &str,
+    config: &RedactionConfig,
+) -> Result<()> {
+    let key = format!("redaction:{tenant_id}");
+    let json =
+        serde_json::to_string(config).map_err(|e| Error::from(format!("JSON error: {e}")))?;
+    kv.put(&key, json)?.execute().await?;
+    Ok(())
+}
+
+/// Record how many values of each class were masked out of a logged
+/// inbound message, as a JSON object (`{"card":1,"phone":2}`).
+pub async fn set_message_redactions(
+    db: &D1Database,
+    message_id: &str,
+    counts: &std::collections::BTreeMap<PiiClass, u32>,
+) -> Result<()> {
+    let json =
+        serde_json::to_string(counts).map_err(|e| Error::from(format!("JSON error: {e}")))?;
+    db.prepare("UPDATE messages SET redactions = ? WHERE id = ?")
+        .bind(&[json.into(), message_id.into()])?
+        .run()
+        .await?;
+    Ok(())
+}
+
+/// Masked values per class across the tenant's messages from the last
+/// `days` days, for the audit summary.
+pub async fn redaction_totals(
+    db: &D1Database,
+    tenant_id: &str,
+    days: u32,
+) -> Result<std::collections::BTreeMap<PiiClass, u32>> {
+    let rows = db
+        .prepare(
+            "SELECT redactions FROM messages
+             WHERE tenant_id = ? AND redactions IS NOT NULL
+               AND created_at >= datetime('now', ?)",
+        )
+        .bind(&[tenant_id.into(), format!("-{days} days").into()])?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    let mut totals = std::collections::BTreeMap::new();
+    for row in rows {
+        let Some(counts) = row
+            .get("redactions")
+            .and_then(|v| v.as_str())
+            .and_then(|s| {
+                serde_json::from_str::<std::collections::BTreeMap<PiiClass, u32>>(s).ok()
+            })
+        else {
+            continue;
+        };
+        for (class, n) in counts {
+            *totals.entry(class).or_insert(0) += n;
+        }
+    }
+    Ok(totals)
+}
+
+// ============================================================================
+// Prompt Injection (KV config, D1 counts)
+// ============================================================================
+
+pub async fn get_injection_config(kv: &kv::KvStore, tenant_id: &str) -> Result<InjectionConfig> {
+    let key = format!("injection:{tenant_id}");
+    Ok(kv
+        .get(&key)
+        .json::<InjectionConfig>()
+        .await
+        .map_err(|e| Error::from(e.to_string()))?
+        .unwrap_or_default())
+}
+
+pub async fn save_injection_config(
+    kv: &kv::KvStore,
+    tenant_id: &str,
+    config: &InjectionConfig,
+) -> Result<()> {
+    let key = format!("injection:{tenant_id}");
+    let json =
+        serde_json::to_string(config).map_err(|e| Error::from(format!("JSON error: {e}")))?;
+    kv.put(&key, json)?.execute().await?;
+    Ok(())
+}
+
+/// Stamp what the pipeline did with an already-logged inbound message.
+pub async fn set_message_action(
+    db: &D1Database,
+    message_id: &str,
+    action: MessageAction,
+) -> Result<()> {
+    db.prepare("UPDATE messages SET action_taken = ? WHERE id = ?")
+        .bind(&[action.as_str().into(), message_id.into()])?
+        .run()
+        .await?;
+    Ok(())
+}
+
+/// Stamp the reply rule that matched an already-logged inbound message.
+pub async fn set_message_rule(db: &D1Database, message_id: &str, rule_label: &str) -> Result<()> {
+    db.prepare("UPDATE messages SET rule_label = ? WHERE id = ?")
+        .bind(&[rule_label.into(), message_id.into()])?
+        .run()
+        .await?;
+    Ok(())
+}
+
+/// Link an inbound row to the approval its message was queued as.
+pub async fn set_message_approval(
+    db: &D1Database,
+    message_id: &str,
+    approval_id: &str,
+) -> Result<()> {
+    db.prepare("UPDATE messages SET approval_id = ? WHERE id = ?")
+        .bind(&[approval_id.into(), message_id.into()])?
+        .run()
+        .await?;
+    Ok(())
+}
+
+/// Inbound messages held by the injection scan over the last `days` days:
+/// `(detected, scan_failed)`.
+pub async fn injection_totals(db: &D1Database, tenant_id: &str, days: u32) -> Result<(u32, u32)> {
+    let rows = db
+        .prepare(
+            "SELECT action_taken, COUNT(*) AS n FROM messages
+             WHERE tenant_id = ? AND action_taken IN (?, ?)
+               AND created_at >= datetime('now', ?)
+             GROUP BY action_taken",
+        )
+        .bind(&[
+            tenant_id.into(),
+            MessageAction::InjectionBlocked.as_str().into(),
+            MessageAction::InjectionScanFailed.as_str().into(),
+            format!("-{days} days").into(),
+        ])?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    let mut totals = (0, 0);
+    for row in rows {
+        let n = row.get("n").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+        match row.get("action_taken").and_then(|v| v.as_str()) {
+            Some("injection_blocked") => totals.0 = n,
+            Some("injection_scan_failed") => totals.1 = n,
+            _ => {}
+        }
+    }
+    Ok(totals)
+}
+
+// ============================================================================
+// Consent Keywords (KV)
+// ============================================================================
+
+pub async fn get_consent_config(kv: &kv::KvStore, tenant_id: &str) -> Result<ConsentConfig> {
+    let key = format!("consent:{tenant_id}");
+    Ok(kv
+        .get(&key)
+        .json::<ConsentConfig>()
+        .await
+        .map_err(|e| Error::from(e.to_string()))?
+        .unwrap_or_default())
+}
+
+pub async fn save_consent_config(
+    kv: &kv::KvStore,
+    tenant_id: &str,
+    config: &ConsentConfig,
+) -> Result<()> {
+    let key = format!("consent:{tenant_id}");
+    let json =
+        serde_json::to_string(config).map_err(|e| Error::from(format!("JSON error: {e}")))?;
+    kv.put(&key, json)?.execute().await?;
+    Ok(())
+}
+
+// ============================================================================
+// AI Usage (D1)
+// ============================================================================
+
+/// Log one model call (or one multi-turn draft) against a tenant.
+pub async fn record_ai_usage(
+    db: &D1Database,
+    tenant_id: &str,
+    purpose: crate::ai::Purpose,
+    tier: crate::ai::provider::Tier,
+    usage: &crate::ai::Usage,
+) -> Result<()> {
+    db.prepare(
+        "INSERT INTO ai_usage (id, tenant_id, purpose, tier, provider, model, input_tokens, output_tokens, estimated)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&[
+        crate::helpers::generate_id().into(),
+        tenant_id.into(),
+        purpose.as_str().into(),
+        tier.as_str().into(),
+        usage.provider.as_str().into(),
+        usage.model.as_str().into(),
+        JsValue::from(usage.input_tokens as f64),
+        JsValue::from(usage.output_tokens as f64),
+        JsValue::from(if usage.estimated { 1.0_f64 } else { 0.0_f64 }),
+    ])?
+    .run()
+    .await?;
+    Ok(())
+}
+
+/// Token totals for one tenant, purpose and tier over a reporting window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsageTotal {
+    pub tenant_id: String,
+    pub purpose: String,
+    pub tier: crate::ai::provider::Tier,
+    pub calls: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+}
+
+/// Usage grouped by tenant, purpose and tier over the last `days` days.
+pub async fn ai_usage_totals(db: &D1Database, days: u32) -> Result<Vec<UsageTotal>> {
+    let rows = db
+        .prepare(
+            "SELECT tenant_id, purpose, tier, COUNT(*) AS calls,
+                    SUM(input_tokens) AS input_tokens, SUM(output_tokens) AS output_tokens
+             FROM ai_usage
+             WHERE created_at >= datetime('now', ?)
+             GROUP BY tenant_id, purpose, tier",
+        )
+        .bind(&[format!("-{days} days").into()])?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            let s = |k: &str| row.get(k).and_then(|v| v.as_str()).map(str::to_string);
+            let n = |k: &str| row.get(k).and_then(|v| v.as_i64()).unwrap_or(0);
+            Some(UsageTotal {
+                tenant_id: s("tenant_id")?,
+                purpose: s("purpose")?,
+                tier: crate::ai::provider::Tier::from_wire(&s("tier")?)?,
+                calls: n("calls"),
+                input_tokens: n("input_tokens"),
+                output_tokens: n("output_tokens"),
+            })
+        })
+        .collect())
+}
+
+/// Captured payments per tenant and currency (minor units) over the last
+/// `days` days.
+pub async fn revenue_totals(db: &D1Database, days: u32) -> Result<Vec<(String, String, i64)>> {
+    let rows = db
+        .prepare(
+            "SELECT tenant_id, currency, SUM(amount) AS amount
+             FROM payments
+             WHERE status = 'captured' AND tenant_id IS NOT NULL
+               AND created_at >= datetime('now', ?)
+             GROUP BY tenant_id, currency",
+        )
+        .bind(&[format!("-{days} days").into()])?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            Some((
+                row.get("tenant_id")?.as_str()?.to_string(),
+                row.get("currency")?.as_str()?.to_uppercase(),
+                row.get("amount")?.as_i64()?,
+            ))
+        })
+        .collect())
+}
+
 // ============================================================================
 // Billing Storage
 // ============================================================================
@@ -1103,12 +1654,33 @@ impl PricingConcept {
     }
 }
 
+/// What the operator pays per million tokens on a model tier, in micro-USD
+/// (1/1,000,000 of a dollar). Only feeds cost reporting; tenants are billed
+/// in credits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenRate {
+    pub input: i64,
+    pub output: i64,
+}
+
 /// Operator-controlled pricing snapshot — currency-agnostic config plus a
 /// `(concept, currency_code)` map keyed by ISO 4217 code. Every currency
 /// uses the same unit per concept (see `PricingConcept::is_milli`).
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Pricing {
     pub email_pack_size: i64,
+    /// Reply credits an AI reply costs on each model tier.
+    pub fast_reply_credits: i64,
+    pub quality_reply_credits: i64,
+    /// Model tiers tenants may pick on AI rules. Never empty.
+    pub allowed_tiers: Vec<crate::types::ModelTier>,
+    /// Operator cost per tier, for the usage report.
+    pub fast_rate: TokenRate,
+    pub quality_rate: TokenRate,
+    pub embedding_rate: TokenRate,
+    /// Reply tokens (input + output) one credit buys for tenants on
+    /// per-token pricing.
+    pub tokens_per_credit: i64,
     pub amounts: std::collections::BTreeMap<(PricingConcept, String), i64>,
 }
 
@@ -1125,12 +1697,94 @@ impl Default for Pricing {
         amounts.insert((PricingConcept::VerificationAmount, "USD".into()), 100);
         Self {
             email_pack_size: 5,
+            fast_reply_credits: 1,
+            quality_reply_credits: 1,
+            allowed_tiers: crate::types::ModelTier::ALL.to_vec(),
+            // Workers AI list prices for the default models.
+            fast_rate: TokenRate {
+                input: 45_000,
+                output: 384_000,
+            },
+            quality_rate: TokenRate {
+                input: 270_000,
+                output: 850_000,
+            },
+            embedding_rate: TokenRate {
+                input: 67_000,
+                output: 0,
+            },
+            tokens_per_credit: 1_000,
             amounts,
         }
     }
 }
 
 impl Pricing {
+    /// Tier a rule actually runs on: its own if the operator allows it,
+    /// otherwise the first allowed tier.
+    pub fn effective_tier(&self, tier: crate::types::ModelTier) -> crate::types::ModelTier {
+        if self.allowed_tiers.contains(&tier) {
+            tier
+        } else {
+            self.allowed_tiers.first().copied().unwrap_or(tier)
+        }
+    }
+
+    /// Credits one AI reply costs on `tier` (after `effective_tier`).
+    pub fn reply_credits(&self, tier: crate::types::ModelTier) -> i64 {
+        match self.effective_tier(tier) {
+            crate::types::ModelTier::Fast => self.fast_reply_credits,
+            crate::types::ModelTier::Quality => self.quality_reply_credits,
+        }
+        .max(1)
+    }
+
+    /// Credits a reply costs for a tenant on per-token pricing: tokens
+    /// over `tokens_per_credit`, rounded up, never less than one.
+    pub fn token_credits(&self, tokens: u32) -> i64 {
+        let per = self.tokens_per_credit.max(1);
+        (tokens as i64 + per - 1).div_euclid(per).max(1)
+    }
+
+    pub fn token_rate(&self, tier: crate::ai::provider::Tier) -> TokenRate {
+        match tier {
+            crate::ai::provider::Tier::Fast => self.fast_rate,
+            crate::ai::provider::Tier::Quality => self.quality_rate,
+            crate::ai::provider::Tier::Embedding => self.embedding_rate,
+        }
+    }
+
+    /// Operator cost in micro-USD of `input` + `output` tokens on `tier`.
+    pub fn token_cost(&self, tier: crate::ai::provider::Tier, input: i64, output: i64) -> i64 {
+        let rate = self.token_rate(tier);
+        (input * rate.input + output * rate.output) / 1_000_000
+    }
+
+    /// Wire form of `allowed_tiers` for the `pricing_config` column.
+    pub fn allowed_tiers_wire(&self) -> String {
+        self.allowed_tiers
+            .iter()
+            .map(|t| t.as_str())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Parse the `allowed_model_tiers` column. Unknown entries are dropped;
+    /// an empty result means "all tiers" so a bad edit can't disable AI
+    /// replies outright.
+    pub fn parse_allowed_tiers(s: &str) -> Vec<crate::types::ModelTier> {
+        let tiers: Vec<_> = crate::types::ModelTier::ALL
+            .iter()
+            .copied()
+            .filter(|t| s.split(',').any(|w| w.trim() == t.as_str()))
+            .collect();
+        if tiers.is_empty() {
+            crate::types::ModelTier::ALL.to_vec()
+        } else {
+            tiers
+        }
+    }
+
     /// Look up the stored amount for a concept-currency pair. The unit
     /// (minor vs milli-minor) is determined by `concept.is_milli()`.
     pub fn amount(&self, concept: PricingConcept, currency_code: &str) -> Option<i64> {
@@ -1176,13 +1830,46 @@ pub async fn get_pricing(db: &D1Database) -> Pricing {
 
     // Currency-agnostic singleton.
     if let Ok(Some(row)) = db
-        .prepare("SELECT email_pack_size FROM pricing_config WHERE id = 1")
+        .prepare(
+            "SELECT email_pack_size, fast_reply_credits, quality_reply_credits, \
+             allowed_model_tiers, fast_input_rate, fast_output_rate, quality_input_rate, \
+             quality_output_rate, embedding_input_rate, tokens_per_credit \
+             FROM pricing_config WHERE id = 1",
+        )
         .first::<serde_json::Value>(None)
         .await
     {
         if let Some(n) = row.get("email_pack_size").and_then(|v| v.as_i64()) {
             p.email_pack_size = n;
         }
+        if let Some(n) = row.get("fast_reply_credits").and_then(|v| v.as_i64()) {
+            p.fast_reply_credits = n;
+        }
+        if let Some(n) = row.get("quality_reply_credits").and_then(|v| v.as_i64()) {
+            p.quality_reply_credits = n;
+        }
+        if let Some(s) = row.get("allowed_model_tiers").and_then(|v| v.as_str()) {
+            p.allowed_tiers = Pricing::parse_allowed_tiers(s);
+        }
+        let n = |k: &str| row.get(k).and_then(|v| v.as_i64());
+        if let Some(v) = n("fast_input_rate") {
+            p.fast_rate.input = v;
+        }
+        if let Some(v) = n("fast_output_rate") {
+            p.fast_rate.output = v;
+        }
+        if let Some(v) = n("quality_input_rate") {
+            p.quality_rate.input = v;
+        }
+        if let Some(v) = n("quality_output_rate") {
+            p.quality_rate.output = v;
+        }
+        if let Some(v) = n("embedding_input_rate") {
+            p.embedding_rate.input = v;
+        }
+        if let Some(v) = n("tokens_per_credit") {
+            p.tokens_per_credit = v;
+        }
     }
 
     // Per-currency amounts. We treat the seeded defaults as a fallback so a
@@ -1244,15 +1931,37 @@ pub async fn delete_pricing_currency(db: &D1Database, currency_code: &str) -> Re
     Ok(())
 }
 
-/// Persist the currency-agnostic settings (just `email_pack_size` for now).
-pub async fn update_pricing_config(db: &D1Database, email_pack_size: i64) -> Result<()> {
+/// Persist the currency-agnostic settings: pack size, per-tier reply
+/// credit costs, the allowed model tiers, token cost rates and the
+/// per-token credit size.
+pub async fn update_pricing_config(db: &D1Database, p: &Pricing) -> Result<()> {
     db.prepare(
         "UPDATE pricing_config SET \
            email_pack_size = ?, \
+           fast_reply_credits = ?, \
+           quality_reply_credits = ?, \
+           allowed_model_tiers = ?, \
+           fast_input_rate = ?, \
+           fast_output_rate = ?, \
+           quality_input_rate = ?, \
+           quality_output_rate = ?, \
+           embedding_input_rate = ?, \
+           tokens_per_credit = ?, \
            updated_at = datetime('now') \
          WHERE id = 1",
     )
-    .bind(&[JsValue::from_f64(email_pack_size as f64)])?
+    .bind(&[
+        JsValue::from_f64(p.email_pack_size as f64),
+        JsValue::from_f64(p.fast_reply_credits as f64),
+        JsValue::from_f64(p.quality_reply_credits as f64),
+        p.allowed_tiers_wire().as_str().into(),
+        JsValue::from_f64(p.fast_rate.input as f64),
+        JsValue::from_f64(p.fast_rate.output as f64),
+        JsValue::from_f64(p.quality_rate.input as f64),
+        JsValue::from_f64(p.quality_rate.output as f64),
+        JsValue::from_f64(p.embedding_rate.input as f64),
+        JsValue::from_f64(p.tokens_per_credit as f64),
+    ])?
     .run()
     .await?;
     Ok(())
@@ -1383,3 +2092,33 @@ pub async fn record_scheduled_grant_run(
     .await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> rusqlite::Connection {
+        let db = rusqlite::Connection::open_in_memory().unwrap();
+        db.execute_batch(include_str!("../migrations/0001_create_schema.sql"))
+            .unwrap();
+        db
+    }
+
+    #[test]
+    fn lead_form_submission_insert_matches_schema() {
+        let db = schema();
+        for (id, message, mode) in [("s1", "Hi there!", "static"), ("s2", "", "suppressed")] {
+            db.execute(
+                INSERT_LEAD_FORM_SUBMISSION,
+                rusqlite::params![id, "form1", "+919800000000", "wa1", message, mode, "t1"],
+            )
+            .unwrap();
+        }
+        let count: i64 = db
+            .query_row("SELECT COUNT(*) FROM lead_form_submissions", [], |r| {
+                r.get(0)
+            })
+            .unwrap();
+        assert_eq!(count, 2);
+    }
+}
@@ -0,0 +1,505 @@
+//! Team members: people the owner lets into the admin pages without
+//! sharing the owner's login.
+//!
+//! The owner invites an email address with a role (`MemberRole`). The
+//! invite email links to `/auth/join`, which remembers the invite in a
+//! cookie and sends the invitee to sign in; the sign-in callback accepts it
+//! when the signed-in address matches. From then on that address signs in
+//! to this tenant as the member (unless it owns a tenant of its own).
+//!
+//! Sessions stay keyed to the tenant; a member's session also names the
+//! member (`storage::save_session_member`), and every admin request looks
+//! the member up again, so revoking one (deleting the row) signs them out
+//! on their next click. `required_role` is the single table of which role
+//! each admin route needs; the `handle_admin` dispatcher enforces it.
+
+use worker::*;
+
+use crate::email::send::OutboundEmail;
+use crate::helpers::{generate_id, generate_token, html_escape};
+use crate::types::MemberRole;
+
+/// Days an invite link works for.
+pub const INVITE_TTL_DAYS: u32 = 7;
+
+/// Members per tenant, pending invites included.
+pub const MAX_MEMBERS: usize = 25;
+
+/// One `tenant_members` row.
+#[derive(Debug, Clone)]
+pub struct Member {
+    pub id: String,
+    pub tenant_id: String,
+    pub email: String,
+    pub role: MemberRole,
+    /// Set until the invite is accepted.
+    pub invite_token: Option<String>,
+    pub invited_by: String,
+    pub created_at: String,
+    pub accepted_at: Option<String>,
+}
+
+impl Member {
+    fn from_row(row: &serde_json::Value) -> Self {
+        let s = |k: &str| {
+            row.get(k)
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string()
+        };
+        let opt = |k: &str| row.get(k).and_then(|v| v.as_str()).map(str::to_string);
+        Member {
+            id: s("id"),
+            tenant_id: s("tenant_id"),
+            email: s("email"),
+            role: MemberRole::from_wire(&s("role")).unwrap_or(MemberRole::Viewer),
+            invite_token: opt("invite_token"),
+            invited_by: s("invited_by"),
+            created_at: s("created_at"),
+            accepted_at: opt("accepted_at"),
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.accepted_at.is_none()
+    }
+}
+
+/// Whoever is signed in to the admin pages.
+#[derive(Debug, Clone)]
+pub struct Actor {
+    /// `None` for the owner.
+    pub member_id: Option<String>,
+    /// The member's or agency owner's address; `None` for the owner, whose
+    /// address is `Tenant.email`.
+    pub email: Option<String>,
+    /// Set when an agency's owner switched in to a client (`agency`).
+    pub agency_id: Option<String>,
+    pub role: MemberRole,
+}
+
+impl Actor {
+    pub fn owner() -> Self {
+        Actor {
+            member_id: None,
+            email: None,
+            agency_id: None,
+            role: MemberRole::Owner,
+        }
+    }
+
+    /// An agency's owner acting for a client. They run it as an Admin:
+    /// billing, exports, the team, sign-in methods and deleting the account
+    /// stay with the client's owner.
+    pub fn agency(agency_id: &str, email: &str) -> Self {
+        Actor {
+            member_id: None,
+            email: Some(email.to_string()),
+            agency_id: Some(agency_id.to_string()),
+            role: MemberRole::Admin,
+        }
+    }
+
+    pub fn member(member: &Member) -> Self {
+        Actor {
+            member_id: Some(member.id.clone()),
+            email: Some(member.email.clone()),
+            agency_id: None,
+            role: member.role,
+        }
+    }
+
+    /// The tenant's own owner, not a member or an agency acting for it.
+    pub fn is_owner(&self) -> bool {
+        self.role == MemberRole::Owner && self.member_id.is_none() && self.agency_id.is_none()
+    }
+
+    /// Whose CSRF token the session uses (`storage::save_csrf_token`):
+    /// `None` for the owner's.
+    pub fn csrf_scope(&self) -> Option<String> {
+        match (&self.member_id, &self.agency_id) {
+            (Some(member_id), _) => Some(member_id.clone()),
+            (None, Some(agency_id)) => Some(format!("agency-{agency_id}")),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Routes only the owner can use, and their subpaths.
+const OWNER_ROUTES: &[&str] = &[
+    "/admin/team",
+    "/admin/billing",
+    "/admin/export",
+    "/admin/delete-account",
+];
+
+/// Pages admins can open but others can't: they show the owner's sign-in
+/// links or start connecting a channel.
+const ADMIN_PAGES: &[&str] = &[
+    "/admin/settings",
+    "/admin/whatsapp/new",
+    "/admin/whatsapp/manual",
+    "/admin/discord/install",
+];
+
+fn under(path: &str, route: &str) -> bool {
+    path == route
+        || path
+            .strip_prefix(route)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// The least role that may make this request. `mutating` is true for
+/// POST, PUT and DELETE.
+pub fn required_role(path: &str, mutating: bool) -> MemberRole {
+    if OWNER_ROUTES.iter().any(|r| under(path, r)) {
+        return MemberRole::Owner;
+    }
+    // The agency overview and the tenant switcher act for the login, not
+    // the current tenant; their handler checks what the login may do.
+    if under(path, "/admin/agency") {
+        return MemberRole::Viewer;
+    }
+    if !mutating {
+        return if ADMIN_PAGES.iter().any(|r| under(path, r)) {
+            MemberRole::Admin
+        } else {
+            MemberRole::Viewer
+        };
+    }
+    if path == "/admin/notifications/read-all" {
+        return MemberRole::Viewer;
+    }
+    // Deciding drafts and replying to a conversation.
+    let inbox_reply = path
+        .strip_prefix("/admin/inbox/")
+        .is_some_and(|rest| rest.ends_with("/reply"));
+    if path.starts_with("/admin/approvals/") || inbox_reply {
+        return MemberRole::Approver;
+    }
+    MemberRole::Admin
+}
+
+/// Lowercased and trimmed; how addresses are stored and compared.
+pub fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+pub async fn list(db: &D1Database, tenant_id: &str) -> Result<Vec<Member>> {
+    let rows = db
+        .prepare("SELECT * FROM tenant_members WHERE tenant_id = ?1 ORDER BY created_at, email")
+        .bind(&[tenant_id.into()])?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    Ok(rows.iter().map(Member::from_row).collect())
+}
+
+pub async fn get(db: &D1Database, tenant_id: &str, id: &str) -> Result<Option<Member>> {
+    let row = db
+        .prepare("SELECT * FROM tenant_members WHERE tenant_id = ?1 AND id = ?2")
+        .bind(&[tenant_id.into(), id.into()])?
+        .first::<serde_json::Value>(None)
+        .await?;
+    Ok(row.as_ref().map(Member::from_row))
+}
+
+/// Record an invite and return it with its token. `Ok(None)` when the
+/// address is already a member or invited.
+pub async fn invite(
+    db: &D1Database,
+    tenant_id: &str,
+    email: &str,
+    role: MemberRole,
+    invited_by: &str,
+) -> Result<Option<Member>> {
+    let member = Member {
+        id: generate_id(),
+        tenant_id: tenant_id.to_string(),
+        email: normalize_email(email),
+        role,
+        invite_token: Some(generate_token()?),
+        invited_by: invited_by.to_string(),
+        created_at: String::new(),
+        accepted_at: None,
+    };
+    let result = db
+        .prepare(
+            "INSERT OR IGNORE INTO tenant_members (id, tenant_id, email, role, invite_token, invited_by)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )
+        .bind(&[
+            member.id.as_str().into(),
+            tenant_id.into(),
+            member.email.as_str().into(),
+            role.as_str().into(),
+            member.invite_token.as_deref().unwrap_or_default().into(),
+            invited_by.into(),
+        ])?
+        .run()
+        .await?;
+    let inserted = result
+        .meta()?
+        .and_then(|m| m.changes)
+        .is_some_and(|n| n > 0);
+    Ok(inserted.then_some(member))
+}
+
+/// The outstanding invite behind `token`, if it hasn't expired.
+pub async fn find_invite(db: &D1Database, token: &str) -> Result<Option<Member>> {
+    let row = db
+        .prepare(
+            "SELECT * FROM tenant_members
+             WHERE invite_token = ?1 AND accepted_at IS NULL
+               AND created_at > datetime('now', ?2)",
+        )
+        .bind(&[token.into(), format!("-{INVITE_TTL_DAYS} days").into()])?
+        .first::<serde_json::Value>(None)
+        .await?;
+    Ok(row.as_ref().map(Member::from_row))
+}
+
+/// Mark the invite accepted; the token stops working.
+pub async fn accept(db: &D1Database, member_id: &str) -> Result<()> {
+    db.prepare(
+        "UPDATE tenant_members SET accepted_at = datetime('now'), invite_token = NULL
+         WHERE id = ?1",
+    )
+    .bind(&[member_id.into()])?
+    .run()
+    .await?;
+    Ok(())
+}
+
+/// A fresh link and another week for a pending invite. Returns the new
+/// token, or `None` if the invite was accepted or removed meanwhile.
+pub async fn renew_invite(db: &D1Database, tenant_id: &str, id: &str) -> Result<Option<String>> {
+    let token = generate_token()?;
+    let result = db
+        .prepare(
+            "UPDATE tenant_members SET invite_token = ?3, created_at = datetime('now')
+             WHERE tenant_id = ?1 AND id = ?2 AND accepted_at IS NULL",
+        )
+        .bind(&[tenant_id.into(), id.into(), token.as_str().into()])?
+        .run()
+        .await?;
+    let renewed = result
+        .meta()?
+        .and_then(|m| m.changes)
+        .is_some_and(|n| n > 0);
+    Ok(renewed.then_some(token))
+}
+
+/// The accepted membership an address signs in to, if any. The oldest
+/// wins when an address belongs to several teams.
+pub async fn active_by_email(db: &D1Database, email: &str) -> Result<Option<Member>> {
+    let row = db
+        .prepare(
+            "SELECT * FROM tenant_members WHERE email = ?1 AND accepted_at IS NOT NULL
+             ORDER BY accepted_at LIMIT 1",
+        )
+        .bind(&[normalize_email(email).into()])?
+        .first::<serde_json::Value>(None)
+        .await?;
+    Ok(row.as_ref().map(Member::from_row))
+}
+
+pub async fn set_role(db: &D1Database, tenant_id: &str, id: &str, role: MemberRole) -> Result<()> {
+    db.prepare("UPDATE tenant_members SET role = ?3 WHERE tenant_id = ?1 AND id = ?2")
+        .bind(&[tenant_id.into(), id.into(), role.as_str().into()])?
+        .run()
+        .await?;
+    Ok(())
+}
+
+/// Remove a member or withdraw an invite. Their sessions stop working on
+/// the next request.
+pub async fn revoke(db: &D1Database, tenant_id: &str, id: &str) -> Result<()> {
+    db.prepare("DELETE FROM tenant_members WHERE tenant_id = ?1 AND id = ?2")
+        .bind(&[tenant_id.into(), id.into()])?
+        .run()
+        .await?;
+    Ok(())
+}
+
+pub fn invite_url(base_url: &str, token: &str) -> String {
+    format!("{base_url}/auth/join?token={token}")
+}
+
+pub fn build_invite_email(
+    member: &Member,
+    business: &str,
+    url: &str,
+    from_addr: &str,
+) -> OutboundEmail {
+    let role = member.role.label();
+    let invited_by = &member.invited_by;
+    let text = format!(
+        "{invited_by} invited you to help run {business} on Concierge as {article} {role} ({what}).\n\n\
+         Accept: {url}\n\n\
+         Sign in with Google or Facebook as {email}. The link works for {INVITE_TTL_DAYS} days.\n",
+        article = article(role),
+        what = member.role.description().to_lowercase(),
+        email = member.email,
+    );
+    let html = format!(
+        r#"<!doctype html>
+<html><body style="font-family:-apple-system,BlinkMacSystemFont,sans-serif;max-width:640px;margin:0 auto;padding:24px">
+<h1 style="font-size:20px;margin:0 0 12px">Join {business} on Concierge</h1>
+<p>{invited_by} invited you as {article} <strong>{role}</strong>: {what}.</p>
+<p style="margin-top:24px"><a href="{url}" style="display:inline-block;padding:8px 14px;background:#5865F2;color:#fff;border-radius:4px;text-decoration:none">Accept invite</a></p>
+<p style="color:#999;font-size:12px;margin-top:32px">Sign in with Google or Facebook as {email}. The link works for {INVITE_TTL_DAYS} days.</p>
+</body></html>"#,
+        business = html_escape(business),
+        invited_by = html_escape(invited_by),
+        article = article(role),
+        role = html_escape(role),
+        what = html_escape(&member.role.description().to_lowercase()),
+        url = html_escape(url),
+        email = html_escape(&member.email),
+    );
+    OutboundEmail {
+        from: from_addr.to_string(),
+        to: member.email.clone(),
+        subject: format!("Join {business} on Concierge"),
+        text: Some(text),
+        html: Some(html),
+        reply_to: None,
+        cc: vec![],
+        bcc: vec![],
+        headers: vec![],
+    }
+}
+
+fn article(word: &str) -> &'static str {
+    if word.starts_with(['A', 'E', 'I', 'O', 'U']) {
+        "an"
+    } else {
+        "a"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn owner_routes_need_the_owner() {
+        for path in [
+            "/admin/team",
+            "/admin/team/abc/role",
+            "/admin/billing",
+            "/admin/billing/checkout",
+            "/admin/export",
+            "/admin/delete-account",
+        ] {
+            assert_eq!(required_role(path, false), MemberRole::Owner, "{path}");
+            assert_eq!(required_role(path, true), MemberRole::Owner, "{path}");
+        }
+        // A prefix that isn't a path segment doesn't count.
+        assert_eq!(required_role("/admin/teamwork", false), MemberRole::Viewer);
+    }
+
+    #[test]
+    fn agencies_run_clients_as_admins() {
+        let agency = Actor::agency("a1", "me@agency.example");
+        assert!(!agency.is_owner());
+        for path in [
+            "/admin/team/abc",
+            "/admin/billing",
+            "/admin/export",
+            "/admin/delete-account",
+        ] {
+            assert!(!agency.role.allows(required_role(path, true)), "{path}");
+        }
+        assert!(agency.role.allows(required_role("/admin/persona", true)));
+        assert!(agency
+            .role
+            .allows(required_role("/admin/whatsapp/new", false)));
+        assert!(Actor::owner().is_owner());
+    }
+
+    #[test]
+    fn approvers_decide_drafts_and_reply() {
+        assert_eq!(
+            required_role("/admin/approvals/abc/approve", true),
+            MemberRole::Approver
+        );
+        assert_eq!(
+            required_role("/admin/inbox/abc/reply", true),
+            MemberRole::Approver
+        );
+        assert_eq!(
+            required_role("/admin/inbox/settings", true),
+            MemberRole::Admin
+        );
+        assert_eq!(required_role("/admin/approvals", false), MemberRole::Viewer);
+    }
+
+    #[test]
+    fn other_changes_need_an_admin() {
+        assert_eq!(required_role("/admin/persona", true), MemberRole::Admin);
+        assert_eq!(required_role("/admin/rules/x", true), MemberRole::Admin);
+        assert_eq!(required_role("/admin/settings", false), MemberRole::Admin);
+        assert_eq!(
+            required_role("/admin/discord/install", false),
+            MemberRole::Admin
+        );
+        assert_eq!(
+            required_role("/admin/messages.csv", false),
+            MemberRole::Viewer
+        );
+        assert_eq!(
+            required_role("/admin/notifications/read-all", true),
+            MemberRole::Viewer
+        );
+        assert_eq!(
+            required_role("/admin/notifications/delivery", true),
+            MemberRole::Admin
+        );
+    }
+
+    #[test]
+    fn agency_routes_are_checked_by_their_handler() {
+        assert_eq!(required_role("/admin/agency", false), MemberRole::Viewer);
+        assert_eq!(
+            required_role("/admin/agency/switch", true),
+            MemberRole::Viewer
+        );
+        assert_eq!(required_role("/admin/agencyx", true), MemberRole::Admin);
+    }
+
+    #[test]
+    fn csrf_scope_separates_owner_members_and_agencies() {
+        assert_eq!(Actor::owner().csrf_scope(), None);
+        assert_eq!(
+            Actor::agency("a1", "me@agency.example").csrf_scope(),
+            Some("agency-a1".to_string())
+        );
+    }
+
+    #[test]
+    fn invite_email_names_the_role_and_address() {
+        let member = Member {
+            id: "m1".into(),
+            tenant_id: "t1".into(),
+            email: "desk@example.com".into(),
+            role: MemberRole::Approver,
+            invite_token: Some("tok".into()),
+            invited_by: "owner@example.com".into(),
+            created_at: String::new(),
+            accepted_at: None,
+        };
+        let url = invite_url("https://example.com", "tok");
+        let email = build_invite_email(&member, "Salon <Rose>", &url, "noreply@example.com");
+        assert_eq!(email.to, "desk@example.com");
+        assert_eq!(email.subject, "Join Salon <Rose> on Concierge");
+        let html = email.html.unwrap();
+        assert!(html.contains("Salon &lt;Rose&gt;"));
+        assert!(html.contains(r#"href="https://example.com/auth/join?token=tok""#));
+        assert!(email
+            .text
+            .unwrap()
+            .contains("as an Approver (approve drafts and reply from the inbox)"));
+    }
+}
@@ -188,17 +188,42 @@ btn?.addEventListener('click', async () => {{
     base_html(&t(locale, "admin-login-title"), &content, locale)
 }
 
-pub fn admin_settings_html(
-    tenant: &Tenant,
-    base_url: &str,
-    google_client_id: &str,
-    meta_app_id: &str,
-    wa: &[WhatsAppAccount],
-    ig: &[InstagramAccount],
-    discord: Option<&DiscordConfig>,
-    tenant_id: &str,
-    locale: &Locale,
-) -> String {
+/// What the Settings page shows: the tenant, its connected channels and
+/// the viewer's access.
+pub struct SettingsProps<'a> {
+    pub tenant: &'a Tenant,
+    pub tenant_id: &'a str,
+    pub base_url: &'a str,
+    pub google_client_id: &'a str,
+    pub meta_app_id: &'a str,
+    pub wa: &'a [WhatsAppAccount],
+    pub ig: &'a [InstagramAccount],
+    pub discord: Option<&'a DiscordConfig>,
+    /// The viewer's role; hides what they can't change.
+    pub role: MemberRole,
+    /// The managing agency's name, for the owner's "Managed by" card.
+    pub managed_by: Option<&'a str>,
+    /// The managing agency pays for this account's replies.
+    pub agency_pays: bool,
+    /// Agencies asking to manage this account, shown to its owner.
+    pub agency_invites: &'a [crate::agency::Invite],
+}
+
+pub fn admin_settings_html(p: &SettingsProps, locale: &Locale) -> String {
+    let SettingsProps {
+        tenant,
+        tenant_id,
+        base_url,
+        google_client_id,
+        meta_app_id,
+        wa,
+        ig,
+        discord,
+        role,
+        managed_by,
+        agency_pays,
+        agency_invites,
+    } = *p;
     let has_google = !tenant.email.is_empty();
     let has_facebook = tenant.facebook_id.is_some();
 
@@ -364,10 +389,93 @@ pub fn admin_settings_html(
         }
     };
 
-    let content = format!(
-        "<div class=\"page-pad\">
-        <h1 class=\"display-sm m-0 mb-16\">{h1}</h1>
-        <div class=\"card p-22\">
+    // Sign-in methods, the team, exports and deletion are the owner's;
+    // admins see the rest.
+    let owner = role == MemberRole::Owner;
+    let agency_card = match managed_by {
+        Some(agency) => format!(
+            "<div class=\"card p-22\">
+            <h2>{h2}</h2>
+            <p class=\"muted mb-16\">{lead}</p>
+            <div class=\"row gap-8\">
+                {pay_own}
+                <button class=\"btn ghost\" hx-delete=\"{base_url}/admin/agency/manager\" hx-confirm=\"{confirm}\">{cta}</button>
+            </div>
+        </div>",
+            pay_own = if agency_pays {
+                format!(
+                    "<button class=\"btn ghost\" hx-post=\"{base_url}/admin/agency/manager/billing\" hx-confirm=\"{confirm}\">{cta}</button>",
+                    confirm = html_escape(&t(locale, "admin-settings-agency-pay-own-confirm")),
+                    cta = t(locale, "admin-settings-agency-pay-own-cta"),
+                )
+            } else {
+                String::new()
+            },
+            h2 = t(locale, "admin-settings-agency-h2"),
+            lead = html_escape(&crate::i18n::t_args(
+                locale,
+                "admin-settings-agency-managed-lead",
+                &[("agency", agency)]
+            )),
+            confirm = html_escape(&crate::i18n::t_args(
+                locale,
+                "admin-settings-agency-managed-confirm",
+                &[("agency", agency)]
+            )),
+            cta = t(locale, "admin-settings-agency-managed-cta"),
+        ),
+        None => format!(
+            "<div class=\"card p-22\">
+            <h2>{h2}</h2>
+            <p class=\"muted mb-16\">{lead}</p>
+            <a href=\"{base_url}/admin/agency\" class=\"btn ghost\">{cta}</a>
+        </div>",
+            h2 = t(locale, "admin-settings-agency-h2"),
+            lead = t(locale, "admin-settings-agency-lead"),
+            cta = t(locale, "admin-settings-agency-cta"),
+        ),
+    };
+    let invite_cards: String = agency_invites
+        .iter()
+        .map(|invite| {
+            let args = [
+                ("agency", invite.agency.as_str()),
+                ("business", invite.name.as_str()),
+            ];
+            let billing = match invite.billing {
+                ClientBilling::Agency => "admin-settings-agency-invite-billing-agency",
+                ClientBilling::Client => "admin-settings-agency-invite-billing-client",
+            };
+            format!(
+                "<div class=\"card p-22\">
+            <h2>{h2}</h2>
+            <p class=\"muted mb-16\">{lead} {billing}</p>
+            <div class=\"row gap-8\">
+                <button class=\"btn primary\" hx-post=\"{base_url}/admin/agency/invites/{id}/accept\" hx-confirm=\"{confirm}\">{accept}</button>
+                <button class=\"btn ghost\" hx-delete=\"{base_url}/admin/agency/invites/{id}\">{decline}</button>
+            </div>
+        </div>",
+                h2 = t(locale, "admin-settings-agency-invite-h2"),
+                lead = html_escape(&crate::i18n::t_args(
+                    locale,
+                    "admin-settings-agency-invite-lead",
+                    &args
+                )),
+                billing = t(locale, billing),
+                id = html_escape(&invite.id),
+                confirm = html_escape(&crate::i18n::t_args(
+                    locale,
+                    "admin-settings-agency-invite-confirm",
+                    &args
+                )),
+                accept = t(locale, "admin-settings-agency-invite-accept"),
+                decline = t(locale, "admin-settings-agency-invite-decline"),
+            )
+        })
+        .collect();
+    let linked_section = if owner {
+        format!(
+            "<div class=\"card p-22\">
             <h2>{linked_h2}</h2>
             <p class=\"muted mb-16\">{linked_lead}</p>
             <div id=\"linked-providers\" role=\"region\" aria-label=\"{linked_region}\">
@@ -377,7 +485,68 @@ pub fn admin_settings_html(
                 </table></div>
             </div>
         </div>
+        <div class=\"card p-22\">
+            <h2>{team_h2}</h2>
+            <p class=\"muted mb-16\">{team_lead}</p>
+            <a href=\"{base_url}/admin/team\" class=\"btn ghost\">{team_cta}</a>
+        </div>
+        {agency_card}",
+            linked_h2 = t(locale, "admin-settings-linked-h2"),
+            linked_lead = t(locale, "admin-settings-linked-lead"),
+            linked_region = html_escape(&t(locale, "admin-settings-linked-region")),
+            th_provider = t(locale, "admin-settings-th-provider"),
+            th_details = t(locale, "admin-settings-th-details"),
+            team_h2 = t(locale, "admin-settings-team-h2"),
+            team_lead = t(locale, "admin-settings-team-lead"),
+            team_cta = t(locale, "admin-settings-team-cta"),
+        )
+    } else {
+        String::new()
+    };
+    let owner_section = if owner {
+        format!(
+            "<div class=\"card p-22\">
+            <h2>{export_h2}</h2>
+            <p class=\"muted mb-16\">{export_lead}</p>
+            <button class=\"btn ghost\" hx-post=\"{base_url}/admin/export\" hx-target=\"{hash}exports\" hx-swap=\"innerHTML\">{export_cta}</button>
+            <div id=\"exports\" class=\"mt-16\" role=\"status\" aria-live=\"polite\" hx-get=\"{base_url}/admin/export\" hx-trigger=\"load\" hx-swap=\"innerHTML\"></div>
+        </div>
+        <div class=\"card card-warn p-22\">
+            <h2 class=\"text-warn\">{delete_h2}</h2>
+            <p class=\"muted mb-16\">{delete_lead}</p>
+            <button class=\"btn\" style=\"background:var(--warn);border-color:var(--warn);color:#fff\"
+                    hx-delete=\"{base_url}/admin/delete-account\"
+                    hx-confirm=\"{delete_confirm}\"
+                    >{delete_cta}</button>
+        </div>",
+            hash = HASH,
+            export_h2 = t(locale, "admin-settings-export-h2"),
+            export_lead = crate::i18n::t_args(
+                locale,
+                "admin-settings-export-lead",
+                &[("days", &crate::exports::EXPORT_TTL_DAYS.to_string())]
+            ),
+            export_cta = t(locale, "admin-settings-export-cta"),
+            delete_h2 = t(locale, "admin-settings-delete-h2"),
+            delete_lead = t(locale, "admin-settings-delete-lead"),
+            delete_confirm = html_escape(&t(locale, "admin-settings-delete-confirm")),
+            delete_cta = t(locale, "admin-settings-delete-cta"),
+        )
+    } else {
+        String::new()
+    };
+
+    let content = format!(
+        "<div class=\"page-pad\">
+        <h1 class=\"display-sm m-0 mb-16\">{h1}</h1>
+        {invite_cards}
+        {linked_section}
         {integrations_section}
+        <div class=\"card p-22\">
+            <h2>{brands_h2}</h2>
+            <p class=\"muted mb-16\">{brands_lead}</p>
+            <a href=\"{base_url}/admin/brands\" class=\"btn ghost\">{brands_cta}</a>
+        </div>
         <div class=\"card p-22\" hx-ext=\"json-enc\">
             <h2>{currency_h2}</h2>
             <p class=\"muted mb-16\">{currency_lead}</p>
@@ -392,49 +561,109 @@ pub fn admin_settings_html(
             </form>
             <div id=\"currency-toast\" class=\"mt-8\" role=\"status\" aria-live=\"polite\" aria-atomic=\"true\"></div>
         </div>
+        <div class=\"card p-22\">
+            <h2>{tools_h2}</h2>
+            <p class=\"muted mb-16\">{tools_lead}</p>
+            <a href=\"{base_url}/admin/tools\" class=\"btn ghost\">{tools_cta}</a>
+        </div>
+        <div class=\"card p-22\">
+            <h2>{guardrails_h2}</h2>
+            <p class=\"muted mb-16\">{guardrails_lead}</p>
+            <a href=\"{base_url}/admin/guardrails\" class=\"btn ghost\">{guardrails_cta}</a>
+        </div>
         <div class=\"card p-22\">
             <h2>{session_h2}</h2>
             <a href=\"{base_url}/auth/logout\" class=\"btn ghost\">{signout}</a>
         </div>
-        <div class=\"card card-warn p-22\">
-            <h2 class=\"text-warn\">{delete_h2}</h2>
-            <p class=\"muted mb-16\">{delete_lead}</p>
-            <button class=\"btn\" style=\"background:var(--warn);border-color:var(--warn);color:#fff\"
-                    hx-delete=\"{base_url}/admin/delete-account\"
-                    hx-confirm=\"{delete_confirm}\"
-                    >{delete_cta}</button>
-        </div>
+        {owner_section}
         </div>",
         base_url = base_url,
         hash = HASH,
-        google_row = google_row,
-        facebook_row = facebook_row,
+        linked_section = linked_section,
         integrations_section = integrations_section,
+        owner_section = owner_section,
         inr_sel = if tenant.currency == crate::locale::Currency::Inr { " selected" } else { "" },
         usd_sel = if tenant.currency == crate::locale::Currency::Usd { " selected" } else { "" },
         h1 = t(locale, "admin-settings-h1"),
-        linked_h2 = t(locale, "admin-settings-linked-h2"),
-        linked_lead = t(locale, "admin-settings-linked-lead"),
-        linked_region = html_escape(&t(locale, "admin-settings-linked-region")),
-        th_provider = t(locale, "admin-settings-th-provider"),
-        th_details = t(locale, "admin-settings-th-details"),
         currency_h2 = t(locale, "admin-settings-currency-h2"),
         currency_lead = t(locale, "admin-settings-currency-lead"),
         inr_label = t(locale, "admin-settings-currency-inr"),
         usd_label = t(locale, "admin-settings-currency-usd"),
         save = t(locale, "admin-save"),
+        brands_h2 = t(locale, "admin-settings-brands-h2"),
+        brands_lead = t(locale, "admin-settings-brands-lead"),
+        brands_cta = t(locale, "admin-settings-brands-cta"),
+        tools_h2 = t(locale, "admin-settings-tools-h2"),
+        tools_lead = t(locale, "admin-settings-tools-lead"),
+        tools_cta = t(locale, "admin-settings-tools-cta"),
+        guardrails_h2 = t(locale, "admin-settings-guardrails-h2"),
+        guardrails_lead = t(locale, "admin-settings-guardrails-lead"),
+        guardrails_cta = t(locale, "admin-settings-guardrails-cta"),
         session_h2 = t(locale, "admin-settings-session-h2"),
         signout = t(locale, "admin-settings-signout"),
-        delete_h2 = t(locale, "admin-settings-delete-h2"),
-        delete_lead = t(locale, "admin-settings-delete-lead"),
-        delete_confirm = html_escape(&t(locale, "admin-settings-delete-confirm")),
-        delete_cta = t(locale, "admin-settings-delete-cta"),
     );
 
     let page = super::base::app_shell(&content, "Settings", base_url, locale);
     base_html(&t(locale, "admin-settings-title"), &page, locale)
 }
 
+/// The settings page's export list, loaded into `#exports`.
+pub fn exports_list_html(
+    exports: &[crate::exports::TenantExport],
+    base_url: &str,
+    locale: &Locale,
+) -> String {
+    if exports.is_empty() {
+        return String::new();
+    }
+    let rows: String = exports
+        .iter()
+        .map(|e| {
+            let status = match (e.status, e.expired) {
+                (ExportStatus::Ready, false) => format!(
+                    r#"<a href="{base_url}/admin/export/{id}" class="btn sm">{download}</a> <span class="muted fs-12">{size}</span>"#,
+                    id = html_escape(&e.id),
+                    download = t(locale, "admin-settings-export-download"),
+                    size = format_size(e.size_bytes),
+                ),
+                (ExportStatus::Ready, true) => format!(
+                    r#"<span class="muted">{}</span>"#,
+                    t(locale, "admin-settings-export-expired")
+                ),
+                (ExportStatus::Pending, _) => format!(
+                    r#"<span class="chip">{}</span>"#,
+                    t(locale, "admin-settings-export-pending")
+                ),
+                (ExportStatus::Failed, _) => format!(
+                    r#"<span class="chip warn" title="{error}">{label}</span>"#,
+                    error = html_escape(e.error.as_deref().unwrap_or("")),
+                    label = t(locale, "admin-settings-export-failed"),
+                ),
+            };
+            format!(
+                r#"<tr><td class="fs-12">{when}</td><td>{status}</td></tr>"#,
+                when = html_escape(e.created_at.get(..16).unwrap_or(&e.created_at)),
+            )
+        })
+        .collect();
+    format!(
+        r#"<div class="table-wrap"><table>
+    <thead><tr><th scope="col">{th_requested}</th><th scope="col">{th_archive}</th></tr></thead>
+    <tbody>{rows}</tbody>
+</table></div>"#,
+        th_requested = t(locale, "admin-settings-export-th-requested"),
+        th_archive = t(locale, "admin-settings-export-th-archive"),
+    )
+}
+
+fn format_size(bytes: i64) -> String {
+    match bytes {
+        b if b >= 1024 * 1024 => format!("{:.1} MB", b as f64 / (1024.0 * 1024.0)),
+        b if b >= 1024 => format!("{} KB", b / 1024),
+        b => format!("{b} B"),
+    }
+}
+
 pub fn admin_dashboard_html(
     whatsapp_accounts: &[WhatsAppAccount],
     instagram_accounts: &[InstagramAccount],
@@ -591,6 +820,10 @@ pub fn admin_dashboard_html(
         </div>
       </div>
     </div>
+    <div id="activity" class="card p-22 mt-16" hx-get="{base_url}/admin/analytics" hx-trigger="load" hx-swap="outerHTML">
+      <div class="eyebrow">{activity_eyebrow}</div>
+      <p class="muted m-0 mt-4">{activity_loading}</p>
+    </div>
     <div class="card p-22 mt-16{email_highlight_cls}">
       <div class="between mb-12">
         <div>
@@ -645,6 +878,8 @@ pub fn admin_dashboard_html(
         stat_lf = t(locale, "admin-dashboard-stat-leads"),
         stat_credits = t(locale, "admin-dashboard-stat-credits"),
         email_eyebrow = t(locale, "admin-dashboard-email-eyebrow"),
+        activity_eyebrow = t(locale, "admin-dashboard-activity-eyebrow"),
+        activity_loading = t(locale, "admin-dashboard-activity-loading"),
     );
 
     let page = app_shell(&content, "Overview", base_url, locale);
@@ -912,6 +1147,7 @@ pub fn admin_whatsapp_edit_html(
                     <input id=\"wa-wait\" type=\"range\" min=\"0\" max=\"30\" step=\"1\" name=\"wait_seconds\" value=\"{wait}\" oninput=\"this.previousElementSibling.textContent='{wait_prefix} (' + this.value + 's)'\">
                     <small class=\"muted fs-12\">{wait_help}</small>
                 </div>
+                {buffer_fields}
                 <div style=\"display: flex; justify-content: flex-end; gap: 0.5rem;\">
                     <button type=\"submit\" class=\"btn\">{save}</button>
                 </div>
@@ -929,6 +1165,7 @@ pub fn admin_whatsapp_edit_html(
         mode_ai_sel = mode_ai_sel,
         hash = HASH,
         wait = account.auto_reply.wait_seconds,
+        buffer_fields = reply_buffer_fields_html(&account.auto_reply, "wa", locale),
         back = t(locale, "admin-wa-edit-back"),
         h1 = t(locale, "admin-wa-edit-h1"),
         lbl_name = t(locale, "admin-wa-edit-label-name"),
@@ -1086,6 +1323,7 @@ pub fn admin_instagram_edit_html(
                     <input id=\"ig-wait\" type=\"range\" min=\"0\" max=\"30\" step=\"1\" name=\"wait_seconds\" value=\"{wait}\" oninput=\"this.previousElementSibling.textContent='{wait_prefix} (' + this.value + 's)'\">
                     <small class=\"muted fs-12\">{wait_help}</small>
                 </div>
+                {buffer_fields}
                 <div style=\"display: flex; justify-content: flex-end;\">
                     <button type=\"submit\" class=\"btn\">{save}</button>
                 </div>
@@ -1101,6 +1339,7 @@ pub fn admin_instagram_edit_html(
         mode_ai_sel = mode_ai_sel,
         prompt = html_escape(account.auto_reply.default_text()),
         wait = account.auto_reply.wait_seconds,
+        buffer_fields = reply_buffer_fields_html(&account.auto_reply, "ig", locale),
         hash = HASH,
         back = t(locale, "admin-ig-edit-back"),
         h1 = t(locale, "admin-ig-edit-h1"),
@@ -1375,6 +1614,42 @@ pub fn admin_lead_form_edit_html(
     base_html(&t(locale, "admin-lf-edit-title"), &content, locale)
 }
 
+/// Max-wait, max-messages and flush-on-question inputs that sit under a
+/// channel's "wait before replying" control. Field names match
+/// `ReplyConfig::apply_buffer_form`.
+pub fn reply_buffer_fields_html(cfg: &ReplyConfig, id_prefix: &str, locale: &Locale) -> String {
+    format!(
+        r#"<div class="row gap-12 wrap">
+  <div class="form-group" style="min-width:180px">
+    <label for="{id_prefix}-max-wait">{max_wait_lbl}</label>
+    <input id="{id_prefix}-max-wait" class="input" name="max_wait_seconds" type="number" min="0" max="{max_wait_cap}" value="{max_wait}">
+  </div>
+  <div class="form-group" style="min-width:180px">
+    <label for="{id_prefix}-max-buffered">{max_buffered_lbl}</label>
+    <input id="{id_prefix}-max-buffered" class="input" name="max_buffered" type="number" min="0" max="{max_buffered_cap}" value="{max_buffered}">
+  </div>
+</div>
+<div class="form-group">
+  <label><input type="checkbox" name="flush_on_question" value="true"{question_checked}> {question_lbl}</label>
+  <small class="muted fs-12">{help}</small>
+</div>"#,
+        id_prefix = id_prefix,
+        max_wait = cfg.max_wait_seconds,
+        max_wait_cap = MAX_BUFFER_WAIT_SECONDS,
+        max_buffered = cfg.max_buffered,
+        max_buffered_cap = MAX_BUFFERED_MESSAGES,
+        question_checked = if cfg.flush_on_question {
+            " checked"
+        } else {
+            ""
+        },
+        max_wait_lbl = t(locale, "admin-buffer-max-wait"),
+        max_buffered_lbl = t(locale, "admin-buffer-max-messages"),
+        question_lbl = t(locale, "admin-buffer-flush-question"),
+        help = t(locale, "admin-buffer-help"),
+    )
+}
+
 pub fn admin_success_html(message: &str) -> String {
     format!(
         "<div class=\"success\" role=\"status\">{}</div>",
@@ -191,6 +191,7 @@ pub fn email_address_html(
                 <input id="email-wait" class="input" name="wait_seconds" type="number" min="0" max="120" value="{wait}">
                 <p class="muted fs-12 mt-4">{wait_help}</p>
             </div>
+            {buffer_fields}
             <div class="row gap-8">
                 <button class="btn primary" type="submit">{save}</button>
             </div>
@@ -204,6 +205,7 @@ pub fn email_address_html(
         ai_sel = if ai_selected { "selected" } else { "" },
         prompt = html_escape(addr.auto_reply.default_text()),
         wait = addr.auto_reply.wait_seconds,
+        buffer_fields = super::reply_buffer_fields_html(&addr.auto_reply, "email", locale),
         rules_prefix = t(locale, "admin-email-edit-rules-prefix"),
         rules_link = t(locale, "admin-email-edit-rules-link"),
         toggle_label = t(locale, "admin-email-edit-toggle-label"),
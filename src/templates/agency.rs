@@ -0,0 +1,213 @@
+//! `/admin/agency`: an agency's clients at a glance, with its pending
+//! invites and the form that sends one. Also the tenant switcher the app shell loads into its header.
+
+use crate::agency::{Access, ClientStatus, Health, Invite, Reach};
+use crate::helpers::html_escape;
+use crate::locale::Locale;
+use crate::types::ClientBilling;
+
+use super::base::{app_shell, base_html};
+use super::{short_date, HASH};
+
+pub fn agency_page_html(
+    agency_name: &str,
+    agency_credits: i64,
+    clients: &[ClientStatus],
+    invites: &[Invite],
+    base_url: &str,
+    locale: &Locale,
+) -> String {
+    let rows: String = if clients.is_empty() {
+        r#"<tr><td colspan="7" class="muted">No clients yet. Invite the first one below.</td></tr>"#
+            .to_string()
+    } else {
+        clients
+            .iter()
+            .map(|c| client_row_html(c, base_url))
+            .collect()
+    };
+    let invites_card = if invites.is_empty() {
+        String::new()
+    } else {
+        let rows: String = invites
+            .iter()
+            .map(|i| invite_row_html(i, base_url))
+            .collect();
+        format!(
+            r#"<div class="card p-0 mb-24" style="overflow-x:auto">
+    <table class="manage-table fs-13" style="width:100%">
+      <thead><tr><th>Invited</th><th>Billing</th><th>Sent</th><th></th></tr></thead>
+      <tbody>{rows}</tbody>
+    </table>
+  </div>"#
+        )
+    };
+    let credits_class = if agency_credits <= 0 {
+        " text-warn"
+    } else {
+        ""
+    };
+
+    let body = format!(
+        r##"<div class="page-pad">
+  <h1 class="display-sm m-0 mb-4">Agency</h1>
+  <p class="muted mb-16">Businesses {agency} runs Concierge for. Open one to work in it as an admin; the switcher in the header brings you back.</p>
+  <div class="card p-18 mb-24">
+    <div class="stat-n serif{credits_class}">{agency_credits}</div>
+    <div class="mono muted fs-11">Credits with {agency}, shared by every client set to &ldquo;Agency pays&rdquo;</div>
+  </div>
+  <div class="card p-0 mb-24" style="overflow-x:auto">
+    <table class="manage-table fs-13" style="width:100%">
+      <thead><tr><th>Client</th><th>Credits</th><th>Billing</th><th>Pending approvals</th><th>Channels</th><th>Health</th><th></th></tr></thead>
+      <tbody>{rows}</tbody>
+    </table>
+  </div>
+  {invites_card}
+  <div class="card p-22">
+    <h2 class="fs-16 m-0 mb-4">Invite a client</h2>
+    <p class="muted fs-13 mb-12">We email the business owner. Once they sign in to Concierge with that address and accept in Settings, the business shows up here; you keep access until one of you removes it.</p>
+    <form class="row gap-8" style="flex-wrap:wrap;align-items:center" hx-ext="json-enc" hx-post="{base_url}/admin/agency/clients" hx-target="{HASH}agency-toast" hx-swap="innerHTML">
+      <input class="input" name="name" required maxlength="100" placeholder="Rose Salon" aria-label="Business name" style="flex:2;min-width:180px">
+      <input class="input" type="email" name="email" required placeholder="owner@rosesalon.example" aria-label="Business owner's email" style="flex:2;min-width:220px">
+      <select class="select" name="billing" aria-label="Who pays" style="flex:1;min-width:140px">{billing}</select>
+      <button class="btn primary sm" type="submit">Invite client</button>
+    </form>
+    <div id="agency-toast" class="mt-8" role="status" aria-live="polite"></div>
+  </div>
+</div>"##,
+        agency = html_escape(agency_name),
+        billing = billing_options(ClientBilling::Agency),
+    );
+    let page = app_shell(&body, "Settings", base_url, locale);
+    base_html("Agency - Concierge", &page, locale)
+}
+
+fn billing_options(selected: ClientBilling) -> String {
+    ClientBilling::ALL
+        .iter()
+        .map(|b| {
+            let sel = if *b == selected { " selected" } else { "" };
+            format!(
+                r#"<option value="{}"{sel}>{}</option>"#,
+                b.as_str(),
+                b.label()
+            )
+        })
+        .collect()
+}
+
+fn client_row_html(status: &ClientStatus, base_url: &str) -> String {
+    let client = &status.client;
+    let id = html_escape(&client.tenant_id);
+    let credits = match client.billing {
+        ClientBilling::Agency => r#"<span class="muted">Agency</span>"#.to_string(),
+        ClientBilling::Client if status.credits <= 0 => {
+            format!(r#"<span class="text-warn">{}</span>"#, status.credits)
+        }
+        ClientBilling::Client => status.credits.to_string(),
+    };
+    let discord = if status.discord { " + Discord" } else { "" };
+    let health = status.health();
+    let chip = match health {
+        Health::Healthy => "chip ok",
+        Health::Check | Health::NeedsAttention | Health::NotConnected => "chip warn",
+    };
+    let alerts = if status.alerts > 0 {
+        format!(
+            r#"<div class="muted fs-11">{} unread notification{}</div>"#,
+            status.alerts,
+            if status.alerts == 1 { "" } else { "s" }
+        )
+    } else {
+        String::new()
+    };
+    // Only the client's owner moves replies back onto their own credits.
+    let billing = match client.billing {
+        ClientBilling::Agency => format!(
+            r#"{}<div class="muted fs-11">The client can take this back in Settings</div>"#,
+            ClientBilling::Agency.label()
+        ),
+        ClientBilling::Client => format!(
+            r#"<select class="select" name="billing" aria-label="Who pays for {name}" style="width:auto" hx-ext="json-enc" hx-post="{base_url}/admin/agency/clients/{id}/billing" hx-trigger="change" hx-confirm="Pay for {name}'s AI replies from your credits? Only they can move them back.">{options}</select>"#,
+            name = html_escape(&client.name),
+            options = billing_options(ClientBilling::Client),
+        ),
+    };
+    let open = html_escape(&serde_json::json!({ "tenant_id": client.tenant_id }).to_string());
+    format!(
+        r#"<tr id="client-{id}">
+  <td><strong>{name}</strong><div class="muted fs-11">{email}</div></td>
+  <td>{credits}</td>
+  <td>{billing}</td>
+  <td>{pending}</td>
+  <td>{channels}{discord}</td>
+  <td><span class="{chip}">{health}</span>{alerts}</td>
+  <td class="row gap-6" style="justify-content:flex-end">
+    <button class="btn sm" hx-ext="json-enc" hx-post="{base_url}/admin/agency/switch" hx-vals='{open}'>Open</button>
+    <button class="btn ghost sm text-warn" hx-delete="{base_url}/admin/agency/clients/{id}" hx-confirm="Stop managing {name}? Their account stays; you lose access to it.">Remove</button>
+  </td>
+</tr>"#,
+        name = html_escape(&client.name),
+        email = html_escape(&client.email),
+        pending = status.pending_approvals,
+        channels = status.channels,
+        health = health.label(),
+    )
+}
+
+fn invite_row_html(invite: &Invite, base_url: &str) -> String {
+    format!(
+        r#"<tr id="invite-{id}">
+  <td><strong>{name}</strong><div class="muted fs-11">{email} &middot; waiting for the owner to accept</div></td>
+  <td>{billing}</td>
+  <td class="muted">{sent}</td>
+  <td class="row gap-6" style="justify-content:flex-end">
+    <button class="btn ghost sm text-warn" hx-delete="{base_url}/admin/agency/invites/{id}" hx-confirm="Withdraw the invite to {email}?">Withdraw</button>
+  </td>
+</tr>"#,
+        id = html_escape(&invite.id),
+        name = html_escape(&invite.name),
+        email = html_escape(&invite.email),
+        billing = invite.billing.label(),
+        sent = html_escape(short_date(&invite.created_at)),
+    )
+}
+
+/// The header's tenant picker. Empty unless the login reaches more than
+/// one tenant.
+pub fn switcher_html(reach: &[Reach], current_tenant_id: &str, base_url: &str) -> String {
+    if reach.len() < 2 {
+        return String::new();
+    }
+    let options: String = reach
+        .iter()
+        .map(|r| {
+            let sel = if r.tenant_id == current_tenant_id {
+                " selected"
+            } else {
+                ""
+            };
+            let via = match &r.access {
+                Access::Owner => String::new(),
+                Access::Agency { .. } => " (client)".to_string(),
+                Access::Member { role, .. } => format!(" ({})", role.label()),
+            };
+            format!(
+                r#"<option value="{}"{sel}>{}{via}</option>"#,
+                html_escape(&r.tenant_id),
+                html_escape(&r.name)
+            )
+        })
+        .collect();
+    let overview = if reach
+        .iter()
+        .any(|r| matches!(r.access, Access::Agency { .. }))
+    {
+        format!(r#"<a class="fs-13" href="{base_url}/admin/agency">All clients</a>"#)
+    } else {
+        String::new()
+    };
+    format!(
+        r#"<span class="row gap-8" style="align-items:center"><select class="select" name="tenant_id" aria-label="Switch account" style="width:auto" hx-ext="json-enc" hx-post="{base_url}/admin/agency/switch" hx-trigger="change">{options}</select>{overview}</span>"#
+    )
+}
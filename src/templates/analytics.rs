@@ -0,0 +1,179 @@
+//! The dashboard's activity panel. GET `/admin/analytics` returns it on
+//! its own: the dashboard loads it after the page, and the range links
+//! swap it in place.
+
+use crate::analytics::{describe_bucket, Activity, DayActivity, RANGES};
+use crate::helpers::html_escape;
+use crate::types::Channel;
+
+/// A stacked series: label, colour and the day's value.
+type Series = (&'static str, &'static str, Box<dyn Fn(&DayActivity) -> u64>);
+
+pub fn activity_panel_html(activity: &Activity, range: u32, base_url: &str) -> String {
+    let ranges: String = RANGES
+        .iter()
+        .map(|days| {
+            let class = if *days == range {
+                "btn primary sm"
+            } else {
+                "btn ghost sm"
+            };
+            format!(
+                r##"<button class="{class}" hx-get="{base_url}/admin/analytics?days={days}" hx-target="#activity" hx-swap="outerHTML">{days}d</button>"##
+            )
+        })
+        .collect();
+
+    let median = activity
+        .median_reply()
+        .map(describe_bucket)
+        .unwrap_or_else(|| "—".to_string());
+    let stats = [
+        (
+            "Inbound",
+            activity.total(DayActivity::inbound_total).to_string(),
+        ),
+        ("Canned replies", activity.total(|d| d.canned).to_string()),
+        ("AI replies", activity.total(|d| d.ai).to_string()),
+        ("Human replies", activity.total(|d| d.human).to_string()),
+        ("Credits used", activity.credits().to_string()),
+        ("Lead forms", activity.total(|d| d.leads).to_string()),
+        ("Median reply", median),
+    ];
+    let stats: String = stats
+        .iter()
+        .map(|(label, value)| {
+            format!(
+                r#"<div class="card p-16 ta-center">
+  <div class="stat-n serif">{value}</div>
+  <div class="mono muted fs-11">{label}</div>
+</div>"#,
+                value = html_escape(value),
+            )
+        })
+        .collect();
+
+    let colours = ["var(--accent)", "var(--sage)", "var(--sky)", "var(--plum)"];
+    let inbound: Vec<Series> = Channel::ALL
+        .iter()
+        .zip(colours)
+        .map(|(channel, colour)| {
+            let wire = channel.as_str();
+            let value: Box<dyn Fn(&DayActivity) -> u64> =
+                Box::new(move |d| d.inbound.get(wire).copied().unwrap_or(0));
+            (channel.label(), colour, value)
+        })
+        .collect();
+    let replies: Vec<Series> = vec![
+        ("Canned", "var(--sage)", Box::new(|d| d.canned)),
+        ("AI", "var(--accent)", Box::new(|d| d.ai)),
+        ("Approved drafts", "var(--plum)", Box::new(|d| d.approved)),
+        ("Human", "var(--sky)", Box::new(|d| d.human)),
+    ];
+
+    let drafts = [
+        ("Queued", activity.total(|d| d.queued)),
+        ("Approved", activity.total(|d| d.approved)),
+        ("Rejected", activity.total(|d| d.rejected)),
+        ("Expired", activity.total(|d| d.expired)),
+    ];
+    let drafts_max = drafts.iter().map(|(_, n)| *n).max().unwrap_or(0).max(1);
+    let drafts: String = drafts
+        .iter()
+        .map(|(label, n)| {
+            format!(
+                r#"<div class="row gap-12 mb-8" style="align-items:center">
+  <div class="fs-13" style="width:90px">{label}</div>
+  <div class="flex-1" style="background:var(--hair);border-radius:var(--r-sm);height:10px">
+    <div style="width:{pct}%;background:var(--accent);border-radius:var(--r-sm);height:10px"></div>
+  </div>
+  <div class="mono fs-13" style="width:48px;text-align:right">{n}</div>
+</div>"#,
+                pct = n * 100 / drafts_max,
+            )
+        })
+        .collect();
+
+    format!(
+        r##"<div id="activity" class="card p-22 mt-16">
+  <div class="between mb-16" style="flex-wrap:wrap;gap:8px">
+    <div>
+      <div class="eyebrow">Activity</div>
+      <h3 class="display-sm m-0 mt-4">Last {range} days</h3>
+    </div>
+    <div class="row gap-8" style="align-items:center">
+      {ranges}
+      <a class="btn ghost sm" href="{base_url}/admin/analytics.csv?days={range}">CSV</a>
+    </div>
+  </div>
+  <div style="display:grid;grid-template-columns:repeat(auto-fit,minmax(120px,1fr));gap:12px">{stats}</div>
+  <div class="mt-16">
+    <div class="eyebrow mb-8">Inbound by channel</div>
+    {inbound_chart}
+  </div>
+  <div class="mt-16">
+    <div class="eyebrow mb-8">Replies sent</div>
+    {reply_chart}
+  </div>
+  <div class="mt-16">
+    <div class="eyebrow mb-8">AI drafts</div>
+    {drafts}
+  </div>
+  <p class="muted fs-12 mt-12 m-0">Days are UTC. Credits are net of refunded drafts. Reply times run from the customer's first unanswered message and are grouped, so the median is approximate.</p>
+</div>"##,
+        inbound_chart = stacked_bars(&activity.days, &inbound),
+        reply_chart = stacked_bars(&activity.days, &replies),
+    )
+}
+
+/// Daily stacked bars with a legend. Bars scale to the busiest day; each
+/// segment's tooltip names the day and count.
+fn stacked_bars(days: &[DayActivity], series: &[Series]) -> String {
+    const BAR: usize = 10;
+    const HEIGHT: u64 = 100;
+    let max = days
+        .iter()
+        .map(|d| series.iter().map(|(_, _, v)| v(d)).sum::<u64>())
+        .max()
+        .unwrap_or(0);
+    if max == 0 {
+        return r#"<p class="muted fs-13 m-0">Nothing in this range.</p>"#.to_string();
+    }
+
+    let mut rects = String::new();
+    for (i, day) in days.iter().enumerate() {
+        let mut top = HEIGHT;
+        for (label, colour, value) in series {
+            let n = value(day);
+            if n == 0 {
+                continue;
+            }
+            let h = (n * HEIGHT).div_ceil(max);
+            top = top.saturating_sub(h);
+            rects.push_str(&format!(
+                r#"<rect x="{x}" y="{top}" width="{w}" height="{h}" fill="{colour}"><title>{day}: {n} {label}</title></rect>"#,
+                x = i * BAR + 1,
+                w = BAR - 2,
+                day = html_escape(&day.day),
+                label = html_escape(label),
+            ));
+        }
+    }
+    let legend: String = series
+        .iter()
+        .map(|(label, colour, _)| {
+            format!(
+                r#"<span class="row gap-4 fs-12" style="align-items:center"><span style="display:inline-block;width:10px;height:10px;border-radius:2px;background:{colour}"></span>{label}</span>"#,
+                label = html_escape(label),
+            )
+        })
+        .collect();
+    let first = days.first().map(|d| d.day.as_str()).unwrap_or("");
+    let last = days.last().map(|d| d.day.as_str()).unwrap_or("");
+    format!(
+        r#"<svg viewBox="0 0 {width} {HEIGHT}" preserveAspectRatio="none" width="100%" height="120" role="img" aria-label="Daily chart, busiest day {max}">{rects}</svg>
+<div class="between mono muted fs-11 mt-4"><span>{first}</span><span>max {max}/day</span><span>{last}</span></div>
+<div class="row gap-12 mt-8" style="flex-wrap:wrap">{legend}</div>"#,
+        width = days.len() * BAR,
+    )
+}
@@ -1,4 +1,5 @@
-//! `/admin/approvals`: list of pending AI drafts awaiting human approval.
+//! `/admin/approvals`: list of pending AI drafts awaiting human approval,
+//! and the record of a single one, decided or not.
 //!
 //! The list refreshes via HTMX polling (`hx-trigger="every 5s"`) — fine for
 //! Phase 2. A future Phase 2.5 may swap the polling block for an SSE
@@ -7,10 +8,10 @@
 use crate::approvals::queue_reason_label;
 use crate::helpers::html_escape;
 use crate::locale::Locale;
-use crate::types::{PendingApproval, QueueReason};
+use crate::types::{ApprovalDecider, ApprovalStatus, PendingApproval, QueueReason, ToolCallRecord};
 
 use super::base::{app_shell, base_html};
-use super::HASH;
+use super::{short_date, HASH};
 
 pub fn approvals_page_html(rows: &[PendingApproval], base_url: &str, locale: &Locale) -> String {
     let list = approvals_list_inner_html(rows);
@@ -70,29 +71,63 @@ fn approvals_list_inner_html(rows: &[PendingApproval]) -> String {
 
 pub fn approval_row_html(row: &PendingApproval) -> String {
     let id = html_escape(&row.id);
-    let sender = html_escape(&row.sender);
-    let rule_label = html_escape(&row.rule_label);
+    let sender = match &row.contact_id {
+        Some(contact_id) => format!(
+            r#"<a href="/admin/contacts/{}">{}</a>"#,
+            html_escape(contact_id),
+            html_escape(&row.sender)
+        ),
+        None => html_escape(&row.sender),
+    };
+    // Messages queued without a rule (injection hits) have no rule to name.
+    let rule_line = if row.rule_label.is_empty() {
+        String::new()
+    } else {
+        format!(
+            r#"<div class="muted fs-13 mb-8" style="white-space:pre-wrap">From rule: {}</div>"#,
+            html_escape(&row.rule_label)
+        )
+    };
+    // Nothing to approve as-is: open straight into the editor.
+    let editing = if row.draft.trim().is_empty() {
+        "true"
+    } else {
+        "false"
+    };
     let inbound = html_escape(&row.inbound_preview);
     let draft = html_escape(&row.draft);
     let channel = row.channel.label();
     let reason_chip = reason_chip(row.queue_reason);
+    let reason_detail = row
+        .queue_detail
+        .as_deref()
+        .map(|d| {
+            format!(
+                r#"<span class="mono fs-12 text-warn">{}</span>"#,
+                html_escape(d)
+            )
+        })
+        .unwrap_or_default();
     let created = html_escape(short_date(&row.created_at));
+    let lookups = tool_calls_html(&row.tool_calls);
 
     format!(
-        r##"<div class="approval-row" id="approval-{id}" x-data="{{ editing: false, draft: '' }}"
+        r##"<div class="approval-row" id="approval-{id}" x-data="{{ editing: {editing}, draft: '' }}"
   style="padding:18px;border-bottom:1px solid var(--border)">
   <div class="row gap-8 mb-4" style="align-items:center;flex-wrap:wrap">
     <strong>{sender}</strong>
     <span class="chip">{channel}</span>
     {reason_chip}
+    {reason_detail}
     <span class="muted fs-12">{created}</span>
   </div>
-  <div class="muted fs-13 mb-8" style="white-space:pre-wrap">From rule: {rule_label}</div>
+  {rule_line}
 
   <details class="mb-8">
     <summary class="muted fs-12">Original message</summary>
     <pre class="mono fs-12 mt-4" style="white-space:pre-wrap">{inbound}</pre>
   </details>
+  {lookups}
 
   <div x-show="!editing">
     <pre class="mono fs-13 m-0 mb-12" style="white-space:pre-wrap">{draft}</pre>
@@ -127,6 +162,148 @@ pub fn approval_row_html(row: &PendingApproval) -> String {
     )
 }
 
+/// GET `/admin/approvals/{id}`, linked from the message log. A pending
+/// row keeps its actions; a decided one shows who decided, when, and what
+/// was sent.
+pub fn approval_page_html(row: &PendingApproval, base_url: &str, locale: &Locale) -> String {
+    let record = if row.status == ApprovalStatus::Pending {
+        format!(
+            r##"<div class="card p-0" style="overflow:hidden">{}</div>"##,
+            approval_row_html(row)
+        )
+    } else {
+        decided_record_html(row)
+    };
+    let body = format!(
+        r##"<div class="page-pad">
+  <p><a href="{base_url}/admin/messages" class="btn ghost sm">&larr; Messages</a></p>
+  <h1 class="display-sm m-0 mb-16">Approval</h1>
+  {record}
+</div>"##
+    );
+    let page = app_shell(&body, "Approvals", base_url, locale);
+    base_html("Approval - Concierge", &page, locale)
+}
+
+fn decided_record_html(row: &PendingApproval) -> String {
+    let (chip, outcome) = match row.status {
+        ApprovalStatus::Approved if row.edited => ("ok", "Edited and sent"),
+        ApprovalStatus::Approved => ("ok", "Approved and sent"),
+        ApprovalStatus::Rejected => ("warn", "Rejected"),
+        ApprovalStatus::Expired => ("warn", "Expired"),
+        ApprovalStatus::Pending => ("", "Pending"),
+    };
+    let decider = match row
+        .decided_by
+        .as_deref()
+        .and_then(ApprovalDecider::from_wire)
+    {
+        Some(ApprovalDecider::Web {
+            email,
+            member_id: None,
+        }) => format!("by {} on the web", html_escape(&email)),
+        Some(ApprovalDecider::Web {
+            email,
+            member_id: Some(_),
+        }) => format!("by team member {} on the web", html_escape(&email)),
+        Some(ApprovalDecider::Discord { user_id }) => {
+            format!("by Discord user {}", html_escape(&user_id))
+        }
+        Some(ApprovalDecider::Expired) | None => String::new(),
+    };
+    let refund = match row.status {
+        ApprovalStatus::Rejected | ApprovalStatus::Expired if row.credits_charged > 0 => format!(
+            r#"<span class="muted fs-12">{} credits refunded</span>"#,
+            row.credits_charged
+        ),
+        _ => String::new(),
+    };
+    let rule_line = if row.rule_label.is_empty() {
+        String::new()
+    } else {
+        format!(
+            r#"<div class="muted fs-13 mb-8">From rule: {}</div>"#,
+            html_escape(&row.rule_label)
+        )
+    };
+    let draft_label = if row.status == ApprovalStatus::Approved {
+        "Sent"
+    } else {
+        "Draft"
+    };
+    format!(
+        r##"<div class="card p-22">
+  <div class="row gap-8 mb-4" style="align-items:center;flex-wrap:wrap">
+    <strong>{sender}</strong>
+    <span class="chip">{channel}</span>
+    {reason_chip}
+    <span class="chip {chip}">{outcome}</span>
+    {refund}
+  </div>
+  <div class="muted fs-12 mb-8">Queued {created}. Decided {decided} {decider}</div>
+  {rule_line}
+  <div class="eyebrow mb-4">Original message</div>
+  <pre class="mono fs-12 m-0 mb-12" style="white-space:pre-wrap">{inbound}</pre>
+  {lookups}
+  <div class="eyebrow mb-4">{draft_label}</div>
+  <pre class="mono fs-13 m-0" style="white-space:pre-wrap">{draft}</pre>
+</div>"##,
+        sender = html_escape(&row.sender),
+        channel = row.channel.label(),
+        reason_chip = reason_chip(row.queue_reason),
+        created = html_escape(short_date(&row.created_at)),
+        decided = html_escape(short_date(row.decided_at.as_deref().unwrap_or(""))),
+        inbound = html_escape(&row.inbound_preview),
+        lookups = tool_calls_html(&row.tool_calls),
+        draft = html_escape(&row.draft),
+    )
+}
+
+/// Tool lookups the draft was written from, so the reviewer can check the
+/// data (order status, free slots) before approving. Empty when the model
+/// made no calls.
+fn tool_calls_html(calls: &[ToolCallRecord]) -> String {
+    if calls.is_empty() {
+        return String::new();
+    }
+    let items: String = calls
+        .iter()
+        .map(|c| {
+            let outcome = match (&c.error, c.status) {
+                (Some(e), _) => format!(r#"<span class="chip warn">{}</span>"#, html_escape(e)),
+                (None, Some(s)) if (200..300).contains(&s) => {
+                    format!(r#"<span class="chip ok">HTTP {s}</span>"#)
+                }
+                (None, Some(s)) => format!(r#"<span class="chip warn">HTTP {s}</span>"#),
+                (None, None) => String::new(),
+            };
+            let truncated = if c.truncated {
+                r#" <span class="muted fs-12">(truncated)</span>"#
+            } else {
+                ""
+            };
+            format!(
+                r#"<li class="mb-8">
+      <div class="row gap-6" style="align-items:center;flex-wrap:wrap"><strong class="mono fs-12">{tool}</strong> {outcome} <span class="muted fs-12">{ms} ms</span>{truncated}</div>
+      <pre class="mono fs-12 m-0 mt-4" style="white-space:pre-wrap">{args}</pre>
+      <pre class="mono fs-12 m-0 mt-4" style="white-space:pre-wrap">{output}</pre>
+    </li>"#,
+                tool = html_escape(&c.tool),
+                ms = c.duration_ms,
+                args = html_escape(&c.arguments.to_string()),
+                output = html_escape(&c.output),
+            )
+        })
+        .collect();
+    format!(
+        r#"<details class="mb-8">
+    <summary class="muted fs-12">Lookups used ({n})</summary>
+    <ul class="mt-4" style="list-style:none;padding:0">{items}</ul>
+  </details>"#,
+        n = calls.len(),
+    )
+}
+
 fn reason_chip(reason: QueueReason) -> String {
     let label = queue_reason_label(reason);
     match reason {
@@ -134,9 +311,3 @@ fn reason_chip(reason: QueueReason) -> String {
         _ => format!(r##"<span class="chip warn">{label}</span>"##),
     }
 }
-
-/// Short rendering of an ISO timestamp: just the date+time prefix. Good
-/// enough for an approval list where age matters more than precise seconds.
-fn short_date(iso: &str) -> &str {
-    iso.get(..16).unwrap_or(iso)
-}
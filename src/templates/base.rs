@@ -546,7 +546,7 @@ mod footer_tests {
     #[test]
     fn privacy_has_one_footer() {
         let l = crate::locale::Locale::default_inr();
-        let s = crate::legal::privacy_policy_html(&l);
+        let s = crate::legal::privacy_policy_html(&l, None, &[]);
         assert_eq!(count(&s, r#"<footer class="site-footer">"#), 1, "privacy");
     }
 
@@ -744,9 +744,12 @@ pub fn app_shell(content: &str, active_nav: &str, base_url: &str, locale: &Local
     // Each entry: (active_key, FTL key, href).
     // active_key matches the `active_nav` arg (kept as English for stable
     // cross-locale routing — callers don't have to translate it too).
-    let nav_items: [(&str, &str, &str); 6] = [
+    let nav_items: [(&str, &str, &str); 9] = [
         ("Overview", "app-nav-overview", "/admin"),
+        ("Inbox", "app-nav-inbox", "/admin/inbox"),
+        ("Contacts", "app-nav-contacts", "/admin/contacts"),
         ("Approvals", "app-nav-approvals", "/admin/approvals"),
+        ("Messages", "app-nav-messages", "/admin/messages"),
         ("Channels", "app-nav-channels", "/admin/whatsapp"),
         ("Email", "app-nav-email", "/admin/email"),
         ("Billing", "app-nav-billing", "/admin/billing"),
@@ -765,6 +768,7 @@ pub fn app_shell(content: &str, active_nav: &str, base_url: &str, locale: &Local
     let nav_aria = t(locale, "app-nav-aria-label");
     let status = t(locale, "app-nav-status-live");
     let logout_aria = t(locale, "app-nav-logout-aria");
+    let bell_aria = t(locale, "app-nav-notifications");
 
     format!(
         r#"<div class="app">
@@ -772,6 +776,10 @@ pub fn app_shell(content: &str, active_nav: &str, base_url: &str, locale: &Local
     {brand}
     <nav class="app-nav" aria-label="{nav_aria}">{nav}</nav>
     <div class="row gap-12">
+      <span id="tenant-switcher" hx-get="{base_url}/admin/agency/switcher" hx-trigger="load"></span>
+      <div hx-ext="sse" sse-connect="{base_url}/admin/notifications/stream">
+        <span id="notification-bell" hx-get="{base_url}/admin/notifications/bell" hx-trigger="load, sse:notification-changed, every 60s"><a href="{base_url}/admin/notifications" aria-label="{bell_aria}">&#128276;</a></span>
+      </div>
       <span class="chip ok">{status}</span>
       <a href="{base_url}/auth/logout" class="avatar" aria-label="{logout_aria}">X</a>
     </div>
@@ -783,6 +791,7 @@ pub fn app_shell(content: &str, active_nav: &str, base_url: &str, locale: &Local
         nav_aria = html_escape(&nav_aria),
         status = html_escape(&status),
         logout_aria = html_escape(&logout_aria),
+        bell_aria = html_escape(&bell_aria),
         base_url = base_url,
         content = content,
     )
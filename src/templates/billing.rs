@@ -157,6 +157,32 @@ pub fn billing_overview_with_addresses_html(
     base_html("Billing - Concierge", &page, locale)
 }
 
+/// The billing page of a client whose agency pays for its replies
+/// (`ClientBilling::Agency`): the shared balance and who holds it.
+pub fn billing_paid_by_agency_html(
+    agency: &str,
+    credits: i64,
+    base_url: &str,
+    locale: &Locale,
+) -> String {
+    let total_class = if credits <= 0 { " text-warn" } else { "" };
+    let content = format!(
+        r##"<div class="page-pad">
+  <p><a href="{base_url}/admin">&larr; Back to Dashboard</a></p>
+  <div class="eyebrow">Billing</div>
+  <h2 class="display-sm m-0 mt-4 mb-16">AI reply credits</h2>
+  <div class="card p-18 mb-24">
+    <div class="stat-n serif{total_class}">{credits}</div>
+    <div class="mono muted fs-11">Remaining with {agency}</div>
+    <p class="muted fs-13 mt-12 mb-0">{agency} pays for this account's replies from its own credits. To buy credits or email address packs here instead, ask them to switch this account to &ldquo;Client pays&rdquo;.</p>
+  </div>
+</div>"##,
+        agency = html_escape(agency),
+    );
+    let page = app_shell(&content, "Billing", base_url, locale);
+    base_html("Billing - Concierge", &page, locale)
+}
+
 pub fn checkout_html(
     order_id: &str,
     amount: i64,
@@ -0,0 +1,219 @@
+//! `/admin/brands`: the tenant's brands with their channel assignments,
+//! and the page that edits one brand's details and opening hours.
+
+use crate::brands::Assignment;
+use crate::helpers::html_escape;
+use crate::locale::Locale;
+use crate::types::{Brand, OpeningHours, PersonaSafetyStatus};
+
+use super::base::{app_shell, base_html};
+use super::HASH;
+
+pub fn brands_page_html(
+    brands: &[Brand],
+    accounts: &[Assignment],
+    base_url: &str,
+    locale: &Locale,
+) -> String {
+    let rows: String = brands
+        .iter()
+        .map(|b| brand_row_html(b, accounts, base_url))
+        .collect();
+    let channels: String = if accounts.is_empty() {
+        r#"<tr><td colspan="3" class="muted">No channels connected yet.</td></tr>"#.to_string()
+    } else {
+        accounts
+            .iter()
+            .map(|a| assignment_row_html(a, brands, base_url))
+            .collect()
+    };
+
+    let body = format!(
+        r##"<div class="page-pad">
+  <p><a href="{base_url}/admin/settings" class="btn ghost sm">&larr; Settings</a></p>
+  <h1 class="display-sm m-0 mb-4">Brands</h1>
+  <p class="muted mb-16">Run more than one business or location from this account. Each brand has its own details, opening hours and persona, and answers on the channels you give it.</p>
+  <div class="card p-0 mb-24" style="overflow-x:auto">
+    <table class="manage-table fs-13" style="width:100%">
+      <thead><tr><th>Brand</th><th>Persona</th><th>Opening hours</th><th>Channels</th><th></th></tr></thead>
+      <tbody>{rows}</tbody>
+    </table>
+  </div>
+  <div class="card p-0 mb-24" style="overflow-x:auto">
+    <table class="manage-table fs-13" style="width:100%">
+      <thead><tr><th>Channel</th><th>Account</th><th>Answers as</th></tr></thead>
+      <tbody>{channels}</tbody>
+    </table>
+  </div>
+  <div class="card p-22">
+    <h2 class="fs-16 m-0 mb-4">Add a brand</h2>
+    <p class="muted fs-13 mb-12">A new brand starts with the default persona. AI replies on its channels wait until its persona passes the safety check.</p>
+    <form class="row gap-8" style="flex-wrap:wrap;align-items:center" hx-ext="json-enc" hx-post="{base_url}/admin/brands" hx-target="{HASH}brands-toast" hx-swap="innerHTML">
+      <input class="input" name="name" required maxlength="100" placeholder="Rose Cafe" aria-label="Brand name" style="flex:2;min-width:200px">
+      <button class="btn primary sm" type="submit">Add brand</button>
+    </form>
+    <div id="brands-toast" class="mt-8" role="status" aria-live="polite"></div>
+  </div>
+</div>"##
+    );
+    let page = app_shell(&body, "Settings", base_url, locale);
+    base_html("Brands - Concierge", &page, locale)
+}
+
+fn persona_chip(brand: &Brand) -> &'static str {
+    if brand.persona.is_safe_to_use() {
+        r#"<span class="chip ok">Approved</span>"#
+    } else if brand.persona.safety.status == PersonaSafetyStatus::Rejected {
+        r#"<span class="chip warn">Turned down</span>"#
+    } else {
+        r#"<span class="chip warn">Being checked</span>"#
+    }
+}
+
+fn persona_href(brand: &Brand, base_url: &str) -> String {
+    if brand.is_main() {
+        format!("{base_url}/admin/persona")
+    } else {
+        format!("{base_url}/admin/persona?brand={}", html_escape(&brand.id))
+    }
+}
+
+fn brand_row_html(brand: &Brand, accounts: &[Assignment], base_url: &str) -> String {
+    let id = html_escape(&brand.id);
+    let channels = accounts
+        .iter()
+        .filter(|a| a.brand_id.as_deref().unwrap_or(Brand::MAIN) == brand.id)
+        .count();
+    let days_open = (0..7).filter(|i| brand.hours.day(*i).is_some()).count();
+    let hours = if days_open == 0 {
+        r#"<span class="muted">Not set</span>"#.to_string()
+    } else {
+        format!(
+            "Open {days_open} day{}",
+            if days_open == 1 { "" } else { "s" }
+        )
+    };
+    let main = if brand.is_main() {
+        r#" <span class="chip">Main</span>"#
+    } else {
+        ""
+    };
+    let remove = if brand.is_main() {
+        String::new()
+    } else {
+        format!(
+            r#"<button class="btn ghost sm text-warn" hx-delete="{base_url}/admin/brands/{id}" hx-confirm="Remove {name}? Its channels go back to the main brand.">Remove</button>"#,
+            name = html_escape(brand.name()),
+        )
+    };
+    format!(
+        r#"<tr id="brand-{id}">
+  <td><strong>{name}</strong>{main}</td>
+  <td>{persona}</td>
+  <td>{hours}</td>
+  <td>{channels}</td>
+  <td class="row gap-6" style="justify-content:flex-end">
+    <a class="btn sm" href="{base_url}/admin/brands/{id}">Details</a>
+    <a class="btn sm" href="{persona_href}">Persona</a>
+    {remove}
+  </td>
+</tr>"#,
+        name = html_escape(brand.name()),
+        persona = persona_chip(brand),
+        persona_href = persona_href(brand, base_url),
+    )
+}
+
+fn assignment_row_html(account: &Assignment, brands: &[Brand], base_url: &str) -> String {
+    let current = account.brand_id.as_deref().unwrap_or(Brand::MAIN);
+    let options: String = brands
+        .iter()
+        .map(|b| {
+            let sel = if b.id == current { " selected" } else { "" };
+            format!(
+                r#"<option value="{}"{sel}>{}</option>"#,
+                html_escape(&b.id),
+                html_escape(b.name())
+            )
+        })
+        .collect();
+    let vals = html_escape(
+        &serde_json::json!({
+            "channel": account.channel.as_str(),
+            "account_id": account.account_id,
+        })
+        .to_string(),
+    );
+    format!(
+        r#"<tr>
+  <td>{channel}</td>
+  <td>{label}</td>
+  <td><select class="select" name="brand_id" aria-label="Brand for {label}" style="width:auto" hx-ext="json-enc" hx-post="{base_url}/admin/brands/assign" hx-vals='{vals}' hx-trigger="change" hx-swap="none">{options}</select></td>
+</tr>"#,
+        channel = account.channel.label(),
+        label = html_escape(&account.label),
+    )
+}
+
+pub fn brand_page_html(brand: &Brand, base_url: &str, locale: &Locale) -> String {
+    let b = &brand.business;
+    let hours: String = OpeningHours::DAYS
+        .iter()
+        .enumerate()
+        .map(|(i, day)| {
+            let (open, close) = brand
+                .hours
+                .day(i)
+                .map(|d| (d.open.as_str(), d.close.as_str()))
+                .unwrap_or(("", ""));
+            format!(
+                r#"<div class="row gap-8 mb-8" style="align-items:center">
+        <span class="fs-13" style="width:100px">{day}</span>
+        <input class="input" type="time" name="open_{i}" value="{open}" aria-label="{day} opens" style="width:auto">
+        <span class="muted fs-13">to</span>
+        <input class="input" type="time" name="close_{i}" value="{close}" aria-label="{day} closes" style="width:auto">
+      </div>"#
+            )
+        })
+        .collect();
+
+    let body = format!(
+        r##"<div class="page-pad">
+  <p><a href="{base_url}/admin/brands" class="btn ghost sm">&larr; Brands</a></p>
+  <h1 class="display-sm m-0 mb-4">{name}</h1>
+  <p class="muted mb-16">Details and opening hours for this brand. The assistant tells customers these hours when they ask; <a href="{persona_href}">its persona</a> sets how it talks.</p>
+  <form hx-ext="json-enc" hx-post="{base_url}/admin/brands/{id}" hx-target="{HASH}brand-toast" hx-swap="innerHTML">
+    <div class="card p-22 mb-16">
+      <h2 class="fs-16 m-0 mb-12">Details</h2>
+      <div style="display:grid;grid-template-columns:1fr 1fr;gap:12px">
+        <div><label for="brand-name" class="eyebrow lbl">Name</label><input id="brand-name" class="input" name="name" required maxlength="100" value="{name}"></div>
+        <div><label for="brand-contact" class="eyebrow lbl">Contact person</label><input id="brand-contact" class="input" name="contact_name" value="{contact_name}"></div>
+        <div><label for="brand-phone" class="eyebrow lbl">Phone</label><input id="brand-phone" class="input" name="phone" value="{phone}"></div>
+        <div><label for="brand-address" class="eyebrow lbl">Address</label><input id="brand-address" class="input" name="address" value="{address}"></div>
+        <div><label for="brand-state" class="eyebrow lbl">State</label><input id="brand-state" class="input" name="state" value="{state}"></div>
+        <div><label for="brand-pincode" class="eyebrow lbl">Pincode</label><input id="brand-pincode" class="input" name="pincode" value="{pincode}"></div>
+      </div>
+    </div>
+    <div class="card p-22 mb-16">
+      <h2 class="fs-16 m-0 mb-4">Opening hours</h2>
+      <p class="muted fs-13 mb-12">Leave a day blank when you're closed.</p>
+      {hours}
+    </div>
+    <div class="row gap-8" style="justify-content:flex-end;align-items:center">
+      <div id="brand-toast" role="status" aria-live="polite"></div>
+      <button class="btn primary" type="submit">Save</button>
+    </div>
+  </form>
+</div>"##,
+        id = html_escape(&brand.id),
+        name = html_escape(brand.name()),
+        persona_href = persona_href(brand, base_url),
+        contact_name = html_escape(&b.contact_name),
+        phone = html_escape(&b.phone),
+        address = html_escape(&b.address),
+        state = html_escape(&b.state),
+        pincode = html_escape(&b.pincode),
+    );
+    let page = app_shell(&body, "Settings", base_url, locale);
+    base_html("Brand - Concierge", &page, locale)
+}
@@ -0,0 +1,191 @@
+//! `/admin/consent`: opt-out keywords per language, the suppression list
+//! and the audit trail of consent changes.
+
+use crate::consent::{ConsentEvent, Suppression, CONSENT_EVENTS_SHOWN};
+use crate::helpers::html_escape;
+use crate::locale::Locale;
+use crate::types::{Channel, ConsentChange, ConsentConfig, ConsentKeywords};
+
+use super::base::{app_shell, base_html};
+use super::{short_date, HASH};
+
+/// Keyword sets a tenant can keep.
+pub const MAX_KEYWORD_SETS: usize = 10;
+
+/// Longest confirmation reply, in characters.
+pub const MAX_CONSENT_REPLY: usize = 500;
+
+pub fn consent_page_html(
+    config: &ConsentConfig,
+    suppressions: &[Suppression],
+    events: &[ConsentEvent],
+    base_url: &str,
+    locale: &Locale,
+) -> String {
+    let body = format!(
+        r##"<div class="page-pad">
+  <p><a href="{base_url}/admin/contacts" class="btn ghost sm">&larr; Contacts</a></p>
+  <h1 class="display-sm m-0 mb-4">Opt-outs</h1>
+  <p class="muted mb-16">Customers who text an opt-out keyword are added to the suppression list and get a confirmation. Nobody on the list is messaged again, on any channel, until they opt back in. WhatsApp Business policy requires honouring these requests.</p>
+  {keywords}
+  {list}
+  {events}
+</div>"##,
+        keywords = keywords_form_html(config, base_url),
+        list = suppressions_html(suppressions, base_url),
+        events = events_html(events),
+    );
+    let page = app_shell(&body, "Contacts", base_url, locale);
+    base_html("Opt-outs - Concierge", &page, locale)
+}
+
+/// One fieldset per language plus a blank one for adding a language.
+/// Clearing a set's keywords removes it.
+fn keywords_form_html(config: &ConsentConfig, base_url: &str) -> String {
+    let blank = ConsentKeywords::default();
+    let sets: String = config
+        .keyword_sets
+        .iter()
+        .take(MAX_KEYWORD_SETS)
+        .chain((config.keyword_sets.len() < MAX_KEYWORD_SETS).then_some(&blank))
+        .enumerate()
+        .map(|(i, set)| keyword_set_html(i, set))
+        .collect();
+    format!(
+        r##"<form class="card p-22 mb-24" hx-ext="json-enc" hx-post="{base_url}/admin/consent/keywords"
+    hx-target="{HASH}consent-keywords-result" hx-swap="innerHTML">
+    <h2 class="m-0 mb-4">Keywords</h2>
+    <p class="muted fs-13 mb-16">A single word only counts when it is the whole message, so "stop" inside a sentence doesn't opt anyone out. Phrases match anywhere. Comma-separated, case and punctuation ignored.</p>
+    {sets}
+    <div id="consent-keywords-result" role="status" aria-live="polite"></div>
+    <div class="row gap-8 mt-8" style="justify-content:flex-end">
+      <button class="btn primary" type="submit">Save keywords</button>
+    </div>
+  </form>"##
+    )
+}
+
+fn keyword_set_html(i: usize, set: &ConsentKeywords) -> String {
+    let legend = if set.locale.is_empty() {
+        "Add a language".to_string()
+    } else {
+        html_escape(&set.locale)
+    };
+    format!(
+        r##"<fieldset class="mb-16" style="border:1px solid var(--border);border-radius:8px;padding:12px 16px">
+      <legend class="eyebrow">{legend}</legend>
+      <label for="locale_{i}" class="eyebrow lbl">Language</label>
+      <input id="locale_{i}" class="input mb-8" name="locale_{i}" value="{locale}" maxlength="16" placeholder="en" style="max-width:120px">
+      <label for="opt_out_{i}" class="eyebrow lbl">Opt-out keywords</label>
+      <input id="opt_out_{i}" class="input mb-8" name="opt_out_{i}" value="{opt_out}">
+      <label for="opt_out_reply_{i}" class="eyebrow lbl">Opt-out confirmation</label>
+      <textarea id="opt_out_reply_{i}" class="textarea mb-8" name="opt_out_reply_{i}" rows="2" maxlength="{MAX_CONSENT_REPLY}">{opt_out_reply}</textarea>
+      <label for="opt_in_{i}" class="eyebrow lbl">Opt-in keywords</label>
+      <input id="opt_in_{i}" class="input mb-8" name="opt_in_{i}" value="{opt_in}">
+      <label for="opt_in_reply_{i}" class="eyebrow lbl">Opt-in confirmation</label>
+      <textarea id="opt_in_reply_{i}" class="textarea" name="opt_in_reply_{i}" rows="2" maxlength="{MAX_CONSENT_REPLY}">{opt_in_reply}</textarea>
+    </fieldset>"##,
+        locale = html_escape(&set.locale),
+        opt_out = html_escape(&set.opt_out.join(", ")),
+        opt_in = html_escape(&set.opt_in.join(", ")),
+        opt_out_reply = html_escape(&set.opt_out_reply),
+        opt_in_reply = html_escape(&set.opt_in_reply),
+    )
+}
+
+fn suppressions_html(suppressions: &[Suppression], base_url: &str) -> String {
+    let rows: String = if suppressions.is_empty() {
+        r#"<tr><td colspan="4" class="muted">Nobody has opted out.</td></tr>"#.to_string()
+    } else {
+        suppressions
+            .iter()
+            .map(|s| {
+                let vals = serde_json::json!({
+                    "channel": s.channel.as_str(),
+                    "identity": s.identity,
+                })
+                .to_string();
+                format!(
+                    r##"<tr><td>{channel}</td><td class="mono fs-12">{identity}</td><td>{source} · {when}</td>
+  <td><button class="btn ghost sm" hx-ext="json-enc" hx-post="{base_url}/admin/consent/opt-in" hx-vals='{vals}'
+    hx-confirm="Only do this if the customer asked to hear from you again. Remove from the list?">Opt back in</button></td></tr>"##,
+                    channel = s.channel.label(),
+                    identity = match &s.contact_id {
+                        Some(contact_id) => format!(
+                            r#"<a href="{base_url}/admin/contacts/{}">{}</a>"#,
+                            html_escape(contact_id),
+                            html_escape(&s.identity)
+                        ),
+                        None => html_escape(&s.identity),
+                    },
+                    source = s.source.label(),
+                    when = html_escape(short_date(&s.created_at)),
+                    vals = html_escape(&vals),
+                )
+            })
+            .collect()
+    };
+    let channel_options: String = [
+        Channel::WhatsApp,
+        Channel::Instagram,
+        Channel::Email,
+        Channel::Discord,
+    ]
+    .iter()
+    .map(|c| format!(r#"<option value="{}">{}</option>"#, c.as_str(), c.label()))
+    .collect();
+    format!(
+        r##"<div class="card p-22 mb-24">
+    <h2 class="m-0 mb-12">Suppression list</h2>
+    <table class="manage-table fs-13" style="width:100%">
+      <thead><tr><th>Channel</th><th>Identity</th><th>Added</th><th></th></tr></thead>
+      <tbody>{rows}</tbody>
+    </table>
+    <form class="row gap-8 mt-16" hx-ext="json-enc" hx-post="{base_url}/admin/consent/opt-out" style="align-items:center;flex-wrap:wrap">
+      <label for="suppress-channel" class="eyebrow lbl m-0">Add</label>
+      <select id="suppress-channel" class="select" name="channel" style="max-width:160px">{channel_options}</select>
+      <input class="input" name="identity" required aria-label="Phone, email or handle" placeholder="Phone, email or handle" style="max-width:280px">
+      <button class="btn ghost sm" type="submit">Suppress</button>
+    </form>
+  </div>"##
+    )
+}
+
+fn events_html(events: &[ConsentEvent]) -> String {
+    let rows: String = if events.is_empty() {
+        r#"<tr><td colspan="5" class="muted">No consent changes yet.</td></tr>"#.to_string()
+    } else {
+        events
+            .iter()
+            .map(|e| {
+                let chip = match e.change {
+                    ConsentChange::OptOut => "chip warn",
+                    ConsentChange::OptIn => "chip ok",
+                };
+                format!(
+                    r#"<tr><td class="fs-12">{when}</td><td><span class="{chip}">{change}</span></td><td>{channel}</td><td class="mono fs-12">{identity}</td><td>{source}{detail}</td></tr>"#,
+                    when = html_escape(short_date(&e.created_at)),
+                    change = e.change.label(),
+                    channel = e.channel.label(),
+                    identity = html_escape(&e.identity),
+                    source = e.source.label(),
+                    detail = e
+                        .detail
+                        .as_deref()
+                        .map(|d| format!(r#" <span class="muted">({})</span>"#, html_escape(d)))
+                        .unwrap_or_default(),
+                )
+            })
+            .collect()
+    };
+    format!(
+        r##"<div class="card p-22">
+    <h2 class="m-0 mb-4">Audit trail</h2>
+    <p class="muted fs-13 mb-12">The last {CONSENT_EVENTS_SHOWN} consent changes.</p>
+    <table class="manage-table fs-13" style="width:100%">
+      <thead><tr><th>When</th><th>Change</th><th>Channel</th><th>Identity</th><th>By</th></tr></thead>
+      <tbody>{rows}</tbody>
+    </table>
+  </div>"##
+    )
+}
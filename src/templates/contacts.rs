@@ -0,0 +1,206 @@
+//! `/admin/contacts`: the contact book. One row per person across channels,
+//! with their sender identities, tags, private notes and a merge control
+//! for folding a duplicate into them.
+
+use crate::contacts::{Contact, ContactIdentity, MAX_NOTES, MAX_TAGS};
+use crate::helpers::html_escape;
+use crate::locale::Locale;
+
+use super::base::{app_shell, base_html};
+use super::{short_date, HASH};
+
+pub fn contacts_page_html(
+    contacts: &[Contact],
+    query: &str,
+    tag: &str,
+    base_url: &str,
+    locale: &Locale,
+) -> String {
+    let rows = if contacts.is_empty() {
+        r##"<div class="card p-22 ta-center">
+  <p class="muted m-0">No contacts yet. Everyone who messages you is added here automatically.</p>
+</div>"##
+            .to_string()
+    } else {
+        let rows: String = contacts.iter().map(contact_row_html).collect();
+        format!(r##"<div class="card p-0" style="overflow:hidden">{rows}</div>"##)
+    };
+    let clear = if query.is_empty() && tag.is_empty() {
+        String::new()
+    } else {
+        format!(r#"<a class="btn ghost sm" href="{base_url}/admin/contacts">Clear</a>"#)
+    };
+    let body = format!(
+        r##"<div class="page-pad">
+  <div class="row gap-8 mb-4" style="align-items:center;justify-content:space-between;flex-wrap:wrap">
+    <h1 class="display-sm m-0">Contacts</h1>
+    <div class="row gap-8">
+      <a class="btn ghost sm" href="{base_url}/admin/data-requests">Data requests</a>
+      <a class="btn ghost sm" href="{base_url}/admin/consent">Opt-outs</a>
+    </div>
+  </div>
+  <p class="muted mb-16">Everyone who has messaged you, on any channel. Merge duplicates when the same person writes from more than one.</p>
+  <form class="row gap-8 mb-16" method="get" action="{base_url}/admin/contacts" style="flex-wrap:wrap">
+    <input class="input" type="search" name="q" value="{query}" placeholder="Name, phone, email or handle" aria-label="Search contacts" style="max-width:320px">
+    <input class="input" type="text" name="tag" value="{tag}" placeholder="Tag" aria-label="Filter by tag" style="max-width:160px">
+    <button class="btn primary sm" type="submit">Search</button>
+    {clear}
+  </form>
+  {rows}
+</div>"##,
+        query = html_escape(query),
+        tag = html_escape(tag),
+    );
+    let page = app_shell(&body, "Contacts", base_url, locale);
+    base_html("Contacts - Concierge", &page, locale)
+}
+
+fn contact_row_html(c: &Contact) -> String {
+    format!(
+        r##"<a class="approval-row" href="/admin/contacts/{id}" style="display:block;padding:14px 18px;border-bottom:1px solid var(--border);color:inherit;text-decoration:none">
+  <div class="row gap-8" style="align-items:center;flex-wrap:wrap">
+    <strong>{name}</strong>
+    {tags}
+    <span class="muted fs-12">{count} messages</span>
+    <span class="muted fs-12">{seen}</span>
+  </div>
+</a>"##,
+        id = html_escape(&c.id),
+        name = html_escape(&display_name(c)),
+        tags = tag_chips(&c.tags),
+        count = c.message_count,
+        seen = html_escape(short_date(c.last_seen_at.as_deref().unwrap_or(""))),
+    )
+}
+
+pub fn contact_page_html(
+    c: &Contact,
+    identities: &[ContactIdentity],
+    others: &[Contact],
+    suppressed: bool,
+    base_url: &str,
+    locale: &Locale,
+) -> String {
+    let id = html_escape(&c.id);
+    let opted_out = if suppressed {
+        format!(
+            r#"<a class="chip warn" href="{base_url}/admin/consent" title="Opted out: no messages are sent">Opted out</a>"#
+        )
+    } else {
+        String::new()
+    };
+    let data_request = match identities.first() {
+        Some(i) => format!(
+            r#"<a class="btn ghost sm" href="{base_url}/admin/data-requests?channel={}&identity={}">Export or erase</a>"#,
+            i.channel.as_str(),
+            html_escape(&urlencoding::encode(&i.identity))
+        ),
+        None => String::new(),
+    };
+    let identity_rows: String = identities.iter().map(identity_row_html).collect();
+    let merge = merge_form_html(c, others, base_url);
+    let body = format!(
+        r##"<div class="page-pad">
+  <p><a href="{base_url}/admin/contacts" class="btn ghost sm">&larr; Contacts</a></p>
+  <div class="row gap-8 mb-16" style="align-items:center;flex-wrap:wrap">
+    <h1 class="display-sm m-0">{name}</h1>
+    {tags}
+    {opted_out}
+    <span class="muted fs-12">Added {added}</span>
+    <a class="btn ghost sm" href="{base_url}/admin/messages?contact={id}">Message log</a>
+    {data_request}
+  </div>
+
+  <div class="card p-0 mb-24" style="overflow:hidden">
+    <table class="manage-table fs-13" style="width:100%">
+      <thead><tr><th>Channel</th><th>Identity</th><th>Name seen</th><th>Messages</th><th>First seen</th><th>Last seen</th></tr></thead>
+      <tbody>{identity_rows}</tbody>
+    </table>
+  </div>
+
+  <form class="card p-22 mb-24" hx-ext="json-enc" hx-put="{base_url}/admin/contacts/{id}"
+    hx-target="{HASH}contact-save-result" hx-swap="innerHTML">
+    <label for="contact-name" class="eyebrow lbl">Name</label>
+    <input id="contact-name" class="input mb-12" type="text" name="name" maxlength="120" value="{name_value}">
+    <label for="contact-tags" class="eyebrow lbl">Tags</label>
+    <input id="contact-tags" class="input mb-4" type="text" name="tags" value="{tags_value}" placeholder="vip, wholesale">
+    <p class="muted fs-12 mb-12">Comma-separated, up to {MAX_TAGS}.</p>
+    <label for="contact-notes" class="eyebrow lbl">Private notes</label>
+    <textarea id="contact-notes" class="textarea" name="notes" rows="4" maxlength="{MAX_NOTES}">{notes}</textarea>
+    <p class="muted fs-12 mt-4">Only your team sees these. They are never sent to the AI.</p>
+    <div id="contact-save-result" role="status" aria-live="polite"></div>
+    <div class="row gap-8 mt-8" style="justify-content:flex-end">
+      <button class="btn primary" type="submit">Save</button>
+    </div>
+  </form>
+
+  {merge}
+</div>"##,
+        name = html_escape(&display_name(c)),
+        tags = tag_chips(&c.tags),
+        added = html_escape(short_date(&c.created_at)),
+        name_value = html_escape(c.name.as_deref().unwrap_or("")),
+        tags_value = html_escape(&c.tags.join(", ")),
+        notes = html_escape(&c.notes),
+    );
+    let page = app_shell(&body, "Contacts", base_url, locale);
+    base_html("Contact - Concierge", &page, locale)
+}
+
+fn identity_row_html(i: &ContactIdentity) -> String {
+    format!(
+        r#"<tr><td>{channel}</td><td class="mono fs-12">{identity}</td><td>{name}</td><td>{count}</td><td class="fs-12">{first}</td><td class="fs-12">{last}</td></tr>"#,
+        channel = i.channel.label(),
+        identity = html_escape(&i.identity),
+        name = html_escape(i.display_name.as_deref().unwrap_or("")),
+        count = i.message_count,
+        first = html_escape(short_date(&i.first_seen_at)),
+        last = html_escape(short_date(&i.last_seen_at)),
+    )
+}
+
+/// Pick another contact to fold into this one. Their identities, history
+/// references, tags and notes move here and the other contact is deleted.
+fn merge_form_html(c: &Contact, others: &[Contact], base_url: &str) -> String {
+    let options: String = others
+        .iter()
+        .filter(|o| o.id != c.id)
+        .map(|o| {
+            format!(
+                r#"<option value="{}">{}</option>"#,
+                html_escape(&o.id),
+                html_escape(&display_name(o))
+            )
+        })
+        .collect();
+    if options.is_empty() {
+        return String::new();
+    }
+    format!(
+        r##"<form class="card p-22" hx-ext="json-enc" hx-post="{base_url}/admin/contacts/{id}/merge"
+    hx-target="{HASH}contact-merge-result" hx-swap="innerHTML"
+    hx-confirm="Merge the selected contact into this one? This can't be undone.">
+    <label for="merge-from" class="eyebrow lbl">Merge a duplicate into this contact</label>
+    <div class="row gap-8" style="align-items:center;flex-wrap:wrap">
+      <select id="merge-from" class="select" name="from" style="max-width:320px">{options}</select>
+      <button class="btn ghost sm" type="submit">Merge</button>
+    </div>
+    <div id="contact-merge-result" role="status" aria-live="polite"></div>
+  </form>"##,
+        id = html_escape(&c.id),
+    )
+}
+
+fn tag_chips(tags: &[String]) -> String {
+    tags.iter()
+        .map(|t| format!(r#"<span class="chip">{}</span>"#, html_escape(t)))
+        .collect()
+}
+
+/// The contact's name, else the identity they last wrote from.
+fn display_name(c: &Contact) -> String {
+    c.name
+        .clone()
+        .or_else(|| c.latest_identity.clone())
+        .unwrap_or_else(|| "Unnamed contact".to_string())
+}
@@ -0,0 +1,131 @@
+//! `/admin/data-requests`: export or erase what's stored about one
+//! customer, with the receipts of past requests.
+
+use crate::data_requests::Receipt;
+use crate::helpers::html_escape;
+use crate::locale::Locale;
+use crate::types::{Channel, DataRequestKind};
+
+use super::base::{app_shell, base_html};
+use super::{short_date, HASH};
+
+pub fn data_requests_page_html(
+    channel: Option<&Channel>,
+    identity: &str,
+    receipts: &[Receipt],
+    base_url: &str,
+    locale: &Locale,
+) -> String {
+    let channel_options: String = [
+        Channel::WhatsApp,
+        Channel::Instagram,
+        Channel::Email,
+        Channel::Discord,
+    ]
+    .iter()
+    .map(|c| {
+        let selected = if Some(c) == channel { " selected" } else { "" };
+        format!(
+            r#"<option value="{}"{selected}>{}</option>"#,
+            c.as_str(),
+            c.label()
+        )
+    })
+    .collect();
+    let body = format!(
+        r##"<div class="page-pad">
+  <p><a href="{base_url}/admin/contacts" class="btn ghost sm">&larr; Contacts</a></p>
+  <h1 class="display-sm m-0 mb-4">Data requests</h1>
+  <p class="muted mb-16">When a customer asks what you hold about them, or asks you to delete it, enter the number, email or handle they contacted you from. If it belongs to a contact, the request covers all of that contact's identities.</p>
+  <form class="card p-22 mb-24" method="post" action="{base_url}/admin/data-requests/export">
+    <div class="row gap-8 mb-16" style="flex-wrap:wrap;align-items:flex-end">
+      <div>
+        <label for="dr-channel" class="eyebrow lbl">Channel</label>
+        <select id="dr-channel" class="select" name="channel" style="max-width:160px">{channel_options}</select>
+      </div>
+      <div style="flex:1;min-width:220px">
+        <label for="dr-identity" class="eyebrow lbl">Identifier</label>
+        <input id="dr-identity" class="input" name="identity" value="{identity}" required placeholder="Phone, email or handle">
+      </div>
+    </div>
+    <p class="muted fs-13 mb-16">The export covers logged messages, queued drafts, lead form submissions, retained conversations, the contact record, opt-outs, Discord relay contexts and email reply aliases. Erasing deletes all of it except the opt-out record, which is kept so the customer isn't messaged again.</p>
+    <div id="dr-result" role="status" aria-live="polite"></div>
+    <div class="row gap-8" style="justify-content:flex-end;flex-wrap:wrap">
+      <button class="btn ghost" type="button" hx-ext="json-enc" hx-post="{base_url}/admin/data-requests/erase"
+        hx-include="closest form" hx-target="{HASH}dr-result" hx-swap="innerHTML"
+        hx-confirm="This permanently deletes everything stored about this customer. Continue?">Erase data</button>
+      <button class="btn primary" type="submit">Download export</button>
+    </div>
+  </form>
+  {receipts}
+</div>"##,
+        identity = html_escape(identity),
+        receipts = receipts_html(receipts),
+    );
+    let page = app_shell(&body, "Contacts", base_url, locale);
+    base_html("Data requests - Concierge", &page, locale)
+}
+
+/// Confirmation shown once a request is carried out.
+pub fn receipt_html(r: &Receipt) -> String {
+    let records: String = if r.records.is_empty() {
+        r#"<li class="muted">Nothing was stored.</li>"#.to_string()
+    } else {
+        r.records
+            .iter()
+            .map(|(table, n)| {
+                format!(
+                    r#"<li><span class="mono fs-12">{}</span>: {n}</li>"#,
+                    html_escape(table)
+                )
+            })
+            .collect()
+    };
+    format!(
+        r#"<div class="success mb-16">
+  <strong>{kind} complete.</strong> Receipt <span class="mono fs-12">{id}</span>, {when}.
+  <ul class="fs-13 mt-8 mb-8">{records}</ul>
+  <div class="fs-12">SHA-256 of the records: <span class="mono">{digest}</span></div>
+</div>"#,
+        kind = r.kind.label(),
+        id = html_escape(&r.id),
+        when = html_escape(short_date(&r.created_at)),
+        digest = html_escape(&r.digest),
+    )
+}
+
+fn receipts_html(receipts: &[Receipt]) -> String {
+    let rows: String = if receipts.is_empty() {
+        r#"<tr><td colspan="5" class="muted">No requests yet.</td></tr>"#.to_string()
+    } else {
+        receipts
+            .iter()
+            .map(|r| {
+                let chip = match r.kind {
+                    DataRequestKind::Export => "chip",
+                    DataRequestKind::Erasure => "chip warn",
+                };
+                format!(
+                    r#"<tr><td class="fs-12">{when}</td><td><span class="{chip}">{kind}</span></td><td class="mono fs-12">{id}</td><td>{total} records, {identities} identities</td><td class="mono fs-12" title="{digest}">{digest_short}</td></tr>"#,
+                    when = html_escape(short_date(&r.created_at)),
+                    kind = r.kind.label(),
+                    id = html_escape(&r.id),
+                    total = r.total(),
+                    identities = r.identities,
+                    digest = html_escape(&r.digest),
+                    digest_short = html_escape(r.digest.get(..12).unwrap_or(&r.digest)),
+                )
+            })
+            .collect()
+    };
+    format!(
+        r##"<div class="card p-22">
+    <h2 class="m-0 mb-4">Receipts</h2>
+    <p class="muted fs-13 mb-12">Kept in the audit log. The customer's identifier is stored only as a hash, so an erasure receipt holds nothing about them.</p>
+    <table class="manage-table fs-13" style="width:100%">
+      <thead><tr><th>When</th><th>Request</th><th>Receipt</th><th>Covered</th><th>SHA-256</th></tr></thead>
+      <tbody>{rows}</tbody>
+    </table>
+  </div>"##
+    )
+}
@@ -171,6 +171,10 @@ pub fn manage_html(
       <small class="muted fs-12">{wait_help}</small>
     </div>
 
+    <div x-show="ar_enabled" x-cloak :aria-hidden="!ar_enabled">
+      {buffer_fields}
+    </div>
+
     {empty_note}
 
     <div class="row gap-8 mt-16" style="justify-content:flex-end">
@@ -199,6 +203,7 @@ pub fn manage_html(
         },
         ar_prompt = html_escape(cfg.auto_reply.default_text()),
         wait = cfg.auto_reply.wait_seconds,
+        buffer_fields = super::reply_buffer_fields_html(&cfg.auto_reply, "dc", locale),
         inbound_mentions = if cfg.inbound_mentions {
             "true"
         } else {
@@ -0,0 +1,307 @@
+//! `/admin/guardrails` — per-tenant output checks on AI drafts, a "try a
+//! draft" simulator that runs them without sending anything, the PII
+//! redaction settings with their audit counts, and what happens to
+//! messages the prompt-injection scan flags.
+
+use std::collections::BTreeMap;
+
+use crate::approvals::queue_reason_label;
+use crate::guardrails::{Hit, MAX_POLICY};
+use crate::helpers::html_escape;
+use crate::i18n::{t, t_args};
+use crate::locale::Locale;
+use crate::types::{GuardrailConfig, InjectionConfig, PiiClass, QueueReason, RedactionConfig};
+
+use super::base::{app_shell, base_html};
+use super::HASH;
+
+/// Days covered by the redaction and injection audit counts.
+pub const REDACTION_AUDIT_DAYS: u32 = 30;
+
+/// Longest holding reply the injection settings accept.
+pub const MAX_HOLDING_REPLY: usize = 500;
+
+#[allow(clippy::too_many_arguments)]
+pub fn guardrails_admin_html(
+    cfg: &GuardrailConfig,
+    redaction: &RedactionConfig,
+    redaction_totals: &BTreeMap<PiiClass, u32>,
+    injection: &InjectionConfig,
+    injection_totals: (u32, u32),
+    base_url: &str,
+    locale: &Locale,
+) -> String {
+    let checked = |on: bool| if on { " checked" } else { "" };
+    let redaction_card = redaction_html(redaction, redaction_totals, base_url, locale);
+    let injection_card = injection_html(injection, injection_totals, base_url, locale);
+
+    let body = format!(
+        r##"<div class="page-pad" hx-ext="json-enc">
+  <p><a href="{base_url}/admin/settings" class="btn ghost sm">{back}</a></p>
+  <h1 class="display-sm m-0 mb-4">{h1}</h1>
+  <p class="muted mb-16">{lead}</p>
+
+  <form class="card p-22 mb-24" hx-post="{base_url}/admin/guardrails" hx-target="{HASH}guardrails-result" hx-swap="innerHTML">
+    <div class="form-group">
+      <label><input type="checkbox" name="check_urls" value="true"{urls_checked}> {urls_lbl}</label>
+      <p class="muted fs-12 mt-4">{urls_help}</p>
+      <label for="gr-domains" class="eyebrow lbl mt-8">{domains_lbl}</label>
+      <textarea id="gr-domains" class="textarea mono" name="allowed_domains" rows="3" placeholder="example.com">{domains}</textarea>
+    </div>
+    <div class="form-group">
+      <label><input type="checkbox" name="check_contacts" value="true"{contacts_checked}> {contacts_lbl}</label>
+      <p class="muted fs-12 mt-4">{contacts_help}</p>
+      <label for="gr-phones" class="eyebrow lbl mt-8">{phones_lbl}</label>
+      <textarea id="gr-phones" class="textarea mono" name="business_phones" rows="2" placeholder="+91 98765 43210">{phones}</textarea>
+      <label for="gr-emails" class="eyebrow lbl mt-8">{emails_lbl}</label>
+      <textarea id="gr-emails" class="textarea mono" name="business_emails" rows="2" placeholder="hello@example.com">{emails}</textarea>
+    </div>
+    <div class="form-group">
+      <label><input type="checkbox" name="check_prompt_leak" value="true"{leak_checked}> {leak_lbl}</label>
+      <p class="muted fs-12 mt-4">{leak_help}</p>
+    </div>
+    <div class="form-group">
+      <label><input type="checkbox" name="llm_policy" value="true"{policy_checked}> {policy_lbl}</label>
+      <p class="muted fs-12 mt-4">{policy_help}</p>
+      <label for="gr-policy" class="eyebrow lbl mt-8">{policy_text_lbl}</label>
+      <textarea id="gr-policy" class="textarea" name="policy" rows="4" maxlength="{max_policy}" placeholder="{policy_ph}">{policy}</textarea>
+    </div>
+    <div id="guardrails-result" role="status" aria-live="polite"></div>
+    <div class="row gap-8 mt-16" style="justify-content:flex-end">
+      <button class="btn primary" type="submit">{save}</button>
+    </div>
+  </form>
+
+  {redaction_card}
+
+  {injection_card}
+
+  <h2 class="display-xs mb-8">{sim_h2}</h2>
+  <form class="card p-22" hx-post="{base_url}/admin/guardrails/simulate" hx-target="{HASH}guardrails-sim" hx-swap="innerHTML">
+    <p class="muted fs-13 mb-8">{sim_lead}</p>
+    <textarea class="textarea" name="draft" rows="5" maxlength="2000" placeholder="{sim_ph}" required aria-required="true"></textarea>
+    <div class="row gap-8 mt-8" style="justify-content:flex-end">
+      <button class="btn ghost" type="submit">{sim_run}</button>
+    </div>
+    <div id="guardrails-sim" class="mt-8" role="status" aria-live="polite"></div>
+  </form>
+</div>"##,
+        base_url = base_url,
+        HASH = HASH,
+        redaction_card = redaction_card,
+        injection_card = injection_card,
+        urls_checked = checked(cfg.check_urls),
+        contacts_checked = checked(cfg.check_contacts),
+        leak_checked = checked(cfg.check_prompt_leak),
+        policy_checked = checked(cfg.llm_policy),
+        domains = html_escape(&cfg.allowed_domains.join("\n")),
+        phones = html_escape(&cfg.business_phones.join("\n")),
+        emails = html_escape(&cfg.business_emails.join("\n")),
+        policy = html_escape(&cfg.policy),
+        max_policy = MAX_POLICY,
+        back = t(locale, "admin-guardrails-back"),
+        h1 = t(locale, "admin-guardrails-h1"),
+        lead = t(locale, "admin-guardrails-lead"),
+        urls_lbl = t(locale, "admin-guardrails-urls"),
+        urls_help = t(locale, "admin-guardrails-urls-help"),
+        domains_lbl = t(locale, "admin-guardrails-domains"),
+        contacts_lbl = t(locale, "admin-guardrails-contacts"),
+        contacts_help = t(locale, "admin-guardrails-contacts-help"),
+        phones_lbl = t(locale, "admin-guardrails-phones"),
+        emails_lbl = t(locale, "admin-guardrails-emails"),
+        leak_lbl = t(locale, "admin-guardrails-leak"),
+        leak_help = t(locale, "admin-guardrails-leak-help"),
+        policy_lbl = t(locale, "admin-guardrails-policy"),
+        policy_help = t(locale, "admin-guardrails-policy-help"),
+        policy_text_lbl = t(locale, "admin-guardrails-policy-text"),
+        policy_ph = html_escape(&t(locale, "admin-guardrails-policy-placeholder")),
+        save = t(locale, "admin-save"),
+        sim_h2 = t(locale, "admin-guardrails-sim-h2"),
+        sim_lead = t(locale, "admin-guardrails-sim-lead"),
+        sim_ph = html_escape(&t(locale, "admin-guardrails-sim-placeholder")),
+        sim_run = t(locale, "admin-guardrails-sim-run"),
+    );
+
+    let page = app_shell(&body, "Settings", base_url, locale);
+    base_html(&t(locale, "admin-guardrails-title"), &page, locale)
+}
+
+/// Which PII classes are masked before model calls, plus how many values
+/// each class caught recently.
+fn redaction_html(
+    cfg: &RedactionConfig,
+    totals: &BTreeMap<PiiClass, u32>,
+    base_url: &str,
+    locale: &Locale,
+) -> String {
+    let classes: String = PiiClass::ALL
+        .iter()
+        .map(|class| {
+            let count = totals.get(class).copied().unwrap_or(0);
+            format!(
+                r#"<label class="row gap-8" style="align-items:center"><input type="checkbox" name="pii_{wire}" value="true"{checked}> {label} <span class="muted fs-12">{count}</span></label>"#,
+                wire = class.as_str(),
+                checked = if cfg.classes.contains(class) { " checked" } else { "" },
+                label = class.label(),
+                count = count,
+            )
+        })
+        .collect();
+
+    format!(
+        r##"<h2 class="display-xs mb-8">{h2}</h2>
+  <form class="card p-22 mb-24" hx-post="{base_url}/admin/guardrails/redaction" hx-target="{HASH}redaction-result" hx-swap="innerHTML">
+    <p class="muted fs-13 mb-8">{lead}</p>
+    <div class="form-group">
+      <label><input type="checkbox" name="enabled" value="true"{enabled}> {enabled_lbl}</label>
+    </div>
+    <div class="form-group">
+      <p class="eyebrow lbl">{classes_lbl}</p>
+      {classes}
+    </div>
+    <div id="redaction-result" role="status" aria-live="polite"></div>
+    <div class="row gap-8 mt-16" style="justify-content:flex-end">
+      <button class="btn primary" type="submit">{save}</button>
+    </div>
+  </form>"##,
+        base_url = base_url,
+        HASH = HASH,
+        classes = classes,
+        enabled = if cfg.enabled { " checked" } else { "" },
+        h2 = t(locale, "admin-redaction-h2"),
+        lead = t(locale, "admin-redaction-lead"),
+        enabled_lbl = t(locale, "admin-redaction-enabled"),
+        classes_lbl = t_args(
+            locale,
+            "admin-redaction-classes",
+            &[("days", &REDACTION_AUDIT_DAYS.to_string())]
+        ),
+        save = t(locale, "admin-save"),
+    )
+}
+
+/// Holding reply, human relay and sender allowlist for messages the
+/// injection scan flags, with recent counts split by cause.
+fn injection_html(
+    cfg: &InjectionConfig,
+    (detected, failed): (u32, u32),
+    base_url: &str,
+    locale: &Locale,
+) -> String {
+    let checked = |on: bool| if on { " checked" } else { "" };
+    format!(
+        r##"<h2 class="display-xs mb-8">{h2}</h2>
+  <form class="card p-22 mb-24" hx-post="{base_url}/admin/guardrails/injection" hx-target="{HASH}injection-result" hx-swap="innerHTML">
+    <p class="muted fs-13 mb-8">{lead}</p>
+    <p class="fs-13 mb-8">{counts}</p>
+    <div class="form-group">
+      <label><input type="checkbox" name="send_holding_reply" value="true"{holding_checked}> {holding_lbl}</label>
+      <label for="inj-reply" class="eyebrow lbl mt-8">{reply_lbl}</label>
+      <textarea id="inj-reply" class="textarea" name="reply_text" rows="2" maxlength="{max_reply}">{reply_text}</textarea>
+    </div>
+    <div class="form-group">
+      <label><input type="checkbox" name="relay_to_human" value="true"{relay_checked}> {relay_lbl}</label>
+    </div>
+    <div class="form-group">
+      <label for="inj-allow" class="eyebrow lbl">{allow_lbl}</label>
+      <textarea id="inj-allow" class="textarea mono" name="allowed_senders" rows="3" placeholder="+91 98765 43210">{allowed}</textarea>
+      <p class="muted fs-12 mt-4">{allow_help}</p>
+    </div>
+    <div id="injection-result" role="status" aria-live="polite"></div>
+    <div class="row gap-8 mt-16" style="justify-content:flex-end">
+      <button class="btn primary" type="submit">{save}</button>
+    </div>
+  </form>"##,
+        base_url = base_url,
+        HASH = HASH,
+        holding_checked = checked(cfg.send_holding_reply),
+        relay_checked = checked(cfg.relay_to_human),
+        reply_text = html_escape(&cfg.reply_text),
+        max_reply = MAX_HOLDING_REPLY,
+        allowed = html_escape(&cfg.allowed_senders.join("\n")),
+        h2 = t(locale, "admin-injection-h2"),
+        lead = t(locale, "admin-injection-lead"),
+        counts = t_args(
+            locale,
+            "admin-injection-counts",
+            &[
+                ("days", &REDACTION_AUDIT_DAYS.to_string()),
+                ("detected", &detected.to_string()),
+                ("failed", &failed.to_string()),
+            ]
+        ),
+        holding_lbl = t(locale, "admin-injection-holding"),
+        reply_lbl = t(locale, "admin-injection-reply-text"),
+        relay_lbl = t(locale, "admin-injection-relay"),
+        allow_lbl = t(locale, "admin-injection-allowlist"),
+        allow_help = t(locale, "admin-injection-allowlist-help"),
+        save = t(locale, "admin-save"),
+    )
+}
+
+/// Simulator result: one line per check. `policy` is the model review's
+/// verdict, `None` when the tenant has it off.
+pub fn simulation_html(
+    cfg: &GuardrailConfig,
+    hits: &[Hit],
+    policy: Option<&Option<Hit>>,
+    locale: &Locale,
+) -> String {
+    let static_line = |reason: QueueReason, enabled: bool| {
+        let found: Vec<&Hit> = hits.iter().filter(|h| h.reason == reason).collect();
+        check_line(reason, enabled, &found, locale)
+    };
+
+    let policy_found: Vec<&Hit> = policy.and_then(|p| p.as_ref()).into_iter().collect();
+    let lines = [
+        static_line(QueueReason::GuardUnknownUrl, cfg.check_urls),
+        static_line(QueueReason::GuardForeignContact, cfg.check_contacts),
+        static_line(QueueReason::GuardPromptLeak, cfg.check_prompt_leak),
+        check_line(
+            QueueReason::GuardPolicy,
+            policy.is_some(),
+            &policy_found,
+            locale,
+        ),
+    ]
+    .concat();
+
+    let verdict = if hits.is_empty() && policy_found.is_empty() {
+        format!(
+            r#"<p class="m-0 mt-8"><span class="chip ok">{}</span></p>"#,
+            t(locale, "admin-guardrails-sim-send")
+        )
+    } else {
+        format!(
+            r#"<p class="m-0 mt-8"><span class="chip warn">{}</span></p>"#,
+            t(locale, "admin-guardrails-sim-queue")
+        )
+    };
+
+    format!(r#"<ul style="list-style:none;padding:0;margin:0">{lines}</ul>{verdict}"#)
+}
+
+fn check_line(reason: QueueReason, enabled: bool, found: &[&Hit], locale: &Locale) -> String {
+    let chip = if !enabled {
+        format!(
+            r#"<span class="chip">{}</span>"#,
+            t(locale, "admin-guardrails-sim-off")
+        )
+    } else if found.is_empty() {
+        format!(
+            r#"<span class="chip ok">{}</span>"#,
+            t(locale, "admin-guardrails-sim-pass")
+        )
+    } else {
+        let details: Vec<String> = found.iter().map(|h| html_escape(&h.detail)).collect();
+        format!(
+            r#"<span class="chip warn">{}</span> <span class="mono fs-12">{}</span>"#,
+            t(locale, "admin-guardrails-sim-flagged"),
+            details.join(", ")
+        )
+    };
+    format!(
+        r#"<li class="row gap-8 mb-4" style="align-items:center;flex-wrap:wrap"><span class="fs-13">{}</span>{chip}</li>"#,
+        queue_reason_label(reason)
+    )
+}
@@ -0,0 +1,267 @@
+//! `/admin/inbox`: every retained conversation across channels, one row per
+//! customer and channel, and a thread view with a reply box.
+//!
+//! Like the approvals page, lists refresh on an SSE ping from the tenant's
+//! `ApprovalsDO` (`inbox-changed`) with a 30s polling fallback.
+
+use crate::helpers::html_escape;
+use crate::inbox::{
+    Conversation, RetainedMessage, MAX_RETAINED_PER_CONVERSATION, RETENTION_WINDOWS,
+};
+use crate::locale::Locale;
+use crate::types::{ConversationStatus, MessageDirection};
+
+use super::base::{app_shell, base_html};
+use super::{short_date, HASH};
+
+/// Characters of the latest message shown in the list.
+const PREVIEW_CHARS: usize = 120;
+
+pub fn inbox_page_html(
+    conversations: &[Conversation],
+    retention_days: u32,
+    filter: Option<ConversationStatus>,
+    base_url: &str,
+    locale: &Locale,
+) -> String {
+    let retention = retention_card_html(retention_days, base_url);
+    let content = if retention_days > 0 {
+        format!(
+            r##"<div class="row gap-8 mb-16" style="flex-wrap:wrap">{filters}</div>
+  {list}"##,
+            filters = filter_links_html(filter, base_url),
+            list = inbox_list_html(conversations, filter),
+        )
+    } else {
+        String::new()
+    };
+
+    let body = format!(
+        r##"<div class="page-pad" hx-ext="sse" sse-connect="/admin/inbox/stream">
+  <h1 class="display-sm m-0 mb-4">Inbox</h1>
+  <p class="muted mb-16">Conversations from WhatsApp, Instagram, email and Discord in one place. Open one to read the thread and reply.</p>
+  {retention}
+  {content}
+</div>"##
+    );
+    let page = app_shell(&body, "Inbox", base_url, locale);
+    base_html("Inbox - Concierge", &page, locale)
+}
+
+/// The opt-in setting. Message text is only kept (and the inbox only
+/// fills) while a window is picked; turning it off deletes what was kept.
+fn retention_card_html(retention_days: u32, base_url: &str) -> String {
+    let lead = if retention_days > 0 {
+        format!(
+            "Keeping the text of new messages, encrypted, for {retention_days} days and up to the last {MAX_RETAINED_PER_CONVERSATION} per conversation. Older messages are deleted daily."
+        )
+    } else {
+        "Concierge stores only who wrote and when, never what they wrote. To read conversations here, turn on message history; only messages from then on are kept, encrypted, for the window you pick.".to_string()
+    };
+    let mut options = format!(
+        r#"<option value="0"{}>Off</option>"#,
+        if retention_days == 0 { " selected" } else { "" }
+    );
+    for days in RETENTION_WINDOWS {
+        let selected = if days == retention_days {
+            " selected"
+        } else {
+            ""
+        };
+        options.push_str(&format!(
+            r#"<option value="{days}"{selected}>{days} days</option>"#
+        ));
+    }
+    format!(
+        r##"<form class="card p-22 mb-24 row gap-16" hx-ext="json-enc" hx-put="{base_url}/admin/inbox/settings"
+    hx-confirm="Change message history? Turning it off deletes stored conversations."
+    style="align-items:center;justify-content:space-between;flex-wrap:wrap">
+    <p class="muted fs-13 m-0" style="flex:1;min-width:240px">{lead}</p>
+    <div class="row gap-8" style="align-items:center">
+      <label for="retention-days" class="eyebrow lbl m-0">Keep messages</label>
+      <select id="retention-days" class="select" name="retention_days">{options}</select>
+      <button class="btn primary sm" type="submit">Save</button>
+    </div>
+  </form>"##
+    )
+}
+
+fn filter_links_html(filter: Option<ConversationStatus>, base_url: &str) -> String {
+    let link = |status: Option<ConversationStatus>, label: &str| {
+        let href = match status {
+            Some(s) => format!("{base_url}/admin/inbox?status={}", s.as_str()),
+            None => format!("{base_url}/admin/inbox"),
+        };
+        let class = if status == filter {
+            "btn primary sm"
+        } else {
+            "btn ghost sm"
+        };
+        format!(r#"<a class="{class}" href="{href}">{label}</a>"#)
+    };
+    let mut out = link(None, "All");
+    for status in ConversationStatus::ALL {
+        out.push_str(&link(Some(status), status.label()));
+    }
+    out
+}
+
+/// The conversation list. Returned on its own by GET `/admin/inbox/list`,
+/// which the SSE event triggers; the `outerHTML` swap rebinds the trigger.
+pub fn inbox_list_html(
+    conversations: &[Conversation],
+    filter: Option<ConversationStatus>,
+) -> String {
+    let query = filter
+        .map(|s| format!("?status={}", s.as_str()))
+        .unwrap_or_default();
+    let inner = if conversations.is_empty() {
+        r##"<div class="card p-22 ta-center">
+  <p class="muted m-0">No conversations yet. New messages show up here as they arrive.</p>
+</div>"##
+            .to_string()
+    } else {
+        let rows: String = conversations.iter().map(conversation_row_html).collect();
+        format!(r##"<div class="card p-0" style="overflow:hidden">{rows}</div>"##)
+    };
+    format!(
+        r##"<div id="inbox-list"
+  role="region"
+  aria-live="polite"
+  aria-atomic="false"
+  hx-get="/admin/inbox/list{query}"
+  hx-trigger="sse:inbox-changed, every 30s"
+  hx-swap="outerHTML">
+  {inner}
+</div>"##
+    )
+}
+
+fn conversation_row_html(c: &Conversation) -> String {
+    let preview: String = c
+        .preview
+        .as_deref()
+        .unwrap_or("")
+        .chars()
+        .take(PREVIEW_CHARS)
+        .collect();
+    format!(
+        r##"<a class="approval-row" href="/admin/inbox/{id}" style="display:block;padding:14px 18px;border-bottom:1px solid var(--border);color:inherit;text-decoration:none">
+  <div class="row gap-8 mb-4" style="align-items:center;flex-wrap:wrap">
+    <strong>{who}</strong>
+    <span class="chip">{channel}</span>
+    {status}
+    <span class="muted fs-12">{when}</span>
+  </div>
+  <div class="muted fs-13" style="white-space:nowrap;overflow:hidden;text-overflow:ellipsis">{preview}</div>
+</a>"##,
+        id = html_escape(&c.id),
+        who = html_escape(&contact_label(c)),
+        channel = c.channel.label(),
+        status = status_chip(c.status),
+        when = html_escape(short_date(&c.last_message_at)),
+        preview = html_escape(&preview),
+    )
+}
+
+pub fn conversation_page_html(
+    c: &Conversation,
+    messages: &[RetainedMessage],
+    base_url: &str,
+    locale: &Locale,
+) -> String {
+    let id = html_escape(&c.id);
+    let body = format!(
+        r##"<div class="page-pad" hx-ext="sse" sse-connect="/admin/inbox/stream">
+  <p><a href="{base_url}/admin/inbox" class="btn ghost sm">&larr; Inbox</a></p>
+  <div class="row gap-8 mb-16" style="align-items:center;flex-wrap:wrap">
+    <h1 class="display-sm m-0">{who}</h1>
+    <span class="chip">{channel}</span>
+    {contact_link}
+  </div>
+  {thread}
+  <form class="card p-22 mt-16" hx-ext="json-enc" hx-post="{base_url}/admin/inbox/{id}/reply"
+    hx-target="{HASH}inbox-reply-result" hx-swap="innerHTML"
+    hx-on::after-request="if (event.detail.successful) this.reset()">
+    <label for="inbox-reply" class="eyebrow lbl">Reply on {channel}</label>
+    <textarea id="inbox-reply" class="textarea" name="text" rows="4" maxlength="2000" required aria-required="true"></textarea>
+    <div id="inbox-reply-result" role="status" aria-live="polite"></div>
+    <div class="row gap-8 mt-8" style="justify-content:flex-end">
+      <button class="btn primary" type="submit">Send</button>
+    </div>
+  </form>
+</div>"##,
+        who = html_escape(&contact_label(c)),
+        channel = c.channel.label(),
+        thread = thread_html(c, messages),
+        contact_link = c
+            .contact_id
+            .as_deref()
+            .map(|cid| format!(
+                r#"<a class="btn ghost sm" href="{base_url}/admin/contacts/{}">Contact</a>"#,
+                html_escape(cid)
+            ))
+            .unwrap_or_default(),
+    );
+    let page = app_shell(&body, "Inbox", base_url, locale);
+    base_html("Conversation - Concierge", &page, locale)
+}
+
+/// The message thread, oldest first. Returned on its own by GET
+/// `/admin/inbox/{id}/thread`; refetched on the SSE ping and after a reply.
+pub fn thread_html(c: &Conversation, messages: &[RetainedMessage]) -> String {
+    let id = html_escape(&c.id);
+    let inner = if messages.is_empty() {
+        r#"<p class="muted m-0">No retained messages in this conversation.</p>"#.to_string()
+    } else {
+        messages.iter().map(message_html).collect()
+    };
+    format!(
+        r##"<div id="inbox-thread"
+  role="log"
+  aria-live="polite"
+  hx-get="/admin/inbox/{id}/thread"
+  hx-trigger="sse:inbox-changed, inbox-replied from:body, every 30s"
+  hx-swap="outerHTML">
+  <div class="row gap-8 mb-8" style="align-items:center">{status}</div>
+  {inner}
+</div>"##,
+        status = status_chip(c.status),
+    )
+}
+
+fn message_html(m: &RetainedMessage) -> String {
+    let (align, who) = match m.direction {
+        MessageDirection::Inbound => ("margin-right:auto", "Customer"),
+        MessageDirection::Outbound => ("margin-left:auto", "Auto-reply"),
+        MessageDirection::Relay => ("margin-left:auto", "You"),
+    };
+    let who = match m.action.as_deref() {
+        Some("ai_approved") => "Approved draft",
+        _ => who,
+    };
+    format!(
+        r##"<div class="card p-12 mb-8" style="max-width:80%;{align}">
+  <div class="muted fs-12 mb-4">{who} · {when}</div>
+  <div class="fs-14" style="white-space:pre-wrap">{body}</div>
+</div>"##,
+        when = html_escape(short_date(&m.created_at)),
+        body = html_escape(&m.body),
+    )
+}
+
+fn status_chip(status: ConversationStatus) -> String {
+    let class = match status {
+        ConversationStatus::Open => "chip warn",
+        ConversationStatus::Queued => "chip",
+        ConversationStatus::AutoReplied | ConversationStatus::HumanHandled => "chip ok",
+    };
+    format!(r#"<span class="{class}">{}</span>"#, status.label())
+}
+
+fn contact_label(c: &Conversation) -> String {
+    match &c.contact_name {
+        Some(name) if !name.is_empty() => format!("{name} ({})", c.contact),
+        _ => c.contact.clone(),
+    }
+}
@@ -1,11 +1,14 @@
 //! Management panel templates: super-admin UI
 
+use crate::ai::provider::Tier;
 use crate::helpers::html_escape;
 use crate::locale::Locale;
+use crate::retention::Retention;
+use crate::tenant_deletion::TenantDeletion;
 use crate::types::*;
 
 use super::base::{base_html, brand_mark};
-use super::HASH;
+use super::{short_date, HASH};
 
 fn manage_shell(
     title: &str,
@@ -18,6 +21,9 @@ fn manage_shell(
         ("Dashboard", "/manage"),
         ("Tenants", "/manage/tenants"),
         ("Billing", "/manage/billing"),
+        ("Usage", "/manage/usage"),
+        ("Retention", "/manage/retention"),
+        ("Deletions", "/manage/deletions"),
         ("Audit Log", "/manage/audit"),
     ];
 
@@ -209,12 +215,38 @@ pub fn tenants_list_html(tenants: &[Tenant], base_url: &str, locale: &Locale) ->
     manage_shell("Tenants - Concierge", &content, "Tenants", base_url, locale)
 }
 
+/// `<option>`s for the tenant AI-provider pin. The empty value means "no
+/// pin" and clears the override.
+fn ai_provider_options(selected: Option<AiProvider>) -> String {
+    let mut out = format!(
+        r#"<option value=""{sel}>Operator default</option>"#,
+        sel = if selected.is_none() { " selected" } else { "" },
+    );
+    for p in AiProvider::SELECTABLE {
+        let sel = if selected == Some(*p) {
+            " selected"
+        } else {
+            ""
+        };
+        out.push_str(&format!(
+            r#"<option value="{val}"{sel}>{label}</option>"#,
+            val = p.as_str(),
+            label = p.label(),
+        ));
+    }
+    out
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn tenant_detail_html(
     tenant: &Tenant,
     wa: &[WhatsAppAccount],
     ig: &[InstagramAccount],
     addrs: &[EmailAddress],
     billing: &TenantBilling,
+    ai_pin: Option<&AiOverride>,
+    retention: &[Retention],
+    tokens_per_credit: i64,
     base_url: &str,
     locale: &Locale,
 ) -> String {
@@ -300,6 +332,20 @@ pub fn tenant_detail_html(
     </form>
   </div>
 
+  <div class="card p-18 mt-16">
+    <h3 class="mb-8">AI reply pricing</h3>
+    <p class="muted mb-12">Per reply charges the rule's tier cost. Per token charges one credit per {tokens_per_credit} reply tokens, rounded up.</p>
+    <div id="credit-mode-toast"></div>
+    <form hx-put="{base_url}/manage/tenants/{id}" hx-target="{hash}credit-mode-toast" hx-swap="innerHTML" hx-ext="json-enc">
+      <div class="row gap-12">
+        <select class="select" name="credit_mode" style="max-width:200px">
+          {credit_mode_options}
+        </select>
+        <button class="btn sm" type="submit">Update</button>
+      </div>
+    </form>
+  </div>
+
   <div class="card p-18 mt-16">
     <h3 class="mb-8">Grant reply-email addresses</h3>
     <p class="muted mb-12">Add to this tenant's reply-email quota. Current quota: <strong>{quota}</strong> address(es).</p>
@@ -311,9 +357,36 @@ pub fn tenant_detail_html(
       </div>
     </form>
   </div>
+
+  <div class="card p-18 mt-16">
+    <h3 class="mb-8">AI provider</h3>
+    <p class="muted mb-12">Pin this tenant to a provider. Blank models use the operator default for that provider; the operator fallback still applies on errors.</p>
+    <div id="ai-provider-toast"></div>
+    <form hx-post="{base_url}/manage/tenants/{id}/ai-provider" hx-target="{hash}ai-provider-toast" hx-swap="innerHTML" hx-ext="json-enc">
+      <div class="row gap-12 wrap">
+        <select class="select" name="provider" style="max-width:200px">
+          {provider_options}
+        </select>
+        <input class="input" name="model" placeholder="Reply model" value="{ai_model}" style="max-width:220px">
+        <input class="input" name="fast_model" placeholder="Fast model" value="{ai_fast_model}" style="max-width:220px">
+        <button class="btn sm" type="submit">Save</button>
+      </div>
+    </form>
+  </div>
+
+  <div class="card p-18 mt-16">
+    <h3 class="mb-8">Retention</h3>
+    <p class="muted mb-12">Override how long this tenant's logs are kept. Daily counts are kept regardless.</p>
+    <div id="retention-toast"></div>
+    <form hx-post="{base_url}/manage/tenants/{id}/retention" hx-target="{hash}retention-toast" hx-swap="innerHTML" hx-ext="json-enc">
+      {retention_fields}
+      <button class="btn sm" type="submit">Save</button>
+    </form>
+  </div>
 </div>"##,
         base_url = base_url,
         hash = HASH,
+        retention_fields = tenant_retention_fields(retention),
         id = html_escape(&tenant.id),
         email = html_escape(&tenant.email),
         name = html_escape(tenant.name.as_deref().unwrap_or("—")),
@@ -330,9 +403,28 @@ pub fn tenant_detail_html(
                 )
             })
             .collect::<String>(),
+        credit_mode_options = CreditMode::ALL
+            .iter()
+            .map(|m| {
+                let sel = if *m == tenant.credit_mode {
+                    " selected"
+                } else {
+                    ""
+                };
+                format!(
+                    r#"<option value="{val}"{sel}>{label}</option>"#,
+                    val = m.as_str(),
+                    label = m.label(),
+                )
+            })
+            .collect::<String>(),
+        tokens_per_credit = tokens_per_credit,
         wa_count = wa.len(),
         ig_count = ig.len(),
         domain_count = addrs.len(),
+        provider_options = ai_provider_options(ai_pin.map(|p| p.provider)),
+        ai_model = html_escape(ai_pin.and_then(|p| p.model.as_deref()).unwrap_or("")),
+        ai_fast_model = html_escape(ai_pin.and_then(|p| p.fast_model.as_deref()).unwrap_or("")),
         balance = billing.total_remaining(),
         quota = tenant.email_address_quota(),
         wa_list = if wa_list.is_empty() {
@@ -430,6 +522,316 @@ pub fn audit_html(log: &[serde_json::Value], base_url: &str, locale: &Locale) ->
     )
 }
 
+/// `<option>`s for a retention period. `default` adds a leading empty
+/// option standing for the operator default.
+fn retention_options(selected: Option<u32>, default: Option<u32>) -> String {
+    let mut out = match default {
+        Some(days) => format!(
+            r#"<option value=""{sel}>Default ({label})</option>"#,
+            sel = if selected.is_none() { " selected" } else { "" },
+            label = crate::retention::describe(days),
+        ),
+        None => String::new(),
+    };
+    for days in crate::retention::CHOICES {
+        out.push_str(&format!(
+            r#"<option value="{days}"{sel}>{label}</option>"#,
+            sel = if selected == Some(*days) {
+                " selected"
+            } else {
+                ""
+            },
+            label = crate::retention::describe(*days),
+        ));
+    }
+    out
+}
+
+fn tenant_retention_fields(retention: &[Retention]) -> String {
+    retention
+        .iter()
+        .filter(|r| crate::retention::overridable(r.table))
+        .map(|r| {
+            format!(
+                r##"<div class="row gap-12 mb-8">
+        <label class="fs-13" for="ret-{name}" style="min-width:200px">{label}</label>
+        <select class="select" id="ret-{name}" name="{name}" style="max-width:200px">{options}</select>
+      </div>"##,
+                name = r.table.name,
+                label = r.table.label,
+                options = retention_options(r.override_days, Some(r.default_days)),
+            )
+        })
+        .collect()
+}
+
+/// Operator defaults for the log tables. `rolled` is each table's first
+/// day not yet in the daily rollups.
+pub fn retention_html(
+    defaults: &[Retention],
+    rolled: &std::collections::BTreeMap<String, String>,
+    base_url: &str,
+    locale: &Locale,
+) -> String {
+    let rows: String = defaults
+        .iter()
+        .map(|r| {
+            let rolled_up = match rolled.get(r.table.name) {
+                Some(day) => format!("Before {}", html_escape(day)),
+                None => "Not yet".to_string(),
+            };
+            format!(
+                r##"<div class="rt-row" style="grid-template-columns:1fr 0.8fr 220px 0.8fr">
+  <div><label for="ret-{name}">{label}</label></div>
+  <div class="mono muted fs-11">{name}</div>
+  <div><select class="select" id="ret-{name}" name="{name}">{options}</select></div>
+  <div class="muted fs-13">{rolled_up}</div>
+</div>"##,
+                name = r.table.name,
+                label = r.table.label,
+                options = retention_options(Some(r.default_days), None),
+            )
+        })
+        .collect();
+
+    let content = format!(
+        r##"<div class="page-pad">
+  <div class="eyebrow">Retention</div>
+  <h2 class="display-sm" style="margin:4px 0 4px">Log retention</h2>
+  <p class="muted fs-13 mb-16">Rows older than these periods are pruned every night at 03:30 UTC, after their day has been counted into the daily rollups, so reports over longer ranges still add up. Tenants can be given their own periods on their page. Pending approvals are never pruned.</p>
+  <div id="toast" role="status" aria-live="polite"></div>
+  <form hx-post="{base_url}/manage/retention" hx-target="{HASH}toast" hx-swap="innerHTML" hx-ext="json-enc">
+    <div class="card" style="padding:0;overflow:hidden">
+      <div class="rt-head" style="grid-template-columns:1fr 0.8fr 220px 0.8fr">
+        <div>Log</div><div>Table</div><div>Keep for</div><div>Rolled up</div>
+      </div>
+      {rows}
+    </div>
+    <div class="mt-16"><button class="btn" type="submit">Save</button></div>
+  </form>
+</div>"##,
+    );
+
+    manage_shell(
+        "Retention - Concierge",
+        &content,
+        "Retention",
+        base_url,
+        locale,
+    )
+}
+
+const DELETION_COLUMNS: &str = "0.7fr 0.7fr 0.8fr 0.7fr 1fr 1fr 150px";
+
+pub fn deletions_html(jobs: &[TenantDeletion], base_url: &str, locale: &Locale) -> String {
+    let rows: String = jobs
+        .iter()
+        .map(|j| deletion_row_html(j, base_url))
+        .collect();
+    let empty = if jobs.is_empty() {
+        r##"<div class="muted p-20 ta-center">No tenants deleted yet.</div>"##
+    } else {
+        ""
+    };
+
+    let content = format!(
+        r##"<div class="page-pad">
+  <div class="eyebrow">Deletions</div>
+  <h2 class="display-sm" style="margin:4px 0 4px">Tenant deletions</h2>
+  <p class="muted fs-13 mb-16">Jobs the cron hasn't finished are resumed every 15 minutes. Verify looks for any D1 row or KV key still under the tenant id. Durable Objects can't be listed, so they're purged during deletion but not re-checked.</p>
+  <div class="card" style="padding:0;overflow:hidden">
+    <div class="rt-head" style="grid-template-columns:{DELETION_COLUMNS}">
+      <div>Requested</div><div>Tenant</div><div>By</div><div>Status</div><div>Removed</div><div>Verified</div><div></div>
+    </div>
+    {rows}{empty}
+  </div>
+</div>"##,
+    );
+
+    manage_shell(
+        "Deletions - Concierge",
+        &content,
+        "Deletions",
+        base_url,
+        locale,
+    )
+}
+
+/// Counts by report label, as a collapsed list.
+fn counts_html(summary: &str, counts: &std::collections::BTreeMap<String, u64>) -> String {
+    let items: String = counts
+        .iter()
+        .filter(|(_, n)| **n > 0)
+        .map(|(label, n)| {
+            format!(
+                r#"<li><span class="mono fs-11">{}</span>: {n}</li>"#,
+                html_escape(label)
+            )
+        })
+        .collect();
+    if items.is_empty() {
+        return format!(r#"<span class="fs-13">{summary}</span>"#);
+    }
+    format!(
+        r#"<details><summary class="fs-13">{summary}</summary><ul class="fs-12 mt-8">{items}</ul></details>"#
+    )
+}
+
+/// One job; the resume and verify buttons swap it in place.
+pub fn deletion_row_html(job: &TenantDeletion, base_url: &str) -> String {
+    let total_steps = crate::tenant_deletion::steps().len();
+    let status = match (job.status, &job.error) {
+        (DeletionStatus::Completed, _) => format!(
+            r#"<span class="chip ok">Completed</span><div class="mono muted fs-11">{}</div>"#,
+            html_escape(short_date(job.completed_at.as_deref().unwrap_or(""))),
+        ),
+        (DeletionStatus::Running, Some(e)) => format!(
+            r#"<span class="chip warn" title="{}">Retrying {}/{total_steps}</span>"#,
+            html_escape(e),
+            job.step,
+        ),
+        (DeletionStatus::Running, None) => format!(
+            r#"<span class="chip">Running {}/{total_steps}</span>"#,
+            job.step
+        ),
+    };
+    let removed = counts_html(
+        &format!("{} removed", job.report.total()),
+        &job.report.removed,
+    );
+    let verified = match (&job.verification, &job.verified_at) {
+        (Some(found), Some(at)) if found.is_empty() => format!(
+            r#"<span class="chip ok">Nothing left</span><div class="mono muted fs-11">{}</div>"#,
+            html_escape(short_date(at)),
+        ),
+        (Some(found), Some(at)) => format!(
+            r#"{}<div class="mono muted fs-11">{}</div>"#,
+            counts_html(
+                &format!(
+                    r#"<span class="chip warn">{} left</span>"#,
+                    found.values().sum::<u64>()
+                ),
+                found
+            ),
+            html_escape(short_date(at)),
+        ),
+        _ => r#"<span class="muted fs-13">Not yet</span>"#.to_string(),
+    };
+    let resume = if job.status == DeletionStatus::Running {
+        format!(
+            r##"<button class="btn ghost sm" hx-post="{base_url}/manage/deletions/{id}/resume" hx-target="closest .rt-row" hx-swap="outerHTML">Resume</button>"##,
+            id = html_escape(&job.id),
+        )
+    } else {
+        String::new()
+    };
+
+    format!(
+        r##"<div class="rt-row" style="grid-template-columns:{DELETION_COLUMNS}">
+  <div class="mono muted fs-11">{created}</div>
+  <div class="mono fs-11" title="{tenant}">{tenant_short}</div>
+  <div class="fs-13">{by}</div>
+  <div>{status}</div>
+  <div>{removed}</div>
+  <div>{verified}</div>
+  <div class="row gap-8">
+    {resume}
+    <button class="btn ghost sm" hx-post="{base_url}/manage/deletions/{id}/verify" hx-target="closest .rt-row" hx-swap="outerHTML">Verify</button>
+  </div>
+</div>"##,
+        created = html_escape(short_date(&job.created_at)),
+        tenant = html_escape(&job.tenant_id),
+        tenant_short = html_escape(job.tenant_id.get(..8).unwrap_or(&job.tenant_id)),
+        by = html_escape(&job.requested_by),
+        id = html_escape(&job.id),
+    )
+}
+
+/// Per-tenant model cost against revenue over the report window.
+pub fn usage_report_html(
+    rows: &[crate::management::usage::TenantUsage],
+    base_url: &str,
+    locale: &Locale,
+) -> String {
+    let body: String = rows
+        .iter()
+        .map(|r| {
+            let revenue = if r.revenue.is_empty() {
+                "—".to_string()
+            } else {
+                r.revenue
+                    .iter()
+                    .map(|(code, amount)| {
+                        html_escape(&crate::helpers::format_money_code(*amount, code))
+                    })
+                    .collect::<Vec<_>>()
+                    .join("<br>")
+            };
+            format!(
+                r##"<tr>
+  <td><a href="{base_url}/manage/tenants/{id}">{email}</a></td>
+  <td><span class="chip">{mode}</span></td>
+  <td class="ta-right mono">{calls}</td>
+  <td class="ta-right mono">{input}</td>
+  <td class="ta-right mono">{output}</td>
+  <td class="ta-right mono">{reply_cost}</td>
+  <td class="ta-right mono">{overhead_cost}</td>
+  <td class="ta-right mono"><strong>{total_cost}</strong></td>
+  <td class="ta-right mono">{revenue}</td>
+</tr>"##,
+                base_url = base_url,
+                id = html_escape(&r.tenant_id),
+                email = html_escape(&r.email),
+                mode = r.credit_mode.label(),
+                calls = crate::helpers::format_count(r.calls, locale),
+                input = crate::helpers::format_count(r.input_tokens, locale),
+                output = crate::helpers::format_count(r.output_tokens, locale),
+                reply_cost = format_micro_usd(r.reply_cost),
+                overhead_cost = format_micro_usd(r.overhead_cost),
+                total_cost = format_micro_usd(r.total_cost()),
+                revenue = revenue,
+            )
+        })
+        .collect();
+
+    let table = if rows.is_empty() {
+        r#"<div class="muted p-20 ta-center">No model calls or payments in this window.</div>"#
+            .to_string()
+    } else {
+        format!(
+            r#"<table class="manage-table fs-13" style="width:100%">
+    <thead><tr>
+      <th>Tenant</th><th>Pricing</th><th class="ta-right">Calls</th>
+      <th class="ta-right">Tokens in</th><th class="ta-right">Tokens out</th>
+      <th class="ta-right">Reply cost</th><th class="ta-right">Other cost</th>
+      <th class="ta-right">Total cost</th><th class="ta-right">Revenue</th>
+    </tr></thead>
+    <tbody>{body}</tbody>
+  </table>"#
+        )
+    };
+
+    let content = format!(
+        r##"<div class="page-pad">
+  <div class="eyebrow">Usage</div>
+  <h2 class="display-sm m-0 mt-4 mb-8">Model cost vs revenue</h2>
+  <p class="muted mb-16">Last {days} days. Cost is token counts at the rates on the billing page; "other" covers injection scans, safety checks, guardrail reviews and embeddings. Revenue is captured payments.</p>
+  <div class="card" style="padding:0;overflow:auto">
+  {table}
+  </div>
+</div>"##,
+        days = crate::management::usage::USAGE_REPORT_DAYS,
+        table = table,
+    );
+
+    manage_shell("Usage - Concierge", &content, "Usage", base_url, locale)
+}
+
+/// Micro-USD as dollars to four places: `12_345` → `$0.0123`.
+fn format_micro_usd(micro: i64) -> String {
+    format!("${}.{:04}", micro / 1_000_000, (micro % 1_000_000) / 100)
+}
+
 fn scheduled_grants_table(scheduled: &[crate::types::ScheduledGrant], base_url: &str) -> String {
     if scheduled.is_empty() {
         return r#"<p class="muted fs-13 m-0">No scheduled grants yet.</p>"#.to_string();
@@ -523,6 +925,22 @@ pub fn billing_overview_html(
         </label>
       </div>
 
+      <div class="eyebrow mb-4 mt-16">AI reply model tiers</div>
+      <p class="muted fs-13 mb-8">Credits one AI reply costs on each tier, and which tiers tenants may pick on their rules. Rules on a disallowed tier run on the first allowed one.</p>
+      <div class="row gap-12 wrap mb-12">
+        {tier_rows}
+      </div>
+
+      <div class="eyebrow mb-4 mt-16">Token metering</div>
+      <p class="muted fs-13 mb-8">What each tier costs you per million tokens, in micro-USD (1,000,000 = $1). Feeds the usage report only. Tenants on per-token pricing pay one credit per block of reply tokens below.</p>
+      <div class="row gap-12 wrap mb-12">
+        {token_rows}
+        <label style="min-width:200px">
+          <div class="eyebrow mb-4">Reply tokens per credit</div>
+          <input class="input mono" name="tokens_per_credit" type="number" min="1" required value="{tokens_per_credit}">
+        </label>
+      </div>
+
       <button class="btn sm mt-12" type="submit">Save settings</button>
     </form>
   </div>
@@ -565,6 +983,9 @@ pub fn billing_overview_html(
         hash = HASH,
         pricing_table = pricing_table,
         email_pack_size = cfg.email_pack_size,
+        tier_rows = model_tier_inputs(cfg),
+        token_rows = token_rate_inputs(cfg),
+        tokens_per_credit = cfg.tokens_per_credit,
         scheduled_rows = scheduled_rows,
         schedule_msg = schedule_msg,
     );
@@ -572,6 +993,71 @@ pub fn billing_overview_html(
     manage_shell("Billing - Concierge", &content, "Billing", base_url, locale)
 }
 
+/// One credits input + "allowed" checkbox per model tier. Field names are
+/// `<tier>_reply_credits` and `tier_allowed__<tier>`.
+fn model_tier_inputs(cfg: &crate::storage::Pricing) -> String {
+    ModelTier::ALL
+        .iter()
+        .map(|tier| {
+            let credits = match tier {
+                ModelTier::Fast => cfg.fast_reply_credits,
+                ModelTier::Quality => cfg.quality_reply_credits,
+            };
+            let checked = if cfg.allowed_tiers.contains(tier) {
+                " checked"
+            } else {
+                ""
+            };
+            format!(
+                r#"<label style="min-width:200px">
+  <div class="eyebrow mb-4">{label} tier · credits per reply</div>
+  <input class="input mono" name="{wire}_reply_credits" type="number" min="1" required value="{credits}">
+  <div class="row gap-6 mt-4 fs-13"><input type="checkbox" name="tier_allowed__{wire}" value="on"{checked}> Tenants may use</div>
+</label>"#,
+                label = model_tier_label(*tier),
+                wire = tier.as_str(),
+            )
+        })
+        .collect()
+}
+
+/// Input/output cost inputs per metering tier. Field names are
+/// `<tier>_input_rate` and `<tier>_output_rate`; embeddings have no
+/// output tokens.
+fn token_rate_inputs(cfg: &crate::storage::Pricing) -> String {
+    Tier::ALL
+        .iter()
+        .map(|tier| {
+            let rate = cfg.token_rate(*tier);
+            let output = if *tier == Tier::Embedding {
+                String::new()
+            } else {
+                format!(
+                    r#"<input class="input mono mt-4" name="{wire}_output_rate" type="number" min="0" required value="{output}" aria-label="{wire} output rate" placeholder="output">"#,
+                    wire = tier.as_str(),
+                    output = rate.output,
+                )
+            };
+            format!(
+                r#"<label style="min-width:200px">
+  <div class="eyebrow mb-4">{wire} · input / output</div>
+  <input class="input mono" name="{wire}_input_rate" type="number" min="0" required value="{input}" placeholder="input">
+  {output}
+</label>"#,
+                wire = tier.as_str(),
+                input = rate.input,
+            )
+        })
+        .collect()
+}
+
+fn model_tier_label(tier: ModelTier) -> &'static str {
+    match tier {
+        ModelTier::Fast => "Fast",
+        ModelTier::Quality => "Quality",
+    }
+}
+
 /// Build the per-(concept, currency) input grid plus the "add currency"
 /// row. Field names follow `<concept>__<CODE>` so the settings POST can
 /// dispatch to `upsert_pricing_amount` cell-by-cell.
@@ -742,6 +1228,7 @@ mod tests {
         let mut cfg = crate::storage::Pricing {
             email_pack_size: 7,
             amounts: std::collections::BTreeMap::new(),
+            ..Default::default()
         };
         cfg.amounts.insert((UnitPriceMilli, "INR".into()), 12_345);
         cfg.amounts.insert((UnitPriceMilli, "USD".into()), 234);
@@ -774,6 +1261,11 @@ mod tests {
         assert!(html.contains(r#"name="email_pack_size""#));
         assert!(html.contains(r#"value="7""#));
 
+        // Model-tier cost + allow-list inputs.
+        assert!(html.contains(r#"name="fast_reply_credits""#));
+        assert!(html.contains(r#"name="quality_reply_credits""#));
+        assert!(html.contains(r#"name="tier_allowed__fast" value="on" checked"#));
+
         // Currency column headers carry the rusty_money symbol + name.
         assert!(html.contains("INR"));
         assert!(html.contains("USD"));
@@ -792,4 +1284,43 @@ mod tests {
         assert!(html.contains("Recurring credit grants"));
         assert!(html.contains("No scheduled grants yet."));
     }
+
+    #[test]
+    fn usage_report_splits_reply_and_overhead_cost() {
+        use crate::ai::provider::Tier;
+        use crate::management::usage::summarize;
+        use crate::storage::{Pricing, UsageTotal};
+
+        let tenants = vec![Tenant {
+            id: "t1".into(),
+            email: "shop@example.com".into(),
+            credit_mode: CreditMode::PerToken,
+            ..Default::default()
+        }];
+        let total = |purpose: &str, tier, input| UsageTotal {
+            tenant_id: "t1".into(),
+            purpose: purpose.into(),
+            tier,
+            calls: 2,
+            input_tokens: input,
+            output_tokens: 0,
+        };
+        let totals = vec![
+            total("reply", Tier::Quality, 1_000_000),
+            total("scan", Tier::Fast, 1_000_000),
+        ];
+        let revenue = vec![("t1".to_string(), "INR".to_string(), 50_000)];
+        let rows = summarize(&tenants, &totals, &revenue, &Pricing::default());
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].calls, 4);
+        assert_eq!(rows[0].reply_cost, 270_000);
+        assert_eq!(rows[0].overhead_cost, 45_000);
+
+        let html = usage_report_html(&rows, "https://example.test", &Locale::default_inr());
+        assert!(html.contains("shop@example.com"));
+        assert!(html.contains("$0.2700"));
+        assert!(html.contains("$0.3150"));
+        assert!(html.contains("Per token"));
+    }
 }
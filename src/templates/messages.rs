@@ -0,0 +1,238 @@
+//! `/admin/messages`: the message log. A filter form over every logged
+//! message, newest first, with a "Load more" row that fetches the next
+//! page in place and a CSV link for the same filter.
+
+use crate::helpers::html_escape;
+use crate::locale::Locale;
+use crate::message_log::{Cursor, Filter, LogRow, Page, CSV_MAX_ROWS};
+use crate::types::{Channel, MessageAction, MessageDirection};
+
+use super::base::{app_shell, base_html};
+use super::short_date;
+
+/// A channel account the filter offers: `(channel_account_id, label)`.
+pub type AccountOption = (String, String);
+
+pub fn messages_page_html(
+    page: &Page,
+    filter: &Filter,
+    accounts: &[AccountOption],
+    rules: &[String],
+    base_url: &str,
+    locale: &Locale,
+) -> String {
+    let query = filter.query_string();
+    let table = if page.rows.is_empty() {
+        let text = if filter.is_empty() {
+            "No messages logged yet."
+        } else {
+            "No messages match these filters."
+        };
+        format!(r#"<div class="card p-22 ta-center"><p class="muted m-0">{text}</p></div>"#)
+    } else {
+        format!(
+            r#"<div class="card p-0" style="overflow-x:auto">
+    <table class="manage-table fs-13" style="width:100%">
+      <thead><tr><th>Time (UTC)</th><th>Channel</th><th>Direction</th><th>Customer</th><th>Action</th><th>Rule</th><th>Credits</th><th></th></tr></thead>
+      <tbody>{rows}</tbody>
+    </table>
+  </div>"#,
+            rows = message_rows_html(&page.rows, page.next.as_ref(), filter, accounts, base_url),
+        )
+    };
+    let clear = if filter.is_empty() {
+        String::new()
+    } else {
+        format!(r#"<a class="btn ghost sm" href="{base_url}/admin/messages">Clear</a>"#)
+    };
+    let contact_note = if filter.contact.is_empty() {
+        String::new()
+    } else {
+        format!(
+            r#"<input type="hidden" name="contact" value="{id}">
+    <a class="chip" href="{base_url}/admin/contacts/{id}">One contact</a>"#,
+            id = html_escape(&filter.contact),
+        )
+    };
+
+    let body = format!(
+        r##"<div class="page-pad">
+  <div class="row gap-8 mb-4" style="align-items:center;justify-content:space-between;flex-wrap:wrap">
+    <h1 class="display-sm m-0">Messages</h1>
+    <a class="btn ghost sm" href="{base_url}/admin/messages.csv{csv_query}">Export CSV</a>
+  </div>
+  <p class="muted mb-16">Every message logged on every channel, newest first. Message text isn't kept here; the inbox has conversations if you turned it on. Exports stop at {CSV_MAX_ROWS} rows.</p>
+  <form class="card p-16 mb-16" method="get" action="{base_url}/admin/messages">
+    <div style="display:grid;grid-template-columns:repeat(auto-fit,minmax(160px,1fr));gap:8px">
+      <select class="select" name="channel" aria-label="Channel">{channels}</select>
+      <select class="select" name="account" aria-label="Account">{accounts}</select>
+      <select class="select" name="direction" aria-label="Direction">{directions}</select>
+      <select class="select" name="action" aria-label="Action">{actions}</select>
+      <select class="select" name="rule" aria-label="Matched rule">{rules}</select>
+      <input class="input" type="search" name="who" value="{who}" placeholder="Phone, email or handle" aria-label="Customer">
+      <input class="input" type="date" name="from" value="{from}" aria-label="From">
+      <input class="input" type="date" name="to" value="{to}" aria-label="To">
+    </div>
+    <div class="row gap-8 mt-12" style="align-items:center;flex-wrap:wrap">
+      {contact_note}
+      <button class="btn primary sm" type="submit">Filter</button>
+      {clear}
+    </div>
+  </form>
+  {table}
+</div>"##,
+        csv_query = if query.is_empty() {
+            String::new()
+        } else {
+            format!("?{}", html_escape(&query))
+        },
+        channels = options(
+            "All channels",
+            Channel::ALL.iter().map(|c| (c.as_str(), c.label())),
+            filter.channel.as_ref().map(|c| c.as_str()).unwrap_or(""),
+        ),
+        accounts = options(
+            "All accounts",
+            accounts
+                .iter()
+                .map(|(id, label)| (id.as_str(), label.as_str())),
+            &filter.account,
+        ),
+        directions = options(
+            "Any direction",
+            MessageDirection::ALL
+                .iter()
+                .map(|d| (d.as_str(), d.label())),
+            filter.direction.map(|d| d.as_str()).unwrap_or(""),
+        ),
+        actions = options(
+            "Any action",
+            MessageAction::ALL.iter().map(|a| (a.as_str(), a.label())),
+            filter.action.map(|a| a.as_str()).unwrap_or(""),
+        ),
+        rules = options(
+            "Any rule",
+            rules.iter().map(|r| (r.as_str(), r.as_str())),
+            &filter.rule,
+        ),
+        who = html_escape(&filter.who),
+        from = html_escape(&filter.from),
+        to = html_escape(&filter.to),
+    );
+    let page = app_shell(&body, "Messages", base_url, locale);
+    base_html("Messages - Concierge", &page, locale)
+}
+
+/// `<option>`s with a leading "any" choice and `selected` on the current
+/// value.
+fn options<'a>(
+    any: &str,
+    values: impl Iterator<Item = (&'a str, &'a str)>,
+    selected: &str,
+) -> String {
+    let mut out = format!(r#"<option value="">{any}</option>"#);
+    for (value, label) in values {
+        let sel = if value == selected { " selected" } else { "" };
+        out.push_str(&format!(
+            r#"<option value="{}"{sel}>{}</option>"#,
+            html_escape(value),
+            html_escape(label)
+        ));
+    }
+    out
+}
+
+/// Table rows for a page, then a "Load more" row when there's another.
+/// GET `/admin/messages/rows` returns this to replace the old "Load more".
+pub fn message_rows_html(
+    rows: &[LogRow],
+    next: Option<&Cursor>,
+    filter: &Filter,
+    accounts: &[AccountOption],
+    base_url: &str,
+) -> String {
+    let mut out: String = rows
+        .iter()
+        .map(|r| message_row_html(r, accounts, base_url))
+        .collect();
+    if let Some(next) = next {
+        let query = filter.query_string();
+        let sep = if query.is_empty() { "" } else { "&" };
+        out.push_str(&format!(
+            r#"<tr id="messages-more"><td colspan="8" class="ta-center">
+  <button class="btn ghost sm" hx-get="{base_url}/admin/messages/rows?{query}{sep}after={after}" hx-target="closest tr" hx-swap="outerHTML">Load more</button>
+</td></tr>"#,
+            query = html_escape(&query),
+            after = html_escape(&urlencoding::encode(&next.encode())),
+        ));
+    }
+    out
+}
+
+fn message_row_html(r: &LogRow, accounts: &[AccountOption], base_url: &str) -> String {
+    let channel = Channel::from_wire(&r.channel)
+        .map(|c| c.label())
+        .unwrap_or(r.channel.as_str());
+    let account = accounts
+        .iter()
+        .find(|(id, _)| *id == r.channel_account_id)
+        .map(|(_, label)| label.as_str())
+        .unwrap_or(r.channel_account_id.as_str());
+    let direction = MessageDirection::from_wire(&r.direction);
+    // The customer is whoever isn't us: the sender of inbound rows, the
+    // recipient of replies.
+    let customer = match direction {
+        Some(MessageDirection::Inbound) => &r.sender,
+        _ => &r.recipient,
+    };
+    let customer_link = match &r.contact_id {
+        Some(contact) => format!(
+            r#"<a href="{base_url}/admin/contacts/{}">{}</a>"#,
+            html_escape(contact),
+            html_escape(customer)
+        ),
+        None => format!(
+            r#"<a href="{base_url}/admin/messages?who={}">{}</a>"#,
+            html_escape(&urlencoding::encode(customer)),
+            html_escape(customer)
+        ),
+    };
+    let action = match r.action_taken.as_deref() {
+        Some(wire) => {
+            let label = MessageAction::from_wire(wire)
+                .map(|a| a.label())
+                .unwrap_or(wire);
+            format!(r#"<span class="chip">{}</span>"#, html_escape(label))
+        }
+        None => String::new(),
+    };
+    let approval = match &r.approval_id {
+        Some(id) => format!(
+            r#"<a class="btn ghost sm" href="{base_url}/admin/approvals/{}">Approval &rarr;</a>"#,
+            html_escape(id)
+        ),
+        None => String::new(),
+    };
+    let credits = if r.credits == 0 {
+        String::new()
+    } else {
+        r.credits.to_string()
+    };
+    format!(
+        r#"<tr>
+  <td class="mono">{time}</td>
+  <td>{channel}<div class="muted fs-12">{account}</div></td>
+  <td>{direction}</td>
+  <td>{customer_link}</td>
+  <td>{action}</td>
+  <td>{rule}</td>
+  <td class="mono">{credits}</td>
+  <td>{approval}</td>
+</tr>"#,
+        time = html_escape(short_date(&r.created_at)),
+        channel = html_escape(channel),
+        account = html_escape(account),
+        direction = direction.map(|d| d.label()).unwrap_or(""),
+        rule = html_escape(r.rule_label.as_deref().unwrap_or("")),
+    )
+}
@@ -2,21 +2,39 @@
 
 mod admin;
 pub mod admin_email;
+pub mod agency;
+pub mod analytics;
 pub mod approvals;
 pub mod base;
 pub mod billing;
+pub mod brands;
+pub mod consent;
+pub mod contacts;
 pub mod credit_slider;
+pub mod data_requests;
 pub mod discord;
 pub mod email_landing;
 pub mod features;
+pub mod guardrails;
+pub mod inbox;
 mod lead_form;
 pub mod management;
+pub mod messages;
+pub mod notifications;
 pub mod onboarding;
 pub mod persona;
 pub mod rules;
+pub mod team;
+pub mod tools;
 
 pub use admin::*;
 pub use lead_form::*;
 
 /// Hash character constant for use in format strings (avoids escaping issues)
 pub(crate) const HASH: &str = "#";
+
+/// Short rendering of an ISO or SQLite timestamp: just the date+time
+/// prefix. Good enough for lists where age matters more than seconds.
+pub(crate) fn short_date(ts: &str) -> &str {
+    ts.get(..16).unwrap_or(ts)
+}
@@ -0,0 +1,156 @@
+//! `/admin/notifications`: the notification center. The list, newest first,
+//! with unread ones marked, and the per-kind email / Discord delivery
+//! choices. The bell in the app shell (`bell_html`) links here.
+
+use crate::helpers::html_escape;
+use crate::locale::Locale;
+use crate::notifications::Notification;
+use crate::types::{NotificationConfig, NotificationKind, NotificationSeverity};
+
+use super::base::{app_shell, base_html};
+use super::short_date;
+
+pub fn notifications_page_html(
+    rows: &[Notification],
+    config: &NotificationConfig,
+    discord_channel: bool,
+    base_url: &str,
+    locale: &Locale,
+) -> String {
+    let unread = rows.iter().filter(|n| n.read_at.is_none()).count();
+    let list = if rows.is_empty() {
+        r#"<div class="card p-22 ta-center"><p class="muted m-0">Nothing yet. Problems that need you and things worth knowing show up here.</p></div>"#.to_string()
+    } else {
+        let items: String = rows.iter().map(|n| row_html(n, base_url)).collect();
+        format!(
+            r#"<div class="card p-0"><ul class="m-0 p-0" style="list-style:none">{items}</ul></div>"#
+        )
+    };
+    let read_all = if unread == 0 {
+        String::new()
+    } else {
+        format!(
+            r#"<button class="btn ghost sm" hx-post="{base_url}/admin/notifications/read-all">Mark all read</button>"#
+        )
+    };
+    let discord_note = if discord_channel {
+        String::new()
+    } else {
+        format!(
+            r#"<p class="muted fs-13 mt-8">Discord copies go to your approvals channel. <a href="{base_url}/admin/discord">Connect Discord</a> to use them.</p>"#
+        )
+    };
+    let kinds: String = NotificationKind::ALL
+        .iter()
+        .map(|&kind| {
+            let checked = |on: bool| if on { " checked" } else { "" };
+            let discord = if kind.discord_deliverable() {
+                format!(
+                    r#"<input type="checkbox" name="discord_{wire}" value="true" aria-label="Post {label} to Discord"{on}>"#,
+                    wire = kind.as_str(),
+                    label = html_escape(kind.label()),
+                    on = checked(config.discord_kinds.contains(&kind)),
+                )
+            } else {
+                r#"<span class="muted fs-12">n/a</span>"#.to_string()
+            };
+            format!(
+                r#"<tr>
+  <td>{label}</td>
+  <td class="ta-center"><input type="checkbox" name="email_{wire}" value="true" aria-label="Email {label}"{email}></td>
+  <td class="ta-center">{discord}</td>
+</tr>"#,
+                label = html_escape(kind.label()),
+                wire = kind.as_str(),
+                email = checked(config.email_kinds.contains(&kind)),
+            )
+        })
+        .collect();
+
+    let body = format!(
+        r##"<div class="page-pad">
+  <div class="row gap-8 mb-4" style="align-items:center;justify-content:space-between;flex-wrap:wrap">
+    <h1 class="display-sm m-0">Notifications</h1>
+    {read_all}
+  </div>
+  <p class="muted mb-16">Things that need your attention, newest first. Opening one marks it read.</p>
+  {list}
+
+  <form class="card p-22 mt-24" hx-ext="json-enc" hx-post="{base_url}/admin/notifications/delivery">
+    <h2 class="fs-16 m-0 mb-4">Delivery</h2>
+    <p class="muted fs-13 mb-12">Every notification lands here. Pick the ones you also want by email or in Discord.</p>
+    <table class="manage-table fs-13" style="width:100%">
+      <thead><tr><th>Notification</th><th class="ta-center">Email</th><th class="ta-center">Discord</th></tr></thead>
+      <tbody>{kinds}</tbody>
+    </table>
+    {discord_note}
+    <button class="btn primary sm mt-12" type="submit">Save</button>
+  </form>
+</div>"##,
+    );
+    let page = app_shell(&body, "Notifications", base_url, locale);
+    base_html("Notifications - Concierge", &page, locale)
+}
+
+fn row_html(n: &Notification, base_url: &str) -> String {
+    let chip = match n.severity {
+        NotificationSeverity::Critical => r#"<span class="chip warn">Critical</span>"#,
+        NotificationSeverity::Warning => r#"<span class="chip warn">Warning</span>"#,
+        NotificationSeverity::Info => r#"<span class="chip">Info</span>"#,
+    };
+    let (weight, dot) = if n.read_at.is_none() {
+        (
+            "600",
+            r#"<span class="dot warn" aria-label="Unread"></span>"#,
+        )
+    } else {
+        ("400", "")
+    };
+    format!(
+        r#"<li class="p-16" style="border-bottom:1px solid var(--hair)">
+  <a href="{base_url}/admin/notifications/{id}/open" class="row gap-12" style="align-items:flex-start;text-decoration:none;color:inherit">
+    {dot}
+    <div style="flex:1">
+      <div class="row gap-8" style="flex-wrap:wrap">{chip}<span style="font-weight:{weight}">{title}</span></div>
+      <div class="muted fs-13 mt-4">{body}</div>
+    </div>
+    <div class="stack gap-4" style="align-items:flex-end">
+      <span class="muted fs-12 mono">{time}</span>
+      <span class="muted fs-12">{kind}</span>
+    </div>
+  </a>
+</li>"#,
+        id = html_escape(&n.id),
+        title = html_escape(&n.title),
+        body = html_escape(&n.body),
+        time = html_escape(short_date(&n.created_at)),
+        kind = n.kind.map(|k| k.label()).unwrap_or(""),
+    )
+}
+
+/// The bell's contents, returned by GET `/admin/notifications/bell`: the
+/// icon and, when something is unread, a count coloured by the most severe.
+pub fn bell_html(unread: u32, worst: Option<NotificationSeverity>, base_url: &str) -> String {
+    let badge = if unread == 0 {
+        String::new()
+    } else {
+        let class = match worst {
+            Some(NotificationSeverity::Warning | NotificationSeverity::Critical) => "chip warn",
+            _ => "chip",
+        };
+        let count = if unread > 99 {
+            "99+".to_string()
+        } else {
+            unread.to_string()
+        };
+        format!(r#"<span class="{class}" style="padding:0 6px;font-size:11px">{count}</span>"#)
+    };
+    let label = match unread {
+        0 => "Notifications".to_string(),
+        1 => "Notifications: 1 unread".to_string(),
+        n => format!("Notifications: {n} unread"),
+    };
+    format!(
+        r#"<a href="{base_url}/admin/notifications" class="row gap-4" aria-label="{label}" title="{label}" style="text-decoration:none;color:inherit">&#128276;{badge}</a>"#
+    )
+}
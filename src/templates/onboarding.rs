@@ -757,6 +757,16 @@ pub fn notifications_html(
       </div>
     </div>
 
+    <div class="card p-22 mb-16">
+      <div class="eyebrow mb-12">{summary_eyebrow}</div>
+      <label class="row gap-12" style="align-items:flex-start;cursor:pointer">
+        <input type="hidden" name="weekly_summary" value="false">
+        <input type="checkbox" name="weekly_summary" value="true"{summary_checked}>
+        <div><div class="fw-600">{summary_lbl}</div>
+        <div class="muted fs-13">{summary_sub}</div></div>
+      </label>
+    </div>
+
     <div class="between mt-36">
       <button type="button" class="btn ghost" hx-post="{base_url}/admin/wizard/goto" hx-vals='{{"to":"channels"}}' hx-target="body" hx-swap="innerHTML">{back}</button>
       <button type="submit" class="btn primary" :disabled="!approval.discord && !approval.email">{cont}</button>
@@ -782,6 +792,14 @@ pub fn notifications_html(
         cadence_prefix = t(locale, "wizard-notifications-cadence-prefix"),
         discord_missing = t(locale, "wizard-notifications-discord-missing"),
         discord_install = t(locale, "wizard-notifications-discord-install"),
+        summary_eyebrow = t(locale, "wizard-notifications-summary-eyebrow"),
+        summary_lbl = t(locale, "wizard-notifications-summary-label"),
+        summary_sub = t(locale, "wizard-notifications-summary-sub"),
+        summary_checked = if config.weekly_summary {
+            " checked"
+        } else {
+            ""
+        },
         back = t(locale, "wizard-back"),
         cont = t(locale, "wizard-notifications-continue"),
     );
@@ -1192,6 +1210,7 @@ mod pricing_tests {
         let mut p = crate::storage::Pricing {
             email_pack_size: pack,
             amounts: std::collections::BTreeMap::new(),
+            ..Default::default()
         };
         // INR side as overridden by the caller.
         p.amounts
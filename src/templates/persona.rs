@@ -6,14 +6,109 @@
 //! decides which source variant to construct.
 
 use crate::helpers::html_escape;
-use crate::i18n::t;
+use crate::i18n::{t, t_args};
 use crate::locale::Locale;
 use crate::personas;
-use crate::types::{PersonaConfig, PersonaPreset, PersonaSafetyStatus, PersonaSource};
+use crate::types::{
+    Brand, Channel, PersonaConfig, PersonaPreset, PersonaSafetyStatus, PersonaSource,
+};
 
 use super::base::{app_shell, base_html};
 
-pub fn persona_admin_html(persona: &PersonaConfig, base_url: &str, locale: &Locale) -> String {
+/// Whose persona the editor shows.
+pub enum PersonaScope<'a> {
+    Brand(&'a Brand),
+    /// A channel account; `own` is false while it uses its brand's persona.
+    Channel {
+        channel: &'a Channel,
+        account_id: &'a str,
+        brand: &'a Brand,
+        own: bool,
+    },
+}
+
+impl PersonaScope<'_> {
+    /// Query string that brings the editor back to this persona.
+    pub fn query(&self) -> String {
+        match self {
+            PersonaScope::Brand(brand) if brand.is_main() => String::new(),
+            PersonaScope::Brand(brand) => format!("?brand={}", urlencoding::encode(&brand.id)),
+            PersonaScope::Channel {
+                channel,
+                account_id,
+                ..
+            } => format!(
+                "?channel={}&account={}",
+                channel.as_str(),
+                urlencoding::encode(account_id)
+            ),
+        }
+    }
+}
+
+pub fn persona_admin_html(
+    persona: &PersonaConfig,
+    scope: &PersonaScope<'_>,
+    base_url: &str,
+    locale: &Locale,
+) -> String {
+    let query = html_escape(&scope.query());
+    // Extra brands and channel accounts post back to their own persona and
+    // name it on the page.
+    let brand_line = match scope {
+        PersonaScope::Brand(brand) if brand.is_main() => String::new(),
+        PersonaScope::Brand(brand) => format!(
+            r#"<p class="mb-12"><span class="chip">{}</span> <a class="fs-13" href="{base_url}/admin/brands">{}</a></p>"#,
+            html_escape(&t_args(
+                locale,
+                "admin-persona-brand",
+                &[("brand", brand.name())]
+            )),
+            t(locale, "admin-persona-brand-all"),
+        ),
+        PersonaScope::Channel {
+            channel,
+            account_id,
+            brand,
+            own,
+        } => {
+            let status = if *own {
+                format!(
+                    r#"<button type="button" class="btn ghost sm" hx-delete="{base_url}/admin/persona{query}" hx-confirm="{confirm}">{cta}</button>"#,
+                    confirm = html_escape(&t_args(
+                        locale,
+                        "admin-persona-channel-reset-confirm",
+                        &[("brand", brand.name())]
+                    )),
+                    cta = html_escape(&t_args(
+                        locale,
+                        "admin-persona-channel-reset",
+                        &[("brand", brand.name())]
+                    )),
+                )
+            } else {
+                format!(
+                    r#"<span class="muted fs-13">{}</span>"#,
+                    html_escape(&t_args(
+                        locale,
+                        "admin-persona-channel-inherited",
+                        &[("brand", brand.name())]
+                    ))
+                )
+            };
+            format!(
+                r#"<p class="mb-12 row gap-8" style="align-items:center;flex-wrap:wrap"><span class="chip">{label}</span> {status} <a class="fs-13" href="{base_url}/admin/rules/{slug}/{account}">{rules}</a></p>"#,
+                label = html_escape(&t_args(
+                    locale,
+                    "admin-persona-channel",
+                    &[("channel", channel.label())]
+                )),
+                slug = channel.as_str(),
+                account = html_escape(&urlencoding::encode(account_id)),
+                rules = t(locale, "admin-persona-channel-rules"),
+            )
+        }
+    };
     // Active mode + a shadow copy of every field so switching modes doesn't
     // lose user input.
     let (active_mode, active_preset_slug, builder, custom_prompt) = match &persona.source {
@@ -73,7 +168,7 @@ pub fn persona_admin_html(persona: &PersonaConfig, base_url: &str, locale: &Loca
   <p><a href="{base_url}/admin" class="btn ghost sm">{back}</a></p>
   <h1 class="display-sm m-0 mb-4">{h1}</h1>
   <p class="muted mb-16">{lead}</p>
-
+  {brand_line}
   {safety_badge}
 
   <div class="card p-22 mb-16">
@@ -84,7 +179,7 @@ pub fn persona_admin_html(persona: &PersonaConfig, base_url: &str, locale: &Loca
       <label class="row gap-6"><input type="radio" name="mode" value="custom" x-model="mode"> {mode_custom}</label>
     </div>
 
-    <form hx-post="{base_url}/admin/persona" hx-target="body" hx-swap="innerHTML">
+    <form hx-post="{base_url}/admin/persona{query}" hx-target="body" hx-swap="innerHTML">
       <input type="hidden" name="mode" :value="mode">
 
       <!-- PRESET -->
@@ -5,10 +5,12 @@
 
 use crate::handlers::admin_rules::ChannelRef;
 use crate::helpers::html_escape;
-use crate::i18n::t;
+use crate::i18n::{t, t_args};
 use crate::locale::Locale;
+use crate::storage::Pricing;
 use crate::types::{
-    default_match_threshold, ApprovalPolicy, ReplyConfig, ReplyMatcher, ReplyResponse, ReplyRule,
+    default_match_threshold, ApprovalPolicy, GenerationParams, ModelTier, ReplyConfig,
+    ReplyMatcher, ReplyResponse, ReplyRule, MAX_MAX_TOKENS, MAX_TEMPERATURE, MIN_MAX_TOKENS,
 };
 
 use super::base::{app_shell, base_html};
@@ -17,6 +19,7 @@ use super::HASH;
 pub fn rules_list_html(
     cfg: &ReplyConfig,
     channel: &ChannelRef<'_>,
+    pricing: &Pricing,
     base_url: &str,
     locale: &Locale,
 ) -> String {
@@ -29,7 +32,7 @@ pub fn rules_list_html(
         .rules
         .iter()
         .enumerate()
-        .map(|(i, rule)| rule_row_html(rule, i, last_idx, &rules_base, locale))
+        .map(|(i, rule)| rule_row_html(rule, i, last_idx, &rules_base, pricing, locale))
         .collect();
 
     let empty_note = if cfg.rules.is_empty() {
@@ -61,6 +64,12 @@ pub fn rules_list_html(
   <div class="card p-22 mb-24">
     {default_summary}
   </div>
+
+  <h2 class="display-xs mb-8">{persona_h2}</h2>
+  <div class="card p-22 mb-24 row gap-12" style="align-items:center;justify-content:space-between">
+    <span class="muted fs-13">{persona_state}</span>
+    <a class="btn ghost sm" href="{persona_url}">{persona_cta}</a>
+  </div>
 </div>"##,
         back = back,
         channel_label = channel_label,
@@ -74,6 +83,14 @@ pub fn rules_list_html(
         routing_h2 = t(locale, "admin-rules-list-routing-h2"),
         add = t(locale, "admin-rules-list-add"),
         default_h2 = t(locale, "admin-rules-list-default-h2"),
+        persona_h2 = t(locale, "admin-rules-list-persona-h2"),
+        persona_state = if cfg.persona.is_some() {
+            t(locale, "admin-rules-list-persona-own")
+        } else {
+            t(locale, "admin-rules-list-persona-inherited")
+        },
+        persona_url = html_escape(&channel.persona_url(base_url)),
+        persona_cta = t(locale, "admin-rules-list-persona-cta"),
     );
 
     let page = app_shell(&body, "Rules", base_url, locale);
@@ -85,6 +102,7 @@ fn rule_row_html(
     idx: usize,
     last_idx: usize,
     rules_base: &str,
+    pricing: &Pricing,
     locale: &Locale,
 ) -> String {
     let label = html_escape(&rule.label);
@@ -109,7 +127,10 @@ fn rule_row_html(
     };
     let response_chip = match &rule.response {
         ReplyResponse::Canned { .. } => format!(r#"<span class="chip">{chip_canned}</span>"#),
-        ReplyResponse::Prompt { .. } => format!(r#"<span class="chip ok">{chip_ai}</span>"#),
+        ReplyResponse::Prompt { .. } => format!(
+            r#"<span class="chip ok">{chip_ai}</span> <span class="chip">{}</span>"#,
+            tier_chip_text(&rule.generation, pricing, locale)
+        ),
     };
     let approval_chip = match (&rule.response, &rule.approval) {
         // Approval policy is irrelevant for canned text (no AI draft).
@@ -203,6 +224,27 @@ fn render_default_summary(default_rule: &ReplyRule, rules_base: &str, locale: &L
     )
 }
 
+/// "Fast · 1 cr" — the tier the pipeline will actually run (after the
+/// operator's allow-list) and what each reply costs.
+fn tier_chip_text(generation: &GenerationParams, pricing: &Pricing, locale: &Locale) -> String {
+    let tier = pricing.effective_tier(generation.tier);
+    t_args(
+        locale,
+        "admin-rules-chip-tier",
+        &[
+            ("tier", &tier_label(tier, locale)),
+            ("credits", &pricing.reply_credits(tier).to_string()),
+        ],
+    )
+}
+
+fn tier_label(tier: ModelTier, locale: &Locale) -> String {
+    match tier {
+        ModelTier::Fast => t(locale, "admin-rules-model-fast"),
+        ModelTier::Quality => t(locale, "admin-rules-model-quality"),
+    }
+}
+
 /// Title shown on the edit form. The default rule has fixed text since its
 /// matcher can't change; regular rules use their current label. Falls back
 /// to localized strings via Cow so the caller can use `&` against either
@@ -223,6 +265,7 @@ pub fn rule_form_html(
     base_url: &str,
     title: impl AsRef<str>,
     allow_no_gate: bool,
+    pricing: &Pricing,
     locale: &Locale,
 ) -> String {
     let rules_base = channel.rules_base(base_url);
@@ -243,6 +286,7 @@ pub fn rule_form_html(
             text: String::new(),
         },
         approval: crate::types::ApprovalPolicy::default(),
+        generation: GenerationParams::default(),
     });
 
     let label_val = html_escape(&initial.label);
@@ -334,6 +378,7 @@ pub fn rule_form_html(
         )
     };
 
+    let generation_block = generation_block_html(&initial.generation, pricing, locale);
     let approval_block = approval_block_html(approval_kind, allow_no_gate, locale);
     let no_gate_modal = no_gate_modal_html(locale);
 
@@ -361,6 +406,8 @@ pub fn rule_form_html(
       <p class="muted fs-12 mt-4" x-show="responseKind === 'prompt'" x-cloak :aria-hidden="responseKind !== 'prompt'">{resp_help}</p>
     </div>
 
+    {generation_block}
+
     {approval_block}
 
     <input type="hidden" name="approval_kind" :value="approvalKind">
@@ -382,6 +429,7 @@ pub fn rule_form_html(
         label_val = label_val,
         matcher_block = matcher_block,
         response_text = html_escape(&response_text),
+        generation_block = generation_block,
         approval_block = approval_block,
         no_gate_modal = no_gate_modal,
         x_data = build_x_data(
@@ -408,6 +456,91 @@ pub fn rule_form_html(
     base_html(&t(locale, "admin-rules-edit-title"), &page, locale)
 }
 
+/// Model tier + sampling knobs, only meaningful for AI responses. Tiers the
+/// operator has disabled aren't offered; a rule saved on one falls back to
+/// the first allowed tier here, same as the pipeline does.
+fn generation_block_html(
+    generation: &GenerationParams,
+    pricing: &Pricing,
+    locale: &Locale,
+) -> String {
+    let selected = pricing.effective_tier(generation.tier);
+    let tier_radios: String = pricing
+        .allowed_tiers
+        .iter()
+        .map(|&tier| {
+            let detail_key = match tier {
+                ModelTier::Fast => "admin-rules-model-fast-detail",
+                ModelTier::Quality => "admin-rules-model-quality-detail",
+            };
+            let cost = t_args(
+                locale,
+                "admin-rules-model-cost",
+                &[("credits", &pricing.reply_credits(tier).to_string())],
+            );
+            format!(
+                r#"<label class="row gap-6">
+      <input type="radio" name="model_tier" value="{value}"{checked}>
+      <span><strong>{label}</strong> <span class="chip">{cost}</span><br><span class="muted fs-12">{detail}</span></span>
+    </label>"#,
+                value = tier.as_str(),
+                checked = if tier == selected { " checked" } else { "" },
+                label = tier_label(tier, locale),
+                cost = html_escape(&cost),
+                detail = t(locale, detail_key),
+            )
+        })
+        .collect();
+
+    let temperature = generation
+        .temperature
+        .map(|v| v.to_string())
+        .unwrap_or_default();
+    let max_tokens = generation
+        .max_tokens
+        .map(|v| v.to_string())
+        .unwrap_or_default();
+
+    format!(
+        r##"<div class="form-group" x-show="responseKind === 'prompt'" x-cloak :aria-hidden="responseKind !== 'prompt'">
+  <label class="eyebrow lbl" id="rule-model-label">{eyebrow}</label>
+  <div class="col gap-8 mb-12" role="radiogroup" aria-labelledby="rule-model-label">
+    {tier_radios}
+  </div>
+  <div class="row gap-12" style="flex-wrap:wrap">
+    <div style="flex:1;min-width:180px">
+      <label for="rule-temperature" class="eyebrow lbl">{temp_lbl}</label>
+      <input id="rule-temperature" class="input" type="number" name="temperature" min="0" max="{max_temp}" step="0.1" value="{temperature}">
+      <p class="muted fs-12 mt-4">{temp_help}</p>
+    </div>
+    <div style="flex:1;min-width:180px">
+      <label for="rule-max-tokens" class="eyebrow lbl">{len_lbl}</label>
+      <input id="rule-max-tokens" class="input" type="number" name="max_tokens" min="{min_tokens}" max="{max_tokens_cap}" step="1" value="{max_tokens}">
+      <p class="muted fs-12 mt-4">{len_help}</p>
+    </div>
+  </div>
+</div>"##,
+        eyebrow = t(locale, "admin-rules-model-eyebrow"),
+        tier_radios = tier_radios,
+        temp_lbl = t(locale, "admin-rules-model-temperature"),
+        temp_help = t(locale, "admin-rules-model-temperature-help"),
+        max_temp = MAX_TEMPERATURE,
+        temperature = temperature,
+        len_lbl = t(locale, "admin-rules-model-max-tokens"),
+        len_help = t_args(
+            locale,
+            "admin-rules-model-max-tokens-help",
+            &[
+                ("min", &MIN_MAX_TOKENS.to_string()),
+                ("max", &MAX_MAX_TOKENS.to_string()),
+            ],
+        ),
+        min_tokens = MIN_MAX_TOKENS,
+        max_tokens_cap = MAX_MAX_TOKENS,
+        max_tokens = max_tokens,
+    )
+}
+
 fn approval_block_html(approval_kind: &str, allow_no_gate: bool, locale: &Locale) -> String {
     let no_gate_radio = if allow_no_gate {
         format!(
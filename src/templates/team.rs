@@ -0,0 +1,149 @@
+//! `/admin/team`: the owner's members page. Everyone with access, their
+//! role, outstanding invites with their links, and the invite form. Also
+//! the result page `/auth/join` shows when an invite can't be used.
+
+use crate::helpers::html_escape;
+use crate::i18n::t;
+use crate::locale::Locale;
+use crate::team::{invite_url, Member, INVITE_TTL_DAYS, MAX_MEMBERS};
+use crate::types::MemberRole;
+
+use super::base::{app_shell, base_html};
+use super::HASH;
+
+pub fn team_page_html(
+    owner_email: &str,
+    members: &[Member],
+    base_url: &str,
+    locale: &Locale,
+) -> String {
+    let rows: String = members
+        .iter()
+        .map(|m| member_row_html(m, base_url))
+        .collect();
+    let invite_form = if members.len() >= MAX_MEMBERS {
+        format!(
+            r#"<p class="muted m-0">You've reached {MAX_MEMBERS} members. Remove someone to invite another.</p>"#
+        )
+    } else {
+        format!(
+            r##"<form class="row gap-8" style="flex-wrap:wrap;align-items:center" hx-ext="json-enc" hx-post="{base_url}/admin/team/invite" hx-target="{HASH}team-toast" hx-swap="innerHTML">
+      <input class="input" type="email" name="email" required placeholder="frontdesk@example.com" aria-label="Email address" style="flex:2;min-width:220px">
+      <select class="select" name="role" aria-label="Role" style="flex:1;min-width:160px">{roles}</select>
+      <button class="btn primary sm" type="submit">Send invite</button>
+    </form>
+    <div id="team-toast" class="mt-8" role="status" aria-live="polite"></div>"##,
+            roles = role_options(MemberRole::Approver),
+        )
+    };
+    let role_help: String = MemberRole::ALL
+        .iter()
+        .map(|r| {
+            format!(
+                "<li><strong>{}</strong>: {}</li>",
+                r.label(),
+                r.description()
+            )
+        })
+        .collect();
+
+    let body = format!(
+        r##"<div class="page-pad">
+  <h1 class="display-sm m-0 mb-4">Team</h1>
+  <p class="muted mb-16">People who can sign in to this account with their own Google or Facebook login.</p>
+  <div class="card p-0 mb-24" style="overflow-x:auto">
+    <table class="manage-table fs-13" style="width:100%">
+      <thead><tr><th>Email</th><th>Role</th><th>Status</th><th></th></tr></thead>
+      <tbody>
+        <tr><td>{owner}</td><td>Owner</td><td><span class="chip ok">You</span></td><td></td></tr>
+        {rows}
+      </tbody>
+    </table>
+  </div>
+  <div class="card p-22">
+    <h2 class="fs-16 m-0 mb-4">Invite someone</h2>
+    <p class="muted fs-13 mb-12">We email them a link that works for {INVITE_TTL_DAYS} days. They accept by signing in as that address.</p>
+    {invite_form}
+    <ul class="muted fs-13 mt-16 mb-0">{role_help}</ul>
+  </div>
+</div>"##,
+        owner = html_escape(owner_email),
+    );
+    let page = app_shell(&body, "Settings", base_url, locale);
+    base_html("Team - Concierge", &page, locale)
+}
+
+fn role_options(selected: MemberRole) -> String {
+    MemberRole::INVITABLE
+        .iter()
+        .map(|r| {
+            let sel = if *r == selected { " selected" } else { "" };
+            format!(
+                r#"<option value="{}"{sel}>{}</option>"#,
+                r.as_str(),
+                r.label()
+            )
+        })
+        .collect()
+}
+
+fn member_row_html(m: &Member, base_url: &str) -> String {
+    let id = html_escape(&m.id);
+    let since = |at: &str| html_escape(at.get(..10).unwrap_or(at));
+    let (status, actions) = match (&m.accepted_at, &m.invite_token) {
+        (Some(at), _) => (
+            format!("Joined {}", since(at)),
+            format!(
+                r#"<button class="btn ghost sm text-warn" hx-delete="{base_url}/admin/team/{id}" hx-confirm="Remove {email}? They're signed out straight away.">Remove</button>"#,
+                email = html_escape(&m.email),
+            ),
+        ),
+        (None, token) => {
+            let copy = match token {
+                Some(token) => format!(
+                    r#"<button class="btn ghost sm copy-btn" data-copy-url="{}">Copy link</button>"#,
+                    html_escape(&invite_url(base_url, token))
+                ),
+                None => String::new(),
+            };
+            (
+                format!(
+                    r#"<span class="chip warn">Invited {}</span>"#,
+                    since(&m.created_at)
+                ),
+                format!(
+                    r#"{copy}
+    <button class="btn ghost sm" hx-post="{base_url}/admin/team/{id}/resend">Resend</button>
+    <button class="btn ghost sm text-warn" hx-delete="{base_url}/admin/team/{id}" hx-confirm="Withdraw the invite to {email}?">Withdraw</button>"#,
+                    email = html_escape(&m.email),
+                ),
+            )
+        }
+    };
+    format!(
+        r#"<tr id="member-{id}">
+  <td>{email}</td>
+  <td><select class="select" name="role" aria-label="Role for {email}" style="width:auto" hx-ext="json-enc" hx-post="{base_url}/admin/team/{id}/role" hx-trigger="change">{roles}</select></td>
+  <td>{status}</td>
+  <td class="row gap-6" style="justify-content:flex-end">{actions}</td>
+</tr>"#,
+        email = html_escape(&m.email),
+        roles = role_options(m.role),
+    )
+}
+
+/// Shown by `/auth/join` and the sign-in callback when an invite can't be
+/// used: expired, withdrawn, or accepted by a different address.
+pub fn team_join_result_html(message: &str, locale: &Locale) -> String {
+    let body = format!(
+        r#"<div class="page-pad ta-center">
+            <h1 class="display-md mb-12">{h1}</h1>
+            <p class="lead">{message}</p>
+            <p class="mt-16"><a class="btn ghost sm" href="/">{back}</a></p>
+        </div>"#,
+        message = html_escape(message),
+        h1 = t(locale, "team-join-h1"),
+        back = t(locale, "team-join-back"),
+    );
+    base_html(&t(locale, "team-join-title"), &body, locale)
+}
@@ -0,0 +1,205 @@
+//! `/admin/tools` — list of the tenant's lookup tools plus the add form.
+//! Secrets are write-only: the list shows everything except the secret.
+
+use crate::ai::tools::{
+    CALL_TIMEOUT_MS, MAX_CALLS_PER_REPLY, MAX_DESCRIPTION, MAX_RESPONSE_BYTES, MAX_TOOLS,
+    MIN_SECRET_LEN, SIGNATURE_HEADER, TIMESTAMP_HEADER,
+};
+use crate::helpers::html_escape;
+use crate::i18n::{t, t_args};
+use crate::locale::Locale;
+use crate::types::AiTool;
+
+use super::base::{app_shell, base_html};
+use super::HASH;
+
+const PARAMETERS_PLACEHOLDER: &str = r#"{
+  "type": "object",
+  "properties": {
+    "order_id": { "type": "string", "description": "Order number, e.g. 1234" }
+  },
+  "required": ["order_id"]
+}"#;
+
+pub fn tools_admin_html(tools: &[AiTool], base_url: &str, locale: &Locale) -> String {
+    let rows: String = tools
+        .iter()
+        .map(|tool| tool_row_html(tool, base_url, locale))
+        .collect();
+    let list = if tools.is_empty() {
+        format!(
+            r#"<p class="muted ta-center" style="padding:18px">{}</p>"#,
+            t(locale, "admin-tools-empty")
+        )
+    } else {
+        rows
+    };
+
+    let add_form = if tools.len() >= MAX_TOOLS {
+        format!(
+            r#"<p class="muted">{}</p>"#,
+            t_args(
+                locale,
+                "admin-tools-cap",
+                &[("max", &MAX_TOOLS.to_string())]
+            )
+        )
+    } else {
+        add_form_html(base_url, locale)
+    };
+
+    let body = format!(
+        r##"<div class="page-pad" hx-ext="json-enc">
+  <p><a href="{base_url}/admin/settings" class="btn ghost sm">{back}</a></p>
+  <h1 class="display-sm m-0 mb-4">{h1}</h1>
+  <p class="muted mb-16">{lead}</p>
+
+  <div class="card p-0 mb-24" style="overflow:hidden">
+    {list}
+  </div>
+
+  <h2 class="display-xs mb-8">{add_h2}</h2>
+  {add_form}
+
+  <h2 class="display-xs mb-8 mt-24">{verify_h2}</h2>
+  <div class="card p-22">
+    <p class="muted fs-13 mb-8">{verify_lead}</p>
+    <pre class="mono fs-12 m-0" style="white-space:pre-wrap">{ts_header}: &lt;unix seconds&gt;
+{sig_header}: sha256=&lt;hex HMAC-SHA256(secret, "&lt;timestamp&gt;.&lt;body&gt;")&gt;</pre>
+    <p class="muted fs-12 mt-8 m-0">{limits}</p>
+  </div>
+</div>"##,
+        base_url = base_url,
+        list = list,
+        add_form = add_form,
+        ts_header = TIMESTAMP_HEADER,
+        sig_header = SIGNATURE_HEADER,
+        back = t(locale, "admin-tools-back"),
+        h1 = t(locale, "admin-tools-h1"),
+        lead = t(locale, "admin-tools-lead"),
+        add_h2 = t(locale, "admin-tools-add-h2"),
+        verify_h2 = t(locale, "admin-tools-verify-h2"),
+        verify_lead = t(locale, "admin-tools-verify-lead"),
+        limits = t_args(
+            locale,
+            "admin-tools-limits",
+            &[
+                ("timeout", &(CALL_TIMEOUT_MS / 1000).to_string()),
+                ("bytes", &MAX_RESPONSE_BYTES.to_string()),
+                ("calls", &MAX_CALLS_PER_REPLY.to_string()),
+            ],
+        ),
+    );
+
+    let page = app_shell(&body, "Settings", base_url, locale);
+    base_html(&t(locale, "admin-tools-title"), &page, locale)
+}
+
+fn tool_row_html(tool: &AiTool, base_url: &str, locale: &Locale) -> String {
+    let id = html_escape(&tool.id);
+    let (state_chip, toggle) = if tool.enabled {
+        (
+            format!(
+                r#"<span class="chip ok">{}</span>"#,
+                t(locale, "admin-tools-chip-on")
+            ),
+            t(locale, "admin-tools-disable"),
+        )
+    } else {
+        (
+            format!(
+                r#"<span class="chip">{}</span>"#,
+                t(locale, "admin-tools-chip-off")
+            ),
+            t(locale, "admin-tools-enable"),
+        )
+    };
+    let schema = serde_json::to_string_pretty(&tool.parameters).unwrap_or_default();
+
+    format!(
+        r##"<div id="tool-{id}" style="padding:14px 18px;border-bottom:1px solid var(--border)">
+  <div class="row gap-8" style="align-items:center;flex-wrap:wrap">
+    <strong class="mono">{name}</strong>
+    {state_chip}
+    <span class="muted fs-12 mono">{url}</span>
+  </div>
+  <p class="muted fs-13 mt-4 mb-8">{description}</p>
+  <details class="mb-8">
+    <summary class="muted fs-12">{schema_lbl}</summary>
+    <pre class="mono fs-12 mt-4" style="white-space:pre-wrap">{schema}</pre>
+  </details>
+  <div class="row gap-6">
+    <button class="btn ghost sm" hx-post="{base_url}/admin/tools/{id}/toggle" hx-swap="none">{toggle}</button>
+    <button class="btn ghost sm text-warn"
+      hx-delete="{base_url}/admin/tools/{id}"
+      hx-confirm="{confirm}"
+      hx-target="{HASH}tool-{id}" hx-swap="outerHTML">{delete}</button>
+  </div>
+</div>"##,
+        id = id,
+        name = html_escape(&tool.name),
+        state_chip = state_chip,
+        url = html_escape(&tool.url),
+        description = html_escape(&tool.description),
+        schema_lbl = t(locale, "admin-tools-schema"),
+        schema = html_escape(&schema),
+        base_url = base_url,
+        toggle = toggle,
+        confirm = html_escape(&t(locale, "admin-tools-delete-confirm")),
+        delete = t(locale, "admin-tools-delete"),
+        HASH = HASH,
+    )
+}
+
+fn add_form_html(base_url: &str, locale: &Locale) -> String {
+    format!(
+        r##"<form class="card p-22" hx-post="{base_url}/admin/tools" hx-target="{HASH}tool-form-result" hx-swap="innerHTML">
+  <div class="form-group">
+    <label for="tool-name" class="eyebrow lbl">{name_lbl}</label>
+    <input id="tool-name" class="input mono" name="name" maxlength="40" pattern="[a-z][a-z0-9_]*" placeholder="order_status" required aria-required="true">
+    <p class="muted fs-12 mt-4">{name_help}</p>
+  </div>
+  <div class="form-group">
+    <label for="tool-description" class="eyebrow lbl">{desc_lbl}</label>
+    <input id="tool-description" class="input" name="description" maxlength="{max_desc}" placeholder="{desc_ph}" required aria-required="true">
+  </div>
+  <div class="form-group">
+    <label for="tool-url" class="eyebrow lbl">{url_lbl}</label>
+    <input id="tool-url" class="input mono" name="url" type="url" placeholder="https://shop.example.com/api/order-status" required aria-required="true">
+  </div>
+  <div class="form-group">
+    <label for="tool-parameters" class="eyebrow lbl">{params_lbl}</label>
+    <textarea id="tool-parameters" class="textarea mono" name="parameters" rows="8" placeholder="{params_ph}"></textarea>
+    <p class="muted fs-12 mt-4">{params_help}</p>
+  </div>
+  <div class="form-group">
+    <label for="tool-secret" class="eyebrow lbl">{secret_lbl}</label>
+    <input id="tool-secret" class="input mono" name="secret" type="password" minlength="{min_secret}" autocomplete="off" required aria-required="true">
+    <p class="muted fs-12 mt-4">{secret_help}</p>
+  </div>
+  <div id="tool-form-result" role="status" aria-live="polite"></div>
+  <div class="row gap-8 mt-16" style="justify-content:flex-end">
+    <button class="btn primary" type="submit">{save}</button>
+  </div>
+</form>"##,
+        base_url = base_url,
+        HASH = HASH,
+        max_desc = MAX_DESCRIPTION,
+        min_secret = MIN_SECRET_LEN,
+        params_ph = html_escape(PARAMETERS_PLACEHOLDER),
+        name_lbl = t(locale, "admin-tools-name"),
+        name_help = t(locale, "admin-tools-name-help"),
+        desc_lbl = t(locale, "admin-tools-description"),
+        desc_ph = t(locale, "admin-tools-description-placeholder"),
+        url_lbl = t(locale, "admin-tools-url"),
+        params_lbl = t(locale, "admin-tools-parameters"),
+        params_help = t(locale, "admin-tools-parameters-help"),
+        secret_lbl = t(locale, "admin-tools-secret"),
+        secret_help = t_args(
+            locale,
+            "admin-tools-secret-help",
+            &[("min", &MIN_SECRET_LEN.to_string())]
+        ),
+        save = t(locale, "admin-tools-save"),
+    )
+}
@@ -0,0 +1,844 @@
+//! Tenant deletion: remove everything stored for a tenant, and prove it.
+//!
+//! What a tenant owns is listed once, in the registries below: every D1
+//! table with a tenant column (`D1_TABLES`), every KV key family
+//! (`KV_FAMILIES`) and both Durable Object classes. A deletion is a
+//! `tenant_deletions` job that walks `steps()` in order and saves its
+//! position and report after each one. Every step is idempotent, so a job
+//! cut short (request timeout, a failing binding) is simply resumed by the
+//! 15-minute cron from the step it stopped at.
+//!
+//! The report counts what each step removed. Keys that are indexed by a
+//! record id rather than the tenant id (`wa_phone:`, `instagram_token:`,
+//! `tenant_export:`...) are found through the ids the first step takes an
+//! inventory of; those ids stay in the report so `verify` can look for the
+//! same keys again later. `verify` re-runs every D1 and KV match without
+//! deleting and stores what it still found.
+//!
+//! Kept on purpose: `audit_log` (the operator trail has no tenant column),
+//! `payments` rows with the tenant column cleared (accounting), and the
+//! job row itself, which holds only counts and ids.
+//!
+//! When adding tenant-scoped storage, add it to a registry here; the
+//! export (`exports`) and data requests (`data_requests`) keep their own
+//! lists of what's worth handing back.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+use worker::*;
+
+use crate::helpers::generate_id;
+use crate::storage::tenant_listed_ids;
+use crate::types::DeletionStatus;
+
+/// What happens to a tenant's rows in a D1 table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RowPolicy {
+    Delete,
+    /// Keep the row with the tenant column cleared.
+    Anonymize,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct D1Table {
+    pub name: &'static str,
+    pub column: &'static str,
+    pub policy: RowPolicy,
+}
+
+impl D1Table {
+    /// `d1:{table}`, with the column for a table matched on another one
+    /// too, so a table listed twice has two steps.
+    fn label(&self) -> String {
+        match self.column {
+            "tenant_id" | "id" => format!("d1:{}", self.name),
+            column => format!("d1:{}.{column}", self.name),
+        }
+    }
+}
+
+const fn table(name: &'static str) -> D1Table {
+    D1Table {
+        name,
+        column: "tenant_id",
+        policy: RowPolicy::Delete,
+    }
+}
+
+/// Every D1 table holding tenant rows, children before parents and the
+/// tenant itself last, so a half-finished job still has its tenant row.
+pub const D1_TABLES: &[D1Table] = &[
+    table("pending_approvals"),
+    table("conversation_messages"),
+    table("conversations"),
+    table("contact_identities"),
+    table("contacts"),
+    table("suppressions"),
+    table("consent_events"),
+    table("messages"),
+    table("whatsapp_messages"),
+    table("instagram_messages"),
+    table("lead_form_submissions"),
+    table("ai_usage"),
+    table("tenant_exports"),
+    table("tenant_retention"),
+    table("daily_rollups"),
+    table("weekly_summaries"),
+    table("notifications"),
+    table("tenant_members"),
+    // A client's link to its agency, then an agency's links to its clients
+    // and the invites it has out.
+    table("agency_clients"),
+    D1Table {
+        name: "agency_clients",
+        column: "agency_id",
+        policy: RowPolicy::Delete,
+    },
+    D1Table {
+        name: "agency_invites",
+        column: "agency_id",
+        policy: RowPolicy::Delete,
+    },
+    table("tenant_billing"),
+    D1Table {
+        name: "payments",
+        column: "tenant_id",
+        policy: RowPolicy::Anonymize,
+    },
+    D1Table {
+        name: "tenants",
+        column: "id",
+        policy: RowPolicy::Delete,
+    },
+];
+
+/// Records whose ids other KV keys are indexed by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Owned {
+    WhatsAppAccount,
+    InstagramAccount,
+    LeadForm,
+    Export,
+}
+
+impl Owned {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Owned::WhatsAppAccount => "whatsapp_account",
+            Owned::InstagramAccount => "instagram_account",
+            Owned::LeadForm => "lead_form",
+            Owned::Export => "export",
+        }
+    }
+}
+
+/// How a family of KV keys belongs to a tenant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KvFamily {
+    /// The single key `{prefix}{tenant_id}`.
+    Key(&'static str),
+    /// Every key starting `{prefix}{tenant_id}:`.
+    Under(&'static str),
+    /// Keys under the prefix whose value is the tenant id.
+    ValueIs(&'static str),
+    /// Keys under the prefix whose JSON value has the tenant's `tenant_id`.
+    JsonTenant(&'static str),
+    /// Keys under the prefix whose value is the id of an owned record.
+    ValueOwned(&'static str, Owned),
+    /// `{prefix}{id}` and everything under `{prefix}{id}:` for each owned
+    /// record.
+    KeyOwned(&'static str, Owned),
+    /// `{prefix}{id}` for each `tenant:{tenant_id}:{prefix}{id}` the tenant
+    /// lists. Must run before `Under("tenant:")` removes the list.
+    Listed(&'static str),
+}
+
+impl KvFamily {
+    pub fn prefix(self) -> &'static str {
+        match self {
+            KvFamily::Key(p)
+            | KvFamily::Under(p)
+            | KvFamily::ValueIs(p)
+            | KvFamily::JsonTenant(p)
+            | KvFamily::ValueOwned(p, _)
+            | KvFamily::KeyOwned(p, _)
+            | KvFamily::Listed(p) => p,
+        }
+    }
+}
+
+/// Every KV key family holding tenant data. Sessions go first so a
+/// deleted tenant is signed out before anything else happens.
+pub const KV_FAMILIES: &[KvFamily] = &[
+    KvFamily::ValueIs("session:"),
+    KvFamily::JsonTenant("session_member:"),
+    KvFamily::JsonTenant("session_agency:"),
+    KvFamily::Key("csrf:"),
+    // member_csrf:{tenant}:{member}
+    KvFamily::Under("member_csrf:"),
+    KvFamily::Key("onboarding:"),
+    KvFamily::Key("ai_override:"),
+    KvFamily::Key("ai_tools:"),
+    KvFamily::Key("guardrails:"),
+    KvFamily::Key("redaction:"),
+    KvFamily::Key("injection:"),
+    KvFamily::Key("consent:"),
+    KvFamily::Key("discord_config:"),
+    KvFamily::Key("email_addrs:"),
+    KvFamily::Key("brands:"),
+    KvFamily::Listed("conv:"),
+    KvFamily::Listed("email_reverse:"),
+    // tenant:{id}:whatsapp:*, :instagram:*, :lead_form:* and :credentials
+    KvFamily::Under("tenant:"),
+    KvFamily::ValueIs("email_addr:"),
+    KvFamily::ValueIs("wa_signup_state:"),
+    KvFamily::ValueIs("instagram_oauth_state:"),
+    KvFamily::ValueOwned("wa_phone:", Owned::WhatsAppAccount),
+    KvFamily::ValueOwned("ig_page:", Owned::InstagramAccount),
+    KvFamily::KeyOwned("instagram_token:", Owned::InstagramAccount),
+    KvFamily::KeyOwned("tenant_export:", Owned::Export),
+    KvFamily::KeyOwned("ratelimit:lead:", Owned::LeadForm),
+    KvFamily::JsonTenant("whatsapp:"),
+    KvFamily::JsonTenant("instagram:"),
+    KvFamily::JsonTenant("lead_form:"),
+    KvFamily::JsonTenant("discord_guild:"),
+    KvFamily::JsonTenant("discord_oauth_state:"),
+    KvFamily::JsonTenant("email_verify:"),
+];
+
+/// Record ids collected by the inventory step, from these JSON families.
+const OWNED_SOURCES: &[(&str, Owned)] = &[
+    ("whatsapp:", Owned::WhatsAppAccount),
+    ("instagram:", Owned::InstagramAccount),
+    ("lead_form:", Owned::LeadForm),
+];
+
+/// One step of a deletion job.
+#[derive(Clone, Copy, Debug)]
+pub enum Step {
+    /// Note the ids of owned records before their keys go.
+    Inventory,
+    /// Drop buffered messages in each conversation's `ReplyBufferDO`. Runs
+    /// before `messages` is deleted, since that's where the senders are.
+    ReplyBuffers,
+    /// Close the tenant's live approval and inbox streams.
+    Approvals,
+    Kv(KvFamily),
+    D1(&'static D1Table),
+}
+
+impl Step {
+    /// Key in the report and verification maps.
+    pub fn label(&self) -> String {
+        match self {
+            Step::Inventory => "inventory".to_string(),
+            Step::ReplyBuffers => "do:ReplyBufferDO".to_string(),
+            Step::Approvals => "do:ApprovalsDO".to_string(),
+            Step::Kv(f) => format!("kv:{}", f.prefix()),
+            Step::D1(t) => t.label(),
+        }
+    }
+}
+
+/// The steps of every job, in order. A job's `step` indexes this list, so
+/// a registry change shifts the position of running jobs; ship one when
+/// `/manage/deletions` shows none running.
+pub fn steps() -> Vec<Step> {
+    let mut steps = vec![Step::Inventory];
+    steps.extend(KV_FAMILIES.iter().take(1).map(|f| Step::Kv(*f)));
+    steps.push(Step::ReplyBuffers);
+    steps.push(Step::Approvals);
+    steps.extend(KV_FAMILIES.iter().skip(1).map(|f| Step::Kv(*f)));
+    steps.extend(D1_TABLES.iter().map(Step::D1));
+    steps
+}
+
+/// What a job removed.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct DeletionReport {
+    /// Ids of owned records, by `Owned::as_str`.
+    #[serde(default)]
+    pub owned: BTreeMap<String, Vec<String>>,
+    /// Keys, rows or Durable Objects removed, by `Step::label`. Anonymized
+    /// rows count as removed.
+    #[serde(default)]
+    pub removed: BTreeMap<String, u64>,
+}
+
+impl DeletionReport {
+    pub fn owned_ids(&self, kind: Owned) -> &[String] {
+        self.owned.get(kind.as_str()).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn total(&self) -> u64 {
+        self.removed.values().sum()
+    }
+}
+
+/// One row of `tenant_deletions`.
+#[derive(Debug, Clone)]
+pub struct TenantDeletion {
+    pub id: String,
+    pub tenant_id: String,
+    pub status: DeletionStatus,
+    pub step: usize,
+    pub report: DeletionReport,
+    pub error: Option<String>,
+    pub requested_by: String,
+    pub created_at: String,
+    pub completed_at: Option<String>,
+    /// What the last re-check still found, by `Step::label`. Empty means
+    /// nothing remained.
+    pub verification: Option<BTreeMap<String, u64>>,
+    pub verified_at: Option<String>,
+}
+
+fn row_to_deletion(row: &serde_json::Value) -> Option<TenantDeletion> {
+    let s = |k: &str| row.get(k).and_then(|v| v.as_str()).map(str::to_string);
+    Some(TenantDeletion {
+        id: s("id")?,
+        tenant_id: s("tenant_id")?,
+        status: DeletionStatus::from_wire(&s("status")?)?,
+        step: row.get("step").and_then(|v| v.as_u64()).unwrap_or(0) as usize,
+        report: s("report")
+            .and_then(|r| serde_json::from_str(&r).ok())
+            .unwrap_or_default(),
+        error: s("error"),
+        requested_by: s("requested_by").unwrap_or_default(),
+        created_at: s("created_at").unwrap_or_default(),
+        completed_at: s("completed_at"),
+        verification: s("verification").and_then(|v| serde_json::from_str(&v).ok()),
+        verified_at: s("verified_at"),
+    })
+}
+
+/// Deletion jobs, newest first.
+pub async fn list_deletions(db: &D1Database, limit: u32) -> Result<Vec<TenantDeletion>> {
+    let rows = db
+        .prepare("SELECT * FROM tenant_deletions ORDER BY created_at DESC LIMIT ?")
+        .bind(&[JsValue::from(limit as f64)])?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    Ok(rows.iter().filter_map(row_to_deletion).collect())
+}
+
+pub async fn get_deletion(db: &D1Database, id: &str) -> Result<Option<TenantDeletion>> {
+    let row = db
+        .prepare("SELECT * FROM tenant_deletions WHERE id = ?")
+        .bind(&[id.into()])?
+        .first::<serde_json::Value>(None)
+        .await?;
+    Ok(row.as_ref().and_then(row_to_deletion))
+}
+
+/// Milliseconds a request spends on a deletion before leaving the rest to
+/// the cron.
+pub const INLINE_BUDGET_MS: f64 = 10_000.0;
+
+/// Milliseconds the cron spends per tick across all running jobs.
+const SWEEP_BUDGET_MS: f64 = 5.0 * 60_000.0;
+
+/// Rows removed per D1 statement.
+const BATCH_ROWS: u32 = 500;
+
+/// Senders' reply buffers fetched per D1 page.
+const PAGE_SENDERS: u32 = 500;
+
+/// Start deleting a tenant, or return the job already running for it, and
+/// work on it for up to `INLINE_BUDGET_MS`.
+pub async fn start(env: &Env, tenant_id: &str, requested_by: &str) -> Result<TenantDeletion> {
+    let db = env.d1("DB")?;
+    let running = db
+        .prepare(
+            "SELECT id FROM tenant_deletions WHERE tenant_id = ? AND status = 'running' LIMIT 1",
+        )
+        .bind(&[tenant_id.into()])?
+        .first::<serde_json::Value>(None)
+        .await?
+        .and_then(|r| r.get("id").and_then(|v| v.as_str()).map(str::to_string));
+    let id = match running {
+        Some(id) => id,
+        None => {
+            let id = generate_id();
+            db.prepare(
+                "INSERT INTO tenant_deletions (id, tenant_id, requested_by) VALUES (?, ?, ?)",
+            )
+            .bind(&[id.as_str().into(), tenant_id.into(), requested_by.into()])?
+            .run()
+            .await?;
+            id
+        }
+    };
+    let mut job = get_deletion(&db, &id)
+        .await?
+        .ok_or_else(|| Error::from("Deletion job vanished"))?;
+    run(env, &mut job, js_sys::Date::now() + INLINE_BUDGET_MS).await?;
+    Ok(job)
+}
+
+/// Resume running jobs, oldest first. Runs on the 15-minute cron.
+pub async fn sweep(env: &Env) -> Result<()> {
+    let db = env.d1("DB")?;
+    let deadline = js_sys::Date::now() + SWEEP_BUDGET_MS;
+    let rows = db
+        .prepare("SELECT * FROM tenant_deletions WHERE status = 'running' ORDER BY created_at")
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    for mut job in rows.iter().filter_map(row_to_deletion) {
+        if js_sys::Date::now() >= deadline {
+            break;
+        }
+        run(env, &mut job, deadline).await?;
+    }
+    Ok(())
+}
+
+/// Work through the job's remaining steps until it completes, a step
+/// fails or `deadline` (epoch ms) passes. Progress is saved after every
+/// step; a failure is recorded on the job and left for the next run.
+pub async fn run(env: &Env, job: &mut TenantDeletion, deadline: f64) -> Result<()> {
+    let kv = env.kv("KV")?;
+    let db = env.d1("DB")?;
+    let steps = steps();
+    while job.step < steps.len() {
+        if js_sys::Date::now() >= deadline {
+            return Ok(());
+        }
+        let step = steps[job.step];
+        match run_step(env, &kv, &db, step, &job.tenant_id, &mut job.report).await {
+            Ok(()) => {
+                job.step += 1;
+                job.error = None;
+            }
+            Err(e) => {
+                console_log!("Deletion {} failed at {}: {e:?}", job.id, step.label());
+                job.error = Some(format!("{}: {e}", step.label()));
+            }
+        }
+        let done = job.step == steps.len();
+        if done {
+            job.status = DeletionStatus::Completed;
+        }
+        db.prepare(
+            "UPDATE tenant_deletions
+             SET status = ?, step = ?, report = ?, error = ?, updated_at = datetime('now'),
+                 completed_at = CASE WHEN ? THEN datetime('now') ELSE completed_at END
+             WHERE id = ?",
+        )
+        .bind(&[
+            job.status.as_str().into(),
+            JsValue::from(job.step as f64),
+            serde_json::to_string(&job.report)?.into(),
+            job.error.as_deref().map_or(JsValue::NULL, JsValue::from),
+            JsValue::from(done),
+            job.id.as_str().into(),
+        ])?
+        .run()
+        .await?;
+        if job.error.is_some() {
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+
+async fn run_step(
+    env: &Env,
+    kv: &kv::KvStore,
+    db: &D1Database,
+    step: Step,
+    tenant_id: &str,
+    report: &mut DeletionReport,
+) -> Result<()> {
+    let removed = match step {
+        Step::Inventory => {
+            report.owned = inventory(kv, db, tenant_id).await?;
+            return Ok(());
+        }
+        Step::ReplyBuffers => purge_reply_buffers(env, db, tenant_id).await?,
+        Step::Approvals => close_approval_streams(env, tenant_id).await?,
+        Step::Kv(family) => match_kv(kv, family, tenant_id, report, true).await?,
+        Step::D1(table) => match_d1(db, table, tenant_id, true).await?,
+    };
+    *report.removed.entry(step.label()).or_default() += removed;
+    Ok(())
+}
+
+async fn inventory(
+    kv: &kv::KvStore,
+    db: &D1Database,
+    tenant_id: &str,
+) -> Result<BTreeMap<String, Vec<String>>> {
+    let mut owned = BTreeMap::new();
+    for (prefix, kind) in OWNED_SOURCES {
+        let mut ids = Vec::new();
+        for key in list_keys(kv, prefix).await? {
+            if let Some(value) = kv
+                .get(&key)
+                .text()
+                .await
+                .map_err(|e| Error::from(e.to_string()))?
+            {
+                if json_names_tenant(&value, tenant_id) {
+                    ids.push(key[prefix.len()..].to_string());
+                }
+            }
+        }
+        owned.insert(kind.as_str().to_string(), ids);
+    }
+    let exports = db
+        .prepare("SELECT id FROM tenant_exports WHERE tenant_id = ?")
+        .bind(&[tenant_id.into()])?
+        .all()
+        .await?
+        .results::<serde_json::Value>()?;
+    owned.insert(
+        Owned::Export.as_str().to_string(),
+        exports
+            .iter()
+            .filter_map(|r| r.get("id").and_then(|v| v.as_str()).map(str::to_string))
+            .collect(),
+    );
+    Ok(owned)
+}
+
+/// Every KV key under `prefix`.
+async fn list_keys(kv: &kv::KvStore, prefix: &str) -> Result<Vec<String>> {
+    let mut keys = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let mut list = kv.list().prefix(prefix.to_string());
+        if let Some(c) = cursor.take() {
+            list = list.cursor(c);
+        }
+        let page = list
+            .execute()
+            .await
+            .map_err(|e| Error::from(e.to_string()))?;
+        keys.extend(page.keys.into_iter().map(|k| k.name));
+        match page.cursor {
+            Some(c) if !page.list_complete => cursor = Some(c),
+            _ => return Ok(keys),
+        }
+    }
+}
+
+/// Whether a KV value is a JSON object naming the tenant.
+fn json_names_tenant(value: &str, tenant_id: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(value)
+        .ok()
+        .and_then(|v| v.get("tenant_id")?.as_str().map(|t| t == tenant_id))
+        .unwrap_or(false)
+}
+
+/// Whether `key` is `{prefix}{id}` or under `{prefix}{id}:`.
+fn key_is_owned(key: &str, prefix: &str, id: &str) -> bool {
+    key.strip_prefix(prefix)
+        .and_then(|rest| rest.strip_prefix(id))
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(':'))
+}
+
+/// Count the family's keys for the tenant, deleting them when `delete`.
+async fn match_kv(
+    kv: &kv::KvStore,
+    family: KvFamily,
+    tenant_id: &str,
+    report: &DeletionReport,
+    delete: bool,
+) -> Result<u64> {
+    let get = |key: String| async move {
+        kv.get(&key)
+            .text()
+            .await
+            .map_err(|e| Error::from(e.to_string()))
+    };
+    let mut matched = Vec::new();
+    match family {
+        KvFamily::Key(prefix) => {
+            let key = format!("{prefix}{tenant_id}");
+            if get(key.clone()).await?.is_some() {
+                matched.push(key);
+            }
+        }
+        KvFamily::Under(prefix) => {
+            matched = list_keys(kv, &format!("{prefix}{tenant_id}:")).await?;
+        }
+        KvFamily::ValueIs(prefix) => {
+            for key in list_keys(kv, prefix).await? {
+                if get(key.clone()).await?.as_deref().map(str::trim) == Some(tenant_id) {
+                    matched.push(key);
+                }
+            }
+        }
+        KvFamily::JsonTenant(prefix) => {
+            for key in list_keys(kv, prefix).await? {
+                if let Some(value) = get(key.clone()).await? {
+                    if json_names_tenant(&value, tenant_id) {
+                        matched.push(key);
+                    }
+                }
+            }
+        }
+        KvFamily::ValueOwned(prefix, kind) => {
+            let ids = report.owned_ids(kind);
+            if !ids.is_empty() {
+                for key in list_keys(kv, prefix).await? {
+                    if let Some(value) = get(key.clone()).await? {
+                        if ids.iter().any(|id| id == value.trim()) {
+                            matched.push(key);
+                        }
+                    }
+                }
+            }
+        }
+        KvFamily::Listed(prefix) => {
+            for id in tenant_listed_ids(kv, tenant_id, prefix).await? {
+                let key = format!("{prefix}{id}");
+                if get(key.clone()).await?.is_some() {
+                    matched.push(key);
+                }
+            }
+        }
+        KvFamily::KeyOwned(prefix, kind) => {
+            for id in report.owned_ids(kind) {
+                let keys = list_keys(kv, &format!("{prefix}{id}")).await?;
+                matched.extend(keys.into_iter().filter(|k| key_is_owned(k, prefix, id)));
+            }
+        }
+    }
+    if delete {
+        for key in &matched {
+            kv.delete(key).await?;
+        }
+    }
+    Ok(matched.len() as u64)
+}
+
+/// Count the tenant's rows in `table`; with `apply`, delete or anonymize
+/// them a batch at a time and count those instead.
+async fn match_d1(db: &D1Database, table: &D1Table, tenant_id: &str, apply: bool) -> Result<u64> {
+    let D1Table {
+        name,
+        column,
+        policy,
+    } = *table;
+    if !apply {
+        let row = db
+            .prepare(format!(
+                "SELECT COUNT(*) AS n FROM {name} WHERE {column} = ?"
+            ))
+            .bind(&[tenant_id.into()])?
+            .first::<serde_json::Value>(None)
+            .await?;
+        return Ok(row
+            .and_then(|r| r.get("n").and_then(|v| v.as_u64()))
+            .unwrap_or(0));
+    }
+    let action = match policy {
+        RowPolicy::Delete => format!("DELETE FROM {name}"),
+        RowPolicy::Anonymize => format!("UPDATE {name} SET {column} = NULL"),
+    };
+    let mut total = 0;
+    loop {
+        let result = db
+            .prepare(format!(
+                "{action} WHERE rowid IN (SELECT rowid FROM {name} WHERE {column} = ?1 LIMIT ?2)"
+            ))
+            .bind(&[tenant_id.into(), JsValue::from(BATCH_ROWS as f64)])?
+            .run()
+            .await?;
+        let changed = result.meta()?.and_then(|m| m.changes).unwrap_or(0) as u64;
+        total += changed;
+        if changed < BATCH_ROWS as u64 {
+            return Ok(total);
+        }
+    }
+}
+
+/// Purge the reply buffer of every conversation the tenant has had. Buffers
+/// are keyed by sender, so the senders come from the message log and the
+/// approval queue. Returns how many buffers still held messages.
+async fn purge_reply_buffers(env: &Env, db: &D1Database, tenant_id: &str) -> Result<u64> {
+    let ns = env.durable_object("REPLY_BUFFER")?;
+    let mut offset = 0;
+    let mut purged = 0;
+    loop {
+        let page = db
+            .prepare(
+                "SELECT channel, sender FROM messages WHERE tenant_id = ?1 AND direction = 'inbound'
+                 UNION SELECT channel, sender FROM pending_approvals WHERE tenant_id = ?1
+                 ORDER BY channel, sender LIMIT ?2 OFFSET ?3",
+            )
+            .bind(&[
+                tenant_id.into(),
+                JsValue::from(PAGE_SENDERS as f64),
+                JsValue::from(offset as f64),
+            ])?
+            .all()
+            .await?
+            .results::<serde_json::Value>()?;
+        for row in &page {
+            let s = |k: &str| row.get(k).and_then(|v| v.as_str()).unwrap_or("");
+            // Same name as `pipeline::forward_to_buffer`.
+            let name = format!("{tenant_id}:{}:{}", s("channel"), s("sender"));
+            let stub = ns.id_from_name(&name)?.get_stub()?;
+            let mut init = RequestInit::new();
+            init.with_method(Method::Post);
+            let req = Request::new_with_init("https://buffer.do/purge", &init)?;
+            let mut resp = stub.fetch_with_request(req).await?;
+            if resp.text().await? == "purged" {
+                purged += 1;
+            }
+        }
+        if page.len() < PAGE_SENDERS as usize {
+            return Ok(purged);
+        }
+        offset += PAGE_SENDERS;
+    }
+}
+
+/// Disconnect the tenant's open approval and inbox streams. Returns how
+/// many were open.
+async fn close_approval_streams(env: &Env, tenant_id: &str) -> Result<u64> {
+    let ns = env.durable_object("APPROVALS_DO")?;
+    let stub = ns.id_from_name(tenant_id)?.get_stub()?;
+    let mut init = RequestInit::new();
+    init.with_method(Method::Post);
+    let req = Request::new_with_init("https://do.invalid/close", &init)?;
+    let mut resp = stub.fetch_with_request(req).await?;
+    Ok(resp.text().await?.trim().parse().unwrap_or(0))
+}
+
+/// Look for anything the registries still match for the job's tenant and
+/// store the result on the job. Durable Objects can't be listed, so they
+/// aren't re-checked: reply buffers drop their contents when they flush
+/// and the approval streams keep nothing.
+pub async fn verify(env: &Env, job: &mut TenantDeletion) -> Result<()> {
+    let kv = env.kv("KV")?;
+    let db = env.d1("DB")?;
+    let mut found = BTreeMap::new();
+    for step in steps() {
+        let n = match step {
+            Step::Kv(family) => match_kv(&kv, family, &job.tenant_id, &job.report, false).await?,
+            Step::D1(table) => match_d1(&db, table, &job.tenant_id, false).await?,
+            Step::Inventory | Step::ReplyBuffers | Step::Approvals => continue,
+        };
+        if n > 0 {
+            found.insert(step.label(), n);
+        }
+    }
+    db.prepare(
+        "UPDATE tenant_deletions SET verification = ?, verified_at = datetime('now') WHERE id = ?",
+    )
+    .bind(&[
+        serde_json::to_string(&found)?.into(),
+        job.id.as_str().into(),
+    ])?
+    .run()
+    .await?;
+    job.verification = Some(found);
+    job.verified_at = Some(crate::helpers::now_iso());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn step_labels_are_unique() {
+        let labels: Vec<String> = steps().iter().map(Step::label).collect();
+        let unique: std::collections::BTreeSet<&String> = labels.iter().collect();
+        assert_eq!(labels.len(), unique.len());
+    }
+
+    #[test]
+    fn steps_run_inventory_first_and_tenant_row_last() {
+        let steps = steps();
+        assert!(matches!(steps[0], Step::Inventory));
+        assert_eq!(steps[1].label(), "kv:session:");
+        assert_eq!(steps.last().unwrap().label(), "d1:tenants");
+        // Reply buffers are found through the message log, so they go
+        // before it does.
+        let at = |label: &str| steps.iter().position(|s| s.label() == label).unwrap();
+        assert!(at("do:ReplyBufferDO") < at("d1:messages"));
+        assert!(at("do:ReplyBufferDO") < at("d1:pending_approvals"));
+    }
+
+    #[test]
+    fn listed_families_run_before_their_lists_go() {
+        let pos = |f: KvFamily| KV_FAMILIES.iter().position(|k| *k == f).unwrap();
+        for family in KV_FAMILIES {
+            if matches!(family, KvFamily::Listed(_)) {
+                assert!(pos(*family) < pos(KvFamily::Under("tenant:")), "{family:?}");
+            }
+        }
+        assert!(KV_FAMILIES.contains(&KvFamily::Listed("conv:")));
+        assert!(KV_FAMILIES.contains(&KvFamily::Listed("email_reverse:")));
+    }
+
+    #[test]
+    fn owned_sources_are_deleted_families() {
+        for (prefix, _) in OWNED_SOURCES {
+            assert!(KV_FAMILIES.contains(&KvFamily::JsonTenant(prefix)));
+        }
+    }
+
+    #[test]
+    fn owned_keys_match_whole_ids_only() {
+        assert!(key_is_owned(
+            "instagram_token:ig1",
+            "instagram_token:",
+            "ig1"
+        ));
+        assert!(key_is_owned("tenant_export:e1:0", "tenant_export:", "e1"));
+        assert!(key_is_owned(
+            "ratelimit:lead:f1:203.0.113.9",
+            "ratelimit:lead:",
+            "f1"
+        ));
+        assert!(!key_is_owned(
+            "instagram_token:ig10",
+            "instagram_token:",
+            "ig1"
+        ));
+        assert!(!key_is_owned(
+            "tenant_export:e1:0",
+            "instagram_token:",
+            "e1"
+        ));
+    }
+
+    #[test]
+    fn json_values_match_on_tenant_id() {
+        assert!(json_names_tenant(r#"{"tenant_id":"t1","from":""}"#, "t1"));
+        assert!(!json_names_tenant(r#"{"tenant_id":"t10"}"#, "t1"));
+        assert!(!json_names_tenant(r#"{"id":"t1"}"#, "t1"));
+        assert!(!json_names_tenant("t1", "t1"));
+    }
+
+    #[test]
+    fn report_round_trips_and_totals() {
+        let mut report = DeletionReport::default();
+        report
+            .owned
+            .insert("export".into(), vec!["e1".into(), "e2".into()]);
+        report.removed.insert("d1:messages".into(), 40);
+        report.removed.insert("kv:session:".into(), 2);
+        let json = serde_json::to_string(&report).unwrap();
+        let back: DeletionReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, report);
+        assert_eq!(back.total(), 42);
+        assert_eq!(back.owned_ids(Owned::Export), ["e1", "e2"]);
+        assert!(back.owned_ids(Owned::LeadForm).is_empty());
+        // Reports written before a field existed still load.
+        assert_eq!(
+            serde_json::from_str::<DeletionReport>("{}").unwrap(),
+            DeletionReport::default()
+        );
+    }
+}
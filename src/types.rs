@@ -51,6 +51,44 @@ impl Plan {
     }
 }
 
+/// How a tenant's AI replies draw down credits. Canned replies are free
+/// in either mode.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CreditMode {
+    /// Fixed cost per reply, set per model tier in `pricing_config`.
+    #[default]
+    PerReply,
+    /// Reply tokens over `pricing_config.tokens_per_credit`, rounded up.
+    PerToken,
+}
+
+impl CreditMode {
+    pub const ALL: &'static [CreditMode] = &[CreditMode::PerReply, CreditMode::PerToken];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CreditMode::PerReply => "per_reply",
+            CreditMode::PerToken => "per_token",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CreditMode::PerReply => "Per reply",
+            CreditMode::PerToken => "Per token",
+        }
+    }
+
+    pub fn from_wire(s: &str) -> Option<Self> {
+        match s {
+            "per_reply" => Some(CreditMode::PerReply),
+            "per_token" => Some(CreditMode::PerToken),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct Tenant {
     pub id: String,
@@ -78,6 +116,13 @@ pub struct Tenant {
     /// amount; this flips on success and gates wizard "Finish".
     #[serde(default)]
     pub verified_at: Option<String>,
+    /// Operator-set; see `CreditMode`.
+    #[serde(default)]
+    pub credit_mode: CreditMode,
+    /// Tenant opt-in: days to keep encrypted message text for the web
+    /// inbox, one of `inbox::RETENTION_WINDOWS`. 0 = off, metadata only.
+    #[serde(default)]
+    pub retention_days: u32,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -96,6 +141,568 @@ fn default_locale() -> String {
     "en-IN".to_string()
 }
 
+// ============================================================================
+// AI Provider Types
+// ============================================================================
+
+/// Backend that answers chat and embedding calls. The operator picks the
+/// default (and an optional fallback) via `AI_PROVIDER` /
+/// `AI_FALLBACK_PROVIDER`; a tenant can be pinned to a different one from
+/// the management panel. `Stub` is a deterministic local backend for tests
+/// and `wrangler dev` without an AI binding — never offered to tenants.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AiProvider {
+    #[default]
+    WorkersAi,
+    #[serde(rename = "openai")]
+    OpenAi,
+    Stub,
+}
+
+impl AiProvider {
+    /// Providers an operator may pin a tenant to.
+    pub const SELECTABLE: &'static [AiProvider] = &[AiProvider::WorkersAi, AiProvider::OpenAi];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AiProvider::WorkersAi => "workers_ai",
+            AiProvider::OpenAi => "openai",
+            AiProvider::Stub => "stub",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AiProvider::WorkersAi => "Workers AI",
+            AiProvider::OpenAi => "OpenAI-compatible",
+            AiProvider::Stub => "Local stub",
+        }
+    }
+
+    pub fn from_wire(s: &str) -> Option<Self> {
+        match s {
+            "workers_ai" => Some(AiProvider::WorkersAi),
+            "openai" => Some(AiProvider::OpenAi),
+            "stub" => Some(AiProvider::Stub),
+            _ => None,
+        }
+    }
+}
+
+/// Per-tenant provider pin, stored at `ai_override:{tenant_id}` in KV.
+/// Empty model fields fall back to the operator's model for that provider.
+/// The operator fallback chain still applies when the pinned provider errors.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct AiOverride {
+    pub provider: AiProvider,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub fast_model: Option<String>,
+}
+
+// ============================================================================
+// AI Tool Types
+// ============================================================================
+
+/// A tenant-registered HTTP lookup the reply model may call ("where is
+/// order #1234?", "is 4pm free?"). Stored as a list at KV
+/// `ai_tools:{tenant_id}`. Calls are POSTed as JSON and signed with the
+/// tenant's HMAC secret, which is kept AES-GCM encrypted under
+/// `ENCRYPTION_KEY`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AiTool {
+    pub id: String,
+    /// Function name shown to the model: `[a-z0-9_]`, unique per tenant.
+    pub name: String,
+    pub description: String,
+    /// JSON Schema (`type: object`) for the call arguments.
+    pub parameters: serde_json::Value,
+    pub url: String,
+    pub secret_encrypted: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    pub created_at: String,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Per-tenant post-generation checks, run on `Auto` AI drafts after the
+/// keyword risk gate. Stored at KV `guardrails:{tenant_id}`; a missing key
+/// means the defaults (everything but the model review on, empty lists).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct GuardrailConfig {
+    /// Queue drafts linking anywhere outside `allowed_domains`.
+    pub check_urls: bool,
+    /// Hostnames (subdomains included) drafts may link to.
+    pub allowed_domains: Vec<String>,
+    /// Queue drafts quoting phone numbers / emails other than the ones below.
+    pub check_contacts: bool,
+    pub business_phones: Vec<String>,
+    pub business_emails: Vec<String>,
+    /// Queue drafts that echo the persona or rule prompt.
+    pub check_prompt_leak: bool,
+    /// Ask the fast model whether the draft breaks `policy`. Off by
+    /// default: it's an extra model call per draft.
+    pub llm_policy: bool,
+    /// Tenant-specific rules for the model review, in plain language.
+    pub policy: String,
+}
+
+impl Default for GuardrailConfig {
+    fn default() -> Self {
+        Self {
+            check_urls: true,
+            allowed_domains: Vec::new(),
+            check_contacts: true,
+            business_phones: Vec::new(),
+            business_emails: Vec::new(),
+            check_prompt_leak: true,
+            llm_policy: false,
+            policy: String::new(),
+        }
+    }
+}
+
+/// A kind of personal data masked out of inbound text before any model
+/// call. Indian identifiers are first-class alongside the generic ones.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum PiiClass {
+    Email,
+    Phone,
+    /// Payment card number (13-19 digits, Luhn-valid).
+    Card,
+    /// 12-digit Aadhaar number (Verhoeff-valid).
+    Aadhaar,
+    /// Income-tax PAN, e.g. ABCPE1234F.
+    Pan,
+    /// UPI VPA, e.g. name@okicici.
+    Upi,
+    /// Indian passport number, e.g. K1234567.
+    Passport,
+    /// Voter ID (EPIC), e.g. ABC1234567.
+    VoterId,
+    /// Six-digit PIN code — the part of a postal address we can spot
+    /// reliably. Off by default: prices and order numbers collide.
+    Pincode,
+}
+
+impl PiiClass {
+    pub const ALL: &'static [PiiClass] = &[
+        PiiClass::Email,
+        PiiClass::Phone,
+        PiiClass::Card,
+        PiiClass::Aadhaar,
+        PiiClass::Pan,
+        PiiClass::Upi,
+        PiiClass::Passport,
+        PiiClass::VoterId,
+        PiiClass::Pincode,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PiiClass::Email => "email",
+            PiiClass::Phone => "phone",
+            PiiClass::Card => "card",
+            PiiClass::Aadhaar => "aadhaar",
+            PiiClass::Pan => "pan",
+            PiiClass::Upi => "upi",
+            PiiClass::Passport => "passport",
+            PiiClass::VoterId => "voter_id",
+            PiiClass::Pincode => "pincode",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PiiClass::Email => "Email addresses",
+            PiiClass::Phone => "Phone numbers",
+            PiiClass::Card => "Card numbers",
+            PiiClass::Aadhaar => "Aadhaar numbers",
+            PiiClass::Pan => "PAN",
+            PiiClass::Upi => "UPI IDs",
+            PiiClass::Passport => "Passport numbers",
+            PiiClass::VoterId => "Voter IDs",
+            PiiClass::Pincode => "PIN codes",
+        }
+    }
+
+    pub fn from_wire(s: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|c| c.as_str() == s)
+    }
+
+    /// Whether the original value may be put back into the reply. The
+    /// customer's own email or number is fine to echo; identity and
+    /// payment numbers come back masked to their last four characters.
+    pub fn restorable(self) -> bool {
+        matches!(
+            self,
+            PiiClass::Email | PiiClass::Phone | PiiClass::Upi | PiiClass::Pincode
+        )
+    }
+}
+
+/// Per-tenant PII redaction. Stored at KV `redaction:{tenant_id}`; a
+/// missing key means on, with every class except PIN codes.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct RedactionConfig {
+    pub enabled: bool,
+    pub classes: Vec<PiiClass>,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            classes: PiiClass::ALL
+                .iter()
+                .copied()
+                .filter(|c| *c != PiiClass::Pincode)
+                .collect(),
+        }
+    }
+}
+
+/// Per-tenant handling of messages the prompt-injection scanner flags.
+/// Stored at KV `injection:{tenant_id}`; a missing key sends the holding
+/// reply and relays to a human.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct InjectionConfig {
+    /// Answer the sender with `reply_text` straight away.
+    pub send_holding_reply: bool,
+    pub reply_text: String,
+    /// Queue the message for a human answer on the web and Discord.
+    pub relay_to_human: bool,
+    /// Senders (phone, email, handle) that skip the scan, for regulars
+    /// who keep tripping false positives. Compared case-insensitively.
+    pub allowed_senders: Vec<String>,
+}
+
+impl Default for InjectionConfig {
+    fn default() -> Self {
+        Self {
+            send_holding_reply: true,
+            reply_text:
+                "Thanks for your message. Someone from our team will get back to you shortly."
+                    .to_string(),
+            relay_to_human: true,
+            allowed_senders: Vec::new(),
+        }
+    }
+}
+
+impl InjectionConfig {
+    pub fn allows(&self, sender: &str) -> bool {
+        let sender = sender.trim();
+        self.allowed_senders
+            .iter()
+            .any(|s| s.trim().eq_ignore_ascii_case(sender))
+    }
+}
+
+/// Opt-out / opt-in keywords for one language, with the confirmation
+/// replies sent in that language.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[serde(default)]
+pub struct ConsentKeywords {
+    /// Language tag the set is for ("en", "hi", "es"); shown in the admin.
+    pub locale: String,
+    pub opt_out: Vec<String>,
+    pub opt_in: Vec<String>,
+    pub opt_out_reply: String,
+    pub opt_in_reply: String,
+}
+
+/// Per-tenant opt-out handling. Stored at KV `consent:{tenant_id}`; a
+/// missing key uses the built-in English, Hindi and Spanish sets.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ConsentConfig {
+    pub keyword_sets: Vec<ConsentKeywords>,
+}
+
+impl Default for ConsentConfig {
+    fn default() -> Self {
+        let set = |locale: &str, opt_out: &[&str], opt_in: &[&str], out: &str, in_: &str| {
+            ConsentKeywords {
+                locale: locale.to_string(),
+                opt_out: opt_out.iter().map(|s| s.to_string()).collect(),
+                opt_in: opt_in.iter().map(|s| s.to_string()).collect(),
+                opt_out_reply: out.to_string(),
+                opt_in_reply: in_.to_string(),
+            }
+        };
+        Self {
+            keyword_sets: vec![
+                set(
+                    "en",
+                    &[
+                        "stop",
+                        "stop all",
+                        "unsubscribe",
+                        "opt out",
+                        "cancel",
+                        "quit",
+                        "don't message me",
+                        "do not message me",
+                        "stop messaging me",
+                    ],
+                    &["start", "unstop", "subscribe", "opt in"],
+                    "You're unsubscribed and won't get more messages from us. Reply START to opt back in.",
+                    "You're subscribed again. Reply STOP at any time to opt out.",
+                ),
+                set(
+                    "hi",
+                    &["बंद करो", "band karo", "मैसेज मत करो", "message mat karo"],
+                    &["शुरू करो", "shuru karo"],
+                    "आपको अब हमारी ओर से संदेश नहीं मिलेंगे। फिर से जुड़ने के लिए शुरू करो लिखें।",
+                    "आप फिर से जुड़ गए हैं। कभी भी बंद करने के लिए बंद करो लिखें।",
+                ),
+                set(
+                    "es",
+                    &["baja", "no me escribas", "no me envíes mensajes"],
+                    &["alta"],
+                    "Te has dado de baja y no recibirás más mensajes. Responde ALTA para volver.",
+                    "Te has suscrito de nuevo. Responde BAJA cuando quieras.",
+                ),
+            ],
+        }
+    }
+}
+
+impl ConsentConfig {
+    /// Whether the whole message is an opt-out or opt-in keyword, and which
+    /// set matched (for the reply language). Single words must be the
+    /// entire message, so "stop" inside a sentence doesn't unsubscribe
+    /// anyone; phrases may appear anywhere. Opt-out wins over opt-in.
+    pub fn classify(&self, body: &str) -> Option<(ConsentChange, &ConsentKeywords)> {
+        let text = normalize_keyword(body);
+        if text.is_empty() {
+            return None;
+        }
+        let hit = |keywords: &[String]| {
+            keywords.iter().any(|k| {
+                let k = normalize_keyword(k);
+                !k.is_empty() && (text == k || (k.contains(' ') && contains_phrase(&text, &k)))
+            })
+        };
+        if let Some(set) = self.keyword_sets.iter().find(|s| hit(&s.opt_out)) {
+            return Some((ConsentChange::OptOut, set));
+        }
+        self.keyword_sets
+            .iter()
+            .find(|s| hit(&s.opt_in))
+            .map(|set| (ConsentChange::OptIn, set))
+    }
+}
+
+/// Lowercase, drop apostrophes and punctuation, collapse whitespace.
+fn normalize_keyword(s: &str) -> String {
+    s.to_lowercase()
+        .chars()
+        .filter(|c| *c != '\'' && *c != '’')
+        .map(|c| {
+            if c.is_alphanumeric() || c.is_whitespace() || is_devanagari(c) {
+                c
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Devanagari viramas and some vowel signs aren't alphanumeric but are
+/// part of the word.
+fn is_devanagari(c: char) -> bool {
+    ('\u{0900}'..='\u{097F}').contains(&c)
+}
+
+/// `phrase` occurs in `text` on word boundaries.
+fn contains_phrase(text: &str, phrase: &str) -> bool {
+    format!(" {text} ").contains(&format!(" {phrase} "))
+}
+
+/// A change to a sender's consent to be messaged.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsentChange {
+    OptOut,
+    OptIn,
+}
+
+impl ConsentChange {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ConsentChange::OptOut => "opt_out",
+            ConsentChange::OptIn => "opt_in",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ConsentChange::OptOut => "Opted out",
+            ConsentChange::OptIn => "Opted in",
+        }
+    }
+
+    pub fn from_wire(s: &str) -> Option<Self> {
+        match s {
+            "opt_out" => Some(ConsentChange::OptOut),
+            "opt_in" => Some(ConsentChange::OptIn),
+            _ => None,
+        }
+    }
+}
+
+/// Who changed a sender's consent, for the audit trail.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsentSource {
+    /// The sender texted an opt-out / opt-in keyword.
+    Keyword,
+    /// Someone on the tenant's team changed it from the admin.
+    Admin,
+}
+
+impl ConsentSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ConsentSource::Keyword => "keyword",
+            ConsentSource::Admin => "admin",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ConsentSource::Keyword => "Keyword",
+            ConsentSource::Admin => "Admin",
+        }
+    }
+
+    pub fn from_wire(s: &str) -> Option<Self> {
+        match s {
+            "keyword" => Some(ConsentSource::Keyword),
+            "admin" => Some(ConsentSource::Admin),
+            _ => None,
+        }
+    }
+}
+
+/// A customer's request about the data a tenant holds on them.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DataRequestKind {
+    /// Every stored record about the customer, as JSON.
+    Export,
+    /// Those records deleted.
+    Erasure,
+}
+
+impl DataRequestKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DataRequestKind::Export => "export",
+            DataRequestKind::Erasure => "erasure",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DataRequestKind::Export => "Export",
+            DataRequestKind::Erasure => "Erasure",
+        }
+    }
+}
+
+/// Where a tenant's data export is. See `exports`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportStatus {
+    /// Waiting for the cron to build it.
+    Pending,
+    Ready,
+    Failed,
+}
+
+impl ExportStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ExportStatus::Pending => "pending",
+            ExportStatus::Ready => "ready",
+            ExportStatus::Failed => "failed",
+        }
+    }
+
+    pub fn from_wire(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(ExportStatus::Pending),
+            "ready" => Some(ExportStatus::Ready),
+            "failed" => Some(ExportStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// Where a tenant deletion job is. See `tenant_deletion`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeletionStatus {
+    /// Steps remain; the cron resumes it. A failed step leaves the job
+    /// here with its error so the next run retries.
+    Running,
+    Completed,
+}
+
+impl DeletionStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DeletionStatus::Running => "running",
+            DeletionStatus::Completed => "completed",
+        }
+    }
+
+    pub fn from_wire(s: &str) -> Option<Self> {
+        match s {
+            "running" => Some(DeletionStatus::Running),
+            "completed" => Some(DeletionStatus::Completed),
+            _ => None,
+        }
+    }
+}
+
+/// What a tool call sent and got back, kept on the approval row so a
+/// reviewer can see what data the draft was based on.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ToolCallRecord {
+    pub tool: String,
+    pub arguments: serde_json::Value,
+    /// HTTP status, when the endpoint answered at all.
+    #[serde(default)]
+    pub status: Option<u16>,
+    /// Response body as handed to the model (capped).
+    #[serde(default)]
+    pub output: String,
+    #[serde(default)]
+    pub truncated: bool,
+    /// Timeout, transport or validation failure. The model is told the
+    /// lookup failed; it never sees this text verbatim.
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub duration_ms: u64,
+}
+
 // ============================================================================
 // WhatsApp Account Resource
 // ============================================================================
@@ -108,6 +715,9 @@ pub struct WhatsAppAccount {
     pub phone_number: String,
     pub phone_number_id: String,
     pub auto_reply: ReplyConfig,
+    /// The brand this number answers for; `None` is the main brand.
+    #[serde(default)]
+    pub brand_id: Option<String>,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -125,6 +735,21 @@ pub struct ReplyConfig {
     /// AI call. 0 = reply immediately (no buffering).
     #[serde(default = "default_wait_seconds")]
     pub wait_seconds: u32,
+    /// Ceiling on the total wait, counted from the first buffered message,
+    /// so a customer who keeps typing still gets a reply. 0 = no ceiling.
+    #[serde(default = "default_max_wait_seconds")]
+    pub max_wait_seconds: u32,
+    /// Reply as soon as this many messages are buffered. 0 = no limit.
+    #[serde(default = "default_max_buffered")]
+    pub max_buffered: u32,
+    /// Reply straight away when the latest message ends in a question
+    /// mark: the customer has finished asking.
+    #[serde(default = "default_true")]
+    pub flush_on_question: bool,
+    /// This account's own persona, replacing its brand's for AI replies.
+    /// Vetted by the safety queue like any other persona.
+    #[serde(default)]
+    pub persona: Option<PersonaConfig>,
 }
 
 impl Default for ReplyConfig {
@@ -134,6 +759,10 @@ impl Default for ReplyConfig {
             rules: Vec::new(),
             default_rule: ReplyRule::default_fallback(),
             wait_seconds: default_wait_seconds(),
+            max_wait_seconds: default_max_wait_seconds(),
+            max_buffered: default_max_buffered(),
+            flush_on_question: true,
+            persona: None,
         }
     }
 }
@@ -161,12 +790,42 @@ impl ReplyConfig {
             _ => ReplyResponse::Canned { text },
         };
     }
+
+    /// Apply the buffering limits from a channel settings form. Skipped
+    /// when the form doesn't carry them, so forms that only post
+    /// `wait_seconds` leave the limits alone. `field` returns a form value
+    /// by name; an absent checkbox reads as off.
+    pub fn apply_buffer_form(&mut self, field: impl Fn(&str) -> Option<String>) {
+        let Some(max_wait) = field("max_wait_seconds") else {
+            return;
+        };
+        if let Ok(n) = max_wait.trim().parse::<u32>() {
+            self.max_wait_seconds = n.min(MAX_BUFFER_WAIT_SECONDS);
+        }
+        if let Some(Ok(n)) = field("max_buffered").map(|v| v.trim().parse::<u32>()) {
+            self.max_buffered = n.min(MAX_BUFFERED_MESSAGES);
+        }
+        self.flush_on_question = field("flush_on_question").is_some_and(|v| v != "false");
+    }
 }
 
 pub fn default_wait_seconds() -> u32 {
     5
 }
 
+/// Upper bound an admin can set for `ReplyConfig::max_wait_seconds`.
+pub const MAX_BUFFER_WAIT_SECONDS: u32 = 300;
+/// Upper bound an admin can set for `ReplyConfig::max_buffered`.
+pub const MAX_BUFFERED_MESSAGES: u32 = 50;
+
+fn default_max_wait_seconds() -> u32 {
+    60
+}
+
+fn default_max_buffered() -> u32 {
+    10
+}
+
 /// One reply routing entry: a matcher (when does this fire?) and a response
 /// (what do we send?).
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -177,6 +836,9 @@ pub struct ReplyRule {
     pub response: ReplyResponse,
     #[serde(default)]
     pub approval: ApprovalPolicy,
+    /// Model tier and sampling for AI responses. Ignored for canned text.
+    #[serde(default)]
+    pub generation: GenerationParams,
 }
 
 impl ReplyRule {
@@ -191,10 +853,59 @@ impl ReplyRule {
                 text: "Reply to the customer's message helpfully.".to_string(),
             },
             approval: ApprovalPolicy::default(),
+            generation: GenerationParams::default(),
         }
     }
 }
 
+/// Which model slot an AI rule draws on. `Quality` is the operator's reply
+/// model (what every rule used before tiers existed); `Fast` is the
+/// scanning model, good enough for acknowledgements. Credit cost per tier
+/// and which tiers tenants may pick are operator settings on
+/// `storage::Pricing`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelTier {
+    Fast,
+    #[default]
+    Quality,
+}
+
+impl ModelTier {
+    pub const ALL: &'static [ModelTier] = &[ModelTier::Fast, ModelTier::Quality];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ModelTier::Fast => "fast",
+            ModelTier::Quality => "quality",
+        }
+    }
+
+    pub fn from_wire(s: &str) -> Option<Self> {
+        match s {
+            "fast" => Some(ModelTier::Fast),
+            "quality" => Some(ModelTier::Quality),
+            _ => None,
+        }
+    }
+}
+
+/// Bounds for `GenerationParams`, enforced by the rules form.
+pub const MAX_TEMPERATURE: f32 = 1.5;
+pub const MIN_MAX_TOKENS: u32 = 16;
+pub const MAX_MAX_TOKENS: u32 = 1024;
+
+/// Per-rule generation knobs. `None` leaves the provider's default in place.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct GenerationParams {
+    #[serde(default)]
+    pub tier: ModelTier,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+}
+
 /// Per-rule policy for AI-generated drafts. Only consulted when the rule's
 /// `response` is `ReplyResponse::Prompt` (canned rules send verbatim, no draft).
 ///
@@ -239,6 +950,19 @@ pub enum QueueReason {
     RiskCommitment,
     /// Draft contained a topic in the persona's off-topics or never list.
     RiskPersonaDrift,
+    /// Guardrail: draft links to a domain not on the tenant's allowlist.
+    GuardUnknownUrl,
+    /// Guardrail: draft gives a phone number or email that isn't the
+    /// business's own.
+    GuardForeignContact,
+    /// Guardrail: draft repeats a chunk of the persona/rule prompt.
+    GuardPromptLeak,
+    /// Guardrail: the optional model-based policy review flagged the draft.
+    GuardPolicy,
+    /// The injection scanner flagged the inbound message; a human answers.
+    InjectionSuspected,
+    /// The injection scanner failed, so the message was held as if flagged.
+    InjectionScanFailed,
 }
 
 /// One row of the pending_approvals D1 table, mirrored as a Rust struct.
@@ -252,15 +976,25 @@ pub struct PendingApproval {
     pub rule_label: String,
     pub sender: String,
     pub sender_name: Option<String>,
+    #[serde(default)]
+    pub contact_id: Option<String>,
     pub inbound_preview: String,
     pub draft: String,
     pub queue_reason: QueueReason,
+    /// What tripped a guardrail (the unlisted link, the foreign number),
+    /// shown next to the reason chip. `None` for the other reasons.
+    #[serde(default)]
+    pub queue_detail: Option<String>,
     pub status: ApprovalStatus,
     pub created_at: String,
     pub decided_at: Option<String>,
     pub decided_by: Option<String>,
     pub edited: bool,
     pub last_digest_at: Option<String>,
+    /// Credits deducted for the draft; refunded in full on reject/expiry.
+    pub credits_charged: i64,
+    /// Tool lookups made while drafting, in call order.
+    pub tool_calls: Vec<ToolCallRecord>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
@@ -273,12 +1007,19 @@ pub enum ApprovalStatus {
 }
 
 /// Who decided a pending approval. Stored on `pending_approvals.decided_by`
-/// in a flat string form (`"discord:<id>" | "web:<email>" | "expired"`) so
-/// the column stays human-readable in the audit log.
+/// in a flat string form (`"discord:<id>" | "web:<email>" |
+/// "web:<email>#<member id>" | "expired"`) so the column stays
+/// human-readable in the audit log. Web decisions by a team member carry
+/// the member's id; the owner's don't.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ApprovalDecider {
-    Discord { user_id: String },
-    Web { email: String },
+    Discord {
+        user_id: String,
+    },
+    Web {
+        email: String,
+        member_id: Option<String>,
+    },
     Expired,
 }
 
@@ -287,7 +1028,14 @@ impl ApprovalDecider {
     pub fn wire(&self) -> String {
         match self {
             ApprovalDecider::Discord { user_id } => format!("discord:{user_id}"),
-            ApprovalDecider::Web { email } => format!("web:{email}"),
+            ApprovalDecider::Web {
+                email,
+                member_id: None,
+            } => format!("web:{email}"),
+            ApprovalDecider::Web {
+                email,
+                member_id: Some(id),
+            } => format!("web:{email}#{id}"),
             ApprovalDecider::Expired => "expired".to_string(),
         }
     }
@@ -304,9 +1052,14 @@ impl ApprovalDecider {
                 user_id: id.to_string(),
             });
         }
-        if let Some(email) = s.strip_prefix("web:") {
+        if let Some(web) = s.strip_prefix("web:") {
+            let (email, member_id) = match web.rsplit_once('#') {
+                Some((email, id)) => (email, Some(id.to_string())),
+                None => (web, None),
+            };
             return Some(ApprovalDecider::Web {
                 email: email.to_string(),
+                member_id,
             });
         }
         None
@@ -362,6 +1115,9 @@ pub struct InstagramAccount {
     pub page_id: String,
     pub auto_reply: ReplyConfig,
     pub enabled: bool,
+    /// The brand this account answers for; `None` is the main brand.
+    #[serde(default)]
+    pub brand_id: Option<String>,
     pub created_at: String,
     #[serde(default)]
     pub updated_at: String,
@@ -557,6 +1313,9 @@ pub struct EmailAddress {
     pub auto_reply: ReplyConfig,
     #[serde(default)]
     pub notification_recipients: Vec<NotificationRecipient>,
+    /// The brand this address answers for; `None` is the main brand.
+    #[serde(default)]
+    pub brand_id: Option<String>,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -615,6 +1374,13 @@ pub enum Channel {
 }
 
 impl Channel {
+    pub const ALL: [Channel; 4] = [
+        Channel::WhatsApp,
+        Channel::Instagram,
+        Channel::Email,
+        Channel::Discord,
+    ];
+
     /// Lowercase wire form used by the D1 `channel` column and inside
     /// API/webhook payloads. Diverges from serde's snake_case form (which
     /// would emit "whats_app"); keep both intact.
@@ -627,6 +1393,16 @@ impl Channel {
         }
     }
 
+    pub fn from_wire(s: &str) -> Option<Self> {
+        match s {
+            "whatsapp" => Some(Channel::WhatsApp),
+            "instagram" => Some(Channel::Instagram),
+            "email" => Some(Channel::Email),
+            "discord" => Some(Channel::Discord),
+            _ => None,
+        }
+    }
+
     /// Display label used in templates and email digests.
     pub fn label(&self) -> &'static str {
         match self {
@@ -649,6 +1425,12 @@ pub enum MessageDirection {
 }
 
 impl MessageDirection {
+    pub const ALL: [MessageDirection; 3] = [
+        MessageDirection::Inbound,
+        MessageDirection::Outbound,
+        MessageDirection::Relay,
+    ];
+
     pub fn as_str(self) -> &'static str {
         match self {
             MessageDirection::Inbound => "inbound",
@@ -656,6 +1438,23 @@ impl MessageDirection {
             MessageDirection::Relay => "relay",
         }
     }
+
+    pub fn from_wire(s: &str) -> Option<Self> {
+        match s {
+            "inbound" => Some(MessageDirection::Inbound),
+            "outbound" => Some(MessageDirection::Outbound),
+            "relay" => Some(MessageDirection::Relay),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MessageDirection::Inbound => "Inbound",
+            MessageDirection::Outbound => "Outbound",
+            MessageDirection::Relay => "Relay",
+        }
+    }
 }
 
 /// What was done with a message after the pipeline routed it. Stored on
@@ -663,8 +1462,10 @@ impl MessageDirection {
 #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum MessageAction {
-    /// Auto-reply sent without any approval gate.
+    /// Canned auto-reply (rule text or holding reply), sent as written.
     AutoReply,
+    /// AI-drafted auto-reply sent without waiting for approval.
+    AiReply,
     /// Forwarded to Discord for human relay (not an AI draft).
     Relay,
     /// AI draft was diverted to the approval queue.
@@ -675,17 +1476,145 @@ pub enum MessageAction {
     AiRejected,
     /// AI draft expired past its 24h hold without action.
     AiExpired,
+    /// Inbound message the injection scanner flagged; no AI reply ran.
+    InjectionBlocked,
+    /// Inbound message held because the injection scanner errored.
+    InjectionScanFailed,
+    /// Opt-out keyword; the sender was suppressed and sent a confirmation.
+    OptOut,
+    /// Opt-in keyword; the sender was removed from the suppression list.
+    OptIn,
+    /// Inbound from a suppressed sender; nothing was sent back.
+    Suppressed,
 }
 
 impl MessageAction {
+    pub const ALL: [MessageAction; 12] = [
+        MessageAction::AutoReply,
+        MessageAction::AiReply,
+        MessageAction::Relay,
+        MessageAction::AiQueued,
+        MessageAction::AiApproved,
+        MessageAction::AiRejected,
+        MessageAction::AiExpired,
+        MessageAction::InjectionBlocked,
+        MessageAction::InjectionScanFailed,
+        MessageAction::OptOut,
+        MessageAction::OptIn,
+        MessageAction::Suppressed,
+    ];
+
     pub fn as_str(self) -> &'static str {
         match self {
             MessageAction::AutoReply => "auto_reply",
+            MessageAction::AiReply => "ai_reply",
             MessageAction::Relay => "relay",
             MessageAction::AiQueued => "ai_queued",
             MessageAction::AiApproved => "ai_approved",
             MessageAction::AiRejected => "ai_rejected",
             MessageAction::AiExpired => "ai_expired",
+            MessageAction::InjectionBlocked => "injection_blocked",
+            MessageAction::InjectionScanFailed => "injection_scan_failed",
+            MessageAction::OptOut => "opt_out",
+            MessageAction::OptIn => "opt_in",
+            MessageAction::Suppressed => "suppressed",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MessageAction::AutoReply => "Canned reply",
+            MessageAction::AiReply => "AI reply",
+            MessageAction::Relay => "Human reply",
+            MessageAction::AiQueued => "Draft queued",
+            MessageAction::AiApproved => "Draft approved",
+            MessageAction::AiRejected => "Draft rejected",
+            MessageAction::AiExpired => "Draft expired",
+            MessageAction::InjectionBlocked => "Injection blocked",
+            MessageAction::InjectionScanFailed => "Injection scan failed",
+            MessageAction::OptOut => "Opted out",
+            MessageAction::OptIn => "Opted in",
+            MessageAction::Suppressed => "Suppressed",
+        }
+    }
+
+    pub fn from_wire(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.as_str() == s)
+    }
+
+    /// Whether the customer got an answer: the row counts towards their
+    /// time-to-reply. Consent confirmations don't.
+    pub fn answers_customer(self) -> bool {
+        matches!(
+            self,
+            MessageAction::AutoReply
+                | MessageAction::AiReply
+                | MessageAction::AiApproved
+                | MessageAction::Relay
+        )
+    }
+}
+
+/// Where an inbox conversation stands, from the last thing that happened
+/// on it. Stored on `conversations.status`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConversationStatus {
+    /// The customer wrote last and nothing has answered.
+    #[default]
+    Open,
+    AutoReplied,
+    /// A draft or held message waits in Approvals.
+    Queued,
+    /// A person answered (relay, inbox reply or approved draft).
+    HumanHandled,
+}
+
+impl ConversationStatus {
+    pub const ALL: [ConversationStatus; 4] = [
+        ConversationStatus::Open,
+        ConversationStatus::AutoReplied,
+        ConversationStatus::Queued,
+        ConversationStatus::HumanHandled,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ConversationStatus::Open => "open",
+            ConversationStatus::AutoReplied => "auto_replied",
+            ConversationStatus::Queued => "queued",
+            ConversationStatus::HumanHandled => "human_handled",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ConversationStatus::Open => "Needs reply",
+            ConversationStatus::AutoReplied => "Auto-replied",
+            ConversationStatus::Queued => "Queued",
+            ConversationStatus::HumanHandled => "Human-handled",
+        }
+    }
+
+    pub fn from_wire(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.as_str() == s)
+    }
+
+    /// Status after a reply-side action. Rejected and expired drafts
+    /// leave the customer unanswered.
+    pub fn after(action: MessageAction) -> Self {
+        match action {
+            MessageAction::AutoReply
+            | MessageAction::AiReply
+            | MessageAction::OptOut
+            | MessageAction::OptIn => ConversationStatus::AutoReplied,
+            MessageAction::AiQueued
+            | MessageAction::InjectionBlocked
+            | MessageAction::InjectionScanFailed => ConversationStatus::Queued,
+            MessageAction::Relay | MessageAction::AiApproved => ConversationStatus::HumanHandled,
+            MessageAction::AiRejected | MessageAction::AiExpired | MessageAction::Suppressed => {
+                ConversationStatus::Open
+            }
         }
     }
 }
@@ -704,6 +1633,10 @@ pub struct InboundMessage {
     pub tenant_id: String,
     pub channel_account_id: String,
     pub raw_metadata: serde_json::Value,
+    /// Contact-book entry the sender resolved to (`contacts::resolve`).
+    /// `None` until the pipeline has resolved it.
+    #[serde(default)]
+    pub contact_id: Option<String>,
 }
 
 /// Conversation context for cross-channel Discord relay.
@@ -719,9 +1652,17 @@ pub struct ConversationContext {
     pub reply_metadata: serde_json::Value,
     #[serde(default)]
     pub ai_draft: Option<String>,
+    /// Credits deducted for `ai_draft`. Contexts written before per-tier
+    /// pricing were always charged one credit.
+    #[serde(default = "one_credit")]
+    pub credits_charged: i64,
     pub created_at: String,
 }
 
+fn one_credit() -> i64 {
+    1
+}
+
 /// Business information for KYC / Indian regulatory compliance.
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct BusinessInfo {
@@ -745,10 +1686,99 @@ pub struct BusinessInfo {
     pub pincode: String,
 }
 
-/// Notification delivery configuration. Today this only covers approval
-/// notifications. The previous activity-summary digest scaffolding was
-/// dropped: it was wizard-collected but never read by any send path.
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+/// Weekly opening hours, Monday first. Times are 24-hour `HH:MM` in the
+/// business's local time; a day without both times is closed.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct OpeningHours {
+    #[serde(default)]
+    pub days: Vec<DayHours>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct DayHours {
+    #[serde(default)]
+    pub open: String,
+    #[serde(default)]
+    pub close: String,
+}
+
+impl OpeningHours {
+    pub const DAYS: [&'static str; 7] = [
+        "Monday",
+        "Tuesday",
+        "Wednesday",
+        "Thursday",
+        "Friday",
+        "Saturday",
+        "Sunday",
+    ];
+
+    /// Hours for day `i` (0 = Monday), or `None` when closed.
+    pub fn day(&self, i: usize) -> Option<&DayHours> {
+        self.days
+            .get(i)
+            .filter(|d| !d.open.is_empty() && !d.close.is_empty())
+    }
+
+    pub fn is_set(&self) -> bool {
+        (0..7).any(|i| self.day(i).is_some())
+    }
+
+    /// One line for the system prompt, so the assistant can answer
+    /// "are you open?". Empty when no hours are set.
+    pub fn summary(&self) -> String {
+        if !self.is_set() {
+            return String::new();
+        }
+        let days: Vec<String> = Self::DAYS
+            .iter()
+            .enumerate()
+            .map(|(i, name)| match self.day(i) {
+                Some(d) => format!("{name} {}-{}", d.open, d.close),
+                None => format!("{name} closed"),
+            })
+            .collect();
+        format!("Opening hours: {}.", days.join("; "))
+    }
+}
+
+/// A brand or location inside a tenant, with its own business details,
+/// persona and opening hours. The tenant's main brand is not stored as a
+/// `Brand`: it is the onboarding state's business, persona and hours.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Brand {
+    pub id: String,
+    #[serde(default)]
+    pub business: BusinessInfo,
+    #[serde(default)]
+    pub persona: PersonaConfig,
+    #[serde(default)]
+    pub hours: OpeningHours,
+    pub created_at: String,
+}
+
+impl Brand {
+    /// Id of the tenant's main brand.
+    pub const MAIN: &'static str = "main";
+
+    pub fn is_main(&self) -> bool {
+        self.id == Self::MAIN
+    }
+
+    pub fn name(&self) -> &str {
+        match self.business.name.trim() {
+            "" if self.is_main() => "Main brand",
+            "" => "Untitled brand",
+            name => name,
+        }
+    }
+}
+
+/// Notification delivery configuration: where approvals are asked for,
+/// whether the owner gets the Monday summary (`email::summary`), and which
+/// in-app notifications (`notifications`) are also emailed or posted to
+/// Discord.
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct NotificationConfig {
     #[serde(default)]
     pub approval_discord: bool,
@@ -756,6 +1786,236 @@ pub struct NotificationConfig {
     pub approval_email: bool,
     #[serde(default)]
     pub approval_email_cadence: DigestCadence,
+    /// On unless the owner turns it off.
+    #[serde(default = "default_true")]
+    pub weekly_summary: bool,
+    #[serde(default = "NotificationKind::emailed_by_default")]
+    pub email_kinds: Vec<NotificationKind>,
+    #[serde(default)]
+    pub discord_kinds: Vec<NotificationKind>,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            approval_discord: false,
+            approval_email: false,
+            approval_email_cadence: DigestCadence::default(),
+            weekly_summary: true,
+            email_kinds: NotificationKind::emailed_by_default(),
+            discord_kinds: Vec::new(),
+        }
+    }
+}
+
+/// What an in-app notification is about. Stored on `notifications.kind`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// The safety check turned a persona down; AI replies that would use
+    /// it stay off until it's revised.
+    PersonaRejected,
+    /// An Instagram token expired or couldn't be renewed.
+    InstagramToken,
+    /// An AI reply was skipped for lack of credits.
+    CreditsExhausted,
+    /// A draft couldn't be posted to the Discord approvals channel.
+    DiscordPostFailed,
+    /// A notification recipient confirmed their address.
+    RecipientVerified,
+}
+
+impl NotificationKind {
+    pub const ALL: [NotificationKind; 5] = [
+        NotificationKind::PersonaRejected,
+        NotificationKind::InstagramToken,
+        NotificationKind::CreditsExhausted,
+        NotificationKind::DiscordPostFailed,
+        NotificationKind::RecipientVerified,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NotificationKind::PersonaRejected => "persona_rejected",
+            NotificationKind::InstagramToken => "instagram_token",
+            NotificationKind::CreditsExhausted => "credits_exhausted",
+            NotificationKind::DiscordPostFailed => "discord_post_failed",
+            NotificationKind::RecipientVerified => "recipient_verified",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            NotificationKind::PersonaRejected => "Persona rejected",
+            NotificationKind::InstagramToken => "Instagram connection",
+            NotificationKind::CreditsExhausted => "Out of credits",
+            NotificationKind::DiscordPostFailed => "Discord post failed",
+            NotificationKind::RecipientVerified => "Recipient verified",
+        }
+    }
+
+    pub fn from_wire(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.as_str() == s)
+    }
+
+    pub fn severity(self) -> NotificationSeverity {
+        match self {
+            NotificationKind::PersonaRejected
+            | NotificationKind::InstagramToken
+            | NotificationKind::CreditsExhausted => NotificationSeverity::Critical,
+            NotificationKind::DiscordPostFailed => NotificationSeverity::Warning,
+            NotificationKind::RecipientVerified => NotificationSeverity::Info,
+        }
+    }
+
+    /// Whether Discord delivery can be offered. A failed Discord post
+    /// can't be reported through Discord.
+    pub fn discord_deliverable(self) -> bool {
+        self != NotificationKind::DiscordPostFailed
+    }
+
+    /// Kinds emailed until the owner says otherwise: the ones that stop
+    /// replies going out.
+    pub fn emailed_by_default() -> Vec<NotificationKind> {
+        Self::ALL
+            .into_iter()
+            .filter(|k| k.severity() == NotificationSeverity::Critical)
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl NotificationSeverity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NotificationSeverity::Info => "info",
+            NotificationSeverity::Warning => "warning",
+            NotificationSeverity::Critical => "critical",
+        }
+    }
+
+    pub fn from_wire(s: &str) -> Option<Self> {
+        match s {
+            "info" => Some(NotificationSeverity::Info),
+            "warning" => Some(NotificationSeverity::Warning),
+            "critical" => Some(NotificationSeverity::Critical),
+            _ => None,
+        }
+    }
+}
+
+/// What a team member may do in the admin pages. Each role includes
+/// everything the ones below it can do: viewers read, approvers also
+/// decide drafts and answer conversations, admins also change settings and
+/// channels. Billing, exports, account deletion and the team itself are the
+/// owner's. The owner is the account that signed up (`Tenant.email`); the
+/// other roles are `tenant_members` rows.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MemberRole {
+    Owner,
+    Admin,
+    Approver,
+    Viewer,
+}
+
+impl MemberRole {
+    pub const ALL: [MemberRole; 4] = [
+        MemberRole::Owner,
+        MemberRole::Admin,
+        MemberRole::Approver,
+        MemberRole::Viewer,
+    ];
+
+    /// Roles an invite can grant. There is one owner per tenant.
+    pub const INVITABLE: [MemberRole; 3] =
+        [MemberRole::Admin, MemberRole::Approver, MemberRole::Viewer];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MemberRole::Owner => "owner",
+            MemberRole::Admin => "admin",
+            MemberRole::Approver => "approver",
+            MemberRole::Viewer => "viewer",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MemberRole::Owner => "Owner",
+            MemberRole::Admin => "Admin",
+            MemberRole::Approver => "Approver",
+            MemberRole::Viewer => "Viewer",
+        }
+    }
+
+    /// One line for the role picker.
+    pub fn description(self) -> &'static str {
+        match self {
+            MemberRole::Owner => "Everything, including billing, exports and the team",
+            MemberRole::Admin => "Settings, channels, rules and persona",
+            MemberRole::Approver => "Approve drafts and reply from the inbox",
+            MemberRole::Viewer => "Read-only",
+        }
+    }
+
+    pub fn from_wire(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|r| r.as_str() == s)
+    }
+
+    fn rank(self) -> u8 {
+        match self {
+            MemberRole::Viewer => 0,
+            MemberRole::Approver => 1,
+            MemberRole::Admin => 2,
+            MemberRole::Owner => 3,
+        }
+    }
+
+    /// Whether this role can do what `needed` can.
+    pub fn allows(self, needed: MemberRole) -> bool {
+        self.rank() >= needed.rank()
+    }
+}
+
+/// Who pays for a client an agency manages (`agency_clients.billing`).
+/// `Agency` charges the client's replies to the agency's credits; `Client`
+/// leaves the client on its own balance.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientBilling {
+    Agency,
+    #[default]
+    Client,
+}
+
+impl ClientBilling {
+    pub const ALL: [ClientBilling; 2] = [ClientBilling::Agency, ClientBilling::Client];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ClientBilling::Agency => "agency",
+            ClientBilling::Client => "client",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ClientBilling::Agency => "Agency pays",
+            ClientBilling::Client => "Client pays",
+        }
+    }
+
+    pub fn from_wire(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|b| b.as_str() == s)
+    }
 }
 
 /// How often a tenant wants the approval-queue digest email. The cron sweep
@@ -823,7 +2083,7 @@ impl DigestCadence {
 
 #[cfg(test)]
 mod enum_tests {
-    use super::{ApprovalDecider, GrantCadence, OnboardingStep, Plan};
+    use super::{AiProvider, ApprovalDecider, GrantCadence, MemberRole, OnboardingStep, Plan};
 
     #[test]
     fn grant_cadence_wire_round_trip() {
@@ -856,6 +2116,11 @@ mod enum_tests {
             },
             ApprovalDecider::Web {
                 email: "owner@example.com".into(),
+                member_id: None,
+            },
+            ApprovalDecider::Web {
+                email: "desk@example.com".into(),
+                member_id: Some("3f2a9c1e-0000-4000-8000-000000000000".into()),
             },
             ApprovalDecider::Expired,
         ];
@@ -866,6 +2131,30 @@ mod enum_tests {
         }
     }
 
+    #[test]
+    fn approval_decider_reads_legacy_web_wire() {
+        assert_eq!(
+            ApprovalDecider::from_wire("web:owner@example.com"),
+            Some(ApprovalDecider::Web {
+                email: "owner@example.com".into(),
+                member_id: None,
+            })
+        );
+    }
+
+    #[test]
+    fn member_roles_round_trip_and_nest() {
+        for role in MemberRole::ALL {
+            assert_eq!(MemberRole::from_wire(role.as_str()), Some(role));
+            assert!(MemberRole::Owner.allows(role));
+            assert!(role.allows(MemberRole::Viewer));
+        }
+        assert!(!MemberRole::INVITABLE.contains(&MemberRole::Owner));
+        assert!(MemberRole::Admin.allows(MemberRole::Approver));
+        assert!(!MemberRole::Approver.allows(MemberRole::Admin));
+        assert!(!MemberRole::Viewer.allows(MemberRole::Approver));
+    }
+
     #[test]
     fn approval_decider_from_unknown_returns_none() {
         assert_eq!(ApprovalDecider::from_wire("bogus:value"), None);
@@ -893,6 +2182,16 @@ mod enum_tests {
         assert_eq!(Plan::from_wire("free"), Some(Plan::Free));
         assert_eq!(Plan::from_wire("enterprise"), None);
     }
+
+    #[test]
+    fn ai_provider_wire_matches_serde() {
+        for p in [AiProvider::WorkersAi, AiProvider::OpenAi, AiProvider::Stub] {
+            assert_eq!(AiProvider::from_wire(p.as_str()), Some(p));
+            let json = serde_json::to_string(&p).unwrap();
+            assert_eq!(json, format!("\"{}\"", p.as_str()));
+        }
+        assert_eq!(AiProvider::from_wire("anthropic"), None);
+    }
 }
 
 #[cfg(test)]
@@ -1002,6 +2301,9 @@ pub struct OnboardingState {
     pub notifications: NotificationConfig,
     #[serde(default)]
     pub persona: PersonaConfig,
+    /// Opening hours of the main brand (see `brands`).
+    #[serde(default)]
+    pub hours: OpeningHours,
     /// Default wait_seconds copied into ReplyConfig on every channel account
     /// this tenant connects later. Per-account overrides live on each ReplyConfig.
     #[serde(default = "default_wait_seconds")]
@@ -1066,9 +2368,34 @@ pub enum PersonaSource {
     /// User-filled inputs the system uses to compose a prompt on demand.
     Builder(PersonaBuilder),
     /// Power-user override: raw prompt text. Replaces builder/preset entirely.
+    #[serde(with = "custom_prompt")]
     Custom(String),
 }
 
+/// `Custom`'s prompt on the wire as `{"kind":"custom","prompt":"..."}`: an
+/// internally tagged enum can't carry a bare string.
+mod custom_prompt {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize)]
+    struct Out<'a> {
+        prompt: &'a str,
+    }
+
+    #[derive(Deserialize)]
+    struct In {
+        prompt: String,
+    }
+
+    pub fn serialize<S: Serializer>(prompt: &str, s: S) -> Result<S::Ok, S::Error> {
+        Out { prompt }.serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<String, D::Error> {
+        In::deserialize(d).map(|c| c.prompt)
+    }
+}
+
 /// Curated persona presets shipped in the app. Add a variant here AND in
 /// `personas.rs` (label/description/prompt/default_rules) to ship a new one.
 #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
@@ -1143,12 +2470,164 @@ pub struct DiscordConfig {
     /// AI auto-reply configuration for inbound Discord messages.
     #[serde(default)]
     pub auto_reply: ReplyConfig,
+    /// The brand the bot answers for; `None` is the main brand.
+    #[serde(default)]
+    pub brand_id: Option<String>,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn buffer_form_clamps_and_reads_unchecked_as_off() {
+        let mut cfg = ReplyConfig::default();
+        cfg.apply_buffer_form(|k| match k {
+            "max_wait_seconds" => Some("9000".into()),
+            "max_buffered" => Some("4".into()),
+            _ => None,
+        });
+        assert_eq!(cfg.max_wait_seconds, MAX_BUFFER_WAIT_SECONDS);
+        assert_eq!(cfg.max_buffered, 4);
+        assert!(!cfg.flush_on_question);
+
+        // A form without the block leaves the limits alone.
+        cfg.apply_buffer_form(|_| None);
+        assert_eq!(cfg.max_buffered, 4);
+
+        // Configs stored before the limits existed pick up the defaults.
+        let old: ReplyConfig = serde_json::from_value(serde_json::json!({
+            "enabled": true,
+            "default_rule": ReplyRule::default_fallback(),
+        }))
+        .unwrap();
+        assert_eq!(old.max_wait_seconds, 60);
+        assert!(old.flush_on_question);
+    }
+
+    #[test]
+    fn reply_config_persona_override_is_optional() {
+        let old: ReplyConfig = serde_json::from_value(serde_json::json!({
+            "enabled": true,
+            "default_rule": ReplyRule::default_fallback(),
+        }))
+        .unwrap();
+        assert!(old.persona.is_none());
+
+        let cfg = ReplyConfig {
+            persona: Some(PersonaConfig {
+                source: PersonaSource::Custom("Reply formally.".into()),
+                safety: PersonaSafety::default(),
+            }),
+            ..Default::default()
+        };
+        let back: ReplyConfig =
+            serde_json::from_value(serde_json::to_value(&cfg).unwrap()).unwrap();
+        let persona = back.persona.unwrap();
+        assert_eq!(persona.active_prompt(), "Reply formally.");
+        assert!(!persona.is_safe_to_use());
+    }
+
+    #[test]
+    fn conversation_status_follows_last_action() {
+        assert_eq!(
+            ConversationStatus::after(MessageAction::AutoReply),
+            ConversationStatus::AutoReplied
+        );
+        assert_eq!(
+            ConversationStatus::after(MessageAction::InjectionBlocked),
+            ConversationStatus::Queued
+        );
+        assert_eq!(
+            ConversationStatus::after(MessageAction::Relay),
+            ConversationStatus::HumanHandled
+        );
+        assert_eq!(
+            ConversationStatus::after(MessageAction::AiReply),
+            ConversationStatus::AutoReplied
+        );
+        assert_eq!(
+            ConversationStatus::after(MessageAction::AiExpired),
+            ConversationStatus::Open
+        );
+        for status in ConversationStatus::ALL {
+            assert_eq!(ConversationStatus::from_wire(status.as_str()), Some(status));
+        }
+    }
+
+    #[test]
+    fn only_answers_count_towards_reply_time() {
+        assert!(MessageAction::AiReply.answers_customer());
+        assert!(MessageAction::AiApproved.answers_customer());
+        assert!(!MessageAction::AiQueued.answers_customer());
+        assert!(!MessageAction::OptOut.answers_customer());
+    }
+
+    #[test]
+    fn notification_kinds_round_trip_and_default_to_critical_email() {
+        for kind in NotificationKind::ALL {
+            assert_eq!(
+                serde_json::to_value(kind).unwrap(),
+                serde_json::json!(kind.as_str())
+            );
+            assert_eq!(NotificationKind::from_wire(kind.as_str()), Some(kind));
+        }
+        let old: NotificationConfig = serde_json::from_value(serde_json::json!({
+            "approval_discord": true,
+        }))
+        .unwrap();
+        assert!(old
+            .email_kinds
+            .contains(&NotificationKind::CreditsExhausted));
+        assert!(!old
+            .email_kinds
+            .contains(&NotificationKind::RecipientVerified));
+        assert!(old.discord_kinds.is_empty());
+    }
+
+    #[test]
+    fn message_action_wire_matches_serde() {
+        for action in MessageAction::ALL {
+            assert_eq!(
+                serde_json::to_value(action).unwrap(),
+                serde_json::json!(action.as_str())
+            );
+            assert_eq!(MessageAction::from_wire(action.as_str()), Some(action));
+        }
+        assert_eq!(MessageAction::from_wire("nope"), None);
+    }
+
+    #[test]
+    fn injection_allowlist_ignores_case_and_padding() {
+        let cfg = InjectionConfig {
+            allowed_senders: vec![" Regular@Example.com ".into(), "+919876543210".into()],
+            ..Default::default()
+        };
+        assert!(cfg.allows("regular@example.com"));
+        assert!(cfg.allows("+919876543210"));
+        assert!(!cfg.allows("+919876543211"));
+        assert!(!InjectionConfig::default().allows(""));
+    }
+
+    #[test]
+    fn consent_keywords_match_whole_message_or_phrase() {
+        let cfg = ConsentConfig::default();
+        let change = |body: &str| cfg.classify(body).map(|(c, set)| (c, set.locale.as_str()));
+        assert_eq!(change("STOP"), Some((ConsentChange::OptOut, "en")));
+        assert_eq!(change("  stop. "), Some((ConsentChange::OptOut, "en")));
+        assert_eq!(
+            change("Please dont message me again"),
+            Some((ConsentChange::OptOut, "en"))
+        );
+        assert_eq!(change("बंद करो"), Some((ConsentChange::OptOut, "hi")));
+        assert_eq!(change("Baja"), Some((ConsentChange::OptOut, "es")));
+        assert_eq!(change("start"), Some((ConsentChange::OptIn, "en")));
+        // Single words only count as the whole message.
+        assert_eq!(change("I can't stop smiling, thanks!"), None);
+        assert_eq!(change("when do you start delivery?"), None);
+        assert_eq!(change(""), None);
+    }
+
     #[test]
     fn test_reply_response_serialization() {
         let canned = ReplyResponse::Canned {
@@ -1233,12 +2712,24 @@ mod tests {
             channel_account_id: "example.com".into(),
             reply_metadata: serde_json::json!({"domain": "example.com"}),
             ai_draft: Some("Draft reply text".into()),
+            credits_charged: 2,
             created_at: "2026-01-01T00:00:00Z".into(),
         };
         let json = serde_json::to_string(&ctx).unwrap();
         let parsed: ConversationContext = serde_json::from_str(&json).unwrap();
         assert_eq!(parsed.origin_channel, Channel::Email);
         assert_eq!(parsed.ai_draft.as_deref(), Some("Draft reply text"));
+        assert_eq!(parsed.credits_charged, 2);
+    }
+
+    #[test]
+    fn test_conversation_context_legacy_charge_defaults_to_one() {
+        let json = r#"{"id":"c","discord_channel_id":"","origin_channel":"email",
+            "origin_sender":"a@b.c","origin_recipient":"x@y.z","tenant_id":"t",
+            "channel_account_id":"y.z","reply_metadata":{},"ai_draft":"hi",
+            "created_at":"2026-01-01T00:00:00Z"}"#;
+        let parsed: ConversationContext = serde_json::from_str(json).unwrap();
+        assert_eq!(parsed.credits_charged, 1);
     }
 
     #[test]